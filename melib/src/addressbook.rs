@@ -22,6 +22,11 @@
 #[cfg(feature = "vcard")]
 pub mod vcard;
 
+pub mod abook;
+pub mod collected;
+pub mod ldap;
+pub mod mutt;
+
 use crate::datetime::{self, UnixTimestamp};
 use std::collections::HashMap;
 use uuid::Uuid;
@@ -81,6 +86,9 @@ pub struct Card {
     color: u8,
     last_edited: UnixTimestamp,
     extra_properties: HashMap<String, String>,
+    /// Labels of e-mail addresses, such as `work` or `home`, keyed by address.
+    #[serde(default)]
+    email_labels: HashMap<String, String>,
 
     /// If true, we can't make any changes because we do not manage this resource.
     external_resource: bool,
@@ -125,10 +133,46 @@ impl AddressBook {
         self.cards.contains_key(&card_id)
     }
     pub fn search(&self, term: &str) -> Vec<String> {
-        self.cards
+        let mut ret = vec![];
+        for c in self.cards.values() {
+            for email in c.emails() {
+                if email.contains(term) || (!term.is_empty() && c.name.contains(term)) {
+                    ret.push(format!("{} <{}>", &c.name, email));
+                }
+            }
+        }
+        ret
+    }
+
+    /// Names of all contact groups, sorted.
+    pub fn groups(&self) -> Vec<&str> {
+        let mut ret = self
+            .cards
+            .values()
+            .flat_map(|c| c.groups())
+            .collect::<Vec<&str>>();
+        ret.sort_unstable();
+        ret.dedup();
+        ret
+    }
+
+    /// The addresses of the members of `group`, formatted as `Name <email>` and sorted by name.
+    pub fn group_members(&self, group: &str) -> Vec<String> {
+        let mut members = self
+            .cards
             .values()
-            .filter(|c| c.email.contains(term))
-            .map(|c| format!("{} <{}>", &c.name, &c.email))
+            .filter(|c| !c.email.is_empty() && c.groups().any(|g| g == group))
+            .collect::<Vec<&Card>>();
+        members.sort_unstable_by_key(|c| c.name());
+        members
+            .into_iter()
+            .map(|c| {
+                if c.name.is_empty() {
+                    c.email.clone()
+                } else {
+                    format!("{} <{}>", &c.name, &c.email)
+                }
+            })
             .collect()
     }
 }
@@ -159,6 +203,7 @@ impl Card {
             last_edited: datetime::now(),
             external_resource: false,
             extra_properties: HashMap::default(),
+            email_labels: HashMap::default(),
             color: 0,
         }
     }
@@ -242,6 +287,76 @@ impl Card {
     pub fn external_resource(&self) -> bool {
         self.external_resource
     }
+
+    /// The primary e-mail address followed by the additional ones.
+    pub fn emails(&self) -> impl Iterator<Item = &str> {
+        std::iter::once(self.email.as_str())
+            .filter(|e| !e.is_empty())
+            .chain(
+                self.extra_property("EMAIL")
+                    .into_iter()
+                    .flat_map(split_list),
+            )
+    }
+
+    /// Additional e-mail addresses, besides the primary one.
+    pub fn set_other_emails(&mut self, new: Vec<String>) {
+        self.set_list_property("EMAIL", new);
+    }
+
+    pub fn email_label(&self, email: &str) -> Option<&str> {
+        self.email_labels.get(email).map(String::as_str)
+    }
+
+    pub fn set_email_label(&mut self, email: &str, label: String) {
+        if label.is_empty() {
+            self.email_labels.remove(email);
+        } else {
+            self.email_labels.insert(email.to_string(), label);
+        }
+    }
+
+    pub fn organization(&self) -> &str {
+        self.extra_property("ORG").unwrap_or_default()
+    }
+
+    pub fn set_organization(&mut self, new: String) {
+        self.set_list_property("ORG", vec![new]);
+    }
+
+    pub fn note(&self) -> &str {
+        self.extra_property("NOTE").unwrap_or_default()
+    }
+
+    pub fn set_note(&mut self, new: String) {
+        self.set_list_property("NOTE", vec![new]);
+    }
+
+    /// Names of the contact groups this card belongs to.
+    pub fn groups(&self) -> impl Iterator<Item = &str> {
+        self.extra_property("CATEGORIES")
+            .into_iter()
+            .flat_map(split_list)
+    }
+
+    pub fn set_groups(&mut self, new: Vec<String>) {
+        self.set_list_property("CATEGORIES", new);
+    }
+
+    /// Set a comma separated extra property, or remove it if `values` are empty.
+    fn set_list_property(&mut self, key: &str, values: Vec<String>) {
+        let value = values
+            .iter()
+            .map(|v| v.trim())
+            .filter(|v| !v.is_empty())
+            .collect::<Vec<&str>>()
+            .join(", ");
+        if value.is_empty() {
+            self.extra_properties.remove(key);
+        } else {
+            self.extra_properties.insert(key.to_string(), value);
+        }
+    }
 }
 
 impl From<HashMap<String, String>> for Card {
@@ -263,8 +378,30 @@ impl From<HashMap<String, String>> for Card {
             card.name_suffix = val;
         }
 
+        /* E-mail fields are comma separated lists of addresses, each optionally preceded by a
+         * label and a colon, e.g. `work: alice@example.com`. */
         if let Some(val) = map.remove("E-MAIL") {
-            card.email = val;
+            if let Some((label, email)) = split_labeled_emails(&val).into_iter().next() {
+                card.set_email_label(&email, label);
+                card.email = email;
+            }
+        }
+        if let Some(val) = map.remove("OTHER E-MAILS") {
+            let mut others = vec![];
+            for (label, email) in split_labeled_emails(&val) {
+                card.set_email_label(&email, label);
+                others.push(email);
+            }
+            card.set_other_emails(others);
+        }
+        if let Some(val) = map.remove("ORGANIZATION") {
+            card.set_organization(val);
+        }
+        if let Some(val) = map.remove("GROUPS") {
+            card.set_groups(split_list(&val).map(str::to_string).collect());
+        }
+        if let Some(val) = map.remove("NOTE") {
+            card.set_note(val);
         }
         if let Some(val) = map.remove("URL") {
             card.url = val;
@@ -272,7 +409,8 @@ impl From<HashMap<String, String>> for Card {
         if let Some(val) = map.remove("KEY") {
             card.key = val;
         }
-        card.extra_properties = map;
+        card.extra_properties
+            .extend(map.into_iter().filter(|(_, v)| !v.is_empty()));
         card
     }
 }
@@ -282,3 +420,95 @@ impl Default for Card {
         Self::new()
     }
 }
+
+/// Derive a stable id from the given card values, so that importing the same contact twice
+/// replaces it instead of adding a duplicate.
+fn content_id(values: &[&str]) -> CardId {
+    use std::hash::Hasher;
+    let mut hasher = std::collections::hash_map::DefaultHasher::new();
+    for val in values {
+        hasher.write(val.as_bytes());
+    }
+    CardId::Hash(hasher.finish())
+}
+
+/// Split a comma separated property value such as `EMAIL` or `CATEGORIES`.
+fn split_list(value: &str) -> impl Iterator<Item = &str> {
+    value.split(',').map(str::trim).filter(|s| !s.is_empty())
+}
+
+/// Split a comma separated list of e-mail addresses that are optionally preceded by a label and a
+/// colon into `(label, address)` pairs.
+fn split_labeled_emails(value: &str) -> Vec<(String, String)> {
+    split_list(value)
+        .map(|entry| match entry.find(':') {
+            Some(i) if !entry[..i].contains('@') => (
+                entry[..i].trim().to_string(),
+                entry[i + 1..].trim().to_string(),
+            ),
+            _ => (String::new(), entry.to_string()),
+        })
+        .filter(|(_, email)| !email.is_empty())
+        .collect()
+}
+
+/// Format `emails` of `card` as a list that `split_labeled_emails` can read back.
+pub fn labeled_emails_to_string<'a>(card: &Card, emails: impl Iterator<Item = &'a str>) -> String {
+    emails
+        .map(|e| match card.email_label(e) {
+            Some(label) => format!("{}: {}", label, e),
+            None => e.to_string(),
+        })
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+#[test]
+fn test_card_editor_fields() {
+    let mut map = HashMap::default();
+    map.insert("NAME".to_string(), "Alice".to_string());
+    map.insert("E-MAIL".to_string(), "alice@example.com".to_string());
+    map.insert(
+        "OTHER E-MAILS".to_string(),
+        "work: alice@work.example.com, a@example.org".to_string(),
+    );
+    map.insert("ORGANIZATION".to_string(), "Example Inc.".to_string());
+    map.insert("GROUPS".to_string(), "friends, work".to_string());
+    map.insert("NOTE".to_string(), String::new());
+    let card = Card::from(map);
+    assert_eq!(
+        card.emails().collect::<Vec<&str>>(),
+        vec![
+            "alice@example.com",
+            "alice@work.example.com",
+            "a@example.org"
+        ]
+    );
+    assert_eq!(card.email_label("alice@work.example.com"), Some("work"));
+    assert_eq!(card.email_label("alice@example.com"), None);
+    assert_eq!(
+        labeled_emails_to_string(&card, card.emails().skip(1)),
+        "work: alice@work.example.com, a@example.org"
+    );
+    assert_eq!(card.organization(), "Example Inc.");
+    assert_eq!(card.note(), "");
+    assert_eq!(card.extra_property("NOTE"), None);
+
+    let mut book = AddressBook::new("test".to_string());
+    book.add_card(card);
+    let mut bob = Card::new();
+    bob.set_name("Bob".to_string());
+    bob.set_email("bob@example.com".to_string());
+    bob.set_groups(vec!["friends".to_string()]);
+    book.add_card(bob);
+    assert_eq!(book.groups(), vec!["friends", "work"]);
+    assert_eq!(
+        book.group_members("friends"),
+        vec!["Alice <alice@example.com>", "Bob <bob@example.com>"]
+    );
+    assert_eq!(
+        book.group_members("work"),
+        vec!["Alice <alice@example.com>"]
+    );
+    assert_eq!(book.search("work"), vec!["Alice <alice@work.example.com>"]);
+}
@@ -0,0 +1,169 @@
+/*
+ * meli - addressbook module
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Import and export abook's `addressbook` files.
+ *
+ * Entries are INI sections with `key=value` fields. `name`, `email`, `nick` and `url` map to the
+ * card's fields, `groups` to the `CATEGORIES` property and `notes` to `NOTE`. Other fields are
+ * kept as properties with their name in uppercase so that they are written back on export.
+ */
+use super::*;
+
+const FIELDS: &[&str] = &[
+    "address",
+    "address2",
+    "city",
+    "state",
+    "zip",
+    "country",
+    "phone",
+    "workphone",
+    "fax",
+    "mobile",
+    "anniversary",
+];
+
+/// Parse the entries of an abook address book.
+pub fn parse_addressbook(input: &str) -> Vec<Card> {
+    let mut ret = vec![];
+    let mut entry: Option<HashMap<&str, &str>> = None;
+    for line in input.lines().chain(std::iter::once("[]")) {
+        let line = line.trim();
+        if line.is_empty() || line.starts_with('#') {
+            continue;
+        }
+        if line.starts_with('[') && line.ends_with(']') {
+            ret.extend(entry.take().and_then(entry_to_card));
+            if line != "[format]" {
+                entry = Some(HashMap::default());
+            }
+            continue;
+        }
+        if let (Some(fields), Some(pos)) = (entry.as_mut(), line.find('=')) {
+            fields.insert(&line[..pos], &line[pos + 1..]);
+        }
+    }
+    ret
+}
+
+fn entry_to_card(mut entry: HashMap<&str, &str>) -> Option<Card> {
+    let name = entry.remove("name").unwrap_or_default();
+    let mut emails = split_list(entry.remove("email").unwrap_or_default());
+    let email = emails.next().unwrap_or_default();
+    if name.is_empty() && email.is_empty() {
+        return None;
+    }
+    let mut card = Card::new();
+    card.set_id(content_id(&[name, email]));
+    card.set_name(name.to_string());
+    card.set_email(email.to_string());
+    let others = emails.collect::<Vec<&str>>();
+    if !others.is_empty() {
+        card.set_extra_property("EMAIL", others.join(", "));
+    }
+    if let Some(nick) = entry.remove("nick") {
+        card.set_additionalname(nick.to_string());
+    }
+    if let Some(url) = entry.remove("url") {
+        card.set_url(url.to_string());
+    }
+    if let Some(groups) = entry.remove("groups") {
+        card.set_extra_property(
+            "CATEGORIES",
+            split_list(groups).collect::<Vec<&str>>().join(", "),
+        );
+    }
+    if let Some(notes) = entry.remove("notes") {
+        card.set_extra_property("NOTE", notes.to_string());
+    }
+    for (k, v) in entry {
+        card.set_extra_property(&k.to_ascii_uppercase(), v.to_string());
+    }
+    Some(card)
+}
+
+/// Write cards as an abook address book.
+pub fn export_addressbook<'a>(cards: impl Iterator<Item = &'a Card>) -> String {
+    let mut cards = cards.collect::<Vec<&Card>>();
+    cards.sort_by(|a, b| (a.name(), a.email()).cmp(&(b.name(), b.email())));
+    let mut ret =
+        String::from("# abook addressbook file\n\n[format]\nprogram=abook\nversion=0.6.1\n");
+    for (i, card) in cards.into_iter().enumerate() {
+        ret.push_str(&format!("\n[{}]\nname={}\n", i, card.name()));
+        let emails = std::iter::once(card.email())
+            .chain(
+                card.extra_property("EMAIL")
+                    .map(split_list)
+                    .into_iter()
+                    .flatten(),
+            )
+            .filter(|e| !e.is_empty())
+            .collect::<Vec<&str>>();
+        if !emails.is_empty() {
+            ret.push_str(&format!("email={}\n", emails.join(",")));
+        }
+        if !card.additionalname().is_empty() {
+            ret.push_str(&format!("nick={}\n", card.additionalname()));
+        }
+        if !card.url().is_empty() {
+            ret.push_str(&format!("url={}\n", card.url()));
+        }
+        if let Some(groups) = card.extra_property("CATEGORIES") {
+            ret.push_str(&format!(
+                "groups={}\n",
+                split_list(groups).collect::<Vec<&str>>().join(",")
+            ));
+        }
+        if let Some(notes) = card.extra_property("NOTE") {
+            ret.push_str(&format!("notes={}\n", notes.replace('\n', " ")));
+        }
+        for field in FIELDS {
+            let value = card
+                .extra_property(&field.to_ascii_uppercase())
+                .or_else(|| match *field {
+                    "phone" => card.extra_property("TEL"),
+                    _ => None,
+                });
+            if let Some(value) = value {
+                ret.push_str(&format!("{}={}\n", field, value));
+            }
+        }
+    }
+    ret
+}
+
+#[test]
+fn test_abook() {
+    let cards = parse_addressbook(
+        "# abook addressbook file\n\n[format]\nprogram=abook\nversion=0.6.1\n\n[0]\nname=John Doe\nemail=jdoe@example.com,john@example.com\nnick=jd\ngroups=friends,work\nmobile=+1 555 1212\n",
+    );
+    assert_eq!(cards.len(), 1);
+    assert_eq!(cards[0].name(), "John Doe");
+    assert_eq!(cards[0].email(), "jdoe@example.com");
+    assert_eq!(cards[0].extra_property("EMAIL"), Some("john@example.com"));
+    assert_eq!(cards[0].additionalname(), "jd");
+    assert_eq!(cards[0].extra_property("CATEGORIES"), Some("friends, work"));
+    assert_eq!(cards[0].extra_property("MOBILE"), Some("+1 555 1212"));
+    assert_eq!(
+        export_addressbook(cards.iter()),
+        "# abook addressbook file\n\n[format]\nprogram=abook\nversion=0.6.1\n\n[0]\nname=John Doe\nemail=jdoe@example.com,john@example.com\nnick=jd\ngroups=friends,work\nmobile=+1 555 1212\n"
+    );
+}
@@ -0,0 +1,139 @@
+/*
+ * meli - addressbook module
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Addresses collected from read and sent mail, used for recipient completion.
+ */
+use super::*;
+use crate::email::Address;
+
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct CollectedAddress {
+    pub name: String,
+    pub email: String,
+    /// How many times the address was seen.
+    pub count: u64,
+    pub last_seen: UnixTimestamp,
+}
+
+impl CollectedAddress {
+    pub fn to_address_string(&self) -> String {
+        if self.name.is_empty() {
+            self.email.clone()
+        } else {
+            format!("{} <{}>", self.name, self.email)
+        }
+    }
+}
+
+/// Collected addresses, keyed by their lowercase e-mail.
+#[derive(Debug, Clone, Default, PartialEq, Deserialize, Serialize)]
+pub struct CollectedAddresses {
+    entries: HashMap<String, CollectedAddress>,
+}
+
+impl CollectedAddresses {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Record that `address` was seen at `timestamp`. The latest non-empty display name is kept.
+    pub fn collect(&mut self, address: &Address, timestamp: UnixTimestamp) {
+        let email = address.get_email();
+        if email.is_empty() {
+            return;
+        }
+        let name = address.get_display_name().unwrap_or_default();
+        let entry = self
+            .entries
+            .entry(email.to_lowercase())
+            .or_insert_with(|| CollectedAddress {
+                name: String::new(),
+                email,
+                count: 0,
+                last_seen: timestamp,
+            });
+        entry.count += 1;
+        entry.last_seen = std::cmp::max(entry.last_seen, timestamp);
+        if !name.is_empty() {
+            entry.name = name;
+        }
+    }
+
+    /// Addresses whose name or e-mail contain `term`, most frequent first.
+    pub fn search(&self, term: &str) -> Vec<&CollectedAddress> {
+        let term = term.to_lowercase();
+        let mut ret = self
+            .entries
+            .iter()
+            .filter(|(email, c)| email.contains(&term) || c.name.to_lowercase().contains(&term))
+            .map(|(_, c)| c)
+            .collect::<Vec<&CollectedAddress>>();
+        ret.sort_by(|a, b| {
+            b.count
+                .cmp(&a.count)
+                .then_with(|| b.last_seen.cmp(&a.last_seen))
+        });
+        ret
+    }
+
+    /// Remove addresses last seen before `timestamp`, or all of them if it is `None`. Returns the
+    /// number of removed addresses.
+    pub fn purge(&mut self, timestamp: Option<UnixTimestamp>) -> usize {
+        let len = self.entries.len();
+        if let Some(timestamp) = timestamp {
+            self.entries.retain(|_, c| c.last_seen >= timestamp);
+        } else {
+            self.entries.clear();
+        }
+        len - self.entries.len()
+    }
+}
+
+#[test]
+fn test_collected_addresses() {
+    let mut collected = CollectedAddresses::new();
+    collected.collect(&Address::new(None, "jdoe@example.com".into()), 10);
+    collected.collect(
+        &Address::new(Some("John Doe".into()), "JDoe@example.com".into()),
+        20,
+    );
+    collected.collect(&Address::new(None, "john@example.org".into()), 30);
+    assert_eq!(collected.len(), 2);
+    assert_eq!(
+        collected
+            .search("john")
+            .into_iter()
+            .map(CollectedAddress::to_address_string)
+            .collect::<Vec<String>>(),
+        vec!["John Doe <jdoe@example.com>", "john@example.org"]
+    );
+    assert_eq!(collected.purge(Some(25)), 1);
+    assert_eq!(collected.purge(None), 1);
+    assert!(collected.is_empty());
+}
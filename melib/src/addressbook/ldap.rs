@@ -0,0 +1,272 @@
+/*
+ * meli - addressbook module
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Look up contacts in an LDAP directory with the OpenLDAP `ldapsearch` command.
+ */
+use super::*;
+use crate::conf::false_val;
+use crate::error::{MeliError, Result};
+use crate::shellexpand::ShellExpandTrait;
+use crate::{log, ERROR};
+use data_encoding::BASE64;
+use std::path::Path;
+use std::process::{Command, Stdio};
+use std::sync::Mutex;
+
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct LdapSettings {
+    /// URI of the LDAP server, eg `ldaps://ldap.example.com`.
+    pub uri: String,
+    /// Base DN of searches, eg `ou=people,dc=example,dc=com`.
+    pub base_dn: String,
+    /// Search filter. Every `%s` is replaced by the escaped search term.
+    /// Default: "(|(cn=*%s*)(mail=*%s*))"
+    #[serde(default = "default_filter")]
+    pub filter: String,
+    /// DN to bind as. Searches are anonymous if unset.
+    /// Default: None
+    #[serde(default)]
+    pub bind_dn: Option<String>,
+    /// File containing the password of `bind_dn`.
+    /// Default: None
+    #[serde(default)]
+    pub bind_password_file: Option<String>,
+    /// Require StartTLS on `ldap://` connections.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub starttls: bool,
+    /// Do not verify the server's TLS certificate.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub danger_accept_invalid_certs: bool,
+    /// Seconds to wait for the server before giving up.
+    /// Default: 2
+    #[serde(default = "default_timeout")]
+    pub timeout: u64,
+    /// Maximum number of entries returned by a search.
+    /// Default: 20
+    #[serde(default = "default_size_limit")]
+    pub size_limit: usize,
+    /// Minimum length of a search term before the server is queried.
+    /// Default: 3
+    #[serde(default = "default_min_length")]
+    pub min_length: usize,
+}
+
+fn default_filter() -> String {
+    "(|(cn=*%s*)(mail=*%s*))".to_string()
+}
+
+fn default_timeout() -> u64 {
+    2
+}
+
+fn default_size_limit() -> usize {
+    20
+}
+
+fn default_min_length() -> usize {
+    3
+}
+
+/// Read-only address book backed by an LDAP directory.
+///
+/// Results are cached per search term. When a term extends a cached term whose search was not
+/// truncated by `size_limit`, the cached entries are filtered instead of querying the server again.
+#[derive(Debug)]
+pub struct LdapAddressBook {
+    settings: LdapSettings,
+    cache: Mutex<HashMap<String, (Vec<Card>, bool)>>,
+}
+
+impl LdapAddressBook {
+    pub fn new(settings: LdapSettings) -> Self {
+        LdapAddressBook {
+            settings,
+            cache: Mutex::new(HashMap::default()),
+        }
+    }
+
+    pub fn search(&self, term: &str) -> Vec<Card> {
+        let term = term.trim();
+        if term.chars().count() < self.settings.min_length {
+            return vec![];
+        }
+        let mut cache = self.cache.lock().unwrap();
+        if let Some((cards, _)) = cache.get(term) {
+            return cards.clone();
+        }
+        let lowercase_term = term.to_lowercase();
+        if let Some((cards, _)) = cache
+            .iter()
+            .filter(|(t, (_, complete))| *complete && term.starts_with(t.as_str()))
+            .max_by_key(|(t, _)| t.len())
+            .map(|(_, v)| v)
+        {
+            let cards = cards
+                .iter()
+                .filter(|c| {
+                    c.name().to_lowercase().contains(&lowercase_term)
+                        || c.email().to_lowercase().contains(&lowercase_term)
+                })
+                .cloned()
+                .collect::<Vec<Card>>();
+            cache.insert(term.to_string(), (cards.clone(), true));
+            return cards;
+        }
+        match self.query(term) {
+            Ok(cards) => {
+                let complete = cards.len() < self.settings.size_limit;
+                cache.insert(term.to_string(), (cards.clone(), complete));
+                cards
+            }
+            Err(err) => {
+                log(
+                    format!("LDAP search on {} failed: {}", self.settings.uri, err),
+                    ERROR,
+                );
+                vec![]
+            }
+        }
+    }
+
+    fn query(&self, term: &str) -> Result<Vec<Card>> {
+        let filter = self
+            .settings
+            .filter
+            .replace("%s", &escape_filter_value(term));
+        let mut cmd = Command::new("ldapsearch");
+        cmd.args(&["-x", "-LLL", "-o", "ldif-wrap=no"])
+            .arg("-o")
+            .arg(format!("nettimeout={}", self.settings.timeout))
+            .arg("-l")
+            .arg(self.settings.timeout.to_string())
+            .arg("-z")
+            .arg(self.settings.size_limit.to_string())
+            .arg("-H")
+            .arg(&self.settings.uri)
+            .arg("-b")
+            .arg(&self.settings.base_dn);
+        if self.settings.starttls {
+            cmd.arg("-ZZ");
+        }
+        if self.settings.danger_accept_invalid_certs {
+            cmd.env("LDAPTLS_REQCERT", "never");
+        }
+        if let Some(ref bind_dn) = self.settings.bind_dn {
+            cmd.arg("-D").arg(bind_dn);
+        }
+        if let Some(ref password_file) = self.settings.bind_password_file {
+            cmd.arg("-y").arg(Path::new(password_file).expand());
+        }
+        let output = cmd
+            .arg(filter)
+            .args(&["cn", "mail"])
+            .stdin(Stdio::null())
+            .stdout(Stdio::piped())
+            .stderr(Stdio::piped())
+            .output()
+            .map_err(|err| MeliError::new(format!("Could not execute ldapsearch: {}", err)))?;
+        /* Exit status 4 is LDAP_SIZELIMIT_EXCEEDED, the results up to the limit are still
+         * printed. */
+        if !output.status.success() && output.status.code() != Some(4) {
+            return Err(MeliError::new(format!(
+                "ldapsearch exited with {}: {}",
+                output.status,
+                String::from_utf8_lossy(&output.stderr).trim()
+            )));
+        }
+        Ok(parse_ldif(&String::from_utf8_lossy(&output.stdout)))
+    }
+}
+
+/// Escape special characters of a filter assertion value (RFC 4515).
+pub fn escape_filter_value(s: &str) -> String {
+    let mut ret = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '*' => ret.push_str("\\2a"),
+            '(' => ret.push_str("\\28"),
+            ')' => ret.push_str("\\29"),
+            '\\' => ret.push_str("\\5c"),
+            '\0' => ret.push_str("\\00"),
+            c => ret.push(c),
+        }
+    }
+    ret
+}
+
+/// Convert `ldapsearch -LLL` output to cards, one for each `mail` value of an entry.
+pub fn parse_ldif(s: &str) -> Vec<Card> {
+    let mut ret = vec![];
+    for entry in s.split("\n\n") {
+        let mut name = String::new();
+        let mut emails = vec![];
+        for line in entry.lines() {
+            let (attr, value) = if let Some(pos) = line.find(':') {
+                (&line[..pos], &line[pos + 1..])
+            } else {
+                continue;
+            };
+            let value = if let Some(b64) = value.strip_prefix(':') {
+                match BASE64.decode(b64.trim().as_bytes()) {
+                    Ok(v) => String::from_utf8_lossy(&v).to_string(),
+                    Err(_) => continue,
+                }
+            } else {
+                value.trim().to_string()
+            };
+            if attr.eq_ignore_ascii_case("cn") && name.is_empty() {
+                name = value;
+            } else if attr.eq_ignore_ascii_case("mail") {
+                emails.push(value);
+            }
+        }
+        for email in emails {
+            let mut card = Card::new();
+            card.set_name(name.clone());
+            card.set_email(email);
+            card.set_external_resource(true);
+            ret.push(card);
+        }
+    }
+    ret
+}
+
+#[test]
+fn test_ldap_parse_ldif() {
+    assert_eq!(escape_filter_value("a*(b)\\"), "a\\2a\\28b\\29\\5c");
+    let cards = parse_ldif(
+        "dn: uid=jdoe,ou=people,dc=example,dc=com\ncn: John Doe\nmail: jdoe@example.com\nmail: john@example.com\n\ndn: uid=xy,ou=people,dc=example,dc=com\ncn:: WMOpbmlh\nmail: xenia@example.com\n",
+    );
+    assert_eq!(
+        cards
+            .iter()
+            .map(|c| (c.name(), c.email()))
+            .collect::<Vec<(&str, &str)>>(),
+        vec![
+            ("John Doe", "jdoe@example.com"),
+            ("John Doe", "john@example.com"),
+            ("Xénia", "xenia@example.com"),
+        ]
+    );
+}
@@ -0,0 +1,176 @@
+/*
+ * meli - addressbook module
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Import and export mutt alias files.
+ *
+ * Each alias is a line `alias [-group NAME]... KEY ADDRESS[, ADDRESS]...`. The key becomes the
+ * card's nickname, groups are kept in the `CATEGORIES` property and addresses after the first in
+ * the `EMAIL` property, like additional e-mails of vCards.
+ */
+use super::*;
+use crate::email::parser::address::rfc2822address_list;
+
+/// Parse the aliases of a mutt alias file. Other commands and invalid lines are skipped.
+pub fn parse_aliases(input: &str) -> Vec<Card> {
+    let mut ret = vec![];
+    let mut line = String::new();
+    for l in input.lines() {
+        if let Some(l) = l.strip_suffix('\\') {
+            line.push_str(l);
+            continue;
+        }
+        line.push_str(l);
+        ret.extend(parse_alias(&line));
+        line.clear();
+    }
+    ret.extend(parse_alias(&line));
+    ret
+}
+
+fn next_word(input: &str) -> (&str, &str) {
+    let input = input.trim_start();
+    let end = input
+        .find(char::is_whitespace)
+        .unwrap_or_else(|| input.len());
+    (&input[..end], &input[end..])
+}
+
+fn parse_alias(line: &str) -> Option<Card> {
+    let (command, mut rest) = next_word(line);
+    if command != "alias" {
+        return None;
+    }
+    let mut groups = vec![];
+    let key = loop {
+        let (word, r) = next_word(rest);
+        rest = r;
+        if word == "-group" {
+            let (group, r) = next_word(rest);
+            groups.push(group);
+            rest = r;
+        } else {
+            break word;
+        }
+    };
+    if key.is_empty() {
+        return None;
+    }
+    let (_, addresses) = rfc2822address_list(rest.trim().as_bytes()).ok()?;
+    let mut addresses = addresses.into_iter();
+    let first = addresses.next()?;
+    let name = first.get_display_name().unwrap_or_default();
+    let email = first.get_email();
+    let mut card = Card::new();
+    card.set_id(content_id(&[key, &name, &email]));
+    card.set_additionalname(key.to_string());
+    card.set_name(name);
+    card.set_email(email);
+    let others = addresses.map(|a| a.to_string()).collect::<Vec<String>>();
+    if !others.is_empty() {
+        card.set_extra_property("EMAIL", others.join(", "));
+    }
+    if !groups.is_empty() {
+        card.set_extra_property("CATEGORIES", groups.join(", "));
+    }
+    Some(card)
+}
+
+/// Format a name and e-mail as an address, quoting the name if needed.
+fn format_address(name: &str, email: &str) -> String {
+    if name.is_empty() {
+        email.to_string()
+    } else if name.contains(|c| ",.;:<>@()[]\"\\".contains(c)) {
+        format!(
+            "\"{}\" <{}>",
+            name.replace('\\', "\\\\").replace('"', "\\\""),
+            email
+        )
+    } else {
+        format!("{} <{}>", name, email)
+    }
+}
+
+/// Write cards as mutt aliases. Cards without an e-mail are skipped, and cards without a nickname
+/// are keyed by the local part of their e-mail.
+pub fn export_aliases<'a>(cards: impl Iterator<Item = &'a Card>) -> String {
+    let mut aliases = cards
+        .filter(|c| !c.email().is_empty())
+        .map(|c| {
+            let key = if c.additionalname().is_empty() {
+                c.email().split('@').next().unwrap_or_default()
+            } else {
+                c.additionalname()
+            };
+            (key.split_whitespace().collect::<Vec<&str>>().join("_"), c)
+        })
+        .collect::<Vec<(String, &Card)>>();
+    aliases.sort_by(|a, b| a.0.cmp(&b.0));
+    let mut ret = String::new();
+    let mut used_keys: HashMap<String, usize> = HashMap::default();
+    for (mut key, card) in aliases {
+        let count = used_keys.entry(key.clone()).or_default();
+        *count += 1;
+        if *count > 1 {
+            key = format!("{}{}", key, count);
+        }
+        ret.push_str("alias");
+        for group in card
+            .extra_property("CATEGORIES")
+            .map(split_list)
+            .into_iter()
+            .flatten()
+        {
+            ret.push_str(" -group ");
+            ret.push_str(&group.split_whitespace().collect::<Vec<&str>>().join("_"));
+        }
+        ret.push(' ');
+        ret.push_str(&key);
+        ret.push(' ');
+        ret.push_str(&format_address(card.name(), card.email()));
+        if let Some(others) = card.extra_property("EMAIL") {
+            ret.push_str(", ");
+            ret.push_str(others);
+        }
+        ret.push('\n');
+    }
+    ret
+}
+
+#[test]
+fn test_mutt_aliases() {
+    let cards = parse_aliases(
+        "# comment\nset alias_file=~/.mutt/aliases\nalias -group work jdoe John Doe <jdoe@example.com>\nalias team alice@example.com, \\\n  Bob <bob@example.com>\n",
+    );
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards[0].additionalname(), "jdoe");
+    assert_eq!(cards[0].name(), "John Doe");
+    assert_eq!(cards[0].email(), "jdoe@example.com");
+    assert_eq!(cards[0].extra_property("CATEGORIES"), Some("work"));
+    assert_eq!(cards[1].email(), "alice@example.com");
+    assert_eq!(
+        cards[1].extra_property("EMAIL"),
+        Some("Bob <bob@example.com>")
+    );
+    assert_eq!(
+        export_aliases(cards.iter()),
+        "alias -group work jdoe John Doe <jdoe@example.com>\nalias team alice@example.com, Bob <bob@example.com>\n"
+    );
+}
@@ -50,10 +50,24 @@ static FOOTER: &str = "END:VCARD\r\n";
 
 #[derive(Debug)]
 pub struct VCard<T: VCardVersion>(
-    HashMap<String, ContentLine>,
+    HashMap<String, Vec<ContentLine>>,
     std::marker::PhantomData<*const T>,
 );
 
+impl ContentLine {
+    /// The `TYPE` parameter values of this line, except `pref` and `internet`, e.g. `work`.
+    fn type_label(&self) -> String {
+        self.params
+            .iter()
+            .filter(|p| p.to_ascii_lowercase().starts_with("type="))
+            .flat_map(|p| p["type=".len()..].split(','))
+            .map(|t| t.trim_matches('"'))
+            .filter(|t| !t.eq_ignore_ascii_case("pref") && !t.eq_ignore_ascii_case("internet"))
+            .collect::<Vec<&str>>()
+            .join(",")
+    }
+}
+
 impl<V: VCardVersion> VCard<V> {
     pub fn new_v4() -> VCard<impl VCardVersion> {
         VCard(
@@ -144,7 +158,9 @@ impl CardDeserializer {
                 )));
             }
             el.value = l[value_start..].replace("\\:", ":");
-            ret.insert(name, el);
+            ret.entry(name.to_ascii_uppercase())
+                .or_insert_with(Vec::new)
+                .push(el);
         }
         Ok(VCard(ret, std::marker::PhantomData::<*const VCardVersion4>))
     }
@@ -158,26 +174,42 @@ impl<V: VCardVersion> TryInto<Card> for VCard<V> {
         card.set_id(CardId::Hash({
             use std::hash::Hasher;
             let mut hasher = std::collections::hash_map::DefaultHasher::new();
-            if let Some(val) = self.0.get("FN") {
-                hasher.write(val.value.as_bytes());
-            }
-            if let Some(val) = self.0.get("N") {
-                hasher.write(val.value.as_bytes());
-            }
-            if let Some(val) = self.0.get("EMAIL") {
-                hasher.write(val.value.as_bytes());
+            for name in &["FN", "N", "EMAIL"] {
+                if let Some(val) = self.0.get(*name).and_then(|v| v.first()) {
+                    hasher.write(val.value.as_bytes());
+                }
             }
             hasher.finish()
         }));
-        if let Some(val) = self.0.remove("FN") {
-            card.set_name(val.value);
+        /* Family Name; Given Names; Additional Names; Honorific Prefixes; Honorific Suffixes */
+        let n = self
+            .take("N")
+            .map(|val| split_structured(&val.value))
+            .unwrap_or_default();
+        let n_component = |i: usize| n.get(i).map(String::as_str).unwrap_or_default();
+        if let Some(val) = self.take("FN") {
+            card.set_name(unescape(&val.value));
         } else {
-            return Err(MeliError::new("FN entry missing in VCard."));
+            let name = [n_component(1), n_component(2), n_component(0)]
+                .iter()
+                .filter(|s| !s.is_empty())
+                .cloned()
+                .collect::<Vec<&str>>()
+                .join(" ");
+            if name.is_empty() {
+                return Err(MeliError::new("FN entry missing in VCard."));
+            }
+            card.set_name(name);
         }
-        if let Some(val) = self.0.remove("NICKNAME") {
-            card.set_additionalname(val.value);
+        card.set_name_prefix(n_component(3).to_string());
+        card.set_name_suffix(n_component(4).to_string());
+        if let Some(val) = self.take("NICKNAME") {
+            card.set_additionalname(unescape(&val.value));
         }
-        if let Some(val) = self.0.remove("BDAY") {
+        if let Some(val) = self.take("TITLE") {
+            card.set_title(unescape(&val.value));
+        }
+        if let Some(val) = self.take("BDAY") {
             /* 4.3.4.  DATE-AND-OR-TIME
 
             Either a DATE-TIME, a DATE, or a TIME value.  To allow unambiguous
@@ -204,26 +236,137 @@ impl<V: VCardVersion> TryInto<Card> for VCard<V> {
             card.birthday = crate::datetime::timestamp_from_string(val.value.as_str(), "%Y%m%d\0")
                 .unwrap_or_default();
         }
-        if let Some(val) = self.0.remove("EMAIL") {
-            card.set_email(val.value);
+        if let Some(val) = self.take("EMAIL") {
+            let email = unescape(&val.value);
+            card.set_email_label(&email, val.type_label());
+            card.set_email(email);
+        }
+        for l in self.0.get("EMAIL").into_iter().flatten() {
+            card.set_email_label(&unescape(&l.value), l.type_label());
         }
-        if let Some(val) = self.0.remove("URL") {
-            card.set_url(val.value);
+        if let Some(val) = self.take("URL") {
+            card.set_url(unescape(&val.value));
         }
-        if let Some(val) = self.0.remove("KEY") {
+        if let Some(val) = self.take("KEY") {
             card.set_key(val.value);
         }
+        /* Properties that can appear more than once, e.g. additional EMAIL and TEL entries, are
+         * joined. */
         for (k, v) in self.0.into_iter() {
-            if k.eq_ignore_ascii_case("VERSION") || k.eq_ignore_ascii_case("N") {
+            if k == "VERSION" || k == "PRODID" || v.is_empty() {
                 continue;
             }
-            card.set_extra_property(&k, v.value);
+            let value = v
+                .iter()
+                .map(|l| match k.as_str() {
+                    "ORG" => split_structured(&l.value)
+                        .into_iter()
+                        .filter(|s| !s.is_empty())
+                        .collect::<Vec<String>>()
+                        .join(", "),
+                    "TEL" if l.value.to_ascii_lowercase().starts_with("tel:") => {
+                        unescape(&l.value["tel:".len()..])
+                    }
+                    _ => unescape(&l.value),
+                })
+                .collect::<Vec<String>>()
+                .join(", ");
+            card.set_extra_property(&k, value);
         }
 
         Ok(card)
     }
 }
 
+impl<V: VCardVersion> VCard<V> {
+    /// Remove and return the preferred content line named `name`: the first with a `PREF`
+    /// parameter or `TYPE=pref`, otherwise the first one.
+    fn take(&mut self, name: &str) -> Option<ContentLine> {
+        let lines = self.0.get_mut(name)?;
+        if lines.is_empty() {
+            return None;
+        }
+        let pos = lines
+            .iter()
+            .position(|l| {
+                l.params.iter().any(|p| {
+                    let p = p.to_ascii_lowercase();
+                    p.starts_with("pref=")
+                        || (p.starts_with("type=")
+                            && p["type=".len()..].split(',').any(|t| t == "pref"))
+                })
+            })
+            .unwrap_or(0);
+        Some(lines.remove(pos))
+    }
+}
+
+/// Remove backslash escapes from a text value.
+fn unescape(value: &str) -> String {
+    let mut ret = String::with_capacity(value.len());
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        if c == '\\' {
+            match chars.next() {
+                Some('n') | Some('N') => ret.push('\n'),
+                Some(c) => ret.push(c),
+                None => {}
+            }
+        } else {
+            ret.push(c);
+        }
+    }
+    ret
+}
+
+/// Split a structured value like `N` or `ORG` on unescaped semicolons.
+fn split_structured(value: &str) -> Vec<String> {
+    let mut ret = vec![];
+    let mut start = 0;
+    let mut escaped = false;
+    for (i, c) in value.char_indices() {
+        match c {
+            '\\' if !escaped => escaped = true,
+            ';' if !escaped => {
+                ret.push(unescape(&value[start..i]));
+                start = i + 1;
+            }
+            _ => escaped = false,
+        }
+    }
+    ret.push(unescape(&value[start..]));
+    ret
+}
+
+/// Join folded lines and use CRLF line endings.
+fn unfold(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    for line in input.lines() {
+        if (line.starts_with(' ') || line.starts_with('\t')) && ret.ends_with("\r\n") {
+            ret.truncate(ret.len() - "\r\n".len());
+            ret.push_str(&line[1..]);
+        } else if !line.trim().is_empty() {
+            ret.push_str(line);
+        } else {
+            continue;
+        }
+        ret.push_str("\r\n");
+    }
+    ret
+}
+
+/// Parse all cards in `input`, e.g. the contents of a `.vcf` file or attachment.
+pub fn parse_cards(input: &str) -> Result<Vec<Card>> {
+    let input = unfold(input);
+    let (_, cards) = parse_card()
+        .parse(input.as_str())
+        .map_err(|_| MeliError::new("Error while parsing vcard: no cards found."))?;
+    cards
+        .into_iter()
+        .map(|s| CardDeserializer::from_str(s).and_then(TryInto::try_into))
+        .collect()
+}
+
 fn parse_card<'a>() -> impl Parser<'a, Vec<&'a str>> {
     move |input| {
         one_or_more(prefix(
@@ -269,6 +412,7 @@ pub fn load_cards(p: &std::path::Path) -> Result<Vec<Card>> {
                 use std::io::Read;
                 contents.clear();
                 std::fs::File::open(&f)?.read_to_string(&mut contents)?;
+                contents = unfold(&contents);
                 if let Ok((_, c)) = parse_card().parse(contents.as_str()) {
                     for s in c {
                         ret.push(
@@ -303,3 +447,27 @@ fn test_card() {
     let j = "BEGIN:VCARD\r\nVERSION:4.0\r\nN:Gump;Forrest;;Mr.;\r\nFN:Forrest Gump\r\nORG:Bubba Gump Shrimp Co.\r\nTITLE:Shrimp Man\r\nPHOTO;MEDIATYPE=image/gif:http://www.example.com/dir_photos/my_photo.gif\r\nTEL;TYPE=work,voice;VALUE=uri:tel:+1-111-555-1212\r\nTEL;TYPE=home,voice;VALUE=uri:tel:+1-404-555-1212\r\nADR;TYPE=WORK;PREF=1;LABEL=\"100 Waters Edge\\nBaytown\\, LA 30314\\nUnited States of America\":;;100 Waters Edge;Baytown;LA;30314;United States of America\r\nADR;TYPE=HOME;LABEL=\"42 Plantation St.\\nBaytown\\, LA 30314\\nUnited States of America\":;;42 Plantation St.;Baytown;LA;30314;United States of America\r\nEMAIL:forrestgump@example.com\r\nREV:20080424T195243Z\r\nx-qq:21588891\r\nEND:VCARD\r\n";
     println!("results = {:#?}", CardDeserializer::from_str(j).unwrap());
 }
+
+#[test]
+fn test_parse_cards() {
+    let cards = parse_cards("BEGIN:VCARD\nVERSION:3.0\nN:Gump;Forrest;;Mr.;\nORG:Bubba Gump Shrimp Co.;Ship\n s\nEMAIL;TYPE=work:forrest@example.com\nEMAIL;TYPE=home,pref:gump@example.com\nTEL;TYPE=work:+1-111-555-1212\nTEL;VALUE=uri:tel:+1-404-555-1212\nEND:VCARD\n\nBEGIN:VCARD\nVERSION:4.0\nFN:Jenny\nEND:VCARD\n").unwrap();
+    assert_eq!(cards.len(), 2);
+    assert_eq!(cards[0].name(), "Forrest Gump");
+    assert_eq!(cards[0].name_prefix(), "Mr.");
+    assert_eq!(cards[0].email(), "gump@example.com");
+    assert_eq!(
+        cards[0].extra_property("EMAIL"),
+        Some("forrest@example.com")
+    );
+    assert_eq!(cards[0].email_label("gump@example.com"), Some("home"));
+    assert_eq!(cards[0].email_label("forrest@example.com"), Some("work"));
+    assert_eq!(
+        cards[0].extra_property("ORG"),
+        Some("Bubba Gump Shrimp Co., Ships")
+    );
+    assert_eq!(
+        cards[0].extra_property("TEL"),
+        Some("+1-111-555-1212, +1-404-555-1212")
+    );
+    assert_eq!(cards[1].name(), "Jenny");
+}
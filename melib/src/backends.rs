@@ -291,10 +291,57 @@ pub struct MailBackendCapabilities {
     pub is_remote: bool,
     pub extensions: Option<Vec<(String, MailBackendExtensionStatus)>>,
     pub supports_search: bool,
-    pub supports_tags: bool,
+    pub tag_support: TagSupport,
     pub supports_submission: bool,
+    /// Message bodies are only fetched on demand, so they shouldn't be fetched in bulk e.g. for
+    /// indexing.
+    pub lightweight_sync: bool,
 }
 
+/// Which custom keywords (tags) a backend can store on its messages.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum TagSupport {
+    /// Tags can't be set at all.
+    None,
+    /// Only tags already known to the backend can be set (e.g. an IMAP mailbox whose
+    /// `PERMANENTFLAGS` response lacks `\*`).
+    Existing,
+    /// Any tag can be set, and new ones are created on demand.
+    Any,
+}
+
+impl TagSupport {
+    pub fn can_set(self) -> bool {
+        self != TagSupport::None
+    }
+
+    pub fn can_create(self) -> bool {
+        self == TagSupport::Any
+    }
+}
+
+/// A single flag or tag change in a `MailBackend::set_flags` batch.
+#[derive(Debug, Clone, PartialEq, Eq, Hash)]
+pub enum FlagOp {
+    Flag(Flag, bool),
+    Tag(String, bool),
+}
+
+impl FlagOp {
+    /// Whether this operation sets (as opposed to unsets) its flag or tag.
+    pub fn is_set(&self) -> bool {
+        match self {
+            FlagOp::Flag(_, v) | FlagOp::Tag(_, v) => *v,
+        }
+    }
+
+    pub fn is_tag(&self) -> bool {
+        matches!(self, FlagOp::Tag(_, _))
+    }
+}
+
+pub type FlagOps = SmallVec<[FlagOp; 8]>;
+
 #[derive(Debug, Copy, Clone)]
 pub enum MailBackendExtensionStatus {
     Unsupported { comment: Option<&'static str> },
@@ -339,7 +386,7 @@ pub trait MailBackend: ::std::fmt::Debug + Send + Sync {
         &mut self,
         env_hashes: EnvelopeHashBatch,
         mailbox_hash: MailboxHash,
-        flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+        flags: FlagOps,
     ) -> ResultFuture<()>;
 
     fn delete_messages(
@@ -397,6 +444,12 @@ pub trait MailBackend: ::std::fmt::Debug + Send + Sync {
     ) -> ResultFuture<SmallVec<[EnvelopeHash; 512]>> {
         Err(MeliError::new("Unimplemented."))
     }
+
+    /// Download the bodies of every message in `mailbox_hash` ahead of time, for backends
+    /// that otherwise fetch them on demand.
+    fn download_mailbox(&mut self, _mailbox_hash: MailboxHash) -> ResultFuture<()> {
+        Err(MeliError::new("Unimplemented."))
+    }
 }
 
 /// A `BackendOp` manages common operations for the various mail backends. They only live for the
@@ -513,7 +566,10 @@ impl SpecialUsageMailbox {
             Some(SpecialUsageMailbox::Junk)
         } else if name.eq_ignore_ascii_case("sent") {
             Some(SpecialUsageMailbox::Sent)
-        } else if name.eq_ignore_ascii_case("trash") {
+        } else if ["trash", "deleted items", "deleted messages", "bin"]
+            .iter()
+            .any(|n| name.eq_ignore_ascii_case(n))
+        {
             Some(SpecialUsageMailbox::Trash)
         } else {
             Some(SpecialUsageMailbox::Normal)
@@ -521,6 +577,22 @@ impl SpecialUsageMailbox {
     }
 }
 
+/// How promptly changes to a mailbox should be noticed. Frontends can use it to schedule polls
+/// and to decide which watchers can be paused to save power.
+#[derive(Debug, Copy, Hash, Eq, Clone, Serialize, Deserialize, PartialEq, PartialOrd, Ord)]
+#[serde(rename_all = "lowercase")]
+pub enum MailboxWatchUrgency {
+    Low,
+    Normal,
+    High,
+}
+
+impl Default for MailboxWatchUrgency {
+    fn default() -> Self {
+        MailboxWatchUrgency::Normal
+    }
+}
+
 pub trait BackendMailbox: Debug {
     fn hash(&self) -> MailboxHash;
     fn name(&self) -> &str;
@@ -536,6 +608,11 @@ pub trait BackendMailbox: Debug {
     fn special_usage(&self) -> SpecialUsageMailbox;
     fn permissions(&self) -> MailboxPermissions;
     fn count(&self) -> Result<(usize, usize)>;
+    /// Whether new tags can be set on the messages of this mailbox, if the backend's
+    /// `MailBackendCapabilities::tag_support` allows creating them at all.
+    fn can_create_tags(&self) -> bool {
+        true
+    }
 }
 
 pub type AccountHash = u64;
@@ -697,6 +774,18 @@ fn test_lazy_count_set() {
     assert_eq!(new.len(), 10);
 }
 
+#[test]
+fn test_flag_op() {
+    let ops: FlagOps = smallvec::smallvec![
+        FlagOp::Flag(Flag::SEEN, true),
+        FlagOp::Tag("todo".to_string(), false),
+    ];
+    assert!(ops[0].is_set() && !ops[0].is_tag());
+    assert!(!ops[1].is_set() && ops[1].is_tag());
+    assert!(TagSupport::Existing.can_set() && !TagSupport::Existing.can_create());
+    assert!(!TagSupport::None.can_set());
+}
+
 pub struct IsSubscribedFn(Box<dyn Fn(&str) -> bool + Send + Sync>);
 
 impl std::fmt::Debug for IsSubscribedFn {
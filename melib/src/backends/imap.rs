@@ -44,7 +44,7 @@ use crate::backends::{
 
 use crate::collection::Collection;
 use crate::conf::AccountSettings;
-use crate::connections::timeout;
+use crate::connections::{timeout, ConnectionPolicy};
 use crate::email::{parser::BytesExt, *};
 use crate::error::{MeliError, Result, ResultIntoMeliError};
 use futures::lock::Mutex as FutureMutex;
@@ -98,7 +98,7 @@ pub struct ImapServerConf {
     pub use_tls: bool,
     pub danger_accept_invalid_certs: bool,
     pub protocol: ImapProtocol,
-    pub timeout: Option<Duration>,
+    pub connection_policy: ConnectionPolicy,
 }
 
 type Capabilities = HashSet<Vec<u8>>;
@@ -137,6 +137,8 @@ pub struct UIDStore {
     account_hash: AccountHash,
     account_name: Arc<String>,
     keep_offline_cache: bool,
+    /// Message bodies are only downloaded on demand, and aren't indexed for search.
+    lightweight_sync: bool,
     capabilities: Arc<Mutex<Capabilities>>,
     hash_index: Arc<Mutex<HashMap<EnvelopeHash, (UID, MailboxHash)>>>,
     uid_index: Arc<Mutex<HashMap<(MailboxHash, UID), EnvelopeHash>>>,
@@ -168,6 +170,7 @@ impl UIDStore {
             account_hash,
             account_name,
             keep_offline_cache: false,
+            lightweight_sync: false,
             capabilities: Default::default(),
             uidvalidity: Default::default(),
             envelopes: Default::default(),
@@ -188,6 +191,11 @@ impl UIDStore {
             timeout,
         }
     }
+
+    /// The data items requested for each message when fetching envelopes.
+    fn envelope_fetch_items(&self) -> &'static str {
+        "(UID FLAGS RFC822.SIZE ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)"
+    }
 }
 
 #[derive(Debug)]
@@ -196,6 +204,9 @@ pub struct ImapType {
     connection: Arc<FutureMutex<ImapConnection>>,
     server_conf: ImapServerConf,
     uid_store: Arc<UIDStore>,
+    /// Paths of the mailboxes watched with IDLE on connections of their own, at most
+    /// `max_idle_connections` of `idle_mailboxes`.
+    idle_mailboxes: Vec<String>,
 }
 
 impl MailBackend for ImapType {
@@ -288,8 +299,11 @@ impl MailBackend for ImapType {
             is_remote: true,
             supports_search: true,
             extensions: Some(extensions),
-            supports_tags: true,
+            /* Whether new keywords can be created depends on the mailbox, see
+             * `ImapMailbox::can_create_tags`. */
+            tag_support: TagSupport::Any,
             supports_submission: false,
+            lightweight_sync: self.uid_store.lightweight_sync,
         }
     }
 
@@ -432,7 +446,7 @@ impl MailBackend for ImapType {
 
     fn is_online(&self) -> ResultFuture<()> {
         let connection = self.connection.clone();
-        let timeout_dur = self.server_conf.timeout;
+        let timeout_dur = self.server_conf.connection_policy.read_timeout;
         Ok(Box::pin(async move {
             match timeout(timeout_dur, connection.lock()).await {
                 Ok(mut conn) => {
@@ -454,6 +468,7 @@ impl MailBackend for ImapType {
         let server_conf = self.server_conf.clone();
         let main_conn = self.connection.clone();
         let uid_store = self.uid_store.clone();
+        let idle_mailboxes = self.idle_mailboxes.clone();
         Ok(Box::pin(async move {
             let has_idle: bool = match server_conf.protocol {
                 ImapProtocol::IMAP {
@@ -468,48 +483,65 @@ impl MailBackend for ImapType {
                 }
                 _ => false,
             };
-            while let Err(err) = if has_idle {
-                idle(ImapWatchKit {
-                    conn: ImapConnection::new_connection(&server_conf, uid_store.clone()),
-                    main_conn: main_conn.clone(),
-                    uid_store: uid_store.clone(),
-                })
-                .await
-            } else {
-                poll_with_examine(ImapWatchKit {
-                    conn: ImapConnection::new_connection(&server_conf, uid_store.clone()),
-                    main_conn: main_conn.clone(),
-                    uid_store: uid_store.clone(),
-                })
-                .await
-            } {
-                let mut main_conn_lck = timeout(uid_store.timeout, main_conn.lock()).await?;
-                if err.kind.is_network() {
-                    uid_store.is_online.lock().unwrap().1 = Err(err.clone());
-                } else {
-                    return Err(err);
-                }
-                debug!("Watch failure: {}", err.to_string());
-                match timeout(uid_store.timeout, main_conn_lck.connect())
-                    .await
-                    .and_then(|res| res)
-                {
-                    Err(err2) => {
-                        debug!("Watch reconnect attempt failed: {}", err2.to_string());
-                    }
-                    Ok(()) => {
-                        debug!("Watch reconnect attempt succesful");
-                        continue;
-                    }
-                }
-                let account_hash = uid_store.account_hash;
-                main_conn_lck.add_refresh_event(RefreshEvent {
-                    account_hash,
-                    mailbox_hash: 0,
-                    kind: RefreshEventKind::Failure(err.clone()),
-                });
-                return Err(err);
+            if !has_idle {
+                return keep_watching(None, server_conf, main_conn, uid_store).await;
             }
+            let idle_mailboxes: Vec<(MailboxHash, String)> = {
+                let mailboxes_lck = timeout(uid_store.timeout, uid_store.mailboxes.lock()).await?;
+                idle_mailboxes
+                    .iter()
+                    .filter_map(|path| {
+                        let ret = mailboxes_lck
+                            .values()
+                            .find(|m| m.path() == path)
+                            .map(|m| (m.hash(), path.clone()));
+                        if ret.is_none() {
+                            debug!("idle_mailboxes: mailbox {} not found", path);
+                        }
+                        ret
+                    })
+                    .collect()
+            };
+            /* A watcher of `idle_mailboxes` that fails for other reasons than the network is
+             * dropped on its own, instead of stopping every other watcher with it. */
+            let extra_watchers =
+                futures::future::try_join_all(idle_mailboxes.iter().map(|(h, path)| {
+                    let target = Some(IdleTarget::Mailbox(*h));
+                    let watcher = keep_watching(
+                        target,
+                        server_conf.clone(),
+                        main_conn.clone(),
+                        uid_store.clone(),
+                    );
+                    let account_name = uid_store.account_name.clone();
+                    let path = path.clone();
+                    async move {
+                        match watcher.await {
+                            Err(err) if !err.kind.is_network() => {
+                                crate::log(
+                                    format!(
+                                        "Account `{}`: stopped watching mailbox `{}`: {}",
+                                        account_name, path, err
+                                    ),
+                                    crate::ERROR,
+                                );
+                                Ok(())
+                            }
+                            res => res,
+                        }
+                    }
+                }));
+            let idle_mailboxes = idle_mailboxes.iter().map(|(h, _)| *h).collect();
+            futures::future::try_join(
+                keep_watching(
+                    Some(IdleTarget::Inbox { idle_mailboxes }),
+                    server_conf.clone(),
+                    main_conn.clone(),
+                    uid_store.clone(),
+                ),
+                extra_watchers,
+            )
+            .await?;
             debug!("watch future returning");
             Ok(())
         }))
@@ -684,7 +716,7 @@ impl MailBackend for ImapType {
         &mut self,
         env_hashes: EnvelopeHashBatch,
         mailbox_hash: MailboxHash,
-        flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+        flags: FlagOps,
     ) -> ResultFuture<()> {
         let connection = self.connection.clone();
         let uid_store = self.uid_store.clone();
@@ -707,7 +739,8 @@ impl MailBackend for ImapType {
             let mut conn = connection.lock().await;
             conn.select_mailbox(mailbox_hash, &mut response, false)
                 .await?;
-            if flags.iter().any(|(_, b)| *b) {
+            let can_create_tags = uid_store.mailboxes.lock().await[&mailbox_hash].can_create_tags();
+            if flags.iter().any(FlagOp::is_set) {
                 /* Set flags/tags to true */
                 let mut set_seen = false;
                 let command = {
@@ -717,34 +750,37 @@ impl MailBackend for ImapType {
                         cmd = format!("{},{}", cmd, uid);
                     }
                     cmd = format!("{} +FLAGS (", cmd);
-                    for (f, v) in flags.iter() {
-                        if !*v {
-                            continue;
-                        }
-                        match f {
-                            Ok(flag) if *flag == Flag::REPLIED => {
+                    for op in flags.iter().filter(|op| op.is_set()) {
+                        match op {
+                            FlagOp::Flag(flag, _) if *flag == Flag::REPLIED => {
                                 cmd.push_str("\\Answered ");
                             }
-                            Ok(flag) if *flag == Flag::FLAGGED => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::FLAGGED => {
                                 cmd.push_str("\\Flagged ");
                             }
-                            Ok(flag) if *flag == Flag::TRASHED => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::TRASHED => {
                                 cmd.push_str("\\Deleted ");
                             }
-                            Ok(flag) if *flag == Flag::SEEN => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::SEEN => {
                                 cmd.push_str("\\Seen ");
                                 set_seen = true;
                             }
-                            Ok(flag) if *flag == Flag::DRAFT => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::DRAFT => {
                                 cmd.push_str("\\Draft ");
                             }
-                            Ok(_) => {
+                            FlagOp::Flag(_, _) => {
                                 crate::log(format!("Application error: more than one flag bit set in set_flags: {:?}", flags), crate::ERROR);
                                 return Err(MeliError::new(format!("Application error: more than one flag bit set in set_flags: {:?}", flags)).set_kind(crate::ErrorKind::Bug));
                             }
-                            Err(tag) => {
+                            FlagOp::Tag(tag, _) => {
                                 let hash = tag_hash!(tag);
                                 if !tag_lck.contains_key(&hash) {
+                                    if !can_create_tags {
+                                        return Err(MeliError::new(format!(
+                                            "Mailbox does not allow creating new keywords, {} is not an existing one.",
+                                            tag
+                                        )));
+                                    }
                                     tag_lck.insert(hash, tag.to_string());
                                 }
                                 cmd.push_str(tag);
@@ -769,7 +805,7 @@ impl MailBackend for ImapType {
                     };
                 }
             }
-            if flags.iter().any(|(_, b)| !*b) {
+            if flags.iter().any(|op| !op.is_set()) {
                 let mut set_unseen = false;
                 /* Set flags/tags to false */
                 let command = {
@@ -778,28 +814,25 @@ impl MailBackend for ImapType {
                         cmd = format!("{},{}", cmd, uid);
                     }
                     cmd = format!("{} -FLAGS (", cmd);
-                    for (f, v) in flags.iter() {
-                        if *v {
-                            continue;
-                        }
-                        match f {
-                            Ok(flag) if *flag == Flag::REPLIED => {
+                    for op in flags.iter().filter(|op| !op.is_set()) {
+                        match op {
+                            FlagOp::Flag(flag, _) if *flag == Flag::REPLIED => {
                                 cmd.push_str("\\Answered ");
                             }
-                            Ok(flag) if *flag == Flag::FLAGGED => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::FLAGGED => {
                                 cmd.push_str("\\Flagged ");
                             }
-                            Ok(flag) if *flag == Flag::TRASHED => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::TRASHED => {
                                 cmd.push_str("\\Deleted ");
                             }
-                            Ok(flag) if *flag == Flag::SEEN => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::SEEN => {
                                 cmd.push_str("\\Seen ");
                                 set_unseen = true;
                             }
-                            Ok(flag) if *flag == Flag::DRAFT => {
+                            FlagOp::Flag(flag, _) if *flag == Flag::DRAFT => {
                                 cmd.push_str("\\Draft ");
                             }
-                            Ok(_) => {
+                            FlagOp::Flag(_, _) => {
                                 crate::log(
                                     format!(
                         "Application error: more than one flag bit set in set_flags: {:?}", flags
@@ -810,7 +843,7 @@ impl MailBackend for ImapType {
                         "Application error: more than one flag bit set in set_flags: {:?}", flags
                     )));
                             }
-                            Err(tag) => {
+                            FlagOp::Tag(tag, _) => {
                                 cmd.push_str(tag);
                                 cmd.push(' ');
                             }
@@ -845,7 +878,7 @@ impl MailBackend for ImapType {
         let flag_future = self.set_flags(
             env_hashes,
             mailbox_hash,
-            smallvec::smallvec![(Ok(Flag::TRASHED), true)],
+            smallvec::smallvec![FlagOp::Flag(Flag::TRASHED, true)],
         )?;
         let connection = self.connection.clone();
         Ok(Box::pin(async move {
@@ -1148,6 +1181,40 @@ impl MailBackend for ImapType {
                     s.extend(escape_double_quote(t).chars());
                     s.push_str("\"");
                 }
+                AttachmentName(t) => {
+                    /* File names are in the MIME headers of the body */
+                    s.push_str(" BODY \"");
+                    s.extend(escape_double_quote(t).chars());
+                    s.push_str("\"");
+                }
+                List(t) => {
+                    s.push_str(" HEADER List-Id \"");
+                    s.extend(escape_double_quote(t).chars());
+                    s.push_str("\"");
+                }
+                Body(t) => {
+                    s.push_str(" BODY \"");
+                    s.extend(escape_double_quote(t).chars());
+                    s.push_str("\"");
+                }
+                Before(t) => {
+                    s.push_str(" BEFORE ");
+                    s.push_str(&search_date(*t));
+                }
+                After(t) => {
+                    s.push_str(" SINCE ");
+                    s.push_str(&search_date(*t));
+                }
+                Between(a, b) => {
+                    s.push_str(" SINCE ");
+                    s.push_str(&search_date(*a));
+                    s.push_str(" BEFORE ");
+                    s.push_str(&search_date(*b));
+                }
+                On(t) => {
+                    s.push_str(" ON ");
+                    s.push_str(&search_date(*t));
+                }
                 Flags(v) => {
                     for f in v {
                         match f.as_str() {
@@ -1184,9 +1251,12 @@ impl MailBackend for ImapType {
                     }
                 }
                 And(q1, q2) => {
+                    /* Parenthesize so that the conjunction is a single key inside OR and NOT */
+                    s.push_str(" (");
                     rec(q1, s);
                     s.push_str(" ");
                     rec(q2, s);
+                    s.push_str(")");
                 }
                 Or(q1, q2) => {
                     s.push_str(" OR ");
@@ -1201,6 +1271,12 @@ impl MailBackend for ImapType {
                 _ => {}
             }
         }
+        /// IMAP SEARCH dates, e.g. `1-Feb-1994`.
+        fn search_date(t: crate::UnixTimestamp) -> String {
+            crate::datetime::timestamp_to_string(t, Some("%e-%b-%Y"), true)
+                .trim()
+                .to_string()
+        }
         let mut query_str = String::new();
         rec(&query, &mut query_str);
         let connection = self.connection.clone();
@@ -1240,6 +1316,85 @@ impl MailBackend for ImapType {
             ))
         }))
     }
+
+    fn download_mailbox(&mut self, mailbox_hash: MailboxHash) -> ResultFuture<()> {
+        let connection = self.connection.clone();
+        let uid_store = self.uid_store.clone();
+        Ok(Box::pin(async move {
+            let mut cache_handle = match cache::offline_cache(&uid_store)? {
+                Some(cache_handle) => cache_handle,
+                None => {
+                    return Err(MeliError::new(
+                        "Downloading a mailbox requires the offline cache to be enabled.",
+                    ));
+                }
+            };
+            let mut uids = uid_store
+                .uid_index
+                .lock()
+                .unwrap()
+                .keys()
+                .filter_map(|(h, uid)| if *h == mailbox_hash { Some(*uid) } else { None })
+                .collect::<Vec<UID>>();
+            uids.sort_unstable();
+            /* Skip messages whose bodies have already been downloaded. */
+            uids.retain(|uid| matches!(cache_handle.rfc822(Ok(*uid), mailbox_hash), Ok(None)));
+            let mut response = Vec::with_capacity(8 * 1024);
+            for chunk in uids.chunks(50) {
+                {
+                    let mut conn = connection.lock().await;
+                    conn.connect().await?;
+                    conn.examine_mailbox(mailbox_hash, &mut response, false)
+                        .await?;
+                    conn.send_command(
+                        format!(
+                            "UID FETCH {} (FLAGS RFC822)",
+                            chunk
+                                .iter()
+                                .map(|uid| uid.to_string())
+                                .collect::<Vec<String>>()
+                                .join(",")
+                        )
+                        .as_bytes(),
+                    )
+                    .await?;
+                    conn.read_response(&mut response, RequiredResponses::FETCH_REQUIRED)
+                        .await?;
+                }
+                let (_, v, _) = protocol_parser::fetch_responses(&response)?;
+                for FetchResponse { uid, body, .. } in v {
+                    if let (Some(uid), Some(body)) = (uid, body) {
+                        cache_handle.insert_rfc822(mailbox_hash, uid, body)?;
+                    }
+                }
+            }
+            Ok(())
+        }))
+    }
+}
+
+/// Returns `server_password`, or the output of `server_password_command` if it is set.
+fn server_password(s: &AccountSettings) -> Result<String> {
+    if !s.extra.contains_key("server_password_command") {
+        return Ok(get_conf_val!(s["server_password"])?.to_string());
+    }
+    let invocation = get_conf_val!(s["server_password_command"])?;
+    let output = std::process::Command::new("sh")
+        .args(&["-c", invocation])
+        .stdin(std::process::Stdio::piped())
+        .stdout(std::process::Stdio::piped())
+        .stderr(std::process::Stdio::piped())
+        .output()?;
+    if !output.status.success() {
+        return Err(MeliError::new(format!(
+            "({}) server_password_command `{}` returned {}: {}",
+            s.name,
+            invocation,
+            output.status,
+            String::from_utf8_lossy(&output.stderr)
+        )));
+    }
+    Ok(std::str::from_utf8(&output.stdout)?.trim_end().to_string())
 }
 
 impl ImapType {
@@ -1251,33 +1406,13 @@ impl ImapType {
         let server_hostname = get_conf_val!(s["server_hostname"])?;
         let server_username = get_conf_val!(s["server_username"])?;
         let use_oauth2: bool = get_conf_val!(s["use_oauth2"], false)?;
-        let server_password = if !s.extra.contains_key("server_password_command") {
-            if use_oauth2 {
-                return Err(MeliError::new(format!(
-                    "({}) `use_oauth2` use requires `server_password_command` set with a command that returns an OAUTH2 token. Consult documentation for guidance.",
-                    s.name,
-                )));
-            }
-            get_conf_val!(s["server_password"])?.to_string()
-        } else {
-            let invocation = get_conf_val!(s["server_password_command"])?;
-            let output = std::process::Command::new("sh")
-                .args(&["-c", invocation])
-                .stdin(std::process::Stdio::piped())
-                .stdout(std::process::Stdio::piped())
-                .stderr(std::process::Stdio::piped())
-                .output()?;
-            if !output.status.success() {
-                return Err(MeliError::new(format!(
-                    "({}) server_password_command `{}` returned {}: {}",
-                    s.name,
-                    get_conf_val!(s["server_password_command"])?,
-                    output.status,
-                    String::from_utf8_lossy(&output.stderr)
-                )));
-            }
-            std::str::from_utf8(&output.stdout)?.trim_end().to_string()
-        };
+        if use_oauth2 && !s.extra.contains_key("server_password_command") {
+            return Err(MeliError::new(format!(
+                "({}) `use_oauth2` use requires `server_password_command` set with a command that returns an OAUTH2 token. Consult documentation for guidance.",
+                s.name,
+            )));
+        }
+        let server_password = server_password(s)?;
         let server_port = get_conf_val!(s["server_port"], 143)?;
         let use_tls = get_conf_val!(s["use_tls"], true)?;
         let use_starttls = use_tls && get_conf_val!(s["use_starttls"], !(server_port == 993))?;
@@ -1294,12 +1429,6 @@ impl ImapType {
                 s.name,
             )));
         }
-        let timeout = get_conf_val!(s["timeout"], 16_u64)?;
-        let timeout = if timeout == 0 {
-            None
-        } else {
-            Some(Duration::from_secs(timeout))
-        };
         let server_conf = ImapServerConf {
             server_hostname: server_hostname.to_string(),
             server_username: server_username.to_string(),
@@ -1317,7 +1446,7 @@ impl ImapType {
                     oauth2: use_oauth2,
                 },
             },
-            timeout,
+            connection_policy: ConnectionPolicy::new(s)?,
         };
         let account_hash = {
             let mut hasher = DefaultHasher::new();
@@ -1327,38 +1456,46 @@ impl ImapType {
         let account_name = Arc::new(s.name().to_string());
         let uid_store: Arc<UIDStore> = Arc::new(UIDStore {
             keep_offline_cache,
+            lightweight_sync: get_conf_val!(s["lightweight_sync"], false)?,
             ..UIDStore::new(
                 account_hash,
                 account_name,
                 event_consumer,
-                server_conf.timeout,
+                server_conf.connection_policy.read_timeout,
             )
         });
         let connection = ImapConnection::new_connection(&server_conf, uid_store.clone());
+        let mut idle_mailboxes =
+            mailbox_path_list(&get_conf_val!(s["idle_mailboxes"], String::new())?);
+        idle_mailboxes.truncate(get_conf_val!(s["max_idle_connections"], 4_usize)?);
 
         Ok(Box::new(ImapType {
             server_conf,
             is_subscribed: Arc::new(IsSubscribedFn(is_subscribed)),
             connection: Arc::new(FutureMutex::new(connection)),
             uid_store,
+            idle_mailboxes,
         }))
     }
 
     pub fn shell(&mut self) {
         let mut conn = ImapConnection::new_connection(&self.server_conf, self.uid_store.clone());
 
-        futures::executor::block_on(timeout(self.server_conf.timeout, conn.connect()))
-            .unwrap()
-            .unwrap();
+        futures::executor::block_on(timeout(
+            self.server_conf.connection_policy.read_timeout,
+            conn.connect(),
+        ))
+        .unwrap()
+        .unwrap();
         let mut res = Vec::with_capacity(8 * 1024);
         futures::executor::block_on(timeout(
-            self.server_conf.timeout,
+            self.server_conf.connection_policy.read_timeout,
             conn.send_command(b"NOOP"),
         ))
         .unwrap()
         .unwrap();
         futures::executor::block_on(timeout(
-            self.server_conf.timeout,
+            self.server_conf.connection_policy.read_timeout,
             conn.read_response(&mut res, RequiredResponses::empty()),
         ))
         .unwrap()
@@ -1372,13 +1509,13 @@ impl ImapType {
             match io::stdin().read_line(&mut input) {
                 Ok(_) => {
                     futures::executor::block_on(timeout(
-                        self.server_conf.timeout,
+                        self.server_conf.connection_policy.read_timeout,
                         conn.send_command(input.as_bytes()),
                     ))
                     .unwrap()
                     .unwrap();
                     futures::executor::block_on(timeout(
-                        self.server_conf.timeout,
+                        self.server_conf.connection_policy.read_timeout,
                         conn.read_lines(&mut res, Vec::new()),
                     ))
                     .unwrap()
@@ -1540,7 +1677,10 @@ impl ImapType {
                 )));
             }
         }
+        get_conf_val!(s["lightweight_sync"], false)?;
         get_conf_val!(s["use_idle"], true)?;
+        get_conf_val!(s["idle_mailboxes"], String::new())?;
+        get_conf_val!(s["max_idle_connections"], 4_usize)?;
         get_conf_val!(s["use_condstore"], true)?;
         #[cfg(feature = "deflate_compression")]
         get_conf_val!(s["use_deflate"], true)?;
@@ -1551,7 +1691,8 @@ impl ImapType {
                 s.name.as_str(),
             )));
         }
-        let _timeout = get_conf_val!(s["timeout"], 16_u64)?;
+        ConnectionPolicy::new(s)?;
+        let _managesieve_port = get_conf_val!(s["managesieve_port"], 4190_u16)?;
         Ok(())
     }
 
@@ -1566,6 +1707,96 @@ impl ImapType {
     }
 }
 
+/// Watches with IDLE on `target`, or by polling every mailbox if it's `None`, on a new
+/// connection that is opened again after network errors.
+async fn keep_watching(
+    target: Option<IdleTarget>,
+    server_conf: ImapServerConf,
+    main_conn: Arc<FutureMutex<ImapConnection>>,
+    uid_store: Arc<UIDStore>,
+) -> Result<()> {
+    while let Err(err) = if let Some(ref target) = target {
+        idle(
+            ImapWatchKit {
+                conn: ImapConnection::new_connection(&server_conf, uid_store.clone()),
+                main_conn: main_conn.clone(),
+                uid_store: uid_store.clone(),
+            },
+            target.clone(),
+        )
+        .await
+    } else {
+        poll_with_examine(ImapWatchKit {
+            conn: ImapConnection::new_connection(&server_conf, uid_store.clone()),
+            main_conn: main_conn.clone(),
+            uid_store: uid_store.clone(),
+        })
+        .await
+    } {
+        if err.kind.is_network() {
+            uid_store.is_online.lock().unwrap().1 = Err(err.clone());
+        } else {
+            return Err(err);
+        }
+        debug!("Watch failure: {}", err.to_string());
+        match server_conf
+            .connection_policy
+            .retry(|| async {
+                let mut main_conn_lck = timeout(uid_store.timeout, main_conn.lock()).await?;
+                timeout(uid_store.timeout, main_conn_lck.connect())
+                    .await
+                    .and_then(|res| res)
+            })
+            .await
+        {
+            Err(err2) => {
+                debug!("Watch reconnect attempt failed: {}", err2.to_string());
+            }
+            Ok(()) => {
+                debug!("Watch reconnect attempt succesful");
+                continue;
+            }
+        }
+        let mut main_conn_lck = timeout(uid_store.timeout, main_conn.lock()).await?;
+        let account_hash = uid_store.account_hash;
+        main_conn_lck.add_refresh_event(RefreshEvent {
+            account_hash,
+            mailbox_hash: 0,
+            kind: RefreshEventKind::Failure(err.clone()),
+        });
+        return Err(err);
+    }
+    Ok(())
+}
+
+/// Parses a list of mailbox paths, given either as an array or as a comma separated string.
+fn mailbox_path_list(value: &str) -> Vec<String> {
+    let value = value.trim();
+    let value = value
+        .strip_prefix('[')
+        .and_then(|v| v.strip_suffix(']'))
+        .unwrap_or(value);
+    let mut ret = vec![];
+    let mut current = String::new();
+    let mut quote: Option<char> = None;
+    let mut chars = value.chars();
+    while let Some(c) = chars.next() {
+        match (quote, c) {
+            (Some(q), c) if c == q => quote = None,
+            (Some('"'), '\\') => current.extend(chars.next()),
+            (Some(_), c) => current.push(c),
+            (None, '"') | (None, '\'') => quote = Some(c),
+            (None, ',') => ret.push(std::mem::take(&mut current)),
+            (None, c) => current.push(c),
+        }
+    }
+    ret.push(current);
+    ret.into_iter()
+        .map(|p| p.trim().to_string())
+        .filter(|p| !p.is_empty())
+        .collect()
+}
+
 #[derive(Debug, PartialEq, Copy, Clone)]
 enum FetchStage {
     InitialFresh,
@@ -1713,12 +1944,13 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
                 if max_uid_left > 0 {
                     debug!("{} max_uid_left= {}", mailbox_hash, max_uid_left);
                     let command = if max_uid_left == 1 {
-                        "UID FETCH 1 (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)".to_string()
+                        format!("UID FETCH 1 {}", uid_store.envelope_fetch_items())
                     } else {
                         format!(
-                            "UID FETCH {}:{} (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)",
-                                std::cmp::max(max_uid_left.saturating_sub(chunk_size), 1),
-                            max_uid_left
+                            "UID FETCH {}:{} {}",
+                            std::cmp::max(max_uid_left.saturating_sub(chunk_size), 1),
+                            max_uid_left,
+                            uid_store.envelope_fetch_items()
                         )
                     };
                     debug!("sending {:?}", &command);
@@ -1855,3 +2087,17 @@ async fn fetch_hlpr(state: &mut FetchState) -> Result<Vec<Envelope>> {
         }
     }
 }
+
+#[test]
+fn test_imap_mailbox_path_list() {
+    assert_eq!(
+        mailbox_path_list(r#"["Work", "Lists/meli, rust"]"#),
+        vec!["Work".to_string(), "Lists/meli, rust".to_string()]
+    );
+    assert_eq!(
+        mailbox_path_list("Work, Lists/meli"),
+        vec!["Work".to_string(), "Lists/meli".to_string()]
+    );
+    assert!(mailbox_path_list("").is_empty());
+    assert!(mailbox_path_list("[]").is_empty());
+}
@@ -91,6 +91,8 @@ pub trait ImapCache: Send + core::fmt::Debug {
         identifier: std::result::Result<UID, EnvelopeHash>,
         mailbox_hash: MailboxHash,
     ) -> Result<Option<Vec<u8>>>;
+
+    fn insert_rfc822(&mut self, mailbox_hash: MailboxHash, uid: UID, bytes: &[u8]) -> Result<()>;
 }
 
 #[cfg(feature = "sqlite3")]
@@ -140,7 +142,7 @@ mod sqlite3_m {
     CREATE INDEX IF NOT EXISTS envelope_idx ON envelopes(hash);
     CREATE INDEX IF NOT EXISTS mailbox_idx ON mailbox(mailbox_hash);",
         ),
-        version: 2,
+        version: 3,
     };
 
     impl ToSql for ModSequence {
@@ -637,7 +639,39 @@ mod sqlite3_m {
             }
             Ok(ret.pop().unwrap())
         }
+
+        fn insert_rfc822(
+            &mut self,
+            mailbox_hash: MailboxHash,
+            uid: UID,
+            bytes: &[u8],
+        ) -> Result<()> {
+            self.connection
+                .execute(
+                    "UPDATE envelopes SET rfc822 = ?1 WHERE mailbox_hash = ?2 AND uid = ?3;",
+                    sqlite3::params![bytes, mailbox_hash as i64, uid as Sqlite3UID],
+                )
+                .chain_err_summary(|| {
+                    format!(
+                        "Could not save message with UID {} in header_cache of account {}",
+                        uid, self.uid_store.account_name
+                    )
+                })?;
+            Ok(())
+        }
+    }
+}
+
+/// Opens the offline cache of the account, if it keeps one.
+pub(super) fn offline_cache(uid_store: &Arc<UIDStore>) -> Result<Option<Box<dyn ImapCache>>> {
+    if !uid_store.keep_offline_cache {
+        return Ok(None);
     }
+    #[cfg(not(feature = "sqlite3"))]
+    let ret = DefaultCache::get(uid_store.clone())?;
+    #[cfg(feature = "sqlite3")]
+    let ret = Sqlite3Cache::get(uid_store.clone())?;
+    Ok(Some(ret))
 }
 
 pub(super) async fn fetch_cached_envs(state: &mut FetchState) -> Result<Option<Vec<Envelope>>> {
@@ -747,5 +781,14 @@ mod default_m {
         ) -> Result<Option<Vec<u8>>> {
             Err(MeliError::new("melib is not built with any imap cache").set_kind(ErrorKind::Bug))
         }
+
+        fn insert_rfc822(
+            &mut self,
+            _mailbox_hash: MailboxHash,
+            _uid: UID,
+            _bytes: &[u8],
+        ) -> Result<()> {
+            Err(MeliError::new("melib is not built with any imap cache").set_kind(ErrorKind::Bug))
+        }
     }
 }
@@ -130,8 +130,9 @@ impl ImapConnection {
         // 2.  tag1 UID FETCH <lastseenuid+1>:* <descriptors>
         self.send_command(
             format!(
-                "UID FETCH {}:* (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)",
-                max_uid + 1
+                "UID FETCH {}:* {}",
+                max_uid + 1,
+                self.uid_store.envelope_fetch_items()
             )
             .as_bytes(),
         )
@@ -415,8 +416,9 @@ impl ImapConnection {
             // 2.  tag1 UID FETCH <lastseenuid+1>:* <descriptors>
             self.send_command(
                 format!(
-                    "UID FETCH {}:* (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE) (CHANGEDSINCE {})",
+                    "UID FETCH {}:* {} (CHANGEDSINCE {})",
                     cached_max_uid + 1,
+                    self.uid_store.envelope_fetch_items(),
                     cached_highestmodseq,
                 )
                 .as_bytes(),
@@ -38,6 +38,7 @@ use std::time::{Duration, Instant, SystemTime};
 
 const IMAP_PROTOCOL_TIMEOUT: Duration = Duration::from_secs(60 * 28);
 
+use super::managesieve;
 use super::protocol_parser;
 use super::{Capabilities, ImapServerConf, UIDStore};
 
@@ -116,7 +117,6 @@ impl ImapStream {
     pub async fn new_connection(
         server_conf: &ImapServerConf,
     ) -> Result<(Capabilities, ImapStream)> {
-        use std::net::TcpStream;
         let path = &server_conf.server_hostname;
 
         let cmd_id = 1;
@@ -138,15 +138,8 @@ impl ImapStream {
                 )));
             };
 
-            let mut socket = AsyncWrapper::new(Connection::Tcp(
-                if let Some(timeout) = server_conf.timeout {
-                    TcpStream::connect_timeout(&addr, timeout)
-                        .chain_err_kind(crate::error::ErrorKind::Network)?
-                } else {
-                    TcpStream::connect(&addr).chain_err_kind(crate::error::ErrorKind::Network)?
-                },
-            ))
-            .chain_err_kind(crate::error::ErrorKind::Network)?;
+            let mut socket = AsyncWrapper::new(server_conf.connection_policy.connect_tcp(&addr)?)
+                .chain_err_kind(crate::error::ErrorKind::Network)?;
             if server_conf.use_starttls {
                 let err_fn = || {
                     if server_conf.server_port == 993 {
@@ -163,11 +156,19 @@ impl ImapStream {
                         .chain_err_summary(err_fn)
                         .chain_err_kind(crate::error::ErrorKind::Network)?,
                     ImapProtocol::ManageSieve => {
-                        socket
-                            .read(&mut buf)
-                            .await
-                            .chain_err_summary(err_fn)
-                            .chain_err_kind(crate::error::ErrorKind::Network)?;
+                        /* Read the whole greeting before asking for STARTTLS. */
+                        let mut greeting = Vec::with_capacity(1024);
+                        while managesieve::parse_response(&greeting).is_none() {
+                            let len = socket
+                                .read(&mut buf)
+                                .await
+                                .chain_err_summary(err_fn)
+                                .chain_err_kind(crate::error::ErrorKind::Network)?;
+                            if len == 0 {
+                                break;
+                            }
+                            greeting.extend_from_slice(&buf[0..len]);
+                        }
                         socket
                             .write_all(b"STARTTLS\r\n")
                             .await
@@ -264,32 +265,16 @@ impl ImapStream {
                     &path
                 )));
             };
-            AsyncWrapper::new(Connection::Tcp(
-                if let Some(timeout) = server_conf.timeout {
-                    TcpStream::connect_timeout(&addr, timeout)
-                        .chain_err_kind(crate::error::ErrorKind::Network)?
-                } else {
-                    TcpStream::connect(&addr).chain_err_kind(crate::error::ErrorKind::Network)?
-                },
-            ))
-            .chain_err_kind(crate::error::ErrorKind::Network)?
+            AsyncWrapper::new(server_conf.connection_policy.connect_tcp(&addr)?)
+                .chain_err_kind(crate::error::ErrorKind::Network)?
         };
-        if let Err(err) = stream
-            .get_ref()
-            .set_keepalive(Some(Duration::new(60 * 9, 0)))
-        {
-            crate::log(
-                format!("Could not set TCP keepalive in IMAP connection: {}", err),
-                crate::LoggingLevel::WARN,
-            );
-        }
         let mut res = Vec::with_capacity(8 * 1024);
         let mut ret = ImapStream {
             cmd_id,
             stream,
             protocol: server_conf.protocol,
             current_mailbox: MailboxSelection::None,
-            timeout: server_conf.timeout,
+            timeout: server_conf.connection_policy.read_timeout,
         };
         if let ImapProtocol::ManageSieve = server_conf.protocol {
             use data_encoding::BASE64;
@@ -309,6 +294,14 @@ impl ImapStream {
             )
             .await?;
             ret.read_response(&mut res).await?;
+            if let Some((_, status, message)) = managesieve::parse_response(&res) {
+                if status != managesieve::ResponseStatus::Ok {
+                    return Err(MeliError::new(format!(
+                        "Could not authenticate to {}: {}",
+                        &server_conf.server_hostname, message
+                    )));
+                }
+            }
             return Ok((Default::default(), ret));
         }
 
@@ -433,7 +426,20 @@ impl ImapStream {
     pub async fn read_response(&mut self, ret: &mut Vec<u8>) -> Result<()> {
         let id = match self.protocol {
             ImapProtocol::IMAP { .. } => format!("M{} ", self.cmd_id - 1).into_bytes(),
-            ImapProtocol::ManageSieve => Vec::new(),
+            ImapProtocol::ManageSieve => {
+                /* ManageSieve responses are untagged; read until the final OK, NO or BYE line. */
+                let mut chunk = Vec::new();
+                ret.clear();
+                while managesieve::parse_response(ret).is_none() {
+                    self.read_lines(&mut chunk, &[], true).await?;
+                    if chunk.is_empty() {
+                        return Err(MeliError::new("Disconnected")
+                            .set_err_kind(crate::error::ErrorKind::Network));
+                    }
+                    ret.extend_from_slice(&chunk);
+                }
+                return Ok(());
+            }
         };
         self.read_lines(ret, &id, true).await?;
         Ok(())
@@ -516,12 +522,14 @@ impl ImapStream {
                 self.stream.flush().await?;
                 match self.protocol {
                     ImapProtocol::IMAP { .. } => {
-                        if !command.starts_with(b"LOGIN") {
+                        if command.starts_with(b"LOGIN") {
+                            debug!("sent: M{} LOGIN ..", self.cmd_id - 1);
+                        } else if command.starts_with(b"AUTHENTICATE") {
+                            debug!("sent: M{} AUTHENTICATE ..", self.cmd_id - 1);
+                        } else {
                             debug!("sent: M{} {}", self.cmd_id - 1, unsafe {
                                 std::str::from_utf8_unchecked(command)
                             });
-                        } else {
-                            debug!("sent: M{} LOGIN ..", self.cmd_id - 1);
                         }
                     }
                     ImapProtocol::ManageSieve => {}
@@ -106,6 +106,15 @@ impl BackendMailbox for ImapMailbox {
     fn permissions(&self) -> MailboxPermissions {
         *self.permissions.lock().unwrap()
     }
+    /// Known once the mailbox has been selected, from `\*` in its `PERMANENTFLAGS`.
+    fn can_create_tags(&self) -> bool {
+        self.select
+            .read()
+            .unwrap()
+            .as_ref()
+            .map(|s| s.can_create_flags)
+            .unwrap_or(true)
+    }
     fn is_subscribed(&self) -> bool {
         self.is_subscribed
     }
@@ -123,3 +132,17 @@ impl BackendMailbox for ImapMailbox {
         Ok((self.unseen.lock()?.len(), self.exists.lock()?.len()))
     }
 }
+
+#[test]
+fn test_imap_mailbox_can_create_tags() {
+    use super::protocol_parser::select_response;
+    let mailbox = ImapMailbox::default();
+    assert!(mailbox.can_create_tags());
+    let limited = b"* 1 EXISTS\r\n* FLAGS (\\Seen $Work)\r\n* OK [PERMANENTFLAGS (\\Seen $Work)] Limited\r\n* OK [UIDVALIDITY 1] UIDs valid\r\n* A1 OK [READ-WRITE] SELECT completed\r\n";
+    *mailbox.select.write().unwrap() = Some(select_response(limited).unwrap());
+    assert!(!mailbox.can_create_tags());
+    /* Without PERMANENTFLAGS every flag can be set. */
+    let unlimited = b"* 1 EXISTS\r\n* FLAGS (\\Seen)\r\n* OK [UIDVALIDITY 1] UIDs valid\r\n* A1 OK [READ-WRITE] SELECT completed\r\n";
+    *mailbox.select.write().unwrap() = Some(select_response(unlimited).unwrap());
+    assert!(mailbox.can_create_tags());
+}
@@ -19,7 +19,7 @@
  * along with meli. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::{ImapConnection, ImapProtocol, ImapServerConf, UIDStore};
+use super::{server_password, ImapConnection, ImapProtocol, ImapServerConf, UIDStore};
 use crate::conf::AccountSettings;
 use crate::error::{MeliError, Result};
 use crate::get_conf_val;
@@ -72,17 +72,147 @@ pub fn quoted_raw(input: &[u8]) -> IResult<&[u8], &[u8]> {
     Err(nom::Err::Error((input, ErrorKind::Tag)))
 }
 
-pub trait ManageSieve {
-    fn havespace(&mut self) -> Result<()>;
-    fn putscript(&mut self) -> Result<()>;
+/// Status of a ManageSieve response.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ResponseStatus {
+    Ok,
+    No,
+    Bye,
+}
+
+/// Parse a number ending a line as a literal announcement, e.g. `{42}` or `{42+}`.
+fn literal_length(line: &[u8]) -> Option<usize> {
+    let line = line.strip_suffix(b"}")?;
+    let line = line.strip_suffix(b"+").unwrap_or(line);
+    let start = line.iter().rposition(|&b| b == b'{')?;
+    std::str::from_utf8(&line[start + 1..]).ok()?.parse().ok()
+}
+
+/// Parse a quoted string or a literal, returning its value and the remaining input.
+pub fn sieve_string(input: &[u8]) -> Option<(String, &[u8])> {
+    match input.first()? {
+        b'"' => {
+            let mut ret = Vec::new();
+            let mut i = 1;
+            while i < input.len() {
+                match input[i] {
+                    b'\\' if i + 1 < input.len() => {
+                        ret.push(input[i + 1]);
+                        i += 2;
+                        continue;
+                    }
+                    b'"' => {
+                        return Some((String::from_utf8_lossy(&ret).into(), &input[i + 1..]));
+                    }
+                    b => ret.push(b),
+                }
+                i += 1;
+            }
+            None
+        }
+        b'{' => {
+            let end = input.iter().position(|&b| b == b'}')?;
+            let len = literal_length(&input[..=end])?;
+            let rest = input[end + 1..].strip_prefix(b"\r\n")?;
+            if rest.len() < len {
+                return None;
+            }
+            Some((String::from_utf8_lossy(&rest[..len]).into(), &rest[len..]))
+        }
+        _ => None,
+    }
+}
+
+/// Quote `s` as a ManageSieve string.
+pub fn quote(s: &str) -> String {
+    format!("\"{}\"", s.replace('\\', "\\\\").replace('"', "\\\""))
+}
+
+/// Split a complete response into its data and its final status line's status and human readable
+/// message. Returns `None` if `input` does not contain a complete response yet.
+pub fn parse_response(input: &[u8]) -> Option<(&[u8], ResponseStatus, String)> {
+    let mut pos = 0;
+    loop {
+        let line_end = pos + input[pos..].windows(2).position(|w| w == b"\r\n")?;
+        let line = &input[pos..line_end];
+        let status = [
+            (&b"OK"[..], ResponseStatus::Ok),
+            (&b"NO"[..], ResponseStatus::No),
+            (&b"BYE"[..], ResponseStatus::Bye),
+        ]
+        .iter()
+        .find(|(word, _)| {
+            line.len() >= word.len()
+                && line[..word.len()].eq_ignore_ascii_case(word)
+                && (line.len() == word.len() || line[word.len()] == b' ')
+        })
+        .map(|(word, status)| (word.len(), *status));
+        if let Some((word_len, status)) = status {
+            let mut rest = line[word_len..].trim_ascii_start_compat();
+            /* Skip the response code, e.g. `(QUOTA/MAXSIZE)` */
+            if rest.starts_with(b"(") {
+                let mut in_quotes = false;
+                let mut end = rest.len();
+                for (i, &b) in rest.iter().enumerate() {
+                    match b {
+                        b'"' if i == 0 || rest[i - 1] != b'\\' => in_quotes = !in_quotes,
+                        b')' if !in_quotes => {
+                            end = i + 1;
+                            break;
+                        }
+                        _ => {}
+                    }
+                }
+                rest = rest[end..].trim_ascii_start_compat();
+            }
+            if let Some(len) = literal_length(rest) {
+                let end = line_end + 2 + len;
+                if input.len() < end {
+                    return None;
+                }
+                let message = String::from_utf8_lossy(&input[line_end + 2..end]).into();
+                return Some((&input[..pos], status, message));
+            }
+            let message = sieve_string(rest)
+                .map(|(s, _)| s)
+                .unwrap_or_else(|| String::from_utf8_lossy(rest).into());
+            return Some((&input[..pos], status, message));
+        }
+        pos = match literal_length(line) {
+            Some(len) => line_end + 2 + len,
+            None => line_end + 2,
+        };
+        if pos > input.len() {
+            return None;
+        }
+    }
+}
 
-    fn listscripts(&mut self) -> Result<()>;
-    fn setactive(&mut self) -> Result<()>;
+trait TrimAsciiStart {
+    fn trim_ascii_start_compat(&self) -> &Self;
+}
 
-    fn getscript(&mut self) -> Result<()>;
+impl TrimAsciiStart for [u8] {
+    fn trim_ascii_start_compat(&self) -> &[u8] {
+        let start = self
+            .iter()
+            .position(|b| !b.is_ascii_whitespace())
+            .unwrap_or_else(|| self.len());
+        &self[start..]
+    }
+}
+
+/// A Sieve script stored on the server.
+#[derive(Debug, Clone, PartialEq)]
+pub struct SieveScript {
+    pub name: String,
+    pub active: bool,
+}
 
-    fn deletescript(&mut self) -> Result<()>;
-    fn renamescript(&mut self) -> Result<()>;
+/// A connection to a ManageSieve (RFC 5804) server.
+#[derive(Debug)]
+pub struct ManageSieveConnection {
+    pub inner: ImapConnection,
 }
 
 pub fn new_managesieve_connection(
@@ -90,28 +220,22 @@ pub fn new_managesieve_connection(
     account_name: String,
     s: &AccountSettings,
     event_consumer: crate::backends::BackendEventConsumer,
-) -> Result<ImapConnection> {
+) -> Result<ManageSieveConnection> {
     let server_hostname = get_conf_val!(s["server_hostname"])?;
     let server_username = get_conf_val!(s["server_username"])?;
-    let server_password = get_conf_val!(s["server_password"])?;
-    let server_port = get_conf_val!(s["server_port"], 4190)?;
+    let server_password = server_password(s)?;
+    let server_port = get_conf_val!(s["managesieve_port"], 4190)?;
     let danger_accept_invalid_certs: bool = get_conf_val!(s["danger_accept_invalid_certs"], false)?;
-    let timeout = get_conf_val!(s["timeout"], 16_u64)?;
-    let timeout = if timeout == 0 {
-        None
-    } else {
-        Some(std::time::Duration::from_secs(timeout))
-    };
     let server_conf = ImapServerConf {
         server_hostname: server_hostname.to_string(),
         server_username: server_username.to_string(),
-        server_password: server_password.to_string(),
+        server_password,
         server_port,
         use_starttls: true,
         use_tls: true,
         danger_accept_invalid_certs,
         protocol: ImapProtocol::ManageSieve,
-        timeout,
+        connection_policy: crate::connections::ConnectionPolicy::new(s)?,
     };
     let uid_store = Arc::new(UIDStore {
         is_online: Arc::new(Mutex::new((
@@ -122,35 +246,136 @@ pub fn new_managesieve_connection(
             account_hash,
             Arc::new(account_name),
             event_consumer,
-            server_conf.timeout,
+            server_conf.connection_policy.read_timeout,
         )
     });
-    Ok(ImapConnection::new_connection(&server_conf, uid_store))
+    Ok(ManageSieveConnection {
+        inner: ImapConnection::new_connection(&server_conf, uid_store),
+    })
 }
 
-impl ManageSieve for ImapConnection {
-    fn havespace(&mut self) -> Result<()> {
-        Ok(())
+impl ManageSieveConnection {
+    pub async fn connect(&mut self) -> Result<()> {
+        self.inner.connect().await
     }
-    fn putscript(&mut self) -> Result<()> {
+
+    /// Send `command` and return the data of the response if its status is OK.
+    async fn command(&mut self, command: &[u8]) -> Result<Vec<u8>> {
+        let mut res = Vec::new();
+        self.inner.send_raw(command).await?;
+        self.inner
+            .read_response(&mut res, super::RequiredResponses::empty())
+            .await?;
+        match parse_response(&res) {
+            Some((data, ResponseStatus::Ok, _)) => Ok(data.to_vec()),
+            Some((_, _, message)) => Err(MeliError::new(message)),
+            None => Err(MeliError::new("Invalid ManageSieve response.")),
+        }
+    }
+
+    pub async fn havespace(&mut self, name: &str, size: usize) -> Result<()> {
+        self.command(format!("HAVESPACE {} {}", quote(name), size).as_bytes())
+            .await?;
         Ok(())
     }
 
-    fn listscripts(&mut self) -> Result<()> {
+    /// Upload a script, replacing any script with the same name.
+    pub async fn putscript(&mut self, name: &str, script: &str) -> Result<()> {
+        self.command(
+            format!(
+                "PUTSCRIPT {} {{{}+}}\r\n{}",
+                quote(name),
+                script.len(),
+                script
+            )
+            .as_bytes(),
+        )
+        .await?;
         Ok(())
     }
-    fn setactive(&mut self) -> Result<()> {
+
+    /// Check a script for errors without storing it.
+    pub async fn checkscript(&mut self, script: &str) -> Result<()> {
+        self.command(format!("CHECKSCRIPT {{{}+}}\r\n{}", script.len(), script).as_bytes())
+            .await?;
         Ok(())
     }
 
-    fn getscript(&mut self) -> Result<()> {
+    pub async fn listscripts(&mut self) -> Result<Vec<SieveScript>> {
+        let data = self.command(b"LISTSCRIPTS").await?;
+        let mut ret = vec![];
+        let mut input = &data[..];
+        while let Some((name, rest)) = sieve_string(input) {
+            let (line, rest) = match rest.windows(2).position(|w| w == b"\r\n") {
+                Some(pos) => (&rest[..pos], &rest[pos + 2..]),
+                None => (rest, &b""[..]),
+            };
+            ret.push(SieveScript {
+                name,
+                active: line
+                    .trim_ascii_start_compat()
+                    .eq_ignore_ascii_case(b"ACTIVE"),
+            });
+            input = rest;
+        }
+        Ok(ret)
+    }
+
+    /// Activate script `name`, or deactivate all scripts if it is empty.
+    pub async fn setactive(&mut self, name: &str) -> Result<()> {
+        self.command(format!("SETACTIVE {}", quote(name)).as_bytes())
+            .await?;
         Ok(())
     }
 
-    fn deletescript(&mut self) -> Result<()> {
+    pub async fn getscript(&mut self, name: &str) -> Result<String> {
+        let data = self
+            .command(format!("GETSCRIPT {}", quote(name)).as_bytes())
+            .await?;
+        sieve_string(&data)
+            .map(|(s, _)| s)
+            .ok_or_else(|| MeliError::new("Invalid GETSCRIPT response."))
+    }
+
+    pub async fn deletescript(&mut self, name: &str) -> Result<()> {
+        self.command(format!("DELETESCRIPT {}", quote(name)).as_bytes())
+            .await?;
         Ok(())
     }
-    fn renamescript(&mut self) -> Result<()> {
+
+    pub async fn renamescript(&mut self, old_name: &str, new_name: &str) -> Result<()> {
+        self.command(format!("RENAMESCRIPT {} {}", quote(old_name), quote(new_name)).as_bytes())
+            .await?;
         Ok(())
     }
+
+    pub async fn logout(&mut self) -> Result<()> {
+        self.inner.send_raw(b"LOGOUT").await
+    }
+}
+
+#[test]
+fn test_managesieve_responses() {
+    let (data, status, _) = parse_response(
+        b"\"vacation\"\r\n\"main {1}\" ACTIVE\r\n{5}\r\nlists\r\nOK \"Listscripts completed.\"\r\n",
+    )
+    .unwrap();
+    assert_eq!(status, ResponseStatus::Ok);
+    assert_eq!(
+        data,
+        &b"\"vacation\"\r\n\"main {1}\" ACTIVE\r\n{5}\r\nlists\r\n"[..]
+    );
+    assert_eq!(
+        sieve_string(b"{8}\r\nOK\r\nNO\r\nOK\r\n").unwrap().0,
+        "OK\r\nNO\r\n"
+    );
+    assert!(parse_response(b"{10}\r\nOK\r\n").is_none());
+    assert_eq!(
+        parse_response(b"NO (QUOTA/MAXSIZE) \"Quota exceeded\"\r\n").unwrap(),
+        (&b""[..], ResponseStatus::No, "Quota exceeded".to_string())
+    );
+    assert_eq!(
+        parse_response(b"NO {13}\r\nline 2: error\r\n").unwrap().2,
+        "line 2: error"
+    );
 }
@@ -64,6 +64,18 @@ impl BackendOp for ImapOp {
                 cache.bytes.is_some()
             };
             if !exists_in_cache {
+                let offline_bytes = match super::cache::offline_cache(&uid_store) {
+                    Ok(Some(mut cache_handle)) => {
+                        cache_handle.rfc822(Ok(uid), mailbox_hash).unwrap_or(None)
+                    }
+                    _ => None,
+                };
+                if let Some(bytes) = offline_bytes {
+                    let mut bytes_cache = uid_store.byte_cache.lock()?;
+                    let cache = bytes_cache.entry(uid).or_default();
+                    cache.bytes = Some(bytes.clone());
+                    return Ok(bytes);
+                }
                 let mut response = Vec::with_capacity(8 * 1024);
                 {
                     let mut conn = timeout(uid_store.timeout, connection.lock()).await?;
@@ -97,6 +109,11 @@ impl BackendOp for ImapOp {
                 let _uid = _uid.unwrap();
                 assert_eq!(_uid, uid);
                 assert!(body.is_some());
+                if let Ok(Some(mut cache_handle)) = super::cache::offline_cache(&uid_store) {
+                    if let Err(err) = cache_handle.insert_rfc822(mailbox_hash, uid, body.unwrap()) {
+                        (uid_store.event_consumer)(uid_store.account_hash, err.into());
+                    }
+                }
                 let mut bytes_cache = uid_store.byte_cache.lock()?;
                 let cache = bytes_cache.entry(uid).or_default();
                 if let Some((_flags, _)) = _flags {
@@ -579,6 +579,7 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
     should_start_with!(&input[i..], b"FETCH (");
     i += b"FETCH (".len();
     let mut has_attachments = false;
+    let mut size_ = None;
     while i < input.len() {
         eat_whitespace!(break);
         bounds!(break);
@@ -624,6 +625,19 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
                     String::from_utf8_lossy(&input)
                 ))));
             }
+        } else if input[i..].starts_with(b"RFC822.SIZE ") {
+            i += b"RFC822.SIZE ".len();
+            if let Ok((rest, size)) =
+                take_while::<_, &[u8], (&[u8], nom::error::ErrorKind)>(is_digit)(&input[i..])
+            {
+                i += input.len() - i - rest.len();
+                size_ = usize::from_str(to_str!(size)).ok();
+            } else {
+                return debug!(Err(MeliError::new(format!(
+                    "Unexpected input while parsing RFC822.SIZE in UID FETCH response. Got: `{:.40}`",
+                    String::from_utf8_lossy(&input)
+                ))));
+            }
         } else if input[i..].starts_with(b"RFC822 {") {
             i += b"RFC822 ".len();
             if let Ok((rest, body)) =
@@ -694,6 +708,7 @@ pub fn fetch_response(input: &[u8]) -> ImapParseResult<FetchResponse<'_>> {
 
     if let Some(env) = ret.envelope.as_mut() {
         env.set_has_attachments(has_attachments);
+        env.size = size_;
     }
 
     Ok((&input[i..], ret, None))
@@ -917,6 +932,14 @@ pub fn untagged_responses(input: &[u8]) -> ImapParseResult<Option<UntaggedRespon
     ))
 }
 
+#[test]
+fn test_imap_fetch_response_size() {
+    let input = b"* 1 FETCH (UID 7 RFC822.SIZE 4286 ENVELOPE (\"Wed, 17 Jul 1996 02:23:25 -0700 (PDT)\" \"subject\" NIL NIL NIL NIL NIL NIL NIL \"<a@example.com>\"))\r\n";
+    let (_, fetch, _) = fetch_response(input).unwrap();
+    assert_eq!(fetch.uid, Some(7));
+    assert_eq!(fetch.envelope.unwrap().size, Some(4286));
+}
+
 #[test]
 fn test_untagged_responses() {
     use UntaggedResponse::*;
@@ -1053,6 +1076,8 @@ pub struct SelectResponse {
 pub fn select_response(input: &[u8]) -> Result<SelectResponse> {
     if input.contains_subsequence(b"* OK") {
         let mut ret = SelectResponse::default();
+        /* A missing PERMANENTFLAGS response means all flags can be changed permanently. */
+        ret.can_create_flags = true;
         for l in input.split_rn() {
             if l.starts_with(b"* ") && l.ends_with(b" EXISTS\r\n") {
                 ret.exists = ImapNum::from_str(&String::from_utf8_lossy(
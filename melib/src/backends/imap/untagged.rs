@@ -195,7 +195,7 @@ impl ImapConnection {
                 debug!("exists {}", n);
                 try_fail!(
                     mailbox_hash,
-                    self.send_command(format!("FETCH {} (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)", n).as_bytes()).await
+                    self.send_command(format!("FETCH {} {}", n, self.uid_store.envelope_fetch_items()).as_bytes()).await
                     self.read_response(&mut response, RequiredResponses::FETCH_REQUIRED).await
                 );
                 let mut v = match super::protocol_parser::fetch_responses(&response) {
@@ -321,7 +321,11 @@ impl ImapConnection {
                             for ms in iter {
                                 accum = format!("{},{}", accum, to_str!(ms).trim());
                             }
-                            format!("UID FETCH {} (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)", accum)
+                            format!(
+                                "UID FETCH {} {}",
+                                accum,
+                                self.uid_store.envelope_fetch_items()
+                            )
                         };
                         try_fail!(
                             mailbox_hash,
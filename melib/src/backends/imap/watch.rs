@@ -50,27 +50,50 @@ pub async fn poll_with_examine(kit: ImapWatchKit) -> Result<()> {
     }
 }
 
-pub async fn idle(kit: ImapWatchKit) -> Result<()> {
-    debug!("IDLE");
+/// The mailbox an IDLE connection watches.
+#[derive(Debug, Clone)]
+pub enum IdleTarget {
+    /// INBOX, polling every other mailbox except `idle_mailboxes`, which have their own
+    /// connections.
+    Inbox { idle_mailboxes: Vec<MailboxHash> },
+    /// One of the mailboxes of the `idle_mailboxes` account setting.
+    Mailbox(MailboxHash),
+}
+
+pub async fn idle(kit: ImapWatchKit, target: IdleTarget) -> Result<()> {
+    debug!("IDLE {:?}", &target);
     /* IDLE only watches the connection's selected mailbox. We will IDLE on INBOX and every ~5
-     * minutes wake up and poll the others */
+     * minutes wake up and poll the others, except those with their own IDLE connection */
     let ImapWatchKit {
         mut conn,
         main_conn,
         uid_store,
     } = kit;
     conn.connect().await?;
-    let mailbox: ImapMailbox = match uid_store
-        .mailboxes
-        .lock()
-        .await
-        .values()
-        .find(|f| f.parent.is_none() && (f.special_usage() == SpecialUsageMailbox::Inbox))
-        .map(std::clone::Clone::clone)
-    {
-        Some(mailbox) => mailbox,
-        None => {
-            return Err(MeliError::new("INBOX mailbox not found in local mailbox index. meli may have not parsed the IMAP mailboxes correctly"));
+    let mailbox: ImapMailbox = match target {
+        IdleTarget::Inbox { .. } => match uid_store
+            .mailboxes
+            .lock()
+            .await
+            .values()
+            .find(|f| f.parent.is_none() && (f.special_usage() == SpecialUsageMailbox::Inbox))
+            .map(std::clone::Clone::clone)
+        {
+            Some(mailbox) => mailbox,
+            None => {
+                return Err(MeliError::new("INBOX mailbox not found in local mailbox index. meli may have not parsed the IMAP mailboxes correctly"));
+            }
+        },
+        IdleTarget::Mailbox(mailbox_hash) => {
+            match uid_store.mailboxes.lock().await.get(&mailbox_hash) {
+                Some(mailbox) => std::clone::Clone::clone(mailbox),
+                None => {
+                    return Err(MeliError::new(format!(
+                        "Mailbox {} not found in local mailbox index.",
+                        mailbox_hash
+                    )));
+                }
+            }
         }
     };
     let mailbox_hash = mailbox.hash();
@@ -106,9 +129,17 @@ pub async fn idle(kit: ImapWatchKit) -> Result<()> {
             uidvalidities.insert(mailbox_hash, select_response.uidvalidity);
         }
     }
-    let mailboxes: HashMap<MailboxHash, ImapMailbox> = {
-        let mailboxes_lck = timeout(uid_store.timeout, uid_store.mailboxes.lock()).await?;
-        mailboxes_lck.clone()
+    /* Mailboxes this connection polls */
+    let mailboxes: HashMap<MailboxHash, ImapMailbox> = match target {
+        IdleTarget::Inbox { ref idle_mailboxes } => {
+            let mailboxes_lck = timeout(uid_store.timeout, uid_store.mailboxes.lock()).await?;
+            mailboxes_lck
+                .iter()
+                .filter(|(h, _)| !idle_mailboxes.contains(h))
+                .map(|(h, m)| (*h, std::clone::Clone::clone(m)))
+                .collect()
+        }
+        IdleTarget::Mailbox(_) => HashMap::default(),
     };
     for (h, mailbox) in mailboxes.clone() {
         if mailbox_hash == h {
@@ -147,7 +178,7 @@ pub async fn idle(kit: ImapWatchKit) -> Result<()> {
             }
         };
         let now = std::time::Instant::now();
-        if now.duration_since(watch) >= _5_MINS {
+        if now.duration_since(watch) >= _5_MINS && !mailboxes.is_empty() {
             /* Time to poll all inboxes */
             let mut conn = timeout(uid_store.timeout, main_conn.lock()).await?;
             for (_h, mailbox) in mailboxes.clone() {
@@ -335,17 +366,17 @@ pub async fn examine_updates(
                     cmd.push_str(&n.to_string());
                 }
             }
-            cmd.push_str(
-                " (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)",
-            );
+            cmd.push(' ');
+            cmd.push_str(uid_store.envelope_fetch_items());
             conn.send_command(cmd.as_bytes()).await?;
             conn.read_response(&mut response, RequiredResponses::FETCH_REQUIRED)
                 .await?;
         } else if select_response.exists > mailbox.exists.lock().unwrap().len() {
             conn.send_command(
                 format!(
-                    "FETCH {}:* (UID FLAGS ENVELOPE BODY.PEEK[HEADER.FIELDS (REFERENCES)] BODYSTRUCTURE)",
-                    std::cmp::max(mailbox.exists.lock().unwrap().len(), 1)
+                    "FETCH {}:* {}",
+                    std::cmp::max(mailbox.exists.lock().unwrap().len(), 1),
+                    uid_store.envelope_fetch_items()
                 )
                 .as_bytes(),
             )
@@ -21,6 +21,7 @@
 
 use crate::backends::*;
 use crate::conf::AccountSettings;
+use crate::connections::ConnectionPolicy;
 use crate::email::*;
 use crate::error::{MeliError, Result};
 use crate::Collection;
@@ -105,6 +106,7 @@ pub struct JmapServerConf {
     pub server_password: String,
     pub server_port: u16,
     pub danger_accept_invalid_certs: bool,
+    pub connection_policy: ConnectionPolicy,
 }
 
 macro_rules! get_conf_val {
@@ -143,6 +145,7 @@ impl JmapServerConf {
             server_password: get_conf_val!(s["server_password"])?.to_string(),
             server_port: get_conf_val!(s["server_port"], 443)?,
             danger_accept_invalid_certs: get_conf_val!(s["danger_accept_invalid_certs"], false)?,
+            connection_policy: ConnectionPolicy::new(s)?,
         })
     }
 }
@@ -293,8 +296,9 @@ impl MailBackend for JmapType {
             is_remote: true,
             supports_search: true,
             extensions: None,
-            supports_tags: true,
+            tag_support: TagSupport::Any,
             supports_submission: false,
+            lightweight_sync: false,
         };
         CAPABILITIES
     }
@@ -658,7 +662,7 @@ impl MailBackend for JmapType {
         &mut self,
         env_hashes: EnvelopeHashBatch,
         mailbox_hash: MailboxHash,
-        flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+        flags: FlagOps,
     ) -> ResultFuture<()> {
         let store = self.store.clone();
         let connection = self.connection.clone();
@@ -667,9 +671,10 @@ impl MailBackend for JmapType {
             let mut ids: Vec<Id<EmailObject>> = Vec::with_capacity(env_hashes.rest.len() + 1);
             let mut id_map: HashMap<Id<EmailObject>, EnvelopeHash> = HashMap::default();
             let mut update_keywords: HashMap<String, Value> = HashMap::default();
-            for (flag, value) in flags.iter() {
-                match flag {
-                    Ok(f) => {
+            for op in flags.iter() {
+                let value = op.is_set();
+                match op {
+                    FlagOp::Flag(f, _) => {
                         update_keywords.insert(
                             format!(
                                 "keywords/{}",
@@ -683,17 +688,17 @@ impl MailBackend for JmapType {
                                     _ => continue, //FIXME
                                 }
                             ),
-                            if *value {
+                            if value {
                                 serde_json::json!(true)
                             } else {
                                 serde_json::json!(null)
                             },
                         );
                     }
-                    Err(t) => {
+                    FlagOp::Tag(t, _) => {
                         update_keywords.insert(
                             format!("keywords/{}", t),
-                            if *value {
+                            if value {
                                 serde_json::json!(true)
                             } else {
                                 serde_json::json!(null)
@@ -755,14 +760,9 @@ impl MailBackend for JmapType {
 
             {
                 let mut tag_index_lck = store.collection.tag_index.write().unwrap();
-                for (flag, value) in flags.iter() {
-                    match flag {
-                        Ok(_) => {}
-                        Err(t) => {
-                            if *value {
-                                tag_index_lck.insert(tag_hash!(t), t.clone());
-                            }
-                        }
+                for op in flags.iter() {
+                    if let FlagOp::Tag(t, true) = op {
+                        tag_index_lck.insert(tag_hash!(t), t.clone());
                     }
                 }
                 drop(tag_index_lck);
@@ -869,6 +869,7 @@ impl JmapType {
         get_conf_val!(s["server_password"])?;
         get_conf_val!(s["server_port"], 443)?;
         get_conf_val!(s["danger_accept_invalid_certs"], false)?;
+        ConnectionPolicy::new(s)?;
         Ok(())
     }
 }
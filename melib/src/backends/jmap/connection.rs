@@ -33,9 +33,18 @@ pub struct JmapConnection {
 
 impl JmapConnection {
     pub fn new(server_conf: &JmapServerConf, store: Arc<Store>) -> Result<Self> {
-        let client = HttpClient::builder()
-            .timeout(std::time::Duration::from_secs(10))
-            .redirect_policy(RedirectPolicy::Limit(10))
+        let policy = &server_conf.connection_policy;
+        let mut client = HttpClient::builder().redirect_policy(RedirectPolicy::Limit(10));
+        if let Some(timeout) = policy.read_timeout {
+            client = client.timeout(timeout);
+        }
+        if let Some(timeout) = policy.connect_timeout {
+            client = client.connect_timeout(timeout);
+        }
+        if let Some(keepalive) = policy.keepalive {
+            client = client.tcp_keepalive(keepalive);
+        }
+        let client = client
             .authentication(isahc::auth::Authentication::basic())
             .credentials(isahc::auth::Credentials::new(
                 &server_conf.server_username,
@@ -68,8 +77,15 @@ impl JmapConnection {
         }
         jmap_session_resource_url.push_str("/.well-known/jmap");
 
-        let mut req = self.client.get_async(&jmap_session_resource_url).await?;
-        let res_text = req.text_async().await?;
+        let client = &self.client;
+        let res_text = self
+            .server_conf
+            .connection_policy
+            .retry(|| async {
+                let mut req = client.get_async(&jmap_session_resource_url).await?;
+                Ok(req.text_async().await?)
+            })
+            .await?;
 
         let session: JmapSession = match serde_json::from_str(&res_text) {
             Err(err) => {
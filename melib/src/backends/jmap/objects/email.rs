@@ -699,13 +699,25 @@ impl From<crate::search::Query> for Filter<EmailFilterCondition, EmailObject> {
                     }
                     *f = accum;
                 }
-                HasAttachment => {
+                HasAttachment | AttachmentType(_) => {
+                    /* JMAP can't filter by attachment type, so only require an attachment */
                     *f = Filter::Condition(
                         EmailFilterCondition::new()
                             .has_attachment(Some(true))
                             .into(),
                     );
                 }
+                List(t) => {
+                    *f = Filter::Condition(
+                        EmailFilterCondition::new()
+                            .header(vec!["List-Id".to_string().into(), t.to_string().into()])
+                            .into(),
+                    );
+                }
+                AttachmentName(t) => {
+                    /* The `text` condition includes attachment names */
+                    *f = Filter::Condition(EmailFilterCondition::new().text(t.clone()).into());
+                }
                 And(q1, q2) => {
                     let mut rhs = Filter::Condition(EmailFilterCondition::new().into());
                     let mut lhs = Filter::Condition(EmailFilterCondition::new().into());
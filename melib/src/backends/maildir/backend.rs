@@ -172,8 +172,9 @@ impl MailBackend for MaildirType {
             is_remote: false,
             supports_search: false,
             extensions: None,
-            supports_tags: false,
+            tag_support: TagSupport::None,
             supports_submission: false,
+            lightweight_sync: false,
         };
         CAPABILITIES
     }
@@ -854,10 +855,10 @@ impl MailBackend for MaildirType {
         &mut self,
         env_hashes: EnvelopeHashBatch,
         mailbox_hash: MailboxHash,
-        flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+        flags: FlagOps,
     ) -> ResultFuture<()> {
         let hash_index = self.hash_indexes.clone();
-        if flags.iter().any(|(f, _)| f.is_err()) {
+        if flags.iter().any(FlagOp::is_tag) {
             return Err(MeliError::new("Maildir doesn't support tags."));
         }
 
@@ -886,8 +887,10 @@ impl MailBackend for MaildirType {
                     .ok_or_else(|| MeliError::new(format!("Invalid email filename: {:?}", path)))?
                     + 3;
                 let mut new_name: String = path[..idx].to_string();
-                for (f, value) in flags.iter() {
-                    env_flags.set(*f.as_ref().unwrap(), *value);
+                for op in flags.iter() {
+                    if let FlagOp::Flag(f, value) = op {
+                        env_flags.set(*f, *value);
+                    }
                 }
 
                 if !(env_flags & Flag::DRAFT).is_empty() {
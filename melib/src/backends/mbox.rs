@@ -838,8 +838,9 @@ impl MailBackend for MboxType {
             is_remote: false,
             supports_search: false,
             extensions: None,
-            supports_tags: false,
+            tag_support: TagSupport::None,
             supports_submission: false,
+            lightweight_sync: false,
         };
         CAPABILITIES
     }
@@ -1145,7 +1146,7 @@ impl MailBackend for MboxType {
         &mut self,
         _env_hashes: EnvelopeHashBatch,
         _mailbox_hash: MailboxHash,
-        _flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+        _flags: FlagOps,
     ) -> ResultFuture<()> {
         Err(MeliError::new("Unimplemented."))
     }
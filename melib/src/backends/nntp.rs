@@ -33,7 +33,7 @@ mod connection;
 pub use connection::*;
 
 use crate::conf::AccountSettings;
-use crate::connections::timeout;
+use crate::connections::{timeout, ConnectionPolicy};
 use crate::email::*;
 use crate::error::{MeliError, Result, ResultIntoMeliError};
 use crate::{backends::*, Collection};
@@ -64,6 +64,7 @@ pub struct NntpServerConf {
     pub require_auth: bool,
     pub danger_accept_invalid_certs: bool,
     pub extension_use: NntpExtensionUse,
+    pub connection_policy: ConnectionPolicy,
 }
 
 type Capabilities = HashSet<String>;
@@ -170,8 +171,9 @@ impl MailBackend for NntpType {
             is_remote: true,
             supports_search: false,
             extensions: Some(extensions),
-            supports_tags: false,
+            tag_support: TagSupport::None,
             supports_submission: false,
+            lightweight_sync: false,
         }
     }
 
@@ -284,7 +286,7 @@ impl MailBackend for NntpType {
         &mut self,
         _env_hashes: EnvelopeHashBatch,
         _mailbox_hash: MailboxHash,
-        _flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+        _flags: FlagOps,
     ) -> ResultFuture<()> {
         Err(MeliError::new("NNTP doesn't support flags."))
     }
@@ -413,6 +415,7 @@ impl NntpType {
                 #[cfg(feature = "deflate_compression")]
                 deflate: get_conf_val!(s["use_deflate"], true)?,
             },
+            connection_policy: ConnectionPolicy::new(s)?,
         };
         let account_hash = {
             let mut hasher = DefaultHasher::new();
@@ -527,6 +530,7 @@ impl NntpType {
             )));
         }
         get_conf_val!(s["danger_accept_invalid_certs"], false)?;
+        ConnectionPolicy::new(s)?;
         Ok(())
     }
 
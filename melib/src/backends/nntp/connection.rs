@@ -84,16 +84,12 @@ impl NntpStream {
     pub async fn new_connection(
         server_conf: &NntpServerConf,
     ) -> Result<(Capabilities, NntpStream)> {
-        use std::net::TcpStream;
         let path = &server_conf.server_hostname;
 
         let stream = {
             let addr = lookup_ipv4(path, server_conf.server_port)?;
-            AsyncWrapper::new(Connection::Tcp(
-                TcpStream::connect_timeout(&addr, std::time::Duration::new(16, 0))
-                    .chain_err_kind(crate::error::ErrorKind::Network)?,
-            ))
-            .chain_err_kind(crate::error::ErrorKind::Network)?
+            AsyncWrapper::new(server_conf.connection_policy.connect_tcp(&addr)?)
+                .chain_err_kind(crate::error::ErrorKind::Network)?
         };
         let mut res = String::with_capacity(8 * 1024);
         let mut ret = NntpStream {
@@ -205,17 +201,6 @@ impl NntpStream {
         //    .as_bytes(),
         //)
         //.await?;
-        if let Err(err) = ret
-            .stream
-            .get_ref()
-            .set_keepalive(Some(std::time::Duration::new(60 * 9, 0)))
-        {
-            crate::log(
-                format!("Could not set TCP keepalive in NNTP connection: {}", err),
-                crate::LoggingLevel::WARN,
-            );
-        }
-
         ret.send_command(b"CAPABILITIES").await?;
         ret.read_response(&mut res, true, command_to_replycodes("CAPABILITIES"))
             .await?;
@@ -369,9 +354,13 @@ impl NntpStream {
             self.stream.write_all(command).await?;
             self.stream.write_all(b"\r\n").await?;
             self.stream.flush().await?;
-            debug!("sent: {}", unsafe {
-                std::str::from_utf8_unchecked(command)
-            });
+            if command.starts_with(b"AUTHINFO PASS") {
+                debug!("sent: AUTHINFO PASS ..");
+            } else {
+                debug!("sent: {}", unsafe {
+                    std::str::from_utf8_unchecked(command)
+                });
+            }
             Ok(())
         })
         .await
@@ -437,8 +437,9 @@ impl MailBackend for NotmuchDb {
             is_remote: false,
             supports_search: true,
             extensions: None,
-            supports_tags: true,
+            tag_support: TagSupport::Any,
             supports_submission: false,
+            lightweight_sync: false,
         };
         CAPABILITIES
     }
@@ -685,7 +686,7 @@ impl MailBackend for NotmuchDb {
         &mut self,
         env_hashes: EnvelopeHashBatch,
         _mailbox_hash: MailboxHash,
-        flags: SmallVec<[(std::result::Result<Flag, String>, bool); 8]>,
+        flags: FlagOps,
     ) -> ResultFuture<()> {
         let database = Self::new_connection(
             self.path.as_path(),
@@ -741,31 +742,30 @@ impl MailBackend for NotmuchDb {
                     }};
                 }
 
-                for (f, v) in flags.iter() {
-                    let value = *v;
-                    debug!(&f);
-                    debug!(&value);
-                    match f {
-                        Ok(Flag::DRAFT) if value => add_tag!(b"draft\0"),
-                        Ok(Flag::DRAFT) => remove_tag!(b"draft\0"),
-                        Ok(Flag::FLAGGED) if value => add_tag!(b"flagged\0"),
-                        Ok(Flag::FLAGGED) => remove_tag!(b"flagged\0"),
-                        Ok(Flag::PASSED) if value => add_tag!(b"passed\0"),
-                        Ok(Flag::PASSED) => remove_tag!(b"passed\0"),
-                        Ok(Flag::REPLIED) if value => add_tag!(b"replied\0"),
-                        Ok(Flag::REPLIED) => remove_tag!(b"replied\0"),
-                        Ok(Flag::SEEN) if value => remove_tag!(b"unread\0"),
-                        Ok(Flag::SEEN) => add_tag!(b"unread\0"),
-                        Ok(Flag::TRASHED) if value => add_tag!(b"trashed\0"),
-                        Ok(Flag::TRASHED) => remove_tag!(b"trashed\0"),
-                        Ok(_) => debug!("flags is {:?} value = {}", f, value),
-                        Err(tag) if value => {
+                for op in flags.iter() {
+                    let value = op.is_set();
+                    debug!(&op);
+                    match op {
+                        FlagOp::Flag(Flag::DRAFT, _) if value => add_tag!(b"draft\0"),
+                        FlagOp::Flag(Flag::DRAFT, _) => remove_tag!(b"draft\0"),
+                        FlagOp::Flag(Flag::FLAGGED, _) if value => add_tag!(b"flagged\0"),
+                        FlagOp::Flag(Flag::FLAGGED, _) => remove_tag!(b"flagged\0"),
+                        FlagOp::Flag(Flag::PASSED, _) if value => add_tag!(b"passed\0"),
+                        FlagOp::Flag(Flag::PASSED, _) => remove_tag!(b"passed\0"),
+                        FlagOp::Flag(Flag::REPLIED, _) if value => add_tag!(b"replied\0"),
+                        FlagOp::Flag(Flag::REPLIED, _) => remove_tag!(b"replied\0"),
+                        FlagOp::Flag(Flag::SEEN, _) if value => remove_tag!(b"unread\0"),
+                        FlagOp::Flag(Flag::SEEN, _) => add_tag!(b"unread\0"),
+                        FlagOp::Flag(Flag::TRASHED, _) if value => add_tag!(b"trashed\0"),
+                        FlagOp::Flag(Flag::TRASHED, _) => remove_tag!(b"trashed\0"),
+                        FlagOp::Flag(_, _) => debug!("flags is {:?}", op),
+                        FlagOp::Tag(tag, true) => {
                             let c_tag = CString::new(tag.as_str()).unwrap();
                             add_tag!(&c_tag.as_ref());
                         }
-                        Err(tag) => {
+                        FlagOp::Tag(tag, false) => {
                             let c_tag = CString::new(tag.as_str()).unwrap();
-                            add_tag!(&c_tag.as_ref());
+                            remove_tag!(&c_tag.as_ref());
                         }
                     }
                 }
@@ -778,8 +778,8 @@ impl MailBackend for NotmuchDb {
                     *p = msg_id.into();
                 }
             }
-            for (f, v) in flags.iter() {
-                if let (Err(tag), true) = (f, v) {
+            for op in flags.iter() {
+                if let FlagOp::Tag(tag, true) = op {
                     let hash = tag_hash!(tag);
                     collection
                         .tag_index
@@ -1050,6 +1050,40 @@ impl MelibQueryToNotmuchQuery for crate::search::Query {
             HasAttachment => {
                 ret.push_str("tag:attachment");
             }
+            AttachmentName(s) => {
+                ret.push_str("attachment:\"");
+                for c in s.chars() {
+                    if c == '"' {
+                        ret.push_str("\\\"");
+                    } else {
+                        ret.push(c);
+                    }
+                }
+                ret.push_str("\"");
+            }
+            List(s) => {
+                /* Needs `index.header.List=List-Id` in the notmuch configuration */
+                ret.push_str("List:\"");
+                for c in s.chars() {
+                    if c == '"' {
+                        ret.push_str("\\\"");
+                    } else {
+                        ret.push(c);
+                    }
+                }
+                ret.push_str("\"");
+            }
+            AttachmentType(s) => {
+                ret.push_str("mimetype:\"");
+                for c in s.chars() {
+                    if c == '"' {
+                        ret.push_str("\\\"");
+                    } else {
+                        ret.push(c);
+                    }
+                }
+                ret.push_str("\"");
+            }
             And(q1, q2) => {
                 ret.push_str("(");
                 q1.query_to_string(ret);
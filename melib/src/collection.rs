@@ -59,14 +59,62 @@ impl DerefMut for EnvelopeRefMut<'_> {
     }
 }
 
+/// Tag of messages that were manually made the root of their own thread.
+pub const THREAD_ROOT_TAG: &str = "meli:thread-root";
+/// Prefix of the tag of messages that were manually linked to a parent message. The rest of the
+/// tag is the parent's Message-ID.
+pub const THREAD_PARENT_TAG_PREFIX: &str = "meli:thread-parent:";
+
+/// A manual change to the threading of a message that overrides its `In-Reply-To` and
+/// `References` headers, for messages whose clients dropped them or got them wrong.
+#[derive(Debug, Clone, PartialEq, Eq, Deserialize, Serialize)]
+pub enum ThreadLink {
+    /// The message is a reply to the message with this Message-ID.
+    Parent(String),
+    /// The message starts its own thread.
+    Root,
+}
+
+impl ThreadLink {
+    /// The tag that stores this link in backends that support tags.
+    pub fn tag(&self) -> String {
+        match self {
+            ThreadLink::Parent(ref message_id) => {
+                format!("{}{}", THREAD_PARENT_TAG_PREFIX, message_id)
+            }
+            ThreadLink::Root => THREAD_ROOT_TAG.to_string(),
+        }
+    }
+
+    pub fn from_tag(tag: &str) -> Option<Self> {
+        if tag == THREAD_ROOT_TAG {
+            Some(ThreadLink::Root)
+        } else if tag.starts_with(THREAD_PARENT_TAG_PREFIX)
+            && tag.len() > THREAD_PARENT_TAG_PREFIX.len()
+        {
+            Some(ThreadLink::Parent(
+                tag[THREAD_PARENT_TAG_PREFIX.len()..].to_string(),
+            ))
+        } else {
+            None
+        }
+    }
+}
+
 #[derive(Debug, Clone)]
 pub struct Collection {
     pub envelopes: Arc<RwLock<HashMap<EnvelopeHash, Envelope>>>,
-    pub message_id_index: Arc<RwLock<HashMap<Vec<u8>, EnvelopeHash>>>,
+    /// Envelopes by raw Message-ID; more than one means the message has duplicates, e.g. copies
+    /// of it in several mailboxes.
+    pub message_id_index: Arc<RwLock<HashMap<Vec<u8>, SmallVec<[EnvelopeHash; 2]>>>>,
     pub threads: Arc<RwLock<HashMap<MailboxHash, Threads>>>,
     pub sent_mailbox: Arc<RwLock<Option<MailboxHash>>>,
     pub mailboxes: Arc<RwLock<HashMap<MailboxHash, HashSet<EnvelopeHash>>>>,
     pub tag_index: Arc<RwLock<BTreeMap<u64, String>>>,
+    /// Manual thread links by the raw Message-ID of the linked message.
+    pub thread_links: Arc<RwLock<HashMap<String, ThreadLink>>>,
+    /// Subject threading settings of each mailbox.
+    pub subject_threading: Arc<RwLock<HashMap<MailboxHash, SubjectThreading>>>,
 }
 
 impl Default for Collection {
@@ -121,6 +169,8 @@ impl Collection {
             threads,
             mailboxes,
             sent_mailbox: Arc::new(RwLock::new(None)),
+            thread_links: Arc::new(RwLock::new(HashMap::default())),
+            subject_threading: Arc::new(RwLock::new(HashMap::default())),
         }
     }
 
@@ -134,7 +184,10 @@ impl Collection {
 
     pub fn remove(&self, envelope_hash: EnvelopeHash, mailbox_hash: MailboxHash) {
         debug!("DEBUG: Removing {}", envelope_hash);
-        self.envelopes.write().unwrap().remove(&envelope_hash);
+        let envelope = self.envelopes.write().unwrap().remove(&envelope_hash);
+        if let Some(envelope) = envelope {
+            self.unindex_message_id(&envelope);
+        }
         self.mailboxes
             .write()
             .unwrap()
@@ -165,6 +218,7 @@ impl Collection {
             return false;
         }
         let mut envelope = self.envelopes.write().unwrap().remove(&old_hash).unwrap();
+        self.unindex_message_id(&envelope);
         self.mailboxes
             .write()
             .unwrap()
@@ -174,6 +228,7 @@ impl Collection {
                 m.insert(new_hash);
             });
         envelope.set_hash(new_hash);
+        self.index_message_id(&envelope);
         self.envelopes.write().unwrap().insert(new_hash, envelope);
         let mut threads_lck = self.threads.write().unwrap();
         {
@@ -211,6 +266,15 @@ impl Collection {
         sent_mailbox: Option<MailboxHash>,
     ) -> Option<SmallVec<[MailboxHash; 8]>> {
         *self.sent_mailbox.write().unwrap() = sent_mailbox;
+        for e in new_envelopes.values() {
+            self.load_thread_link_tags(e);
+        }
+        for e in new_envelopes.values_mut() {
+            self.apply_thread_links(e);
+        }
+        for e in new_envelopes.values() {
+            self.index_message_id(e);
+        }
 
         let Collection {
             ref threads,
@@ -223,7 +287,9 @@ impl Collection {
         let mut threads_lck = threads.write().unwrap();
         let mut mailboxes_lck = mailboxes.write().unwrap();
         if !threads_lck.contains_key(&mailbox_hash) {
-            threads_lck.insert(mailbox_hash, Threads::new(new_envelopes.len()));
+            let mut new_threads = Threads::new(new_envelopes.len());
+            self.init_subject_threading(mailbox_hash, &mut new_threads);
+            threads_lck.insert(mailbox_hash, new_threads);
             mailboxes_lck.insert(mailbox_hash, new_envelopes.keys().cloned().collect());
             for (h, e) in new_envelopes {
                 envelopes.write().unwrap().insert(h, e);
@@ -333,7 +399,11 @@ impl Collection {
         mailbox_hash: MailboxHash,
     ) {
         let old_env = self.envelopes.write().unwrap().remove(&old_hash).unwrap();
+        self.unindex_message_id(&old_env);
         envelope.set_thread(old_env.thread());
+        self.load_thread_link_tags(&envelope);
+        self.apply_thread_links(&mut envelope);
+        self.index_message_id(&envelope);
         let new_hash = envelope.hash();
         self.mailboxes
             .write()
@@ -421,7 +491,10 @@ impl Collection {
         }
     }
 
-    pub fn insert(&self, envelope: Envelope, mailbox_hash: MailboxHash) -> bool {
+    pub fn insert(&self, mut envelope: Envelope, mailbox_hash: MailboxHash) -> bool {
+        self.load_thread_link_tags(&envelope);
+        self.apply_thread_links(&mut envelope);
+        self.index_message_id(&envelope);
         let hash = envelope.hash();
         self.mailboxes
             .write()
@@ -456,6 +529,152 @@ impl Collection {
         }
     }
 
+    /// Link the message with raw Message-ID `message_id` to another thread, or make it start its
+    /// own, and rebuild the threads of every mailbox.
+    pub fn set_thread_link(&self, message_id: String, link: ThreadLink) {
+        self.thread_links.write().unwrap().insert(message_id, link);
+        for e in self.envelopes.write().unwrap().values_mut() {
+            self.apply_thread_links(e);
+        }
+        self.rebuild_threads();
+    }
+
+    /// Add the thread link stored in the tags of `envelope`, if any.
+    fn load_thread_link_tags(&self, envelope: &Envelope) {
+        if envelope.labels().is_empty() {
+            return;
+        }
+        let tag_index = self.tag_index.read().unwrap();
+        if let Some(link) = envelope
+            .labels()
+            .iter()
+            .filter_map(|l| tag_index.get(l))
+            .find_map(|tag| ThreadLink::from_tag(tag))
+        {
+            if link == ThreadLink::Root {
+                for t in self.threads.write().unwrap().values_mut() {
+                    t.subject_threading_exempt
+                        .insert(envelope.message_id().raw().to_vec());
+                }
+            }
+            self.thread_links.write().unwrap().insert(
+                String::from_utf8_lossy(envelope.message_id().raw()).to_string(),
+                link,
+            );
+        }
+    }
+
+    /// Set how messages of `mailbox_hash` without `References` are grouped by subject, and
+    /// rebuild its threads if the settings changed.
+    pub fn set_subject_threading(&self, mailbox_hash: MailboxHash, conf: SubjectThreading) {
+        if self
+            .subject_threading
+            .read()
+            .unwrap()
+            .get(&mailbox_hash)
+            .map(|c| *c == conf)
+            .unwrap_or(conf == SubjectThreading::default())
+        {
+            return;
+        }
+        self.subject_threading
+            .write()
+            .unwrap()
+            .insert(mailbox_hash, conf);
+        if self
+            .mailboxes
+            .read()
+            .unwrap()
+            .get(&mailbox_hash)
+            .map(|hashes| !hashes.is_empty())
+            .unwrap_or(false)
+        {
+            self.rebuild_threads();
+        } else if let Some(threads) = self.threads.write().unwrap().get_mut(&mailbox_hash) {
+            self.init_subject_threading(mailbox_hash, threads);
+        }
+    }
+
+    fn init_subject_threading(&self, mailbox_hash: MailboxHash, threads: &mut Threads) {
+        threads.subject_threading = self
+            .subject_threading
+            .read()
+            .unwrap()
+            .get(&mailbox_hash)
+            .cloned()
+            .unwrap_or_default();
+        threads.subject_threading_exempt = self
+            .thread_links
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(_, link)| **link == ThreadLink::Root)
+            .map(|(message_id, _)| message_id.as_bytes().to_vec())
+            .collect();
+    }
+
+    /// Replace the `In-Reply-To` and `References` of `envelope` according to the thread links.
+    fn apply_thread_links(&self, envelope: &mut Envelope) {
+        let thread_links = self.thread_links.read().unwrap();
+        if thread_links.is_empty() {
+            return;
+        }
+        match thread_links.get(String::from_utf8_lossy(envelope.message_id().raw()).as_ref()) {
+            Some(ThreadLink::Root) => {
+                envelope.in_reply_to = None;
+                envelope.references = None;
+            }
+            Some(ThreadLink::Parent(ref parent)) => {
+                envelope.references = None;
+                envelope.set_in_reply_to(parent.as_bytes());
+            }
+            None => {
+                /* The ancestors of a linked message are decided by its link, not by the
+                 * references of its replies. */
+                if let Some(ref mut references) = envelope.references {
+                    if let Some(pos) = references.refs.iter().rposition(|r| {
+                        thread_links.contains_key(String::from_utf8_lossy(r.raw()).as_ref())
+                    }) {
+                        references.refs.drain(..pos);
+                    }
+                }
+            }
+        }
+    }
+
+    /// Thread the envelopes of every mailbox from scratch.
+    fn rebuild_threads(&self) {
+        let mailboxes_lck = self.mailboxes.read().unwrap();
+        let sent_mailbox = *self.sent_mailbox.read().unwrap();
+        let by_date = |hashes: &HashSet<EnvelopeHash>| -> Vec<EnvelopeHash> {
+            let envelopes_lck = self.envelopes.read().unwrap();
+            let mut ret = hashes
+                .iter()
+                .filter(|h| envelopes_lck.contains_key(h))
+                .cloned()
+                .collect::<Vec<EnvelopeHash>>();
+            ret.sort_by_key(|h| envelopes_lck[h].date());
+            ret
+        };
+        let mut threads_lck = self.threads.write().unwrap();
+        for (mailbox_hash, hashes) in mailboxes_lck.iter() {
+            let mut threads = Threads::new(hashes.len());
+            self.init_subject_threading(*mailbox_hash, &mut threads);
+            for h in by_date(hashes) {
+                threads.insert(&self.envelopes, h);
+            }
+            if let Some(sent_hashes) = sent_mailbox
+                .filter(|s| s != mailbox_hash)
+                .and_then(|s| mailboxes_lck.get(&s))
+            {
+                for h in by_date(sent_hashes) {
+                    threads.insert_reply(&self.envelopes, h);
+                }
+            }
+            threads_lck.insert(*mailbox_hash, threads);
+        }
+    }
+
     pub fn get_env(&'_ self, env_hash: EnvelopeHash) -> EnvelopeRef<'_> {
         let guard: RwLockReadGuard<'_, _> = self.envelopes.read().unwrap();
         EnvelopeRef { guard, env_hash }
@@ -483,14 +702,92 @@ impl Collection {
         self.envelopes.read().unwrap().contains_key(env_hash)
     }
 
+    /// Other envelopes with the same Message-ID as `env_hash`.
+    pub fn duplicates(&self, env_hash: EnvelopeHash) -> SmallVec<[EnvelopeHash; 8]> {
+        match self.envelopes.read().unwrap().get(&env_hash) {
+            Some(envelope) => self.envelope_duplicates(envelope),
+            None => SmallVec::new(),
+        }
+    }
+
+    /// Other envelopes with the same Message-ID as `envelope`, for callers already holding a
+    /// reference to it.
+    pub fn envelope_duplicates(&self, envelope: &Envelope) -> SmallVec<[EnvelopeHash; 8]> {
+        self.message_id_index
+            .read()
+            .unwrap()
+            .get(envelope.message_id().raw())
+            .map(|hashes| {
+                hashes
+                    .iter()
+                    .filter(|h| **h != envelope.hash())
+                    .cloned()
+                    .collect()
+            })
+            .unwrap_or_default()
+    }
+
+    fn index_message_id(&self, envelope: &Envelope) {
+        let message_id = envelope.message_id().raw();
+        if message_id.is_empty() {
+            return;
+        }
+        let mut index_lck = self.message_id_index.write().unwrap();
+        let hashes = index_lck.entry(message_id.to_vec()).or_default();
+        if !hashes.contains(&envelope.hash()) {
+            hashes.push(envelope.hash());
+        }
+    }
+
+    fn unindex_message_id(&self, envelope: &Envelope) {
+        let mut index_lck = self.message_id_index.write().unwrap();
+        if let Some(hashes) = index_lck.get_mut(envelope.message_id().raw()) {
+            hashes.retain(|h| *h != envelope.hash());
+            if hashes.is_empty() {
+                index_lck.remove(envelope.message_id().raw());
+            }
+        }
+    }
+
+    /// Replace the envelopes of `mailbox_hash`, a mailbox the backend doesn't know of such as a
+    /// saved search, and update its threads. Returns whether they changed.
+    pub fn set_mailbox_envelopes(
+        &self,
+        mailbox_hash: MailboxHash,
+        env_hashes: HashSet<EnvelopeHash>,
+    ) -> bool {
+        let mut mailboxes_lck = self.mailboxes.write().unwrap();
+        let old = mailboxes_lck.entry(mailbox_hash).or_default();
+        if *old == env_hashes {
+            return false;
+        }
+        let mut threads_lck = self.threads.write().unwrap();
+        let threads = threads_lck.entry(mailbox_hash).or_default();
+        for env_hash in old.difference(&env_hashes) {
+            threads.remove(*env_hash);
+        }
+        let mut new = env_hashes
+            .difference(old)
+            .cloned()
+            .collect::<Vec<EnvelopeHash>>();
+        {
+            let envelopes_lck = self.envelopes.read().unwrap();
+            new.sort_by_key(|h| envelopes_lck[h].date());
+        }
+        for env_hash in new {
+            threads.insert(&self.envelopes, env_hash);
+        }
+        *old = env_hashes;
+        true
+    }
+
     pub fn new_mailbox(&self, mailbox_hash: MailboxHash) {
         let mut mailboxes_lck = self.mailboxes.write().unwrap();
         if !mailboxes_lck.contains_key(&mailbox_hash) {
             mailboxes_lck.insert(mailbox_hash, Default::default());
-            self.threads
-                .write()
-                .unwrap()
-                .insert(mailbox_hash, Threads::default());
+            let mut threads = Threads::default();
+            self.init_subject_threading(mailbox_hash, &mut threads);
+            self.threads.write().unwrap().insert(mailbox_hash, threads);
         }
     }
 }
@@ -507,3 +804,82 @@ impl<K: std::cmp::Eq + std::hash::Hash, V> Deref for RwRef<'_, K, V> {
         self.guard.get(&self.hash).unwrap()
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_thread_links() {
+        assert_eq!(
+            ThreadLink::from_tag(&ThreadLink::Root.tag()),
+            Some(ThreadLink::Root)
+        );
+        let parent = ThreadLink::Parent("a@example.com".to_string());
+        assert_eq!(ThreadLink::from_tag(&parent.tag()), Some(parent.clone()));
+        assert_eq!(ThreadLink::from_tag(THREAD_PARENT_TAG_PREFIX), None);
+
+        let collection = Collection::new();
+        collection
+            .thread_links
+            .write()
+            .unwrap()
+            .insert("b@example.com".to_string(), ThreadLink::Root);
+        collection
+            .thread_links
+            .write()
+            .unwrap()
+            .insert("c@example.com".to_string(), parent);
+        let mut reply = Envelope::from_bytes(
+            b"Message-ID: <d@example.com>\r\nIn-Reply-To: <b@example.com>\r\nReferences: <x@example.com> <b@example.com>\r\nSubject: d\r\n\r\nd\r\n",
+            None,
+        )
+        .unwrap();
+        collection.apply_thread_links(&mut reply);
+        assert_eq!(
+            reply
+                .references()
+                .iter()
+                .map(|r| String::from_utf8_lossy(r.raw()).to_string())
+                .collect::<Vec<String>>(),
+            vec!["b@example.com".to_string()]
+        );
+        let mut linked = Envelope::from_bytes(
+            b"Message-ID: <c@example.com>\r\nReferences: <x@example.com>\r\nSubject: c\r\n\r\nc\r\n",
+            None,
+        )
+        .unwrap();
+        collection.apply_thread_links(&mut linked);
+        assert_eq!(linked.in_reply_to().unwrap().raw(), b"a@example.com");
+        assert!(linked.references().is_empty());
+    }
+
+    #[test]
+    fn test_duplicates() {
+        let collection = Collection::new();
+        let (inbox, list) = (1, 2);
+        collection.new_mailbox(inbox);
+        collection.new_mailbox(list);
+        let copy = |folder: &str| {
+            Envelope::from_bytes(
+                format!(
+                    "Message-ID: <e@example.com>\r\nX-Folder: {}\r\nSubject: e\r\n\r\ne\r\n",
+                    folder
+                )
+                .as_bytes(),
+                None,
+            )
+            .unwrap()
+        };
+        let (a, b) = (copy("inbox"), copy("list"));
+        let (a_hash, b_hash) = (a.hash(), b.hash());
+        assert_ne!(a_hash, b_hash);
+        collection.insert(a, inbox);
+        assert!(collection.duplicates(a_hash).is_empty());
+        collection.insert(b, list);
+        assert_eq!(collection.duplicates(a_hash).as_slice(), &[b_hash]);
+        assert_eq!(collection.duplicates(b_hash).as_slice(), &[a_hash]);
+        collection.remove(b_hash, list);
+        assert!(collection.duplicates(a_hash).is_empty());
+    }
+}
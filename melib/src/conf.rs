@@ -20,7 +20,8 @@
  */
 
 //! Basic mail account configuration to use with [`backends`](./backends/index.html)
-use crate::backends::SpecialUsageMailbox;
+use crate::backends::{MailboxWatchUrgency, SpecialUsageMailbox};
+use crate::thread::SubjectThreading;
 use serde::{Deserialize, Deserializer, Serialize, Serializer};
 use std::collections::HashMap;
 
@@ -74,8 +75,8 @@ impl AccountSettings {
     }
 }
 
-#[serde(default)]
 #[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct MailboxConf {
     #[serde(alias = "rename")]
     pub alias: Option<String>,
@@ -87,6 +88,24 @@ pub struct MailboxConf {
     pub ignore: ToggleFlag,
     #[serde(default = "none")]
     pub usage: Option<SpecialUsageMailbox>,
+    /// Group replies without `In-Reply-To` and `References` headers by their subject.
+    #[serde(default = "false_val", alias = "group-by-subject")]
+    pub group_by_subject: bool,
+    /// Reply and forward prefixes stripped from subjects before comparing them.
+    #[serde(
+        default = "SubjectThreading::default_prefixes",
+        alias = "subject-prefixes"
+    )]
+    pub subject_prefixes: Vec<String>,
+    /// Strip mailing list tags such as `[list-name]` from subjects before comparing them.
+    #[serde(default = "true_val", alias = "strip-list-tags")]
+    pub strip_list_tags: bool,
+    /// Show this mailbox in the unified inbox. Defaults to `true` for inbox mailboxes.
+    #[serde(default = "none", alias = "unified-inbox")]
+    pub unified_inbox: Option<bool>,
+    /// How promptly changes to this mailbox should be noticed.
+    #[serde(default, alias = "watch-urgency")]
+    pub watch_urgency: MailboxWatchUrgency,
     #[serde(flatten)]
     pub extra: HashMap<String, String>,
 }
@@ -99,6 +118,11 @@ impl Default for MailboxConf {
             subscribe: ToggleFlag::Unset,
             ignore: ToggleFlag::Unset,
             usage: None,
+            group_by_subject: false,
+            subject_prefixes: SubjectThreading::default_prefixes(),
+            strip_list_tags: true,
+            unified_inbox: None,
+            watch_urgency: MailboxWatchUrgency::default(),
             extra: HashMap::default(),
         }
     }
@@ -108,6 +132,14 @@ impl MailboxConf {
     pub fn alias(&self) -> Option<&str> {
         self.alias.as_deref()
     }
+
+    pub fn subject_threading(&self) -> SubjectThreading {
+        SubjectThreading {
+            enabled: self.group_by_subject,
+            prefixes: self.subject_prefixes.clone(),
+            strip_list_tags: self.strip_list_tags,
+        }
+    }
 }
 
 pub fn true_val() -> bool {
@@ -295,3 +295,160 @@ pub async fn timeout<O>(dur: Option<Duration>, f: impl Future<Output = O>) -> cr
 pub async fn sleep(dur: Duration) {
     smol::Timer::after(dur).await;
 }
+
+/// Timeouts, TCP keepalive and retry policy of the connections of an account, set with the
+/// `connect_timeout`, `read_timeout`, `tcp_keepalive`, `connection_retries` and `retry_backoff`
+/// account settings.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct ConnectionPolicy {
+    /// Time to wait for a TCP connection to be established.
+    pub connect_timeout: Option<Duration>,
+    /// Time to wait for a reply from the server.
+    pub read_timeout: Option<Duration>,
+    /// Idle time before TCP keepalive probes are sent.
+    pub keepalive: Option<Duration>,
+    /// How many times a connection that failed with a network error is attempted again.
+    pub retries: u32,
+    /// Delay before the first retry, doubled on every following retry.
+    pub retry_backoff: Duration,
+}
+
+impl Default for ConnectionPolicy {
+    fn default() -> Self {
+        ConnectionPolicy {
+            connect_timeout: Some(Duration::from_secs(16)),
+            read_timeout: Some(Duration::from_secs(16)),
+            keepalive: Some(Duration::from_secs(60 * 9)),
+            retries: 3,
+            retry_backoff: Duration::from_secs(5),
+        }
+    }
+}
+
+impl ConnectionPolicy {
+    /// Maximum delay between two retries.
+    const MAX_BACKOFF: Duration = Duration::from_secs(5 * 60);
+
+    /// Reads the policy from the settings of an account. Durations are in seconds and `0`
+    /// disables a timeout or the keepalive. The `timeout` setting, if set, is the default of both
+    /// timeouts.
+    pub fn new(s: &crate::conf::AccountSettings) -> crate::Result<Self> {
+        fn get<T: std::str::FromStr>(
+            s: &crate::conf::AccountSettings,
+            key: &str,
+            default: T,
+        ) -> crate::Result<T>
+        where
+            T::Err: std::fmt::Display,
+        {
+            s.extra
+                .get(key)
+                .map(|v| {
+                    T::from_str(v).map_err(|e| {
+                        crate::error::MeliError::new(format!(
+                            "Configuration error ({}): Invalid value for field `{}`: {}\n{}",
+                            s.name.as_str(),
+                            key,
+                            v,
+                            e
+                        ))
+                    })
+                })
+                .unwrap_or(Ok(default))
+        }
+        fn secs(val: u64) -> Option<Duration> {
+            if val == 0 {
+                None
+            } else {
+                Some(Duration::from_secs(val))
+            }
+        }
+        let default = Self::default();
+        let timeout = get(s, "timeout", 16_u64)?;
+        Ok(ConnectionPolicy {
+            connect_timeout: secs(get(s, "connect_timeout", timeout)?),
+            read_timeout: secs(get(s, "read_timeout", timeout)?),
+            keepalive: secs(get(s, "tcp_keepalive", 60 * 9_u64)?),
+            retries: get(s, "connection_retries", default.retries)?,
+            retry_backoff: Duration::from_secs(get(
+                s,
+                "retry_backoff",
+                default.retry_backoff.as_secs(),
+            )?),
+        })
+    }
+
+    /// Delay before the `attempt`th retry.
+    pub fn backoff(&self, attempt: u32) -> Duration {
+        std::cmp::min(
+            self.retry_backoff
+                .checked_mul(1 << std::cmp::min(attempt.saturating_sub(1), 16))
+                .unwrap_or(Self::MAX_BACKOFF),
+            Self::MAX_BACKOFF,
+        )
+    }
+
+    /// Opens a TCP connection to `addr` within the connect timeout, with TCP keepalive set.
+    pub fn connect_tcp(&self, addr: &std::net::SocketAddr) -> crate::Result<Connection> {
+        use crate::error::ResultIntoMeliError;
+        let stream = if let Some(timeout) = self.connect_timeout {
+            std::net::TcpStream::connect_timeout(addr, timeout)
+        } else {
+            std::net::TcpStream::connect(addr)
+        }
+        .chain_err_kind(crate::error::ErrorKind::Network)?;
+        let ret = Tcp(stream);
+        if let Err(err) = ret.set_keepalive(self.keepalive) {
+            crate::log(
+                format!("Could not set TCP keepalive to {}: {}", addr, err),
+                crate::LoggingLevel::WARN,
+            );
+        }
+        Ok(ret)
+    }
+
+    /// Runs `f` until it succeeds, retrying after network errors and timeouts up to
+    /// `self.retries` times.
+    pub async fn retry<O, F, Fut>(&self, mut f: F) -> crate::Result<O>
+    where
+        F: FnMut() -> Fut,
+        Fut: Future<Output = crate::Result<O>>,
+    {
+        let mut attempt = 0;
+        loop {
+            match f().await {
+                Err(err) if err.is_recoverable() && attempt < self.retries => {
+                    attempt += 1;
+                    debug!(
+                        "Connection failed, retrying in {}s: {}",
+                        self.backoff(attempt).as_secs(),
+                        err
+                    );
+                    sleep(self.backoff(attempt)).await;
+                }
+                ret => return ret,
+            }
+        }
+    }
+}
+
+#[test]
+fn test_connection_policy() {
+    let mut s = crate::conf::AccountSettings::default();
+    assert_eq!(
+        ConnectionPolicy::new(&s).unwrap(),
+        ConnectionPolicy::default()
+    );
+    s.extra.insert("timeout".into(), "30".into());
+    s.extra.insert("read_timeout".into(), "0".into());
+    s.extra.insert("connection_retries".into(), "1".into());
+    let policy = ConnectionPolicy::new(&s).unwrap();
+    assert_eq!(policy.connect_timeout, Some(Duration::from_secs(30)));
+    assert_eq!(policy.read_timeout, None);
+    assert_eq!(policy.retries, 1);
+    assert_eq!(policy.backoff(1), Duration::from_secs(5));
+    assert_eq!(policy.backoff(3), Duration::from_secs(20));
+    assert_eq!(policy.backoff(100), Duration::from_secs(5 * 60));
+    s.extra.insert("tcp_keepalive".into(), "often".into());
+    assert!(ConnectionPolicy::new(&s).is_err());
+}
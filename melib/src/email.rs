@@ -97,11 +97,12 @@ pub mod headers;
 pub mod list_management;
 pub mod mailto;
 pub mod parser;
+pub mod partial;
 pub mod pgp;
 
 pub use address::{Address, MessageID, References, StrBuild, StrBuilder};
 pub use attachments::{Attachment, AttachmentBuilder};
-pub use compose::{attachment_from_file, Draft};
+pub use compose::{attachment_from_file, Draft, DraftBuilder};
 pub use headers::*;
 pub use mailto::*;
 
@@ -224,6 +225,9 @@ pub struct Envelope {
     pub flags: Flag,
     pub has_attachments: bool,
     pub labels: SmallVec<[u64; 8]>,
+    /// Size of the raw message in bytes, if known.
+    #[serde(default)]
+    pub size: Option<usize>,
 }
 
 impl core::fmt::Debug for Envelope {
@@ -267,6 +271,7 @@ impl Envelope {
             has_attachments: false,
             flags: Flag::default(),
             labels: SmallVec::new(),
+            size: None,
         }
     }
 
@@ -281,6 +286,7 @@ impl Envelope {
         let mut e = Envelope::new(h.finish());
         let res = e.populate_headers(bytes).ok();
         if res.is_some() {
+            e.size = Some(bytes.len());
             if let Some(f) = flags {
                 e.flags = f;
             }
@@ -779,6 +785,12 @@ impl Envelope {
         self.has_attachments
     }
 
+    /// The id in the `List-Id` header, e.g. `debian-devel.lists.debian.org`, if the envelope was
+    /// sent through a mailing list.
+    pub fn list_id(&self) -> Option<&str> {
+        list_management::list_id(list_management::list_id_header(self))
+    }
+
     pub fn labels(&self) -> &SmallVec<[u64; 8]> {
         &self.labels
     }
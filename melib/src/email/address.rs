@@ -205,6 +205,47 @@ impl Address {
         }
     }
 
+    /// Whether the local part has non-ASCII characters, so that the address can only be used
+    /// with servers that support SMTPUTF8 ([RFC 6531](https://tools.ietf.org/html/rfc6531)).
+    /// Non-ASCII domains can be converted with [`domain_to_ascii`].
+    pub fn has_utf8_local_part(&self) -> bool {
+        let email = self.get_email();
+        match email.rfind('@') {
+            Some(pos) => !email[..pos].is_ascii(),
+            None => !email.is_ascii(),
+        }
+    }
+
+    /// The e-mail address with its domain converted to A-labels.
+    pub fn get_email_ascii_domain(&self) -> Result<String> {
+        let email = self.get_email();
+        match email.rfind('@') {
+            Some(pos) => Ok(format!(
+                "{}@{}",
+                &email[..pos],
+                domain_to_ascii(&email[pos + 1..])?
+            )),
+            None => Ok(email),
+        }
+    }
+
+    /// Like `to_string()`, but with A-labels in the domain converted to U-labels for display.
+    pub fn to_unicode_string(&self) -> String {
+        domains_to_unicode(&self.to_string())
+    }
+
+    /// Whether the domain of the address is `domain`, comparing their A-labels case
+    /// insensitively.
+    pub fn has_domain(&self, domain: &str) -> bool {
+        match (
+            self.get_fqdn().and_then(|d| domain_to_ascii(&d).ok()),
+            domain_to_ascii(domain),
+        ) {
+            (Some(a), Ok(b)) => a.eq_ignore_ascii_case(&b),
+            _ => false,
+        }
+    }
+
     pub fn get_tags(&self, separator: char) -> Vec<String> {
         let email = self.get_email();
         let at_pos = email
@@ -286,6 +327,82 @@ impl Address {
     }
 }
 
+/// Convert the labels of an internationalized `domain` to A-labels (`xn--` punycode), as used in
+/// SMTP commands and DNS. ASCII domains are returned lowercased.
+pub fn domain_to_ascii(domain: &str) -> Result<String> {
+    idna::domain_to_ascii(domain.trim())
+        .map_err(|err| MeliError::new(format!("Invalid domain {}: {:?}", domain, err)))
+}
+
+/// Convert the A-labels of `domain` to U-labels. Invalid labels are kept as they are.
+pub fn domain_to_unicode(domain: &str) -> String {
+    match idna::domain_to_unicode(domain.trim()) {
+        (ret, Ok(())) => ret,
+        (_, Err(_)) => domain.to_string(),
+    }
+}
+
+/// Replace the domains of the e-mail addresses in `text` with `f(domain)`.
+fn map_domains(text: &str, f: impl Fn(&str) -> Option<String>) -> String {
+    let mut ret = String::with_capacity(text.len());
+    let mut rest = text;
+    while let Some(pos) = rest.find('@') {
+        ret.push_str(&rest[..=pos]);
+        rest = &rest[pos + 1..];
+        let end = rest
+            .find(|c: char| c.is_whitespace() || ">,;)\"]@".contains(c))
+            .unwrap_or_else(|| rest.len());
+        let domain = &rest[..end];
+        ret.push_str(&f(domain).unwrap_or_else(|| domain.to_string()));
+        rest = &rest[end..];
+    }
+    ret.push_str(rest);
+    ret
+}
+
+/// Convert the A-labels in the domains of the e-mail addresses in `text`, such as a `From`
+/// header value, to U-labels for display.
+pub fn domains_to_unicode(text: &str) -> String {
+    if !text.contains("xn--") && !text.contains("XN--") {
+        return text.to_string();
+    }
+    map_domains(text, |d| Some(domain_to_unicode(d)))
+}
+
+/// Convert the non-ASCII domains of the e-mail addresses in `text` to A-labels.
+pub fn domains_to_ascii(text: &str) -> String {
+    if text.is_ascii() {
+        return text.to_string();
+    }
+    map_domains(text, |d| {
+        if d.is_ascii() {
+            None
+        } else {
+            domain_to_ascii(d).ok()
+        }
+    })
+}
+
+#[test]
+fn test_idna_addresses() {
+    let (_, addr) =
+        parser::address::address("Δοκιμή <χρήστης@παράδειγμα.δοκιμή>".as_bytes()).unwrap();
+    assert!(addr.has_utf8_local_part());
+    assert_eq!(
+        addr.get_email_ascii_domain().unwrap(),
+        "χρήστης@xn--hxajbheg2az3al.xn--jxalpdlp"
+    );
+    assert!(addr.has_domain("XN--HXAJBHEG2AZ3AL.xn--jxalpdlp"));
+    assert_eq!(
+        domains_to_unicode("Bob <bob@xn--bcher-kva.example>, alice@example.com"),
+        "Bob <bob@bücher.example>, alice@example.com"
+    );
+    assert_eq!(
+        domains_to_ascii("bob@bücher.example"),
+        "bob@xn--bcher-kva.example"
+    );
+}
+
 impl Eq for Address {}
 
 impl PartialEq for Address {
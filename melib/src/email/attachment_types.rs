@@ -29,6 +29,8 @@ pub enum Charset {
     Ascii,
     UTF8,
     UTF16,
+    UTF16BE,
+    UTF16LE,
     ISO8859_1,
     ISO8859_2,
     ISO8859_3,
@@ -37,21 +39,33 @@ pub enum Charset {
     ISO8859_6,
     ISO8859_7,
     ISO8859_8,
+    ISO8859_8I,
     ISO8859_10,
     ISO8859_13,
     ISO8859_14,
     ISO8859_15,
     ISO8859_16,
+    Windows874,
     Windows1250,
     Windows1251,
     Windows1252,
     Windows1253,
+    Windows1254,
+    Windows1255,
+    Windows1256,
+    Windows1257,
+    Windows1258,
+    IBM866,
+    Macintosh,
+    MacCyrillic,
     GBK,
     GB2312,
     GB18030,
     BIG5,
     ISO2022JP,
     EUCJP,
+    ShiftJIS,
+    EUCKR,
     KOI8R,
     KOI8U,
 }
@@ -62,100 +76,138 @@ impl Default for Charset {
     }
 }
 
+impl Charset {
+    /// The `encoding_rs` encoding used to decode text in this charset.
+    pub fn encoding(self) -> &'static encoding_rs::Encoding {
+        use encoding_rs::*;
+        match self {
+            Charset::Ascii | Charset::UTF8 => UTF_8,
+            Charset::UTF16 | Charset::UTF16LE => UTF_16LE,
+            Charset::UTF16BE => UTF_16BE,
+            /* windows-1252 is a superset of iso-8859-1 apart from the C1 controls. */
+            Charset::ISO8859_1 | Charset::Windows1252 => WINDOWS_1252,
+            Charset::ISO8859_2 => ISO_8859_2,
+            Charset::ISO8859_3 => ISO_8859_3,
+            Charset::ISO8859_4 => ISO_8859_4,
+            Charset::ISO8859_5 => ISO_8859_5,
+            Charset::ISO8859_6 => ISO_8859_6,
+            Charset::ISO8859_7 => ISO_8859_7,
+            Charset::ISO8859_8 => ISO_8859_8,
+            Charset::ISO8859_8I => ISO_8859_8_I,
+            Charset::ISO8859_10 => ISO_8859_10,
+            Charset::ISO8859_13 => ISO_8859_13,
+            Charset::ISO8859_14 => ISO_8859_14,
+            Charset::ISO8859_15 => ISO_8859_15,
+            Charset::ISO8859_16 => ISO_8859_16,
+            Charset::Windows874 => WINDOWS_874,
+            Charset::Windows1250 => WINDOWS_1250,
+            Charset::Windows1251 => WINDOWS_1251,
+            Charset::Windows1253 => WINDOWS_1253,
+            Charset::Windows1254 => WINDOWS_1254,
+            Charset::Windows1255 => WINDOWS_1255,
+            Charset::Windows1256 => WINDOWS_1256,
+            Charset::Windows1257 => WINDOWS_1257,
+            Charset::Windows1258 => WINDOWS_1258,
+            Charset::IBM866 => IBM866,
+            Charset::Macintosh => MACINTOSH,
+            Charset::MacCyrillic => X_MAC_CYRILLIC,
+            /* gb2312 is a subset of GBK. */
+            Charset::GBK | Charset::GB2312 => GBK,
+            Charset::GB18030 => GB18030,
+            Charset::BIG5 => BIG5,
+            Charset::ISO2022JP => ISO_2022_JP,
+            Charset::EUCJP => EUC_JP,
+            Charset::ShiftJIS => SHIFT_JIS,
+            Charset::EUCKR => EUC_KR,
+            Charset::KOI8R => KOI8_R,
+            Charset::KOI8U => KOI8_U,
+        }
+    }
+
+    fn from_encoding(encoding: &'static encoding_rs::Encoding) -> Option<Self> {
+        use encoding_rs::*;
+        const ALL: &[Charset] = &[
+            Charset::UTF8,
+            Charset::UTF16LE,
+            Charset::UTF16BE,
+            Charset::Windows1252,
+            Charset::ISO8859_2,
+            Charset::ISO8859_3,
+            Charset::ISO8859_4,
+            Charset::ISO8859_5,
+            Charset::ISO8859_6,
+            Charset::ISO8859_7,
+            Charset::ISO8859_8,
+            Charset::ISO8859_8I,
+            Charset::ISO8859_10,
+            Charset::ISO8859_13,
+            Charset::ISO8859_14,
+            Charset::ISO8859_15,
+            Charset::ISO8859_16,
+            Charset::Windows874,
+            Charset::Windows1250,
+            Charset::Windows1251,
+            Charset::Windows1253,
+            Charset::Windows1254,
+            Charset::Windows1255,
+            Charset::Windows1256,
+            Charset::Windows1257,
+            Charset::Windows1258,
+            Charset::IBM866,
+            Charset::Macintosh,
+            Charset::MacCyrillic,
+            Charset::GBK,
+            Charset::GB18030,
+            Charset::BIG5,
+            Charset::ISO2022JP,
+            Charset::EUCJP,
+            Charset::ShiftJIS,
+            Charset::EUCKR,
+            Charset::KOI8R,
+            Charset::KOI8U,
+        ];
+        if encoding == REPLACEMENT || encoding == X_USER_DEFINED {
+            return None;
+        }
+        ALL.iter().copied().find(|c| c.encoding() == encoding)
+    }
+}
+
 impl<'a> From<&'a [u8]> for Charset {
     fn from(b: &'a [u8]) -> Self {
-        match b.trim() {
-            b if b.eq_ignore_ascii_case(b"us-ascii") || b.eq_ignore_ascii_case(b"ascii") => {
-                Charset::Ascii
-            }
-            b if b.eq_ignore_ascii_case(b"utf-8") || b.eq_ignore_ascii_case(b"utf8") => {
-                Charset::UTF8
-            }
-            b if b.eq_ignore_ascii_case(b"utf-16") || b.eq_ignore_ascii_case(b"utf16") => {
-                Charset::UTF16
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-1") || b.eq_ignore_ascii_case(b"iso8859-1") => {
-                Charset::ISO8859_1
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-2") || b.eq_ignore_ascii_case(b"iso8859-2") => {
-                Charset::ISO8859_2
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-3") || b.eq_ignore_ascii_case(b"iso8859-3") => {
-                Charset::ISO8859_3
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-4") || b.eq_ignore_ascii_case(b"iso8859-4") => {
-                Charset::ISO8859_4
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-5") || b.eq_ignore_ascii_case(b"iso8859-5") => {
-                Charset::ISO8859_5
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-6") || b.eq_ignore_ascii_case(b"iso8859-6") => {
-                Charset::ISO8859_6
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-7") || b.eq_ignore_ascii_case(b"iso8859-7") => {
-                Charset::ISO8859_7
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-8") || b.eq_ignore_ascii_case(b"iso8859-8") => {
-                Charset::ISO8859_8
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-10")
-                || b.eq_ignore_ascii_case(b"iso8859-10") =>
-            {
-                Charset::ISO8859_10
+        let b = b.trim();
+        /* Labels that the WHATWG encoding standard maps to a different or no encoding. */
+        match b.to_ascii_lowercase().as_slice() {
+            b"us-ascii" | b"ascii" | b"ansi_x3.4-1968" | b"iso646-us" => return Charset::Ascii,
+            b"utf-16" | b"utf16" => return Charset::UTF16,
+            b"iso-8859-1" | b"iso8859-1" | b"iso_8859-1" | b"latin1" | b"l1" => {
+                return Charset::ISO8859_1
             }
-            b if b.eq_ignore_ascii_case(b"iso-8859-13")
-                || b.eq_ignore_ascii_case(b"iso8859-13") =>
-            {
-                Charset::ISO8859_13
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-14")
-                || b.eq_ignore_ascii_case(b"iso8859-14") =>
-            {
-                Charset::ISO8859_14
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-15")
-                || b.eq_ignore_ascii_case(b"iso8859-15") =>
-            {
-                Charset::ISO8859_15
-            }
-            b if b.eq_ignore_ascii_case(b"iso-8859-16")
-                || b.eq_ignore_ascii_case(b"iso8859-16") =>
-            {
-                Charset::ISO8859_16
-            }
-            b if b.eq_ignore_ascii_case(b"windows-1250")
-                || b.eq_ignore_ascii_case(b"windows1250") =>
-            {
-                Charset::Windows1250
-            }
-            b if b.eq_ignore_ascii_case(b"windows-1251")
-                || b.eq_ignore_ascii_case(b"windows1251") =>
-            {
-                Charset::Windows1251
-            }
-            b if b.eq_ignore_ascii_case(b"windows-1252")
-                || b.eq_ignore_ascii_case(b"windows1252") =>
-            {
-                Charset::Windows1252
-            }
-            b if b.eq_ignore_ascii_case(b"windows-1253")
-                || b.eq_ignore_ascii_case(b"windows1253") =>
-            {
-                Charset::Windows1253
-            }
-            b if b.eq_ignore_ascii_case(b"gbk") => Charset::GBK,
-            b if b.eq_ignore_ascii_case(b"gb18030") || b.eq_ignore_ascii_case(b"gb-18030") => {
-                Charset::GB18030
-            }
-            b if b.eq_ignore_ascii_case(b"gb2312") || b.eq_ignore_ascii_case(b"gb-2312") => {
-                Charset::GB2312
-            }
-            b if b.eq_ignore_ascii_case(b"big5") => Charset::BIG5,
-            b if b.eq_ignore_ascii_case(b"iso-2022-jp") => Charset::ISO2022JP,
-            b if b.eq_ignore_ascii_case(b"euc-jp") => Charset::EUCJP,
-            b if b.eq_ignore_ascii_case(b"koi8-r") => Charset::KOI8R,
-            b if b.eq_ignore_ascii_case(b"koi8-u") => Charset::KOI8U,
-            _ => {
+            b"gb2312" | b"gb-2312" | b"x-gbk" => return Charset::GB2312,
+            b"gb-18030" => return Charset::GB18030,
+            b"utf8" | b"unicode-1-1-utf-8" => return Charset::UTF8,
+            b"sjis" | b"cp932" | b"x-ms-cp932" | b"windows-31j" => return Charset::ShiftJIS,
+            b"cp949" | b"ks_c_5601-1987" | b"uhc" => return Charset::EUCKR,
+            b"big5-hkscs" | b"cp950" => return Charset::BIG5,
+            b"iso-2022-jp-2" | b"iso-2022-jp-3" | b"csiso2022jp" => return Charset::ISO2022JP,
+            _ => {}
+        }
+        if let Some(charset) = encoding_rs::Encoding::for_label(b).and_then(Charset::from_encoding)
+        {
+            return charset;
+        }
+        /* Labels such as `windows1252` or `iso8859_2`. */
+        let normalized = String::from_utf8_lossy(b)
+            .to_ascii_lowercase()
+            .replace('_', "-")
+            .replace("windows", "windows-")
+            .replace("iso8859", "iso-8859")
+            .replace("--", "-");
+        match encoding_rs::Encoding::for_label(normalized.as_bytes())
+            .and_then(Charset::from_encoding)
+        {
+            Some(charset) => charset,
+            None => {
                 debug!("unknown tag is {:?}", str::from_utf8(b));
                 Charset::Ascii
             }
@@ -170,30 +222,8 @@ impl Display for Charset {
             Charset::UTF8 => write!(f, "utf-8"),
             Charset::UTF16 => write!(f, "utf-16"),
             Charset::ISO8859_1 => write!(f, "iso-8859-1"),
-            Charset::ISO8859_2 => write!(f, "iso-8859-2"),
-            Charset::ISO8859_3 => write!(f, "iso-8859-3"),
-            Charset::ISO8859_4 => write!(f, "iso-8859-4"),
-            Charset::ISO8859_5 => write!(f, "iso-8859-5"),
-            Charset::ISO8859_6 => write!(f, "iso-8859-6"),
-            Charset::ISO8859_7 => write!(f, "iso-8859-7"),
-            Charset::ISO8859_8 => write!(f, "iso-8859-8"),
-            Charset::ISO8859_10 => write!(f, "iso-8859-10"),
-            Charset::ISO8859_13 => write!(f, "iso-8859-13"),
-            Charset::ISO8859_14 => write!(f, "iso-8859-14"),
-            Charset::ISO8859_15 => write!(f, "iso-8859-15"),
-            Charset::ISO8859_16 => write!(f, "iso-8859-16"),
-            Charset::Windows1250 => write!(f, "windows-1250"),
-            Charset::Windows1251 => write!(f, "windows-1251"),
-            Charset::Windows1252 => write!(f, "windows-1252"),
-            Charset::Windows1253 => write!(f, "windows-1253"),
-            Charset::GBK => write!(f, "gbk"),
             Charset::GB2312 => write!(f, "gb2312"),
-            Charset::GB18030 => write!(f, "gb18030"),
-            Charset::BIG5 => write!(f, "big5"),
-            Charset::ISO2022JP => write!(f, "iso-2022-jp"),
-            Charset::EUCJP => write!(f, "euc-jp"),
-            Charset::KOI8R => write!(f, "koi8-r"),
-            Charset::KOI8U => write!(f, "koi8-u"),
+            other => write!(f, "{}", other.encoding().name().to_ascii_lowercase()),
         }
     }
 }
@@ -345,6 +375,17 @@ impl PartialEq<&str> for ContentType {
             (ContentType::PGPSignature, "application/pgp-signature") => true,
             (ContentType::CMSSignature, "application/pkcs7-signature") => true,
             (ContentType::MessageRfc822, "message/rfc822") => true,
+            (
+                ContentType::Text {
+                    kind: Text::Other { tag },
+                    ..
+                },
+                _,
+            ) => {
+                other.len() == "text/".len() + tag.len()
+                    && other[.."text/".len()].eq_ignore_ascii_case("text/")
+                    && other["text/".len()..].eq_ignore_ascii_case(&String::from_utf8_lossy(&tag))
+            }
             (ContentType::Other { tag, .. }, _) => {
                 other.eq_ignore_ascii_case(&String::from_utf8_lossy(&tag))
             }
@@ -32,6 +32,10 @@ use smallvec::SmallVec;
 
 use crate::email::attachment_types::*;
 
+pub mod stream;
+pub mod tnef;
+pub mod uuencode;
+
 #[derive(Default, Debug, Clone, Serialize, Deserialize, PartialEq, Eq)]
 pub struct AttachmentBuilder {
     pub content_type: ContentType,
@@ -84,6 +88,7 @@ impl AttachmentBuilder {
                 builder.set_content_disposition(ContentDisposition::from(value));
             }
         }
+        builder.expand_embedded_files();
         builder
     }
 
@@ -169,7 +174,7 @@ impl AttachmentBuilder {
                                 charset: ref mut c, ..
                             } = self.content_type
                             {
-                                *c = Charset::from(v);
+                                *c = Charset::from(&*v);
                             }
                         }
                         if let ContentType::Text {
@@ -216,7 +221,7 @@ impl AttachmentBuilder {
                             {
                                 name = Some(v);
                             } else {
-                                name = Some(String::from_utf8_lossy(v).into());
+                                name = Some(String::from_utf8_lossy(&v).into());
                             }
                             break;
                         }
@@ -239,6 +244,37 @@ impl AttachmentBuilder {
         self
     }
 
+    /// Show the contents of a TNEF attachment, or a plain text part and the files uuencoded in
+    /// it, as the parts of a multipart.
+    fn expand_embedded_files(&mut self) {
+        let parts = if tnef::is_tnef(&self.content_type) {
+            tnef::parts(&decode(&self.clone().build(), None))
+        } else if self.content_type == "text/plain" && self.body().find(b"begin ").is_some() {
+            let text = String::from_utf8_lossy(&decode(&self.clone().build(), None)).into_owned();
+            match uuencode::split(&text) {
+                Some((text, mut files)) => {
+                    let mut text_part = AttachmentBuilder::default();
+                    text_part
+                        .set_raw(text.into_bytes())
+                        .set_body_to_raw()
+                        .set_content_type(ContentType::default());
+                    files.insert(0, text_part.build());
+                    files
+                }
+                None => return,
+            }
+        } else {
+            return;
+        };
+        if !parts.is_empty() {
+            self.content_type = ContentType::Multipart {
+                boundary: Vec::new(),
+                kind: MultipartType::Mixed,
+                parts,
+            };
+        }
+    }
+
     pub fn build(self) -> Attachment {
         Attachment {
             content_type: self.content_type,
@@ -287,6 +323,7 @@ impl AttachmentBuilder {
                             builder.set_content_disposition(ContentDisposition::from(value));
                         }
                     }
+                    builder.expand_embedded_files();
                     vec.push(builder.build());
                 }
                 vec
@@ -534,7 +571,7 @@ impl Attachment {
                             None
                         })
                     {
-                        if Attachment::check_if_has_attachments_quick(body, boundary) {
+                        if Attachment::check_if_has_attachments_quick(body, &boundary) {
                             return true;
                         }
                     }
@@ -591,6 +628,64 @@ impl Attachment {
         String::from_utf8_lossy(text.as_slice()).into()
     }
 
+    /// The text of all inline parts with `text/html` parts rendered as plain text, for full text
+    /// indexing. `multipart/alternative` parts use their `text/plain` alternative if they have one.
+    pub fn searchable_text(&self) -> String {
+        let mut text = String::with_capacity(self.body.length);
+        self.get_searchable_text_recursive(&mut text);
+        text
+    }
+
+    fn get_searchable_text_recursive(&self, text: &mut String) {
+        match self.content_type {
+            ContentType::Text {
+                kind: Text::Html, ..
+            } => {
+                let html = String::from_utf8_lossy(&decode(self, None)).to_string();
+                /* Wide enough that phrases are not split across lines */
+                text.push_str(&crate::text_processing::html::html_to_text(&html, 4096).text);
+                text.push('\n');
+            }
+            ContentType::Text { .. } => {
+                text.push_str(&String::from_utf8_lossy(&decode(self, None)));
+                text.push('\n');
+            }
+            ContentType::Multipart {
+                kind: MultipartType::Alternative,
+                ref parts,
+                ..
+            } => {
+                let inline = parts
+                    .iter()
+                    .filter(|a| a.content_disposition.kind.is_inline())
+                    .collect::<Vec<&Attachment>>();
+                if let Some(a) = inline
+                    .iter()
+                    .find(|a| {
+                        matches!(
+                            a.content_type,
+                            ContentType::Text {
+                                kind: Text::Plain,
+                                ..
+                            }
+                        )
+                    })
+                    .or_else(|| inline.first())
+                {
+                    a.get_searchable_text_recursive(text);
+                }
+            }
+            ContentType::Multipart { ref parts, .. } => {
+                for a in parts {
+                    if a.content_disposition.kind.is_inline() {
+                        a.get_searchable_text_recursive(text);
+                    }
+                }
+            }
+            _ => {}
+        }
+    }
+
     pub fn mime_type(&self) -> String {
         self.content_type.to_string()
     }
@@ -750,7 +845,7 @@ impl Attachment {
         ret
     }
 
-    pub fn parameters(&self) -> Vec<(&[u8], &[u8])> {
+    pub fn parameters(&self) -> parser::attachments::Parameters<'_> {
         let mut ret = Vec::new();
         let (headers, _) = match parser::attachments::attachment(&self.raw) {
             Ok((_, v)) => v,
@@ -771,6 +866,25 @@ impl Attachment {
         ret
     }
 
+    /// Returns the value of the `Content-ID` header, without the enclosing angle brackets.
+    pub fn content_id(&self) -> Option<String> {
+        let (headers, _) = match parser::attachments::attachment(&self.raw) {
+            Ok((_, v)) => v,
+            Err(_) => return None,
+        };
+        headers
+            .into_iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(b"content-id"))
+            .map(|(_, value)| {
+                String::from_utf8_lossy(value)
+                    .trim()
+                    .trim_start_matches('<')
+                    .trim_end_matches('>')
+                    .to_string()
+            })
+            .filter(|id| !id.is_empty())
+    }
+
     pub fn filename(&self) -> Option<String> {
         if self.content_disposition.kind.is_attachment() {
             self.content_disposition.filename.clone()
@@ -914,3 +1028,13 @@ fn decode_helper<'a, 'b>(a: &'a Attachment, filter: &mut Option<Filter<'b>>) ->
 pub fn decode<'a, 'b>(a: &'a Attachment, mut filter: Option<Filter<'b>>) -> Vec<u8> {
     decode_helper(a, &mut filter)
 }
+
+#[test]
+fn test_searchable_text() {
+    let raw = b"Content-Type: multipart/alternative; boundary=\"bound\"\n\n--bound\nContent-Type: text/html; charset=utf-8\n\n<html><body><p>Hello <b>world</b></p></body></html>\n--bound--\n";
+    let body = AttachmentBuilder::new(raw).build();
+    assert_eq!(body.text(), "");
+    let text = body.searchable_text();
+    assert!(text.contains("Hello world"));
+    assert!(!text.contains("<b>"));
+}
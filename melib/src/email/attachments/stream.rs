@@ -0,0 +1,329 @@
+/*
+ * meli - melib crate.
+ *
+ * Copyright 2017-2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Find the MIME parts of a message without reading it into memory.
+ *
+ * [`parse_parts`] reads a message line by line, keeping only the headers of the current part and
+ * the start of the current line, and returns where each part is. Single parts can then be read
+ * with [`read_attachment`], so that eg. the text of a message with a huge attachment can be shown
+ * without loading the attachment.
+ */
+use super::*;
+use crate::error::Result;
+use std::io::{self, BufRead, Read, Seek, SeekFrom};
+
+/// Bytes kept from the start of body lines, enough for any boundary delimiter.
+const LINE_PREFIX: usize = 128;
+/// Header blocks larger than this are truncated.
+const MAX_HEADERS_SIZE: usize = 64 * 1024;
+
+/// Where a MIME part is in a message, see [`parse_parts`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PartOffsets {
+    /// 0 for the message itself, 1 for its parts and so on.
+    pub depth: usize,
+    pub headers_start: u64,
+    pub body_start: u64,
+    /// Offset one past the last byte of the body.
+    pub body_end: u64,
+    /// Lowercase, eg. `text/plain`.
+    pub mime_type: String,
+    pub content_transfer_encoding: ContentTransferEncoding,
+    /// File name from `Content-Disposition` or the `name` parameter of `Content-Type`.
+    pub name: Option<String>,
+}
+
+impl PartOffsets {
+    pub fn is_multipart(&self) -> bool {
+        self.mime_type.starts_with("multipart/")
+    }
+
+    pub fn body_len(&self) -> u64 {
+        self.body_end - self.body_start
+    }
+}
+
+/// Read a line into `buf`, keeping at most `limit` bytes of it. Returns the length of the whole
+/// line and of its line ending, `(0, 0)` at end of input.
+fn read_line_prefix<R: BufRead>(
+    reader: &mut R,
+    buf: &mut Vec<u8>,
+    limit: usize,
+) -> io::Result<(u64, u64)> {
+    buf.clear();
+    let mut total = 0;
+    /* Whether the last byte read is `\r`, in case `\r\n` is split between two reads. */
+    let mut last_cr = false;
+    loop {
+        let available = match reader.fill_buf() {
+            Ok(available) => available,
+            Err(err) if err.kind() == io::ErrorKind::Interrupted => continue,
+            Err(err) => return Err(err),
+        };
+        if available.is_empty() {
+            return Ok((total, 0));
+        }
+        let (newline, used) = match available.iter().position(|&b| b == b'\n') {
+            Some(i) => (
+                Some(
+                    if (i > 0 && available[i - 1] == b'\r') || (i == 0 && last_cr) {
+                        2
+                    } else {
+                        1
+                    },
+                ),
+                i + 1,
+            ),
+            None => (None, available.len()),
+        };
+        if buf.len() < limit {
+            let keep = std::cmp::min(used, limit - buf.len());
+            buf.extend_from_slice(&available[..keep]);
+        }
+        last_cr = available[used - 1] == b'\r';
+        reader.consume(used);
+        total += used as u64;
+        if let Some(newline) = newline {
+            return Ok((total, newline));
+        }
+    }
+}
+
+/// If `line` is a delimiter of `boundary`, whether it is the closing one.
+fn delimiter_kind(line: &[u8], boundary: &[u8]) -> Option<bool> {
+    let rest = line.strip_prefix(b"--")?.strip_prefix(boundary)?;
+    let (is_close, rest) = match rest.strip_prefix(b"--") {
+        Some(rest) => (true, rest),
+        None => (false, rest),
+    };
+    if rest.iter().all(u8::is_ascii_whitespace) {
+        Some(is_close)
+    } else {
+        None
+    }
+}
+
+/// The part with header block `headers`, and its boundary if it is a multipart.
+fn part_from_headers(
+    depth: usize,
+    headers_start: u64,
+    body_start: u64,
+    headers: &[u8],
+) -> (PartOffsets, Option<Vec<u8>>) {
+    let mut ret = PartOffsets {
+        depth,
+        headers_start,
+        body_start,
+        body_end: body_start,
+        mime_type: "text/plain".to_string(),
+        content_transfer_encoding: ContentTransferEncoding::default(),
+        name: None,
+    };
+    let mut boundary = None;
+    if let Ok((_, (headers, _))) = parser::attachments::attachment(headers) {
+        for (name, value) in headers {
+            if name.eq_ignore_ascii_case(b"content-type") {
+                if let Ok((_, (ct, cst, params))) = parser::attachments::content_type(value) {
+                    ret.mime_type = format!(
+                        "{}/{}",
+                        String::from_utf8_lossy(ct),
+                        String::from_utf8_lossy(cst)
+                    )
+                    .to_ascii_lowercase();
+                    for (n, v) in params {
+                        if n.eq_ignore_ascii_case(b"boundary")
+                            && ct.eq_ignore_ascii_case(b"multipart")
+                        {
+                            boundary = Some(v.to_vec());
+                        } else if n.eq_ignore_ascii_case(b"name") && ret.name.is_none() {
+                            ret.name = Some(String::from_utf8_lossy(&v).to_string());
+                        }
+                    }
+                }
+            } else if name.eq_ignore_ascii_case(b"content-transfer-encoding") {
+                ret.content_transfer_encoding = ContentTransferEncoding::from(value.trim());
+            } else if name.eq_ignore_ascii_case(b"content-disposition") {
+                if let Some(filename) = ContentDisposition::from(value).filename {
+                    ret.name = Some(filename);
+                }
+            }
+        }
+    }
+    (ret, boundary)
+}
+
+/// Find the parts of the message read from `reader`, in the order they appear. Offsets are
+/// counted from `start`, the position of the message in the file it is read from.
+pub fn parse_parts<R: BufRead>(mut reader: R, start: u64) -> Result<Vec<PartOffsets>> {
+    struct Frame {
+        boundary: Vec<u8>,
+        /// Index of the multipart this is the boundary of.
+        part: usize,
+    }
+    let mut parts: Vec<PartOffsets> = vec![];
+    /* Parts whose end is not known yet, outermost first. */
+    let mut open: Vec<usize> = vec![];
+    let mut frames: Vec<Frame> = vec![];
+    /* The depth, start and contents of the header block being read. */
+    let mut headers: Option<(usize, u64, Vec<u8>)> = Some((0, start, vec![]));
+    let mut line = Vec::with_capacity(LINE_PREFIX);
+    let mut offset = start;
+    /* The line ending before a delimiter belongs to the delimiter. */
+    let mut prev_newline = 0;
+    loop {
+        let limit = if headers.is_some() {
+            MAX_HEADERS_SIZE
+        } else {
+            LINE_PREFIX
+        };
+        let (len, newline) = read_line_prefix(&mut reader, &mut line, limit)?;
+        if len == 0 {
+            break;
+        }
+        let line_start = offset;
+        offset += len;
+        if let Some((depth, headers_start, mut buf)) = headers.take() {
+            let is_blank = len == newline;
+            if buf.len() + line.len() <= MAX_HEADERS_SIZE {
+                buf.extend_from_slice(&line);
+            }
+            if !is_blank {
+                headers = Some((depth, headers_start, buf));
+                continue;
+            }
+            let (part, boundary) = part_from_headers(depth, headers_start, offset, &buf);
+            let idx = parts.len();
+            if let Some(boundary) = boundary {
+                frames.push(Frame {
+                    boundary,
+                    part: idx,
+                });
+            } else if part.mime_type == "message/rfc822" {
+                headers = Some((depth + 1, offset, vec![]));
+            }
+            parts.push(part);
+            open.push(idx);
+            prev_newline = 0;
+            continue;
+        }
+        if line.starts_with(b"--") {
+            if let Some((pos, is_close)) = frames
+                .iter()
+                .enumerate()
+                .rev()
+                .find_map(|(i, f)| delimiter_kind(&line, &f.boundary).map(|c| (i, c)))
+            {
+                let owner = frames[pos].part;
+                let end = line_start - prev_newline;
+                while let Some(&top) = open.last() {
+                    if top == owner {
+                        break;
+                    }
+                    open.pop();
+                    parts[top].body_end = std::cmp::max(end, parts[top].body_start);
+                }
+                frames.truncate(pos + 1);
+                if is_close {
+                    frames.pop();
+                    open.pop();
+                    parts[owner].body_end = offset - newline;
+                } else {
+                    headers = Some((parts[owner].depth + 1, offset, vec![]));
+                }
+            }
+        }
+        prev_newline = newline;
+    }
+    if let Some((depth, headers_start, buf)) = headers {
+        if !buf.is_empty() {
+            /* Header block without a body. */
+            parts.push(part_from_headers(depth, headers_start, offset, &buf).0);
+        }
+    }
+    for idx in open {
+        parts[idx].body_end = offset;
+    }
+    Ok(parts)
+}
+
+/// Read `part` from `reader`, the file of the message given to [`parse_parts`].
+pub fn read_attachment<R: Read + Seek>(reader: &mut R, part: &PartOffsets) -> Result<Attachment> {
+    reader.seek(SeekFrom::Start(part.headers_start))?;
+    let len = part.body_end - part.headers_start;
+    let mut bytes = Vec::with_capacity(len as usize);
+    reader.by_ref().take(len).read_to_end(&mut bytes)?;
+    Ok(AttachmentBuilder::new(&bytes).build())
+}
+
+#[test]
+fn test_parse_parts() {
+    let message: &[u8] = b"From: a@example.com\r\n\
+Content-Type: multipart/mixed; boundary=\"outer\"\r\n\
+\r\n\
+preamble\r\n\
+--outer\r\n\
+Content-Type: multipart/alternative; boundary=inner\r\n\
+\r\n\
+--inner\r\n\
+Content-Type: text/plain\r\n\
+\r\n\
+Hello\r\n\
+--inner\r\n\
+Content-Type: text/html\r\n\
+\r\n\
+<p>Hello</p>\r\n\
+--inner--\r\n\
+--outer\r\n\
+Content-Type: application/octet-stream\r\n\
+Content-Transfer-Encoding: base64\r\n\
+Content-Disposition: attachment; filename=\"data.bin\"\r\n\
+\r\n\
+AAECAw==\r\n\
+--outer--\r\n\
+epilogue\r\n";
+    /* A tiny buffer splits lines and line endings between reads. */
+    let parts = parse_parts(io::BufReader::with_capacity(5, message), 0).unwrap();
+    assert_eq!(
+        parts
+            .iter()
+            .map(|p| (p.depth, p.mime_type.as_str()))
+            .collect::<Vec<_>>(),
+        vec![
+            (0, "multipart/mixed"),
+            (1, "multipart/alternative"),
+            (2, "text/plain"),
+            (2, "text/html"),
+            (1, "application/octet-stream"),
+        ]
+    );
+    let body = |p: &PartOffsets| &message[p.body_start as usize..p.body_end as usize];
+    assert_eq!(body(&parts[2]), b"Hello");
+    assert_eq!(body(&parts[3]), b"<p>Hello</p>");
+    assert_eq!(body(&parts[4]), b"AAECAw==");
+    assert!(body(&parts[1]).ends_with(b"--inner--"));
+    assert_eq!(parts[4].name.as_deref(), Some("data.bin"));
+    assert_eq!(
+        parts[4].content_transfer_encoding,
+        ContentTransferEncoding::Base64
+    );
+    let attachment = read_attachment(&mut io::Cursor::new(message), &parts[4]).unwrap();
+    assert_eq!(decode(&attachment, None), vec![0, 1, 2, 3]);
+}
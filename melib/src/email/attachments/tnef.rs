@@ -0,0 +1,407 @@
+/*
+ * meli - melib crate.
+ *
+ * Copyright 2017-2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Decode `application/ms-tnef` (`winmail.dat`) attachments sent by Outlook, see
+ * [MS-OXTNEF](https://docs.microsoft.com/en-us/openspecs/exchange_server_protocols/ms-oxtnef/).
+ *
+ * [`parts`] returns the attachments and bodies stored in a TNEF stream as ordinary MIME parts. The
+ * compressed RTF body is decompressed as described in
+ * [MS-OXRTFCP](https://docs.microsoft.com/en-us/openspecs/exchange_server_protocols/ms-oxrtfcp/).
+ */
+use super::*;
+
+const TNEF_SIGNATURE: u32 = 0x223E_9F78;
+
+/* Attribute ids, without their type. */
+const ATT_BODY: u16 = 0x800C;
+const ATT_ATTACH_DATA: u16 = 0x800F;
+const ATT_ATTACH_TITLE: u16 = 0x8010;
+const ATT_MAPI_PROPS: u16 = 0x9003;
+const ATT_ATTACH_REND_DATA: u16 = 0x9002;
+const ATT_ATTACHMENT: u16 = 0x9005;
+
+/* MAPI property ids. */
+const PR_BODY: u16 = 0x1000;
+const PR_RTF_COMPRESSED: u16 = 0x1009;
+const PR_BODY_HTML: u16 = 0x1013;
+const PR_ATTACH_DATA_OBJ: u16 = 0x3701;
+const PR_ATTACH_FILENAME: u16 = 0x3704;
+const PR_ATTACH_LONG_FILENAME: u16 = 0x3707;
+const PR_ATTACH_MIME_TAG: u16 = 0x370E;
+
+/* MAPI property types. */
+const PT_STRING8: u16 = 0x001E;
+const PT_UNICODE: u16 = 0x001F;
+const PT_OBJECT: u16 = 0x000D;
+const PT_BINARY: u16 = 0x0102;
+
+/// Whether `content_type` is a TNEF attachment.
+pub fn is_tnef(content_type: &ContentType) -> bool {
+    match content_type {
+        ContentType::Other { tag, .. } => {
+            tag.eq_ignore_ascii_case(b"application/ms-tnef")
+                || tag.eq_ignore_ascii_case(b"application/vnd.ms-tnef")
+        }
+        _ => false,
+    }
+}
+
+struct Reader<'a> {
+    bytes: &'a [u8],
+}
+
+impl<'a> Reader<'a> {
+    fn take(&mut self, n: usize) -> Option<&'a [u8]> {
+        if self.bytes.len() < n {
+            return None;
+        }
+        let (ret, rest) = self.bytes.split_at(n);
+        self.bytes = rest;
+        Some(ret)
+    }
+
+    fn u16(&mut self) -> Option<u16> {
+        self.take(2).map(|b| u16::from_le_bytes([b[0], b[1]]))
+    }
+
+    fn u32(&mut self) -> Option<u32> {
+        self.take(4)
+            .map(|b| u32::from_le_bytes([b[0], b[1], b[2], b[3]]))
+    }
+
+    /// A value padded to a multiple of 4 bytes.
+    fn padded(&mut self, n: usize) -> Option<&'a [u8]> {
+        let ret = self.take(n)?;
+        self.take((4 - n % 4) % 4)?;
+        Some(ret)
+    }
+}
+
+/// Read the MAPI properties in `bytes`, returning the id, type and first value of each. Stops at
+/// the first property whose type is unknown.
+fn mapi_properties(bytes: &[u8]) -> Vec<(u16, u16, &[u8])> {
+    let mut ret = vec![];
+    let mut reader = Reader { bytes };
+    let mut read = || -> Option<()> {
+        let count = reader.u32()?;
+        for _ in 0..count {
+            let ty = reader.u16()?;
+            let id = reader.u16()?;
+            if id >= 0x8000 {
+                /* Named property: GUID, then a number or a name. */
+                reader.take(16)?;
+                if reader.u32()? == 0 {
+                    reader.u32()?;
+                } else {
+                    let len = reader.u32()? as usize;
+                    reader.padded(len)?;
+                }
+            }
+            let base = ty & 0x0FFF;
+            let is_variable = [PT_STRING8, PT_UNICODE, PT_OBJECT, PT_BINARY].contains(&base);
+            let values = if is_variable || ty & 0x1000 != 0 {
+                reader.u32()?
+            } else {
+                1
+            };
+            let mut first = None;
+            for _ in 0..values {
+                let value = if is_variable {
+                    let len = reader.u32()? as usize;
+                    reader.padded(len)?
+                } else {
+                    let size = match base {
+                        0x0001 | 0x0002 | 0x0003 | 0x0004 | 0x000A | 0x000B => 4,
+                        0x0005 | 0x0006 | 0x0007 | 0x0014 | 0x0040 => 8,
+                        0x0048 => 16,
+                        _ => return None,
+                    };
+                    reader.take(size)?
+                };
+                first.get_or_insert(value);
+            }
+            ret.push((id, base, first.unwrap_or_default()));
+        }
+        Some(())
+    };
+    let _ = read();
+    ret
+}
+
+/// A string value, which is NUL terminated.
+fn string_value(ty: u16, value: &[u8]) -> String {
+    if ty == PT_UNICODE {
+        let units = value
+            .chunks_exact(2)
+            .map(|c| u16::from_le_bytes([c[0], c[1]]))
+            .take_while(|&u| u != 0)
+            .collect::<Vec<u16>>();
+        String::from_utf16_lossy(&units)
+    } else {
+        let end = value.iter().position(|&b| b == 0).unwrap_or(value.len());
+        String::from_utf8_lossy(&value[..end]).into_owned()
+    }
+}
+
+/// Decompress a `PR_RTF_COMPRESSED` value.
+pub fn decompress_rtf(bytes: &[u8]) -> Option<Vec<u8>> {
+    const PREBUF: &[u8] = b"{\\rtf1\\ansi\\mac\\deff0\\deftab720{\\fonttbl;}{\\f0\\fnil \\froman \\fswiss \\fmodern \\fscript \\fdecor MS Sans SerifSymbolArialTimes New RomanCourier{\\colortbl\\red0\\green0\\blue0\r\n\\par \\pard\\plain\\f0\\fs20\\b\\i\\u\\tab\\tx";
+    const COMPRESSED: u32 = 0x7546_5A4C;
+    const UNCOMPRESSED: u32 = 0x414C_454D;
+    let mut reader = Reader { bytes };
+    let _compressed_size = reader.u32()?;
+    let raw_size = reader.u32()? as usize;
+    let magic = reader.u32()?;
+    let _crc = reader.u32()?;
+    if magic == UNCOMPRESSED {
+        return reader
+            .take(std::cmp::min(raw_size, reader.bytes.len()))
+            .map(<[u8]>::to_vec);
+    } else if magic != COMPRESSED {
+        return None;
+    }
+    let mut dictionary = [0_u8; 4096];
+    dictionary[..PREBUF.len()].copy_from_slice(PREBUF);
+    let mut write_pos = PREBUF.len();
+    let mut ret = Vec::with_capacity(raw_size);
+    'blocks: while let Some(control) = reader.take(1) {
+        for bit in 0..8 {
+            if control[0] & (1 << bit) == 0 {
+                let byte = match reader.take(1) {
+                    Some(b) => b[0],
+                    None => break 'blocks,
+                };
+                ret.push(byte);
+                dictionary[write_pos] = byte;
+                write_pos = (write_pos + 1) % 4096;
+            } else {
+                let word = match reader.take(2) {
+                    Some(b) => u16::from_be_bytes([b[0], b[1]]) as usize,
+                    None => break 'blocks,
+                };
+                let offset = word >> 4;
+                if offset == write_pos {
+                    break 'blocks;
+                }
+                for i in 0..(word & 0xF) + 2 {
+                    let byte = dictionary[(offset + i) % 4096];
+                    ret.push(byte);
+                    dictionary[write_pos] = byte;
+                    write_pos = (write_pos + 1) % 4096;
+                }
+            }
+        }
+    }
+    Some(ret)
+}
+
+fn part(content_type: ContentType, filename: Option<String>, bytes: Vec<u8>) -> Attachment {
+    let mut builder = AttachmentBuilder::default();
+    builder
+        .set_raw(bytes)
+        .set_body_to_raw()
+        .set_content_type(content_type);
+    if filename.is_some() {
+        builder.set_content_disposition(ContentDisposition {
+            kind: ContentDispositionKind::Attachment,
+            filename,
+            ..ContentDisposition::default()
+        });
+    }
+    builder.build()
+}
+
+#[derive(Default)]
+struct TnefAttachment {
+    title: Option<String>,
+    long_filename: Option<String>,
+    mime_tag: Option<String>,
+    data: Option<Vec<u8>>,
+}
+
+impl TnefAttachment {
+    fn set_properties(&mut self, bytes: &[u8]) {
+        for (id, ty, value) in mapi_properties(bytes) {
+            match id {
+                PR_ATTACH_LONG_FILENAME => self.long_filename = Some(string_value(ty, value)),
+                PR_ATTACH_FILENAME if self.title.is_none() => {
+                    self.title = Some(string_value(ty, value))
+                }
+                PR_ATTACH_MIME_TAG => self.mime_tag = Some(string_value(ty, value)),
+                PR_ATTACH_DATA_OBJ if self.data.is_none() => {
+                    /* Objects start with the id of their interface. */
+                    let skip = if ty == PT_OBJECT { 16 } else { 0 };
+                    self.data = value.get(skip..).map(<[u8]>::to_vec);
+                }
+                _ => {}
+            }
+        }
+    }
+
+    fn into_part(self) -> Option<Attachment> {
+        let data = self.data?;
+        let name = self
+            .long_filename
+            .or(self.title)
+            .filter(|n| !n.is_empty())
+            .unwrap_or_else(|| "attachment".to_string());
+        let content_type = match self.mime_tag.filter(|t| t.contains('/')) {
+            Some(tag) => ContentType::Other {
+                tag: tag.into_bytes(),
+                name: Some(name.clone()),
+            },
+            None => ContentType::OctetStream {
+                name: Some(name.clone()),
+            },
+        };
+        Some(part(content_type, Some(name), data))
+    }
+}
+
+/// The bodies and attachments in the TNEF stream `bytes`, empty if it is not a TNEF stream.
+pub fn parts(bytes: &[u8]) -> Vec<Attachment> {
+    let mut reader = Reader { bytes };
+    if reader.u32() != Some(TNEF_SIGNATURE) || reader.u16().is_none() {
+        return vec![];
+    }
+    /* attBody is in the sender's code page, PR_BODY is converted to UTF-8. */
+    let mut text: Option<(Vec<u8>, Charset)> = None;
+    let mut html: Option<Vec<u8>> = None;
+    let mut rtf: Option<Vec<u8>> = None;
+    let mut attachments: Vec<TnefAttachment> = vec![];
+    while let (Some(_level), Some(id), Some(len)) = (
+        reader.take(1),
+        reader.u32().map(|id| id as u16),
+        reader.u32(),
+    ) {
+        let data = match reader.take(len as usize) {
+            Some(data) => data,
+            None => break,
+        };
+        /* Checksum */
+        if reader.u16().is_none() {
+            break;
+        }
+        match id {
+            ATT_BODY if text.is_none() => {
+                let end = data.iter().position(|&b| b == 0).unwrap_or(data.len());
+                text = Some((data[..end].to_vec(), Charset::Windows1252));
+            }
+            ATT_MAPI_PROPS => {
+                for (id, ty, value) in mapi_properties(data) {
+                    match id {
+                        PR_BODY => {
+                            text = Some((string_value(ty, value).into_bytes(), Charset::UTF8))
+                        }
+                        PR_BODY_HTML => html = Some(value.to_vec()),
+                        PR_RTF_COMPRESSED => rtf = decompress_rtf(value),
+                        _ => {}
+                    }
+                }
+            }
+            ATT_ATTACH_REND_DATA => attachments.push(TnefAttachment::default()),
+            ATT_ATTACH_TITLE => {
+                if let Some(a) = attachments.last_mut() {
+                    a.title = Some(string_value(PT_STRING8, data));
+                }
+            }
+            ATT_ATTACH_DATA => {
+                if let Some(a) = attachments.last_mut() {
+                    a.data = Some(data.to_vec());
+                }
+            }
+            ATT_ATTACHMENT => {
+                if let Some(a) = attachments.last_mut() {
+                    a.set_properties(data);
+                }
+            }
+            _ => {}
+        }
+    }
+    let mut ret = vec![];
+    if let Some((text, charset)) = text.filter(|(t, _)| t.iter().any(|b| !b.is_ascii_whitespace()))
+    {
+        let content_type = ContentType::Text {
+            kind: Text::Plain,
+            parameters: vec![],
+            charset,
+        };
+        ret.push(part(content_type, None, text));
+    }
+    if let Some(html) = html {
+        let content_type = ContentType::Text {
+            kind: Text::Html,
+            parameters: vec![],
+            charset: Charset::UTF8,
+        };
+        ret.push(part(content_type, Some("body.html".to_string()), html));
+    } else if let Some(rtf) = rtf {
+        let content_type = ContentType::Other {
+            tag: b"application/rtf".to_vec(),
+            name: Some("body.rtf".to_string()),
+        };
+        ret.push(part(content_type, Some("body.rtf".to_string()), rtf));
+    }
+    ret.extend(
+        attachments
+            .into_iter()
+            .filter_map(TnefAttachment::into_part),
+    );
+    ret
+}
+
+#[test]
+fn test_tnef_parts() {
+    fn attribute(ret: &mut Vec<u8>, level: u8, id: u32, data: &[u8]) {
+        ret.push(level);
+        ret.extend_from_slice(&id.to_le_bytes());
+        ret.extend_from_slice(&(data.len() as u32).to_le_bytes());
+        ret.extend_from_slice(data);
+        let checksum = data.iter().map(|&b| b as u32).sum::<u32>() as u16;
+        ret.extend_from_slice(&checksum.to_le_bytes());
+    }
+    let mut tnef = TNEF_SIGNATURE.to_le_bytes().to_vec();
+    tnef.extend_from_slice(&[0x01, 0x00]);
+    attribute(&mut tnef, 1, 0x0002_800C, b"Hello from Outlook\0");
+    attribute(&mut tnef, 2, 0x0006_9002, &[0; 14]);
+    attribute(&mut tnef, 2, 0x0001_8010, b"REPORT~1.TXT\0");
+    attribute(&mut tnef, 2, 0x0006_800F, b"quarterly numbers");
+    let mut props = 1_u32.to_le_bytes().to_vec();
+    props.extend_from_slice(&PT_STRING8.to_le_bytes());
+    props.extend_from_slice(&PR_ATTACH_LONG_FILENAME.to_le_bytes());
+    props.extend_from_slice(&1_u32.to_le_bytes());
+    props.extend_from_slice(&15_u32.to_le_bytes());
+    props.extend_from_slice(b"report 2020.txt\0");
+    attribute(&mut tnef, 2, 0x0006_9005, &props);
+
+    let parts = parts(&tnef);
+    assert_eq!(parts.len(), 2);
+    assert_eq!(parts[0].text(), "Hello from Outlook");
+    assert_eq!(parts[1].filename().as_deref(), Some("report 2020.txt"));
+    assert_eq!(parts[1].body(), b"quarterly numbers");
+
+    /* Example from MS-OXRTFCP section 4.1. */
+    let compressed = b"\x2d\x00\x00\x00\x2b\x00\x00\x00\x4c\x5a\x46\x75\xf1\xc5\xc7\xa7\x03\x00\x0a\x00\x72\x63\x70\x67\x31\x32\x35\x42\x32\x0a\xf3\x20\x68\x65\x6c\x09\x00\x20\x62\x77\x05\xb0\x6c\x64\x7d\x0a\x80\x0f\xa0";
+    assert_eq!(
+        decompress_rtf(compressed).unwrap(),
+        b"{\\rtf1\\ansi\\ansicpg1252\\pard hello world}\r\n".to_vec()
+    );
+}
@@ -0,0 +1,159 @@
+/*
+ * meli - melib crate.
+ *
+ * Copyright 2017-2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Files uuencoded in plain text bodies, between `begin MODE NAME` and `end` lines.
+ */
+use super::*;
+
+/// MIME type of a decoded file, guessed from its extension.
+fn mime_type(name: &str) -> &'static str {
+    let extension = name.rsplit('.').next().unwrap_or("").to_ascii_lowercase();
+    match extension.as_str() {
+        "txt" | "asc" => "text/plain",
+        "htm" | "html" => "text/html",
+        "diff" | "patch" => "text/x-diff",
+        "gif" => "image/gif",
+        "jpg" | "jpeg" => "image/jpeg",
+        "png" => "image/png",
+        "pdf" => "application/pdf",
+        "ps" => "application/postscript",
+        "zip" => "application/zip",
+        "gz" | "tgz" => "application/gzip",
+        "tar" => "application/x-tar",
+        _ => "application/octet-stream",
+    }
+}
+
+/// The name in a `begin MODE NAME` line.
+fn begin_line(line: &str) -> Option<&str> {
+    let rest = line.strip_prefix("begin ")?;
+    let mut split = rest.splitn(2, ' ');
+    let mode = split.next()?;
+    let name = split.next()?.trim();
+    if mode.len() < 3 || !mode.bytes().all(|b| (b'0'..=b'7').contains(&b)) || name.is_empty() {
+        return None;
+    }
+    Some(name)
+}
+
+/// Decode one line of uuencoded data into `ret`.
+fn decode_line(line: &[u8], ret: &mut Vec<u8>) -> Option<()> {
+    let value = |c: u8| c.wrapping_sub(b' ') & 0x3F;
+    let (&first, rest) = line.split_first()?;
+    let len = value(first) as usize;
+    let mut decoded = Vec::with_capacity(len + 2);
+    for group in rest.chunks(4) {
+        let mut chars = [b'`'; 4];
+        chars[..group.len()].copy_from_slice(group);
+        let [a, b, c, d] = [
+            value(chars[0]),
+            value(chars[1]),
+            value(chars[2]),
+            value(chars[3]),
+        ];
+        decoded.push(a << 2 | b >> 4);
+        decoded.push(b << 4 | c >> 2);
+        decoded.push(c << 6 | d);
+    }
+    if decoded.len() < len {
+        return None;
+    }
+    ret.extend_from_slice(&decoded[..len]);
+    Some(())
+}
+
+/// Split `text` into the text outside uuencoded blocks and the files in them. Returns `None` if
+/// there are no complete blocks.
+pub fn split(text: &str) -> Option<(String, Vec<Attachment>)> {
+    if !text.contains("begin ") {
+        return None;
+    }
+    let mut rest = String::with_capacity(text.len());
+    let mut files = vec![];
+    let mut lines = text.split_inclusive('\n');
+    while let Some(line) = lines.next() {
+        let name = match begin_line(line.trim_end()) {
+            Some(name) => name,
+            None => {
+                rest.push_str(line);
+                continue;
+            }
+        };
+        let mut data = vec![];
+        let mut block = line.to_string();
+        let mut complete = false;
+        for line in lines.by_ref() {
+            block.push_str(line);
+            let trimmed = line.trim_end_matches(|c| c == '\r' || c == '\n');
+            if trimmed == "end" {
+                complete = true;
+                break;
+            }
+            if decode_line(trimmed.as_bytes(), &mut data).is_none() {
+                break;
+            }
+        }
+        if !complete {
+            /* Not uuencoded after all. */
+            rest.push_str(&block);
+            continue;
+        }
+        let mut builder = AttachmentBuilder::default();
+        builder
+            .set_raw(data)
+            .set_body_to_raw()
+            .set_content_type_from_bytes(
+                format!("{}; name=\"{}\"", mime_type(name), name.replace('"', "")).as_bytes(),
+            )
+            .set_content_disposition(ContentDisposition {
+                kind: ContentDispositionKind::Attachment,
+                filename: Some(name.to_string()),
+                ..ContentDisposition::default()
+            });
+        files.push(builder.build());
+    }
+    if files.is_empty() {
+        None
+    } else {
+        Some((rest, files))
+    }
+}
+
+#[test]
+fn test_uuencode_split() {
+    let text = "See the attached file.\n\
+begin 644 cat.txt\n\
+#0V%T\n\
+`\n\
+end\n\
+Bye.\n\
+begin with a normal sentence\n";
+    let (rest, files) = split(text).unwrap();
+    assert_eq!(
+        rest,
+        "See the attached file.\nBye.\nbegin with a normal sentence\n"
+    );
+    assert_eq!(files.len(), 1);
+    assert_eq!(files[0].filename().as_deref(), Some("cat.txt"));
+    assert_eq!(files[0].body(), b"Cat");
+    assert!(files[0].content_type().is_text());
+    assert!(split("begin 644 broken\nnot uuencoded\n").is_none());
+}
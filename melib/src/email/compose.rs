@@ -33,9 +33,12 @@ use std::path::{Path, PathBuf};
 use std::str;
 use xdg_utils::query_mime_info;
 
+pub mod builder;
 pub mod mime;
 pub mod random;
 
+pub use self::builder::DraftBuilder;
+
 //use self::mime::*;
 
 use super::parser;
@@ -93,6 +96,11 @@ impl str::FromStr for Draft {
 }
 
 impl Draft {
+    /// Start building a draft, see [`DraftBuilder`].
+    pub fn builder() -> DraftBuilder {
+        DraftBuilder::default()
+    }
+
     pub fn edit(envelope: &Envelope, bytes: &[u8]) -> Result<Self> {
         let mut ret = Draft::default();
         for (k, v) in envelope.headers(&bytes).unwrap_or_else(|_| Vec::new()) {
@@ -230,9 +238,9 @@ impl Draft {
         Ok(ret)
     }
 
-    pub fn finalise(mut self) -> Result<String> {
-        let mut ret = String::new();
-
+    /// Generate a `Message-ID` from the domain of the `From` address if the draft doesn't have
+    /// one yet, and return it.
+    pub fn ensure_message_id(&mut self) -> Option<&str> {
         if self.headers.contains_key("From") && !self.headers.contains_key("Message-ID") {
             if let Ok((_, addr)) = super::parser::address::mailbox(self.headers["From"].as_bytes())
             {
@@ -244,6 +252,13 @@ impl Draft {
                 }
             }
         }
+        self.headers.get("Message-ID").map(String::as_str)
+    }
+
+    pub fn finalise(mut self) -> Result<String> {
+        let mut ret = String::new();
+
+        self.ensure_message_id();
         for (k, v) in self.headers.deref() {
             if v.is_ascii() {
                 ret.push_str(&format!("{}: {}\r\n", k, v));
@@ -378,17 +393,21 @@ fn print_attachment(ret: &mut String, a: AttachmentBuilder) {
             };
             if let Some(name) = a.content_type().name() {
                 ret.push_str(&format!(
-                    "Content-Type: {}; name=\"{}\"; charset=\"utf-8\"\r\n",
+                    "Content-Type: {}; {}; charset=\"utf-8\"\r\n",
                     a.content_type(),
-                    name
+                    mime::encode_parameter("name", name)
+                ));
+                ret.push_str(&format!(
+                    "Content-Disposition: attachment; {}\r\n",
+                    mime::encode_parameter("filename", name)
                 ));
             } else {
                 ret.push_str(&format!(
                     "Content-Type: {}; charset=\"utf-8\"\r\n",
                     a.content_type()
                 ));
+                ret.push_str("Content-Disposition: attachment\r\n");
             }
-            ret.push_str("Content-Disposition: attachment\r\n");
             ret.push_str(&format!(
                 "Content-Transfer-Encoding: {}\r\n",
                 content_transfer_encoding
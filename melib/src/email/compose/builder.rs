@@ -0,0 +1,239 @@
+/*
+ * meli - melib crate.
+ *
+ * Copyright 2017-2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Build a `Draft` without assembling its header map by hand.
+ *
+ * ```
+ * # use melib::email::{Address, Draft};
+ * let draft = Draft::builder()
+ *     .from(Address::new(Some("Ζωή".to_string()), "zoe@example.com".to_string()))
+ *     .to(Address::new(None, "bob@example.com".to_string()))
+ *     .subject("Minutes")
+ *     .header("X-Priority", "1")
+ *     .body("See attached.\n")
+ *     .build()
+ *     .unwrap();
+ * assert_eq!(&draft.headers()["To"], "bob@example.com");
+ * assert!(draft.headers()["Message-ID"].ends_with("@example.com>"));
+ * ```
+ */
+use super::*;
+use crate::datetime::UnixTimestamp;
+
+/// Fluent builder for [`Draft`], see [`Draft::builder`].
+///
+/// Address headers are encoded as they are added, `Date` defaults to the time of `build` and a
+/// `Message-ID` is generated from the domain of the first `From` address unless one is set.
+/// Attachments are assembled into a multipart message by [`Draft::finalise`].
+#[derive(Debug, Default, Clone)]
+pub struct DraftBuilder {
+    from: Vec<Address>,
+    to: Vec<Address>,
+    cc: Vec<Address>,
+    bcc: Vec<Address>,
+    reply_to: Vec<Address>,
+    subject: Option<String>,
+    date: Option<UnixTimestamp>,
+    message_id: Option<String>,
+    in_reply_to: Option<String>,
+    references: Vec<String>,
+    headers: Vec<(String, String)>,
+    body: String,
+    attachments: Vec<AttachmentBuilder>,
+}
+
+/// `address` as a header value. A display name with non-ASCII characters is encoded as an RFC
+/// 2047 encoded word and one with special characters is quoted.
+fn encode_address(address: &Address) -> String {
+    match (address, address.get_display_name()) {
+        (Address::Mailbox(_), Some(name)) => {
+            let name = if !name.is_ascii() {
+                mime::encode_header(&name)
+            } else if name.contains(|c: char| "()<>[]:;@\\,.\"".contains(c)) {
+                format!("\"{}\"", name.replace('\\', "\\\\").replace('"', "\\\""))
+            } else {
+                name
+            };
+            format!("{} <{}>", name, address.get_email())
+        }
+        (Address::Mailbox(_), None) => address.get_email(),
+        (Address::Group(_), _) => address.to_string(),
+    }
+}
+
+fn encode_address_list(list: &[Address]) -> String {
+    list.iter()
+        .map(encode_address)
+        .collect::<Vec<String>>()
+        .join(", ")
+}
+
+/// Message IDs are written in angle brackets.
+fn bracketed(message_id: String) -> String {
+    let message_id = message_id.trim();
+    if message_id.starts_with('<') {
+        message_id.to_string()
+    } else {
+        format!("<{}>", message_id)
+    }
+}
+
+impl DraftBuilder {
+    pub fn from(mut self, address: Address) -> Self {
+        self.from.push(address);
+        self
+    }
+
+    pub fn to(mut self, address: Address) -> Self {
+        self.to.push(address);
+        self
+    }
+
+    pub fn cc(mut self, address: Address) -> Self {
+        self.cc.push(address);
+        self
+    }
+
+    pub fn bcc(mut self, address: Address) -> Self {
+        self.bcc.push(address);
+        self
+    }
+
+    pub fn reply_to(mut self, address: Address) -> Self {
+        self.reply_to.push(address);
+        self
+    }
+
+    pub fn subject<S: Into<String>>(mut self, subject: S) -> Self {
+        self.subject = Some(subject.into());
+        self
+    }
+
+    pub fn date(mut self, date: UnixTimestamp) -> Self {
+        self.date = Some(date);
+        self
+    }
+
+    pub fn message_id<S: Into<String>>(mut self, message_id: S) -> Self {
+        self.message_id = Some(bracketed(message_id.into()));
+        self
+    }
+
+    /// Set `In-Reply-To`, and `References` too if no references are added.
+    pub fn in_reply_to<S: Into<String>>(mut self, message_id: S) -> Self {
+        self.in_reply_to = Some(bracketed(message_id.into()));
+        self
+    }
+
+    pub fn reference<S: Into<String>>(mut self, message_id: S) -> Self {
+        self.references.push(bracketed(message_id.into()));
+        self
+    }
+
+    /// Set any other header. Values with non-ASCII characters are encoded by
+    /// [`Draft::finalise`].
+    pub fn header<N: Into<String>, V: Into<String>>(mut self, name: N, value: V) -> Self {
+        self.headers.push((name.into(), value.into()));
+        self
+    }
+
+    pub fn body<S: Into<String>>(mut self, body: S) -> Self {
+        self.body = body.into();
+        self
+    }
+
+    pub fn attachment(mut self, attachment: AttachmentBuilder) -> Self {
+        self.attachments.push(attachment);
+        self
+    }
+
+    /// Fails if a header name set with [`DraftBuilder::header`] is not ASCII.
+    pub fn build(self) -> Result<Draft> {
+        let mut ret = Draft::default();
+        ret.set_header(
+            "Date",
+            crate::datetime::timestamp_to_string(
+                self.date.unwrap_or_else(crate::datetime::now),
+                None,
+                true,
+            ),
+        );
+        ret.set_header("From", encode_address_list(&self.from));
+        ret.set_header("To", encode_address_list(&self.to));
+        ret.set_header("Cc", encode_address_list(&self.cc));
+        ret.set_header("Bcc", encode_address_list(&self.bcc));
+        if !self.reply_to.is_empty() {
+            ret.set_header("Reply-To", encode_address_list(&self.reply_to));
+        }
+        ret.set_header("Subject", self.subject.unwrap_or_default());
+        let from = &self.from;
+        if let Some(message_id) = self.message_id.or_else(|| {
+            from.first()
+                .and_then(Address::get_fqdn)
+                .map(|fqdn| random::gen_message_id(&fqdn))
+        }) {
+            ret.set_header("Message-ID", message_id);
+        }
+        if let Some(in_reply_to) = self.in_reply_to {
+            if self.references.is_empty() {
+                ret.set_header("References", in_reply_to.clone());
+            }
+            ret.set_header("In-Reply-To", in_reply_to);
+        }
+        if !self.references.is_empty() {
+            ret.set_header("References", self.references.join(" "));
+        }
+        for (name, value) in self.headers {
+            ret.headers.insert(name.as_str().try_into()?, value);
+        }
+        ret.body = self.body;
+        ret.attachments = self.attachments;
+        Ok(ret)
+    }
+}
+
+#[test]
+fn test_draft_builder() {
+    let draft = DraftBuilder::default()
+        .from(Address::new(
+            Some("Doe, John".to_string()),
+            "john@example.com".to_string(),
+        ))
+        .to(Address::new(
+            Some("Ζωή".to_string()),
+            "zoe@example.com".to_string(),
+        ))
+        .to(Address::new(None, "bob@example.com".to_string()))
+        .subject("Re: Minutes")
+        .date(0)
+        .in_reply_to("1234@example.com")
+        .build()
+        .unwrap();
+    assert_eq!(&draft.headers()["From"], "\"Doe, John\" <john@example.com>");
+    assert_eq!(
+        &draft.headers()["To"],
+        "=?UTF-8?B?zpbPic6u?= <zoe@example.com>, bob@example.com"
+    );
+    assert_eq!(&draft.headers()["In-Reply-To"], "<1234@example.com>");
+    assert_eq!(&draft.headers()["References"], "<1234@example.com>");
+    assert!(draft.headers()["Message-ID"].ends_with("@example.com>"));
+    assert!(DraftBuilder::default().header("Χ", "").build().is_err());
+}
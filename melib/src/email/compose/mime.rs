@@ -151,6 +151,47 @@ pub fn encode_header(value: &str) -> String {
     }
     ret
 }
+/// Longest parameter section written by `encode_parameter`.
+const PARAMETER_SECTION_LEN: usize = 60;
+
+/// Write the MIME parameter `name` with `value`, quoted if it is short and ASCII, otherwise as an
+/// RFC 2231 extended value split into continuations of at most 60 characters.
+pub fn encode_parameter(name: &str, value: &str) -> String {
+    if value.is_ascii()
+        && !value
+            .chars()
+            .any(|c| c.is_ascii_control() || c == '"' || c == '\\')
+        && name.len() + value.len() + 3 <= PARAMETER_SECTION_LEN
+    {
+        return format!("{}=\"{}\"", name, value);
+    }
+    /* RFC 2231 attribute-char, minus `'` and `%` that delimit and escape. */
+    let is_attribute_char = |b: u8| b.is_ascii_alphanumeric() || b"!#$&+-.^_`|~".contains(&b);
+    let mut sections: Vec<String> = vec![];
+    let mut current = String::from("utf-8''");
+    for &b in value.as_bytes() {
+        let escaped = if is_attribute_char(b) {
+            (b as char).to_string()
+        } else {
+            format!("%{:02X}", b)
+        };
+        if current.len() + escaped.len() > PARAMETER_SECTION_LEN {
+            sections.push(std::mem::replace(&mut current, String::new()));
+        }
+        current.push_str(&escaped);
+    }
+    sections.push(current);
+    if sections.len() == 1 {
+        return format!("{}*={}", name, sections[0]);
+    }
+    sections
+        .iter()
+        .enumerate()
+        .map(|(i, section)| format!("{}*{}*={}", name, i, section))
+        .collect::<Vec<String>>()
+        .join(";\r\n ")
+}
+
 #[test]
 fn test_encode_header() {
     let words = "compilers/2020a σε Rust";
@@ -191,3 +232,25 @@ fn test_encode_header() {
         .unwrap(),
     );
 }
+
+#[test]
+fn test_encode_parameter() {
+    use crate::email::parser::attachments::content_disposition;
+    assert_eq!(encode_parameter("filename", "a.txt"), "filename=\"a.txt\"");
+    assert_eq!(
+        encode_parameter("filename", "σε.txt"),
+        "filename*=utf-8''%CF%83%CE%B5.txt"
+    );
+    let name = "Συμπεριφορά σε flush λόγω misprediction κατά την εκτέλεση store.pdf";
+    let encoded = encode_parameter("filename", name);
+    assert!(encoded.starts_with("filename*0*=utf-8''"));
+    let (_, disposition) =
+        content_disposition(format!("attachment; {}", encoded).as_bytes()).unwrap();
+    assert_eq!(disposition.filename.as_deref(), Some(name));
+    /* Continuations in a legacy charset, out of order, mixing extended and plain sections. */
+    let (_, disposition) = content_disposition(
+        b"attachment; filename*1=\" report.txt\"; filename*0*=iso-8859-1'en'%E9t%E9",
+    )
+    .unwrap();
+    assert_eq!(disposition.filename.as_deref(), Some("été report.txt"));
+}
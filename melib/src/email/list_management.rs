@@ -79,6 +79,23 @@ pub struct ListActions<'a> {
     pub archive: Option<&'a str>,
     pub post: Option<SmallVec<[ListAction<'a>; 4]>>,
     pub unsubscribe: Option<SmallVec<[ListAction<'a>; 4]>>,
+    /// Whether `List-Unsubscribe-Post` allows one-click unsubscription with a POST request,
+    /// [RFC 8058](https://tools.ietf.org/html/rfc8058).
+    pub unsubscribe_one_click: bool,
+}
+
+/// Body of the POST request of a one-click unsubscription.
+pub const ONE_CLICK_UNSUBSCRIBE_BODY: &str = "List-Unsubscribe=One-Click";
+
+/// How to unsubscribe from a mailing list, chosen by [`ListActions::unsubscribe_option`].
+#[derive(Debug, PartialEq, Clone)]
+pub enum UnsubscribeOption {
+    /// POST [`ONE_CLICK_UNSUBSCRIBE_BODY`] to this HTTPS URL.
+    OneClick(String),
+    /// Send the e-mail described by this `mailto:` URL.
+    Email(String),
+    /// Open this URL in a browser.
+    Url(String),
 }
 
 pub fn list_id_header(envelope: &'_ Envelope) -> Option<&'_ str> {
@@ -133,6 +150,11 @@ impl<'a> ListActions<'a> {
 
         if let Some(unsubscribe) = envelope.other_headers().get("List-Unsubscribe") {
             ret.unsubscribe = ListAction::parse_options_list(unsubscribe.as_bytes());
+            ret.unsubscribe_one_click = envelope
+                .other_headers()
+                .get("List-Unsubscribe-Post")
+                .map(|v| v.trim().eq_ignore_ascii_case(ONE_CLICK_UNSUBSCRIBE_BODY))
+                .unwrap_or(false);
         }
 
         if ret.id.is_none()
@@ -145,4 +167,79 @@ impl<'a> ListActions<'a> {
             Some(ret)
         }
     }
+
+    /// The preferred way to unsubscribe: a one-click POST request if the list supports it and
+    /// `one_click` is true, otherwise an e-mail, otherwise a URL.
+    pub fn unsubscribe_option(&self, one_click: bool) -> Option<UnsubscribeOption> {
+        let options = self.unsubscribe.as_ref()?;
+        let url = |action: &ListAction<'_>| match action {
+            ListAction::Url(url) => Some(String::from_utf8_lossy(url).to_string()),
+            _ => None,
+        };
+        if one_click && self.unsubscribe_one_click {
+            if let Some(url) = options
+                .iter()
+                .filter_map(url)
+                .find(|url| url.starts_with("https://"))
+            {
+                return Some(UnsubscribeOption::OneClick(url));
+            }
+        }
+        options
+            .iter()
+            .find_map(|action| match action {
+                ListAction::Email(email) => Some(UnsubscribeOption::Email(
+                    String::from_utf8_lossy(email).to_string(),
+                )),
+                _ => None,
+            })
+            .or_else(|| options.iter().find_map(url).map(UnsubscribeOption::Url))
+    }
+}
+
+/// Unsubscribe from a mailing list by sending a one-click POST request to `url`.
+#[cfg(feature = "http")]
+pub async fn one_click_unsubscribe(url: &str) -> crate::Result<()> {
+    use isahc::prelude::*;
+    let request = Request::post(url)
+        .header("Content-Type", "application/x-www-form-urlencoded")
+        .body(ONE_CLICK_UNSUBSCRIBE_BODY)
+        .map_err(|err| crate::error::MeliError::new(err.to_string()))?;
+    let response = HttpClient::new()?.send_async(request).await?;
+    if !response.status().is_success() {
+        return Err(crate::error::MeliError::new(format!(
+            "Unsubscription request to {} failed: {}",
+            url,
+            response.status()
+        ))
+        .set_kind(crate::error::ErrorKind::Network));
+    }
+    Ok(())
+}
+
+#[test]
+fn test_list_unsubscribe_option() {
+    let envelope = Envelope::from_bytes(
+        b"From: list@example.com\r\n\
+List-Unsubscribe: <https://example.com/unsub?id=1>, <mailto:unsub@example.com>\r\n\
+List-Unsubscribe-Post: List-Unsubscribe=One-Click\r\n\
+\r\n\
+Body\r\n",
+        None,
+    )
+    .unwrap();
+    let actions = ListActions::detect(&envelope).unwrap();
+    assert!(actions.unsubscribe_one_click);
+    assert_eq!(
+        actions.unsubscribe_option(true),
+        Some(UnsubscribeOption::OneClick(
+            "https://example.com/unsub?id=1".to_string()
+        ))
+    );
+    assert_eq!(
+        actions.unsubscribe_option(false),
+        Some(UnsubscribeOption::Email(
+            "mailto:unsub@example.com".to_string()
+        ))
+    );
 }
@@ -25,28 +25,56 @@ use std::convert::TryFrom;
 
 #[derive(Debug)]
 pub struct Mailto {
-    pub address: Address,
+    /// Recipients, from both the URI path and `to` fields.
+    pub address: Vec<Address>,
     pub subject: Option<String>,
     pub cc: Option<String>,
     pub bcc: Option<String>,
     pub body: Option<String>,
+    /// Other header fields, such as `in-reply-to`, with lowercase names.
+    pub headers: Vec<(String, String)>,
 }
 
-impl From<Mailto> for Draft {
-    fn from(val: Mailto) -> Self {
-        let mut ret = Draft::default();
+impl Mailto {
+    /// Set the recipients, headers and body of `draft` that are present in the URI, keeping the
+    /// rest.
+    pub fn apply_to(self, draft: &mut Draft) {
         let Mailto {
             address,
             subject,
             cc,
             bcc,
             body,
-        } = val;
-        ret.set_header("Subject", subject.unwrap_or_default());
-        ret.set_header("Cc", cc.unwrap_or_default());
-        ret.set_header("Bcc", bcc.unwrap_or_default());
-        ret.set_body(body.unwrap_or_default());
-        ret.set_header("To", address.to_string());
+            headers,
+        } = self;
+        if !address.is_empty() {
+            draft.set_header(
+                "To",
+                address
+                    .iter()
+                    .map(|a| a.to_string())
+                    .collect::<Vec<String>>()
+                    .join(", "),
+            );
+        }
+        for (header, value) in [("Subject", subject), ("Cc", cc), ("Bcc", bcc)].iter() {
+            if let Some(value) = value {
+                draft.set_header(header, value.clone());
+            }
+        }
+        for (header, value) in headers {
+            draft.set_header(&header, value);
+        }
+        if let Some(body) = body {
+            draft.set_body(body);
+        }
+    }
+}
+
+impl From<Mailto> for Draft {
+    fn from(val: Mailto) -> Self {
+        let mut ret = Draft::default();
+        val.apply_to(&mut ret);
         debug!(ret)
     }
 }
@@ -86,6 +114,7 @@ mod tests {
             ref cc,
             ref bcc,
             ref body,
+            ..
         } = mailto;
 
         assert_eq!(
@@ -96,7 +125,13 @@ mod tests {
                 bcc.as_ref().map(String::as_str),
                 body.as_ref().map(String::as_str),
             ),
-            (&test_address, Some("email%20subject"), None, None, None)
+            (
+                &vec![test_address.clone()],
+                Some("email subject"),
+                None,
+                None,
+                None
+            )
         );
         let mailto = Mailto::try_from(&b"mailto:info@example.com?cc=8cc9@example.com"[0..])
             .expect("Could not parse mailto link.");
@@ -106,6 +141,7 @@ mod tests {
             ref cc,
             ref bcc,
             ref body,
+            ..
         } = mailto;
         assert_eq!(
             (
@@ -115,7 +151,13 @@ mod tests {
                 bcc.as_ref().map(String::as_str),
                 body.as_ref().map(String::as_str),
             ),
-            (&test_address, None, Some("8cc9@example.com"), None, None)
+            (
+                &vec![test_address.clone()],
+                None,
+                Some("8cc9@example.com"),
+                None,
+                None
+            )
         );
         let mailto = Mailto::try_from(
             &b"mailto:info@example.com?bcc=7bcc8@example.com&body=line%20first%0Abut%20not%0Alast"
@@ -128,6 +170,7 @@ mod tests {
             ref cc,
             ref bcc,
             ref body,
+            ..
         } = mailto;
         assert_eq!(
             (
@@ -138,12 +181,26 @@ mod tests {
                 body.as_ref().map(String::as_str),
             ),
             (
-                &test_address,
+                &vec![test_address.clone()],
                 None,
                 None,
                 Some("7bcc8@example.com"),
                 Some("line first\nbut not\nlast")
             )
         );
+        let mailto = Mailto::try_from(
+            &b"MAILTO:info@example.com,a%40example.com?to=b@example.com&cc=c@example.com&cc=d@example.com&In-Reply-To=%3C1@example.com%3E&body=a%0D%0Ab"[0..],
+        )
+        .expect("Could not parse mailto link.");
+        assert_eq!(mailto.address.len(), 3);
+        assert_eq!(
+            mailto.cc.as_ref().map(String::as_str),
+            Some("c@example.com, d@example.com")
+        );
+        assert_eq!(
+            mailto.headers,
+            vec![("in-reply-to".to_string(), "<1@example.com>".to_string())]
+        );
+        assert_eq!(mailto.body.as_ref().map(String::as_str), Some("a\nb"));
     }
 }
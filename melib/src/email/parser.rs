@@ -804,84 +804,135 @@ pub mod generic {
 
     use crate::email::address::Address;
     use crate::email::mailto::Mailto;
+
+    /// Decode `%XX` escapes in a URI component.
+    fn percent_decode(input: &[u8]) -> String {
+        String::from_utf8_lossy(&percent_decode_bytes(input)).into_owned()
+    }
+
+    /// Decode `%XX` escapes, leaving invalid ones as they are.
+    pub fn percent_decode_bytes(input: &[u8]) -> Vec<u8> {
+        let mut ret = Vec::with_capacity(input.len());
+        let mut i = 0;
+        while i < input.len() {
+            if input[i] == b'%'
+                && i + 2 < input.len()
+                && input[i + 1].is_ascii_hexdigit()
+                && input[i + 2].is_ascii_hexdigit()
+            {
+                let hex = |b: u8| (b as char).to_digit(16).unwrap() as u8;
+                ret.push(hex(input[i + 1]) * 16 + hex(input[i + 2]));
+                i += 3;
+                continue;
+            }
+            ret.push(input[i]);
+            i += 1;
+        }
+        ret
+    }
+
+    /// Parse a comma separated list of addresses of a `mailto` URI.
+    fn mailto_addresses(value: &str) -> std::result::Result<Vec<Address>, ()> {
+        value
+            .split(',')
+            .map(str::trim)
+            .filter(|a| !a.is_empty())
+            .map(|a| {
+                crate::email::parser::address::address(a.as_bytes())
+                    .map(|(_, addr)| addr)
+                    .map_err(|_| ())
+            })
+            .collect()
+    }
+
+    /// Parse a `mailto` URI as described in RFC 6068. Recipients may be given both in the path and
+    /// in `to` fields, and repeated `to`, `cc` and `bcc` fields are combined.
     pub fn mailto(mut input: &[u8]) -> IResult<&[u8], Mailto> {
-        if !input.starts_with(b"mailto:") {
+        if input.len() < b"mailto:".len()
+            || !input[..b"mailto:".len()].eq_ignore_ascii_case(b"mailto:")
+        {
             return Err(nom::Err::Error(
                 (input, "mailto(): input doesn't start with `mailto:`").into(),
             ));
         }
 
         input = &input[b"mailto:".len()..];
+        /* Fragments have no meaning in mailto URIs. */
+        if let Some(pos) = input.iter().position(|e| *e == b'#') {
+            input = &input[..pos];
+        }
 
         let end = input.iter().position(|e| *e == b'?').unwrap_or(input.len());
-        let address: Address;
-
-        if let Ok((_, addr)) = crate::email::parser::address::address(&input[..end]) {
-            address = addr;
-            input = if input[end..].is_empty() {
-                &input[end..]
-            } else {
-                &input[end + 1..]
-            };
+        let mut address = mailto_addresses(&percent_decode(&input[..end]))
+            .map_err(|_| nom::Err::Error((input, "mailto(): address not found in input").into()))?;
+        input = if input[end..].is_empty() {
+            &input[end..]
         } else {
-            return Err(nom::Err::Error(
-                (input, "mailto(): address not found in input").into(),
-            ));
-        }
+            &input[end + 1..]
+        };
 
         let mut subject = None;
-        let mut cc = None;
-        let mut bcc = None;
+        let mut cc: Option<String> = None;
+        let mut bcc: Option<String> = None;
         let mut body = None;
-        while !input.is_empty() {
-            let tag = if let Some(tag_pos) = input.iter().position(|e| *e == b'=') {
-                let ret = &input[0..tag_pos];
-                input = &input[tag_pos + 1..];
-                ret
+        let mut headers = vec![];
+        for field in input.split(|e| *e == b'&').filter(|f| !f.is_empty()) {
+            let tag_pos = if let Some(tag_pos) = field.iter().position(|e| *e == b'=') {
+                tag_pos
             } else {
                 return Err(nom::Err::Error(
-                    (input, "mailto(): extra characters found in input").into(),
+                    (field, "mailto(): extra characters found in input").into(),
                 ));
             };
-
-            let value_end = input.iter().position(|e| *e == b'&').unwrap_or(input.len());
-
-            let value = String::from_utf8_lossy(&input[..value_end]).to_string();
-            match tag {
-                b"subject" if subject.is_none() => {
-                    subject = Some(value);
+            let tag = percent_decode(&field[..tag_pos]).to_ascii_lowercase();
+            let value = percent_decode(&field[tag_pos + 1..]);
+            match tag.as_str() {
+                "to" => {
+                    address.extend(mailto_addresses(&value).map_err(|_| {
+                        nom::Err::Error((field, "mailto(): invalid address in `to` field").into())
+                    })?);
                 }
-                b"cc" if cc.is_none() => {
-                    cc = Some(value);
+                "cc" | "bcc" => {
+                    let list = if tag == "cc" { &mut cc } else { &mut bcc };
+                    match list {
+                        Some(ref mut l) if !value.is_empty() => {
+                            l.push_str(", ");
+                            l.push_str(&value);
+                        }
+                        Some(_) => {}
+                        None => *list = Some(value),
+                    }
                 }
-                b"bcc" if bcc.is_none() => {
-                    bcc = Some(value);
+                "subject" if subject.is_none() => {
+                    subject = Some(value);
                 }
-                b"body" if body.is_none() => {
-                    /* FIXME:
-                     * Parse escaped characters properly.
-                     */
-                    body = Some(value.replace("%20", " ").replace("%0A", "\n"));
-                }
-                _ => {
-                    return Err(nom::Err::Error(
-                        (input, "mailto(): unknown tag in input").into(),
-                    ));
+                "body" if body.is_none() => {
+                    body = Some(value.replace("\r\n", "\n"));
                 }
+                "subject" | "body" => {}
+                _ => headers.push((tag, value)),
             }
-            if input[value_end..].is_empty() {
-                break;
-            }
-            input = &input[value_end + 1..];
+        }
+        if address.is_empty()
+            && headers.is_empty()
+            && subject.is_none()
+            && body.is_none()
+            && cc.is_none()
+            && bcc.is_none()
+        {
+            return Err(nom::Err::Error(
+                (input, "mailto(): address not found in input").into(),
+            ));
         }
         Ok((
-            input,
+            &[],
             Mailto {
                 address,
                 subject,
                 cc,
                 bcc,
                 body,
+                headers,
             },
         ))
     }
@@ -1472,7 +1523,7 @@ pub mod attachments {
     /*! Email attachments */
     use super::*;
     use crate::email::address::*;
-    use crate::email::attachment_types::{ContentDisposition, ContentDispositionKind};
+    use crate::email::attachment_types::{Charset, ContentDisposition, ContentDispositionKind};
     pub fn attachment(input: &[u8]) -> IResult<&[u8], (std::vec::Vec<(&[u8], &[u8])>, &[u8])> {
         alt((
             separated_pair(
@@ -1664,12 +1715,105 @@ pub mod attachments {
         Ok((input, (name, value)))
     }
 
-    pub fn content_type(input: &[u8]) -> IResult<&[u8], (&[u8], &[u8], Vec<(&[u8], &[u8])>)> {
+    /// Parameters of `content_type` and `content_disposition`, with RFC 2231 continuations
+    /// joined and extended values decoded.
+    pub type Parameters<'a> = Vec<(Cow<'a, [u8]>, Cow<'a, [u8]>)>;
+
+    /// Decode an RFC 2231 extended value, `charset'language'percent-encoded`, into UTF-8.
+    fn rfc2231_decode(value: &[u8]) -> Vec<u8> {
+        let mut fields = value.splitn(3, |&b| b == b'\'');
+        let (charset, data) = match (fields.next(), fields.next(), fields.next()) {
+            (Some(charset), Some(_language), Some(data)) => (charset, data),
+            _ => (&b""[..], value),
+        };
+        let bytes = super::generic::percent_decode_bytes(data);
+        if charset.is_empty() || charset.eq_ignore_ascii_case(b"utf-8") {
+            return bytes;
+        }
+        super::encodings::decode_charset(&bytes, Charset::from(charset))
+            .map(String::into_bytes)
+            .unwrap_or(bytes)
+    }
+
+    /// Join RFC 2231 parameter continuations (`name*0=`, `name*1*=`, ...) and decode extended
+    /// values (`name*=utf-8''%CE%B1`) into UTF-8. Other parameters are returned as they are.
+    pub fn rfc2231_parameters<'a>(parameters: Vec<(&'a [u8], &'a [u8])>) -> Parameters<'a> {
+        let mut ret: Parameters<'a> = Vec::with_capacity(parameters.len());
+        /* The sections of each continued parameter: index, whether it is extended and value. */
+        let mut continued: Vec<(&'a [u8], Vec<(usize, bool, &'a [u8])>)> = vec![];
+        for (name, value) in parameters {
+            let name = name.trim();
+            let star = match name.iter().position(|&b| b == b'*') {
+                Some(star) => star,
+                None => {
+                    ret.push((Cow::Borrowed(name), Cow::Borrowed(value)));
+                    continue;
+                }
+            };
+            let (base, rest) = (&name[..star], &name[star + 1..]);
+            if rest.is_empty() {
+                ret.push((Cow::Borrowed(base), Cow::Owned(rfc2231_decode(value))));
+                continue;
+            }
+            let (index, extended) = match rest.strip_suffix(b"*") {
+                Some(index) => (index, true),
+                None => (rest, false),
+            };
+            match str::from_utf8(index)
+                .ok()
+                .and_then(|i| i.parse::<usize>().ok())
+            {
+                Some(index) => {
+                    if let Some((_, sections)) = continued
+                        .iter_mut()
+                        .find(|(n, _)| n.eq_ignore_ascii_case(base))
+                    {
+                        sections.push((index, extended, value));
+                    } else {
+                        continued.push((base, vec![(index, extended, value)]));
+                    }
+                }
+                None => ret.push((Cow::Borrowed(name), Cow::Borrowed(value))),
+            }
+        }
+        for (name, mut sections) in continued {
+            sections.sort_by_key(|(index, _, _)| *index);
+            /* Only the first section carries the charset. */
+            let mut charset: &[u8] = b"";
+            let mut bytes = vec![];
+            for (index, extended, value) in sections {
+                if !extended {
+                    bytes.extend_from_slice(value);
+                    continue;
+                }
+                let mut value = value;
+                if index == 0 {
+                    let mut fields = value.splitn(3, |&b| b == b'\'');
+                    if let (Some(c), Some(_language), Some(data)) =
+                        (fields.next(), fields.next(), fields.next())
+                    {
+                        charset = c;
+                        value = data;
+                    }
+                }
+                bytes.extend(super::generic::percent_decode_bytes(value));
+            }
+            if !charset.is_empty() && !charset.eq_ignore_ascii_case(b"utf-8") {
+                if let Ok(s) = super::encodings::decode_charset(&bytes, Charset::from(charset)) {
+                    bytes = s.into_bytes();
+                }
+            }
+            ret.push((Cow::Borrowed(name), Cow::Owned(bytes)));
+        }
+        ret
+    }
+
+    pub fn content_type(input: &[u8]) -> IResult<&[u8], (&[u8], &[u8], Parameters<'_>)> {
         let (input, _type) = take_until("/")(input.ltrim())?;
         let (input, _) = tag("/")(input)?;
         let (input, _subtype) = is_not(";")(input)?;
         let (input, parameters) = many0(content_type_parameter)(input)?;
-        Ok((input, (_type, _subtype, parameters)))
+        Ok((input, (_type, _subtype, rfc2231_parameters(parameters))))
         /*
            do_parse!(
                _type: take_until!("/") >>
@@ -1710,25 +1854,25 @@ pub mod attachments {
             return Ok((input, ret));
         }
         let (input, parameters) = many0(content_disposition_parameter)(input.ltrim())?;
-        for (k, v) in parameters {
+        /* Values may also contain RFC 2047 encoded words. */
+        let phrase = |v: &[u8]| -> String {
+            super::encodings::phrase(v, false)
+                .map(|(_, v)| String::from_utf8_lossy(&v).into())
+                .unwrap_or_else(|_| String::from_utf8_lossy(v).into())
+        };
+        for (k, v) in rfc2231_parameters(parameters) {
             if k.eq_ignore_ascii_case(b"filename") {
-                ret.filename =
-                    Some(String::from_utf8_lossy(&super::encodings::phrase(v, false)?.1).into());
+                ret.filename = Some(phrase(&v));
             } else if k.eq_ignore_ascii_case(b"size") {
-                ret.size =
-                    Some(String::from_utf8_lossy(&super::encodings::phrase(v, false)?.1).into());
+                ret.size = Some(phrase(&v));
             } else if k.eq_ignore_ascii_case(b"creation-date") {
-                ret.creation_date =
-                    Some(String::from_utf8_lossy(&super::encodings::phrase(v, false)?.1).into());
+                ret.creation_date = Some(phrase(&v));
             } else if k.eq_ignore_ascii_case(b"modification-date") {
-                ret.modification_date =
-                    Some(String::from_utf8_lossy(&super::encodings::phrase(v, false)?.1).into());
+                ret.modification_date = Some(phrase(&v));
             } else if k.eq_ignore_ascii_case(b"read-date") {
-                ret.read_date =
-                    Some(String::from_utf8_lossy(&super::encodings::phrase(v, false)?.1).into());
+                ret.read_date = Some(phrase(&v));
             } else {
-                ret.parameter
-                    .push(String::from_utf8_lossy(&super::encodings::phrase(v, false)?.1).into());
+                ret.parameter.push(phrase(&v));
             }
         }
         Ok((input, ret))
@@ -1740,8 +1884,6 @@ pub mod encodings {
     use super::*;
     use crate::email::attachment_types::Charset;
     use data_encoding::BASE64_MIME;
-    use encoding::all::*;
-    use encoding::{DecoderTrap, Encoding};
     pub fn quoted_printable_byte(input: &[u8]) -> IResult<&[u8], u8> {
         if input.len() < 3 {
             Err(nom::Err::Error(
@@ -1872,41 +2014,29 @@ pub mod encodings {
         }
     }
 
+    /// Decode `s` from `charset`. Text that is malformed in `charset` but valid UTF-8 is
+    /// decoded as UTF-8, since mislabeled UTF-8 is common, otherwise malformed sequences are
+    /// replaced. Text labeled as ASCII with 8-bit bytes is decoded as UTF-8 if valid, or
+    /// windows-1252.
     pub fn decode_charset(s: &[u8], charset: Charset) -> Result<String> {
-        match charset {
-            Charset::UTF8 | Charset::Ascii => Ok(String::from_utf8_lossy(s).to_string()),
-            Charset::ISO8859_1 => Ok(ISO_8859_1.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_2 => Ok(ISO_8859_2.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_3 => Ok(ISO_8859_3.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_4 => Ok(ISO_8859_4.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_5 => Ok(ISO_8859_5.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_6 => Ok(ISO_8859_6.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_7 => Ok(ISO_8859_7.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_8 => Ok(ISO_8859_8.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_10 => Ok(ISO_8859_10.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_13 => Ok(ISO_8859_13.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_14 => Ok(ISO_8859_14.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_15 => Ok(ISO_8859_15.decode(s, DecoderTrap::Strict)?),
-            Charset::ISO8859_16 => Ok(ISO_8859_16.decode(s, DecoderTrap::Strict)?),
-            Charset::GBK => Ok(GBK.decode(s, DecoderTrap::Strict)?),
-            Charset::Windows1250 => Ok(WINDOWS_1250.decode(s, DecoderTrap::Strict)?),
-            Charset::Windows1251 => Ok(WINDOWS_1251.decode(s, DecoderTrap::Strict)?),
-            Charset::Windows1252 => Ok(WINDOWS_1252.decode(s, DecoderTrap::Strict)?),
-            Charset::Windows1253 => Ok(WINDOWS_1253.decode(s, DecoderTrap::Strict)?),
-            Charset::KOI8R => Ok(KOI8_R.decode(s, DecoderTrap::Strict)?),
-            Charset::KOI8U => Ok(KOI8_U.decode(s, DecoderTrap::Strict)?),
-            Charset::BIG5 => Ok(BIG5_2003.decode(s, DecoderTrap::Strict)?),
-            Charset::GB2312 => {
-                Ok(encoding::codec::simpchinese::GBK_ENCODING.decode(s, DecoderTrap::Strict)?)
-            }
-            Charset::GB18030 => Ok(
-                encoding::codec::simpchinese::GB18030_ENCODING.decode(s, DecoderTrap::Strict)?
-            ),
-            Charset::UTF16 => {
-                Ok(encoding::codec::utf_16::UTF_16LE_ENCODING.decode(s, DecoderTrap::Strict)?)
+        let encoding = match charset {
+            Charset::Ascii => encoding_rs::WINDOWS_1252,
+            Charset::UTF16 if s.starts_with(&[0xFE, 0xFF]) => encoding_rs::UTF_16BE,
+            _ => charset.encoding(),
+        };
+        let utf8 = std::str::from_utf8(s);
+        if let Ok(s) = utf8 {
+            if charset == Charset::Ascii
+                || charset == Charset::UTF8
+                || (s.is_ascii() && encoding.is_ascii_compatible())
+            {
+                return Ok(s.to_string());
             }
-            Charset::ISO2022JP => Ok(ISO_2022_JP.decode(s, DecoderTrap::Strict)?),
-            Charset::EUCJP => Ok(EUC_JP.decode(s, DecoderTrap::Strict)?),
+        }
+        let (ret, had_errors) = encoding.decode_with_bom_removal(s);
+        match utf8 {
+            Ok(s) if had_errors => Ok(s.to_string()),
+            _ => Ok(ret.into_owned()),
         }
     }
 
@@ -2563,6 +2693,35 @@ mod tests {
         );
     }
 
+    #[test]
+    fn test_decode_charset() {
+        use crate::email::attachment_types::Charset;
+        assert_eq!(
+            std::str::from_utf8(
+                &phrase(b"=?ISO-2022-JP?B?GyRCJUYlOSVIGyhC?=", false)
+                    .unwrap()
+                    .1
+            )
+            .unwrap(),
+            "テスト"
+        );
+        assert_eq!(Charset::from(&b"x-sjis"[..]), Charset::ShiftJIS);
+        assert_eq!(Charset::from(&b"windows1251"[..]), Charset::Windows1251);
+        assert_eq!(
+            decode_charset(b"\x83e\x83X\x83g", Charset::from(&b"Shift_JIS"[..])).unwrap(),
+            "テスト"
+        );
+        assert_eq!(
+            decode_charset(b"\xf0\xd2\xc9\xd7\xc5\xd4", Charset::KOI8R).unwrap(),
+            "Привет"
+        );
+        /* Mislabeled UTF-8. */
+        assert_eq!(
+            decode_charset("Καλημέρα".as_bytes(), Charset::ISO2022JP).unwrap(),
+            "Καλημέρα"
+        );
+    }
+
     #[test]
     fn test_address_list() {
         let s = b"Obit Oppidum <user@domain>,
@@ -0,0 +1,252 @@
+/*
+ * meli - melib crate.
+ *
+ * Copyright 2017-2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Messages split into `message/partial` parts, [RFC 2046 section 5.2.2](https://tools.ietf.org/html/rfc2046#section-5.2.2).
+ *
+ * [`reassemble`] joins the parts of a message back together and [`split`] splits a message that
+ * is too large to be sent in one piece.
+ */
+use super::parser;
+use crate::error::{MeliError, Result};
+
+/// The `Content-Type` parameters of a `message/partial` message.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct PartialInfo {
+    /// Shared by all parts of the same message.
+    pub id: String,
+    /// Position of this part, starting from 1.
+    pub number: usize,
+    /// Number of parts, required only in the last one.
+    pub total: Option<usize>,
+}
+
+impl PartialInfo {
+    /// The parameters of `message`, if it is a `message/partial` message.
+    pub fn from_bytes(message: &[u8]) -> Option<PartialInfo> {
+        let (headers, _) = parser::mail(message).ok()?;
+        Self::from_headers(&headers)
+    }
+
+    fn from_headers(headers: &[(&[u8], &[u8])]) -> Option<PartialInfo> {
+        let (_, value) = headers
+            .iter()
+            .find(|(name, _)| name.eq_ignore_ascii_case(b"content-type"))?;
+        let (_, (ct, cst, params)) = parser::attachments::content_type(value).ok()?;
+        if !ct.eq_ignore_ascii_case(b"message") || !cst.eq_ignore_ascii_case(b"partial") {
+            return None;
+        }
+        let param = |name: &[u8]| {
+            params
+                .iter()
+                .find(|(n, _)| n.eq_ignore_ascii_case(name))
+                .map(|(_, v)| String::from_utf8_lossy(v).trim().to_string())
+        };
+        Some(PartialInfo {
+            id: param(b"id")?,
+            number: param(b"number")?.parse().ok()?,
+            total: param(b"total").and_then(|t| t.parse().ok()),
+        })
+    }
+}
+
+/// Header fields of the enclosed message that replace those of the enclosing one.
+fn is_enclosed_header(name: &[u8]) -> bool {
+    name.len() >= 8 && name[..8].eq_ignore_ascii_case(b"content-")
+        || name.eq_ignore_ascii_case(b"subject")
+        || name.eq_ignore_ascii_case(b"message-id")
+        || name.eq_ignore_ascii_case(b"encrypted")
+        || name.eq_ignore_ascii_case(b"mime-version")
+}
+
+fn push_header(ret: &mut Vec<u8>, name: &[u8], value: &[u8]) {
+    ret.extend_from_slice(name);
+    ret.extend_from_slice(b": ");
+    ret.extend_from_slice(value);
+    ret.extend_from_slice(b"\r\n");
+}
+
+/// Join the parts of a `message/partial` message, given in any order. Fails if a part is not a
+/// `message/partial` message, the parts have different ids or some are missing.
+pub fn reassemble<'a, I: IntoIterator<Item = &'a [u8]>>(parts: I) -> Result<Vec<u8>> {
+    let mut parts = parts
+        .into_iter()
+        .map(|bytes| {
+            let (headers, body) = parser::mail(bytes)?;
+            let info = PartialInfo::from_headers(&headers)
+                .ok_or_else(|| MeliError::new("Message is not a message/partial message."))?;
+            Ok((info, headers, body))
+        })
+        .collect::<Result<Vec<_>>>()?;
+    parts.sort_by_key(|(info, _, _)| info.number);
+    parts.dedup_by_key(|(info, _, _)| info.number);
+    let id = match parts.first() {
+        Some((info, _, _)) => info.id.clone(),
+        None => return Err(MeliError::new("No message parts given.")),
+    };
+    if parts.iter().any(|(info, _, _)| info.id != id) {
+        return Err(MeliError::new("Message parts have different ids."));
+    }
+    let total = parts
+        .iter()
+        .find_map(|(info, _, _)| info.total)
+        .ok_or_else(|| MeliError::new("The last message part is missing."))?;
+    let missing = (1..=total)
+        .filter(|n| !parts.iter().any(|(info, _, _)| info.number == *n))
+        .map(|n| n.to_string())
+        .collect::<Vec<String>>();
+    if !missing.is_empty() || parts.len() != total {
+        return Err(MeliError::new(format!(
+            "Message parts {} of {} are missing.",
+            missing.join(", "),
+            total
+        )));
+    }
+    let enclosed = parts
+        .iter()
+        .flat_map(|(_, _, body)| body.iter().copied())
+        .collect::<Vec<u8>>();
+    let (enclosed_headers, body) = parser::mail(&enclosed)?;
+    let mut ret = Vec::with_capacity(enclosed.len() + 1024);
+    for (name, value) in parts[0].1.iter() {
+        if !is_enclosed_header(name) {
+            push_header(&mut ret, name, value);
+        }
+    }
+    for (name, value) in enclosed_headers {
+        if is_enclosed_header(name) {
+            push_header(&mut ret, name, value);
+        }
+    }
+    ret.extend_from_slice(b"\r\n");
+    ret.extend_from_slice(body);
+    Ok(ret)
+}
+
+/// Split `message` into `message/partial` messages of about `max_size` bytes each, cutting at
+/// line ends. A message that fits is returned as is.
+pub fn split(message: &[u8], max_size: usize) -> Result<Vec<Vec<u8>>> {
+    if message.len() <= max_size {
+        return Ok(vec![message.to_vec()]);
+    }
+    let (headers, body) = parser::mail(message)?;
+    let get = |name: &[u8]| {
+        headers
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| String::from_utf8_lossy(v).trim().to_string())
+    };
+    let id = get(b"message-id")
+        .unwrap_or_else(|| super::compose::random::gen_message_id("localhost"))
+        .trim_start_matches('<')
+        .trim_end_matches('>')
+        .to_string();
+    let subject = get(b"subject").unwrap_or_default();
+    let mut outer = Vec::new();
+    let mut enclosed = Vec::with_capacity(message.len());
+    for (name, value) in headers.iter() {
+        if is_enclosed_header(name) {
+            push_header(&mut enclosed, name, value);
+        } else {
+            push_header(&mut outer, name, value);
+        }
+    }
+    enclosed.extend_from_slice(b"\r\n");
+    enclosed.extend_from_slice(body);
+    /* Leave room for the headers added to each part. */
+    let chunk_size = std::cmp::max(
+        max_size.saturating_sub(outer.len() + subject.len() + 2 * id.len() + 256),
+        1024,
+    );
+    let mut chunks: Vec<&[u8]> = vec![];
+    let mut rest = enclosed.as_slice();
+    while !rest.is_empty() {
+        let mut end = std::cmp::min(chunk_size, rest.len());
+        if end < rest.len() {
+            end = match rest[..end].iter().rposition(|&b| b == b'\n') {
+                Some(pos) => pos + 1,
+                /* A line longer than a chunk is not cut. */
+                None => rest
+                    .iter()
+                    .position(|&b| b == b'\n')
+                    .map(|pos| pos + 1)
+                    .unwrap_or_else(|| rest.len()),
+            };
+        }
+        chunks.push(&rest[..end]);
+        rest = &rest[end..];
+    }
+    let total = chunks.len();
+    Ok(chunks
+        .into_iter()
+        .enumerate()
+        .map(|(i, chunk)| {
+            let number = i + 1;
+            let mut part = outer.clone();
+            push_header(
+                &mut part,
+                b"Subject",
+                format!("{} ({}/{})", subject, number, total).as_bytes(),
+            );
+            push_header(
+                &mut part,
+                b"Message-ID",
+                format!("<{}.{}>", number, id).as_bytes(),
+            );
+            push_header(&mut part, b"MIME-Version", b"1.0");
+            push_header(
+                &mut part,
+                b"Content-Type",
+                format!(
+                    "message/partial; id=\"{}\"; number={}; total={}",
+                    id, number, total
+                )
+                .as_bytes(),
+            );
+            part.extend_from_slice(b"\r\n");
+            part.extend_from_slice(chunk);
+            part
+        })
+        .collect())
+}
+
+#[test]
+fn test_partial_split_reassemble() {
+    let mut message = b"From: a@example.com\r\n\
+To: b@example.com\r\n\
+Subject: Large\r\n\
+Message-ID: <large@example.com>\r\n\
+MIME-Version: 1.0\r\n\
+Content-Type: text/plain\r\n\
+\r\n"
+        .to_vec();
+    for i in 0..500 {
+        message.extend_from_slice(format!("line {}\r\n", i).as_bytes());
+    }
+    let parts = split(&message, 2048).unwrap();
+    assert!(parts.len() > 1);
+    assert!(parts.iter().all(|p| p.len() <= 2048));
+    let last = PartialInfo::from_bytes(parts.last().unwrap()).unwrap();
+    assert_eq!(last.id, "large@example.com");
+    assert_eq!(last.total, Some(parts.len()));
+    let reassembled = reassemble(parts.iter().rev().map(Vec::as_slice)).unwrap();
+    assert_eq!(reassembled, message);
+    assert!(reassemble(parts[1..].iter().map(Vec::as_slice)).is_err());
+}
@@ -42,6 +42,11 @@ pub enum ErrorKind {
     Bug,
     Network,
     Timeout,
+    NotFound,
+    /// The other end sent something unexpected or malformed.
+    Protocol,
+    Io,
+    Configuration,
 }
 
 impl fmt::Display for ErrorKind {
@@ -56,6 +61,10 @@ impl fmt::Display for ErrorKind {
                 ErrorKind::Bug => "Bug, please report this!",
                 ErrorKind::Network => "Network",
                 ErrorKind::Timeout => "Timeout",
+                ErrorKind::NotFound => "Not found",
+                ErrorKind::Protocol => "Protocol",
+                ErrorKind::Io => "I/O",
+                ErrorKind::Configuration => "Configuration",
             }
         )
     }
@@ -82,6 +91,14 @@ impl ErrorKind {
             _ => false,
         }
     }
+
+    /// Whether an operation that failed with this kind of error may succeed if retried later.
+    pub fn is_recoverable(&self) -> bool {
+        match self {
+            ErrorKind::Network | ErrorKind::Timeout => true,
+            _ => false,
+        }
+    }
 }
 
 #[derive(Debug, Clone)]
@@ -178,6 +195,42 @@ impl MeliError {
         self.kind = new_val;
         self
     }
+
+    /// The kind of this error, or if it is `ErrorKind::None` the first other kind in its
+    /// `source` chain.
+    pub fn root_kind(&self) -> ErrorKind {
+        let mut source: Option<&(dyn Error + 'static)> = Some(self);
+        while let Some(err) = source {
+            if let Some(kind) = err
+                .downcast_ref::<MeliError>()
+                .map(|err| err.kind)
+                .or_else(|| err.downcast_ref::<io::Error>().map(io_error_kind))
+            {
+                if kind != ErrorKind::None {
+                    return kind;
+                }
+            }
+            source = err.source();
+        }
+        ErrorKind::None
+    }
+
+    /// Whether the failed operation may succeed if retried later, eg. after a network error,
+    /// instead of being reported as a failure.
+    pub fn is_recoverable(&self) -> bool {
+        self.root_kind().is_recoverable()
+    }
+}
+
+fn io_error_kind(err: &io::Error) -> ErrorKind {
+    use io::ErrorKind::*;
+    match err.kind() {
+        TimedOut | WouldBlock => ErrorKind::Timeout,
+        ConnectionRefused | ConnectionReset | ConnectionAborted | NotConnected | AddrInUse
+        | AddrNotAvailable | BrokenPipe | UnexpectedEof => ErrorKind::Network,
+        NotFound => ErrorKind::NotFound,
+        _ => ErrorKind::Io,
+    }
 }
 
 impl fmt::Display for MeliError {
@@ -207,6 +260,7 @@ impl From<io::Error> for MeliError {
     fn from(kind: io::Error) -> MeliError {
         MeliError::new(kind.to_string())
             .set_summary(format!("{:?}", kind.kind()))
+            .set_kind(io_error_kind(&kind))
             .set_source(Some(Arc::new(kind)))
     }
 }
@@ -252,7 +306,9 @@ impl<T: Sync + Send + 'static + core::fmt::Debug> From<native_tls::HandshakeErro
 {
     #[inline]
     fn from(kind: native_tls::HandshakeError<T>) -> MeliError {
-        MeliError::new(format!("{}", kind)).set_source(Some(Arc::new(kind)))
+        MeliError::new(format!("{}", kind))
+            .set_kind(ErrorKind::Network)
+            .set_source(Some(Arc::new(kind)))
     }
 }
 
@@ -260,7 +316,9 @@ impl<T: Sync + Send + 'static + core::fmt::Debug> From<native_tls::HandshakeErro
 impl From<native_tls::Error> for MeliError {
     #[inline]
     fn from(kind: native_tls::Error) -> MeliError {
-        MeliError::new(format!("{}", kind)).set_source(Some(Arc::new(kind)))
+        MeliError::new(format!("{}", kind))
+            .set_kind(ErrorKind::Network)
+            .set_source(Some(Arc::new(kind)))
     }
 }
 
@@ -271,11 +329,13 @@ impl From<std::num::ParseIntError> for MeliError {
     }
 }
 
-#[cfg(feature = "jmap_backend")]
+#[cfg(feature = "http")]
 impl From<isahc::Error> for MeliError {
     #[inline]
     fn from(kind: isahc::Error) -> MeliError {
-        MeliError::new(kind.to_string()).set_source(Some(Arc::new(kind)))
+        MeliError::new(kind.to_string())
+            .set_kind(ErrorKind::Network)
+            .set_source(Some(Arc::new(kind)))
     }
 }
 
@@ -283,7 +343,9 @@ impl From<isahc::Error> for MeliError {
 impl From<serde_json::error::Error> for MeliError {
     #[inline]
     fn from(kind: serde_json::error::Error) -> MeliError {
-        MeliError::new(format!("{}", kind)).set_source(Some(Arc::new(kind)))
+        MeliError::new(format!("{}", kind))
+            .set_kind(ErrorKind::Protocol)
+            .set_source(Some(Arc::new(kind)))
     }
 }
 
@@ -373,3 +435,19 @@ impl<'a> From<&'a MeliError> for MeliError {
         kind.clone()
     }
 }
+
+#[test]
+fn test_error_kind_is_recoverable() {
+    let err: MeliError = io::Error::new(io::ErrorKind::ConnectionReset, "reset").into();
+    assert_eq!(err.kind, ErrorKind::Network);
+    assert!(err.is_recoverable());
+    let err: MeliError = io::Error::new(io::ErrorKind::PermissionDenied, "denied").into();
+    assert_eq!(err.kind, ErrorKind::Io);
+    assert!(!err.is_recoverable());
+    let err = MeliError::new("Could not fetch mailbox.").set_source(Some(Arc::new(
+        MeliError::new("Timed out.").set_kind(ErrorKind::Timeout),
+    )));
+    assert_eq!(err.root_kind(), ErrorKind::Timeout);
+    assert!(err.is_recoverable());
+    assert!(!MeliError::new("Parsing error").is_recoverable());
+}
@@ -3780,12 +3780,9 @@ fn bindgen_test_layout__gpgme_op_import_result() {
     );
 }
 pub type gpgme_import_result_t = *mut _gpgme_op_import_result;
-extern "C" {
-    pub fn gpgme_op_import_result(ctx: gpgme_ctx_t) -> gpgme_import_result_t;
-}
-extern "C" {
-    pub fn gpgme_op_import_start(ctx: gpgme_ctx_t, keydata: gpgme_data_t) -> gpgme_error_t;
-}
+pub type gpgme_op_import_result = unsafe extern "C" fn(ctx: gpgme_ctx_t) -> gpgme_import_result_t;
+pub type gpgme_op_import_start =
+    unsafe extern "C" fn(ctx: gpgme_ctx_t, keydata: gpgme_data_t) -> gpgme_error_t;
 extern "C" {
     pub fn gpgme_op_import(ctx: gpgme_ctx_t, keydata: gpgme_data_t) -> gpgme_error_t;
 }
@@ -367,6 +367,31 @@ impl Context {
         }
     }
 
+    /// Look keys up only on the keyserver at `url`, eg. `hkps://keys.openpgp.org`, when listing
+    /// keys with [`Context::set_locate_keys`].
+    pub fn set_keyserver(&self, url: &str) -> Result<()> {
+        let value = CString::new(format!("clear,nodefault,{}", url))?;
+        self.set_flag_inner(c_string_literal!("auto-key-locate"), value.as_ptr())
+    }
+
+    /// Also look keys up with the mechanisms of `auto-key-locate` when listing keys; keys found
+    /// remotely are imported into the keyring. Remote lookups need offline mode to be off.
+    pub fn set_locate_keys(&self, val: bool) -> Result<()> {
+        unsafe {
+            gpgme_error_try(
+                &self.inner.lib,
+                call!(&self.inner.lib, gpgme_set_keylist_mode)(
+                    self.inner.inner.as_ptr(),
+                    if val {
+                        GPGME_KEYLIST_MODE_LOCATE
+                    } else {
+                        GPGME_KEYLIST_MODE_LOCAL
+                    },
+                ),
+            )
+        }
+    }
+
     pub fn get_auto_key_locate(&self) -> Result<LocateKey> {
         let auto_key_locate: *const ::std::os::raw::c_char = c_string_literal!("auto-key-locate");
         let raw_value =
@@ -797,6 +822,121 @@ impl Context {
         })
     }
 
+    /// Imports the keys in `keydata` into the keyring, returning the fingerprints of the keys it
+    /// contained.
+    pub fn import_key(
+        &mut self,
+        mut keydata: Data,
+    ) -> Result<impl Future<Output = Result<Vec<String>>> + Send> {
+        unsafe {
+            gpgme_error_try(
+                &self.inner.lib,
+                call!(&self.inner.lib, gpgme_op_import_start)(
+                    self.inner.inner.as_ptr(),
+                    keydata.inner.as_mut(),
+                ),
+            )?;
+        }
+
+        let ctx = self.inner.clone();
+        let io_state = self.io_state.clone();
+        let io_state_lck = self.io_state.lock().unwrap();
+        let done = io_state_lck.done.clone();
+        let fut = io_state_lck
+            .ops
+            .values()
+            .map(|a| Async::new(a.clone()).unwrap())
+            .collect::<Vec<Async<GpgmeFd>>>();
+        drop(io_state_lck);
+        Ok(async move {
+            let _k = keydata;
+            futures::future::join_all(fut.iter().map(|fut| {
+                let done = done.clone();
+                if fut.get_ref().write {
+                    futures::future::select(
+                        fut.get_ref().receiver.recv().boxed(),
+                        fut.write_with(move |_f| {
+                            if done.lock().unwrap().is_some() {
+                                return Ok(());
+                            }
+                            unsafe {
+                                (fut.get_ref().fnc.unwrap())(
+                                    fut.get_ref().fnc_data,
+                                    fut.get_ref().fd,
+                                )
+                            };
+                            if done.lock().unwrap().is_none() {
+                                return Err(std::io::ErrorKind::WouldBlock.into());
+                            }
+                            Ok(())
+                        })
+                        .boxed(),
+                    )
+                    .boxed()
+                } else {
+                    futures::future::select(
+                        fut.get_ref().receiver.recv().boxed(),
+                        fut.read_with(move |_f| {
+                            if done.lock().unwrap().is_some() {
+                                return Ok(());
+                            }
+                            unsafe {
+                                (fut.get_ref().fnc.unwrap())(
+                                    fut.get_ref().fnc_data,
+                                    fut.get_ref().fd,
+                                )
+                            };
+                            if done.lock().unwrap().is_none() {
+                                return Err(std::io::ErrorKind::WouldBlock.into());
+                            }
+                            Ok(())
+                        })
+                        .boxed(),
+                    )
+                    .boxed()
+                }
+            }))
+            .await;
+            let rcv = {
+                let io_state_lck = io_state.lock().unwrap();
+                io_state_lck.receiver.clone()
+            };
+            let _ = rcv.recv().await;
+            {
+                let io_state_lck = io_state.lock().unwrap();
+                let ret = io_state_lck
+                    .done
+                    .lock()
+                    .unwrap()
+                    .take()
+                    .unwrap_or_else(|| Err(MeliError::new("Unspecified libgpgme error")));
+                ret?;
+            }
+            let import_result =
+                unsafe { call!(&ctx.lib, gpgme_op_import_result)(ctx.inner.as_ptr()) };
+            if import_result.is_null() {
+                return Err(MeliError::new(
+                    "Unspecified libgpgme error: gpgme_op_import_result returned NULL.",
+                )
+                .set_err_kind(ErrorKind::External));
+            }
+            let mut ret = vec![];
+            unsafe {
+                let mut status = (*import_result).imports;
+                while !status.is_null() {
+                    if (*status).result == 0 && !(*status).fpr.is_null() {
+                        let fpr = CStr::from_ptr((*status).fpr).to_string_lossy().to_string();
+                        if !ret.contains(&fpr) {
+                            ret.push(fpr);
+                        }
+                    }
+                    status = (*status).next;
+                }
+            }
+            Ok(ret)
+        })
+    }
+
     pub fn decrypt(
         &mut self,
         mut cipher: Data,
@@ -1298,6 +1438,50 @@ impl Key {
     pub fn fingerprint(&self) -> Cow<'_, str> {
         (unsafe { CStr::from_ptr((*(self.inner.inner.as_ptr())).fpr) }).to_string_lossy()
     }
+
+    /// The long key ID of the primary key.
+    pub fn key_id(&self) -> Option<Cow<'_, str>> {
+        unsafe {
+            let subkey = (*(self.inner.inner.as_ptr())).subkeys;
+            if subkey.is_null() || (*subkey).keyid.is_null() {
+                return None;
+            }
+            Some(CStr::from_ptr((*subkey).keyid).to_string_lossy())
+        }
+    }
+
+    /// When the primary key was created, if known.
+    pub fn created(&self) -> Option<crate::UnixTimestamp> {
+        unsafe {
+            let subkey = (*(self.inner.inner.as_ptr())).subkeys;
+            if subkey.is_null() || (*subkey).timestamp <= 0 {
+                return None;
+            }
+            Some((*subkey).timestamp as crate::UnixTimestamp)
+        }
+    }
+
+    /// How much the primary user ID is trusted to belong to the key's owner.
+    pub fn validity(&self) -> &'static str {
+        let validity = unsafe {
+            let uid = (*(self.inner.inner.as_ptr())).uids;
+            if uid.is_null() {
+                return "unknown";
+            }
+            (*uid).validity
+        };
+        [
+            (gpgme_validity_t_GPGME_VALIDITY_NEVER, "never"),
+            (gpgme_validity_t_GPGME_VALIDITY_MARGINAL, "marginal"),
+            (gpgme_validity_t_GPGME_VALIDITY_FULL, "full"),
+            (gpgme_validity_t_GPGME_VALIDITY_ULTIMATE, "ultimate"),
+            (gpgme_validity_t_GPGME_VALIDITY_UNDEFINED, "undefined"),
+        ]
+        .iter()
+        .find(|(v, _)| *v == validity)
+        .map(|(_, name)| *name)
+        .unwrap_or("unknown")
+    }
 }
 
 impl std::fmt::Debug for Key {
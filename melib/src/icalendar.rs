@@ -0,0 +1,927 @@
+/*
+ * meli - melib crate.
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Parse iCalendar ([RFC 5545](https://tools.ietf.org/html/rfc5545)) invitations and build
+replies to them ([RFC 5546](https://tools.ietf.org/html/rfc5546)).
+
+Only what is needed to show an event summary and answer an invitation is interpreted: events,
+their organizer and attendees and their start and end times. Times with a `TZID` are converted
+with the offsets of the matching `VTIMEZONE` component of the calendar.
+*/
+
+use crate::datetime::{self, UnixTimestamp};
+use crate::error::{MeliError, Result};
+use std::fmt;
+
+/// Maximum length in octets of a content line, excluding the line break.
+const LINE_LENGTH: usize = 75;
+
+/// A content line: `NAME;PARAM=VALUE:value`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Property {
+    pub name: String,
+    pub params: Vec<(String, String)>,
+    pub value: String,
+}
+
+impl Property {
+    fn parse(line: &str) -> Result<Self> {
+        let mut in_quotes = false;
+        let mut fields = vec![];
+        let mut start = 0;
+        let mut value_start = None;
+        for (i, c) in line.char_indices() {
+            match c {
+                '"' => in_quotes = !in_quotes,
+                ';' if !in_quotes => {
+                    fields.push(&line[start..i]);
+                    start = i + 1;
+                }
+                ':' if !in_quotes => {
+                    fields.push(&line[start..i]);
+                    value_start = Some(i + 1);
+                    break;
+                }
+                _ => {}
+            }
+        }
+        let value_start = value_start.ok_or_else(|| {
+            MeliError::new(format!(
+                "Error while parsing iCalendar: no colon in content line {:?}",
+                line
+            ))
+        })?;
+        let name = fields.remove(0).to_ascii_uppercase();
+        if name.is_empty() {
+            return Err(MeliError::new(format!(
+                "Error while parsing iCalendar: no name in content line {:?}",
+                line
+            )));
+        }
+        let params = fields
+            .into_iter()
+            .map(|p| {
+                let mut parts = p.splitn(2, '=');
+                let n = parts.next().unwrap_or_default().to_ascii_uppercase();
+                let v = parts.next().unwrap_or_default().replace('"', "");
+                (n, v)
+            })
+            .collect();
+        Ok(Property {
+            name,
+            params,
+            value: line[value_start..].to_string(),
+        })
+    }
+
+    pub fn param(&self, name: &str) -> Option<&str> {
+        self.params
+            .iter()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+            .map(|(_, v)| v.as_str())
+    }
+
+    /// The value with TEXT escapes removed.
+    pub fn text(&self) -> String {
+        let mut ret = String::with_capacity(self.value.len());
+        let mut chars = self.value.chars();
+        while let Some(c) = chars.next() {
+            if c == '\\' {
+                match chars.next() {
+                    Some('n') | Some('N') => ret.push('\n'),
+                    Some(c) => ret.push(c),
+                    None => {}
+                }
+            } else {
+                ret.push(c);
+            }
+        }
+        ret
+    }
+
+    /// The value with a `mailto:` prefix removed, as used by `ORGANIZER` and `ATTENDEE`.
+    fn address(&self) -> &str {
+        if self.value.len() > "mailto:".len()
+            && self
+                .value
+                .get(.."mailto:".len())
+                .map_or(false, |p| p.eq_ignore_ascii_case("mailto:"))
+        {
+            &self.value["mailto:".len()..]
+        } else {
+            &self.value
+        }
+    }
+
+    fn set_param(&mut self, name: &str, value: &str) {
+        if let Some(p) = self
+            .params
+            .iter_mut()
+            .find(|(n, _)| n.eq_ignore_ascii_case(name))
+        {
+            p.1 = value.to_string();
+        } else {
+            self.params.push((name.to_string(), value.to_string()));
+        }
+    }
+}
+
+impl fmt::Display for Property {
+    /// Write the content line, folded to lines of at most 75 octets.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let mut line = self.name.clone();
+        for (n, v) in &self.params {
+            line.push(';');
+            line.push_str(n);
+            line.push('=');
+            if v.contains(|c| c == ':' || c == ';' || c == ',') {
+                line.push('"');
+                line.push_str(v);
+                line.push('"');
+            } else {
+                line.push_str(v);
+            }
+        }
+        line.push(':');
+        line.push_str(&self.value);
+        let mut line_len = 0;
+        for c in line.chars() {
+            if line_len + c.len_utf8() > LINE_LENGTH {
+                write!(f, "\r\n ")?;
+                line_len = 1;
+            }
+            write!(f, "{}", c)?;
+            line_len += c.len_utf8();
+        }
+        write!(f, "\r\n")
+    }
+}
+
+/// A `BEGIN:NAME` ... `END:NAME` block.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Component {
+    pub name: String,
+    pub properties: Vec<Property>,
+    pub components: Vec<Component>,
+}
+
+impl Component {
+    fn new(name: String) -> Self {
+        Component {
+            name,
+            properties: vec![],
+            components: vec![],
+        }
+    }
+
+    pub fn property(&self, name: &str) -> Option<&Property> {
+        self.properties
+            .iter()
+            .find(|p| p.name.eq_ignore_ascii_case(name))
+    }
+}
+
+impl fmt::Display for Component {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "BEGIN:{}\r\n", self.name)?;
+        for p in &self.properties {
+            write!(f, "{}", p)?;
+        }
+        for c in &self.components {
+            write!(f, "{}", c)?;
+        }
+        write!(f, "END:{}\r\n", self.name)
+    }
+}
+
+/// Participation status of an attendee.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum PartStat {
+    NeedsAction,
+    Accepted,
+    Tentative,
+    Declined,
+    Delegated,
+}
+
+impl PartStat {
+    fn from_param(value: &str) -> Option<Self> {
+        match value.to_ascii_uppercase().as_str() {
+            "NEEDS-ACTION" => Some(PartStat::NeedsAction),
+            "ACCEPTED" => Some(PartStat::Accepted),
+            "TENTATIVE" => Some(PartStat::Tentative),
+            "DECLINED" => Some(PartStat::Declined),
+            "DELEGATED" => Some(PartStat::Delegated),
+            _ => None,
+        }
+    }
+
+    fn as_param(self) -> &'static str {
+        match self {
+            PartStat::NeedsAction => "NEEDS-ACTION",
+            PartStat::Accepted => "ACCEPTED",
+            PartStat::Tentative => "TENTATIVE",
+            PartStat::Declined => "DECLINED",
+            PartStat::Delegated => "DELEGATED",
+        }
+    }
+}
+
+impl fmt::Display for PartStat {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self {
+            PartStat::NeedsAction => write!(f, "needs action"),
+            PartStat::Accepted => write!(f, "accepted"),
+            PartStat::Tentative => write!(f, "tentative"),
+            PartStat::Declined => write!(f, "declined"),
+            PartStat::Delegated => write!(f, "delegated"),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Attendee {
+    pub address: String,
+    pub name: Option<String>,
+    pub status: Option<PartStat>,
+}
+
+impl Attendee {
+    fn from_property(p: &Property) -> Self {
+        Attendee {
+            address: p.address().to_string(),
+            name: p.param("CN").map(str::to_string),
+            status: p.param("PARTSTAT").and_then(PartStat::from_param),
+        }
+    }
+}
+
+impl fmt::Display for Attendee {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if let Some(ref name) = self.name {
+            write!(f, "{} <{}>", name, self.address)
+        } else {
+            write!(f, "{}", self.address)
+        }
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct EventTime {
+    pub timestamp: UnixTimestamp,
+    /// A `DATE` value without a time of day.
+    pub all_day: bool,
+}
+
+impl fmt::Display for EventTime {
+    /// Format in local time.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let fmt = if self.all_day {
+            "%a, %d %b %Y"
+        } else {
+            "%a, %d %b %Y %R"
+        };
+        write!(
+            f,
+            "{}",
+            datetime::timestamp_to_string(self.timestamp, Some(fmt), false)
+        )
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Event {
+    pub uid: Option<String>,
+    pub summary: Option<String>,
+    pub location: Option<String>,
+    pub description: Option<String>,
+    pub organizer: Option<Attendee>,
+    pub attendees: Vec<Attendee>,
+    pub start: Option<EventTime>,
+    pub end: Option<EventTime>,
+    component: Component,
+}
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct Calendar {
+    /// `REQUEST` for invitations, `CANCEL` for cancellations, `REPLY` for replies.
+    pub method: Option<String>,
+    pub events: Vec<Event>,
+    timezones: Vec<Component>,
+}
+
+impl Calendar {
+    pub fn from_str(input: &str) -> Result<Self> {
+        let mut stack: Vec<Component> = vec![];
+        let mut root = None;
+        for line in unfold(input).lines() {
+            if line.trim().is_empty() {
+                continue;
+            }
+            let p = Property::parse(line)?;
+            match p.name.as_str() {
+                "BEGIN" => stack.push(Component::new(p.value.to_ascii_uppercase())),
+                "END" => {
+                    let c = stack.pop().ok_or_else(|| {
+                        MeliError::new(format!(
+                            "Error while parsing iCalendar: unexpected END:{}",
+                            p.value
+                        ))
+                    })?;
+                    if !c.name.eq_ignore_ascii_case(&p.value) {
+                        return Err(MeliError::new(format!(
+                            "Error while parsing iCalendar: expected END:{}, found END:{}",
+                            c.name, p.value
+                        )));
+                    }
+                    if let Some(parent) = stack.last_mut() {
+                        parent.components.push(c);
+                    } else {
+                        root = Some(c);
+                        break;
+                    }
+                }
+                _ => {
+                    if let Some(c) = stack.last_mut() {
+                        c.properties.push(p);
+                    }
+                }
+            }
+        }
+        let root = match root {
+            Some(root) if root.name == "VCALENDAR" => root,
+            _ => {
+                return Err(MeliError::new(
+                    "Error while parsing iCalendar: no VCALENDAR component.",
+                ))
+            }
+        };
+        let timezones = root
+            .components
+            .iter()
+            .filter(|c| c.name == "VTIMEZONE")
+            .cloned()
+            .collect::<Vec<Component>>();
+        let events = root
+            .components
+            .iter()
+            .filter(|c| c.name == "VEVENT")
+            .map(|c| Event {
+                uid: c.property("UID").map(|p| p.value.clone()),
+                summary: c.property("SUMMARY").map(Property::text),
+                location: c.property("LOCATION").map(Property::text),
+                description: c.property("DESCRIPTION").map(Property::text),
+                organizer: c.property("ORGANIZER").map(Attendee::from_property),
+                attendees: c
+                    .properties
+                    .iter()
+                    .filter(|p| p.name == "ATTENDEE")
+                    .map(Attendee::from_property)
+                    .collect(),
+                start: c
+                    .property("DTSTART")
+                    .and_then(|p| event_time(p, &timezones)),
+                end: c.property("DTEND").and_then(|p| event_time(p, &timezones)),
+                component: c.clone(),
+            })
+            .collect();
+        Ok(Calendar {
+            method: root
+                .property("METHOD")
+                .map(|p| p.value.to_ascii_uppercase()),
+            events,
+            timezones,
+        })
+    }
+
+    /// Whether this is an invitation that can be replied to.
+    pub fn is_request(&self) -> bool {
+        self.method.as_deref() == Some("REQUEST")
+            && self.events.iter().any(|e| e.organizer.is_some())
+    }
+
+    /// Build a `METHOD:REPLY` calendar that sets the participation status of `attendee` (an
+    /// e-mail address) in the first event to `status`.
+    pub fn reply(&self, attendee: &str, status: PartStat) -> Result<String> {
+        let event = self
+            .events
+            .iter()
+            .find(|e| e.organizer.is_some())
+            .ok_or_else(|| MeliError::new("Calendar has no event with an organizer."))?;
+        let mut vevent = Component::new("VEVENT".to_string());
+        for name in &[
+            "UID",
+            "RECURRENCE-ID",
+            "SEQUENCE",
+            "DTSTART",
+            "DTEND",
+            "DURATION",
+            "SUMMARY",
+            "ORGANIZER",
+        ] {
+            if let Some(p) = event.component.property(name) {
+                vevent.properties.push(p.clone());
+            }
+        }
+        vevent.properties.push(Property {
+            name: "DTSTAMP".to_string(),
+            params: vec![],
+            value: utc_date_time(datetime::now()),
+        });
+        let mut attendee_property = event
+            .component
+            .properties
+            .iter()
+            .find(|p| p.name == "ATTENDEE" && p.address().eq_ignore_ascii_case(attendee))
+            .cloned()
+            .unwrap_or_else(|| Property {
+                name: "ATTENDEE".to_string(),
+                params: vec![],
+                value: format!("mailto:{}", attendee),
+            });
+        attendee_property
+            .params
+            .retain(|(n, _)| n != "RSVP" && n != "ROLE");
+        attendee_property.set_param("PARTSTAT", status.as_param());
+        vevent.properties.push(attendee_property);
+
+        let used_timezones = vevent
+            .properties
+            .iter()
+            .filter_map(|p| p.param("TZID"))
+            .collect::<Vec<&str>>();
+        let mut vcalendar = Component::new("VCALENDAR".to_string());
+        vcalendar.properties = vec![
+            Property {
+                name: "PRODID".to_string(),
+                params: vec![],
+                value: "-//meli//meli//EN".to_string(),
+            },
+            Property {
+                name: "VERSION".to_string(),
+                params: vec![],
+                value: "2.0".to_string(),
+            },
+            Property {
+                name: "METHOD".to_string(),
+                params: vec![],
+                value: "REPLY".to_string(),
+            },
+        ];
+        vcalendar.components = self
+            .timezones
+            .iter()
+            .filter(|tz| {
+                tz.property("TZID")
+                    .map(|p| used_timezones.contains(&p.value.as_str()))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect();
+        vcalendar.components.push(vevent);
+        Ok(vcalendar.to_string())
+    }
+}
+
+impl fmt::Display for Calendar {
+    /// A plain text summary of the events.
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        let kind = match self.method.as_deref() {
+            Some("REQUEST") => "Invitation",
+            Some("CANCEL") => "Cancelled event",
+            Some("REPLY") => "Reply to invitation",
+            _ => "Event",
+        };
+        for (i, event) in self.events.iter().enumerate() {
+            if i > 0 {
+                writeln!(f)?;
+            }
+            writeln!(
+                f,
+                "{}: {}",
+                kind,
+                event.summary.as_deref().unwrap_or("(no title)")
+            )?;
+            match (event.start, event.end) {
+                (Some(start), Some(end)) if start.all_day && end.all_day => {
+                    /* DTEND of an all day event is exclusive */
+                    if end.timestamp > start.timestamp + 24 * 60 * 60 {
+                        let end = EventTime {
+                            timestamp: end.timestamp - 24 * 60 * 60,
+                            ..end
+                        };
+                        writeln!(f, "When: {} – {}", start, end)?;
+                    } else {
+                        writeln!(f, "When: {}", start)?;
+                    }
+                }
+                (Some(start), Some(end)) => writeln!(f, "When: {} – {}", start, end)?,
+                (Some(start), None) => writeln!(f, "When: {}", start)?,
+                _ => {}
+            }
+            if let Some(ref location) = event.location {
+                writeln!(f, "Where: {}", location)?;
+            }
+            if let Some(ref organizer) = event.organizer {
+                writeln!(f, "Organizer: {}", organizer)?;
+            }
+            if !event.attendees.is_empty() {
+                writeln!(f, "Attendees:")?;
+                for a in &event.attendees {
+                    if let Some(status) = a.status {
+                        writeln!(f, "  {} ({})", a, status)?;
+                    } else {
+                        writeln!(f, "  {}", a)?;
+                    }
+                }
+            }
+            if let Some(ref description) = event.description {
+                writeln!(f)?;
+                writeln!(f, "{}", description.trim_end())?;
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Join folded lines: a line break followed by a space or tab continues the previous line.
+fn unfold(input: &str) -> String {
+    input
+        .replace("\r\n ", "")
+        .replace("\r\n\t", "")
+        .replace("\n ", "")
+        .replace("\n\t", "")
+}
+
+/// Parse a `DATE` (`19970714`) or `DATE-TIME` (`19970714T133000`, `19970714T173000Z`) value.
+///
+/// Returns the seconds since the epoch as if the time was in UTC, whether it actually is in UTC
+/// and whether the value is a `DATE`.
+fn parse_date_time(value: &str) -> Option<(i64, bool, bool)> {
+    let value = value.trim();
+    let num = |s: &str| -> Option<i64> {
+        if s.bytes().all(|b| b.is_ascii_digit()) {
+            s.parse().ok()
+        } else {
+            None
+        }
+    };
+    if value.len() < 8 {
+        return None;
+    }
+    let days = days_from_civil(
+        num(value.get(0..4)?)?,
+        num(value.get(4..6)?)?,
+        num(value.get(6..8)?)?,
+    );
+    if value.len() == 8 {
+        return Some((days * 86400, false, true));
+    }
+    if value.get(8..9)? != "T" {
+        return None;
+    }
+    let secs =
+        num(value.get(9..11)?)? * 3600 + num(value.get(11..13)?)? * 60 + num(value.get(13..15)?)?;
+    let utc = match value.get(15..) {
+        Some("Z") => true,
+        Some("") => false,
+        _ => return None,
+    };
+    Some((days * 86400 + secs, utc, false))
+}
+
+fn event_time(p: &Property, timezones: &[Component]) -> Option<EventTime> {
+    let (local, utc, all_day) = parse_date_time(&p.value)?;
+    let timezone = p.param("TZID").and_then(|tzid| {
+        timezones.iter().find(|tz| {
+            tz.property("TZID")
+                .map(|t| t.value == tzid)
+                .unwrap_or(false)
+        })
+    });
+    let timestamp = if utc {
+        local
+    } else if let (Some(tz), false) = (timezone, all_day) {
+        local - utc_offset(tz, local)?
+    } else {
+        /* Floating times, dates and unknown timezones are in local time */
+        let fmt = if all_day { "%Y%m%d" } else { "%Y%m%dT%H%M%S" };
+        return datetime::timestamp_from_string(p.value.trim(), fmt)
+            .ok()
+            .flatten()
+            .map(|timestamp| EventTime { timestamp, all_day });
+    };
+    if timestamp < 0 {
+        return None;
+    }
+    Some(EventTime {
+        timestamp: timestamp as UnixTimestamp,
+        all_day,
+    })
+}
+
+/// UTC offset in seconds of `local` time in the timezone `tz`.
+///
+/// The offset is that of the `STANDARD` or `DAYLIGHT` observance with the latest onset before
+/// `local`. Yearly recurrence rules with `BYMONTH` and `BYDAY` (e.g. `BYMONTH=3;BYDAY=-1SU`) are
+/// supported, which is what calendar software generates.
+fn utc_offset(tz: &Component, local: i64) -> Option<i64> {
+    let (year, _, _) = civil_from_days(local.div_euclid(86400));
+    let mut ret: Option<(i64, i64)> = None;
+    for observance in tz
+        .components
+        .iter()
+        .filter(|c| c.name == "STANDARD" || c.name == "DAYLIGHT")
+    {
+        let offset = match observance
+            .property("TZOFFSETTO")
+            .and_then(|p| parse_utc_offset(&p.value))
+        {
+            Some(offset) => offset,
+            None => continue,
+        };
+        let start = match observance
+            .property("DTSTART")
+            .and_then(|p| parse_date_time(&p.value))
+        {
+            Some((start, _, _)) => start,
+            None => continue,
+        };
+        let mut onset = if start <= local { Some(start) } else { None };
+        if let Some(rule) = observance.property("RRULE") {
+            let time_of_day = start.rem_euclid(86400);
+            for y in &[year - 1, year] {
+                if let Some(day) = yearly_rule_day(&rule.value, *y) {
+                    let t = day * 86400 + time_of_day;
+                    if t >= start && t <= local && onset.map(|o| t > o).unwrap_or(true) {
+                        onset = Some(t);
+                    }
+                }
+            }
+        }
+        if let Some(onset) = onset {
+            if ret.map(|(o, _)| onset > o).unwrap_or(true) {
+                ret = Some((onset, offset));
+            }
+        }
+    }
+    ret.map(|(_, offset)| offset)
+}
+
+/// Day (since the epoch) in `year` of a `FREQ=YEARLY;BYMONTH=M;BYDAY=[N]DD` rule.
+fn yearly_rule_day(rule: &str, year: i64) -> Option<i64> {
+    let mut freq = None;
+    let mut month = None;
+    let mut by_day = None;
+    for part in rule.split(';') {
+        let mut kv = part.splitn(2, '=');
+        match (kv.next()?.to_ascii_uppercase().as_str(), kv.next()) {
+            ("FREQ", Some(v)) => freq = Some(v.to_ascii_uppercase()),
+            ("BYMONTH", Some(v)) => month = v.parse::<i64>().ok().filter(|m| (1..=12).contains(m)),
+            ("BYDAY", Some(v)) => by_day = Some(v.to_ascii_uppercase()),
+            ("UNTIL", Some(v)) => {
+                let (until, _, _) = parse_date_time(v)?;
+                if days_from_civil(year, 1, 1) * 86400 > until {
+                    return None;
+                }
+            }
+            _ => {}
+        }
+    }
+    if freq.as_deref() != Some("YEARLY") {
+        return None;
+    }
+    let month = month?;
+    let by_day = by_day?;
+    let weekday = ["SU", "MO", "TU", "WE", "TH", "FR", "SA"]
+        .iter()
+        .position(|d| by_day.ends_with(d))? as i64;
+    let n = match &by_day[..by_day.len() - 2] {
+        "" => 1,
+        n => n.trim_start_matches('+').parse::<i64>().ok()?,
+    };
+    /* The rule has a BYMONTH, so the ordinal counts weekdays within a month. */
+    if n == 0 || !(-5..=5).contains(&n) {
+        return None;
+    }
+    if n > 0 {
+        let first = days_from_civil(year, month, 1);
+        Some(first + (weekday - day_of_week(first)).rem_euclid(7) + 7 * (n - 1))
+    } else {
+        let last = days_from_civil(year, month, days_in_month(year, month));
+        Some(last - (day_of_week(last) - weekday).rem_euclid(7) + 7 * (n + 1))
+    }
+}
+
+/// Parse a UTC offset like `+0200`, `-0500` or `+053000` into seconds.
+fn parse_utc_offset(value: &str) -> Option<i64> {
+    let value = value.trim();
+    let sign = match value.get(0..1)? {
+        "+" => 1,
+        "-" => -1,
+        _ => return None,
+    };
+    let hours = value.get(1..3)?.parse::<i64>().ok()?;
+    let minutes = value.get(3..5)?.parse::<i64>().ok()?;
+    let seconds = value
+        .get(5..7)
+        .and_then(|s| s.parse::<i64>().ok())
+        .unwrap_or(0);
+    Some(sign * (hours * 3600 + minutes * 60 + seconds))
+}
+
+fn utc_date_time(timestamp: UnixTimestamp) -> String {
+    let timestamp = timestamp as i64;
+    let (y, m, d) = civil_from_days(timestamp.div_euclid(86400));
+    let secs = timestamp.rem_euclid(86400);
+    format!(
+        "{:04}{:02}{:02}T{:02}{:02}{:02}Z",
+        y,
+        m,
+        d,
+        secs / 3600,
+        secs % 3600 / 60,
+        secs % 60
+    )
+}
+
+/* Calendar arithmetic from http://howardhinnant.github.io/date_algorithms.html */
+
+fn days_from_civil(y: i64, m: i64, d: i64) -> i64 {
+    let y = if m <= 2 { y - 1 } else { y };
+    let era = y.div_euclid(400);
+    let yoe = y - era * 400;
+    let doy = (153 * (if m > 2 { m - 3 } else { m + 9 }) + 2) / 5 + d - 1;
+    let doe = yoe * 365 + yoe / 4 - yoe / 100 + doy;
+    era * 146097 + doe - 719468
+}
+
+fn civil_from_days(z: i64) -> (i64, i64, i64) {
+    let z = z + 719468;
+    let era = z.div_euclid(146097);
+    let doe = z - era * 146097;
+    let yoe = (doe - doe / 1460 + doe / 36524 - doe / 146096) / 365;
+    let doy = doe - (365 * yoe + yoe / 4 - yoe / 100);
+    let mp = (5 * doy + 2) / 153;
+    let d = doy - (153 * mp + 2) / 5 + 1;
+    let m = if mp < 10 { mp + 3 } else { mp - 9 };
+    (
+        if m <= 2 {
+            yoe + era * 400 + 1
+        } else {
+            yoe + era * 400
+        },
+        m,
+        d,
+    )
+}
+
+/// 0 is Sunday.
+fn day_of_week(days: i64) -> i64 {
+    (days + 4).rem_euclid(7)
+}
+
+fn days_in_month(y: i64, m: i64) -> i64 {
+    match m {
+        2 if y % 4 == 0 && (y % 100 != 0 || y % 400 == 0) => 29,
+        2 => 28,
+        4 | 6 | 9 | 11 => 30,
+        _ => 31,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const INVITE: &str = "BEGIN:VCALENDAR\r
+PRODID:-//Google Inc//Google Calendar 70.9054//EN\r
+VERSION:2.0\r
+METHOD:REQUEST\r
+BEGIN:VTIMEZONE\r
+TZID:Europe/Berlin\r
+BEGIN:DAYLIGHT\r
+TZOFFSETFROM:+0100\r
+TZOFFSETTO:+0200\r
+TZNAME:CEST\r
+DTSTART:19700329T020000\r
+RRULE:FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU\r
+END:DAYLIGHT\r
+BEGIN:STANDARD\r
+TZOFFSETFROM:+0200\r
+TZOFFSETTO:+0100\r
+TZNAME:CET\r
+DTSTART:19701025T030000\r
+RRULE:FREQ=YEARLY;BYMONTH=10;BYDAY=-1SU\r
+END:STANDARD\r
+END:VTIMEZONE\r
+BEGIN:VEVENT\r
+DTSTART;TZID=Europe/Berlin:20201020T100000\r
+DTEND;TZID=Europe/Berlin:20201020T110000\r
+DTSTAMP:20201012T090000Z\r
+ORGANIZER;CN=Alice:mailto:alice@example.com\r
+UID:1234567890@example.com\r
+ATTENDEE;CUTYPE=INDIVIDUAL;ROLE=REQ-PARTICIPANT;PARTSTAT=NEEDS-ACTION;RSVP=\r
+ TRUE;CN=\"Doe, Bob\";X-NUM-GUESTS=0:mailto:bob@example.com\r
+SEQUENCE:0\r
+SUMMARY:Planning\\, part 2\r
+LOCATION:Room 1\r
+END:VEVENT\r
+END:VCALENDAR\r
+";
+
+    #[test]
+    fn test_icalendar_invite() {
+        let cal = Calendar::from_str(INVITE).unwrap();
+        assert!(cal.is_request());
+        assert_eq!(cal.events.len(), 1);
+        let event = &cal.events[0];
+        assert_eq!(event.summary.as_deref(), Some("Planning, part 2"));
+        assert_eq!(
+            event.attendees,
+            vec![Attendee {
+                address: "bob@example.com".to_string(),
+                name: Some("Doe, Bob".to_string()),
+                status: Some(PartStat::NeedsAction),
+            }]
+        );
+        /* 10:00 CEST */
+        assert_eq!(event.start.unwrap().timestamp, 1603180800);
+        /* 10:00 CET, after the switch on the last Sunday of October */
+        assert_eq!(
+            event_time(
+                &Property::parse("DTSTART;TZID=Europe/Berlin:20201026T100000").unwrap(),
+                &cal.timezones
+            )
+            .unwrap()
+            .timestamp,
+            1603702800
+        );
+
+        let reply = cal.reply("bob@example.com", PartStat::Accepted).unwrap();
+        assert!(reply.contains("METHOD:REPLY\r\n"));
+        assert!(reply.contains("TZID:Europe/Berlin\r\n"));
+        assert!(reply.lines().all(|l| l.len() <= LINE_LENGTH));
+        assert!(unfold(&reply).contains(
+            "ATTENDEE;CUTYPE=INDIVIDUAL;PARTSTAT=ACCEPTED;CN=\"Doe, Bob\";X-NUM-GUESTS=0:mailto:bob@example.com\r\n"
+        ));
+        let reply = Calendar::from_str(&reply).unwrap();
+        assert_eq!(reply.method.as_deref(), Some("REPLY"));
+        assert_eq!(reply.events[0].start, cal.events[0].start);
+        assert_eq!(
+            reply.events[0].attendees[0].status,
+            Some(PartStat::Accepted)
+        );
+    }
+
+    #[test]
+    fn test_icalendar_malformed() {
+        let cal = Calendar::from_str(
+            "BEGIN:VCALENDAR\r
+METHOD:REQUEST\r
+BEGIN:VEVENT\r
+ORGANIZER:\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}\r
+ATTENDEE:mailto:\u{e9}@example.com\r
+END:VEVENT\r
+END:VCALENDAR\r
+",
+        )
+        .unwrap();
+        let event = &cal.events[0];
+        assert_eq!(
+            event.organizer.as_ref().unwrap().address,
+            "\u{e9}\u{e9}\u{e9}\u{e9}\u{e9}"
+        );
+        assert_eq!(event.attendees[0].address, "\u{e9}@example.com");
+        assert_eq!(
+            yearly_rule_day("FREQ=YEARLY;BYMONTH=3;BYDAY=9999999999999999SU", 2020),
+            None
+        );
+        assert_eq!(
+            yearly_rule_day("FREQ=YEARLY;BYMONTH=99999999999999999;BYDAY=1SU", 2020),
+            None
+        );
+        assert_eq!(
+            yearly_rule_day("FREQ=YEARLY;BYMONTH=3;BYDAY=-1SU", 2020),
+            Some(days_from_civil(2020, 3, 29))
+        );
+    }
+}
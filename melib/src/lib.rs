@@ -57,38 +57,42 @@ pub mod dbg {
         };
     }
 
+    /// Log a value or a formatted message at `DEBUG` level, see [`logging`](./fn.log.html). Like
+    /// `std::dbg`, `debug!(expr)` returns the value of `expr`.
     #[allow(clippy::redundant_closure)]
     #[macro_export]
     macro_rules! debug {
         ($val:literal) => {
             {
-            if cfg!(feature="debug-tracing") {
-                log_tag!();
-                eprintln!($val);
+            if $crate::log_enabled(file!(), $crate::LoggingLevel::DEBUG) {
+                $crate::log_from(file!(), format!("{}:{} {}", line!(), column!(), $val), $crate::LoggingLevel::DEBUG);
             }
             $val
         }
         };
         ($val:expr) => {
-            if cfg!(feature="debug-tracing") {
-                let stringify = stringify!($val);
-                // Use of `match` here is intentional because it affects the lifetimes
-                // of temporaries - https://stackoverflow.com/a/48732525/1063961
-                match $val {
-                    tmp => {
-                        log_tag!();
-                        eprintln!("{} = {:?}", stringify, tmp);
-                        tmp
+            // Use of `match` here is intentional because it affects the lifetimes
+            // of temporaries - https://stackoverflow.com/a/48732525/1063961
+            match $val {
+                tmp => {
+                    if $crate::log_enabled(file!(), $crate::LoggingLevel::DEBUG) {
+                        $crate::log_from(
+                            file!(),
+                            format!("{}:{} {} = {:?}", line!(), column!(), stringify!($val), tmp),
+                            $crate::LoggingLevel::DEBUG,
+                        );
                     }
+                    tmp
                 }
-            } else {
-                $val
             }
         };
         ($fmt:literal, $($arg:tt)*) => {
-            if cfg!(feature="debug-tracing") {
-                log_tag!();
-                eprintln!($fmt, $($arg)*);
+            if $crate::log_enabled(file!(), $crate::LoggingLevel::DEBUG) {
+                $crate::log_from(
+                    file!(),
+                    format!("{}:{} {}", line!(), column!(), format_args!($fmt, $($arg)*)),
+                    $crate::LoggingLevel::DEBUG,
+                );
             }
         };
     }
@@ -98,6 +102,7 @@ pub mod dbg {
 pub mod text_processing;
 
 pub mod datetime;
+pub mod icalendar;
 pub use datetime::UnixTimestamp;
 
 #[macro_use]
@@ -133,8 +138,8 @@ pub mod sqlite3;
 #[macro_use]
 extern crate serde_derive;
 /* parser */
-extern crate data_encoding;
-extern crate encoding;
+pub extern crate data_encoding;
+pub extern crate encoding_rs;
 pub extern crate nom;
 
 #[macro_use]
@@ -19,11 +19,22 @@
  * along with meli. If not, see <http://www.gnu.org/licenses/>.
  */
 
+//! Leveled logging to a log file, with a maximum level per module and size based rotation.
+//!
+//! The module of a record is derived from the source file of the caller: `imap`, `jmap` and the
+//! other backend names for `melib/src/backends/*`, `smtp`, `melib` for the rest of this crate and
+//! `ui` for everything else.
+//!
+//! The most recent records are also kept in memory, see [`log_records_since`].
+
 use crate::shellexpand::ShellExpandTrait;
+use std::collections::{HashMap, VecDeque};
 use std::fs::OpenOptions;
 use std::io::{BufWriter, Write};
-use std::path::PathBuf;
-use std::sync::{Arc, Mutex};
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU8, Ordering};
+use std::sync::Mutex;
 
 #[derive(Copy, Clone, PartialEq, PartialOrd, Hash, Debug, Serialize, Deserialize)]
 pub enum LoggingLevel {
@@ -54,6 +65,27 @@ impl std::fmt::Display for LoggingLevel {
     }
 }
 
+impl std::str::FromStr for LoggingLevel {
+    type Err = crate::error::MeliError;
+    fn from_str(s: &str) -> crate::error::Result<Self> {
+        Ok(match s.to_ascii_uppercase().as_str() {
+            "OFF" => OFF,
+            "FATAL" => FATAL,
+            "ERROR" => ERROR,
+            "WARN" => WARN,
+            "INFO" => INFO,
+            "DEBUG" => DEBUG,
+            "TRACE" => TRACE,
+            _ => {
+                return Err(crate::error::MeliError::new(format!(
+                    "Unknown logging level `{}`, expected one of OFF, FATAL, ERROR, WARN, INFO, DEBUG or TRACE.",
+                    s
+                )))
+            }
+        })
+    }
+}
+
 impl Default for LoggingLevel {
     fn default() -> Self {
         LoggingLevel::INFO
@@ -62,65 +94,278 @@ impl Default for LoggingLevel {
 
 use LoggingLevel::*;
 
+/// Default size in bytes after which the log file is rotated.
+pub const LOG_MAX_SIZE: u64 = 5 * 1024 * 1024;
+/// Default number of rotated log files that are kept.
+pub const LOG_ROTATE: usize = 3;
+/// Number of recent records kept in memory.
+pub const LOG_BUFFER_SIZE: usize = 1000;
+
+/// A record kept in memory, see [`log_records_since`].
+#[derive(Clone, Debug)]
+pub struct LogRecord {
+    /// Sequence number, increasing by one with every record.
+    pub seq: u64,
+    pub timestamp: crate::datetime::UnixTimestamp,
+    pub level: LoggingLevel,
+    pub module: String,
+    pub message: String,
+}
+
+impl std::fmt::Display for LogRecord {
+    fn fmt(&self, f: &mut std::fmt::Formatter) -> std::fmt::Result {
+        write!(
+            f,
+            "{} [{}] {}: {}",
+            crate::datetime::timestamp_to_string(self.timestamp, None, false),
+            self.level,
+            self.module,
+            self.message
+        )
+    }
+}
+
 struct LoggingBackend {
-    dest: BufWriter<std::fs::File>,
+    path: PathBuf,
+    /// Opened on the first write.
+    dest: Option<BufWriter<std::fs::File>>,
+    size: u64,
     level: LoggingLevel,
+    module_levels: HashMap<String, LoggingLevel>,
+    max_size: u64,
+    rotate: usize,
+    records: VecDeque<LogRecord>,
+    next_seq: u64,
+}
+
+static LOG: Mutex<Option<LoggingBackend>> = Mutex::new(None);
+/// The most verbose level of any module, checked before taking the lock.
+static MAX_LEVEL: AtomicU8 = AtomicU8::new(INFO as u8);
+
+/// `$XDG_STATE_HOME/meli/meli.log`, where `$XDG_STATE_HOME` defaults to `~/.local/state`.
+pub fn default_log_path() -> PathBuf {
+    let state_home = match std::env::var_os("XDG_STATE_HOME") {
+        Some(dir) if !dir.is_empty() => PathBuf::from(dir),
+        _ => PathBuf::from(std::env::var_os("HOME").unwrap_or_default())
+            .join(".local")
+            .join("state"),
+    };
+    state_home.join("meli").join("meli.log")
 }
 
-thread_local!(static LOG: Arc<Mutex<LoggingBackend>> = Arc::new(Mutex::new({
-    let data_dir = xdg::BaseDirectories::with_prefix("meli").unwrap();
-    let log_file = OpenOptions::new().append(true) /* writes will append to a file instead of overwriting previous contents */
-        .create(true) /* a new file will be created if the file does not yet already exist.*/
-        .read(true)
-        .open(data_dir.place_data_file("meli.log").unwrap()).unwrap();
-    LoggingBackend {
-        dest: BufWriter::new(log_file),
+fn with_backend<T>(f: impl FnOnce(&mut LoggingBackend) -> T) -> T {
+    let mut lck = LOG.lock().unwrap_or_else(|err| err.into_inner());
+    let backend = lck.get_or_insert_with(|| LoggingBackend {
+        path: default_log_path(),
+        dest: None,
+        size: 0,
         level: LoggingLevel::default(),
-    }}))
-);
+        module_levels: HashMap::default(),
+        max_size: LOG_MAX_SIZE,
+        rotate: LOG_ROTATE,
+        records: VecDeque::new(),
+        next_seq: 0,
+    });
+    f(backend)
+}
 
-pub fn log<S: AsRef<str>>(val: S, level: LoggingLevel) {
-    LOG.with(|f| {
-        let mut b = f.lock().unwrap();
-        if level <= b.level {
-            b.dest
-                .write_all(
-                    crate::datetime::timestamp_to_string(crate::datetime::now(), None, false)
-                        .as_bytes(),
-                )
-                .unwrap();
-            b.dest.write_all(b" [").unwrap();
-            b.dest.write_all(level.to_string().as_bytes()).unwrap();
-            b.dest.write_all(b"]: ").unwrap();
-            b.dest.write_all(val.as_ref().as_bytes()).unwrap();
-            b.dest.write_all(b"\n").unwrap();
-            b.dest.flush().unwrap();
+impl LoggingBackend {
+    fn level_of(&self, module: &str) -> LoggingLevel {
+        self.module_levels
+            .get(module)
+            .cloned()
+            .unwrap_or(self.level)
+    }
+
+    fn update_max_level(&self) {
+        let max = self
+            .module_levels
+            .values()
+            .fold(self.level, |max, &l| if l > max { l } else { max });
+        MAX_LEVEL.store(max as u8, Ordering::Relaxed);
+    }
+
+    fn write(&mut self, record: &[u8]) -> std::io::Result<()> {
+        if self.max_size > 0 && self.size > 0 && self.size + record.len() as u64 > self.max_size {
+            self.dest = None;
+            rotate_files(&self.path, self.rotate)?;
+            self.size = 0;
         }
+        if self.dest.is_none() {
+            if let Some(parent) = self.path.parent() {
+                std::fs::create_dir_all(parent)?;
+            }
+            /* Debug records may contain message contents and server responses. */
+            let file = OpenOptions::new()
+                .append(true)
+                .create(true)
+                .mode(0o600)
+                .open(&self.path)?;
+            self.size = file.metadata()?.len();
+            self.dest = Some(BufWriter::new(file));
+        }
+        let dest = self.dest.as_mut().unwrap();
+        dest.write_all(record)?;
+        dest.flush()?;
+        self.size += record.len() as u64;
+        Ok(())
+    }
+}
+
+/// Shift `path.1` to `path.2` and so on, keeping `count` old files, and move `path` to `path.1`.
+fn rotate_files(path: &Path, count: usize) -> std::io::Result<()> {
+    let numbered = |i: usize| {
+        let mut p = path.as_os_str().to_os_string();
+        p.push(format!(".{}", i));
+        PathBuf::from(p)
+    };
+    if count == 0 {
+        return std::fs::remove_file(path);
+    }
+    for i in (1..count).rev() {
+        if numbered(i).exists() {
+            std::fs::rename(numbered(i), numbered(i + 1))?;
+        }
+    }
+    std::fs::rename(path, numbered(1))
+}
+
+/// The module a record logged from source file `file` belongs to.
+pub fn log_module(file: &str) -> &str {
+    let file = file.trim_end_matches(".rs");
+    if let Some(pos) = file.find("melib/src/") {
+        let rest = &file[pos + "melib/src/".len()..];
+        if let Some(backend) = rest.strip_prefix("backends/") {
+            return backend.split('/').next().unwrap_or("melib");
+        }
+        if rest == "smtp" {
+            return "smtp";
+        }
+        return "melib";
+    }
+    "ui"
+}
+
+/// Whether a record of `level` from source file `file` would be written.
+pub fn log_enabled(file: &str, level: LoggingLevel) -> bool {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return false;
+    }
+    with_backend(|b| level <= b.level_of(log_module(file)))
+}
+
+/// Write a record from source file `file`, if its module's level allows it. Use `log` or `debug!`
+/// instead.
+pub fn log_from<S: AsRef<str>>(file: &str, val: S, level: LoggingLevel) {
+    if level as u8 > MAX_LEVEL.load(Ordering::Relaxed) {
+        return;
+    }
+    let module = log_module(file);
+    with_backend(|b| {
+        if level > b.level_of(module) {
+            return;
+        }
+        let record = LogRecord {
+            seq: b.next_seq,
+            timestamp: crate::datetime::now(),
+            level,
+            module: module.to_string(),
+            message: val.as_ref().to_string(),
+        };
+        b.next_seq += 1;
+        /* There is nowhere to report failures to write to the log. */
+        let _ = b.write(format!("{}\n", record).as_bytes());
+        if b.records.len() == LOG_BUFFER_SIZE {
+            b.records.pop_front();
+        }
+        b.records.push_back(record);
     });
 }
 
+#[track_caller]
+pub fn log<S: AsRef<str>>(val: S, level: LoggingLevel) {
+    log_from(std::panic::Location::caller().file(), val, level);
+}
+
+/// The records kept in memory whose sequence number is at least `seq`, oldest first.
+pub fn log_records_since(seq: u64) -> Vec<LogRecord> {
+    with_backend(|b| b.records.iter().filter(|r| r.seq >= seq).cloned().collect())
+}
+
 pub fn get_log_level() -> LoggingLevel {
-    let mut level = INFO;
-    LOG.with(|f| {
-        level = f.lock().unwrap().level;
-    });
-    level
+    with_backend(|b| b.level)
 }
 
 pub fn change_log_dest(path: PathBuf) {
-    LOG.with(|f| {
+    with_backend(|b| {
         let path = path.expand(); // expand shell stuff
-        let mut backend = f.lock().unwrap();
-        backend.dest = BufWriter::new(OpenOptions::new().append(true) /* writes will append to a file instead of overwriting previous contents */
-                         .create(true) /* a new file will be created if the file does not yet already exist.*/
-                         .read(true)
-                         .open(path).unwrap());
+        if b.path != path {
+            b.path = path;
+            b.dest = None;
+        }
     });
 }
 
 pub fn change_log_level(new_val: LoggingLevel) {
-    LOG.with(|f| {
-        let mut backend = f.lock().unwrap();
-        backend.level = new_val;
+    with_backend(|b| {
+        b.level = new_val;
+        b.update_max_level();
+    });
+}
+
+/// Set the maximum level of `module`, or make it follow the global level if `new_val` is `None`.
+pub fn change_module_log_level(module: &str, new_val: Option<LoggingLevel>) {
+    with_backend(|b| {
+        if let Some(new_val) = new_val {
+            b.module_levels.insert(module.to_string(), new_val);
+        } else {
+            b.module_levels.remove(module);
+        }
+        b.update_max_level();
+    });
+}
+
+/// Replace the maximum levels of all modules.
+pub fn change_module_log_levels<I: IntoIterator<Item = (String, LoggingLevel)>>(levels: I) {
+    with_backend(|b| {
+        b.module_levels = levels.into_iter().collect();
+        b.update_max_level();
+    });
+}
+
+/// Rotate the log file once it grows larger than `max_size` bytes, keeping `rotate` old files. A
+/// `max_size` of 0 disables rotation.
+pub fn change_log_rotation(max_size: u64, rotate: usize) {
+    with_backend(|b| {
+        b.max_size = max_size;
+        b.rotate = rotate;
     });
 }
+
+#[test]
+fn test_log_module_and_rotation() {
+    assert_eq!(log_module("melib/src/backends/imap/connection.rs"), "imap");
+    assert_eq!(log_module("melib/src/backends/jmap.rs"), "jmap");
+    assert_eq!(log_module("melib/src/smtp.rs"), "smtp");
+    assert_eq!(log_module("melib/src/email/parser.rs"), "melib");
+    assert_eq!(log_module("src/components/mail/listing.rs"), "ui");
+
+    let dir = std::env::temp_dir().join(format!("meli-log-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("meli.log");
+    for i in 0..4 {
+        std::fs::write(&path, i.to_string()).unwrap();
+        rotate_files(&path, 2).unwrap();
+    }
+    assert!(!path.exists());
+    assert_eq!(
+        std::fs::read_to_string(dir.join("meli.log.1")).unwrap(),
+        "3"
+    );
+    assert_eq!(
+        std::fs::read_to_string(dir.join("meli.log.2")).unwrap(),
+        "2"
+    );
+    assert!(!dir.join("meli.log.3").exists());
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -48,6 +48,12 @@ pub enum Query {
     /* * * * */
     Flags(Vec<String>),
     HasAttachment,
+    /// Matches attachments whose file name contains the value.
+    AttachmentName(String),
+    /// Matches attachments whose MIME type contains the value, e.g. `image` or `application/pdf`.
+    AttachmentType(String),
+    /// Matches envelopes whose `List-Id` header contains the value, e.g. `debian-devel`.
+    List(String),
     And(Box<Query>, Box<Query>),
     Or(Box<Query>, Box<Query>),
     Not(Box<Query>),
@@ -60,6 +66,24 @@ pub trait QueryTrait {
 impl QueryTrait for crate::Envelope {
     fn is_match(&self, query: &Query) -> bool {
         use Query::*;
+        let header_contains = |name: &str, s: &str| {
+            self.other_headers()
+                .get(name)
+                .map(|v| v.contains(s))
+                .unwrap_or(false)
+        };
+        /* Match both the A-label and U-label forms of internationalized domains. */
+        let address_contains = |name: &str, s: &str| {
+            use crate::email::address::{domains_to_ascii, domains_to_unicode};
+            self.other_headers()
+                .get(name)
+                .map(|v| {
+                    v.contains(s)
+                        || domains_to_unicode(v).contains(s)
+                        || v.contains(&domains_to_ascii(s))
+                })
+                .unwrap_or(false)
+        };
         match query {
             Before(timestamp) => self.date() < *timestamp,
             After(timestamp) => self.date() > *timestamp,
@@ -70,10 +94,10 @@ impl QueryTrait for crate::Envelope {
                 self.date() > timestamp.saturating_sub(60 * 60 * 24)
                     && self.date() < *timestamp + 60 * 60 * 24
             }
-            From(s) => self.other_headers()["From"].contains(s),
-            To(s) => self.other_headers()["To"].contains(s),
-            Cc(s) => self.other_headers()["Cc"].contains(s),
-            Bcc(s) => self.other_headers()["Bcc"].contains(s),
+            From(s) => address_contains("From", s),
+            To(s) => address_contains("To", s),
+            Cc(s) => address_contains("Cc", s),
+            Bcc(s) => address_contains("Bcc", s),
             AllAddresses(s) => {
                 self.is_match(&From(s.clone()))
                     || self.is_match(&To(s.clone()))
@@ -81,7 +105,10 @@ impl QueryTrait for crate::Envelope {
                     || self.is_match(&Bcc(s.clone()))
             }
             Flags(v) => v.iter().any(|s| self.flags() == s.as_str()),
-            Subject(s) => self.other_headers()["Subject"].contains(s),
+            Subject(s) => header_contains("Subject", s),
+            List(s) => crate::email::list_management::list_id_header(self)
+                .map(|v| v.to_lowercase().contains(&s.to_lowercase()))
+                .unwrap_or(false),
             HasAttachment => self.has_attachments(),
             And(q_a, q_b) => self.is_match(q_a) && self.is_match(q_b),
             Or(q_a, q_b) => self.is_match(q_a) || self.is_match(q_b),
@@ -144,6 +171,82 @@ pub mod query_parser {
         .map(Query::Bcc)
     }
 
+    fn body<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(match_literal("body:")),
+            whitespace_wrap(literal()),
+        )
+        .map(Query::Body)
+    }
+
+    fn attachment_name<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(match_literal_anycase("filename:")),
+            whitespace_wrap(literal()),
+        )
+        .map(Query::AttachmentName)
+    }
+
+    fn attachment_type<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(either(
+                match_literal_anycase("type:"),
+                match_literal_anycase("mimetype:"),
+            )),
+            whitespace_wrap(literal()),
+        )
+        .map(Query::AttachmentType)
+    }
+
+    fn list<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(match_literal_anycase("list:")),
+            whitespace_wrap(literal()),
+        )
+        .map(Query::List)
+    }
+
+    /// A `YYYY-MM-DD` date, in local time.
+    fn date<'a>() -> impl Parser<'a, UnixTimestamp> {
+        move |input| {
+            let (rest, s) = word().parse(input)?;
+            match crate::datetime::timestamp_from_string(s.as_str(), "%Y-%m-%d") {
+                Ok(Some(timestamp)) => Ok((rest, timestamp)),
+                _ => Err(input),
+            }
+        }
+    }
+
+    fn before<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(match_literal_anycase("before:")),
+            whitespace_wrap(date()),
+        )
+        .map(Query::Before)
+    }
+
+    fn after<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(either(
+                match_literal_anycase("after:"),
+                match_literal_anycase("since:"),
+            )),
+            whitespace_wrap(date()),
+        )
+        .map(Query::After)
+    }
+
+    fn on<'a>() -> impl Parser<'a, Query> {
+        prefix(
+            whitespace_wrap(either(
+                match_literal_anycase("on:"),
+                match_literal_anycase("date:"),
+            )),
+            whitespace_wrap(date()),
+        )
+        .map(Query::On)
+    }
+
     fn or<'a>() -> impl Parser<'a, Query> {
         move |input| {
             whitespace_wrap(match_literal_anycase("or"))
@@ -180,7 +283,15 @@ pub mod query_parser {
     }
 
     fn literal<'a>() -> impl Parser<'a, String> {
-        move |input| either(quoted_string(), string()).parse(input)
+        move |input| either(quoted_string(), word()).parse(input)
+    }
+
+    /// A run of characters up to whitespace, a parenthesis or a double quote.
+    fn word<'a>() -> impl Parser<'a, String> {
+        one_or_more(pred(any_char, |c| {
+            !c.is_whitespace() && !['(', ')', '"'].contains(c)
+        }))
+        .map(|r| r.into_iter().collect())
     }
 
     fn parentheses_query<'a>() -> impl Parser<'a, Query> {
@@ -198,23 +309,24 @@ pub mod query_parser {
         move |input| {
             whitespace_wrap(either(
                 either(
-                    match_literal_anycase("flags:"),
-                    match_literal_anycase("tags:"),
+                    either(
+                        match_literal_anycase("flags:"),
+                        match_literal_anycase("flag:"),
+                    ),
+                    either(
+                        match_literal_anycase("tags:"),
+                        match_literal_anycase("tag:"),
+                    ),
                 ),
                 match_literal_anycase("is:"),
             ))
             .parse(input)
-            .and_then(|(rest, _)| {
-                map(one_or_more(pred(any_char, |c| *c != ' ')), |chars| {
-                    chars.into_iter().collect::<String>()
-                })
-                .parse(rest)
-            })
+            .and_then(|(rest, _)| word().parse(rest))
             .and_then(|(rest, flags_list)| {
                 if let Ok(r) = flags_list
                     .split(',')
                     .map(|t| {
-                        either(quoted_string(), string())
+                        either(quoted_string(), word())
                             .parse_complete(t)
                             .map(|(_, r)| r)
                     })
@@ -231,6 +343,13 @@ pub mod query_parser {
 
     /// Parser from `String` to `Query`.
     ///
+    /// Terms are `from:`, `to:`, `cc:`, `bcc:`, `subject:` and `body:` followed by a word or a
+    /// quoted string, `before:`, `after:` and `on:` followed by a `YYYY-MM-DD` date, `flag:`,
+    /// `tag:` and `is:` followed by a comma separated list, `has:attachment`, `filename:` and `type:`
+    /// followed by part of an attachment's file name or MIME type, `list:` followed by part of a
+    /// mailing list id and bare words which match all text. Terms can be combined with `and`, `or`, `not` and parentheses; terms
+    /// without an operator between them must all match.
+    ///
     /// # Invocation
     /// ```
     /// use melib::search::query;
@@ -250,6 +369,13 @@ pub mod query_parser {
                 .or_else(|_| cc().parse(input))
                 .or_else(|_| bcc().parse(input))
                 .or_else(|_| subject().parse(input))
+                .or_else(|_| body().parse(input))
+                .or_else(|_| attachment_name().parse(input))
+                .or_else(|_| attachment_type().parse(input))
+                .or_else(|_| list().parse(input))
+                .or_else(|_| before().parse(input))
+                .or_else(|_| after().parse(input))
+                .or_else(|_| on().parse(input))
                 .or_else(|_| flags().parse(input))
                 .or_else(|_| has_attachment().parse(input))
             {
@@ -282,7 +408,8 @@ pub mod query_parser {
             } else if let Ok((rest, query_b)) = or().parse(rest) {
                 Ok((rest, Or(Box::new(query_a), Box::new(query_b))))
             } else if let Ok((rest, query_b)) = query().parse(rest) {
-                Ok((rest, Or(Box::new(query_a), Box::new(query_b))))
+                /* Terms without an operator between them must all match */
+                Ok((rest, And(Box::new(query_a), Box::new(query_b))))
             } else {
                 Ok((rest, query_a))
             }
@@ -380,6 +507,48 @@ pub mod query_parser {
             Ok(("", Flags(vec!["f".to_string()]))),
             query().parse_complete("tags:f")
         );
+        let before = crate::datetime::timestamp_from_string("2021-01-01", "%Y-%m-%d")
+            .unwrap()
+            .unwrap();
+        assert_eq!(
+            Ok((
+                "",
+                And(
+                    Box::new(From("alice@example.com".to_string())),
+                    Box::new(And(
+                        Box::new(Subject("foo bar".to_string())),
+                        Box::new(And(
+                            Box::new(Before(before)),
+                            Box::new(And(
+                                Box::new(Flags(vec!["unseen".to_string()])),
+                                Box::new(Flags(vec!["work".to_string()]))
+                            ))
+                        ))
+                    ))
+                )
+            )),
+            query().parse_complete(
+                "from:alice@example.com subject:\"foo bar\" before:2021-01-01 flag:unseen tag:work"
+            )
+        );
+        assert_eq!(
+            Ok(("", AllText("before:yesterday".to_string()))),
+            query().parse_complete("before:yesterday")
+        );
+        assert_eq!(
+            Ok((
+                "",
+                And(
+                    Box::new(AttachmentName("pdf".to_string())),
+                    Box::new(AttachmentType("image".to_string()))
+                )
+            )),
+            query().parse_complete("filename:pdf type:image")
+        );
+        assert_eq!(
+            Ok(("", List("debian-devel".to_string()))),
+            query().parse_complete("list:debian-devel")
+        );
     }
 }
 
@@ -71,7 +71,7 @@
  *```
  */
 
-use crate::connections::{lookup_ipv4, Connection};
+use crate::connections::{lookup_ipv4, timeout, Connection, ConnectionPolicy};
 use crate::email::{parser::BytesExt, Address, Envelope};
 use crate::error::{MeliError, Result, ResultIntoMeliError};
 use futures::io::{AsyncReadExt, AsyncWriteExt};
@@ -81,7 +81,6 @@ use smol::unblock;
 use smol::Async as AsyncWrapper;
 use std::borrow::Cow;
 use std::convert::TryFrom;
-use std::net::TcpStream;
 use std::process::Command;
 
 /// Kind of server security (StartTLS/TLS/None) the client should attempt
@@ -185,6 +184,9 @@ pub struct SmtpServerConf {
     pub security: SmtpSecurity,
     #[serde(default)]
     pub extensions: SmtpExtensionSupport,
+    /// Set from the settings of the account that sends the message.
+    #[serde(skip)]
+    pub connection_policy: ConnectionPolicy,
 }
 
 //example: "SIZE 52428800", "8BITMIME", "PIPELINING", "CHUNKING", "PRDR",
@@ -243,8 +245,17 @@ pub struct SmtpConnection {
 }
 
 impl SmtpConnection {
-    /// Performs connection and if configured: TLS negotiation and SMTP AUTH
-    pub async fn new_connection(mut server_conf: SmtpServerConf) -> Result<Self> {
+    /// Performs connection and if configured: TLS negotiation and SMTP AUTH, retrying after
+    /// network errors as set in `server_conf.connection_policy`.
+    pub async fn new_connection(server_conf: SmtpServerConf) -> Result<Self> {
+        let policy = server_conf.connection_policy;
+        policy
+            .retry(|| Self::connect_once(server_conf.clone()))
+            .await
+    }
+
+    async fn connect_once(mut server_conf: SmtpServerConf) -> Result<Self> {
+        let read_timeout = server_conf.connection_policy.read_timeout;
         let path = &server_conf.hostname;
         let mut res = String::with_capacity(8 * 1024);
         let stream = match server_conf.security {
@@ -266,16 +277,15 @@ impl SmtpConnection {
                     .chain_err_kind(crate::error::ErrorKind::Network)?;
 
                 let addr = lookup_ipv4(path, server_conf.port)?;
-                let mut socket = AsyncWrapper::new(Connection::Tcp(
-                    TcpStream::connect_timeout(&addr, std::time::Duration::new(4, 0))
-                        .chain_err_kind(crate::error::ErrorKind::Network)?,
-                ))
-                .chain_err_kind(crate::error::ErrorKind::Network)?;
+                let mut socket =
+                    AsyncWrapper::new(server_conf.connection_policy.connect_tcp(&addr)?)
+                        .chain_err_kind(crate::error::ErrorKind::Network)?;
                 let pre_ehlo_extensions_reply = read_lines(
                     &mut socket,
                     &mut res,
                     Some((ReplyCode::_220, &[])),
                     &mut String::new(),
+                    read_timeout,
                 )
                 .await?;
                 drop(pre_ehlo_extensions_reply);
@@ -303,6 +313,7 @@ impl SmtpConnection {
                         &mut res,
                         Some((ReplyCode::_250, &[])),
                         &mut String::new(),
+                        read_timeout,
                     )
                     .await?;
                     drop(pre_tls_extensions_reply);
@@ -316,6 +327,7 @@ impl SmtpConnection {
                         &mut res,
                         Some((ReplyCode::_220, &[])),
                         &mut String::new(),
+                        read_timeout,
                     )
                     .await?;
                     //debug!(post_starttls_extensions_reply);
@@ -362,17 +374,15 @@ impl SmtpConnection {
             }
             SmtpSecurity::None => {
                 let addr = lookup_ipv4(path, server_conf.port)?;
-                let mut ret = AsyncWrapper::new(Connection::Tcp(
-                    TcpStream::connect_timeout(&addr, std::time::Duration::new(4, 0))
-                        .chain_err_kind(crate::error::ErrorKind::Network)?,
-                ))
-                .chain_err_kind(crate::error::ErrorKind::Network)?;
+                let mut ret = AsyncWrapper::new(server_conf.connection_policy.connect_tcp(&addr)?)
+                    .chain_err_kind(crate::error::ErrorKind::Network)?;
                 res.clear();
                 let reply = read_lines(
                     &mut ret,
                     &mut res,
                     Some((ReplyCode::_220, &[])),
                     &mut String::new(),
+                    read_timeout,
                 )
                 .await?;
                 let code = reply.code;
@@ -578,6 +588,7 @@ impl SmtpConnection {
             ret,
             expected_reply_code,
             &mut self.read_buffer,
+            self.server_conf.connection_policy.read_timeout,
         )
         .await
     }
@@ -619,24 +630,54 @@ impl SmtpConnection {
         if tos.is_empty() {
             return Err(MeliError::new("SMTP submission was aborted because there was no e-mail address found in the To: header field. Consider adding recipients."));
         }
-        let mut current_command: SmallVec<[&[u8]; 16]> = SmallVec::new();
-        //first step in the procedure is the MAIL command.
-        // MAIL FROM:<reverse-path> [SP <mail-parameters> ] <CRLF>
-        current_command.push(b"MAIL FROM:<");
-        if !envelope_from.is_empty() {
-            current_command.push(envelope_from.trim().as_bytes());
+        let smtputf8 = self.server_conf.extensions.smtputf8;
+        let from = if !envelope_from.is_empty() {
+            envelope_from.trim().to_string()
         } else {
             if envelope.from().is_empty() {
                 return Err(MeliError::new("SMTP submission was aborted because there was no e-mail address found in the From: header field. Consider adding a valid value or setting `envelope_from` in SMTP client settings"));
             } else if envelope.from().len() != 1 {
                 return Err(MeliError::new("SMTP submission was aborted because there was more than one e-mail address found in the From: header field. Consider setting `envelope_from` in SMTP client settings"));
             }
-            current_command.push(envelope.from()[0].address_spec_raw().trim());
+            String::from_utf8_lossy(envelope.from()[0].address_spec_raw().trim()).to_string()
+        };
+        let (from, mut needs_smtputf8) = envelope_address(&from, smtputf8)?;
+        let rcpts = tos
+            .iter()
+            .map(|addr| {
+                envelope_address(
+                    &String::from_utf8_lossy(addr.address_spec_raw().trim()),
+                    smtputf8,
+                )
+            })
+            .collect::<Result<Vec<(String, bool)>>>()?;
+        needs_smtputf8 |= rcpts.iter().any(|(_, utf8)| *utf8);
+        let headers_end = mail
+            .find("\r\n\r\n")
+            .or_else(|| mail.find("\n\n"))
+            .unwrap_or_else(|| mail.len());
+        if !mail[..headers_end].is_ascii() {
+            if smtputf8 {
+                needs_smtputf8 = true;
+            } else {
+                crate::log(
+                    "SMTP server does not support SMTPUTF8 but the message has non-ASCII header fields; it might be rejected or mangled.",
+                    crate::LoggingLevel::WARN,
+                );
+            }
         }
+        let mut current_command: SmallVec<[&[u8]; 16]> = SmallVec::new();
+        //first step in the procedure is the MAIL command.
+        // MAIL FROM:<reverse-path> [SP <mail-parameters> ] <CRLF>
+        current_command.push(b"MAIL FROM:<");
+        current_command.push(from.as_bytes());
         current_command.push(b">");
         if self.server_conf.extensions.prdr {
             current_command.push(b" PRDR");
         }
+        if needs_smtputf8 {
+            current_command.push(b" SMTPUTF8");
+        }
         self.send_command(&current_command).await?;
         current_command.clear();
         if !self.server_conf.extensions.pipelining {
@@ -651,10 +692,10 @@ impl SmtpConnection {
         //return a reply indicating whether the failure is permanent (i.e., will occur again if
         //the client tries to send the same address again) or temporary (i.e., the address might
         //be accepted if the client tries again later).
-        for addr in tos {
+        for (addr, _) in rcpts.iter() {
             current_command.clear();
             current_command.push(b"RCPT TO:<");
-            current_command.push(addr.address_spec_raw().trim());
+            current_command.push(addr.as_bytes());
             if let Some(dsn_notify) = dsn_notify.as_ref() {
                 current_command.push(b"> NOTIFY=");
                 current_command.push(dsn_notify.as_bytes());
@@ -952,11 +993,38 @@ impl<'s> Reply<'s> {
     }
 }
 
+/// The form of `email` to use in `MAIL FROM` and `RCPT TO` commands, and whether it needs
+/// SMTPUTF8. Without SMTPUTF8, non-ASCII domains are converted to A-labels and non-ASCII local
+/// parts are an error.
+fn envelope_address(email: &str, smtputf8: bool) -> Result<(String, bool)> {
+    if email.is_ascii() {
+        return Ok((email.to_string(), false));
+    }
+    if smtputf8 {
+        return Ok((email.to_string(), true));
+    }
+    match email.rfind('@') {
+        Some(pos) if email[..pos].is_ascii() => Ok((
+            format!(
+                "{}@{}",
+                &email[..pos],
+                crate::email::address::domain_to_ascii(&email[pos + 1..])?
+            ),
+            false,
+        )),
+        _ => Err(MeliError::new(format!(
+            "SMTP submission was aborted because {} has a non-ASCII local part and the server does not support SMTPUTF8.",
+            email
+        ))),
+    }
+}
+
 async fn read_lines<'r>(
     _self: &mut (impl futures::io::AsyncRead + std::marker::Unpin),
     ret: &'r mut String,
     expected_reply_code: Option<(ReplyCode, &[ReplyCode])>,
     buffer: &mut String,
+    read_timeout: Option<std::time::Duration>,
 ) -> Result<Reply<'r>> {
     let mut buf: [u8; 1024] = [0; 1024];
     ret.clear();
@@ -995,7 +1063,7 @@ async fn read_lines<'r>(
             }
             last_line_idx += pos + "\r\n".len();
         }
-        match _self.read(&mut buf).await {
+        match timeout(read_timeout, _self.read(&mut buf)).await? {
             Ok(0) => break,
             Ok(b) => {
                 ret.push_str(unsafe { std::str::from_utf8_unchecked(&buf[0..b]) });
@@ -0,0 +1,182 @@
+/*
+ * meli - text_processing crate.
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Recognise unified diffs in text, e.g. patches sent with `git send-email`.
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum DiffLine {
+    /// `diff --git`, `index`, `---`, `+++` and other file headers
+    Header,
+    /// `@@ -1,2 +1,3 @@`
+    Hunk,
+    Added,
+    Removed,
+    Context,
+}
+
+/// Prefixes of extended header lines of a file diff.
+const FILE_HEADERS: &[&str] = &[
+    "index ",
+    "--- ",
+    "+++ ",
+    "new file mode",
+    "deleted file mode",
+    "old mode",
+    "new mode",
+    "similarity index",
+    "rename ",
+    "copy ",
+    "Binary files",
+];
+
+/// Returns true if `text` contains a unified diff.
+pub fn looks_like_patch(text: &str) -> bool {
+    let mut prev_minus = false;
+    for line in text.lines() {
+        if line.starts_with("diff --git ") || (prev_minus && line.starts_with("+++ ")) {
+            return true;
+        }
+        prev_minus = line.starts_with("--- ");
+    }
+    false
+}
+
+/// Classify each line of `lines`. Lines that are not part of a diff are `None`.
+///
+/// Lines starting with `⤷` are continuations of long lines that were split by the pager and get
+/// the kind of the line they continue.
+pub fn diff_line_kinds<S: AsRef<str>>(lines: &[S]) -> Vec<Option<DiffLine>> {
+    let mut ret: Vec<Option<DiffLine>> = Vec::with_capacity(lines.len());
+    let mut in_diff = false;
+    for line in lines.iter().map(AsRef::as_ref) {
+        if line.starts_with('⤷') {
+            let prev = ret.last().cloned().unwrap_or(None);
+            ret.push(prev);
+            continue;
+        }
+        if line.starts_with("diff ") {
+            in_diff = true;
+            ret.push(Some(DiffLine::Header));
+            continue;
+        }
+        if !in_diff {
+            if line.starts_with("+++ ") && ret.last() == Some(&Some(DiffLine::Header)) {
+                in_diff = true;
+                ret.push(Some(DiffLine::Header));
+            } else if line.starts_with("--- ") {
+                /* Might be the start of a diff without a `diff` line, see above. */
+                ret.push(Some(DiffLine::Header));
+            } else {
+                ret.push(None);
+            }
+            continue;
+        }
+        let kind = if line == "-- " {
+            /* signature separator */
+            None
+        } else if line.starts_with("@@") {
+            Some(DiffLine::Hunk)
+        } else if FILE_HEADERS.iter().any(|h| line.starts_with(h)) {
+            Some(DiffLine::Header)
+        } else if line.starts_with('+') {
+            Some(DiffLine::Added)
+        } else if line.starts_with('-') {
+            Some(DiffLine::Removed)
+        } else if line.starts_with(' ') || line.starts_with('\\') {
+            Some(DiffLine::Context)
+        } else {
+            None
+        };
+        if kind.is_none() {
+            in_diff = false;
+        }
+        ret.push(kind);
+    }
+    /* A `---` line that wasn't followed by `+++` is not a diff header. */
+    for i in 0..ret.len() {
+        if ret[i] == Some(DiffLine::Header)
+            && lines[i].as_ref().starts_with("--- ")
+            && (i == 0 || ret[i - 1].is_none())
+            && lines
+                .get(i + 1)
+                .map(|l| !l.as_ref().starts_with("+++ "))
+                .unwrap_or(true)
+        {
+            ret[i] = None;
+        }
+    }
+    ret
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_diff_line_kinds() {
+        let text = "Fix the thing.
+
+- a list item
+---
+ src/lib.rs | 2 +-
+ 1 file changed, 1 insertion(+), 1 deletion(-)
+
+diff --git a/src/lib.rs b/src/lib.rs
+index 1234567..89abcde 100644
+--- a/src/lib.rs
++++ b/src/lib.rs
+@@ -1,3 +1,3 @@
+ fn main() {
+-    println!(\"hello\");
++    println!(\"world\");
+ }
+-- \n2.28.0
+";
+        assert!(looks_like_patch(text));
+        assert!(!looks_like_patch("- a list\n- b list\n"));
+        let lines = text.lines().collect::<Vec<&str>>();
+        let kinds = diff_line_kinds(&lines);
+        use DiffLine::*;
+        assert_eq!(
+            kinds,
+            vec![
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                None,
+                Some(Header),
+                Some(Header),
+                Some(Header),
+                Some(Header),
+                Some(Hunk),
+                Some(Context),
+                Some(Removed),
+                Some(Added),
+                Some(Context),
+                None,
+                None,
+            ]
+        );
+    }
+}
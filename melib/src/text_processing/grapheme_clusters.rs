@@ -30,7 +30,7 @@
 */
 
 use super::types::Reflow;
-use super::wcwidth::{wcwidth, CodePointsIter};
+use super::wcwidth::{grapheme_cluster_width, CodePointsIter};
 extern crate unicode_segmentation;
 use self::unicode_segmentation::UnicodeSegmentation;
 
@@ -52,12 +52,9 @@ pub trait TextProcessing: UnicodeSegmentation + CodePointsIter {
     }
 
     fn grapheme_width(&self) -> usize {
-        let mut count = 0;
-        for c in self.code_points() {
-            count += wcwidth(c).unwrap_or(0);
-        }
-
-        count
+        UnicodeSegmentation::graphemes(self, true)
+            .map(grapheme_cluster_width)
+            .sum()
     }
 
     fn grapheme_len(&self) -> usize {
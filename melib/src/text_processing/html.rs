@@ -0,0 +1,929 @@
+/*
+ * meli - text_processing/html.rs
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Render `text/html` documents as plain text.
+ *
+ * This is a forgiving renderer meant for e-mail bodies, not a conforming HTML parser. It
+ * understands paragraphs, headings, lists, blockquotes, preformatted text, tables and links.
+ * Links are printed as footnotes (`text[1]`) and the link targets are collected in
+ * [`HtmlText::links`](struct.HtmlText.html#structfield.links) and appended to the output.
+ */
+
+use super::TextProcessing;
+use std::borrow::Cow;
+
+/// Plain text rendering of an HTML document.
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct HtmlText {
+    /// The rendered text, including the link footnotes.
+    pub text: String,
+    /// Link targets in the order their footnotes appear in `text`, i.e. footnote `[n]` refers to
+    /// `links[n - 1]`.
+    pub links: Vec<String>,
+}
+
+/// Render `html` as plain text wrapped at `width` columns.
+pub fn html_to_text(html: &str, width: usize) -> HtmlText {
+    let width = std::cmp::max(width, 20);
+    let root = parse(html);
+    let mut renderer = Renderer { links: vec![] };
+    let lines = renderer.block(&root, width);
+    let mut text = String::with_capacity(html.len() / 2);
+    let mut blank = true;
+    for l in lines {
+        let l = l.trim_end();
+        if l.is_empty() {
+            if !blank {
+                text.push('\n');
+            }
+            blank = true;
+            continue;
+        }
+        blank = false;
+        text.push_str(l);
+        text.push('\n');
+    }
+    while text.ends_with("\n\n") {
+        text.pop();
+    }
+    if !renderer.links.is_empty() {
+        if !text.is_empty() {
+            text.push('\n');
+        }
+        for (i, l) in renderer.links.iter().enumerate() {
+            text.push_str(&format!("[{}] {}\n", i + 1, l));
+        }
+    }
+    HtmlText {
+        text,
+        links: renderer.links,
+    }
+}
+
+#[derive(Debug)]
+enum Node {
+    Text(String),
+    Element {
+        name: String,
+        attrs: Vec<(String, String)>,
+        children: Vec<Node>,
+    },
+}
+
+impl Node {
+    fn attr(&self, key: &str) -> Option<&str> {
+        match self {
+            Node::Element { attrs, .. } => attrs
+                .iter()
+                .find(|(k, _)| k == key)
+                .map(|(_, v)| v.as_str()),
+            Node::Text(_) => None,
+        }
+    }
+
+    fn children(&self) -> &[Node] {
+        match self {
+            Node::Element { children, .. } => children,
+            Node::Text(_) => &[],
+        }
+    }
+
+    fn name(&self) -> &str {
+        match self {
+            Node::Element { name, .. } => name,
+            Node::Text(_) => "",
+        }
+    }
+}
+
+const VOID_ELEMENTS: &[&str] = &[
+    "area", "base", "br", "col", "embed", "hr", "img", "input", "link", "meta", "param", "source",
+    "track", "wbr",
+];
+
+/* Elements whose contents are not displayed at all. */
+const SKIPPED_ELEMENTS: &[&str] = &[
+    "head", "script", "style", "template", "title", "noscript", "object", "svg",
+];
+
+const BLOCK_ELEMENTS: &[&str] = &[
+    "address",
+    "article",
+    "aside",
+    "blockquote",
+    "body",
+    "center",
+    "dd",
+    "details",
+    "dialog",
+    "div",
+    "dl",
+    "dt",
+    "fieldset",
+    "figcaption",
+    "figure",
+    "footer",
+    "form",
+    "h1",
+    "h2",
+    "h3",
+    "h4",
+    "h5",
+    "h6",
+    "header",
+    "hr",
+    "html",
+    "li",
+    "main",
+    "nav",
+    "ol",
+    "p",
+    "pre",
+    "section",
+    "summary",
+    "table",
+    "tbody",
+    "td",
+    "tfoot",
+    "th",
+    "thead",
+    "tr",
+    "ul",
+];
+
+fn is_block(name: &str) -> bool {
+    BLOCK_ELEMENTS.contains(&name)
+}
+
+/// Returns true if an open `open` element is implicitly closed when `new` starts.
+fn implicitly_closes(open: &str, new: &str) -> bool {
+    match open {
+        "p" => is_block(new),
+        "li" => new == "li",
+        "dt" | "dd" => new == "dt" || new == "dd",
+        "td" | "th" => matches!(new, "td" | "th" | "tr" | "tbody" | "thead" | "tfoot"),
+        "tr" => matches!(new, "tr" | "tbody" | "thead" | "tfoot"),
+        "thead" | "tbody" | "tfoot" => matches!(new, "tbody" | "thead" | "tfoot"),
+        "option" => new == "option",
+        _ => false,
+    }
+}
+
+/* Parsing */
+
+fn parse(input: &str) -> Node {
+    /* Stack of open elements; stack[0] is the document root. */
+    let mut stack: Vec<Node> = vec![Node::Element {
+        name: String::from("#root"),
+        attrs: vec![],
+        children: vec![],
+    }];
+
+    fn close_top(stack: &mut Vec<Node>) {
+        if stack.len() > 1 {
+            let node = stack.pop().unwrap();
+            if let Some(Node::Element { children, .. }) = stack.last_mut() {
+                children.push(node);
+            }
+        }
+    }
+
+    fn push_text(stack: &mut Vec<Node>, text: String) {
+        if text.is_empty() {
+            return;
+        }
+        if let Some(Node::Element { children, .. }) = stack.last_mut() {
+            if let Some(Node::Text(prev)) = children.last_mut() {
+                prev.push_str(&text);
+            } else {
+                children.push(Node::Text(text));
+            }
+        }
+    }
+
+    let bytes = input.as_bytes();
+    let mut i = 0;
+    let mut text_start = 0;
+    while i < bytes.len() {
+        if bytes[i] != b'<' {
+            i += 1;
+            continue;
+        }
+        let rest = &input[i..];
+        if rest.starts_with("<!--") {
+            push_text(
+                &mut stack,
+                decode_entities(&input[text_start..i]).into_owned(),
+            );
+            i = rest
+                .find("-->")
+                .map(|p| i + p + "-->".len())
+                .unwrap_or_else(|| bytes.len());
+            text_start = i;
+            continue;
+        }
+        if rest.starts_with("<!") || rest.starts_with("<?") {
+            push_text(
+                &mut stack,
+                decode_entities(&input[text_start..i]).into_owned(),
+            );
+            i = rest
+                .find('>')
+                .map(|p| i + p + 1)
+                .unwrap_or_else(|| bytes.len());
+            text_start = i;
+            continue;
+        }
+        let is_end_tag = rest.starts_with("</");
+        let name_start = if is_end_tag { i + 2 } else { i + 1 };
+        let mut name_end = name_start;
+        while name_end < bytes.len()
+            && (bytes[name_end].is_ascii_alphanumeric() || bytes[name_end] == b'-')
+        {
+            name_end += 1;
+        }
+        if name_end == name_start {
+            /* Not a tag, treat '<' as text */
+            i += 1;
+            continue;
+        }
+        push_text(
+            &mut stack,
+            decode_entities(&input[text_start..i]).into_owned(),
+        );
+        let name = input[name_start..name_end].to_ascii_lowercase();
+        let (attrs, self_closing, tag_end) = parse_attributes(input, name_end);
+        i = tag_end;
+        text_start = i;
+
+        if is_end_tag {
+            if let Some(pos) = stack.iter().rposition(|n| n.name() == name) {
+                if pos > 0 {
+                    while stack.len() > pos {
+                        close_top(&mut stack);
+                    }
+                }
+            }
+            continue;
+        }
+
+        while stack.len() > 1 && implicitly_closes(stack.last().unwrap().name(), &name) {
+            close_top(&mut stack);
+        }
+
+        if SKIPPED_ELEMENTS.contains(&name.as_str()) {
+            /* Skip everything until the matching end tag. */
+            let end_tag = format!("</{}", name);
+            let lowercase_rest = input[i..].to_ascii_lowercase();
+            i = lowercase_rest
+                .find(&end_tag)
+                .map(|p| {
+                    let after = i + p;
+                    input[after..]
+                        .find('>')
+                        .map(|q| after + q + 1)
+                        .unwrap_or_else(|| bytes.len())
+                })
+                .unwrap_or_else(|| bytes.len());
+            text_start = i;
+            continue;
+        }
+
+        let node = Node::Element {
+            name,
+            attrs,
+            children: vec![],
+        };
+        if self_closing || VOID_ELEMENTS.contains(&node.name()) {
+            if let Some(Node::Element { children, .. }) = stack.last_mut() {
+                children.push(node);
+            }
+        } else {
+            stack.push(node);
+        }
+    }
+    push_text(
+        &mut stack,
+        decode_entities(&input[text_start..]).into_owned(),
+    );
+    while stack.len() > 1 {
+        close_top(&mut stack);
+    }
+    stack.pop().unwrap()
+}
+
+/// Parse attributes starting at `pos` (right after the tag name). Returns the attributes, whether
+/// the tag is self-closing and the offset right after the closing `>`.
+fn parse_attributes(input: &str, mut pos: usize) -> (Vec<(String, String)>, bool, usize) {
+    let bytes = input.as_bytes();
+    let mut attrs = vec![];
+    let mut self_closing = false;
+    loop {
+        while pos < bytes.len() && (bytes[pos].is_ascii_whitespace() || bytes[pos] == b'/') {
+            if bytes[pos] == b'/' {
+                self_closing = true;
+            }
+            pos += 1;
+        }
+        if pos >= bytes.len() {
+            return (attrs, self_closing, bytes.len());
+        }
+        if bytes[pos] == b'>' {
+            return (attrs, self_closing, pos + 1);
+        }
+        self_closing = false;
+        let key_start = pos;
+        while pos < bytes.len()
+            && !bytes[pos].is_ascii_whitespace()
+            && !matches!(bytes[pos], b'=' | b'>' | b'/')
+        {
+            pos += 1;
+        }
+        let key = input[key_start..pos].to_ascii_lowercase();
+        while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+            pos += 1;
+        }
+        if pos < bytes.len() && bytes[pos] == b'=' {
+            pos += 1;
+            while pos < bytes.len() && bytes[pos].is_ascii_whitespace() {
+                pos += 1;
+            }
+            let value = if pos < bytes.len() && (bytes[pos] == b'"' || bytes[pos] == b'\'') {
+                let quote = bytes[pos];
+                let value_start = pos + 1;
+                pos = value_start;
+                while pos < bytes.len() && bytes[pos] != quote {
+                    pos += 1;
+                }
+                let value = &input[value_start..pos];
+                pos = std::cmp::min(pos + 1, bytes.len());
+                value
+            } else {
+                let value_start = pos;
+                while pos < bytes.len() && !bytes[pos].is_ascii_whitespace() && bytes[pos] != b'>' {
+                    pos += 1;
+                }
+                &input[value_start..pos]
+            };
+            attrs.push((key, decode_entities(value).into_owned()));
+        } else if !key.is_empty() {
+            attrs.push((key, String::new()));
+        }
+    }
+}
+
+/// Decode character references such as `&amp;`, `&#8212;` and `&#x2014;`.
+pub fn decode_entities(s: &str) -> Cow<'_, str> {
+    if !s.contains('&') {
+        return Cow::Borrowed(s);
+    }
+    let mut ret = String::with_capacity(s.len());
+    let mut rest = s;
+    while let Some(pos) = rest.find('&') {
+        ret.push_str(&rest[..pos]);
+        rest = &rest[pos..];
+        let end = rest
+            .bytes()
+            .take(12)
+            .position(|b| b == b';')
+            .filter(|&end| end > 1);
+        let decoded = end.and_then(|end| {
+            let entity = &rest[1..end];
+            let c = if entity.starts_with("#x") || entity.starts_with("#X") {
+                u32::from_str_radix(&entity[2..], 16)
+                    .ok()
+                    .and_then(std::char::from_u32)
+            } else if entity.starts_with('#') {
+                entity[1..]
+                    .parse::<u32>()
+                    .ok()
+                    .and_then(std::char::from_u32)
+            } else {
+                named_entity(entity)
+            };
+            c.map(|c| (c, end + 1))
+        });
+        if let Some((c, len)) = decoded {
+            ret.push(c);
+            rest = &rest[len..];
+        } else {
+            ret.push('&');
+            rest = &rest[1..];
+        }
+    }
+    ret.push_str(rest);
+    Cow::Owned(ret)
+}
+
+fn named_entity(name: &str) -> Option<char> {
+    Some(match name {
+        "amp" => '&',
+        "lt" => '<',
+        "gt" => '>',
+        "quot" => '"',
+        "apos" => '\'',
+        "nbsp" => '\u{a0}',
+        "shy" => '\u{ad}',
+        "zwnj" => '\u{200c}',
+        "zwj" => '\u{200d}',
+        "copy" => '©',
+        "reg" => '®',
+        "trade" => '™',
+        "deg" => '°',
+        "plusmn" => '±',
+        "times" => '×',
+        "divide" => '÷',
+        "middot" => '·',
+        "bull" => '•',
+        "hellip" => '…',
+        "ndash" => '–',
+        "mdash" => '—',
+        "lsquo" => '‘',
+        "rsquo" => '’',
+        "sbquo" => '‚',
+        "ldquo" => '“',
+        "rdquo" => '”',
+        "bdquo" => '„',
+        "laquo" => '«',
+        "raquo" => '»',
+        "euro" => '€',
+        "pound" => '£',
+        "yen" => '¥',
+        "cent" => '¢',
+        "sect" => '§',
+        "para" => '¶',
+        "iexcl" => '¡',
+        "iquest" => '¿',
+        "larr" => '←',
+        "rarr" => '→',
+        "uarr" => '↑',
+        "darr" => '↓',
+        "ensp" => '\u{2002}',
+        "emsp" => '\u{2003}',
+        "thinsp" => '\u{2009}',
+        _ => return None,
+    })
+}
+
+/* Rendering */
+
+struct Renderer {
+    links: Vec<String>,
+}
+
+/// Collapse whitespace the way a browser does for normal flow text.
+fn push_collapsed(acc: &mut String, text: &str) {
+    for c in text.chars() {
+        if c.is_whitespace() && c != '\u{a0}' {
+            if !acc.is_empty() && !acc.ends_with(' ') && !acc.ends_with('\n') {
+                acc.push(' ');
+            }
+        } else if c == '\u{a0}' {
+            acc.push(' ');
+        } else if c != '\u{ad}' {
+            acc.push(c);
+        }
+    }
+}
+
+/// Greedy word wrapping of an inline paragraph. Forced line breaks are marked with `'\n'`.
+fn wrap(paragraph: &str, width: usize) -> Vec<String> {
+    let mut lines = vec![];
+    for forced_line in paragraph.split('\n') {
+        let mut line = String::new();
+        let mut line_width = 0;
+        for word in forced_line.split(' ').filter(|w| !w.is_empty()) {
+            let word_width = word.grapheme_width();
+            if line_width > 0 && line_width + 1 + word_width > width {
+                lines.push(std::mem::replace(&mut line, String::new()));
+                line_width = 0;
+            }
+            if line_width > 0 {
+                line.push(' ');
+                line_width += 1;
+            }
+            line.push_str(word);
+            line_width += word_width;
+        }
+        lines.push(line);
+    }
+    while lines.last().map(|l| l.is_empty()).unwrap_or(false) {
+        lines.pop();
+    }
+    lines
+}
+
+fn indent(lines: Vec<String>, first: &str, rest: &str) -> Vec<String> {
+    lines
+        .into_iter()
+        .enumerate()
+        .map(|(i, l)| {
+            let prefix = if i == 0 { first } else { rest };
+            if l.is_empty() {
+                prefix.trim_end().to_string()
+            } else {
+                format!("{}{}", prefix, l)
+            }
+        })
+        .collect()
+}
+
+impl Renderer {
+    /// Render the children of `node` as a sequence of lines.
+    fn block(&mut self, node: &Node, width: usize) -> Vec<String> {
+        let mut lines = vec![];
+        let mut inline = String::new();
+        for child in node.children() {
+            match child {
+                Node::Text(t) => push_collapsed(&mut inline, t),
+                Node::Element { name, .. } if is_block(name) => {
+                    lines.extend(wrap(&inline, width));
+                    inline.clear();
+                    let margin = matches!(
+                        name.as_str(),
+                        "p" | "h1"
+                            | "h2"
+                            | "h3"
+                            | "h4"
+                            | "h5"
+                            | "h6"
+                            | "ul"
+                            | "ol"
+                            | "dl"
+                            | "blockquote"
+                            | "pre"
+                            | "table"
+                    );
+                    if margin {
+                        lines.push(String::new());
+                    }
+                    lines.extend(self.element(child, width));
+                    if margin {
+                        lines.push(String::new());
+                    }
+                }
+                Node::Element { .. } => self.inline(child, &mut inline, width),
+            }
+        }
+        lines.extend(wrap(&inline, width));
+        /* Collapse consecutive margins and remove them from the edges, the parent decides the
+         * spacing around this block. */
+        lines.dedup_by(|a, b| a.is_empty() && b.is_empty());
+        while lines.last().map(|l| l.is_empty()).unwrap_or(false) {
+            lines.pop();
+        }
+        if lines.first().map(|l| l.is_empty()).unwrap_or(false) {
+            lines.remove(0);
+        }
+        lines
+    }
+
+    /// Render a block-level element.
+    fn element(&mut self, node: &Node, width: usize) -> Vec<String> {
+        match node.name() {
+            "h1" | "h2" => {
+                let mut lines = self.block(node, width);
+                let underline_width = lines.iter().map(|l| l.grapheme_width()).max().unwrap_or(0);
+                if underline_width > 0 {
+                    let c = if node.name() == "h1" { "=" } else { "-" };
+                    lines.push(c.repeat(underline_width));
+                }
+                lines
+            }
+            "hr" => vec![String::new(), "─".repeat(width), String::new()],
+            "blockquote" => indent(self.block(node, width.saturating_sub(2)), "> ", "> "),
+            "ul" | "ol" => self.list(node, width),
+            "li" => indent(self.block(node, width.saturating_sub(2)), "* ", "  "),
+            "dd" => indent(self.block(node, width.saturating_sub(4)), "    ", "    "),
+            "pre" => {
+                let mut text = String::new();
+                Self::raw_text(node, &mut text);
+                let text = text.strip_prefix('\n').unwrap_or(&text);
+                text.trim_end()
+                    .lines()
+                    .map(|l| l.replace('\t', "    "))
+                    .collect()
+            }
+            "table" => self.table(node, width),
+            "tr" | "tbody" | "thead" | "tfoot" => {
+                /* Stray table parts, outside of a table */
+                self.table(node, width)
+            }
+            _ => self.block(node, width),
+        }
+    }
+
+    fn list(&mut self, node: &Node, width: usize) -> Vec<String> {
+        let ordered = node.name() == "ol";
+        let mut counter: usize = node
+            .attr("start")
+            .and_then(|s| s.trim().parse().ok())
+            .unwrap_or(1);
+        let mut lines = vec![];
+        let mut stray = String::new();
+        for child in node.children() {
+            match child {
+                Node::Element { name, .. } if name == "li" => {
+                    lines.extend(wrap(&stray, width));
+                    stray.clear();
+                    let marker = if ordered {
+                        let m = format!("{}. ", counter);
+                        counter += 1;
+                        m
+                    } else {
+                        "* ".to_string()
+                    };
+                    let hang = " ".repeat(marker.len());
+                    let item = self.block(child, width.saturating_sub(marker.len()));
+                    let item = if item.is_empty() {
+                        vec![String::new()]
+                    } else {
+                        item
+                    };
+                    lines.extend(indent(item, &marker, &hang));
+                }
+                Node::Text(t) => push_collapsed(&mut stray, t),
+                Node::Element { name, .. } if is_block(name) => {
+                    lines.extend(wrap(&stray, width));
+                    stray.clear();
+                    lines.extend(indent(self.element(child, width - 2), "  ", "  "));
+                }
+                Node::Element { .. } => self.inline(child, &mut stray, width),
+            }
+        }
+        lines.extend(wrap(&stray, width));
+        lines
+    }
+
+    fn rows<'n>(node: &'n Node, acc: &mut Vec<&'n Node>) {
+        for child in node.children() {
+            match child.name() {
+                "tr" => acc.push(child),
+                "thead" | "tbody" | "tfoot" => Self::rows(child, acc),
+                _ => {}
+            }
+        }
+    }
+
+    fn table(&mut self, node: &Node, width: usize) -> Vec<String> {
+        let mut rows = vec![];
+        if node.name() == "tr" {
+            rows.push(node);
+        } else {
+            Self::rows(node, &mut rows);
+        }
+        let mut caption = vec![];
+        for child in node.children() {
+            if child.name() == "caption" {
+                caption = self.block(child, width);
+            }
+        }
+        let cells: Vec<Vec<&Node>> = rows
+            .iter()
+            .map(|r| {
+                r.children()
+                    .iter()
+                    .filter(|c| c.name() == "td" || c.name() == "th")
+                    .collect()
+            })
+            .collect();
+        let columns = cells.iter().map(Vec::len).max().unwrap_or(0);
+        if columns == 0 {
+            return caption;
+        }
+        if columns == 1 {
+            /* Layout tables with a single column are just a sequence of blocks. */
+            let mut lines = caption;
+            for row in cells {
+                for cell in row {
+                    lines.extend(self.block(cell, width));
+                }
+            }
+            return lines;
+        }
+
+        const SEPARATOR: &str = "  ";
+        /* Render each cell unconstrained, to find out the natural column widths. Links are
+         * collected during the final rendering pass only. */
+        let mut natural = vec![0; columns];
+        let links_len = self.links.len();
+        for row in &cells {
+            for (i, cell) in row.iter().enumerate() {
+                let w = self
+                    .block(cell, usize::MAX / 2)
+                    .iter()
+                    .map(|l| l.grapheme_width())
+                    .max()
+                    .unwrap_or(0);
+                natural[i] = std::cmp::max(natural[i], w);
+            }
+        }
+        self.links.truncate(links_len);
+
+        let available = width.saturating_sub(SEPARATOR.len() * (columns - 1));
+        let total: usize = natural.iter().sum();
+        let col_widths: Vec<usize> = if total <= available {
+            natural
+        } else {
+            let share = std::cmp::max(available / columns, 1);
+            /* Columns narrower than their fair share keep their width, the rest split what is
+             * left proportionally. */
+            let narrow: usize = natural.iter().filter(|&&w| w <= share).sum();
+            let wide: usize = natural.iter().filter(|&&w| w > share).sum();
+            let remaining = available.saturating_sub(narrow);
+            natural
+                .iter()
+                .map(|&w| {
+                    if w <= share {
+                        w
+                    } else {
+                        std::cmp::max(remaining * w / std::cmp::max(wide, 1), 1)
+                    }
+                })
+                .collect()
+        };
+
+        let mut lines = caption;
+        for row in cells {
+            let rendered: Vec<Vec<String>> = row
+                .iter()
+                .enumerate()
+                .map(|(i, cell)| self.block(cell, std::cmp::max(col_widths[i], 1)))
+                .collect();
+            let height = rendered.iter().map(Vec::len).max().unwrap_or(0);
+            for y in 0..height {
+                let mut line = String::new();
+                for (x, cell_lines) in rendered.iter().enumerate() {
+                    let cell_line = cell_lines.get(y).map(String::as_str).unwrap_or("");
+                    line.push_str(cell_line);
+                    if x + 1 < rendered.len() {
+                        let pad = col_widths[x].saturating_sub(cell_line.grapheme_width());
+                        line.extend(std::iter::repeat(' ').take(pad));
+                        line.push_str(SEPARATOR);
+                    }
+                }
+                lines.push(line.trim_end().to_string());
+            }
+        }
+        lines
+    }
+
+    /// Render an inline element into the current paragraph.
+    fn inline(&mut self, node: &Node, acc: &mut String, width: usize) {
+        match node.name() {
+            "br" => {
+                /* Remove trailing space before the break */
+                if acc.ends_with(' ') {
+                    acc.pop();
+                }
+                acc.push('\n');
+            }
+            "img" => {
+                if let Some(alt) = node.attr("alt").map(str::trim).filter(|a| !a.is_empty()) {
+                    push_collapsed(acc, &format!("[{}]", alt));
+                }
+            }
+            "a" => {
+                let before = acc.len();
+                for child in node.children() {
+                    self.inline_child(child, acc, width);
+                }
+                let href = node.attr("href").map(str::trim).unwrap_or("");
+                if href.is_empty()
+                    || href.starts_with('#')
+                    || href.to_ascii_lowercase().starts_with("javascript:")
+                {
+                    return;
+                }
+                if acc.len() == before || acc[before..].trim() == href {
+                    /* The link text is the target itself. */
+                    if acc.len() == before {
+                        push_collapsed(acc, href);
+                    }
+                    return;
+                }
+                let idx = if let Some(pos) = self.links.iter().position(|l| l == href) {
+                    pos + 1
+                } else {
+                    self.links.push(href.to_string());
+                    self.links.len()
+                };
+                if acc.ends_with(' ') {
+                    acc.pop();
+                }
+                acc.push_str(&format!("[{}]", idx));
+            }
+            "q" => {
+                acc.push('"');
+                for child in node.children() {
+                    self.inline_child(child, acc, width);
+                }
+                acc.push('"');
+            }
+            "input" | "select" | "textarea" | "button" | "iframe" | "video" | "audio" => {}
+            _ => {
+                for child in node.children() {
+                    self.inline_child(child, acc, width);
+                }
+            }
+        }
+    }
+
+    fn inline_child(&mut self, node: &Node, acc: &mut String, width: usize) {
+        match node {
+            Node::Text(t) => push_collapsed(acc, t),
+            Node::Element { name, .. } if is_block(name) => {
+                /* A block element inside an inline element, eg `<a><div>..</div></a>`. */
+                if !acc.is_empty() && !acc.ends_with('\n') {
+                    acc.push('\n');
+                }
+                let lines = self.element(node, width);
+                acc.push_str(lines.join("\n").trim_matches('\n'));
+                acc.push('\n');
+            }
+            Node::Element { .. } => self.inline(node, acc, width),
+        }
+    }
+
+    fn raw_text(node: &Node, acc: &mut String) {
+        for child in node.children() {
+            match child {
+                Node::Text(t) => acc.push_str(t),
+                Node::Element { name, .. } if name == "br" => acc.push('\n'),
+                Node::Element { .. } => Self::raw_text(child, acc),
+            }
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_html_to_text() {
+        let html = r#"<html><head><title>t</title><style>p { color: red; }</style></head>
+<body>
+<h1>Hello &amp; welcome</h1>
+<p>Some   <b>bold</b> text with a <a href="https://example.com/a">link</a>.<br>New line.</p>
+<ul><li>one<li>two</ul>
+<ol start="3"><li>three</li></ol>
+<blockquote><p>quoted</p></blockquote>
+<table><tr><th>Name</th><th>Value</th></tr><tr><td>a</td><td>1</td></tr></table>
+<pre>  keep
+    this</pre>
+</body></html>"#;
+        let HtmlText { text, links } = html_to_text(html, 80);
+        assert_eq!(links, vec!["https://example.com/a".to_string()]);
+        assert_eq!(
+            text,
+            "Hello & welcome
+===============
+
+Some bold text with a link[1].
+New line.
+
+* one
+* two
+
+3. three
+
+> quoted
+
+Name  Value
+a     1
+
+  keep
+    this
+
+[1] https://example.com/a
+"
+        );
+    }
+
+    #[test]
+    fn test_html_entities() {
+        assert_eq!(
+            decode_entities("a &lt;b&gt; &#65;&#x42; &bogus; &"),
+            "a <b> AB &bogus; &"
+        );
+    }
+}
@@ -19,7 +19,9 @@
  * along with meli. If not, see <http://www.gnu.org/licenses/>.
  */
 
+pub mod diff;
 pub mod grapheme_clusters;
+pub mod html;
 pub mod line_break;
 pub mod search;
 mod tables;
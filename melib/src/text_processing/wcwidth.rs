@@ -125,6 +125,20 @@ pub fn wcwidth(ucs: WChar) -> Option<usize> {
     }
 }
 
+/// Display width of a single grapheme cluster: the width of its widest code point, or two if it
+/// asks for emoji presentation with U+FE0F. Combining marks, joiners and modifiers don't take
+/// columns of their own.
+pub fn grapheme_cluster_width(grapheme: &str) -> usize {
+    let mut width = 0;
+    for c in grapheme.code_points() {
+        if c == 0xFE0F {
+            return 2;
+        }
+        width = std::cmp::max(width, wcwidth(c).unwrap_or(0));
+    }
+    std::cmp::min(width, 2)
+}
+
 #[test]
 fn test_wcwidth() {
     assert_eq!(
@@ -153,6 +167,16 @@ fn test_wcwidth() {
     assert_eq!("👻".grapheme_width(), 2);
 }
 
+#[test]
+fn test_grapheme_cluster_width() {
+    use super::grapheme_clusters::TextProcessing;
+    assert_eq!("e\u{301}".grapheme_width(), 1);
+    assert_eq!("❤\u{FE0F}".grapheme_width(), 2);
+    assert_eq!("👍🏽".grapheme_width(), 2);
+    assert_eq!("👨\u{200D}👩\u{200D}👧".grapheme_width(), 2);
+    assert_eq!("日本語 ok".grapheme_width(), 9);
+}
+
 pub fn wcswidth(mut pwcs: WChar, mut n: usize) -> Option<usize> {
     let mut width = 0;
 
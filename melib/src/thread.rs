@@ -34,7 +34,6 @@
 
 use crate::datetime::UnixTimestamp;
 use crate::email::address::StrBuild;
-use crate::email::parser::BytesExt;
 use crate::email::*;
 
 mod iterators;
@@ -172,60 +171,64 @@ macro_rules! make {
         }};
 }
 
-/* Strip common prefixes from subjects */
-trait SubjectPrefix {
-    fn is_a_reply(&self) -> bool;
-    fn strip_prefixes(&mut self) -> &mut Self;
+/// How messages without `In-Reply-To` and `References` headers are grouped by subject, and which
+/// subject prefixes are ignored when comparing subjects.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SubjectThreading {
+    /// Group replies that have no `In-Reply-To` and `References` headers with messages of the same
+    /// normalized subject.
+    pub enabled: bool,
+    /// Reply and forward prefixes stripped from subjects, matched case-insensitively.
+    pub prefixes: Vec<String>,
+    /// Strip mailing list tags such as `[list-name]`. Tags starting with `PATCH` or `RFC` are kept.
+    pub strip_list_tags: bool,
 }
 
-impl SubjectPrefix for &[u8] {
-    fn is_a_reply(&self) -> bool {
-        self.starts_with(b"RE: ")
-            || self.starts_with(b"Re: ")
-            || self.starts_with(b"FW: ")
-            || self.starts_with(b"Fw: ")
+impl Default for SubjectThreading {
+    fn default() -> Self {
+        SubjectThreading {
+            enabled: false,
+            prefixes: Self::default_prefixes(),
+            strip_list_tags: true,
+        }
     }
+}
 
-    fn strip_prefixes(&mut self) -> &mut Self {
-        let result = {
-            let mut slice = self.trim();
-            loop {
-                if slice.starts_with(b"RE: ")
-                    || slice.starts_with(b"Re: ")
-                    || slice.starts_with(b"FW: ")
-                    || slice.starts_with(b"Fw: ")
-                {
-                    slice = &slice[3..];
-                    continue;
-                }
-                if slice.starts_with(b"FWD: ")
-                    || slice.starts_with(b"Fwd: ")
-                    || slice.starts_with(b"fwd: ")
+impl SubjectThreading {
+    pub fn default_prefixes() -> Vec<String> {
+        vec!["Re:".to_string(), "Fw:".to_string(), "Fwd:".to_string()]
+    }
+
+    /// Strip prefixes and list tags from `subject`. Returns the stripped subject and whether a
+    /// reply or forward prefix was found.
+    pub fn strip_prefixes<'s>(&self, subject: &'s str) -> (&'s str, bool) {
+        let mut slice = subject.trim();
+        let mut is_reply = false;
+        'strip: loop {
+            for prefix in &self.prefixes {
+                let prefix = prefix.trim();
+                if !prefix.is_empty()
+                    && slice.len() >= prefix.len()
+                    && slice.is_char_boundary(prefix.len())
+                    && slice[..prefix.len()].eq_ignore_ascii_case(prefix)
                 {
-                    slice = &slice[4..];
-                    continue;
-                }
-                if slice.starts_with(b" ") || slice.starts_with(b"\t") || slice.starts_with(b"\r") {
-                    //FIXME just trim whitespace
-                    slice = &slice[1..];
-                    continue;
+                    slice = slice[prefix.len()..].trim_start();
+                    is_reply = true;
+                    continue 'strip;
                 }
-                if slice.starts_with(b"[")
-                    && !(slice.starts_with(b"[PATCH") || slice.starts_with(b"[RFC"))
-                {
-                    if let Some(pos) = slice.find(b"]") {
-                        slice = &slice[pos..];
-                        continue;
-                    }
-                    slice = &slice[1..];
+            }
+            if self.strip_list_tags
+                && slice.starts_with('[')
+                && !(slice[1..].starts_with("PATCH") || slice[1..].starts_with("RFC"))
+            {
+                if let Some(pos) = slice.find(']') {
+                    slice = slice[pos + 1..].trim_start();
                     continue;
                 }
-                break;
             }
-            slice
-        };
-        *self = result;
-        self
+            break;
+        }
+        (slice, is_reply)
     }
 }
 
@@ -241,6 +244,16 @@ pub enum SortOrder {
 pub enum SortField {
     Subject,
     Date,
+    /// Author of the first message.
+    Sender,
+    /// Size of the first message, if known.
+    Size,
+    /// Whether there are attachments.
+    Attachments,
+    /// Number of unseen messages.
+    Unread,
+    /// Date of the latest message.
+    Activity,
 }
 
 impl Default for SortField {
@@ -261,6 +274,11 @@ impl FromStr for SortField {
         match s.trim() {
             "subject" | "s" | "sub" | "sbj" | "subj" => Ok(SortField::Subject),
             "date" | "d" => Ok(SortField::Date),
+            "sender" | "from" | "f" => Ok(SortField::Sender),
+            "size" => Ok(SortField::Size),
+            "attachments" | "attachment" | "a" => Ok(SortField::Attachments),
+            "unread" | "u" => Ok(SortField::Unread),
+            "activity" | "latest" => Ok(SortField::Activity),
             _ => Err(()),
         }
     }
@@ -420,6 +438,12 @@ pub struct Threads {
     pub hash_set: HashSet<EnvelopeHash>,
     sort: Arc<RwLock<(SortField, SortOrder)>>,
     subsort: Arc<RwLock<(SortField, SortOrder)>>,
+
+    pub subject_threading: SubjectThreading,
+    /// Message-IDs of messages that must not be grouped by subject.
+    pub subject_threading_exempt: HashSet<Vec<u8>>,
+    /// Normalized subjects of thread roots, and whether the root's subject had a reply prefix.
+    subject_index: HashMap<String, (ThreadNodeHash, bool)>,
 }
 
 impl PartialEq for ThreadNode {
@@ -609,9 +633,8 @@ impl Threads {
         envelopes: &Envelopes,
     ) {
         let envelopes = envelopes.read().unwrap();
-        let mut subject = envelopes[&env_hash].subject();
-        let mut subject = subject.to_mut().as_bytes();
-        let stripped_subject = subject.strip_prefixes();
+        let subject = envelopes[&env_hash].subject();
+        let (stripped_subject, _) = self.subject_threading.strip_prefixes(&subject);
         if let Some(parent_id) = self.thread_nodes[&id].parent {
             if let Some(parent_hash) = self.thread_nodes[&parent_id].message {
                 debug_assert!(envelopes.contains_key(&parent_hash));
@@ -619,10 +642,8 @@ impl Threads {
                  * If parent subject is Foobar and reply is `Re: Foobar`
                  * then showing the reply's subject is reduntant
                  */
-                let mut parent_subject = envelopes[&parent_hash].subject();
-                let mut parent_subject = parent_subject.to_mut().as_bytes();
-                parent_subject.strip_prefixes();
-                if stripped_subject == &parent_subject {
+                let parent_subject = envelopes[&parent_hash].subject();
+                if stripped_subject == self.subject_threading.strip_prefixes(&parent_subject).0 {
                     self.thread_nodes.entry(id).and_modify(|e| {
                         e.show_subject = false;
                     });
@@ -632,10 +653,8 @@ impl Threads {
         for i in 0..self.thread_nodes[&id].children.len() {
             let child_hash = self.thread_nodes[&id].children[i];
             if let Some(child_env_hash) = self.thread_nodes[&child_hash].message() {
-                let mut child_subject = envelopes[&child_env_hash].subject();
-                let mut child_subject = child_subject.to_mut().as_bytes();
-                child_subject.strip_prefixes();
-                if stripped_subject == &child_subject {
+                let child_subject = envelopes[&child_env_hash].subject();
+                if stripped_subject == self.subject_threading.strip_prefixes(&child_subject).0 {
                     self.thread_nodes.entry(child_hash).and_modify(|e| {
                         e.show_subject = false;
                     });
@@ -644,6 +663,35 @@ impl Threads {
         }
     }
 
+    /// Attach `id` to the thread of a message with the same normalized subject, if `id` is a reply,
+    /// or make it the parent of a reply that arrived before it.
+    fn group_by_subject(&mut self, id: ThreadNodeHash, subject: &str) {
+        let (stripped_subject, is_reply) = self.subject_threading.strip_prefixes(subject);
+        if stripped_subject.is_empty() || self.thread_nodes[&id].parent.is_some() {
+            return;
+        }
+        let key = stripped_subject.to_lowercase();
+        match self.subject_index.get(&key).cloned() {
+            None => {
+                self.subject_index.insert(key, (id, is_reply));
+            }
+            Some((root, _)) if root == id => {}
+            Some((root, root_is_reply)) => {
+                if self.find_group(self.thread_nodes[&root].group)
+                    == self.find_group(self.thread_nodes[&id].group)
+                {
+                    return;
+                }
+                if is_reply {
+                    make!((root) parent of (id), self);
+                } else if root_is_reply && self.thread_nodes[&root].parent.is_none() {
+                    make!((id) parent of (root), self);
+                    self.subject_index.insert(key, (id, false));
+                }
+            }
+        }
+    }
+
     pub fn insert(&mut self, envelopes: &Envelopes, env_hash: EnvelopeHash) {
         self.insert_internal(envelopes, env_hash, false);
     }
@@ -833,6 +881,13 @@ impl Threads {
                 }
             }
         }
+        if self.subject_threading.enabled
+            && envelopes_lck[&env_hash].in_reply_to().is_none()
+            && envelopes_lck[&env_hash].references.is_none()
+            && !self.subject_threading_exempt.contains(message_id)
+        {
+            self.group_by_subject(new_id, &envelopes_lck[&env_hash].subject());
+        }
         drop(envelopes_lck);
         self.update_show_subject(new_id, env_hash, envelopes);
         envelopes
@@ -1006,8 +1061,51 @@ impl Threads {
                     mb.subject().as_ref().cmp(&ma.subject())
                 }
             }
+            (field, order) => self.cmp_threads_by(*a, *b, field, order, &envelopes),
         });
     }
+
+    /// Compare threads `a` and `b` by one of the fields other than `Date` and `Subject`. Ties are
+    /// broken by the date of the latest message, newest first.
+    fn cmp_threads_by(
+        &self,
+        a: ThreadHash,
+        b: ThreadHash,
+        field: SortField,
+        order: SortOrder,
+        envelopes: &HashMap<EnvelopeHash, Envelope>,
+    ) -> Ordering {
+        let (a, b) = (self.thread_ref(a), self.thread_ref(b));
+        let first_message = |t: &Thread| {
+            self.thread_nodes[&t.root()]
+                .message()
+                .and_then(|h| envelopes.get(&h))
+        };
+        let ord = match field {
+            SortField::Sender => {
+                let sender = |t: &Thread| {
+                    first_message(t)
+                        .map(|e| e.field_from_to_string().to_lowercase())
+                        .unwrap_or_default()
+                };
+                sender(a).cmp(&sender(b))
+            }
+            SortField::Size => {
+                let size = |t: &Thread| first_message(t).and_then(|e| e.size);
+                size(a).cmp(&size(b))
+            }
+            SortField::Attachments => a.has_attachments().cmp(&b.has_attachments()),
+            SortField::Unread => a.unseen().cmp(&b.unseen()),
+            SortField::Date | SortField::Subject | SortField::Activity => a.date().cmp(&b.date()),
+        };
+        let ord = if order == SortOrder::Desc {
+            ord.reverse()
+        } else {
+            ord
+        };
+        ord.then_with(|| b.date().cmp(&a.date()))
+    }
+
     pub fn node_inner_sort_by(
         &self,
         vec: &mut [ThreadNodeHash],
@@ -1086,6 +1184,13 @@ impl Threads {
                     mb.subject().as_ref().cmp(&ma.subject())
                 }
             }
+            (field, order) => self.cmp_threads_by(
+                self.thread_nodes[a].group,
+                self.thread_nodes[b].group,
+                field,
+                order,
+                &envelopes,
+            ),
         });
     }
     fn inner_sort_by(&self, sort: (SortField, SortOrder), envelopes: &Envelopes) {
@@ -1162,6 +1267,13 @@ impl Threads {
                     mb.subject().as_ref().cmp(&ma.subject())
                 }
             }
+            (field, order) => self.cmp_threads_by(
+                self.thread_nodes[a].group,
+                self.thread_nodes[b].group,
+                field,
+                order,
+                &envelopes,
+            ),
         });
     }
 
@@ -1390,3 +1502,57 @@ fn save_graph(
     }
 }
 */
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_subject_threading() {
+        let conf = SubjectThreading {
+            enabled: true,
+            prefixes: vec!["Re:".to_string(), "AW:".to_string(), "Fwd:".to_string()],
+            strip_list_tags: true,
+        };
+        assert_eq!(
+            conf.strip_prefixes("RE: aw: [meli-dev] Hello"),
+            ("Hello", true)
+        );
+        assert_eq!(conf.strip_prefixes("[PATCH] Fix"), ("[PATCH] Fix", false));
+        assert_eq!(conf.strip_prefixes("Fwd:"), ("", true));
+
+        let envelopes: Envelopes = Arc::new(RwLock::new(HashMap::default()));
+        let mut hashes = vec![];
+        for (i, subject) in ["Re: [list] Hello", "Hello", "AW: Hello", "Hello"]
+            .iter()
+            .enumerate()
+        {
+            let mut env = Envelope::from_bytes(
+                format!(
+                    "Message-ID: <{}@example.com>\r\nSubject: {}\r\nDate: Thu, 1 Oct 2020 10:0{}:00 +0000\r\n\r\nbody\r\n",
+                    i, subject, i
+                )
+                .as_bytes(),
+                None,
+            )
+            .unwrap();
+            env.set_hash(i as EnvelopeHash + 1);
+            hashes.push(env.hash());
+            envelopes.write().unwrap().insert(env.hash(), env);
+        }
+        let mut threads = Threads::new(hashes.len());
+        threads.subject_threading = conf;
+        for h in &hashes {
+            threads.insert(&envelopes, *h);
+        }
+        let group = |h: &EnvelopeHash| {
+            let node = envelopes.read().unwrap()[h].thread();
+            threads.find_group(threads.thread_nodes[&node].group)
+        };
+        /* The reply that arrived first is moved under the original */
+        assert_eq!(group(&hashes[0]), group(&hashes[1]));
+        assert_eq!(group(&hashes[2]), group(&hashes[1]));
+        /* Messages with the same subject that are not replies start their own threads */
+        assert_ne!(group(&hashes[3]), group(&hashes[1]));
+    }
+}
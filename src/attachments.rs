@@ -0,0 +1,114 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Save several attachments at once to a directory, see the `save-attachments` and
+ * `save-thread-attachments` commands.
+ */
+
+use melib::email::attachment_types::ContentType;
+use melib::email::attachments::decode;
+use melib::{Attachment, Result};
+use std::io::Write;
+use std::os::unix::fs::OpenOptionsExt;
+use std::path::{Path, PathBuf};
+
+/// Returns the parts of `message` that are attachments: parts with an `attachment` disposition
+/// or a file name. Multipart containers and inline body text are skipped.
+pub fn saveable_attachments(message: &Attachment) -> Vec<Attachment> {
+    message
+        .attachments()
+        .into_iter()
+        .filter(|a| {
+            !matches!(a.content_type(), ContentType::Multipart { .. })
+                && (a.content_disposition.kind.is_attachment() || a.filename().is_some())
+        })
+        .collect()
+}
+
+/// Returns a path in `dir` for `filename` that doesn't exist yet, adding `-1`, `-2`, ... before
+/// the extension until one is free.
+pub fn unique_path(dir: &Path, filename: &str) -> PathBuf {
+    let path = dir.join(filename);
+    if !path.exists() {
+        return path;
+    }
+    let (stem, ext) = match filename.rfind('.') {
+        Some(i) if i > 0 => (&filename[..i], &filename[i..]),
+        _ => (filename, ""),
+    };
+    (1..)
+        .map(|i| dir.join(format!("{}-{}{}", stem, i, ext)))
+        .find(|p| !p.exists())
+        .unwrap()
+}
+
+/// Decode and write `attachments` to `dir`, creating it if needed, without overwriting existing
+/// files. Returns the paths of the written files.
+pub fn save_attachments(dir: &Path, attachments: &[Attachment]) -> Result<Vec<PathBuf>> {
+    std::fs::create_dir_all(dir)?;
+    let mut ret = Vec::with_capacity(attachments.len());
+    for a in attachments {
+        let filename = a.filename().unwrap_or_else(|| "attachment".to_string());
+        let path = unique_path(dir, &filename);
+        let mut f = std::fs::OpenOptions::new()
+            .write(true)
+            .create_new(true)
+            .mode(0o600) // Read/write for owner only.
+            .open(&path)?;
+        f.write_all(&decode(a, None))?;
+        f.flush()?;
+        ret.push(path);
+    }
+    Ok(ret)
+}
+
+/// Notification text listing the `paths` of saved attachments.
+pub fn saved_message(paths: &[PathBuf]) -> String {
+    format!(
+        "Saved {} attachment{}:\n{}",
+        paths.len(),
+        if paths.len() == 1 { "" } else { "s" },
+        paths
+            .iter()
+            .map(|p| p.display().to_string())
+            .collect::<Vec<String>>()
+            .join("\n")
+    )
+}
+
+#[test]
+fn test_save_attachments() {
+    use melib::email::attachments::AttachmentBuilder;
+    let raw = b"Content-Type: multipart/mixed; boundary=\"bound\"\n\n--bound\nContent-Type: text/plain\n\nbody\n--bound\nContent-Type: text/plain; name=\"notes.txt\"\nContent-Disposition: attachment; filename=\"notes.txt\"\n\nfirst\n--bound\nContent-Type: text/plain; name=\"notes.txt\"\nContent-Disposition: attachment; filename=\"notes.txt\"\n\nsecond\n--bound--\n";
+    let message = AttachmentBuilder::new(raw).build();
+    let attachments = saveable_attachments(&message);
+    assert_eq!(attachments.len(), 2);
+
+    let dir = std::env::temp_dir().join(format!("meli-attachments-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    let paths = save_attachments(&dir, &attachments).unwrap();
+    assert_eq!(paths, vec![dir.join("notes.txt"), dir.join("notes-1.txt")]);
+    assert_eq!(std::fs::read(&paths[0]).unwrap(), b"first");
+    assert_eq!(std::fs::read(&paths[1]).unwrap(), b"second");
+    assert_eq!(unique_path(&dir, "notes.txt"), dir.join("notes-2.txt"));
+    assert_eq!(unique_path(&dir, "README"), dir.join("README"));
+    std::fs::remove_dir_all(&dir).unwrap();
+}
@@ -74,8 +74,20 @@ use crate::conf::*;
 #[cfg(feature = "sqlite3")]
 pub mod sqlite3;
 
+pub mod attachments;
+pub mod dates;
+pub mod export;
+pub mod follow_up;
+pub mod import;
+pub mod ipc;
 pub mod jobs;
 pub mod mailcap;
+pub mod network;
+pub mod saved_search;
+pub mod session;
+pub mod snooze;
+pub mod status;
+pub mod watch_scheduler;
 
 use std::os::raw::c_int;
 
@@ -121,6 +133,26 @@ fn notify(
     Ok(r)
 }
 
+/// Read a message from standard input and replace standard input with the controlling terminal
+/// so that keyboard input still works.
+fn read_stdin_message() -> Result<Vec<u8>> {
+    use std::io::Read;
+    use std::os::unix::io::AsRawFd;
+    let mut bytes = vec![];
+    std::io::stdin()
+        .read_to_end(&mut bytes)
+        .chain_err_summary(|| "Could not read from standard input")?;
+    let tty = std::fs::OpenOptions::new()
+        .read(true)
+        .write(true)
+        .open("/dev/tty")
+        .chain_err_summary(|| "Could not open controlling terminal")?;
+    nix::unistd::dup2(tty.as_raw_fd(), libc::STDIN_FILENO)
+        .map_err(|err| MeliError::new(err.to_string()))
+        .chain_err_summary(|| "Could not open controlling terminal")?;
+    Ok(bytes)
+}
+
 fn parse_manpage(src: &str) -> Result<ManPages> {
     match src {
         "" | "meli" | "main" => Ok(ManPages::Main),
@@ -153,6 +185,10 @@ struct Opt {
     #[structopt(short, long, parse(from_os_str))]
     config: Option<PathBuf>,
 
+    /// open a composer pre-filled from a mailto: URI, eg. when meli is the system mail handler.
+    #[structopt(value_name = "MAILTO_URI")]
+    mailto: Option<String>,
+
     #[structopt(subcommand)]
     subcommand: Option<SubCommand>,
 }
@@ -169,21 +205,64 @@ enum SubCommand {
         #[structopt(value_name = "NEW_CONFIG_PATH", parse(from_os_str))]
         path: Option<PathBuf>,
     },
-    /// test a configuration file for syntax issues or missing options.
+    /// test a configuration file for syntax issues, missing options and commands or paths that don't exist.
     #[structopt(display_order = 2)]
     TestConfig {
         #[structopt(value_name = "CONFIG_PATH", parse(from_os_str))]
         path: Option<PathBuf>,
+        /// also connect to the servers of remote accounts and their SMTP servers.
+        #[structopt(long)]
+        connect: bool,
+    },
+    /// import messages from an mbox file or a maildir tree into a mailbox of an account.
+    Import {
+        /// only report how many messages would be imported.
+        #[structopt(long)]
+        dry_run: bool,
+        #[structopt(value_name = "ACCOUNT")]
+        account: String,
+        #[structopt(value_name = "MAILBOX_PATH")]
+        mailbox: String,
+        #[structopt(value_name = "INPUT", parse(from_os_str))]
+        input: PathBuf,
+    },
+    /// export a mailbox of an account, or the messages in it matching a search query, to an mbox file or a maildir.
+    Export {
+        /// export only the messages matching this search query.
+        #[structopt(long, value_name = "QUERY")]
+        query: Option<String>,
+        /// one of mbox, mboxcl2, mboxcl, mboxrd, mboxo or maildir.
+        #[structopt(long, default_value = "mbox", value_name = "FORMAT")]
+        format: export::ExportFormat,
+        /// add the messages to OUTPUT if it already exists.
+        #[structopt(long, conflicts_with = "overwrite")]
+        append: bool,
+        /// replace the mbox file OUTPUT if it already exists.
+        #[structopt(long)]
+        overwrite: bool,
+        #[structopt(value_name = "ACCOUNT")]
+        account: String,
+        #[structopt(value_name = "MAILBOX_PATH")]
+        mailbox: String,
+        #[structopt(value_name = "OUTPUT", parse(from_os_str))]
+        output: PathBuf,
+    },
+    /// print unread and total message counts of the subscribed mailboxes of every account and exit.
+    Status {
+        /// print counts as JSON.
+        #[structopt(long)]
+        json: bool,
     },
     #[structopt(visible_alias="docs", aliases=&["docs", "manpage", "manpages"])]
     #[structopt(display_order = 3)]
     /// print documentation page and exit (Piping to a pager is recommended.).
     Man(ManOpt),
 
-    /// View mail from input file.
+    /// View mail from input file, or standard input if INPUT is `-` or missing, without loading
+    /// any accounts.
     View {
         #[structopt(value_name = "INPUT", parse(from_os_str))]
-        path: PathBuf,
+        path: Option<PathBuf>,
     },
 }
 
@@ -212,15 +291,79 @@ fn run_app(opt: Opt) -> Result<()> {
         std::env::set_var("MELI_CONFIG", config_location);
     }
 
+    if let Some(ref uri) = opt.mailto {
+        if <Mailto as std::convert::TryFrom<&[u8]>>::try_from(uri.as_bytes()).is_err() {
+            return Err(MeliError::new(format!(
+                "`{}` is not a valid mailto: URI.",
+                uri
+            )));
+        }
+    }
+
     match opt.subcommand {
-        Some(SubCommand::TestConfig { path }) => {
+        Some(SubCommand::TestConfig { path, connect }) => {
             let config_path = if let Some(path) = path {
                 path
             } else {
                 crate::conf::get_config_file()?
             };
-            conf::FileSettings::validate(config_path)?;
-            return Ok(());
+            let settings = conf::FileSettings::validate(config_path)?;
+            let mut problems = conf::test_config::check_references(&settings);
+            if connect {
+                problems.extend(conf::test_config::probe_accounts(&settings));
+            }
+            if problems.is_empty() {
+                println!("No problems found.");
+                return Ok(());
+            }
+            for problem in problems.iter() {
+                println!("{}", problem);
+            }
+            return Err(MeliError::new(format!(
+                "{} problem{} found.",
+                problems.len(),
+                if problems.len() == 1 { "" } else { "s" }
+            )));
+        }
+        Some(SubCommand::Import {
+            dry_run,
+            ref account,
+            ref mailbox,
+            ref input,
+        }) => {
+            let settings = conf::FileSettings::new()?;
+            return import::import(&settings, account, mailbox, input, dry_run);
+        }
+        Some(SubCommand::Export {
+            ref query,
+            format,
+            append,
+            overwrite,
+            ref account,
+            ref mailbox,
+            ref output,
+        }) => {
+            let settings = conf::FileSettings::new()?;
+            let mode = if append {
+                export::ExportMode::Append
+            } else if overwrite {
+                export::ExportMode::Overwrite
+            } else {
+                export::ExportMode::Create
+            };
+            return export::export(
+                &settings,
+                account,
+                mailbox,
+                query.as_deref(),
+                format,
+                mode,
+                output,
+            );
+        }
+        Some(SubCommand::Status { json }) => {
+            let settings = conf::FileSettings::new()?;
+            return status::status(&settings, json);
         }
         Some(SubCommand::CreateConfig { path }) => {
             let config_path = if let Some(path) = path {
@@ -299,7 +442,9 @@ fn run_app(opt: Opt) -> Result<()> {
             print!("{}", conf::Themes::default().key_to_string("dark", false));
             return Ok(());
         }
-        Some(SubCommand::View { ref path }) => {
+        Some(SubCommand::View {
+            path: Some(ref path),
+        }) if path.as_os_str() != "-" => {
             if !path.exists() {
                 return Err(MeliError::new(format!(
                     "`{}` is not a valid path",
@@ -312,7 +457,7 @@ fn run_app(opt: Opt) -> Result<()> {
                 )));
             }
         }
-        None => {}
+        Some(SubCommand::View { .. }) | None => {}
     }
 
     /* Create a channel to communicate with other threads. The main process is the sole receiver.
@@ -324,6 +469,8 @@ fn run_app(opt: Opt) -> Result<()> {
         signal_hook::SIGWINCH,
         /* Catch SIGCHLD to handle embed applications status change */
         signal_hook::SIGCHLD,
+        /* Catch SIGUSR1 to reload the configuration file */
+        signal_hook::SIGUSR1,
     ];
 
     let signal_recvr = notify(signals, sender.clone())?;
@@ -332,30 +479,74 @@ fn run_app(opt: Opt) -> Result<()> {
     let mut state;
 
     if let Some(SubCommand::View { path }) = opt.subcommand {
-        let bytes = std::fs::read(&path)
-            .chain_err_summary(|| format!("Could not read from `{}`", path.display()))?;
+        let (bytes, name) = match path {
+            Some(path) if path.as_os_str() != "-" => (
+                std::fs::read(&path)
+                    .chain_err_summary(|| format!("Could not read from `{}`", path.display()))?,
+                path.display().to_string(),
+            ),
+            _ => (read_stdin_message()?, "standard input".to_string()),
+        };
         let wrapper = Mail::new(bytes, Some(Flag::SEEN))
-            .chain_err_summary(|| format!("Could not parse `{}`", path.display()))?;
+            .chain_err_summary(|| format!("Could not parse `{}`", name))?;
         state = State::new(
             Some(Settings::without_accounts().unwrap_or_default()),
             sender,
             receiver.clone(),
         )?;
+        /* Don't overwrite the saved session of the normal mode. */
+        state.context.settings.terminal.restore_session = ToggleFlag::False;
         state.register_component(Box::new(EnvelopeView::new(wrapper, None, None, 0)));
     } else {
         state = State::new(None, sender, receiver.clone())?;
         #[cfg(feature = "svgscreenshot")]
         state.register_component(Box::new(components::svg::SVGScreenshotFilter::new()));
-        let window = Box::new(Tabbed::new(
+        let session = if state.context.settings.terminal.restore_session.is_true() {
+            session::Session::load().unwrap_or_else(|err| {
+                log(format!("Could not load session: {}", err), ERROR);
+                None
+            })
+        } else {
+            None
+        };
+        let mut listings = session
+            .as_ref()
+            .map(|s| s.listings.clone())
+            .unwrap_or_default()
+            .into_iter()
+            .map(|listing_session| {
+                let mut listing = listing::Listing::new(&mut state.context);
+                listing.restore_session(listing_session, &mut state.context);
+                listing
+            })
+            .collect::<VecDeque<_>>();
+        let mut window = Tabbed::new(
             vec![
-                Box::new(listing::Listing::new(&mut state.context)),
+                Box::new(
+                    listings
+                        .pop_front()
+                        .unwrap_or_else(|| listing::Listing::new(&mut state.context)),
+                ),
                 Box::new(ContactList::new(&state.context)),
             ],
             &state.context,
-        ));
+        );
+        /* Restored listings besides the main one are opened after the pinned tabs */
+        let pinned = 2;
+        for listing in listings {
+            window.add_component(Box::new(listing));
+        }
+        match session.and_then(|s| s.active_listing) {
+            Some(0) | None => {}
+            Some(idx) => window.set_active_tab(pinned + idx - 1, &state.context),
+        }
+        let window = Box::new(window);
 
         let status_bar = Box::new(StatusBar::new(&state.context, window));
         state.register_component(status_bar);
+        if let Some(uri) = opt.mailto {
+            state.rcv_event(UIEvent::Command(format!("compose {}", uri)));
+        }
 
         #[cfg(all(target_os = "linux", feature = "dbus-notifications"))]
         {
@@ -418,12 +609,16 @@ fn run_app(opt: Opt) -> Result<()> {
                                 state.redraw();
                             }
                         },
+                        ThreadEvent::Input((Key::Focus(_), _)) if state.mode != UIMode::Embed => {}
                         ThreadEvent::Input((k, r)) => {
                             match state.mode {
                                 UIMode::Normal => {
                                     match k {
                                         _ if k == quit_key => {
                                             if state.can_quit_cleanly() {
+                                                if state.context.settings.terminal.restore_session.is_true() {
+                                                    state.save_session();
+                                                }
                                                 drop(state);
                                                 break 'main;
                                             } else {
@@ -436,7 +631,9 @@ fn run_app(opt: Opt) -> Result<()> {
                                             state.redraw();
                                         }
                                         key  => {
-                                            state.rcv_event(UIEvent::Input(key));
+                                            for key in state.key_sequence(key) {
+                                                state.rcv_event(UIEvent::Input(key));
+                                            }
                                             state.redraw();
                                         },
                                     }
@@ -518,6 +715,9 @@ fn run_app(opt: Opt) -> Result<()> {
                             state.redraw();
 
                         }
+                        signal_hook::SIGUSR1 => {
+                            state.reload_config();
+                        }
                         other => {
                             debug!("got other signal: {:?}", other);
                         }
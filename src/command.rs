@@ -27,20 +27,23 @@ use melib::nom::{
     branch::alt,
     bytes::complete::{is_a, is_not, tag, take_until},
     character::complete::{digit1, not_line_ending},
-    combinator::{map, map_res},
+    combinator::{map, map_res, opt},
     multi::separated_list,
-    sequence::{pair, preceded, separated_pair},
+    sequence::{pair, preceded, separated_pair, terminated},
     IResult,
 };
 pub use melib::thread::{SortField, SortOrder};
 use melib::MeliError;
 pub mod actions;
+use crate::export::{ExportFormat, ExportMode};
 use actions::MailboxOperation;
 use std::collections::HashSet;
+use std::convert::TryFrom;
 pub mod history;
 pub use crate::actions::AccountAction::{self, *};
 pub use crate::actions::Action::{self, *};
 pub use crate::actions::ComposeAction::{self, *};
+pub use crate::actions::ContactsFormat;
 pub use crate::actions::ListingAction::{self, *};
 pub use crate::actions::MailingListAction::{self, *};
 pub use crate::actions::TabAction::{self, *};
@@ -102,7 +105,7 @@ use TokenAdicity::*;
 impl TokenStream {
     fn matches<'s>(&self, s: &mut &'s str, sugg: &mut HashSet<String>) -> Vec<(&'s str, Token)> {
         let mut tokens = vec![];
-        for t in self.tokens.iter() {
+        for (i, t) in self.tokens.iter().enumerate() {
             let mut ptr = 0;
             while ptr + 1 < s.len() && s.as_bytes()[ptr].is_ascii_whitespace() {
                 ptr += 1;
@@ -129,6 +132,7 @@ impl TokenStream {
                     | Filepath
                     | AccountName
                     | MailboxPath
+                    | TagValue
                     | QuotedStringValue
                     | AlphanumericStringValue => {}
                 }
@@ -176,14 +180,22 @@ impl TokenStream {
                 | Filepath
                 | AccountName
                 | MailboxPath
+                | TagValue
                 | QuotedStringValue
                 | AlphanumericStringValue => {
                     let mut ptr = 0;
                     while ptr + 1 < s.len() && !s.as_bytes()[ptr].is_ascii_whitespace() {
                         ptr += 1;
                     }
-                    tokens.push((&s[..ptr + 1], *t.inner()));
+                    let value = &s[..ptr + 1];
+                    tokens.push((value, *t.inner()));
                     *s = &s[ptr + 1..];
+                    /* An optional last value might be the start of the next token instead. */
+                    if let (true, ZeroOrOne(_), Some(next)) =
+                        (s.is_empty(), t, self.tokens.get(i + 1))
+                    {
+                        tokens.push((value, *next.inner()));
+                    }
                 }
             }
         }
@@ -220,6 +232,7 @@ pub enum Token {
     Seq(&'static [TokenAdicity]),
     AccountName,
     MailboxPath,
+    TagValue,
     QuotedStringValue,
     RestOfStringValue,
     AlphanumericStringValue,
@@ -261,6 +274,52 @@ define_commands!([
                        }
                    )
                  },
+                 { tags: ["purge"],
+                   desc: "permanently delete message",
+                   tokens: &[One(Literal("purge"))],
+                   parser: (
+                       fn purge_message<'a>(input: &'a [u8]) -> IResult<&'a [u8], Action> {
+                           let (input, ret) = map(preceded(tag("purge"), eof), |_| Listing(Purge))(input)?;
+                           let (input, _) = eof(input)?;
+                           Ok((input, ret))
+                       }
+                   )
+                 },
+                 { tags: ["archive"],
+                   desc: "move message to the Archive mailbox",
+                   tokens: &[One(Literal("archive"))],
+                   parser: (
+                       fn archive_message<'a>(input: &'a [u8]) -> IResult<&'a [u8], Action> {
+                           let (input, ret) = map(preceded(tag("archive"), eof), |_| Listing(Archive))(input)?;
+                           let (input, _) = eof(input)?;
+                           Ok((input, ret))
+                       }
+                   )
+                 },
+                 { tags: ["snooze"],
+                   desc: "snooze <TIME>, hides message until TIME (e.g. 2h, 3d, 2024-01-31 09:00)",
+                   tokens: &[One(Literal("snooze")), One(RestOfStringValue)],
+                   parser: (
+                       fn snooze_message<'a>(input: &'a [u8]) -> IResult<&'a [u8], Action> {
+                           let (input, _) = tag("snooze")(input.trim())?;
+                           let (input, _) = is_a(" ")(input)?;
+                           let (input, when) = map_res(not_line_ending, std::str::from_utf8)(input)?;
+                           let (input, _) = eof(input)?;
+                           Ok((input, Listing(Snooze(when.trim().to_string()))))
+                       }
+                   )
+                 },
+                 { tags: ["awaiting-reply"],
+                   desc: "show sent messages with a follow-up reminder that haven't been replied to",
+                   tokens: &[One(Literal("awaiting-reply"))],
+                   parser: (
+                       fn awaiting_reply<'a>(input: &'a [u8]) -> IResult<&'a [u8], Action> {
+                           let (input, _) = tag("awaiting-reply")(input.trim())?;
+                           let (input, _) = eof(input)?;
+                           Ok((input, Listing(AwaitingReply)))
+                       }
+                   )
+                 },
                  { tags: ["copyto", "moveto"],
                    desc: "copy/move message",
                    tokens: &[One(Alternatives(&[to_stream!(One(Literal("copyto"))), to_stream!(One(Literal("moveto")))])), ZeroOrOne(AccountName), One(MailboxPath)],
@@ -356,8 +415,8 @@ define_commands!([
                    )
                  },
                 { tags: ["sort"],
-                  desc: "sort [date/subject] [asc/desc], sorts threads.",
-                   tokens: &[One(Literal("sort")), One(Alternatives(&[to_stream!(One(Literal("date"))), to_stream!(One(Literal("subject")))])), One(Alternatives(&[to_stream!(One(Literal("asc"))), to_stream!(One(Literal("desc")))])) ],
+                  desc: "sort [date/subject/sender/size/attachments/unread/activity] [asc/desc], sorts threads and remembers the choice for the mailbox.",
+                   tokens: &[One(Literal("sort")), One(Alternatives(&[to_stream!(One(Literal("date"))), to_stream!(One(Literal("subject"))), to_stream!(One(Literal("sender"))), to_stream!(One(Literal("size"))), to_stream!(One(Literal("attachments"))), to_stream!(One(Literal("unread"))), to_stream!(One(Literal("activity")))])), One(Alternatives(&[to_stream!(One(Literal("asc"))), to_stream!(One(Literal("desc")))])) ],
                   parser: (
                       fn sort(input: &[u8]) -> IResult<&[u8], Action> {
                           let (input, _) = tag("sort")(input)?;
@@ -393,6 +452,28 @@ define_commands!([
                       }
                   )
                 },
+                { tags: ["link-threads"],
+                  desc: "merge the selected threads into the oldest one",
+                  tokens: &[One(Literal("link-threads"))],
+                  parser: (
+                      fn link_threads(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("link-threads")(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(LinkThreads)))
+                      }
+                  )
+                },
+                { tags: ["break-thread"],
+                  desc: "break the opened message and its replies out of their thread",
+                  tokens: &[One(Literal("break-thread"))],
+                  parser: (
+                      fn break_thread(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("break-thread")(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(BreakThread)))
+                      }
+                  )
+                },
                 { tags: ["search"],
                   desc: "search <TERM>, searches list with given term",
                   tokens: &[One(Literal("search")), One(RestOfStringValue)],
@@ -420,21 +501,70 @@ define_commands!([
                   )
                 },
                 { tags: ["export-mbox "],
-                  desc: "export-mbox PATH",
-                  tokens: &[One(Literal("export-mbox")), One(Filepath)],
+                  desc: "export-mbox [append|overwrite] PATH",
+                  tokens: &[One(Literal("export-mbox")), ZeroOrOne(Alternatives(&[to_stream!(One(Literal("append"))), to_stream!(One(Literal("overwrite")))])), One(Filepath)],
                   parser:(
                       fn export_mbox(input: &[u8]) -> IResult<&[u8], Action> {
                           let (input, _) = tag("export-mbox")(input.trim())?;
                           let (input, _) = is_a(" ")(input)?;
+                          let (input, mode) = export_mode(input)?;
+                          let (input, path) = quoted_argument(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(Export(ExportFormat::Mbox(melib::backends::mbox::MboxFormat::MboxCl2), mode, path.to_string().into()))))
+                      }
+                  )
+                },
+                { tags: ["export-maildir "],
+                  desc: "export-maildir [append|overwrite] PATH",
+                  tokens: &[One(Literal("export-maildir")), ZeroOrOne(Alternatives(&[to_stream!(One(Literal("append"))), to_stream!(One(Literal("overwrite")))])), One(Filepath)],
+                  parser:(
+                      fn export_maildir(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("export-maildir")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, mode) = export_mode(input)?;
                           let (input, path) = quoted_argument(input.trim())?;
                           let (input, _) = eof(input)?;
-                          Ok((input, Listing(ExportMbox(Some(melib::backends::mbox::MboxFormat::MboxCl2), path.to_string().into()))))
+                          Ok((input, Listing(Export(ExportFormat::Maildir, mode, path.to_string().into()))))
                       }
                   )
                 },
-                { tags: ["list-archive", "list-post", "list-unsubscribe", "list-"],
-                  desc: "list-[unsubscribe/post/archive]",
-                  tokens: &[One(Alternatives(&[to_stream!(One(Literal("list-archive"))), to_stream!(One(Literal("list-post"))), to_stream!(One(Literal("list-unsubscribe")))]))],
+                { tags: ["export-mailbox "],
+                  desc: "export-mailbox FORMAT [append|overwrite] PATH",
+                  tokens: &[One(Literal("export-mailbox")), One(Alternatives(&[to_stream!(One(Literal("mbox"))), to_stream!(One(Literal("maildir")))])), ZeroOrOne(Alternatives(&[to_stream!(One(Literal("append"))), to_stream!(One(Literal("overwrite")))])), One(Filepath)],
+                  parser:(
+                      fn export_mailbox(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("export-mailbox")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, format) = export_format(input)?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, mode) = export_mode(input)?;
+                          let (input, path) = quoted_argument(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(ExportMailbox(format, mode, None, path.to_string().into()))))
+                      }
+                  )
+                },
+                { tags: ["export-search "],
+                  desc: "export-search FORMAT [append|overwrite] PATH QUERY",
+                  tokens: &[One(Literal("export-search")), One(Alternatives(&[to_stream!(One(Literal("mbox"))), to_stream!(One(Literal("maildir")))])), ZeroOrOne(Alternatives(&[to_stream!(One(Literal("append"))), to_stream!(One(Literal("overwrite")))])), One(Filepath), One(RestOfStringValue)],
+                  parser:(
+                      fn export_search(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("export-search")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, format) = export_format(input)?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, mode) = export_mode(input)?;
+                          let (input, path) = quoted_argument(input)?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, query) = map_res(not_line_ending, std::str::from_utf8)(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(ExportMailbox(format, mode, Some(query.to_string()), path.to_string().into()))))
+                      }
+                  )
+                },
+                { tags: ["list-archive", "list-post", "list-unsubscribe", "list-reply", "list-filter", "list-"],
+                  desc: "list-[unsubscribe/post/archive/reply/filter]",
+                  tokens: &[One(Alternatives(&[to_stream!(One(Literal("list-archive"))), to_stream!(One(Literal("list-post"))), to_stream!(One(Literal("list-unsubscribe"))), to_stream!(One(Literal("list-reply"))), to_stream!(One(Literal("list-filter")))]))],
                   parser: (
                       fn mailinglist(input: &[u8]) -> IResult<&[u8], Action> {
                           let (input, ret) = alt((
@@ -445,6 +575,8 @@ define_commands!([
                               , map(tag("list-archive"), |_| MailingListAction(
                                       ListArchive
                               ))
+                              , map(tag("list-reply"), |_| MailingListAction(ListReply))
+                              , map(tag("list-filter"), |_| MailingListAction(ListFilter))
                           ))(input.trim())?;
                           let (input, _) = eof(input)?;
                           Ok((input, ret))
@@ -510,6 +642,109 @@ define_commands!([
                       }
                   )
                 },
+                /* Pipe the message, its body or one of its attachments to a shell command */
+                { tags: ["pipe-raw ", "pipe-body ", "pipe-attachment "],
+                  desc: "pipe-raw SHELL_COMMAND, pipe-body SHELL_COMMAND, pipe-attachment INDEX SHELL_COMMAND",
+                  tokens: &[One(
+Alternatives(&[to_stream!(One(Literal("pipe-raw")), One(RestOfStringValue)), to_stream!(One(Literal("pipe-body")), One(RestOfStringValue)), to_stream!(One(Literal("pipe-attachment")), One(AttachmentIndexValue), One(RestOfStringValue))]))],
+                  parser:(
+                      fn pipe_message<'a>(input: &'a [u8]) -> IResult<&'a [u8], Action> {
+                          fn shell_command(input: &[u8]) -> IResult<&[u8], String> {
+                              let (input, cmd) = map_res(not_line_ending, std::str::from_utf8)(input)?;
+                              if cmd.trim().is_empty() {
+                                  return Err(nom::Err::Error((input, nom::error::ErrorKind::Tag)));
+                              }
+                              let (input, _) = eof(input)?;
+                              Ok((input, cmd.trim().to_string()))
+                          }
+                          alt((
+                                  |input: &'a [u8]| -> IResult<&'a [u8], Action> {
+                                      let (input, _) = tag("pipe-raw")(input.ltrim())?;
+                                      let (input, _) = is_a(" ")(input)?;
+                                      let (input, cmd) = shell_command(input)?;
+                                      Ok((input, View(PipeRaw(cmd))))
+                                  }, |input: &'a [u8]| -> IResult<&'a [u8], Action> {
+                                      let (input, _) = tag("pipe-body")(input.ltrim())?;
+                                      let (input, _) = is_a(" ")(input)?;
+                                      let (input, cmd) = shell_command(input)?;
+                                      Ok((input, View(PipeBody(cmd))))
+                                  }, |input: &'a [u8]| -> IResult<&'a [u8], Action> {
+                                      let (input, _) = tag("pipe-attachment")(input.ltrim())?;
+                                      let (input, _) = is_a(" ")(input)?;
+                                      let (input, idx) = map_res(quoted_argument, usize::from_str)(input)?;
+                                      let (input, _) = is_a(" ")(input)?;
+                                      let (input, cmd) = shell_command(input)?;
+                                      Ok((input, View(PipeAttachment(idx, cmd))))
+                                  }
+                          ))(input)
+                      }
+                  )
+                },
+                { tags: ["apply-patch"],
+                  desc: "apply-patch [INDEX]",
+                  tokens: &[One(Literal("apply-patch")), ZeroOrOne(AttachmentIndexValue)],
+                  parser:(
+                      fn apply_patch(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("apply-patch")(input.trim())?;
+                          let (input, idx) = opt(preceded(is_a(" "), map_res(quoted_argument, usize::from_str)))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, View(ApplyPatch(idx))))
+                      }
+                  )
+                },
+                { tags: ["rsvp "],
+                  desc: "rsvp accept|tentative|decline",
+                  tokens: &[One(Literal("rsvp")), One(Alternatives(&[to_stream!(One(Literal("accept"))), to_stream!(One(Literal("tentative"))), to_stream!(One(Literal("decline")))]))],
+                  parser:(
+                      fn rsvp(input: &[u8]) -> IResult<&[u8], Action> {
+                          use melib::icalendar::PartStat;
+                          let (input, _) = tag("rsvp")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, status) = alt((
+                                  map(tag("accept"), |_| PartStat::Accepted),
+                                  map(tag("tentative"), |_| PartStat::Tentative),
+                                  map(tag("decline"), |_| PartStat::Declined),
+                          ))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, View(Rsvp(status))))
+                      }
+                  )
+                },
+                { tags: ["import-vcard"],
+                  desc: "import-vcard [INDEX]",
+                  tokens: &[One(Literal("import-vcard")), ZeroOrOne(AttachmentIndexValue)],
+                  parser:(
+                      fn import_vcard(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("import-vcard")(input.trim())?;
+                          let (input, idx) = opt(preceded(is_a(" "), map_res(quoted_argument, usize::from_str)))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, View(ImportVcard(idx))))
+                      }
+                  )
+                },
+                { tags: ["import-key"],
+                  desc: "import-key [INDEX]",
+                  tokens: &[One(Literal("import-key")), ZeroOrOne(AttachmentIndexValue)],
+                  parser:(
+                      fn import_key(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("import-key")(input.trim())?;
+                          let (input, idx) = opt(preceded(is_a(" "), map_res(quoted_argument, usize::from_str)))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, View(ImportKey(idx))))
+                      }
+                  )
+                },
+                { tags: ["lookup-sender-key"],
+                  desc: "look the sender's key up on the configured keyserver",
+                  tokens: &[One(Literal("lookup-sender-key"))],
+                  parser:(
+                      fn lookup_sender_key(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("lookup-sender-key")(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, View(LookupSenderKey)))
+                      }
+                  )
+                },
                 { tags: ["add-attachment ", "add-attachment-file-picker "],
                   desc: "add-attachment PATH",
                   tokens: &[One(
@@ -572,6 +807,19 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["remind-if-no-reply "],
+                  desc: "remind-if-no-reply <TIME>, notify if there's no reply to this message by TIME (e.g. 3d, 2024-01-31 09:00)",
+                  tokens: &[One(Literal("remind-if-no-reply")), One(RestOfStringValue)],
+                  parser:(
+                      fn remind_if_no_reply(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("remind-if-no-reply")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, when) = map_res(not_line_ending, std::str::from_utf8)(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Compose(RemindIfNoReply(when.trim().to_string()))))
+                      }
+                  )
+                },
                 { tags: ["toggle sign "],
                   desc: "switch between sign/unsign for this draft",
                   tokens: &[One(Literal("toggle")), One(Literal("sign"))],
@@ -598,6 +846,24 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["encrypt-preference "],
+                  desc: "encrypt-preference always|never|auto, always or never encrypt to the recipients of this draft",
+                  tokens: &[One(Literal("encrypt-preference")), One(Alternatives(&[to_stream!(One(Literal("always"))), to_stream!(One(Literal("never"))), to_stream!(One(Literal("auto")))]))],
+                  parser:(
+                      fn encrypt_preference(input: &[u8]) -> IResult<&[u8], Action> {
+                          use crate::conf::EncryptPreference;
+                          let (input, _) = tag("encrypt-preference")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, preference) = alt((
+                              map(tag("always"), |_| Some(EncryptPreference::Always)),
+                              map(tag("never"), |_| Some(EncryptPreference::Never)),
+                              map(tag("auto"), |_| None),
+                          ))(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Compose(SetEncryptPreference(preference))))
+                      }
+                  )
+                },
                 { tags: ["create-mailbox "],
                   desc: "create-mailbox ACCOUNT MAILBOX_PATH",
                   tokens: &[One(Literal("create-mailbox")), One(AccountName), One(MailboxPath)],
@@ -675,16 +941,39 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
-                { tags: ["reindex "],
-                  desc: "reindex ACCOUNT, rebuild account cache in the background",
-                  tokens: &[One(Literal("reindex")), One(AccountName)],
+                { tags: ["download-mailbox "],
+                  desc: "download-mailbox ACCOUNT MAILBOX_PATH, download the bodies of every message in MAILBOX_PATH for offline use",
+                  tokens: &[One(Literal("download-mailbox")), One(AccountName), One(MailboxPath)],
                   parser:(
-                      fn reindex(input: &[u8]) -> IResult<&[u8], Action> {
-                          let (input, _) = tag("reindex")(input.trim())?;
+                      fn download_mailbox(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("download-mailbox")(input.trim())?;
                           let (input, _) = is_a(" ")(input)?;
                           let (input, account) = quoted_argument(input)?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, path) = quoted_argument(input)?;
                           let (input, _) = eof(input)?;
-                          Ok( (input, AccountAction(account.to_string(), ReIndex)))
+                          Ok((input, Mailbox(account.to_string(), MailboxOperation::Download(path.to_string()))))
+                      }
+                  )
+                },
+                { tags: ["reindex"],
+                  desc: "reindex [ACCOUNT], drop and rebuild the search index of ACCOUNT, or of every account, in the background",
+                  tokens: &[One(Literal("reindex")), ZeroOrOne(AccountName)],
+                  parser:(
+                      fn reindex(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("reindex")(input.trim())?;
+                          alt((
+                              |input| {
+                                  let (input, _) = is_a(" ")(input)?;
+                                  let (input, account) = quoted_argument(input)?;
+                                  let (input, _) = eof(input)?;
+                                  Ok( (input, AccountAction(account.to_string(), ReIndex)))
+                              },
+                              |input| {
+                                  let (input, _) = eof(input)?;
+                                  Ok( (input, ReIndexAll))
+                              },
+                          ))(input)
                       }
                   )
                 },
@@ -714,6 +1003,38 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["save-attachments "],
+                  desc: "save-attachments all|INDEX[,INDEX..] [PATH]",
+                  tokens: &[One(Literal("save-attachments")), One(Alternatives(&[to_stream!(One(Literal("all"))), to_stream!(One(AttachmentIndexValue))])), ZeroOrOne(Filepath)],
+                  parser:(
+                      fn save_attachments(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("save-attachments")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, indices) = alt((
+                                  map(tag("all"), |_| None),
+                                  map(separated_list(tag(","), map_res(map_res(digit1, std::str::from_utf8), usize::from_str)), Some),
+                          ))(input)?;
+                          if indices.as_ref().map(Vec::is_empty).unwrap_or(false) {
+                              return Err(nom::Err::Error((input, nom::error::ErrorKind::Tag)));
+                          }
+                          let (input, path) = opt(preceded(is_a(" "), quoted_argument))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, View(SaveAttachments(indices, path.map(str::to_string)))))
+                      }
+                  )
+                },
+                { tags: ["save-thread-attachments"],
+                  desc: "save-thread-attachments [PATH]",
+                  tokens: &[One(Literal("save-thread-attachments")), ZeroOrOne(Filepath)],
+                  parser:(
+                      fn save_thread_attachments(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("save-thread-attachments")(input.trim())?;
+                          let (input, path) = opt(preceded(is_a(" "), quoted_argument))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, Listing(SaveThreadAttachments(path.map(|p| p.to_string().into())))))
+                      }
+                  )
+                },
                 { tags: ["export-mail "],
                   desc: "export-mail PATH",
                   tokens: &[One(Literal("export-mail")), One(Filepath)],
@@ -727,9 +1048,20 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["reassemble"],
+                  desc: "reassemble",
+                  tokens: &[One(Literal("reassemble"))],
+                  parser:(
+                      fn reassemble(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("reassemble")(input.trim())?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, View(Reassemble)))
+                      }
+                  )
+                },
                 { tags: ["tag", "tag add", "tag remove"],
                    desc: "tag [add/remove], edits message's tags.",
-                   tokens: &[One(Literal("tag")), One(Alternatives(&[to_stream!(One(Literal("add"))), to_stream!(One(Literal("remove")))]))],
+                   tokens: &[One(Literal("tag")), One(Alternatives(&[to_stream!(One(Literal("add")), One(TagValue)), to_stream!(One(Literal("remove")), One(TagValue))]))],
                    parser: (
                        fn _tag<'a>(input: &'a [u8]) -> IResult<&'a [u8], Action> {
                            preceded(
@@ -752,6 +1084,54 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                        }
                    )
                 },
+                { tags: ["import-contacts ", "export-contacts "],
+                  desc: "import-contacts/export-contacts ACCOUNT mutt|abook PATH, import contacts from or export them to a mutt alias file or an abook address book",
+                  tokens: &[One(Alternatives(&[to_stream!(One(Literal("import-contacts"))), to_stream!(One(Literal("export-contacts")))])), One(AccountName), One(Alternatives(&[to_stream!(One(Literal("mutt"))), to_stream!(One(Literal("abook")))])), One(Filepath)],
+                  parser:(
+                      fn contacts(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, export) = alt((map(tag("import-contacts"), |_| false), map(tag("export-contacts"), |_| true)))(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, account) = quoted_argument(input)?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, format) = alt((map(tag("mutt"), |_| ContactsFormat::MuttAliases), map(tag("abook"), |_| ContactsFormat::Abook)))(input)?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, path) = quoted_argument(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, AccountAction(account.to_string(), if export {
+                              ExportContacts(format, path.to_string())
+                          } else {
+                              ImportContacts(format, path.to_string())
+                          })))
+                      }
+                  )
+                },
+                { tags: ["purge-addresses "],
+                  desc: "purge-addresses ACCOUNT [DAYS], forget collected addresses, or only those not seen in the last DAYS days",
+                  tokens: &[One(Literal("purge-addresses")), One(AccountName), ZeroOrOne(QuotedStringValue)],
+                  parser:(
+                      fn purge_addresses(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("purge-addresses")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, account) = quoted_argument(input)?;
+                          let (input, days) = opt(preceded(is_a(" "), map_res(quoted_argument, u64::from_str)))(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, AccountAction(account.to_string(), PurgeCollectedAddresses(days))))
+                      }
+                  )
+                },
+                { tags: ["manage-sieve "],
+                  desc: "manage-sieve ACCOUNT, list, edit and activate the Sieve scripts of an IMAP account on its ManageSieve server",
+                  tokens: &[One(Literal("manage-sieve")), One(AccountName)],
+                  parser:(
+                      fn manage_sieve(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("manage-sieve")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, account) = quoted_argument(input)?;
+                          let (input, _) = eof(input)?;
+                          Ok((input, AccountAction(account.to_string(), ManageSieve)))
+                      }
+                  )
+                },
                 { tags: ["print "],
                   desc: "print ACCOUNT SETTING",
                   tokens: &[One(Literal("print")), One(AccountName), One(QuotedStringValue)],
@@ -780,6 +1160,81 @@ Alternatives(&[to_stream!(One(Literal("add-attachment")), One(Filepath)), to_str
                       }
                   )
                 },
+                { tags: ["unified-inbox"],
+                  desc: "open the inboxes of all accounts in a new tab",
+                  tokens: &[One(Literal("unified-inbox"))],
+                  parser:(
+                      fn unified_inbox(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("unified-inbox")(input.trim())?;
+                          let (input, _) = eof(input.trim())?;
+                          Ok((input, UnifiedInbox))
+                      }
+                  )
+                },
+                { tags: ["compose"],
+                  desc: "compose [MAILTO_URI], open a new composer tab, pre-filled from a mailto: URI if given",
+                  tokens: &[One(Literal("compose")), ZeroOrOne(RestOfStringValue)],
+                  parser:(
+                      fn compose(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("compose")(input.trim())?;
+                          if input.trim().is_empty() {
+                              return Ok((input.trim(), NewComposer(None)));
+                          }
+                          let (input, _) = is_a(" ")(input)?;
+                          let mailto = melib::email::Mailto::try_from(input.trim())
+                              .map_err(|_| nom::Err::Error((input, nom::error::ErrorKind::Verify)))?;
+                          Ok((&input[input.len()..], NewComposer(Some(mailto))))
+                      }
+                  )
+                },
+                { tags: ["set log_level "],
+                  desc: "set log_level [MODULE] LEVEL, changes the maximum level of logged messages, of MODULE only if given",
+                  tokens: &[One(Literal("set")), One(Literal("log_level")), ZeroOrOne(AlphanumericStringValue), One(Alternatives(&[to_stream!(One(Literal("OFF"))), to_stream!(One(Literal("FATAL"))), to_stream!(One(Literal("ERROR"))), to_stream!(One(Literal("WARN"))), to_stream!(One(Literal("INFO"))), to_stream!(One(Literal("DEBUG"))), to_stream!(One(Literal("TRACE")))]))],
+                  parser:(
+                      fn set_log_level(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("set")(input.trim())?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, _) = tag("log_level")(input)?;
+                          let (input, _) = is_a(" ")(input)?;
+                          let (input, first) = map_res(is_not(" "), std::str::from_utf8)(input)?;
+                          let (input, second) = opt(preceded(is_a(" "), map_res(is_not(" "), std::str::from_utf8)))(input)?;
+                          let (input, _) = eof(input.trim())?;
+                          let (module, level) = match second {
+                              Some(level) => (Some(first.to_string()), level),
+                              None => (None, first),
+                          };
+                          let level = level.parse::<melib::LoggingLevel>()
+                              .map_err(|_| nom::Err::Error((input, nom::error::ErrorKind::Verify)))?;
+                          Ok((input, SetLogLevel(module, level)))
+                      }
+                  )
+                },
+                { tags: ["jobs"],
+                  desc: "open a tab listing the jobs in progress of all accounts",
+                  tokens: &[One(Literal("jobs"))],
+                  parser:(
+                      fn jobs(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("jobs")(input.trim())?;
+                          let (input, _) = eof(input.trim())?;
+                          Ok((input, Jobs))
+                      }
+                  )
+                },
+                { tags: ["log"],
+                  desc: "log [LEVEL], open a tab showing the recent records of meli's log, optionally only those at least as severe as LEVEL",
+                  tokens: &[One(Literal("log")), ZeroOrOne(Alternatives(&[to_stream!(One(Literal("FATAL"))), to_stream!(One(Literal("ERROR"))), to_stream!(One(Literal("WARN"))), to_stream!(One(Literal("INFO"))), to_stream!(One(Literal("DEBUG"))), to_stream!(One(Literal("TRACE")))]))],
+                  parser:(
+                      fn log(input: &[u8]) -> IResult<&[u8], Action> {
+                          let (input, _) = tag("log")(input.trim())?;
+                          let (input, level) = opt(preceded(is_a(" "), map_res(is_not(" "), std::str::from_utf8)))(input)?;
+                          let (input, _) = eof(input.trim())?;
+                          let level = level.map(|l| l.parse::<melib::LoggingLevel>())
+                              .transpose()
+                              .map_err(|_| nom::Err::Error((input, nom::error::ErrorKind::Verify)))?;
+                          Ok((input, Log(level)))
+                      }
+                  )
+                },
                 { tags: ["toggle mouse"],
                   desc: "toggle mouse support",
                   tokens: &[One(Literal("toggle")), One(Literal("mouse"))],
@@ -854,18 +1309,44 @@ fn conversations(input: &[u8]) -> IResult<&[u8], Action> {
     map(tag("conversations"), |_| Listing(SetConversations))(input.trim())
 }
 
+fn export_format(input: &[u8]) -> IResult<&[u8], ExportFormat> {
+    map_res(map_res(is_not(" "), std::str::from_utf8), str::parse)(input)
+}
+
+/// An optional `append` or `overwrite` argument followed by a space, so that a path named
+/// `append` alone is still parsed as a path.
+fn export_mode(input: &[u8]) -> IResult<&[u8], ExportMode> {
+    map(
+        opt(terminated(
+            map_res(map_res(is_not(" "), std::str::from_utf8), str::parse),
+            is_a(" "),
+        )),
+        Option::unwrap_or_default,
+    )(input)
+}
+
 fn listing_action(input: &[u8]) -> IResult<&[u8], Action> {
     alt((
         toggle,
         seen_flag,
         delete_message,
+        purge_message,
+        archive_message,
+        snooze_message,
+        awaiting_reply,
         copymove,
         import,
         search,
         select,
         toggle_thread_snooze,
+        link_threads,
+        break_thread,
         open_in_new_tab,
         export_mbox,
+        export_maildir,
+        export_mailbox,
+        export_search,
+        save_thread_attachments,
         _tag,
     ))(input)
 }
@@ -876,16 +1357,36 @@ fn compose_action(input: &[u8]) -> IResult<&[u8], Action> {
         remove_attachment,
         toggle_sign,
         toggle_encrypt,
+        encrypt_preference,
         save_draft,
+        remind_if_no_reply,
     ))(input)
 }
 
 fn account_action(input: &[u8]) -> IResult<&[u8], Action> {
-    alt((reindex, print_account_setting))(input)
+    alt((
+        reindex,
+        contacts,
+        purge_addresses,
+        manage_sieve,
+        print_account_setting,
+    ))(input)
 }
 
 fn view(input: &[u8]) -> IResult<&[u8], Action> {
-    alt((pipe, save_attachment, export_mail))(input)
+    alt((
+        pipe_message,
+        apply_patch,
+        rsvp,
+        import_vcard,
+        import_key,
+        lookup_sender_key,
+        pipe,
+        save_attachment,
+        save_attachments,
+        export_mail,
+        reassemble,
+    ))(input)
 }
 
 pub fn parse_command(input: &[u8]) -> Result<Action, MeliError> {
@@ -905,16 +1406,53 @@ pub fn parse_command(input: &[u8]) -> Result<Action, MeliError> {
         unsub_mailbox,
         delete_mailbox,
         rename_mailbox,
+        download_mailbox,
         account_action,
         print_setting,
+        unified_inbox,
         toggle_mouse,
-        reload_config,
-        quit,
+        alt((jobs, log, compose, set_log_level, reload_config, quit)),
     ))(input)
     .map(|(_, v)| v)
     .map_err(|err| err.into())
 }
 
+#[test]
+fn test_command_completion_values() {
+    let (_, _, tokens) = COMMAND_COMPLETION
+        .iter()
+        .find(|(tags, _, _)| *tags == "moveto")
+        .unwrap();
+    let mut sugg = HashSet::default();
+    let m = tokens.matches(&mut "moveto INB", &mut sugg);
+    assert!(matches!(m[m.len() - 2], ("INB", AccountName)));
+    assert!(matches!(m[m.len() - 1], ("INB", MailboxPath)));
+
+    let candidates = || {
+        vec![
+            "INBOX".to_string(),
+            "INBOX/lists".to_string(),
+            "Sent Mail".to_string(),
+        ]
+        .into_iter()
+    };
+    complete_value("INBOX/", candidates(), &mut sugg);
+    complete_value("\"Se", candidates(), &mut sugg);
+    complete_value("", candidates(), &mut sugg);
+    let mut sugg = sugg.into_iter().collect::<Vec<String>>();
+    sugg.sort();
+    assert_eq!(
+        sugg,
+        vec![
+            " \"Sent Mail\"",
+            " INBOX",
+            " INBOX/lists",
+            "lists",
+            "nt Mail\""
+        ]
+    );
+}
+
 #[test]
 #[ignore]
 fn test_parser() {
@@ -957,8 +1495,35 @@ fn test_parser() {
     println!("alright");
 }
 
+/// Insert the completions of the partially typed value `partial` among `candidates` into `sugg`,
+/// quoting values with spaces.
+fn complete_value<I: Iterator<Item = String>>(
+    partial: &str,
+    candidates: I,
+    sugg: &mut HashSet<String>,
+) {
+    let unquoted = partial.trim_start_matches('"');
+    for candidate in candidates {
+        if !candidate.starts_with(unquoted) || candidate.len() == unquoted.len() {
+            continue;
+        }
+        let has_spaces = candidate.contains(' ');
+        if partial.is_empty() {
+            sugg.insert(if has_spaces {
+                format!(" \"{}\"", candidate)
+            } else {
+                format!(" {}", candidate)
+            });
+        } else if partial.starts_with('"') {
+            sugg.insert(format!("{}\"", &candidate[unquoted.len()..]));
+        } else if !has_spaces {
+            sugg.insert(candidate[unquoted.len()..].to_string());
+        }
+    }
+}
+
 /// Get command suggestions for input
-pub fn command_completion_suggestions(input: &str) -> Vec<String> {
+pub fn command_completion_suggestions(input: &str, context: &crate::Context) -> Vec<String> {
     use crate::melib::ShellExpandTrait;
     let mut sugg = Default::default();
     for (_tags, _desc, tokens) in COMMAND_COMPLETION.iter() {
@@ -970,6 +1535,46 @@ pub fn command_completion_suggestions(input: &str) -> Vec<String> {
             let p = std::path::Path::new(s);
             sugg.extend(p.complete(true).into_iter().map(|m| m.into()));
         }
+        let account = _m.iter().find_map(|(s, t)| match t {
+            AccountName => context
+                .accounts
+                .values()
+                .find(|a| a.name() == s.trim().trim_matches('"')),
+            _ => None,
+        });
+        let accounts = match account {
+            Some(a) => vec![a],
+            None => context.accounts.values().collect(),
+        };
+        let last = _m.last().unwrap().0;
+        for (_, t) in _m.iter().rev().take_while(|(s, _)| *s == last) {
+            let candidates: Vec<String> = match t {
+                AccountName => context
+                    .accounts
+                    .values()
+                    .map(|a| a.name().to_string())
+                    .collect(),
+                MailboxPath => accounts
+                    .iter()
+                    .flat_map(|a| a.mailbox_entries.values())
+                    .map(|m| m.ref_mailbox.path().to_string())
+                    .collect(),
+                TagValue => accounts
+                    .iter()
+                    .flat_map(|a| {
+                        a.collection
+                            .tag_index
+                            .read()
+                            .unwrap()
+                            .values()
+                            .cloned()
+                            .collect::<Vec<String>>()
+                    })
+                    .collect(),
+                _ => continue,
+            };
+            complete_value(last.trim(), candidates.into_iter(), &mut sugg);
+        }
     }
     sugg.into_iter()
         .map(|s| {
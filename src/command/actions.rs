@@ -24,25 +24,30 @@
  */
 
 use crate::components::Component;
+use crate::conf::EncryptPreference;
+use melib::backends::{AccountHash, MailboxHash};
 pub use melib::thread::{SortField, SortOrder};
 use std::path::PathBuf;
 
 extern crate uuid;
 use uuid::Uuid;
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum TagAction {
     Add(String),
     Remove(String),
 }
 
-#[derive(Debug)]
+#[derive(Debug, Clone)]
 pub enum ListingAction {
     SetPlain,
     SetThreaded,
     SetCompact,
     SetConversations,
     Search(String),
+    /// Filter the listing by subject and author while a `search` command is being typed. An empty
+    /// term restores the listing.
+    SearchPreview(String),
     Select(String),
     SetSeen,
     SetUnseen,
@@ -51,11 +56,38 @@ pub enum ListingAction {
     MoveTo(MailboxPath),
     MoveToOtherAccount(AccountName, MailboxPath),
     Import(PathBuf, MailboxPath),
-    ExportMbox(Option<melib::backends::mbox::MboxFormat>, PathBuf),
+    /// Export the selected threads.
+    Export(
+        crate::export::ExportFormat,
+        crate::export::ExportMode,
+        PathBuf,
+    ),
+    /// Export the current mailbox, or the results of a search query in it.
+    ExportMailbox(
+        crate::export::ExportFormat,
+        crate::export::ExportMode,
+        Option<String>,
+        PathBuf,
+    ),
+    /// Save the attachments of the selected threads to a directory, or to `pager.attachment_dir`.
+    SaveThreadAttachments(Option<PathBuf>),
+    /// Delete the selected threads, or move them to the Trash mailbox if `move_to_trash` is set.
     Delete,
+    /// Delete the selected threads permanently.
+    Purge,
+    /// Move the selected threads to the Archive mailbox.
+    Archive,
+    /// Move the selected threads to the snooze mailbox until the given time.
+    Snooze(String),
+    /// Show only sent messages with a follow-up reminder that haven't been replied to.
+    AwaitingReply,
     OpenInNewTab,
     Tag(TagAction),
     ToggleThreadSnooze,
+    LinkThreads,
+    BreakThread,
+    /// Show a mailbox, e.g. when a new mail desktop notification is clicked.
+    OpenMailbox(AccountHash, MailboxHash),
 }
 
 #[derive(Debug)]
@@ -70,13 +102,32 @@ pub enum MailingListAction {
     ListPost,
     ListArchive,
     ListUnsubscribe,
+    /// Reply to the list's `List-Post` address only.
+    ListReply,
+    /// Show only the envelopes of the same mailing list.
+    ListFilter,
 }
 
 #[derive(Debug)]
 pub enum ViewAction {
     Pipe(String, Vec<String>),
+    PipeRaw(String),
+    PipeBody(String),
+    PipeAttachment(usize, String),
+    ApplyPatch(Option<usize>),
+    Rsvp(melib::icalendar::PartStat),
+    ImportVcard(Option<usize>),
+    /// Import the PGP keys of the attachment with the given index, or of all `application/pgp-keys`
+    /// attachments, into the keyring.
+    ImportKey(Option<usize>),
+    /// Look the sender's PGP key up on `pgp.keyserver` and import it into the keyring.
+    LookupSenderKey,
     SaveAttachment(usize, String),
+    /// Save the attachments with the given indices, or all of them, to a directory, or to
+    /// `pager.attachment_dir`.
+    SaveAttachments(Option<Vec<usize>>, Option<String>),
     ExportMail(String),
+    Reassemble,
 }
 
 #[derive(Debug)]
@@ -88,12 +139,26 @@ pub enum ComposeAction {
     SaveDraft,
     ToggleSign,
     ToggleEncrypt,
+    /// Always or never encrypt to the recipients of the draft, or forget their preference.
+    SetEncryptPreference(Option<EncryptPreference>),
+    /// Remind the user if there's no reply to the message by the given time.
+    RemindIfNoReply(String),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq)]
+pub enum ContactsFormat {
+    MuttAliases,
+    Abook,
 }
 
 #[derive(Debug)]
 pub enum AccountAction {
     ReIndex,
     PrintAccountSetting(String),
+    ImportContacts(ContactsFormat, String),
+    ExportContacts(ContactsFormat, String),
+    PurgeCollectedAddresses(Option<u64>),
+    ManageSieve,
 }
 
 #[derive(Debug)]
@@ -103,6 +168,7 @@ pub enum MailboxOperation {
     Subscribe(MailboxPath),
     Unsubscribe(MailboxPath),
     Rename(MailboxPath, NewMailboxPath),
+    Download(MailboxPath),
     // Placeholder
     SetPermissions(MailboxPath),
 }
@@ -121,7 +187,16 @@ pub enum Action {
     Compose(ComposeAction),
     Mailbox(AccountName, MailboxOperation),
     AccountAction(AccountName, AccountAction),
+    ReIndexAll,
     PrintSetting(String),
+    UnifiedInbox,
+    Jobs,
+    /// Open the log tab, showing records at most as verbose as the level if given.
+    Log(Option<melib::LoggingLevel>),
+    /// Open a composer tab, with the recipients, headers and body of a `mailto:` URI if given.
+    NewComposer(Option<melib::email::Mailto>),
+    /// Change the maximum level of logged messages, of a module only if given.
+    SetLogLevel(Option<String>, melib::LoggingLevel),
     ReloadConfiguration,
     ToggleMouse,
     Quit,
@@ -135,6 +210,10 @@ impl Action {
             Action::Sort(_, _) => false,
             Action::SubSort(_, _) => false,
             Action::Tab(_) => false,
+            /* Asks for confirmation itself, showing how it will unsubscribe */
+            Action::MailingListAction(MailingListAction::ListUnsubscribe) => false,
+            Action::MailingListAction(MailingListAction::ListReply) => false,
+            Action::MailingListAction(MailingListAction::ListFilter) => false,
             Action::MailingListAction(_) => true,
             Action::View(_) => false,
             Action::SetEnv(_, _) => false,
@@ -142,7 +221,13 @@ impl Action {
             Action::Compose(_) => false,
             Action::Mailbox(_, _) => true,
             Action::AccountAction(_, _) => false,
+            Action::ReIndexAll => false,
             Action::PrintSetting(_) => false,
+            Action::UnifiedInbox => false,
+            Action::Jobs => false,
+            Action::Log(_) => false,
+            Action::NewComposer(_) => false,
+            Action::SetLogLevel(_, _) => false,
             Action::ToggleMouse => false,
             Action::Quit => true,
             Action::ReloadConfiguration => false,
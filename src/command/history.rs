@@ -23,27 +23,62 @@ use std::fs::OpenOptions;
 use std::io::{Read, Write};
 use std::sync::{Arc, Mutex};
 
-thread_local!(static CMD_HISTORY_FILE: Arc<Mutex<std::fs::File>> = Arc::new(Mutex::new({
+fn open_history_file(name: &str) -> std::fs::File {
     let data_dir = xdg::BaseDirectories::with_prefix("meli").unwrap();
-OpenOptions::new().append(true) /* writes will append to a file instead of overwriting previous contents */
-                         .create(true) /* a new file will be created if the file does not yet already exist.*/
-                         .read(true)
-                         .open(data_dir.place_data_file("cmd_history").unwrap()).unwrap()
-})));
+    OpenOptions::new()
+        .append(true) /* writes will append to a file instead of overwriting previous contents */
+        .create(true) /* a new file will be created if the file does not yet already exist.*/
+        .read(true)
+        .open(data_dir.place_data_file(name).unwrap())
+        .unwrap()
+}
+
+thread_local!(static CMD_HISTORY_FILE: Arc<Mutex<std::fs::File>> = Arc::new(Mutex::new(open_history_file("cmd_history"))));
+thread_local!(static SEARCH_HISTORY_FILE: Arc<Mutex<std::fs::File>> = Arc::new(Mutex::new(open_history_file("search_history"))));
+
+fn log_line(file: &Mutex<std::fs::File>, mut line: String) {
+    line.push('\n');
+    file.lock().unwrap().write_all(line.as_bytes()).unwrap();
+}
 
-pub fn log_cmd(mut cmd: String) {
-    CMD_HISTORY_FILE.with(|f| {
-        cmd.push('\n');
-        f.lock().unwrap().write_all(cmd.as_bytes()).unwrap();
-    });
+fn read_lines(file: &Mutex<std::fs::File>) -> Vec<String> {
+    let mut old_history = String::new();
+    file.lock()
+        .unwrap()
+        .read_to_string(&mut old_history)
+        .unwrap();
+    old_history.lines().map(|s| s.to_string()).collect()
+}
+
+pub fn log_cmd(cmd: String) {
+    CMD_HISTORY_FILE.with(|f| log_line(f, cmd));
 }
 
 pub fn old_cmd_history() -> Vec<String> {
-    let mut ret = Vec::new();
-    CMD_HISTORY_FILE.with(|f| {
-        let mut old_history = String::new();
-        f.lock().unwrap().read_to_string(&mut old_history).unwrap();
-        ret.extend(old_history.lines().map(|s| s.to_string()));
-    });
-    ret
+    CMD_HISTORY_FILE.with(|f| read_lines(f))
+}
+
+/// Append the pattern of a `search`, `filter` or `select` command to the search history.
+pub fn log_search(pattern: String) {
+    SEARCH_HISTORY_FILE.with(|f| log_line(f, pattern));
+}
+
+pub fn old_search_history() -> Vec<String> {
+    SEARCH_HISTORY_FILE.with(|f| read_lines(f))
+}
+
+/// Whether the characters of `query` appear in `entry` in order, ignoring case.
+pub fn fuzzy_match(query: &str, entry: &str) -> bool {
+    let mut entry = entry.chars().flat_map(char::to_lowercase);
+    query
+        .chars()
+        .flat_map(char::to_lowercase)
+        .all(|q| entry.any(|c| c == q))
+}
+
+#[test]
+fn test_fuzzy_match() {
+    assert!(fuzzy_match("srch rust", "search Rust is:unseen"));
+    assert!(fuzzy_match("", "quit"));
+    assert!(!fuzzy_match("rust tag", "tag add rust"));
 }
@@ -34,6 +34,12 @@ pub use crate::mail::*;
 
 pub mod notifications;
 
+pub mod jobs;
+pub use self::jobs::*;
+
+pub mod log;
+pub use self::log::*;
+
 pub mod utilities;
 pub use self::utilities::*;
 
@@ -64,6 +70,30 @@ pub enum PageMovement {
     PageDown(usize),
     Home,
     End,
+    /// Move to the entry drawn on the given row of the visible page, e.g. after a mouse click.
+    Row(usize),
+}
+
+/// Lines scrolled by one step of the mouse wheel.
+pub const MOUSE_SCROLL_LINES: usize = 3;
+
+/// The position of mouse event coordinates relative to `area`, if they are inside it. Terminal
+/// mouse coordinates start at 1.
+pub fn mouse_position(x: u16, y: u16, area: Area) -> Option<(usize, usize)> {
+    let pos = (
+        usize::from(x).checked_sub(1)?,
+        usize::from(y).checked_sub(1)?,
+    );
+    if is_valid_area!(area)
+        && get_x(pos) >= get_x(upper_left!(area))
+        && get_x(pos) <= get_x(bottom_right!(area))
+        && get_y(pos) >= get_y(upper_left!(area))
+        && get_y(pos) <= get_y(bottom_right!(area))
+    {
+        Some(pos_dec(pos, upper_left!(area)))
+    } else {
+        None
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq)]
@@ -107,4 +137,11 @@ pub trait Component: Display + Debug + Send + Sync {
     fn get_status(&self, _context: &Context) -> String {
         String::new()
     }
+
+    /// The account and mailbox shown by the component, if any, for the status bar.
+    fn status_coordinates(&self) -> Option<(AccountHash, Option<MailboxHash>)> {
+        None
+    }
+    /// Add the state of the component that should be restored on the next start to `session`.
+    fn save_session(&self, _session: &mut crate::session::Session, _context: &Context) {}
 }
@@ -44,6 +44,7 @@ pub struct ContactManager {
     account_pos: usize,
     content: CellBuffer,
     theme_default: ThemeAttribute,
+    note_attr: ThemeAttribute,
     dirty: bool,
     has_changes: bool,
 
@@ -68,6 +69,7 @@ impl ContactManager {
             account_pos: 0,
             content: CellBuffer::new_with_context(100, 1, None, context),
             theme_default,
+            note_attr: crate::conf::value(context, "contacts.view.note"),
             dirty: true,
             has_changes: false,
             initialized: false,
@@ -80,18 +82,18 @@ impl ContactManager {
         let (x, _) = write_string_to_grid(
             "Last edited: ",
             &mut self.content,
-            Color::Byte(250),
-            self.theme_default.bg,
-            self.theme_default.attrs,
+            self.note_attr.fg,
+            self.note_attr.bg,
+            self.note_attr.attrs,
             ((0, 0), (width - 1, 0)),
             None,
         );
         let (x, y) = write_string_to_grid(
             &self.card.last_edited(),
             &mut self.content,
-            Color::Byte(250),
-            self.theme_default.bg,
-            self.theme_default.attrs,
+            self.note_attr.fg,
+            self.note_attr.bg,
+            self.note_attr.attrs,
             ((x, 0), (width - 1, 0)),
             None,
         );
@@ -102,9 +104,9 @@ impl ContactManager {
             write_string_to_grid(
                 "This contact's origin is external and cannot be edited within meli.",
                 &mut self.content,
-                Color::Byte(250),
-                self.theme_default.bg,
-                self.theme_default.attrs,
+                self.note_attr.fg,
+                self.note_attr.bg,
+                self.note_attr.attrs,
                 ((x, y), (width - 1, y)),
                 None,
             );
@@ -126,13 +128,44 @@ impl ContactManager {
             "NAME SUFFIX".into(),
             self.card.name_suffix().to_string().into(),
         ));
+        self.form.push((
+            "E-MAIL".into(),
+            melib::addressbook::labeled_emails_to_string(
+                &self.card,
+                std::iter::once(self.card.email()).filter(|e| !e.is_empty()),
+            )
+            .into(),
+        ));
+        /* Additional addresses as `label: address` entries separated by commas. */
+        self.form.push((
+            "OTHER E-MAILS".into(),
+            melib::addressbook::labeled_emails_to_string(
+                &self.card,
+                self.card
+                    .extra_property("EMAIL")
+                    .into_iter()
+                    .flat_map(|v| v.split(',').map(str::trim).filter(|s| !s.is_empty())),
+            )
+            .into(),
+        ));
+        self.form.push((
+            "ORGANIZATION".into(),
+            self.card.organization().to_string().into(),
+        ));
+        self.form.push((
+            "GROUPS".into(),
+            self.card.groups().collect::<Vec<&str>>().join(", ").into(),
+        ));
         self.form
-            .push(("E-MAIL".into(), self.card.email().to_string().into()));
+            .push(("NOTE".into(), self.card.note().to_string().into()));
         self.form
             .push(("URL".into(), self.card.url().to_string().into()));
         self.form
             .push(("KEY".into(), self.card.key().to_string().into()));
         for (k, v) in self.card.extra_properties() {
+            if ["EMAIL", "ORG", "CATEGORIES", "NOTE"].contains(&k.as_str()) {
+                continue;
+            }
             self.form.push((k.to_string().into(), v.to_string().into()));
         }
     }
@@ -183,6 +216,7 @@ impl Component for ContactManager {
         match event {
             UIEvent::ConfigReload { old_settings: _ } => {
                 self.theme_default = crate::conf::value(context, "theme_default");
+                self.note_attr = crate::conf::value(context, "contacts.view.note");
                 self.content = CellBuffer::new_with_context(100, 1, None, context);
                 self.initialized = false;
                 self.set_dirty(true);
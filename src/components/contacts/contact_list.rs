@@ -49,6 +49,7 @@ pub struct ContactList {
     data_columns: DataColumns,
     initialized: bool,
     theme_default: ThemeAttribute,
+    highlighted_attr: ThemeAttribute,
 
     id_positions: Vec<CardId>,
 
@@ -95,6 +96,7 @@ impl ContactList {
             mode: ViewMode::List,
             data_columns: DataColumns::default(),
             theme_default: crate::conf::value(context, "theme_default"),
+            highlighted_attr: crate::conf::value(context, "contacts.list.highlighted"),
             initialized: false,
             dirty: true,
             movement: None,
@@ -222,7 +224,7 @@ impl ContactList {
         /* Reset previously highlighted line */
         let fg_color = self.theme_default.fg;
         let bg_color = if idx == self.new_cursor_pos {
-            Color::Byte(246)
+            self.highlighted_attr.bg
         } else {
             self.theme_default.bg
         };
@@ -400,6 +402,12 @@ impl ContactList {
                     }
                 }
                 PageMovement::Right(_) | PageMovement::Left(_) => {}
+                PageMovement::Row(row) => {
+                    let idx = (self.new_cursor_pos / rows) * rows + row;
+                    if idx < self.length {
+                        self.new_cursor_pos = idx;
+                    }
+                }
                 PageMovement::Home => {
                     self.new_cursor_pos = 0;
                 }
@@ -574,7 +582,8 @@ impl Component for ContactList {
         if !self.dirty {
             return;
         }
-        if !self.initialized {
+        if !self.initialized || self.length != context.accounts[self.account_pos].address_book.len()
+        {
             self.initialize(context);
         }
 
@@ -619,6 +628,7 @@ impl Component for ContactList {
     fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
         if let UIEvent::ConfigReload { old_settings: _ } = event {
             self.theme_default = crate::conf::value(context, "theme_default");
+            self.highlighted_attr = crate::conf::value(context, "contacts.list.highlighted");
             self.initialized = false;
             self.sidebar_divider = context.settings.listing.sidebar_divider;
             self.sidebar_divider_theme = conf::value(context, "mail.sidebar_divider");
@@ -673,6 +683,54 @@ impl Component for ContactList {
 
                     return true;
                 }
+                UIEvent::Input(ref key)
+                    if shortcut!(key == shortcuts[Self::DESCRIPTION]["delete_contact"])
+                        && self.length > 0 =>
+                {
+                    let card = &context.accounts[self.account_pos].address_book
+                        [&self.id_positions[self.cursor_pos]];
+                    if card.external_resource() {
+                        context
+                            .replies
+                            .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            "This contact's origin is external and cannot be deleted within meli."
+                                .to_string(),
+                        )));
+                        return true;
+                    }
+                    let card_id = *card.id();
+                    let list_id = self.id;
+                    let title = format!("Delete contact {}?", card.name());
+                    context.replies.push_back(UIEvent::GlobalUIDialog(Box::new(
+                        UIConfirmationDialog::new(
+                            &title,
+                            vec![(true, "yes".to_string()), (false, "no".to_string())],
+                            true,
+                            Some(Box::new(move |_id: ComponentId, result: bool| {
+                                if result {
+                                    Some(UIEvent::FinishedUIDialog(list_id, Box::new(card_id)))
+                                } else {
+                                    None
+                                }
+                            })),
+                            context,
+                        ),
+                    )));
+                    return true;
+                }
+                UIEvent::FinishedUIDialog(id, ref results) if id == self.id => {
+                    if let Some(card_id) = results.downcast_ref::<CardId>() {
+                        context.accounts[self.account_pos]
+                            .address_book
+                            .remove_card(*card_id);
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage("Contact deleted.".to_string()),
+                        ));
+                        self.initialized = false;
+                        self.set_dirty(true);
+                    }
+                    return true;
+                }
                 UIEvent::Input(ref key)
                     if shortcut!(key == shortcuts[Self::DESCRIPTION]["mail_contact"])
                         && self.length > 0 =>
@@ -681,11 +739,11 @@ impl Component for ContactList {
                     let account_hash = account.hash();
                     let book = &account.address_book;
                     let card = &book[&self.id_positions[self.cursor_pos]];
-                    let mut draft: Draft = Draft::default();
-                    *draft.headers_mut().get_mut("To").unwrap() =
-                        format!("{} <{}>", &card.name(), &card.email());
+                    let to = Address::new(Some(card.name().to_string()), card.email().to_string());
                     let mut composer = Composer::with_account(account_hash, context);
-                    composer.set_draft(draft);
+                    if let Ok(draft) = Draft::builder().to(to).build() {
+                        composer.set_draft(draft);
+                    }
                     context
                         .replies
                         .push_back(UIEvent::Action(Tab(New(Some(Box::new(composer))))));
@@ -0,0 +1,264 @@
+/*
+ * meli - jobs tab module.
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! List the jobs in progress of all accounts and cancel them.
+ */
+use super::*;
+use crate::jobs::{JobId, Timer};
+use std::time::{Duration, Instant};
+
+#[derive(Debug)]
+pub struct JobsView {
+    /// Jobs shown in the last draw, oldest first.
+    rows: Vec<(AccountHash, JobId)>,
+    cursor_pos: usize,
+    status: String,
+    /// Redraws the running times of jobs every second.
+    timer: Timer,
+    theme_default: ThemeAttribute,
+    dirty: bool,
+    id: ComponentId,
+}
+
+impl fmt::Display for JobsView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", JobsView::DESCRIPTION)
+    }
+}
+
+/// Format the running time of a job, eg. `42s` or `3m 05s`.
+fn format_elapsed(elapsed: Duration) -> String {
+    let secs = elapsed.as_secs();
+    if secs < 60 {
+        format!("{}s", secs)
+    } else if secs < 3600 {
+        format!("{}m {:02}s", secs / 60, secs % 60)
+    } else {
+        format!("{}h {:02}m", secs / 3600, (secs % 3600) / 60)
+    }
+}
+
+impl JobsView {
+    pub const DESCRIPTION: &'static str = "jobs";
+
+    pub fn new(context: &Context) -> Self {
+        JobsView {
+            rows: vec![],
+            cursor_pos: 0,
+            status: String::new(),
+            timer: context
+                .job_executor
+                .clone()
+                .create_timer(Duration::from_secs(1), Duration::from_secs(1)),
+            theme_default: crate::conf::value(context, "theme_default"),
+            dirty: true,
+            id: ComponentId::new_v4(),
+        }
+    }
+
+    /// The active jobs of all accounts with the time they were started, oldest first.
+    fn active_jobs(context: &Context) -> Vec<(AccountHash, JobId, Option<Instant>)> {
+        let mut ret = context
+            .accounts
+            .values()
+            .flat_map(|account| {
+                account
+                    .active_jobs
+                    .keys()
+                    .map(move |job_id| (account.hash(), *job_id, account.job_start(job_id)))
+            })
+            .collect::<Vec<_>>();
+        ret.sort_by_key(|(_, _, start)| *start);
+        ret
+    }
+}
+
+impl Component for JobsView {
+    fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
+        if !self.is_dirty() {
+            return;
+        }
+        self.dirty = false;
+        clear_area(grid, area, self.theme_default);
+        let upper_left = upper_left!(area);
+        let bottom_right = bottom_right!(area);
+        let header_attrs = crate::conf::value(context, "widgets.list.header");
+        let highlight_attrs = crate::conf::value(context, "widgets.options.highlighted");
+        let jobs = Self::active_jobs(context);
+        self.rows = jobs
+            .iter()
+            .map(|(account_hash, job_id, _)| (*account_hash, *job_id))
+            .collect();
+        self.cursor_pos = std::cmp::min(self.cursor_pos, self.rows.len().saturating_sub(1));
+        let account_width = context
+            .accounts
+            .values()
+            .map(|a| a.name().grapheme_width())
+            .max()
+            .unwrap_or(0)
+            .max("ACCOUNT".len());
+        write_string_to_grid(
+            &format!(
+                "{:account_width$}  {:>10}  JOB",
+                "ACCOUNT",
+                "RUNNING",
+                account_width = account_width
+            ),
+            grid,
+            header_attrs.fg,
+            header_attrs.bg,
+            header_attrs.attrs,
+            (upper_left, bottom_right),
+            None,
+        );
+        if jobs.is_empty() {
+            write_string_to_grid(
+                "No jobs in progress.",
+                grid,
+                self.theme_default.fg,
+                self.theme_default.bg,
+                self.theme_default.attrs,
+                (pos_inc(upper_left, (0, 2)), bottom_right),
+                None,
+            );
+        }
+        let rows = height!(area).saturating_sub(3);
+        let top = if rows == 0 {
+            0
+        } else {
+            (self.cursor_pos / rows) * rows
+        };
+        let now = Instant::now();
+        for (i, (account_hash, job_id, start)) in jobs.iter().enumerate().skip(top).take(rows) {
+            let y = get_y(upper_left) + 2 + i - top;
+            let theme = if i == self.cursor_pos {
+                highlight_attrs
+            } else {
+                self.theme_default
+            };
+            let account = &context.accounts[account_hash];
+            let (x, _) = write_string_to_grid(
+                &format!(
+                    "{:account_width$}  {:>10}  {}",
+                    account.name(),
+                    start
+                        .map(|start| format_elapsed(now.duration_since(start)))
+                        .unwrap_or_default(),
+                    account.active_jobs[job_id],
+                    account_width = account_width
+                ),
+                grid,
+                theme.fg,
+                theme.bg,
+                theme.attrs,
+                ((get_x(upper_left), y), (get_x(bottom_right), y)),
+                None,
+            );
+            if i == self.cursor_pos {
+                change_colors(grid, ((x, y), (get_x(bottom_right), y)), theme.fg, theme.bg);
+            }
+        }
+        let y = get_y(bottom_right);
+        write_string_to_grid(
+            &self.status,
+            grid,
+            self.theme_default.fg,
+            self.theme_default.bg,
+            self.theme_default.attrs,
+            ((get_x(upper_left), y), (get_x(bottom_right), y)),
+            None,
+        );
+        context.dirty_areas.push_back(area);
+    }
+
+    fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        let shortcuts = self.get_shortcuts(context);
+        match event {
+            UIEvent::Timer(id) if *id == self.timer.id() => {
+                self.set_dirty(true);
+            }
+            UIEvent::StatusEvent(StatusEvent::NewJob(_))
+            | UIEvent::StatusEvent(StatusEvent::JobFinished(_))
+            | UIEvent::StatusEvent(StatusEvent::JobCanceled(_)) => {
+                self.set_dirty(true);
+            }
+            UIEvent::ConfigReload { old_settings: _ } => {
+                self.theme_default = crate::conf::value(context, "theme_default");
+                self.set_dirty(true);
+            }
+            UIEvent::Resize => {
+                self.set_dirty(true);
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["jobs"]["scroll_up"]) => {
+                self.cursor_pos = self.cursor_pos.saturating_sub(1);
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["jobs"]["scroll_down"]) => {
+                if self.cursor_pos + 1 < self.rows.len() {
+                    self.cursor_pos += 1;
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["jobs"]["cancel_job"]) => {
+                if let Some((account_hash, job_id)) = self.rows.get(self.cursor_pos).cloned() {
+                    if let Some(job) = context.accounts[&account_hash].cancel_job(job_id) {
+                        self.status = format!("Canceled: {}", job);
+                    }
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
+        let mut map = ShortcutMaps::default();
+        map.insert("jobs", context.settings.shortcuts.jobs.key_values());
+        map
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, value: bool) {
+        self.dirty = value;
+    }
+
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: ComponentId) {
+        self.id = id;
+    }
+}
+
+#[test]
+fn test_format_elapsed() {
+    assert_eq!(format_elapsed(Duration::from_secs(42)), "42s");
+    assert_eq!(format_elapsed(Duration::from_secs(185)), "3m 05s");
+    assert_eq!(format_elapsed(Duration::from_secs(7260)), "2h 01m");
+}
@@ -0,0 +1,306 @@
+/*
+ * meli - log tab module.
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Tail the records of meli's own log, filtered by level and search term.
+ */
+use super::*;
+use crate::jobs::Timer;
+use melib::{LogRecord, LoggingLevel};
+use std::time::Duration;
+
+#[derive(Debug)]
+pub struct LogView {
+    /// Records fetched so far, oldest first.
+    records: Vec<LogRecord>,
+    /// Sequence number of the next record to fetch.
+    next_seq: u64,
+    /// Least severe level shown.
+    level: LoggingLevel,
+    search: Option<String>,
+    /// Index of the selected row among the shown records.
+    cursor_pos: usize,
+    /// Rows of records shown in the last draw.
+    page_rows: usize,
+    /// Keep the cursor on the newest record as records arrive.
+    follow: bool,
+    /// Polls for new records every second.
+    timer: Timer,
+    theme_default: ThemeAttribute,
+    dirty: bool,
+    id: ComponentId,
+}
+
+impl fmt::Display for LogView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", LogView::DESCRIPTION)
+    }
+}
+
+/// Whether `record` is shown with least severe level `level` and search term `search`, which is
+/// matched case insensitively against the module and the message.
+fn record_matches(record: &LogRecord, level: LoggingLevel, search: Option<&str>) -> bool {
+    if record.level > level {
+        return false;
+    }
+    match search {
+        None => true,
+        Some(search) => {
+            let search = search.to_lowercase();
+            record.module.to_lowercase().contains(&search)
+                || record.message.to_lowercase().contains(&search)
+        }
+    }
+}
+
+impl LogView {
+    pub const DESCRIPTION: &'static str = "log";
+
+    pub fn new(level: Option<LoggingLevel>, context: &Context) -> Self {
+        let mut ret = LogView {
+            records: vec![],
+            next_seq: 0,
+            level: level.unwrap_or(LoggingLevel::TRACE),
+            search: None,
+            cursor_pos: 0,
+            page_rows: 1,
+            follow: true,
+            timer: context
+                .job_executor
+                .clone()
+                .create_timer(Duration::from_secs(1), Duration::from_secs(1)),
+            theme_default: crate::conf::value(context, "theme_default"),
+            dirty: true,
+            id: ComponentId::new_v4(),
+        };
+        ret.fetch_records();
+        ret
+    }
+
+    /// Append records logged since the last fetch. Returns whether there were any.
+    fn fetch_records(&mut self) -> bool {
+        let new = melib::log_records_since(self.next_seq);
+        if let Some(last) = new.last() {
+            self.next_seq = last.seq + 1;
+        } else {
+            return false;
+        }
+        self.records.extend(new);
+        if self.records.len() > melib::LOG_BUFFER_SIZE {
+            let excess = self.records.len() - melib::LOG_BUFFER_SIZE;
+            self.records.drain(..excess);
+        }
+        true
+    }
+}
+
+impl Component for LogView {
+    fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
+        if !self.is_dirty() {
+            return;
+        }
+        self.dirty = false;
+        clear_area(grid, area, self.theme_default);
+        let upper_left = upper_left!(area);
+        let bottom_right = bottom_right!(area);
+        let header_attrs = crate::conf::value(context, "widgets.list.header");
+        let highlight_attrs = crate::conf::value(context, "widgets.options.highlighted");
+        let (level, search) = (self.level, self.search.as_deref());
+        let records = self
+            .records
+            .iter()
+            .filter(|r| record_matches(r, level, search))
+            .collect::<Vec<&LogRecord>>();
+        if self.follow {
+            self.cursor_pos = records.len().saturating_sub(1);
+        } else {
+            self.cursor_pos = std::cmp::min(self.cursor_pos, records.len().saturating_sub(1));
+        }
+        write_string_to_grid(
+            &format!(
+                "LEVEL {}{}  {} of {} records",
+                self.level,
+                self.search
+                    .as_ref()
+                    .map(|s| format!("  SEARCH {}", s))
+                    .unwrap_or_default(),
+                records.len(),
+                self.records.len()
+            ),
+            grid,
+            header_attrs.fg,
+            header_attrs.bg,
+            header_attrs.attrs,
+            (upper_left, bottom_right),
+            None,
+        );
+        if records.is_empty() {
+            write_string_to_grid(
+                "No log records.",
+                grid,
+                self.theme_default.fg,
+                self.theme_default.bg,
+                self.theme_default.attrs,
+                (pos_inc(upper_left, (0, 2)), bottom_right),
+                None,
+            );
+        }
+        let rows = height!(area).saturating_sub(2);
+        self.page_rows = std::cmp::max(rows, 1);
+        let top = if rows == 0 {
+            0
+        } else {
+            (self.cursor_pos / rows) * rows
+        };
+        for (i, record) in records.iter().enumerate().skip(top).take(rows) {
+            let y = get_y(upper_left) + 2 + i - top;
+            let theme = if i == self.cursor_pos {
+                highlight_attrs
+            } else {
+                self.theme_default
+            };
+            let (x, _) = write_string_to_grid(
+                &record.to_string(),
+                grid,
+                theme.fg,
+                theme.bg,
+                theme.attrs,
+                ((get_x(upper_left), y), (get_x(bottom_right), y)),
+                None,
+            );
+            if i == self.cursor_pos {
+                change_colors(grid, ((x, y), (get_x(bottom_right), y)), theme.fg, theme.bg);
+            }
+        }
+        context.dirty_areas.push_back(area);
+    }
+
+    fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        let shortcuts = self.get_shortcuts(context);
+        match event {
+            UIEvent::Timer(id) if *id == self.timer.id() => {
+                if self.fetch_records() {
+                    self.set_dirty(true);
+                }
+            }
+            UIEvent::ConfigReload { old_settings: _ } => {
+                self.theme_default = crate::conf::value(context, "theme_default");
+                self.set_dirty(true);
+            }
+            UIEvent::Resize => {
+                self.set_dirty(true);
+            }
+            UIEvent::Action(Action::Listing(ListingAction::Search(pattern))) => {
+                self.search = Some(pattern.to_string());
+                self.follow = true;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(Key::Esc) if self.search.is_some() => {
+                self.search = None;
+                self.follow = true;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["log"]["scroll_up"]) => {
+                self.cursor_pos = self.cursor_pos.saturating_sub(1);
+                self.follow = false;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["log"]["scroll_down"]) => {
+                self.cursor_pos += 1;
+                self.follow = false;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["log"]["page_up"]) => {
+                self.cursor_pos = self.cursor_pos.saturating_sub(self.page_rows);
+                self.follow = false;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["log"]["page_down"]) => {
+                self.cursor_pos += self.page_rows;
+                self.follow = false;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["log"]["go_to_end"]) => {
+                self.follow = true;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["log"]["cycle_level"]) => {
+                use LoggingLevel::*;
+                self.level = match self.level {
+                    TRACE => DEBUG,
+                    DEBUG => INFO,
+                    INFO => WARN,
+                    WARN => ERROR,
+                    ERROR | FATAL | OFF => TRACE,
+                };
+                self.follow = true;
+                self.set_dirty(true);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
+        let mut map = ShortcutMaps::default();
+        map.insert("log", context.settings.shortcuts.log.key_values());
+        map
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, value: bool) {
+        self.dirty = value;
+    }
+
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: ComponentId) {
+        self.id = id;
+    }
+}
+
+#[test]
+fn test_record_matches() {
+    let record = LogRecord {
+        seq: 0,
+        timestamp: 0,
+        level: LoggingLevel::WARN,
+        module: "imap".to_string(),
+        message: "Connection reset by peer".to_string(),
+    };
+    assert!(record_matches(&record, LoggingLevel::TRACE, None));
+    assert!(!record_matches(&record, LoggingLevel::ERROR, None));
+    assert!(record_matches(&record, LoggingLevel::WARN, Some("RESET")));
+    assert!(record_matches(&record, LoggingLevel::WARN, Some("imap")));
+    assert!(!record_matches(&record, LoggingLevel::WARN, Some("smtp")));
+}
@@ -39,6 +39,9 @@ pub mod pgp;
 mod status;
 pub use self::status::*;
 
+mod sieve;
+pub use self::sieve::*;
+
 fn get_display_name(context: &Context, account_hash: AccountHash) -> String {
     let settings = context.accounts[&account_hash].settings.account();
     if let Some(d) = settings.display_name.as_ref() {
@@ -25,6 +25,7 @@ use melib::list_management;
 use melib::Draft;
 
 use crate::conf::accounts::JobRequest;
+use crate::conf::hooks::{run_hook, spawn_hook};
 use crate::jobs::JoinHandle;
 use crate::terminal::embed::EmbedGrid;
 use indexmap::IndexSet;
@@ -91,8 +92,18 @@ pub struct Composer {
 
     embed_area: Area,
     embed: Option<EmbedStatus>,
+    /// Lines scrolled back in the embedded terminal's scrollback, if browsing it.
+    embed_scrollback: Option<usize>,
     #[cfg(feature = "gpgme")]
     gpg_state: gpg::GpgComposeState,
+    /// Remote lookups of recipients' missing keys, see [`Composer::lookup_key`].
+    #[cfg(feature = "gpgme")]
+    key_lookups: std::collections::HashMap<
+        crate::jobs::JobId,
+        (String, JoinHandle<Result<Vec<melib::gpgme::Key>>>),
+    >,
+    /// When to remind the user if there's no reply, see `remind-if-no-reply`.
+    follow_up: Option<UnixTimestamp>,
     dirty: bool,
     has_changes: bool,
     initialized: bool,
@@ -107,10 +118,11 @@ enum ViewMode {
     },
     Edit,
     Embed,
-    SelectRecipients(UIDialog<Address>),
+    SelectRecipients(UIDialog<ReplyMode>),
     #[cfg(feature = "gpgme")]
     SelectEncryptKey(bool, gpg::KeySelection),
-    Send(UIConfirmationDialog),
+    /// The confirmation dialog, and the failed pre-send checks still to confirm after it.
+    Send(UIConfirmationDialog, Vec<String>),
     WaitingForSendResult(UIDialog<char>, JoinHandle<Result<()>>),
 }
 
@@ -161,10 +173,14 @@ impl Composer {
             mode: ViewMode::Edit,
             #[cfg(feature = "gpgme")]
             gpg_state: gpg::GpgComposeState::new(),
+            #[cfg(feature = "gpgme")]
+            key_lookups: Default::default(),
+            follow_up: None,
             dirty: true,
             has_changes: false,
             embed_area: ((0, 0), (0, 0)),
             embed: None,
+            embed_scrollback: None,
             initialized: false,
             id: ComponentId::new_v4(),
         }
@@ -215,7 +231,7 @@ impl Composer {
         coordinates: (AccountHash, MailboxHash, EnvelopeHash),
         reply_body: String,
         context: &mut Context,
-        reply_to_all: bool,
+        mode: ReplyMode,
     ) -> Self {
         let mut ret = Composer::with_account(coordinates.0, context);
         let account = &context.accounts[&coordinates.0];
@@ -249,70 +265,16 @@ impl Composer {
         ret.draft
             .set_header("In-Reply-To", envelope.message_id_display().into());
 
-        // "Mail-Followup-To/(To+Cc+(Mail-Reply-To/Reply-To/From)) for follow-up,
-        // Mail-Reply-To/Reply-To/From for reply-to-author."
-        // source: https://cr.yp.to/proto/replyto.html
-        if reply_to_all {
-            let mut to = IndexSet::new();
-
-            if let Some(actions) = list_management::ListActions::detect(&envelope) {
-                if let Some(post) = actions.post {
-                    if let list_management::ListAction::Email(list_post_addr) = post[0] {
-                        if let Ok(list_address) =
-                            melib::email::parser::generic::mailto(list_post_addr)
-                                .map(|(_, m)| m.address)
-                        {
-                            to.insert(list_address);
-                        }
-                    }
-                }
-            }
-            if let Some(reply_to) = envelope
-                .other_headers()
-                .get("Mail-Followup-To")
-                .and_then(|v| v.as_str().try_into().ok())
-            {
-                to.insert(reply_to);
-            } else {
-                if let Some(reply_to) = envelope
-                    .other_headers()
-                    .get("Reply-To")
-                    .and_then(|v| v.as_str().try_into().ok())
-                {
-                    to.insert(reply_to);
-                } else {
-                    to.extend(envelope.from().iter().cloned());
-                }
-            }
-            to.extend(envelope.to().iter().cloned());
-            if let Some(ours) = TryInto::<Address>::try_into(
-                crate::components::mail::get_display_name(context, coordinates.0).as_str(),
-            )
-            .ok()
-            {
-                to.remove(&ours);
-            }
-            ret.draft.set_header("To", {
-                let mut ret: String =
-                    to.into_iter()
-                        .fold(String::new(), |mut s: String, n: Address| {
-                            s.extend(n.to_string().chars());
-                            s.push_str(", ");
-                            s
-                        });
-                ret.pop();
-                ret.pop();
-                ret
-            });
-            ret.draft.set_header("Cc", envelope.field_cc_to_string());
-        } else {
-            if let Some(reply_to) = envelope.other_headers().get("Mail-Reply-To") {
-                ret.draft.set_header("To", reply_to.to_string());
-            } else if let Some(reply_to) = envelope.other_headers().get("Reply-To") {
-                ret.draft.set_header("To", reply_to.to_string());
-            } else {
-                ret.draft.set_header("To", envelope.field_from_to_string());
-            }
+        let ours = our_addresses(context, coordinates.0);
+        let (to, cc) = reply_recipients(&envelope, mode, &ours);
+        ret.draft.set_header("To", to);
+        ret.draft.set_header("Cc", cc);
+        let identity = reply_identity(
+            &envelope,
+            account_settings!(context[coordinates.0].composing.identities),
+        );
+        if let Some(identity) = identity {
+            ret.draft.set_header("From", identity.from_header());
         }
         ret.draft.body = {
             let mut ret = attribution_string(
@@ -336,6 +298,10 @@ impl Composer {
                 ret.push_str(l);
                 ret.push('\n');
             }
+            if let Some(signature) = identity.and_then(|i| i.signature.as_ref()) {
+                ret.push_str("\n-- \n");
+                ret.push_str(signature);
+            }
             ret
         };
 
@@ -344,50 +310,53 @@ impl Composer {
         ret
     }
 
+    /// Reply according to `composing.reply_mode`, asking for the recipients if it is `ask` and
+    /// the envelope is from a mailing list or has other recipients.
     pub fn reply_to_select(
         coordinates: (AccountHash, MailboxHash, EnvelopeHash),
         reply_body: String,
         context: &mut Context,
     ) -> Self {
-        let mut ret = Composer::reply_to(coordinates, reply_body, context, false);
-        let account = &context.accounts[&coordinates.0];
-        let parent_message = account.collection.get_env(coordinates.2);
-        /* If message is from a mailing list and we detect a List-Post header, ask user if they
-         * want to reply to the mailing list or the submitter of the message */
-        if let Some(actions) = list_management::ListActions::detect(&parent_message) {
-            if let Some(post) = actions.post {
-                if let list_management::ListAction::Email(list_post_addr) = post[0] {
-                    if let Ok(list_address) = melib::email::parser::generic::mailto(list_post_addr)
-                        .map(|(_, m)| m.address)
-                    {
-                        let list_address_string = list_address.to_string();
-                        ret.mode = ViewMode::SelectRecipients(UIDialog::new(
-                            "select recipients",
-                            vec![
-                                (
-                                    parent_message.from()[0].clone(),
-                                    parent_message.field_from_to_string(),
-                                ),
-                                (list_address, list_address_string),
-                            ],
-                            false,
-                            Some(Box::new(move |id: ComponentId, results: &[Address]| {
-                                Some(UIEvent::FinishedUIDialog(
-                                    id,
-                                    Box::new(
-                                        results
-                                            .iter()
-                                            .map(|a| a.to_string())
-                                            .collect::<Vec<String>>()
-                                            .join(", "),
-                                    ),
-                                ))
-                            })),
-                            context,
-                        ));
-                    }
-                }
-            }
+        let mode = *mailbox_settings!(context[coordinates.0][&coordinates.1].composing.reply_mode);
+        if mode != ReplyMode::Ask {
+            return Composer::reply_to(coordinates, reply_body, context, mode);
+        }
+        let mut ret = Composer::reply_to(coordinates, reply_body, context, ReplyMode::Author);
+        let ours = our_addresses(context, coordinates.0);
+        let envelope = context.accounts[&coordinates.0]
+            .collection
+            .get_env(coordinates.2);
+        let (author, _) = reply_recipients(&envelope, ReplyMode::Author, &ours);
+        let mut choices = vec![];
+        if let Some(list_address) = list_post_address(&envelope) {
+            choices.push((ReplyMode::List, format!("list: {}", list_address)));
+        }
+        let (all_to, all_cc) = reply_recipients(&envelope, ReplyMode::All, &ours);
+        if all_to != author || !all_cc.is_empty() {
+            choices.push((
+                ReplyMode::All,
+                if all_cc.is_empty() {
+                    format!("all: {}", all_to)
+                } else {
+                    format!("all: {}, {}", all_to, all_cc)
+                },
+            ));
+        }
+        drop(envelope);
+        if !choices.is_empty() {
+            choices.insert(0, (ReplyMode::Author, format!("author: {}", author)));
+            ret.mode = ViewMode::SelectRecipients(UIDialog::new(
+                "reply to",
+                choices,
+                true,
+                Some(Box::new(move |id: ComponentId, results: &[ReplyMode]| {
+                    Some(UIEvent::FinishedUIDialog(
+                        id,
+                        Box::new(results.get(0).cloned()),
+                    ))
+                })),
+                context,
+            ));
         }
         ret
     }
@@ -397,7 +366,7 @@ impl Composer {
         reply_body: String,
         context: &mut Context,
     ) -> Self {
-        Composer::reply_to(coordinates, reply_body, context, false)
+        Composer::reply_to(coordinates, reply_body, context, ReplyMode::Author)
     }
 
     pub fn reply_to_all(
@@ -405,7 +374,21 @@ impl Composer {
         reply_body: String,
         context: &mut Context,
     ) -> Self {
-        Composer::reply_to(coordinates, reply_body, context, true)
+        Composer::reply_to(coordinates, reply_body, context, ReplyMode::All)
+    }
+
+    /// Reply only to the `List-Post` address of the envelope's mailing list, or to all if it has
+    /// none.
+    pub fn reply_to_list(
+        coordinates: (AccountHash, MailboxHash, EnvelopeHash),
+        reply_body: String,
+        context: &mut Context,
+    ) -> Self {
+        Composer::reply_to(coordinates, reply_body, context, ReplyMode::List)
+    }
+
+    pub fn draft(&self) -> &Draft {
+        &self.draft
     }
 
     pub fn set_draft(&mut self, draft: Draft) {
@@ -423,6 +406,90 @@ impl Composer {
         }
     }
 
+    /// Decide whether to encrypt according to `pgp.opportunistic_encryption` and the encryption
+    /// preferences of the draft's recipients, unless the user toggled encryption themselves.
+    /// Keys are only looked up again when the recipients change.
+    #[cfg(feature = "gpgme")]
+    fn update_encryption_policy(&mut self, context: &mut Context) {
+        let account = &context.accounts[&self.account_hash];
+        let recipients = draft_recipients(&self.draft)
+            .into_iter()
+            .map(|a| {
+                let email = a.get_email();
+                gpg::PolicyRecipient {
+                    preference: account.encrypt_preference(&email),
+                    key_choice: account.encrypt_key_choice(&email).map(str::to_string),
+                    email,
+                }
+            })
+            .collect::<Vec<_>>();
+        if recipients == self.gpg_state.policy_recipients {
+            return;
+        }
+        let policy = gpg::EncryptionPolicy::new(
+            &recipients,
+            *account_settings!(context[self.account_hash].pgp.opportunistic_encryption),
+        );
+        if !policy.warnings.is_empty() {
+            context
+                .replies
+                .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                    "Not encrypting: {}.",
+                    policy.warnings.join(", ")
+                ))));
+        }
+        if self.gpg_state.encrypt_mail.is_unset() || self.gpg_state.encrypt_mail.is_internal() {
+            self.gpg_state.encrypt_mail = ToggleFlag::InternalVal(policy.encrypt);
+            self.gpg_state.encrypt_keys = policy.keys.clone();
+        }
+        if account_settings!(context[self.account_hash].pgp.allow_remote_lookup).is_true() {
+            for email in &policy.missing {
+                if !self.gpg_state.looked_up.insert(email.clone()) {
+                    continue;
+                }
+                if let Err(err) = self.lookup_key(email, context) {
+                    context.replies.push_back(UIEvent::Notification(
+                        Some(format!("Could not look up the key of {}", email)),
+                        err.to_string(),
+                        Some(NotificationType::Error(err.kind)),
+                    ));
+                }
+            }
+        }
+        if self.mode.is_edit() && self.gpg_state.encrypt_mail.is_internal() {
+            if let Some((email, keys)) = policy.choices.first() {
+                self.gpg_state.choosing_key_for = Some(email.clone());
+                self.mode = ViewMode::SelectEncryptKey(
+                    true,
+                    gpg::recipient_key_selection(email, keys.clone(), context),
+                );
+            }
+        }
+        self.gpg_state.policy_recipients = recipients;
+        self.gpg_state.policy = policy;
+    }
+
+    /// Look `email`'s key up in a background job with `pgp.remote_lookup_mechanisms`, eg. with a
+    /// Web Key Directory; gpg imports the keys it finds into the keyring.
+    #[cfg(feature = "gpgme")]
+    fn lookup_key(&mut self, email: &str, context: &mut Context) -> Result<()> {
+        use melib::gpgme::{Context as GpgmeContext, GpgmeFlag};
+        let mut ctx = GpgmeContext::new()?;
+        ctx.set_flag(GpgmeFlag::OfflineMode, false)?;
+        ctx.set_auto_key_locate(*account_settings!(
+            context[self.account_hash].pgp.remote_lookup_mechanisms
+        ))?;
+        ctx.set_locate_keys(true)?;
+        let job = ctx.keylist(false, Some(email.to_string()))?;
+        let handle = context.job_executor.spawn_specialized(job);
+        context
+            .replies
+            .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(handle.job_id)));
+        self.key_lookups
+            .insert(handle.job_id, (email.to_string(), handle));
+        Ok(())
+    }
+
     fn update_form(&mut self) {
         let old_cursor = self.form.cursor();
         self.form = FormWidget::new(("Save".into(), true));
@@ -437,10 +504,35 @@ impl Composer {
                     headers[k].to_string().into(),
                     Box::new(move |c, term| {
                         let book: &AddressBook = &c.accounts[&account_hash].address_book;
-                        let results: Vec<String> = book.search(term);
-                        results
+                        let mut results: Vec<String> = book.search(term);
+                        /* A contact group expands to the addresses of its members. */
+                        let groups = book
+                            .groups()
                             .into_iter()
-                            .map(|r| AutoCompleteEntry::from(r))
+                            .filter(|g| !term.is_empty() && g.contains(term))
+                            .map(|g| AutoCompleteEntry {
+                                entry: book.group_members(g).join(", "),
+                                description: format!("group {}", g),
+                            })
+                            .collect::<Vec<AutoCompleteEntry>>();
+                        for collected in c.accounts[&account_hash].collected_addresses.search(term)
+                        {
+                            let r = collected.to_address_string();
+                            if !results.contains(&r) {
+                                results.push(r);
+                            }
+                        }
+                        if let Some(ref ldap) = c.accounts[&account_hash].ldap {
+                            for card in ldap.search(term) {
+                                let r = format!("{} <{}>", card.name(), card.email());
+                                if !results.contains(&r) {
+                                    results.push(r);
+                                }
+                            }
+                        }
+                        groups
+                            .into_iter()
+                            .chain(results.into_iter().map(AutoCompleteEntry::from))
                             .collect::<Vec<AutoCompleteEntry>>()
                     }),
                 ));
@@ -452,7 +544,7 @@ impl Composer {
                         let results: Vec<(String, String)> = c
                             .accounts
                             .values()
-                            .map(|acc| {
+                            .flat_map(|acc| {
                                 let addr = if let Some(display_name) =
                                     acc.settings.account.display_name()
                                 {
@@ -464,22 +556,34 @@ impl Composer {
                                 } else {
                                     acc.settings.account.identity().to_string()
                                 };
-                                let desc =
-                                    match account_settings!(c[acc.hash()].composing.send_mail) {
-                                        crate::conf::composing::SendMail::ShellCommand(ref cmd) => {
-                                            let mut cmd = cmd.as_str();
-                                            cmd.truncate_at_boundary(10);
-                                            format!("{} [exec: {}]", acc.name(), cmd)
-                                        }
-                                        #[cfg(feature = "smtp")]
-                                        crate::conf::composing::SendMail::Smtp(ref inner) => {
-                                            let mut hostname = inner.hostname.as_str();
-                                            hostname.truncate_at_boundary(10);
-                                            format!("{} [smtp: {}]", acc.name(), hostname)
-                                        }
-                                    };
+                                let send_mail =
+                                    account_settings!(c[acc.hash()].composing.send_mail);
+                                let desc = |send_mail: &SendMail| match send_mail {
+                                    crate::conf::composing::SendMail::ShellCommand(ref cmd) => {
+                                        let mut cmd = cmd.as_str();
+                                        cmd.truncate_at_boundary(10);
+                                        format!("{} [exec: {}]", acc.name(), cmd)
+                                    }
+                                    #[cfg(feature = "smtp")]
+                                    crate::conf::composing::SendMail::Smtp(ref inner) => {
+                                        let mut hostname = inner.hostname.as_str();
+                                        hostname.truncate_at_boundary(10);
+                                        format!("{} [smtp: {}]", acc.name(), hostname)
+                                    }
+                                };
 
-                                (addr, desc)
+                                std::iter::once((addr, desc(send_mail)))
+                                    .chain(
+                                        account_settings!(c[acc.hash()].composing.identities)
+                                            .iter()
+                                            .map(|i| {
+                                                (
+                                                    i.from_header(),
+                                                    desc(i.send_mail.as_ref().unwrap_or(send_mail)),
+                                                )
+                                            }),
+                                    )
+                                    .collect::<Vec<_>>()
                             })
                             .collect::<Vec<_>>();
                         results
@@ -497,6 +601,7 @@ impl Composer {
     fn draw_attachments(&self, grid: &mut CellBuffer, area: Area, context: &Context) {
         let attachments_no = self.draft.attachments().len();
         let theme_default = crate::conf::value(context, "theme_default");
+        let highlighted_attr = crate::conf::value(context, "mail.compose.highlighted");
         clear_area(grid, area, theme_default);
         #[cfg(feature = "gpgme")]
         if self.gpg_state.sign_mail.is_true() {
@@ -519,7 +624,7 @@ impl Composer {
                 grid,
                 theme_default.fg,
                 if self.cursor == Cursor::Sign {
-                    Color::Byte(237)
+                    highlighted_attr.bg
                 } else {
                     theme_default.bg
                 },
@@ -533,7 +638,7 @@ impl Composer {
                 grid,
                 theme_default.fg,
                 if self.cursor == Cursor::Sign {
-                    Color::Byte(237)
+                    highlighted_attr.bg
                 } else {
                     theme_default.bg
                 },
@@ -558,7 +663,7 @@ impl Composer {
                     if self.gpg_state.encrypt_keys.is_empty() {
                         "☐ no keys to encrypt with!"
                     } else {
-                        "☑ encrypt with "
+                        "☑ 🔒 encrypt with "
                     },
                     if self.gpg_state.encrypt_keys.is_empty() {
                         ""
@@ -569,7 +674,7 @@ impl Composer {
                 grid,
                 theme_default.fg,
                 if self.cursor == Cursor::Encrypt {
-                    Color::Byte(237)
+                    highlighted_attr.bg
                 } else {
                     theme_default.bg
                 },
@@ -578,12 +683,17 @@ impl Composer {
                 None,
             );
         } else {
+            let warnings = if self.gpg_state.policy.warnings.is_empty() {
+                String::new()
+            } else {
+                format!(" ({})", self.gpg_state.policy.warnings.join(", "))
+            };
             write_string_to_grid(
-                "☐ don't encrypt",
+                &format!("☐ 🔓 don't encrypt{}", warnings),
                 grid,
                 theme_default.fg,
                 if self.cursor == Cursor::Encrypt {
-                    Color::Byte(237)
+                    highlighted_attr.bg
                 } else {
                     theme_default.bg
                 },
@@ -598,7 +708,7 @@ impl Composer {
                 grid,
                 theme_default.fg,
                 if self.cursor == Cursor::Attachments {
-                    Color::Byte(237)
+                    highlighted_attr.bg
                 } else {
                     theme_default.bg
                 },
@@ -612,7 +722,7 @@ impl Composer {
                 grid,
                 theme_default.fg,
                 if self.cursor == Cursor::Attachments {
-                    Color::Byte(237)
+                    highlighted_attr.bg
                 } else {
                     theme_default.bg
                 },
@@ -684,8 +794,15 @@ impl Component for Composer {
             self.update_form();
             self.initialized = true;
         }
+        /* Recipients are only final once the user leaves the headers. */
+        #[cfg(feature = "gpgme")]
+        if self.mode.is_edit() && self.cursor != Cursor::Headers {
+            self.update_draft();
+            self.update_encryption_policy(context);
+        }
         let header_height = self.form.len();
         let theme_default = crate::conf::value(context, "theme_default");
+        let heading_attr = crate::conf::value(context, "mail.compose.heading");
 
         let mid = if width > 80 {
             let width = width - 80;
@@ -742,9 +859,9 @@ impl Component for Composer {
                 "COMPOSING MESSAGE"
             },
             grid,
-            Color::Byte(189),
-            Color::Byte(167),
-            Attr::DEFAULT,
+            heading_attr.fg,
+            heading_attr.bg,
+            heading_attr.attrs,
             (
                 pos_dec(upper_left!(header_area), (0, 1)),
                 bottom_right!(header_area),
@@ -758,8 +875,8 @@ impl Component for Composer {
                 set_x(pos_dec(upper_left!(header_area), (0, 1)), x),
                 set_y(bottom_right!(header_area), y),
             ),
-            Color::Byte(189),
-            Color::Byte(167),
+            heading_attr.fg,
+            heading_attr.bg,
         );
         clear_area(
             grid,
@@ -789,12 +906,38 @@ impl Component for Composer {
                 EmbedStatus::Running(_, _) => {
                     let mut guard = embed_pty.lock().unwrap();
                     clear_area(grid, embed_area, theme_default);
-                    copy_area(
-                        grid,
-                        &guard.grid,
-                        embed_area,
-                        ((0, 0), pos_dec(guard.terminal_size, (1, 1))),
-                    );
+                    if let Some(offset) = self.embed_scrollback {
+                        guard.draw_scrollback(grid, embed_area, offset);
+                        let indicator = format!(
+                            "[scrollback {}/{}]",
+                            std::cmp::min(offset, guard.scrollback.len()),
+                            guard.scrollback.len()
+                        );
+                        let highlighted = crate::conf::value(context, "mail.compose.highlighted");
+                        write_string_to_grid(
+                            &indicator,
+                            grid,
+                            highlighted.fg,
+                            highlighted.bg,
+                            highlighted.attrs,
+                            (
+                                (
+                                    get_x(bottom_right!(embed_area))
+                                        .saturating_sub(indicator.len()),
+                                    get_y(upper_left!(embed_area)),
+                                ),
+                                bottom_right!(embed_area),
+                            ),
+                            None,
+                        );
+                    } else {
+                        copy_area(
+                            grid,
+                            &guard.grid,
+                            embed_area,
+                            ((0, 0), pos_dec(guard.terminal_size, (1, 1))),
+                        );
+                    }
                     guard.set_terminal_size((width!(embed_area), height!(embed_area)));
                     context.dirty_areas.push_back(area);
                     self.dirty = false;
@@ -808,7 +951,12 @@ impl Component for Composer {
                         embed_area,
                         ((0, 0), pos_dec(guard.terminal_size, (1, 1))),
                     );
-                    change_colors(grid, embed_area, Color::Byte(8), theme_default.bg);
+                    change_colors(
+                        grid,
+                        embed_area,
+                        crate::conf::value(context, "mail.compose.embed_stopped").fg,
+                        theme_default.bg,
+                    );
                     const STOPPED_MESSAGE: &str = "process has stopped, press 'e' to re-activate";
                     let inner_area = create_box(
                         grid,
@@ -876,7 +1024,7 @@ impl Component for Composer {
                         ),
                     ),
                     theme_default.fg,
-                    Color::Byte(237),
+                    crate::conf::value(context, "mail.compose.highlighted").bg,
                 );
             }
             Cursor::Sign | Cursor::Encrypt | Cursor::Attachments => {}
@@ -902,7 +1050,7 @@ impl Component for Composer {
                     context,
                 );
             }
-            ViewMode::Send(ref mut s) => {
+            ViewMode::Send(ref mut s, _) => {
                 s.draw(grid, area, context);
             }
             #[cfg(feature = "gpgme")]
@@ -940,6 +1088,26 @@ impl Component for Composer {
         if let UIEvent::VisibilityChange(_) = event {
             self.pager.process_event(event, context);
         }
+        #[cfg(feature = "gpgme")]
+        if let UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id)) = event {
+            if let Some((email, mut handle)) = self.key_lookups.remove(job_id) {
+                if let Ok(Some(Ok(keys))) = handle.chan.try_recv() {
+                    if !keys.is_empty() {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(format!(
+                                "Found the public key of {}.",
+                                email
+                            )),
+                        ));
+                        /* Decide again with the imported keys. */
+                        self.gpg_state.policy_recipients.clear();
+                        self.update_encryption_policy(context);
+                        self.set_dirty(true);
+                    }
+                }
+                return true;
+            }
+        }
         let shortcuts = self.get_shortcuts(context);
         match (&mut self.mode, &mut event) {
             (ViewMode::Edit, _) => {
@@ -961,11 +1129,32 @@ impl Component for Composer {
                     return true;
                 }
             }
-            (ViewMode::Send(ref selector), UIEvent::FinishedUIDialog(id, result))
-                if selector.id() == *id =>
-            {
-                if let Some(true) = result.downcast_ref::<bool>() {
+            (
+                ViewMode::Send(ref selector, ref mut warnings),
+                UIEvent::FinishedUIDialog(id, result),
+            ) if selector.id() == *id => {
+                if let (Some(true), Some(warning)) = (result.downcast_ref::<bool>(), warnings.pop())
+                {
+                    let warnings = std::mem::replace(warnings, vec![]);
+                    self.mode = ViewMode::Send(
+                        send_confirmation(&format!("{} Send anyway?", warning), context),
+                        warnings,
+                    );
+                } else if let Some(true) = result.downcast_ref::<bool>() {
                     self.update_draft();
+                    if self.follow_up.is_some() {
+                        self.draft.ensure_message_id();
+                    }
+                    if *account_settings!(context[self.account_hash].composing.collect_addresses) {
+                        let addresses = draft_recipients(&self.draft);
+                        let ignore = account_settings!(
+                            context[self.account_hash]
+                                .composing
+                                .collect_addresses_ignore
+                        )
+                        .clone();
+                        context.accounts[&self.account_hash].collect_addresses(&addresses, &ignore);
+                    }
                     match send_draft_async(
                         #[cfg(feature = "gpgme")]
                         self.gpg_state.clone(),
@@ -1019,7 +1208,9 @@ impl Component for Composer {
                 self.set_dirty(true);
                 return true;
             }
-            (ViewMode::Send(ref dialog), UIEvent::ComponentKill(ref id)) if *id == dialog.id() => {
+            (ViewMode::Send(ref dialog, _), UIEvent::ComponentKill(ref id))
+                if *id == dialog.id() =>
+            {
                 self.mode = ViewMode::Edit;
                 self.set_dirty(true);
             }
@@ -1043,7 +1234,7 @@ impl Component for Composer {
                 self.set_dirty(true);
                 return true;
             }
-            (ViewMode::Send(ref mut selector), _) => {
+            (ViewMode::Send(ref mut selector, _), _) => {
                 if selector.process_event(event, context) {
                     return true;
                 }
@@ -1052,10 +1243,21 @@ impl Component for Composer {
                 ViewMode::SelectRecipients(ref selector),
                 UIEvent::FinishedUIDialog(id, ref mut result),
             ) if selector.id() == *id => {
-                if let Some(to_val) = result.downcast_mut::<String>() {
-                    self.draft
-                        .set_header("To", std::mem::replace(to_val, String::new()));
-                    self.update_form();
+                if let (Some(Some(mode)), Some((_, env_hash))) = (
+                    result.downcast_ref::<Option<ReplyMode>>(),
+                    self.reply_context,
+                ) {
+                    let account = &context.accounts[&self.account_hash];
+                    if account.contains_key(env_hash) {
+                        let (to, cc) = reply_recipients(
+                            &account.collection.get_env(env_hash),
+                            *mode,
+                            &our_addresses(context, self.account_hash),
+                        );
+                        self.draft.set_header("To", to);
+                        self.draft.set_header("Cc", cc);
+                        self.update_form();
+                    }
                 }
                 self.mode = ViewMode::Edit;
                 return true;
@@ -1146,6 +1348,20 @@ impl Component for Composer {
                         self.set_dirty(true);
                     }
                     Ok(None) | Ok(Some(Ok(()))) => {
+                        if let (Some(until), Some(message_id)) =
+                            (self.follow_up, self.draft.headers().get("Message-ID"))
+                        {
+                            let follow_up = crate::follow_up::FollowUp::new(
+                                message_id,
+                                self.draft
+                                    .headers()
+                                    .get("Subject")
+                                    .cloned()
+                                    .unwrap_or_default(),
+                                until,
+                            );
+                            context.accounts[&self.account_hash].add_follow_up(follow_up);
+                        }
                         context
                             .replies
                             .push_back(UIEvent::Action(Tab(Kill(self.id))));
@@ -1166,6 +1382,12 @@ impl Component for Composer {
                 debug!(&result);
                 if let Some(key) = result.downcast_mut::<Option<melib::gpgme::Key>>() {
                     debug!("got key {:?}", key);
+                    if let (Some(key), Some(email)) =
+                        (key.as_ref(), self.gpg_state.choosing_key_for.as_ref())
+                    {
+                        context.accounts[&self.account_hash]
+                            .set_encrypt_key_choice(email, key.fingerprint().to_string());
+                    }
                     if let Some(key) = key {
                         if *is_encrypt {
                             self.gpg_state.encrypt_keys.clear();
@@ -1177,6 +1399,12 @@ impl Component for Composer {
                     }
                 }
                 self.mode = ViewMode::Edit;
+                /* The policy picks up the chosen key, or leaves the draft unencrypted if the
+                 * selection was cancelled. */
+                if self.gpg_state.choosing_key_for.take().is_some() {
+                    self.gpg_state.encrypt_keys.clear();
+                    self.update_encryption_policy(context);
+                }
                 self.set_dirty(true);
                 return true;
             }
@@ -1279,16 +1507,109 @@ impl Component for Composer {
                     && self.mode.is_edit() =>
             {
                 self.update_draft();
-                self.mode = ViewMode::Send(UIConfirmationDialog::new(
-                    "send mail?",
-                    vec![(true, "yes".to_string()), (false, "no".to_string())],
-                    /* only one choice */
-                    true,
-                    Some(Box::new(move |id: ComponentId, result: bool| {
-                        Some(UIEvent::FinishedUIDialog(id, Box::new(result)))
-                    })),
-                    context,
-                ));
+                #[cfg(feature = "gpgme")]
+                {
+                    self.update_encryption_policy(context);
+                    /* A recipient's key has to be chosen first. */
+                    if !self.mode.is_edit() {
+                        return true;
+                    }
+                }
+                let checks =
+                    account_settings!(context[self.account_hash].composing.pre_send_checks);
+                let mut warnings = pre_send_warnings(&self.draft, checks);
+                #[cfg(feature = "gpgme")]
+                if !self.gpg_state.encrypt_mail.is_true()
+                    && !self.gpg_state.policy.warnings.is_empty()
+                {
+                    warnings.push(format!(
+                        "The message is not encrypted: {}.",
+                        self.gpg_state.policy.warnings.join(", ")
+                    ));
+                }
+                #[cfg(feature = "gpgme")]
+                if checks.unencrypted_with_keys
+                    && !self.gpg_state.encrypt_mail.is_true()
+                    && have_public_keys(&draft_recipients(&self.draft))
+                {
+                    warnings.push(
+                        "The message is not encrypted although all recipients have public keys."
+                            .to_string(),
+                    );
+                }
+                warnings.reverse();
+                let question = match warnings.pop() {
+                    Some(warning) => format!("{} Send anyway?", warning),
+                    None => "send mail?".to_string(),
+                };
+                self.mode = ViewMode::Send(send_confirmation(&question, context), warnings);
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[Self::DESCRIPTION]["cycle_identity"])
+                    && self.mode.is_edit() =>
+            {
+                self.update_draft();
+                let identities = account_settings!(context[self.account_hash].composing.identities);
+                if identities.is_empty() {
+                    return true;
+                }
+                /* `None` is the account's main identity. */
+                let current = draft_identity(context, self.account_hash, &self.draft);
+                let next = match current {
+                    None => Some(&identities[0]),
+                    Some(c) => identities
+                        .iter()
+                        .skip_while(|i| !std::ptr::eq(*i, c))
+                        .nth(1),
+                };
+                if let Some(signature) = current.and_then(|i| i.signature.as_ref()) {
+                    let suffix = format!("\n-- \n{}", signature);
+                    if self.draft.body.ends_with(&suffix) {
+                        let len = self.draft.body.len() - suffix.len();
+                        self.draft.body.truncate(len);
+                    }
+                }
+                self.draft.set_header(
+                    "From",
+                    next.map(|i| i.from_header()).unwrap_or_else(|| {
+                        crate::components::mail::get_display_name(context, self.account_hash)
+                    }),
+                );
+                if let Some(signature) = next.and_then(|i| i.signature.as_ref()) {
+                    self.draft.body.push_str("\n-- \n");
+                    self.draft.body.push_str(signature);
+                }
+                self.pager.update_from_str(self.draft.body(), Some(77));
+                self.update_form();
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::EmbedInput((ref k, _)) if self.embed_scrollback.is_some() => {
+                let scrollback_len = match self.embed {
+                    Some(ref embed) => embed.lock().unwrap().scrollback.len(),
+                    None => 0,
+                };
+                let page = height!(self.embed_area) + 1;
+                let offset = self.embed_scrollback.unwrap();
+                self.embed_scrollback = match k {
+                    Key::Up | Key::Char('k') => Some(std::cmp::min(offset + 1, scrollback_len)),
+                    Key::Down | Key::Char('j') => Some(offset.saturating_sub(1)),
+                    Key::PageUp => Some(std::cmp::min(offset + page, scrollback_len)),
+                    Key::PageDown => Some(offset.saturating_sub(page)),
+                    Key::Char('g') | Key::Home => Some(scrollback_len),
+                    Key::Char('G') | Key::End => Some(0),
+                    Key::Esc | Key::Char('q') => None,
+                    _ => Some(offset),
+                };
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::EmbedInput((ref k, _))
+                if shortcut!(k == shortcuts[Self::DESCRIPTION]["scroll_embed"]) =>
+            {
+                self.embed_scrollback = Some(0);
+                self.set_dirty(true);
                 return true;
             }
             UIEvent::EmbedInput((Key::Ctrl('z'), _)) => {
@@ -1308,7 +1629,16 @@ impl Component for Composer {
                 use std::io::Write;
                 if let Some(ref mut embed) = self.embed {
                     let mut embed_guard = embed.lock().unwrap();
-                    if embed_guard.stdin.write_all(b).is_err() {
+                    let write_result = match k {
+                        Key::Paste(_) if embed_guard.bracketed_paste_mode => embed_guard
+                            .stdin
+                            .write_all(BRACKET_PASTE_START)
+                            .and_then(|()| embed_guard.stdin.write_all(b))
+                            .and_then(|()| embed_guard.stdin.write_all(BRACKET_PASTE_END)),
+                        Key::Focus(_) if !embed_guard.focus_reporting_mode => Ok(()),
+                        _ => embed_guard.stdin.write_all(b),
+                    };
+                    if write_result.is_err() {
                         match embed_guard.is_active() {
                             Ok(WaitStatus::Exited(_, exit_code)) => {
                                 drop(embed_guard);
@@ -1575,6 +1905,7 @@ impl Component for Composer {
                 );
 
                 if *account_settings!(context[self.account_hash].composing.embed) {
+                    self.embed_scrollback = None;
                     self.embed = Some(EmbedStatus::Running(
                         crate::terminal::embed::create_pty(
                             width!(self.embed_area),
@@ -1829,6 +2160,25 @@ impl Component for Composer {
                     );
                     return true;
                 }
+                Action::Compose(ComposeAction::RemindIfNoReply(ref when)) => {
+                    match crate::snooze::parse_until(when, melib::datetime::now()) {
+                        Ok(until) => {
+                            self.follow_up = Some(until);
+                            context.replies.push_back(UIEvent::StatusEvent(
+                                StatusEvent::DisplayMessage(format!(
+                                    "You will be reminded if there's no reply by {}.",
+                                    melib::datetime::timestamp_to_string(until, None, false)
+                                )),
+                            ));
+                        }
+                        Err(err) => {
+                            context.replies.push_back(UIEvent::StatusEvent(
+                                StatusEvent::DisplayMessage(err.to_string()),
+                            ));
+                        }
+                    }
+                    return true;
+                }
                 #[cfg(feature = "gpgme")]
                 Action::Compose(ComposeAction::ToggleSign) => {
                     let is_true = self.gpg_state.sign_mail.is_true();
@@ -1843,6 +2193,26 @@ impl Component for Composer {
                     self.set_dirty(true);
                     return true;
                 }
+                #[cfg(feature = "gpgme")]
+                Action::Compose(ComposeAction::SetEncryptPreference(preference)) => {
+                    self.update_draft();
+                    let recipients = draft_recipients(&self.draft);
+                    if recipients.is_empty() {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage("The draft has no recipients.".to_string()),
+                        ));
+                        return true;
+                    }
+                    let account = &mut context.accounts[&self.account_hash];
+                    for address in &recipients {
+                        account.set_encrypt_preference(&address.get_email(), *preference);
+                    }
+                    /* The preference overrides the user's last toggle. */
+                    self.gpg_state.encrypt_mail = ToggleFlag::Unset;
+                    self.update_encryption_policy(context);
+                    self.set_dirty(true);
+                    return true;
+                }
                 _ => {}
             },
             _ => {}
@@ -1865,7 +2235,7 @@ impl Component for Composer {
             ViewMode::SelectEncryptKey(_, ref widget) => {
                 widget.is_dirty() || self.pager.is_dirty() || self.form.is_dirty()
             }
-            ViewMode::Send(ref widget) => {
+            ViewMode::Send(ref widget, _) => {
                 widget.is_dirty() || self.pager.is_dirty() || self.form.is_dirty()
             }
             ViewMode::WaitingForSendResult(ref widget, _) => {
@@ -1970,6 +2340,122 @@ impl Component for Composer {
     }
 }
 
+/// The addresses of the account's identities, removed from the recipients of replies.
+fn our_addresses(context: &Context, account_hash: AccountHash) -> Vec<Address> {
+    std::iter::once(crate::components::mail::get_display_name(
+        context,
+        account_hash,
+    ))
+    .chain(
+        account_settings!(context[account_hash].composing.identities)
+            .iter()
+            .map(|i| i.address.clone()),
+    )
+    .filter_map(|a| a.as_str().try_into().ok())
+    .collect()
+}
+
+/// The identity `envelope` was delivered to, if any, according to its `Delivered-To`,
+/// `X-Original-To`, `To` and `Cc` headers in that order.
+fn reply_identity<'a>(envelope: &Envelope, identities: &'a [Identity]) -> Option<&'a Identity> {
+    if identities.is_empty() {
+        return None;
+    }
+    let emails: Vec<(String, &Identity)> = identities
+        .iter()
+        .filter_map(|i| {
+            let address: Option<Address> = i.address.as_str().try_into().ok();
+            address.map(|a| (a.get_email(), i))
+        })
+        .collect();
+    ["Delivered-To", "X-Original-To", "To", "Cc"]
+        .iter()
+        .filter_map(|h| envelope.other_headers().get(*h))
+        .filter_map(|v| melib::email::parser::address::rfc2822address_list(v.as_bytes()).ok())
+        .flat_map(|(_, list)| list.into_iter())
+        .find_map(|a| {
+            emails
+                .iter()
+                .find(|(email, _)| email.eq_ignore_ascii_case(&a.get_email()))
+                .map(|(_, i)| *i)
+        })
+}
+
+/// The `To` and `Cc` values of a reply to `envelope` in `mode`, without `ours`.
+///
+/// "Mail-Followup-To/(To+Cc+(Mail-Reply-To/Reply-To/From)) for follow-up,
+/// Mail-Reply-To/Reply-To/From for reply-to-author."
+/// source: https://cr.yp.to/proto/replyto.html
+fn reply_recipients(envelope: &Envelope, mode: ReplyMode, ours: &[Address]) -> (String, String) {
+    let header_addresses = |name: &str| {
+        envelope
+            .other_headers()
+            .get(name)
+            .and_then(|v| melib::email::parser::address::rfc2822address_list(v.as_bytes()).ok())
+            .map(|(_, list)| list.into_vec())
+            .filter(|list| !list.is_empty())
+    };
+    let list_address = list_post_address(envelope);
+    let author = || {
+        header_addresses("Mail-Reply-To")
+            .or_else(|| {
+                /* Lists that set Reply-To to their own address hide the author */
+                header_addresses("Reply-To").filter(|reply_to| match list_address {
+                    Some(ref list_address) => !reply_to.contains(list_address),
+                    None => true,
+                })
+            })
+            .unwrap_or_else(|| envelope.from().to_vec())
+    };
+    let (mut to, mut cc): (IndexSet<Address>, IndexSet<Address>) = match mode {
+        ReplyMode::List if list_address.is_some() => {
+            (list_address.into_iter().collect(), IndexSet::new())
+        }
+        ReplyMode::Ask | ReplyMode::Author => (author().into_iter().collect(), IndexSet::new()),
+        ReplyMode::All | ReplyMode::List => match header_addresses("Mail-Followup-To") {
+            Some(followup) => (followup.into_iter().collect(), IndexSet::new()),
+            None => {
+                let mut to: IndexSet<Address> = author().into_iter().collect();
+                to.extend(list_address);
+                to.extend(envelope.to().iter().cloned());
+                let cc = header_addresses("Cc")
+                    .unwrap_or_default()
+                    .into_iter()
+                    .filter(|a| !to.contains(a))
+                    .collect();
+                (to, cc)
+            }
+        },
+    };
+    for ours in ours {
+        to.remove(ours);
+        cc.remove(ours);
+    }
+    if to.is_empty() {
+        /* Replying to our own message */
+        to.extend(envelope.to().iter().cloned());
+    }
+    let join = |set: IndexSet<Address>| {
+        set.into_iter()
+            .map(|a| a.to_string())
+            .collect::<Vec<String>>()
+            .join(", ")
+    };
+    (join(to), join(cc))
+}
+
+/// The address in the `List-Post` header of `envelope`, if it is an e-mail address.
+fn list_post_address(envelope: &Envelope) -> Option<Address> {
+    let actions = list_management::ListActions::detect(envelope)?;
+    if let list_management::ListAction::Email(list_post_addr) = actions.post?[0] {
+        melib::email::parser::generic::mailto(list_post_addr)
+            .ok()
+            .and_then(|(_, m)| m.address.into_iter().next())
+    } else {
+        None
+    }
+}
+
 pub fn send_draft(
     _sign_mail: ToggleFlag,
     context: &mut Context,
@@ -2068,14 +2554,178 @@ pub fn send_draft(
             draft.attachments.insert(0, body);
         }
     }
+    let send_mail = send_mail_for(context, account_hash, &draft);
+    let fcc = draft_identity(context, account_hash, &draft).and_then(|i| i.fcc.clone());
+    let store_sent_mail = *account_settings!(context[account_hash].composing.store_sent_mail);
     let bytes = draft.finalise().unwrap();
-    let send_mail = account_settings!(context[account_hash].composing.send_mail).clone();
     let ret =
         context.accounts[&account_hash].send(bytes.clone(), send_mail, complete_in_background);
-    save_draft(bytes.as_bytes(), context, mailbox_type, flags, account_hash);
+    if store_sent_mail {
+        save_sent(
+            bytes.as_bytes(),
+            context,
+            fcc.as_deref(),
+            mailbox_type,
+            flags,
+            account_hash,
+        );
+    }
+    ret
+}
+
+fn send_confirmation(question: &str, context: &Context) -> UIConfirmationDialog {
+    UIConfirmationDialog::new(
+        question,
+        vec![(true, "yes".to_string()), (false, "no".to_string())],
+        /* only one choice */
+        true,
+        Some(Box::new(move |id: ComponentId, result: bool| {
+            Some(UIEvent::FinishedUIDialog(id, Box::new(result)))
+        })),
+        context,
+    )
+}
+
+/// The addresses in the `To`, `Cc` and `Bcc` headers of `draft`.
+fn draft_recipients(draft: &Draft) -> Vec<Address> {
+    ["To", "Cc", "Bcc"]
+        .iter()
+        .filter_map(|h| draft.headers().get(*h))
+        .filter_map(|v| melib::email::parser::address::rfc2822address_list(v.as_bytes()).ok())
+        .flat_map(|(_, list)| list.into_iter())
+        .collect()
+}
+
+/// The `checks` that `draft` fails, except `unencrypted_with_keys`.
+fn pre_send_warnings(draft: &Draft, checks: &PreSendChecks) -> Vec<String> {
+    let mut ret = vec![];
+    if checks.missing_subject
+        && draft
+            .headers()
+            .get("Subject")
+            .map(|s| s.trim().is_empty())
+            .unwrap_or(true)
+    {
+        ret.push("The subject is empty.".to_string());
+    }
+    if draft.attachments().is_empty() {
+        let body = draft
+            .body()
+            .lines()
+            .filter(|l| !l.starts_with('>'))
+            .collect::<Vec<&str>>()
+            .join("\n")
+            .to_lowercase();
+        if let Some(keyword) = checks
+            .attachment_keywords
+            .iter()
+            .find(|k| body.contains(&k.to_lowercase()))
+        {
+            ret.push(format!(
+                "The message mentions `{}` but has no attachments.",
+                keyword
+            ));
+        }
+    }
+    let recipients = draft_recipients(draft);
+    if !checks.internal_domains.is_empty() {
+        let external = recipients
+            .iter()
+            .map(|a| a.get_email())
+            .filter(|email| {
+                let domain = email.rsplit('@').next().unwrap_or_default();
+                !checks
+                    .internal_domains
+                    .iter()
+                    .any(|d| d.eq_ignore_ascii_case(domain))
+            })
+            .collect::<Vec<String>>();
+        if !external.is_empty() {
+            ret.push(format!("External recipients: {}.", external.join(", ")));
+        }
+    }
+    if let Some(max) = checks.max_recipients {
+        if recipients.len() > max {
+            ret.push(format!("The message has {} recipients.", recipients.len()));
+        }
+    }
     ret
 }
 
+/// Whether there are public keys for all of `recipients`.
+#[cfg(feature = "gpgme")]
+fn have_public_keys(recipients: &[Address]) -> bool {
+    !recipients.is_empty()
+        && recipients.iter().all(|a| {
+            melib::gpgme::Context::new()
+                .and_then(|mut ctx| ctx.keylist(false, Some(a.get_email())))
+                .and_then(futures::executor::block_on)
+                .map(|keys| !keys.is_empty())
+                .unwrap_or(false)
+        })
+}
+
+/// The identity `draft` is sent from according to its `From` header, if it's not the account's
+/// main one.
+fn draft_identity<'a>(
+    context: &'a Context,
+    account_hash: AccountHash,
+    draft: &Draft,
+) -> Option<&'a Identity> {
+    let from: Address = draft
+        .headers()
+        .get("From")
+        .and_then(|f| f.as_str().try_into().ok())?;
+    account_settings!(context[account_hash].composing.identities)
+        .iter()
+        .find(|i| {
+            let address: Option<Address> = i.address.as_str().try_into().ok();
+            address.map(|a| a.get_email().eq_ignore_ascii_case(&from.get_email())) == Some(true)
+        })
+}
+
+/// The `send_mail` setting of the identity `draft` is sent from, or else of the account.
+fn send_mail_for(context: &Context, account_hash: AccountHash, draft: &Draft) -> SendMail {
+    draft_identity(context, account_hash, draft)
+        .and_then(|i| i.send_mail.clone())
+        .unwrap_or_else(|| account_settings!(context[account_hash].composing.send_mail).clone())
+}
+
+/// Save a sent message in the mailbox with path `fcc` if it exists, or else in the account's
+/// `mailbox_type` mailbox.
+fn save_sent(
+    bytes: &[u8],
+    context: &mut Context,
+    fcc: Option<&str>,
+    mailbox_type: SpecialUsageMailbox,
+    flags: Flag,
+    account_hash: AccountHash,
+) {
+    if let Some(path) = fcc {
+        let account = &mut context.accounts[&account_hash];
+        match account
+            .mailbox_by_path(path)
+            .and_then(|mailbox_hash| account.save(bytes, mailbox_hash, Some(flags)))
+        {
+            Ok(()) => {
+                context.replies.push_back(UIEvent::Notification(
+                    Some("Message saved".into()),
+                    format!("Message saved in `{}`", path),
+                    Some(NotificationType::Info),
+                ));
+                return;
+            }
+            Err(err) => {
+                melib::log(
+                    format!("Could not save sent message in `{}`: {}", path, err),
+                    melib::ERROR,
+                );
+            }
+        }
+    }
+    save_draft(bytes, context, mailbox_type, flags, account_hash);
+}
+
 pub fn save_draft(
     bytes: &[u8],
     context: &mut Context,
@@ -2131,9 +2781,14 @@ pub fn send_draft_async(
         >,
     > = vec![];
     #[cfg(feature = "gpgme")]
+    let sign_key = draft_identity(context, account_hash, &draft)
+        .and_then(|i| i.pgp_key.clone())
+        .or_else(|| account_settings!(context[account_hash].pgp.sign_key).clone());
+    #[cfg(feature = "gpgme")]
     if gpg_state.sign_mail.is_true() && !gpg_state.encrypt_mail.is_true() {
         filters_stack.push(Box::new(crate::components::mail::pgp::sign_filter(
             gpg_state.sign_keys.clone(),
+            sign_key,
         )?));
     } else if gpg_state.encrypt_mail.is_true() {
         filters_stack.push(Box::new(crate::components::mail::pgp::encrypt_filter(
@@ -2142,11 +2797,20 @@ pub fn send_draft_async(
             } else {
                 None
             },
+            sign_key,
             gpg_state.encrypt_keys.clone(),
         )?));
     }
-    let send_mail = account_settings!(context[account_hash].composing.send_mail).clone();
+    let send_mail = send_mail_for(context, account_hash, &draft);
+    let fcc = draft_identity(context, account_hash, &draft).and_then(|i| i.fcc.clone());
+    let max_message_size = *account_settings!(context[account_hash].composing.max_message_size);
     let send_cb = context.accounts[&account_hash].send_async(send_mail);
+    let pre_send_hook = context.settings.hooks.pre_send.clone();
+    let post_send_hook = context.settings.hooks.post_send.clone();
+    let hook_env = vec![(
+        "MELI_ACCOUNT",
+        context.accounts[&account_hash].name().to_string(),
+    )];
     let mut content_type = ContentType::default();
     if format_flowed {
         if let ContentType::Text {
@@ -2184,28 +2848,64 @@ pub fn send_draft_async(
         }
 
         draft.attachments.insert(0, body);
-        let message = Arc::new(draft.finalise()?);
-        let ret = send_cb(message.clone()).await;
+        let mut message = draft.finalise()?;
+        let mut ret = Ok(());
+        if let Some(ref hook) = pre_send_hook {
+            match run_hook("pre_send", hook, Some(message.as_bytes()), hook_env.clone()) {
+                Ok(output) if !output.trim().is_empty() => message = output,
+                Ok(_) => {}
+                Err(err) => ret = Err(err),
+            }
+        }
+        let message = Arc::new(message);
+        if let (true, Some(max_message_size)) = (ret.is_ok(), max_message_size) {
+            /* Large messages are sent as message/partial parts. */
+            match melib::email::partial::split(message.as_bytes(), max_message_size) {
+                Ok(parts) => {
+                    for part in parts {
+                        ret = send_cb(Arc::new(String::from_utf8_lossy(&part).into_owned())).await;
+                        if ret.is_err() {
+                            break;
+                        }
+                    }
+                }
+                Err(err) => ret = Err(err),
+            }
+        } else if ret.is_ok() {
+            ret = send_cb(message.clone()).await;
+        }
         let is_ok = ret.is_ok();
+        if let (true, Some(hook)) = (is_ok, post_send_hook) {
+            spawn_hook(
+                "post_send",
+                hook,
+                Some(message.as_bytes().to_vec()),
+                hook_env,
+                event_sender.clone(),
+            );
+        }
         if !is_ok || (store_sent_mail && is_ok) {
             event_sender
                 .send(ThreadEvent::UIEvent(UIEvent::Callback(CallbackFn(
                     Box::new(move |context| {
-                        save_draft(
-                            message.as_bytes(),
-                            context,
-                            if is_ok {
-                                mailbox_type
-                            } else {
-                                SpecialUsageMailbox::Drafts
-                            },
-                            if is_ok {
-                                flags
-                            } else {
-                                Flag::SEEN | Flag::DRAFT
-                            },
-                            account_hash,
-                        );
+                        if is_ok {
+                            save_sent(
+                                message.as_bytes(),
+                                context,
+                                fcc.as_deref(),
+                                mailbox_type,
+                                flags,
+                                account_hash,
+                            );
+                        } else {
+                            save_draft(
+                                message.as_bytes(),
+                                context,
+                                SpecialUsageMailbox::Drafts,
+                                Flag::SEEN | Flag::DRAFT,
+                                account_hash,
+                            );
+                        }
                     }),
                 ))))
                 .unwrap();
@@ -2255,3 +2955,116 @@ fn attribution_string(
     );
     melib::datetime::timestamp_to_string(date, Some(fmt.as_str()), posix)
 }
+
+#[test]
+fn test_reply_identity() {
+    let identity = |address: &str| Identity {
+        address: address.to_string(),
+        display_name: None,
+        signature: None,
+        send_mail: None,
+        pgp_key: None,
+        fcc: None,
+    };
+    let identities = vec![
+        identity("Me <me@example.com>"),
+        identity("Sales <sales@example.com>"),
+    ];
+    let envelope = Envelope::from_bytes(
+        b"From: Alice <alice@example.com>\r\n\
+To: Bob <bob@example.com>\r\n\
+Cc: SALES@example.com\r\n\
+Subject: Hi\r\n\
+\r\n\
+Hello\r\n",
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        reply_identity(&envelope, &identities).map(|i| i.address.as_str()),
+        Some("Sales <sales@example.com>")
+    );
+    let envelope = Envelope::from_bytes(
+        b"Delivered-To: me@example.com\r\n\
+From: Alice <alice@example.com>\r\n\
+To: list@lists.example.com\r\n\
+Cc: sales@example.com\r\n\
+Subject: Hi\r\n\
+\r\n\
+Hello\r\n",
+        None,
+    )
+    .unwrap();
+    assert_eq!(
+        reply_identity(&envelope, &identities).map(|i| i.address.as_str()),
+        Some("Me <me@example.com>")
+    );
+    assert!(reply_identity(&envelope, &[]).is_none());
+}
+
+#[test]
+fn test_reply_recipients() {
+    let envelope = Envelope::from_bytes(
+        b"From: Alice <alice@example.com>\r\n\
+To: list@lists.example.com\r\n\
+Cc: Bob <bob@example.com>, me@example.com\r\n\
+Reply-To: list@lists.example.com\r\n\
+List-Post: <mailto:list@lists.example.com>\r\n\
+Subject: Hi\r\n\
+\r\n\
+Hello\r\n",
+        None,
+    )
+    .unwrap();
+    let ours: Address = "me@example.com".try_into().unwrap();
+    assert_eq!(
+        reply_recipients(&envelope, ReplyMode::Author, &[ours.clone()]),
+        ("Alice <alice@example.com>".to_string(), String::new())
+    );
+    assert_eq!(
+        reply_recipients(&envelope, ReplyMode::List, &[ours.clone()]),
+        ("list@lists.example.com".to_string(), String::new())
+    );
+    assert_eq!(
+        reply_recipients(&envelope, ReplyMode::All, &[ours]),
+        (
+            "Alice <alice@example.com>, list@lists.example.com".to_string(),
+            "Bob <bob@example.com>".to_string()
+        )
+    );
+}
+
+#[test]
+fn test_pre_send_warnings() {
+    let mut checks = PreSendChecks::default();
+    let mut draft = Draft::default();
+    draft.set_header(
+        "To",
+        "Alice <alice@example.com>, bob@other.example.org".to_string(),
+    );
+    draft.set_header("Subject", "Report".to_string());
+    draft.set_body("Hi,\n\n> see the attached file\n".to_string());
+    assert!(pre_send_warnings(&draft, &checks).is_empty());
+
+    draft.set_header("Subject", " ".to_string());
+    draft.set_body("Hi,\n\nI've ATTACHED the report.\n".to_string());
+    assert_eq!(
+        pre_send_warnings(&draft, &checks),
+        vec![
+            "The subject is empty.".to_string(),
+            "The message mentions `attach` but has no attachments.".to_string(),
+        ]
+    );
+
+    checks.missing_subject = false;
+    checks.attachment_keywords.clear();
+    checks.internal_domains = vec!["EXAMPLE.com".to_string()];
+    checks.max_recipients = Some(1);
+    assert_eq!(
+        pre_send_warnings(&draft, &checks),
+        vec![
+            "External recipients: bob@other.example.org.".to_string(),
+            "The message has 2 recipients.".to_string(),
+        ]
+    );
+}
@@ -20,6 +20,7 @@
  */
 
 use super::*;
+use std::collections::HashSet;
 
 #[derive(Debug)]
 pub enum KeySelection {
@@ -65,7 +66,9 @@ impl KeySelection {
         if local {
             ctx.set_auto_key_locate(LocateKey::LOCAL)?;
         } else {
+            ctx.set_flag(GpgmeFlag::OfflineMode, false)?;
             ctx.set_auto_key_locate(LocateKey::WKD | LocateKey::LOCAL)?;
+            ctx.set_locate_keys(true)?;
         }
         let job = ctx.keylist(secret, Some(pattern.clone()))?;
         let handle = context.job_executor.spawn_specialized(job);
@@ -273,6 +276,13 @@ pub struct GpgComposeState {
     pub encrypt_keys: Vec<melib::gpgme::Key>,
     pub encrypt_for_self: bool,
     pub sign_keys: Vec<melib::gpgme::Key>,
+    /// The recipients and their preferences `policy` was decided for.
+    pub policy_recipients: Vec<PolicyRecipient>,
+    pub policy: EncryptionPolicy,
+    /// The recipient whose key is being chosen in `ViewMode::SelectEncryptKey`, if any.
+    pub choosing_key_for: Option<String>,
+    /// Recipients whose missing keys were already looked up remotely.
+    pub looked_up: HashSet<String>,
 }
 
 impl GpgComposeState {
@@ -283,6 +293,206 @@ impl GpgComposeState {
             encrypt_keys: vec![],
             encrypt_for_self: true,
             sign_keys: vec![],
+            policy_recipients: vec![],
+            policy: EncryptionPolicy::default(),
+            choosing_key_for: None,
+            looked_up: HashSet::default(),
         }
     }
 }
+
+/// A recipient of a draft, as seen by [`EncryptionPolicy`].
+#[derive(Debug, Clone, PartialEq)]
+pub struct PolicyRecipient {
+    pub email: String,
+    pub preference: Option<EncryptPreference>,
+    /// Fingerprint of the key the user chose among several keys matching `email`.
+    pub key_choice: Option<String>,
+}
+
+/// What was found in the keyring for a recipient.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum RecipientKey {
+    Valid,
+    /// Only keys that are expired, revoked, disabled or can't encrypt.
+    Expired,
+    Missing,
+}
+
+/// Whether to encrypt a draft and with which keys, according to its recipients' keys and
+/// preferences.
+#[derive(Debug, Clone, Default)]
+pub struct EncryptionPolicy {
+    pub encrypt: bool,
+    pub keys: Vec<melib::gpgme::Key>,
+    /// Recipients keeping the draft from being encrypted, eg. "no public key for x@example.com".
+    pub warnings: Vec<String>,
+    /// Recipients with several usable keys and no key chosen among them yet.
+    pub choices: Vec<(String, Vec<melib::gpgme::Key>)>,
+    /// Recipients without any key in the keyring.
+    pub missing: Vec<String>,
+}
+
+impl EncryptionPolicy {
+    /// Keys are only looked up if `opportunistic` is set or a recipient always wants encrypted
+    /// messages.
+    pub fn new(recipients: &[PolicyRecipient], opportunistic: bool) -> Self {
+        if !wants_encryption(recipients, opportunistic) {
+            return EncryptionPolicy::default();
+        }
+        let mut ret = EncryptionPolicy::default();
+        let mut statuses = Vec::with_capacity(recipients.len());
+        for recipient in recipients {
+            let (status, mut keys) = recipient_keys(&recipient.email);
+            statuses.push((recipient.email.as_str(), status));
+            if status == RecipientKey::Missing {
+                ret.missing.push(recipient.email.clone());
+            }
+            let chosen = recipient.key_choice.as_ref().and_then(|fpr| {
+                keys.iter()
+                    .position(|k| k.fingerprint().eq_ignore_ascii_case(fpr))
+            });
+            match chosen {
+                Some(i) => ret.keys.push(keys.swap_remove(i)),
+                None if keys.len() > 1 => ret.choices.push((recipient.email.clone(), keys)),
+                None => ret.keys.extend(keys),
+            }
+        }
+        ret.warnings = encryption_warnings(&statuses);
+        ret.encrypt = ret.warnings.is_empty() && ret.choices.is_empty();
+        if !ret.encrypt {
+            ret.keys.clear();
+        }
+        ret
+    }
+}
+
+/// No recipient must be marked `never`, and either `opportunistic` is set or one of them is
+/// marked `always`.
+fn wants_encryption(recipients: &[PolicyRecipient], opportunistic: bool) -> bool {
+    !recipients.is_empty()
+        && !recipients
+            .iter()
+            .any(|r| r.preference == Some(EncryptPreference::Never))
+        && (opportunistic
+            || recipients
+                .iter()
+                .any(|r| r.preference == Some(EncryptPreference::Always)))
+}
+
+fn encryption_warnings(recipients: &[(&str, RecipientKey)]) -> Vec<String> {
+    recipients
+        .iter()
+        .filter_map(|(email, status)| match status {
+            RecipientKey::Valid => None,
+            RecipientKey::Expired => Some(format!("the public key of {} has expired", email)),
+            RecipientKey::Missing => Some(format!("no public key for {}", email)),
+        })
+        .collect()
+}
+
+/// The usable public keys of `email` in the keyring.
+fn recipient_keys(email: &str) -> (RecipientKey, Vec<melib::gpgme::Key>) {
+    let keys = match melib::gpgme::Context::new()
+        .and_then(|mut ctx| ctx.keylist(false, Some(email.to_string())))
+        .and_then(futures::executor::block_on)
+    {
+        Ok(keys) => keys,
+        Err(_) => return (RecipientKey::Missing, vec![]),
+    };
+    if keys.is_empty() {
+        return (RecipientKey::Missing, vec![]);
+    }
+    let valid = keys
+        .into_iter()
+        .filter(|k| {
+            !k.expired() && !k.revoked() && !k.disabled() && !k.invalid() && k.can_encrypt()
+        })
+        .collect::<Vec<_>>();
+    if valid.is_empty() {
+        (RecipientKey::Expired, valid)
+    } else {
+        (RecipientKey::Valid, valid)
+    }
+}
+
+/// A selector of the key to encrypt to `email` with among `keys`, showing their IDs, creation
+/// dates and trust.
+pub fn recipient_key_selection(
+    email: &str,
+    keys: Vec<melib::gpgme::Key>,
+    context: &Context,
+) -> KeySelection {
+    let mut widget = UIDialog::new(
+        &format!("select key for {}", email),
+        keys.iter()
+            .map(|k| {
+                let label = format!(
+                    "{} created {} trust: {}",
+                    k.key_id().unwrap_or_else(|| k.fingerprint()),
+                    k.created()
+                        .map(|t| melib::datetime::timestamp_to_string(t, Some("%Y-%m-%d"), false))
+                        .unwrap_or_else(|| "?".to_string()),
+                    k.validity()
+                );
+                match k.primary_uid() {
+                    Some(uid) => (k.clone(), format!("{} {}", label, uid)),
+                    None => (k.clone(), label),
+                }
+            })
+            .collect::<Vec<(melib::gpgme::Key, String)>>(),
+        true,
+        Some(Box::new(
+            move |id: ComponentId, results: &[melib::gpgme::Key]| {
+                Some(UIEvent::FinishedUIDialog(
+                    id,
+                    Box::new(results.get(0).cloned()),
+                ))
+            },
+        )),
+        context,
+    );
+    widget.set_dirty(true);
+    KeySelection::Loaded { widget, keys }
+}
+
+#[test]
+fn test_encryption_policy() {
+    let recipients = |prefs: &[Option<EncryptPreference>]| {
+        prefs
+            .iter()
+            .enumerate()
+            .map(|(i, p)| PolicyRecipient {
+                email: format!("{}@example.com", i),
+                preference: *p,
+                key_choice: None,
+            })
+            .collect::<Vec<_>>()
+    };
+    assert!(!wants_encryption(&[], true));
+    assert!(wants_encryption(&recipients(&[None, None]), true));
+    assert!(!wants_encryption(&recipients(&[None, None]), false));
+    assert!(wants_encryption(
+        &recipients(&[None, Some(EncryptPreference::Always)]),
+        false
+    ));
+    assert!(!wants_encryption(
+        &recipients(&[
+            Some(EncryptPreference::Always),
+            Some(EncryptPreference::Never)
+        ]),
+        true
+    ));
+    assert!(encryption_warnings(&[("a@example.com", RecipientKey::Valid)]).is_empty());
+    assert_eq!(
+        encryption_warnings(&[
+            ("a@example.com", RecipientKey::Valid),
+            ("b@example.com", RecipientKey::Expired),
+            ("c@example.com", RecipientKey::Missing),
+        ]),
+        vec![
+            "the public key of b@example.com has expired".to_string(),
+            "no public key for c@example.com".to_string(),
+        ]
+    );
+}
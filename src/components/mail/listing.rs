@@ -21,8 +21,10 @@
 
 use super::*;
 use crate::conf::accounts::JobRequest;
+use crate::session::{ListingSession, Session};
 use crate::types::segment_tree::SegmentTree;
-use melib::backends::EnvelopeHashBatch;
+use melib::backends::{EnvelopeHashBatch, FlagOp, FlagOps};
+use melib::text_processing::GlobMatch;
 use smallvec::SmallVec;
 use std::collections::{HashMap, HashSet};
 use std::convert::TryFrom;
@@ -43,6 +45,12 @@ pub use self::plain::*;
 mod offline;
 pub use self::offline::*;
 
+mod index_format;
+pub use self::index_format::*;
+
+mod unified;
+pub use self::unified::*;
+
 #[derive(Debug, Copy, PartialEq, Clone)]
 pub enum Modifier {
     SymmetricDifference,
@@ -101,6 +109,85 @@ pub(super) struct EntryStrings {
     pub(super) tags: TagString,
 }
 
+/// The `listing.mailing_list_indicator` of the mailbox at `coordinates` if `envelope` was sent
+/// through a mailing list, otherwise an empty string.
+pub(super) fn mailing_list_indicator<'a>(
+    context: &'a Context,
+    coordinates: (AccountHash, MailboxHash),
+    envelope: &Envelope,
+) -> &'a str {
+    if envelope.list_id().is_none() {
+        return "";
+    }
+    mailbox_settings!(
+        context[coordinates.0][&coordinates.1]
+            .listing
+            .mailing_list_indicator
+    )
+    .as_deref()
+    .unwrap_or("📨")
+}
+
+/// The `listing.duplicate_indicator` of the mailbox at `coordinates` if `envelope` has
+/// duplicates, otherwise an empty string.
+pub(super) fn duplicate_indicator<'a>(
+    context: &'a Context,
+    coordinates: (AccountHash, MailboxHash),
+    envelope: &Envelope,
+) -> &'a str {
+    if context.accounts[&coordinates.0]
+        .collection
+        .envelope_duplicates(envelope)
+        .is_empty()
+    {
+        return "";
+    }
+    mailbox_settings!(
+        context[coordinates.0][&coordinates.1]
+            .listing
+            .duplicate_indicator
+    )
+    .as_deref()
+    .unwrap_or("⧉")
+}
+
+/// Whether `env_hash` is hidden from the listing of the mailbox at `coordinates` because of
+/// `listing.hide_duplicates`: it has a copy in a mailbox that doesn't hide duplicates, or an
+/// older copy in the same mailbox. Copies with the same date are ordered by hash, so that exactly
+/// one of them is shown.
+pub(super) fn is_hidden_duplicate(
+    context: &Context,
+    coordinates: (AccountHash, MailboxHash),
+    env_hash: EnvelopeHash,
+) -> bool {
+    if !*mailbox_settings!(
+        context[coordinates.0][&coordinates.1]
+            .listing
+            .hide_duplicates
+    ) {
+        return false;
+    }
+    let account = &context.accounts[&coordinates.0];
+    let duplicates = account.duplicates(env_hash);
+    if duplicates.is_empty() {
+        return false;
+    }
+    let envelopes = account.collection.envelopes.read().unwrap();
+    let sort_key = |h: EnvelopeHash| (envelopes.get(&h).map(|e| e.date()).unwrap_or(0), h);
+    duplicates.into_iter().any(|(h, mailbox_hash)| {
+        if mailbox_hash == coordinates.1 {
+            sort_key(h) < sort_key(env_hash)
+        } else {
+            account.mailbox_entries.contains_key(&mailbox_hash)
+                && !*mailbox_settings!(
+                    context[coordinates.0][&mailbox_hash]
+                        .listing
+                        .hide_duplicates
+                )
+        }
+    })
+}
+
 #[macro_export]
 /// Creates a comma separated list `String` out of an `Address` iterable.
 macro_rules! address_list {
@@ -151,6 +238,68 @@ struct AccountMenuEntry {
     hash: AccountHash,
     index: usize,
     entries: SmallVec<[(usize, u32, bool, MailboxHash); 16]>,
+    /// If the account's mailboxes are folded away in the sidebar.
+    collapsed: bool,
+}
+
+impl AccountMenuEntry {
+    /// Number of mailbox entries shown in the sidebar.
+    fn visible_entries(&self) -> usize {
+        if self.collapsed {
+            0
+        } else {
+            self.entries.len()
+        }
+    }
+}
+
+/// The subscribed mailboxes of an account shown in the sidebar, except those matching
+/// `listing.sidebar_hide_mailboxes`.
+fn sidebar_entries(
+    account_hash: AccountHash,
+    context: &Context,
+) -> SmallVec<[(usize, u32, bool, MailboxHash); 16]> {
+    let account = &context.accounts[&account_hash];
+    let hidden = account_settings!(context[account_hash].listing.sidebar_hide_mailboxes);
+    account
+        .list_mailboxes()
+        .into_iter()
+        .filter(|mailbox_node| {
+            let mailbox = &account[&mailbox_node.hash].ref_mailbox;
+            mailbox.is_subscribed()
+                && !hidden
+                    .iter()
+                    .any(|pattern| mailbox.path().matches_glob(pattern))
+        })
+        .map(|f| (f.depth, f.indentation, f.has_sibling, f.hash))
+        .collect::<_>()
+}
+
+/// Format the message counts of a sidebar entry with `listing.sidebar_count_format`.
+fn format_sidebar_count(format: Option<&str>, unread: usize, total: usize) -> String {
+    match format {
+        _ if unread == 0 && total == 0 => String::new(),
+        None if unread == 0 => String::new(),
+        None => format!(" {}", unread),
+        Some(format) => format!(
+            " {}",
+            format
+                .replace("%u", &unread.to_string())
+                .replace("%t", &total.to_string())
+        ),
+    }
+}
+
+const COLLAPSED_ACCOUNTS_FILE: &str = "sidebar_collapsed";
+
+/// Names of the accounts collapsed in the sidebar when meli last exited.
+fn load_collapsed_accounts() -> HashSet<String> {
+    xdg::BaseDirectories::with_prefix("meli")
+        .ok()
+        .and_then(|data_dir| data_dir.find_data_file(COLLAPSED_ACCOUNTS_FILE))
+        .and_then(|path| std::fs::read_to_string(path).ok())
+        .map(|s| s.lines().map(|l| l.to_string()).collect())
+        .unwrap_or_default()
 }
 
 pub trait MailListingTrait: ListingTrait {
@@ -176,13 +325,89 @@ pub trait MailListingTrait: ListingTrait {
         if envs_to_set.is_empty() {
             return;
         }
+        perform_action_on_messages(context, account_hash, mailbox_hash, envs_to_set, a);
+        self.set_dirty(true);
+    }
+
+    fn row_updates(&mut self) -> &mut SmallVec<[ThreadHash; 8]>;
+    fn selection(&mut self) -> &mut HashMap<ThreadHash, bool>;
+    fn get_focused_items(&self, _context: &Context) -> SmallVec<[ThreadHash; 8]>;
+    fn redraw_threads_list(
+        &mut self,
+        context: &Context,
+        items: Box<dyn Iterator<Item = ThreadHash>>,
+    );
+
+    fn redraw_envelope_list(
+        &mut self,
+        _context: &Context,
+        _items: Box<dyn Iterator<Item = EnvelopeHash>>,
+    ) {
+    }
+
+    /// Use `force` when there have been changes in the mailbox or account lists in `context`
+    fn refresh_mailbox(&mut self, context: &mut Context, force: bool);
+}
+
+/// Apply `a` to the messages `envs_to_set` of `mailbox_hash` in account `account_hash`. Shared by
+/// [`MailListingTrait::perform_action`] and listings that aren't tied to a single mailbox.
+pub fn perform_action_on_messages(
+    context: &mut Context,
+    account_hash: AccountHash,
+    mailbox_hash: MailboxHash,
+    envs_to_set: SmallVec<[EnvelopeHash; 8]>,
+    a: &ListingAction,
+) {
+    /* Messages of a saved search are acted upon in the mailboxes they are stored in. */
+    let mut groups: SmallVec<[(MailboxHash, SmallVec<[EnvelopeHash; 8]>); 1]> = SmallVec::new();
+    match a {
+        ListingAction::Export(_, _, _) | ListingAction::SaveThreadAttachments(_) => {
+            groups.push((mailbox_hash, envs_to_set));
+        }
+        _ => {
+            let act_on_duplicates = matches!(
+                a,
+                ListingAction::SetSeen
+                    | ListingAction::SetUnseen
+                    | ListingAction::Tag(_)
+                    | ListingAction::Delete
+            ) && *mailbox_settings!(
+                context[account_hash][&mailbox_hash]
+                    .listing
+                    .act_on_duplicates
+            );
+            let account = &context.accounts[&account_hash];
+            let mut targets: SmallVec<[(EnvelopeHash, MailboxHash); 8]> = envs_to_set
+                .iter()
+                .map(|h| (*h, account.envelope_source_mailbox(*h, mailbox_hash)))
+                .collect();
+            if act_on_duplicates {
+                for env_hash in envs_to_set {
+                    for duplicate in account.duplicates(env_hash) {
+                        if !targets.contains(&duplicate) {
+                            targets.push(duplicate);
+                        }
+                    }
+                }
+            }
+            for (env_hash, source) in targets {
+                if let Some((_, envs)) = groups.iter_mut().find(|(h, _)| *h == source) {
+                    envs.push(env_hash);
+                } else {
+                    groups.push((source, smallvec::smallvec![env_hash]));
+                }
+            }
+        }
+    }
+    for (mailbox_hash, envs_to_set) in groups {
+        let account = &mut context.accounts[&account_hash];
         let env_hashes = EnvelopeHashBatch::try_from(envs_to_set.as_slice()).unwrap();
         match a {
             ListingAction::SetSeen => {
                 let job = account.backend.write().unwrap().set_flags(
                     env_hashes.clone(),
                     mailbox_hash,
-                    smallvec::smallvec![(Ok(Flag::SEEN), true)],
+                    smallvec::smallvec![FlagOp::Flag(Flag::SEEN, true)],
                 );
                 match job {
                     Err(err) => {
@@ -201,7 +426,7 @@ pub trait MailListingTrait: ListingTrait {
                 let job = account.backend.write().unwrap().set_flags(
                     env_hashes.clone(),
                     mailbox_hash,
-                    smallvec::smallvec![(Ok(Flag::SEEN), false)],
+                    smallvec::smallvec![FlagOp::Flag(Flag::SEEN, false)],
                 );
                 match job {
                     Err(err) => {
@@ -217,11 +442,14 @@ pub trait MailListingTrait: ListingTrait {
                 }
             }
             ListingAction::Tag(Remove(ref tag_str)) => {
-                let job = account.backend.write().unwrap().set_flags(
-                    env_hashes.clone(),
-                    mailbox_hash,
-                    smallvec::smallvec![(Err(tag_str.to_string()), false)],
-                );
+                let flags: FlagOps = smallvec::smallvec![FlagOp::Tag(tag_str.to_string(), false)];
+                let job = account.check_flag_ops(mailbox_hash, &flags).and_then(|()| {
+                    account.backend.write().unwrap().set_flags(
+                        env_hashes.clone(),
+                        mailbox_hash,
+                        flags,
+                    )
+                });
                 match job {
                     Err(err) => {
                         context.replies.push_back(UIEvent::StatusEvent(
@@ -236,11 +464,14 @@ pub trait MailListingTrait: ListingTrait {
                 }
             }
             ListingAction::Tag(Add(ref tag_str)) => {
-                let job = account.backend.write().unwrap().set_flags(
-                    env_hashes.clone(),
-                    mailbox_hash,
-                    smallvec::smallvec![(Err(tag_str.to_string()), true)],
-                );
+                let flags: FlagOps = smallvec::smallvec![FlagOp::Tag(tag_str.to_string(), true)];
+                let job = account.check_flag_ops(mailbox_hash, &flags).and_then(|()| {
+                    account.backend.write().unwrap().set_flags(
+                        env_hashes.clone(),
+                        mailbox_hash,
+                        flags,
+                    )
+                });
                 match job {
                     Err(err) => {
                         context.replies.push_back(UIEvent::StatusEvent(
@@ -254,7 +485,69 @@ pub trait MailListingTrait: ListingTrait {
                     }
                 }
             }
-            ListingAction::Delete => {
+            ListingAction::Delete
+                if account.settings.conf.move_to_trash
+                    && account.special_use_mailbox(SpecialUsageMailbox::Trash)
+                        != Some(mailbox_hash) =>
+            {
+                account.mark_trashed(&env_hashes);
+                match account
+                    .special_use_mailbox(SpecialUsageMailbox::Trash)
+                    .ok_or_else(|| {
+                        MeliError::new(
+                            "No Trash mailbox found. Set `usage = \"Trash\"` in the configuration of a mailbox or use `purge` to delete permanently.",
+                        )
+                    })
+                    .and_then(|trash_hash| {
+                        account.backend.write().unwrap().copy_messages(
+                            env_hashes,
+                            mailbox_hash,
+                            trash_hash,
+                            /* move? */ true,
+                        )
+                    }) {
+                    Err(err) => {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(err.to_string()),
+                        ));
+                    }
+                    Ok(fut) => {
+                        let handle = account.job_executor.spawn_specialized(fut);
+                        account.insert_job(
+                            handle.job_id,
+                            JobRequest::Generic {
+                                name: "moving messages to Trash".into(),
+                                handle,
+                                on_finish: None,
+                                logging_level: melib::LoggingLevel::INFO,
+                            },
+                        );
+                    }
+                }
+            }
+            ListingAction::Archive => {
+                let env_hashes: Vec<EnvelopeHash> = env_hashes.iter().collect();
+                if let Err(err) = account.archive(&env_hashes, mailbox_hash) {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            err.to_string(),
+                        )));
+                }
+            }
+            ListingAction::Snooze(ref when) => {
+                let env_hashes: Vec<EnvelopeHash> = env_hashes.iter().collect();
+                if let Err(err) = crate::snooze::parse_until(when, melib::datetime::now())
+                    .and_then(|until| account.snooze(&env_hashes, mailbox_hash, until))
+                {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            err.to_string(),
+                        )));
+                }
+            }
+            ListingAction::Delete | ListingAction::Purge => {
                 let job = account
                     .backend
                     .write()
@@ -342,101 +635,35 @@ pub trait MailListingTrait: ListingTrait {
                     }
                 }
             }
-            ListingAction::ExportMbox(format, ref path) => {
-                use futures::future::try_join_all;
-                use std::future::Future;
-                use std::io::Write;
-                use std::pin::Pin;
-
-                let futures: Result<Vec<_>> = envs_to_set
-                    .iter()
-                    .map(|&env_hash| account.operation(env_hash).and_then(|mut op| op.as_bytes()))
-                    .collect::<Result<Vec<_>>>();
-                let path_ = path.to_path_buf();
-                let format = format.clone().unwrap_or_default();
-                let collection = account.collection.clone();
-                let (sender, mut receiver) = crate::jobs::oneshot::channel();
-                let fut: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>> =
-                    Box::pin(async move {
-                        let cl = async move {
-                            use melib::backends::mbox::MboxMetadata;
-                            let bytes: Vec<Vec<u8>> = try_join_all(futures?).await?;
-                            let envs: Vec<_> = envs_to_set
-                                .iter()
-                                .map(|&env_hash| collection.get_env(env_hash))
-                                .collect();
-                            let mut file = std::io::BufWriter::new(std::fs::File::create(&path_)?);
-                            let mut iter = envs.iter().zip(bytes.into_iter());
-                            let tags_lck = collection.tag_index.read().unwrap();
-                            if let Some((env, ref bytes)) = iter.next() {
-                                let tags: Vec<&str> = env
-                                    .labels()
-                                    .iter()
-                                    .filter_map(|h| tags_lck.get(h).map(|s| s.as_str()))
-                                    .collect();
-                                format.append(
-                                    &mut file,
-                                    bytes.as_slice(),
-                                    env.from().get(0),
-                                    Some(env.date()),
-                                    (env.flags(), tags),
-                                    MboxMetadata::CClient,
-                                    true,
-                                    false,
-                                )?;
-                            }
-                            for (env, bytes) in iter {
-                                let tags: Vec<&str> = env
-                                    .labels()
-                                    .iter()
-                                    .filter_map(|h| tags_lck.get(h).map(|s| s.as_str()))
-                                    .collect();
-                                format.append(
-                                    &mut file,
-                                    bytes.as_slice(),
-                                    env.from().get(0),
-                                    Some(env.date()),
-                                    (env.flags(), tags),
-                                    MboxMetadata::CClient,
-                                    false,
-                                    false,
-                                )?;
-                            }
-                            file.flush()?;
-                            Ok(())
-                        };
-                        let r: Result<()> = cl.await;
-                        let _ = sender.send(r);
-                        Ok(())
-                    });
-                let handle = account.job_executor.spawn_blocking(fut);
-                let path = path.to_path_buf();
-                account.insert_job(
-                    handle.job_id,
-                    JobRequest::Generic {
-                        name: "exporting mbox".into(),
-                        handle,
-                        on_finish: Some(CallbackFn(Box::new(move |context: &mut Context| {
-                            context.replies.push_back(match receiver.try_recv() {
-                                Err(_) | Ok(None) => UIEvent::Notification(
-                                    Some("Could not export mbox".to_string()),
-                                    "Job was canceled.".to_string(),
-                                    Some(NotificationType::Info),
-                                ),
-                                Ok(Some(Err(err))) => UIEvent::Notification(
-                                    Some("Could not export mbox".to_string()),
-                                    err.to_string(),
-                                    Some(NotificationType::Error(err.kind)),
-                                ),
-                                Ok(Some(Ok(()))) => UIEvent::Notification(
-                                    Some("Succesfully exported mbox".to_string()),
-                                    format!("Wrote to file {}", path.display()),
-                                    Some(NotificationType::Info),
-                                ),
-                            });
-                        }))),
-                        logging_level: melib::LoggingLevel::INFO,
-                    },
+            ListingAction::Export(format, mode, ref path) => {
+                let env_hashes = envs_to_set.to_vec();
+                export_job(
+                    account,
+                    Box::pin(async move { Ok(env_hashes) }),
+                    *format,
+                    *mode,
+                    path.to_path_buf(),
+                );
+            }
+            ListingAction::SaveThreadAttachments(ref path) => {
+                let dir = if let Some(path) = path {
+                    path.expand()
+                } else if let Some(dir) =
+                    mailbox_settings!(context[account_hash][&mailbox_hash].pager.attachment_dir)
+                {
+                    std::path::Path::new(dir).expand()
+                } else {
+                    context.replies.push_back(UIEvent::Notification(
+                        Some("Cannot save attachments".to_string()),
+                        "Give a PATH or set pager.attachment_dir.".to_string(),
+                        Some(NotificationType::Error(melib::ErrorKind::None)),
+                    ));
+                    return;
+                };
+                save_attachments_job(
+                    &mut context.accounts[&account_hash],
+                    envs_to_set.to_vec(),
+                    dir,
                 );
             }
             ListingAction::MoveToOtherAccount(ref _account_name, ref _mailbox_path) => {
@@ -448,27 +675,143 @@ pub trait MailListingTrait: ListingTrait {
             }
             _ => unreachable!(),
         }
-        self.set_dirty(true);
     }
+}
 
-    fn row_updates(&mut self) -> &mut SmallVec<[ThreadHash; 8]>;
-    fn selection(&mut self) -> &mut HashMap<ThreadHash, bool>;
-    fn get_focused_items(&self, _context: &Context) -> SmallVec<[ThreadHash; 8]>;
-    fn redraw_threads_list(
-        &mut self,
-        context: &Context,
-        items: Box<dyn Iterator<Item = ThreadHash>>,
+/// Spawn a job that exports the messages `env_hashes` resolves to in `format` to `path`, writing
+/// each one as soon as it's fetched, and notify the user when it's done.
+fn export_job(
+    account: &mut Account,
+    env_hashes: std::pin::Pin<
+        Box<dyn std::future::Future<Output = Result<Vec<EnvelopeHash>>> + Send + 'static>,
+    >,
+    format: crate::export::ExportFormat,
+    mode: crate::export::ExportMode,
+    path: PathBuf,
+) {
+    use std::future::Future;
+    use std::pin::Pin;
+
+    let backend = account.backend.clone();
+    let collection = account.collection.clone();
+    let path_ = path.clone();
+    let (sender, mut receiver) = crate::jobs::oneshot::channel();
+    let fut: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>> = Box::pin(async move {
+        let cl = async move {
+            let env_hashes = env_hashes.await?;
+            let mut exporter = crate::export::Exporter::new(format, mode, &path_)?;
+            for env_hash in env_hashes {
+                if !collection.contains_key(&env_hash) {
+                    continue;
+                }
+                let op = backend.read().unwrap().operation(env_hash);
+                let bytes = op?.as_bytes()?.await?;
+                let (env, tags) = {
+                    let env = collection.get_env(env_hash);
+                    let tags_lck = collection.tag_index.read().unwrap();
+                    let tags: Vec<String> = env
+                        .labels()
+                        .iter()
+                        .filter_map(|h| tags_lck.get(h).cloned())
+                        .collect();
+                    ((*env).clone(), tags)
+                };
+                exporter.write(&env, bytes, &tags)?;
+            }
+            exporter.finish().map(|_| ())
+        };
+        let r: Result<()> = cl.await;
+        let _ = sender.send(r);
+        Ok(())
+    });
+    let handle = account.job_executor.spawn_blocking(fut);
+    account.insert_job(
+        handle.job_id,
+        JobRequest::Generic {
+            name: "exporting messages".into(),
+            handle,
+            on_finish: Some(CallbackFn(Box::new(move |context: &mut Context| {
+                context.replies.push_back(match receiver.try_recv() {
+                    Err(_) | Ok(None) => UIEvent::Notification(
+                        Some("Could not export messages".to_string()),
+                        "Job was canceled.".to_string(),
+                        Some(NotificationType::Info),
+                    ),
+                    Ok(Some(Err(err))) => UIEvent::Notification(
+                        Some("Could not export messages".to_string()),
+                        err.to_string(),
+                        Some(NotificationType::Error(err.kind)),
+                    ),
+                    Ok(Some(Ok(()))) => UIEvent::Notification(
+                        Some("Succesfully exported messages".to_string()),
+                        format!("Wrote to {}", path.display()),
+                        Some(NotificationType::Info),
+                    ),
+                });
+            }))),
+            logging_level: melib::LoggingLevel::INFO,
+        },
     );
+}
 
-    fn redraw_envelope_list(
-        &mut self,
-        _context: &Context,
-        _items: Box<dyn Iterator<Item = EnvelopeHash>>,
-    ) {
-    }
-
-    /// Use `force` when there have been changes in the mailbox or account lists in `context`
-    fn refresh_mailbox(&mut self, context: &mut Context, force: bool);
+/// Spawn a job that saves the attachments of the messages `env_hashes` to the directory `dir`, and
+/// notify the user of the saved paths when it's done.
+fn save_attachments_job(account: &mut Account, env_hashes: Vec<EnvelopeHash>, dir: PathBuf) {
+    use std::future::Future;
+    use std::pin::Pin;
+
+    let backend = account.backend.clone();
+    let collection = account.collection.clone();
+    let (sender, mut receiver) = crate::jobs::oneshot::channel();
+    let fut: Pin<Box<dyn Future<Output = Result<()>> + Send + 'static>> = Box::pin(async move {
+        let cl = async move {
+            let mut attachments = vec![];
+            for env_hash in env_hashes {
+                if !collection.contains_key(&env_hash) {
+                    continue;
+                }
+                let op = backend.read().unwrap().operation(env_hash);
+                let bytes = op?.as_bytes()?.await?;
+                let body = AttachmentBuilder::new(&bytes).build();
+                attachments.extend(crate::attachments::saveable_attachments(&body));
+            }
+            crate::attachments::save_attachments(&dir, &attachments)
+        };
+        let r: Result<Vec<PathBuf>> = cl.await;
+        let _ = sender.send(r);
+        Ok(())
+    });
+    let handle = account.job_executor.spawn_blocking(fut);
+    account.insert_job(
+        handle.job_id,
+        JobRequest::Generic {
+            name: "saving attachments".into(),
+            handle,
+            on_finish: Some(CallbackFn(Box::new(move |context: &mut Context| {
+                context.replies.push_back(match receiver.try_recv() {
+                    Err(_) | Ok(None) => UIEvent::Notification(
+                        Some("Could not save attachments".to_string()),
+                        "Job was canceled.".to_string(),
+                        Some(NotificationType::Info),
+                    ),
+                    Ok(Some(Err(err))) => UIEvent::Notification(
+                        Some("Could not save attachments".to_string()),
+                        err.to_string(),
+                        Some(NotificationType::Error(err.kind)),
+                    ),
+                    Ok(Some(Ok(paths))) if paths.is_empty() => UIEvent::StatusEvent(
+                        StatusEvent::DisplayMessage("No attachments to save.".to_string()),
+                    ),
+                    Ok(Some(Ok(paths))) => UIEvent::Notification(
+                        None,
+                        crate::attachments::saved_message(&paths),
+                        Some(NotificationType::Info),
+                    ),
+                });
+            }))),
+            logging_level: melib::LoggingLevel::INFO,
+        },
+    );
 }
 
 pub trait ListingTrait: Component {
@@ -483,6 +826,8 @@ pub trait ListingTrait: Component {
         _context: &Context,
     ) {
     }
+    /// Highlight the occurrences of `term` in the listed entries, see `highlight_matches`.
+    fn set_highlight_term(&mut self, _term: Option<String>) {}
     fn unfocused(&self) -> bool;
     fn set_modifier_active(&mut self, _new_val: bool) {}
     fn set_modifier_command(&mut self, _new_val: Option<Modifier>) {}
@@ -490,6 +835,150 @@ pub trait ListingTrait: Component {
         None
     }
     fn set_movement(&mut self, mvm: PageMovement);
+    /// Start or stop selecting exactly the entries between the cursor's position when visual
+    /// mode started and the cursor, see the `visual_mode` shortcut.
+    fn set_visual_mode(&mut self, _active: bool) {}
+    /// Index of the entry under the cursor.
+    fn cursor_row(&self) -> usize {
+        0
+    }
+    /// Area the entries were last drawn in, if they are visible. Mouse clicks inside it select
+    /// the entry under the pointer.
+    fn list_area(&self) -> Option<Area> {
+        None
+    }
+    /// Grow or shrink the view of the opened entry in split layouts by `delta` percent of the
+    /// listing's area.
+    fn adjust_view_ratio(&mut self, _delta: isize, _context: &Context) {}
+}
+
+/// The confirmation asked before applying `action` to `count` selected threads, or `None` if it
+/// doesn't modify them.
+fn bulk_action_question(action: &ListingAction, count: usize) -> Option<String> {
+    Some(match action {
+        ListingAction::SetSeen => format!("Mark {} threads as read?", count),
+        ListingAction::SetUnseen => format!("Mark {} threads as unread?", count),
+        ListingAction::Delete => format!("Delete {} threads?", count),
+        ListingAction::Purge => format!("Permanently delete {} threads?", count),
+        ListingAction::Archive => format!("Archive {} threads?", count),
+        ListingAction::Snooze(ref until) => format!("Snooze {} threads until {}?", count, until),
+        ListingAction::MoveTo(ref path) => format!("Move {} threads to {}?", count, path),
+        ListingAction::MoveToOtherAccount(ref account, ref path) => {
+            format!("Move {} threads to {} in {}?", count, path, account)
+        }
+        ListingAction::Tag(TagAction::Add(ref tag)) => {
+            format!("Add tag `{}` to {} threads?", tag, count)
+        }
+        ListingAction::Tag(TagAction::Remove(ref tag)) => {
+            format!("Remove tag `{}` from {} threads?", tag, count)
+        }
+        _ => return None,
+    })
+}
+
+/// Highlight the occurrences of `term` in `area` of `grid` with `attr`, ignoring case.
+pub fn highlight_matches(grid: &mut CellBuffer, area: Area, term: &str, attr: ThemeAttribute) {
+    let term: Vec<char> = term.to_lowercase().chars().collect();
+    if term.is_empty() || !is_valid_area!(area) {
+        return;
+    }
+    let (width, height) = grid.size();
+    let (upper_left, bottom_right) = area;
+    let xs = get_x(upper_left)..std::cmp::min(get_x(bottom_right) + 1, width);
+    for y in get_y(upper_left)..std::cmp::min(get_y(bottom_right) + 1, height) {
+        let row: Vec<char> = xs
+            .clone()
+            .map(|x| grid[(x, y)].ch().to_lowercase().next().unwrap_or(' '))
+            .collect();
+        let mut i = 0;
+        while i + term.len() <= row.len() {
+            if row[i..i + term.len()] != term[..] {
+                i += 1;
+                continue;
+            }
+            for x in (xs.start + i)..(xs.start + i + term.len()) {
+                grid[(x, y)]
+                    .set_fg(attr.fg)
+                    .set_bg(attr.bg)
+                    .set_attrs(attr.attrs);
+            }
+            i += term.len();
+        }
+    }
+}
+
+/// Split `area` between the entry list and the view of an opened entry according to the
+/// `listing.view_layout` setting of the mailbox at `coordinates`, or `default_layout` if it is
+/// unset, and draw the boundary between them. The view takes `view_ratio` percent of the area,
+/// or `pager.pager_ratio` if it is `None`. Returns the area of the list, if it is shown, and the
+/// area of the view.
+pub fn split_view_area(
+    grid: &mut CellBuffer,
+    area: Area,
+    coordinates: (AccountHash, MailboxHash),
+    default_layout: ViewLayout,
+    view_ratio: Option<usize>,
+    context: &mut Context,
+) -> (Option<Area>, Area) {
+    let layout = mailbox_settings!(context[coordinates.0][&coordinates.1].listing.view_layout)
+        .unwrap_or(default_layout);
+    let ratio = view_ratio.unwrap_or_else(|| {
+        *mailbox_settings!(context[coordinates.0][&coordinates.1].pager.pager_ratio)
+    });
+    let (upper_left, bottom_right) = area;
+    match layout {
+        ViewLayout::FullScreen => (None, area),
+        ViewLayout::Bottom => {
+            let total_rows = height!(area) + 1;
+            let view_rows = std::cmp::min((ratio * total_rows) / 100, total_rows.saturating_sub(2));
+            if view_rows == 0 {
+                return (None, area);
+            }
+            let mid = get_y(bottom_right) - view_rows;
+            for x in get_x(upper_left)..=get_x(bottom_right) {
+                set_and_join_box(grid, (x, mid), BoxBoundary::Horizontal);
+            }
+            context
+                .dirty_areas
+                .push_back((set_y(upper_left, mid), set_y(bottom_right, mid)));
+            (
+                Some((upper_left, set_y(bottom_right, mid - 1))),
+                (set_y(upper_left, mid + 1), bottom_right),
+            )
+        }
+        ViewLayout::Right => {
+            let total_cols = width!(area) + 1;
+            let view_cols = std::cmp::min((ratio * total_cols) / 100, total_cols.saturating_sub(2));
+            if view_cols == 0 {
+                return (None, area);
+            }
+            let mid = get_x(bottom_right) - view_cols;
+            for y in get_y(upper_left)..=get_y(bottom_right) {
+                set_and_join_box(grid, (mid, y), BoxBoundary::Vertical);
+            }
+            context
+                .dirty_areas
+                .push_back((set_x(upper_left, mid), set_x(bottom_right, mid)));
+            (
+                Some((upper_left, set_x(bottom_right, mid - 1))),
+                (set_x(upper_left, mid + 1), bottom_right),
+            )
+        }
+    }
+}
+
+/// Change the view share `view_ratio` of the mailbox at `coordinates` by `delta` percent, keeping
+/// both the list and the view visible.
+fn adjust_view_ratio(
+    view_ratio: &mut Option<usize>,
+    delta: isize,
+    coordinates: (AccountHash, MailboxHash),
+    context: &Context,
+) {
+    let ratio = view_ratio.unwrap_or_else(|| {
+        *mailbox_settings!(context[coordinates.0][&coordinates.1].pager.pager_ratio)
+    }) as isize;
+    *view_ratio = Some((ratio + delta).max(10).min(90) as usize);
 }
 
 #[derive(Debug)]
@@ -588,6 +1077,11 @@ pub struct Listing {
     cursor_pos: (usize, MenuEntryCursor),
     menu_cursor_pos: (usize, MenuEntryCursor),
     menu_content: CellBuffer,
+    /// Area the menu was last drawn in, its first visible row and the row of each account in
+    /// `menu_content`, to find the menu entry under the mouse pointer.
+    menu_area: Area,
+    menu_scroll_offset: usize,
+    menu_account_rows: Vec<usize>,
     menu_scrollbar_show_timer: crate::jobs::Timer,
     show_menu_scrollbar: ShowMenuScrollbar,
     startup_checks_rate: RateLimit,
@@ -603,6 +1097,18 @@ pub struct Listing {
     ratio: usize, // right/(container width) * 100
     menu_width: WidgetWidth,
     focus: ListingFocus,
+    /// Listing position of a previous session, to restore once its account has loaded its
+    /// mailboxes.
+    pending_session: Option<ListingSession>,
+    /// Entry of a previous session to move the cursor to once its mailbox has loaded.
+    pending_cursor: Option<(MailboxHash, usize)>,
+    /// Term of a `search` command being typed, applied when `search_preview_timer` fires.
+    search_preview: Option<String>,
+    search_preview_timer: crate::jobs::Timer,
+    /// Whether the listing is filtered by a search preview.
+    search_previewing: bool,
+    /// Action on several selected threads waiting for the user's confirmation.
+    pending_bulk_action: Option<(SmallVec<[ThreadHash; 8]>, ListingAction)>,
 }
 
 impl fmt::Display for Listing {
@@ -618,6 +1124,32 @@ impl fmt::Display for Listing {
 }
 
 impl Component for Listing {
+    fn save_session(&self, session: &mut Session, context: &Context) {
+        if let Some(ref pending) = self.pending_session {
+            /* The listing hasn't been restored yet */
+            session.listings.push(pending.clone());
+            return;
+        }
+        let account = &context.accounts[self.cursor_pos.0];
+        let (mailbox, cursor) = match self.cursor_pos.1 {
+            MenuEntryCursor::Status => (None, 0),
+            MenuEntryCursor::Mailbox(_) => (
+                account
+                    .mailbox_entries
+                    .get(&self.component.coordinates().1)
+                    .map(|m| m.ref_mailbox.path().to_string()),
+                self.pending_cursor
+                    .map(|(_, cursor)| cursor)
+                    .unwrap_or_else(|| self.component.cursor_row()),
+            ),
+        };
+        session.listings.push(ListingSession {
+            account: account.name().to_string(),
+            mailbox,
+            cursor,
+        });
+    }
+
     fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
         if !self.is_dirty() {
             return;
@@ -625,6 +1157,9 @@ impl Component for Listing {
         if !is_valid_area!(area) {
             return;
         }
+        if self.pending_session.is_some() || self.pending_cursor.is_some() {
+            self.apply_pending_session(context);
+        }
         let upper_left = upper_left!(area);
         let bottom_right = bottom_right!(area);
         let total_cols = get_x(bottom_right) - get_x(upper_left);
@@ -709,6 +1244,11 @@ impl Component for Listing {
     }
 
     fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        if let UIEvent::Input(_) = event {
+            /* Don't move the cursor away from where the user has moved it to */
+            self.pending_session = None;
+            self.pending_cursor = None;
+        }
         match event {
             UIEvent::ConfigReload { old_settings: _ } => {
                 self.theme_default = crate::conf::value(context, "theme_default");
@@ -751,16 +1291,7 @@ impl Component for Listing {
                 if self.cursor_pos.0 == account_index {
                     self.change_account(context);
                 } else {
-                    self.accounts[account_index].entries = context.accounts[&*account_hash]
-                        .list_mailboxes()
-                        .into_iter()
-                        .filter(|mailbox_node| {
-                            context.accounts[&*account_hash][&mailbox_node.hash]
-                                .ref_mailbox
-                                .is_subscribed()
-                        })
-                        .map(|f| (f.depth, f.indentation, f.has_sibling, f.hash))
-                        .collect::<_>();
+                    self.accounts[account_index].entries = sidebar_entries(*account_hash, context);
                     self.set_dirty(true);
                     self.menu_content.empty();
                     context
@@ -778,16 +1309,7 @@ impl Component for Listing {
                     .get_index_of(account_hash)
                     .expect("Invalid account_hash in UIEventMailbox{Delete,Create}");
                 self.menu_content.empty();
-                self.accounts[account_index].entries = context.accounts[&*account_hash]
-                    .list_mailboxes()
-                    .into_iter()
-                    .filter(|mailbox_node| {
-                        context.accounts[&*account_hash][&mailbox_node.hash]
-                            .ref_mailbox
-                            .is_subscribed()
-                    })
-                    .map(|f| (f.depth, f.indentation, f.has_sibling, f.hash))
-                    .collect::<_>();
+                self.accounts[account_index].entries = sidebar_entries(*account_hash, context);
                 let mut fallback = 0;
                 if let MenuEntryCursor::Mailbox(ref mut cur) = self.cursor_pos.1 {
                     *cur = std::cmp::min(
@@ -819,9 +1341,78 @@ impl Component for Listing {
             UIEvent::ChangeMode(UIMode::Normal) => {
                 self.set_dirty(true);
             }
+            UIEvent::Action(Action::Listing(ListingAction::SearchPreview(_)))
+                if self.component.unfocused() =>
+            {
+                /* The opened entry highlights the matches in its pager instead */
+                return self.component.process_event(event, context);
+            }
+            UIEvent::Action(Action::Listing(ListingAction::SearchPreview(ref term))) => {
+                if !term.is_empty() {
+                    self.search_preview = Some(term.to_string());
+                    self.search_preview_timer.rearm();
+                } else if self.search_preview.take().is_some() || self.search_previewing {
+                    self.search_previewing = false;
+                    let coordinates = self.component.coordinates();
+                    self.component.set_highlight_term(None);
+                    self.component.set_coordinates(coordinates);
+                    self.component.refresh_mailbox(context, false);
+                    self.component.set_dirty(true);
+                }
+                return true;
+            }
+            UIEvent::Timer(n) if *n == self.search_preview_timer.id() => {
+                if let Some(term) = self.search_preview.take() {
+                    let (account_hash, mailbox_hash) = self.component.coordinates();
+                    let results = context.accounts[&account_hash].quick_search(&term, mailbox_hash);
+                    self.component.filter(term.clone(), Ok(results), context);
+                    self.component.set_highlight_term(Some(term));
+                    self.component.set_dirty(true);
+                    self.search_previewing = true;
+                }
+                return true;
+            }
+            UIEvent::FinishedUIDialog(ref id, ref results) if *id == self.id => {
+                if let Some(true) = results.downcast_ref::<bool>() {
+                    if let Some((focused, action)) = self.pending_bulk_action.take() {
+                        self.perform_bulk_action(focused, &action, context);
+                    }
+                } else {
+                    self.pending_bulk_action = None;
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Action(Action::Listing(ListingAction::Search(_))) => {
+                /* The search replaces the preview. */
+                self.search_preview = None;
+                self.search_previewing = false;
+                self.component.set_highlight_term(None);
+            }
             UIEvent::Resize => {
                 self.set_dirty(true);
             }
+            UIEvent::Action(Action::Listing(ListingAction::OpenMailbox(
+                ref account_hash,
+                ref mailbox_hash,
+            ))) => {
+                if let Some((account_idx, idx)) =
+                    self.accounts.iter().enumerate().find_map(|(i, a)| {
+                        if a.hash != *account_hash {
+                            return None;
+                        }
+                        a.entries
+                            .iter()
+                            .position(|e| e.3 == *mailbox_hash)
+                            .map(|idx| (i, idx))
+                    })
+                {
+                    self.cursor_pos = (account_idx, MenuEntryCursor::Mailbox(idx));
+                    self.menu_cursor_pos = self.cursor_pos;
+                    self.change_account(context);
+                }
+                return true;
+            }
             UIEvent::Action(Action::ViewMailbox(ref idx)) => {
                 if let Some((_, _, _, mailbox_hash)) =
                     self.accounts[self.cursor_pos.0].entries.get(*idx)
@@ -829,6 +1420,7 @@ impl Component for Listing {
                     let account_hash = self.accounts[self.cursor_pos.0].hash;
                     self.cursor_pos.1 = MenuEntryCursor::Mailbox(*idx);
                     self.status = None;
+                    self.leave_mailbox((account_hash, *mailbox_hash), context);
                     self.component
                         .process_event(&mut UIEvent::VisibilityChange(false), context);
                     self.component
@@ -841,6 +1433,77 @@ impl Component for Listing {
             _ => {}
         }
 
+        match *event {
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(MouseButton::Left, x, y)))
+                if self.menu_visibility && mouse_position(x, y, self.menu_area).is_some() =>
+            {
+                let (_, row) = mouse_position(x, y, self.menu_area).unwrap();
+                if let Some(cursor) = self.menu_entry_at(row + self.menu_scroll_offset) {
+                    self.cursor_pos = cursor;
+                    self.menu_cursor_pos = cursor;
+                    if cursor.1 == MenuEntryCursor::Status {
+                        self.open_status(cursor.0, context);
+                    } else {
+                        self.change_account(context);
+                    }
+                    self.focus = ListingFocus::Mailbox;
+                    self.ratio = 90;
+                    self.set_dirty(true);
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::ScrollUpdate(
+                            ScrollUpdate::End(self.id),
+                        )));
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::UpdateStatus(
+                            self.get_status(context),
+                        )));
+                }
+                return true;
+            }
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(button, x, y)))
+                if self.status.is_none()
+                    && self
+                        .component
+                        .list_area()
+                        .and_then(|area| mouse_position(x, y, area))
+                        .is_some() =>
+            {
+                let (_, row) = self
+                    .component
+                    .list_area()
+                    .and_then(|area| mouse_position(x, y, area))
+                    .unwrap();
+                match button {
+                    MouseButton::Left => {
+                        self.component.set_movement(PageMovement::Row(row));
+                    }
+                    MouseButton::WheelUp => {
+                        self.component
+                            .set_movement(PageMovement::Up(MOUSE_SCROLL_LINES));
+                    }
+                    MouseButton::WheelDown => {
+                        self.component
+                            .set_movement(PageMovement::Down(MOUSE_SCROLL_LINES));
+                    }
+                    _ => return false,
+                }
+                if self.focus == ListingFocus::Menu {
+                    self.focus = ListingFocus::Mailbox;
+                    self.ratio = 90;
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::ScrollUpdate(
+                            ScrollUpdate::End(self.id),
+                        )));
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            _ => {}
+        }
+
         if self.focus == ListingFocus::Mailbox && self.status.is_some() {
             if let Some(s) = self.status.as_mut() {
                 if s.process_event(event, context) {
@@ -1013,6 +1676,28 @@ impl Component for Listing {
                     self.menu_visibility = !self.menu_visibility;
                     self.set_dirty(true);
                 }
+                UIEvent::Input(ref k)
+                    if shortcut!(
+                        k == shortcuts[Listing::DESCRIPTION]["toggle_account_collapse"]
+                    ) =>
+                {
+                    self.toggle_account_collapse(self.cursor_pos.0);
+                    return true;
+                }
+                UIEvent::Input(ref k)
+                    if shortcut!(k == shortcuts[Listing::DESCRIPTION]["increase_view_ratio"]) =>
+                {
+                    self.component.adjust_view_ratio(10, context);
+                    self.set_dirty(true);
+                    return true;
+                }
+                UIEvent::Input(ref k)
+                    if shortcut!(k == shortcuts[Listing::DESCRIPTION]["decrease_view_ratio"]) =>
+                {
+                    self.component.adjust_view_ratio(-10, context);
+                    self.set_dirty(true);
+                    return true;
+                }
                 _ => {}
             }
 
@@ -1035,6 +1720,64 @@ impl Component for Listing {
                             self.component.set_style(IndexStyle::Conversations);
                             return true;
                         }
+                        Action::Listing(ListingAction::AwaitingReply) => {
+                            let (account_hash, mailbox_hash) = self.component.coordinates();
+                            let results =
+                                context.accounts[&account_hash].awaiting_reply(mailbox_hash);
+                            self.component.filter(
+                                "awaiting-reply".to_string(),
+                                Ok(results),
+                                context,
+                            );
+                            self.component.set_dirty(true);
+                            return true;
+                        }
+                        Action::Listing(ListingAction::ExportMailbox(
+                            format,
+                            mode,
+                            query,
+                            path,
+                        )) => {
+                            let (account_hash, mailbox_hash) = self.component.coordinates();
+                            let account = &mut context.accounts[&account_hash];
+                            let env_hashes: std::pin::Pin<
+                                Box<
+                                    dyn std::future::Future<Output = Result<Vec<EnvelopeHash>>>
+                                        + Send
+                                        + 'static,
+                                >,
+                            > = if let Some(query) = query {
+                                match account.search(
+                                    query,
+                                    (SortField::Date, SortOrder::Asc),
+                                    mailbox_hash,
+                                ) {
+                                    Ok(fut) => Box::pin(async move { Ok(fut.await?.to_vec()) }),
+                                    Err(err) => {
+                                        context.replies.push_back(UIEvent::StatusEvent(
+                                            StatusEvent::DisplayMessage(err.to_string()),
+                                        ));
+                                        return true;
+                                    }
+                                }
+                            } else {
+                                let mut env_hashes: Vec<EnvelopeHash> = account
+                                    .collection
+                                    .get_mailbox(mailbox_hash)
+                                    .iter()
+                                    .cloned()
+                                    .collect();
+                                {
+                                    let envelopes = account.collection.envelopes.read().unwrap();
+                                    env_hashes.sort_by_key(|h| {
+                                        envelopes.get(h).map(|e| e.date()).unwrap_or(0)
+                                    });
+                                }
+                                Box::pin(async move { Ok(env_hashes) })
+                            };
+                            export_job(account, env_hashes, *format, *mode, path.to_path_buf());
+                            return true;
+                        }
                         Action::Listing(ListingAction::Import(file_path, mailbox_path)) => {
                             let account = &mut context.accounts[self.cursor_pos.0];
                             if let Err(err) = account
@@ -1060,21 +1803,152 @@ impl Component for Listing {
                         Action::Listing(a @ ListingAction::SetSeen)
                         | Action::Listing(a @ ListingAction::SetUnseen)
                         | Action::Listing(a @ ListingAction::Delete)
+                        | Action::Listing(a @ ListingAction::Purge)
+                        | Action::Listing(a @ ListingAction::Archive)
+                        | Action::Listing(a @ ListingAction::Snooze(_))
                         | Action::Listing(a @ ListingAction::CopyTo(_))
                         | Action::Listing(a @ ListingAction::MoveTo(_))
                         | Action::Listing(a @ ListingAction::CopyToOtherAccount(_, _))
                         | Action::Listing(a @ ListingAction::MoveToOtherAccount(_, _))
-                        | Action::Listing(a @ ListingAction::ExportMbox(_, _))
+                        | Action::Listing(a @ ListingAction::Export(_, _, _))
+                        | Action::Listing(a @ ListingAction::SaveThreadAttachments(_))
                         | Action::Listing(a @ ListingAction::Tag(_)) => {
                             let focused = self.component.get_focused_items(context);
-                            self.component.perform_action(context, focused, a);
-                            let mut row_updates: SmallVec<[ThreadHash; 8]> = SmallVec::new();
-                            for (k, v) in self.component.selection().iter_mut() {
-                                if *v {
-                                    *v = false;
-                                    row_updates.push(*k);
+                            if focused.len() > 1 {
+                                if let Some(question) = bulk_action_question(a, focused.len()) {
+                                    self.pending_bulk_action = Some((focused, a.clone()));
+                                    let id = self.id;
+                                    context.replies.push_back(UIEvent::GlobalUIDialog(Box::new(
+                                        UIConfirmationDialog::new(
+                                            &question,
+                                            vec![
+                                                (true, "yes".to_string()),
+                                                (false, "no".to_string()),
+                                            ],
+                                            true,
+                                            Some(Box::new(
+                                                move |_id: ComponentId, result: bool| {
+                                                    Some(UIEvent::FinishedUIDialog(
+                                                        id,
+                                                        Box::new(result),
+                                                    ))
+                                                },
+                                            )),
+                                            context,
+                                        ),
+                                    )));
+                                    return true;
                                 }
                             }
+                            self.perform_bulk_action(focused, a, context);
+                        }
+                        Action::Listing(ListingAction::LinkThreads) => {
+                            let focused = self.component.get_focused_items(context);
+                            let (account_hash, mailbox_hash) = self.component.coordinates();
+                            let account = &mut context.accounts[&account_hash];
+                            /* The first message of every focused thread, oldest thread first */
+                            let mut roots: SmallVec<[(UnixTimestamp, EnvelopeHash); 8]> = {
+                                let threads = account.collection.get_threads(mailbox_hash);
+                                focused
+                                    .iter()
+                                    .filter_map(|thread_hash| {
+                                        threads
+                                            .thread_group_iter(*thread_hash)
+                                            .next()
+                                            .and_then(|(_, h)| threads.thread_nodes()[&h].message())
+                                    })
+                                    .map(|env_hash| {
+                                        (account.collection.get_env(env_hash).date(), env_hash)
+                                    })
+                                    .collect()
+                            };
+                            if roots.len() < 2 {
+                                context.replies.push_back(UIEvent::StatusEvent(
+                                    StatusEvent::DisplayMessage(
+                                        "Select at least two threads to link.".to_string(),
+                                    ),
+                                ));
+                                return true;
+                            }
+                            roots.sort();
+                            let parent = String::from_utf8_lossy(
+                                account.collection.get_env(roots[0].1).message_id().raw(),
+                            )
+                            .to_string();
+                            for (_, env_hash) in roots.iter().skip(1) {
+                                if let Err(err) = account.set_thread_link(
+                                    *env_hash,
+                                    melib::ThreadLink::Parent(parent.clone()),
+                                ) {
+                                    context.replies.push_back(UIEvent::StatusEvent(
+                                        StatusEvent::DisplayMessage(err.to_string()),
+                                    ));
+                                    return true;
+                                }
+                            }
+                            for v in self.component.selection().values_mut() {
+                                *v = false;
+                            }
+                            context.replies.push_back(UIEvent::StatusEvent(
+                                StatusEvent::DisplayMessage(format!(
+                                    "Linked {} threads.",
+                                    roots.len()
+                                )),
+                            ));
+                            return true;
+                        }
+                        Action::Listing(ListingAction::BreakThread) => {
+                            let focused = self.component.get_focused_items(context);
+                            let (account_hash, mailbox_hash) = self.component.coordinates();
+                            let account = &mut context.accounts[&account_hash];
+                            /* Every message of every focused thread but its first one */
+                            let replies: SmallVec<[EnvelopeHash; 8]> = {
+                                let threads = account.collection.get_threads(mailbox_hash);
+                                focused
+                                    .iter()
+                                    .flat_map(|thread_hash| {
+                                        threads.thread_group_iter(*thread_hash).skip(1)
+                                    })
+                                    .filter_map(|(_, h)| threads.thread_nodes()[&h].message())
+                                    .filter(|h| {
+                                        !account
+                                            .collection
+                                            .get_env(*h)
+                                            .message_id()
+                                            .raw()
+                                            .is_empty()
+                                    })
+                                    .collect()
+                            };
+                            if focused.is_empty() {
+                                context.replies.push_back(UIEvent::StatusEvent(
+                                    StatusEvent::DisplayMessage(
+                                        "Select threads to break.".to_string(),
+                                    ),
+                                ));
+                                return true;
+                            }
+                            for env_hash in replies.iter() {
+                                if let Err(err) =
+                                    account.set_thread_link(*env_hash, melib::ThreadLink::Root)
+                                {
+                                    context.replies.push_back(UIEvent::StatusEvent(
+                                        StatusEvent::DisplayMessage(err.to_string()),
+                                    ));
+                                    return true;
+                                }
+                            }
+                            for v in self.component.selection().values_mut() {
+                                *v = false;
+                            }
+                            context.replies.push_back(UIEvent::StatusEvent(
+                                StatusEvent::DisplayMessage(format!(
+                                    "Broke {} threads into {} threads.",
+                                    focused.len(),
+                                    focused.len() + replies.len()
+                                )),
+                            ));
+                            return true;
                         }
                         _ => {}
                     },
@@ -1197,6 +2071,14 @@ impl Component for Listing {
                             return true;
                         }
                     }
+                    UIEvent::Input(ref key)
+                        if shortcut!(key == shortcuts[Listing::DESCRIPTION]["archive"]) =>
+                    {
+                        let mut event = UIEvent::Action(Action::Listing(ListingAction::Archive));
+                        if self.process_event(&mut event, context) {
+                            return true;
+                        }
+                    }
                     UIEvent::Input(ref key)
                         if shortcut!(key == shortcuts[Listing::DESCRIPTION]["refresh"]) =>
                     {
@@ -1248,6 +2130,14 @@ impl Component for Listing {
             }
         } else if self.focus == ListingFocus::Menu {
             match *event {
+                UIEvent::Input(ref k)
+                    if shortcut!(
+                        k == shortcuts[Listing::DESCRIPTION]["toggle_account_collapse"]
+                    ) =>
+                {
+                    self.toggle_account_collapse(self.menu_cursor_pos.0);
+                    return true;
+                }
                 UIEvent::Input(Key::Right) => {
                     self.focus = ListingFocus::Mailbox;
                     context
@@ -1325,12 +2215,16 @@ impl Component for Listing {
                                 ) => {
                                     if *account_cursor > 0 {
                                         *account_cursor -= 1;
-                                        *entry_cursor = MenuEntryCursor::Mailbox(
-                                            self.accounts[*account_cursor]
-                                                .entries
-                                                .len()
-                                                .saturating_sub(1),
-                                        );
+                                        *entry_cursor = if self.accounts[*account_cursor]
+                                            .visible_entries()
+                                            == 0
+                                        {
+                                            MenuEntryCursor::Status
+                                        } else {
+                                            MenuEntryCursor::Mailbox(
+                                                self.accounts[*account_cursor].entries.len() - 1,
+                                            )
+                                        };
                                     } else {
                                         return true;
                                     }
@@ -1353,7 +2247,7 @@ impl Component for Listing {
                                 (
                                     ref account_cursor,
                                     ref mut entry_cursor @ MenuEntryCursor::Status,
-                                ) if !self.accounts[*account_cursor].entries.is_empty() => {
+                                ) if self.accounts[*account_cursor].visible_entries() > 0 => {
                                     *entry_cursor = MenuEntryCursor::Mailbox(0);
                                 }
                                 /* If current account has no mailboxes, go to next account */
@@ -1440,10 +2334,11 @@ impl Component for Listing {
                         }
                         _ => return true,
                     };
-                    if self.accounts[self.menu_cursor_pos.0]
-                        .entries
-                        .get(target)
-                        .is_some()
+                    if !self.accounts[self.menu_cursor_pos.0].collapsed
+                        && self.accounts[self.menu_cursor_pos.0]
+                            .entries
+                            .get(target)
+                            .is_some()
                     {
                         self.menu_cursor_pos.1 = MenuEntryCursor::Mailbox(target)
                     } else {
@@ -1609,6 +2504,17 @@ impl Component for Listing {
         self.component.set_id(id);
     }
 
+    fn status_coordinates(&self) -> Option<(AccountHash, Option<MailboxHash>)> {
+        let account = &self.accounts[self.cursor_pos.0];
+        Some((
+            account.hash,
+            match self.cursor_pos.1 {
+                MenuEntryCursor::Mailbox(idx) => account.entries.get(idx).map(|e| e.3),
+                MenuEntryCursor::Status => None,
+            },
+        ))
+    }
+
     fn get_status(&self, context: &Context) -> String {
         let mailbox_hash = match self.cursor_pos.1 {
             MenuEntryCursor::Mailbox(idx) => {
@@ -1654,24 +2560,17 @@ impl Component for Listing {
 impl Listing {
     pub const DESCRIPTION: &'static str = "listing";
     pub fn new(context: &mut Context) -> Self {
+        let collapsed_accounts = load_collapsed_accounts();
         let account_entries: Vec<AccountMenuEntry> = context
             .accounts
             .iter()
             .enumerate()
-            .map(|(i, (h, a))| {
-                let entries: SmallVec<[(usize, u32, bool, MailboxHash); 16]> = a
-                    .list_mailboxes()
-                    .into_iter()
-                    .filter(|mailbox_node| a[&mailbox_node.hash].ref_mailbox.is_subscribed())
-                    .map(|f| (f.depth, f.indentation, f.has_sibling, f.hash))
-                    .collect::<_>();
-
-                AccountMenuEntry {
-                    name: a.name().to_string(),
-                    hash: *h,
-                    index: i,
-                    entries,
-                }
+            .map(|(i, (h, a))| AccountMenuEntry {
+                name: a.name().to_string(),
+                hash: *h,
+                index: i,
+                entries: sidebar_entries(*h, context),
+                collapsed: collapsed_accounts.contains(a.name()),
             })
             .collect();
         let first_account_hash = account_entries[0].hash;
@@ -1684,10 +2583,20 @@ impl Listing {
             cursor_pos: (0, MenuEntryCursor::Mailbox(0)),
             menu_cursor_pos: (0, MenuEntryCursor::Mailbox(0)),
             menu_content: CellBuffer::new_with_context(0, 0, None, context),
+            menu_area: ((0, 0), (0, 0)),
+            menu_scroll_offset: 0,
+            menu_account_rows: Vec::new(),
             menu_scrollbar_show_timer: context.job_executor.clone().create_timer(
                 std::time::Duration::from_secs(0),
                 std::time::Duration::from_millis(1200),
             ),
+            search_preview: None,
+            search_preview_timer: context.job_executor.clone().create_timer(
+                std::time::Duration::from_secs(0),
+                std::time::Duration::from_millis(250),
+            ),
+            search_previewing: false,
+            pending_bulk_action: None,
             show_menu_scrollbar: ShowMenuScrollbar::Never,
             startup_checks_rate: RateLimit::new(2, 1000, context.job_executor.clone()),
             theme_default: conf::value(context, "theme_default"),
@@ -1701,6 +2610,8 @@ impl Listing {
             menu_width: WidgetWidth::Unset,
             focus: ListingFocus::Mailbox,
             cmd_buf: String::with_capacity(4),
+            pending_session: None,
+            pending_cursor: None,
         };
         ret.change_account(context);
         ret
@@ -1712,7 +2623,7 @@ impl Listing {
             + self
                 .accounts
                 .iter()
-                .map(|entry| entry.entries.len() + 1)
+                .map(|entry| entry.visible_entries() + 1)
                 .sum::<usize>();
         let min_width: usize = 2 * width!(area);
         let (width, height) = self.menu_content.size();
@@ -1724,10 +2635,12 @@ impl Listing {
             let _ = self.menu_content.resize(min_width * 2, total_height, None);
             let bottom_right = pos_dec(self.menu_content.size(), (1, 1));
             let mut y = 0;
+            self.menu_account_rows.clear();
             for a in 0..self.accounts.len() {
                 if y > get_y(bottom_right) {
                     break;
                 }
+                self.menu_account_rows.push(y);
                 y += self.print_account(((0, y), bottom_right), a, context);
                 y += 3;
             }
@@ -1741,7 +2654,7 @@ impl Listing {
                 .accounts
                 .iter()
                 .take(cursor.0)
-                .map(|entry| entry.entries.len() + 1)
+                .map(|entry| entry.visible_entries() + 1)
                 .sum::<usize>()
             + match cursor.1 {
                 MenuEntryCursor::Status => 0,
@@ -1754,15 +2667,14 @@ impl Listing {
             rows * y_offset.wrapping_div(rows).saturating_sub(1) + y_offset.wrapping_rem(rows)
         };
 
+        self.menu_area = area;
+        self.menu_scroll_offset = std::cmp::min((height - 1).saturating_sub(rows), skip_offset);
         copy_area(
             grid,
             &self.menu_content,
             area,
             (
-                (
-                    0,
-                    std::cmp::min((height - 1).saturating_sub(rows), skip_offset),
-                ),
+                (0, self.menu_scroll_offset),
                 (width - 1, std::cmp::min(skip_offset + rows, height - 1)),
             ),
         );
@@ -1828,7 +2740,8 @@ impl Listing {
 
         let must_highlight_account: bool = cursor.0 == self.accounts[aidx].index;
 
-        let mut lines: Vec<(usize, usize, u32, bool, MailboxHash, Option<usize>)> = Vec::new();
+        let mut lines: Vec<(usize, usize, u32, bool, MailboxHash, Option<(usize, usize)>)> =
+            Vec::new();
 
         for (i, &(depth, indentation, has_sibling, mailbox_hash)) in
             self.accounts[aidx].entries.iter().enumerate()
@@ -1845,7 +2758,7 @@ impl Listing {
                             indentation,
                             has_sibling,
                             mailbox_hash,
-                            mailboxes[&mailbox_hash].count().ok().map(|(v, _)| v),
+                            mailboxes[&mailbox_hash].count().ok(),
                         ));
                     }
                 }
@@ -1866,9 +2779,20 @@ impl Listing {
             crate::conf::value(context, "mail.sidebar_account_name")
         };
 
+        let count_format = account_settings!(
+            context[self.accounts[aidx].hash]
+                .listing
+                .sidebar_count_format
+        )
+        .as_deref();
+
         /* Print account name first */
-        write_string_to_grid(
-            &self.accounts[aidx].name,
+        let (x, _) = write_string_to_grid(
+            &if self.accounts[aidx].collapsed {
+                format!("+ {}", self.accounts[aidx].name)
+            } else {
+                self.accounts[aidx].name.clone()
+            },
             &mut self.menu_content,
             account_attrs.fg,
             account_attrs.bg,
@@ -1877,11 +2801,37 @@ impl Listing {
             None,
         );
 
+        if self.accounts[aidx].collapsed {
+            /* Show the counts of all mailboxes of the account instead */
+            let (unread, total) = lines
+                .iter()
+                .filter_map(|line| line.5)
+                .fold((0, 0), |(u, t), (unread, total)| (u + unread, t + total));
+            let unread_count_att = crate::conf::value(context, "mail.sidebar_unread_count");
+            write_string_to_grid(
+                &format_sidebar_count(count_format, unread, total),
+                &mut self.menu_content,
+                unread_count_att.fg,
+                account_attrs.bg,
+                unread_count_att.attrs | account_attrs.attrs,
+                ((x, get_y(upper_left)), bottom_right),
+                None,
+            );
+            return 0;
+        }
+
         if lines.is_empty() {
+            let account = &context.accounts[self.accounts[aidx].index];
             write_string_to_grid(
-                "offline",
+                if !account.is_connected() {
+                    "not connected"
+                } else if account.is_loading() {
+                    "loading..."
+                } else {
+                    "offline"
+                },
                 &mut self.menu_content,
-                Color::Byte(243),
+                crate::conf::value(context, "mail.listing.offline").fg,
                 account_attrs.bg,
                 account_attrs.attrs,
                 (pos_inc(upper_left, (0, 1)), bottom_right),
@@ -2037,12 +2987,8 @@ impl Listing {
             );
 
             /* Unread message count */
-            let count_string = if let Some(c) = count {
-                if c > 0 {
-                    format!(" {}", c)
-                } else {
-                    String::new()
-                }
+            let count_string = if let Some((unread, total)) = count {
+                format_sidebar_count(count_format, unread, total)
             } else {
                 " ...".to_string()
             };
@@ -2053,7 +2999,7 @@ impl Listing {
                 unread_count_att.fg,
                 unread_count_att.bg,
                 unread_count_att.attrs
-                    | if count.unwrap_or(0) > 0 {
+                    | if count.map(|(unread, _)| unread).unwrap_or(0) > 0 {
                         Attr::BOLD
                     } else {
                         Attr::DEFAULT
@@ -2083,24 +3029,84 @@ impl Listing {
         }
     }
 
+    /// The menu entry drawn on `row` of `menu_content`, if any.
+    fn menu_entry_at(&self, row: usize) -> Option<(usize, MenuEntryCursor)> {
+        let account_idx = self
+            .menu_account_rows
+            .iter()
+            .rposition(|&account_row| account_row <= row)?;
+        match row - self.menu_account_rows[account_idx] {
+            0 => Some((account_idx, MenuEntryCursor::Status)),
+            n if n <= self.accounts[account_idx].visible_entries() => {
+                Some((account_idx, MenuEntryCursor::Mailbox(n - 1)))
+            }
+            _ => None,
+        }
+    }
+
+    /// Fold or unfold the mailboxes of account `idx` in the sidebar, and remember it for the next
+    /// session.
+    fn toggle_account_collapse(&mut self, idx: usize) {
+        self.accounts[idx].collapsed = !self.accounts[idx].collapsed;
+        if self.accounts[idx].collapsed && self.menu_cursor_pos.0 == idx {
+            self.menu_cursor_pos.1 = MenuEntryCursor::Status;
+        }
+        let collapsed_accounts = self
+            .accounts
+            .iter()
+            .filter(|a| a.collapsed)
+            .map(|a| format!("{}\n", a.name))
+            .collect::<String>();
+        if let Err(err) = xdg::BaseDirectories::with_prefix("meli")
+            .map_err(|err| MeliError::new(err.to_string()))
+            .and_then(|data_dir| Ok(data_dir.place_data_file(COLLAPSED_ACCOUNTS_FILE)?))
+            .and_then(|path| Ok(std::fs::write(path, collapsed_accounts)?))
+        {
+            log(
+                format!("Could not save collapsed sidebar accounts: {}", err),
+                ERROR,
+            );
+        }
+        self.menu_content.empty();
+        self.set_dirty(true);
+    }
+
+    /// Apply `action` to the `focused` threads and clear the selection.
+    fn perform_bulk_action(
+        &mut self,
+        focused: SmallVec<[ThreadHash; 8]>,
+        action: &ListingAction,
+        context: &mut Context,
+    ) {
+        self.component.perform_action(context, focused, action);
+        self.component.set_visual_mode(false);
+        let mut row_updates: SmallVec<[ThreadHash; 8]> = SmallVec::new();
+        for (k, v) in self.component.selection().iter_mut() {
+            if *v {
+                *v = false;
+                row_updates.push(*k);
+            }
+        }
+    }
+
+    /// Defer the fetch of the mailbox being left for `next`, if it's still being fetched.
+    fn leave_mailbox(&self, next: (AccountHash, MailboxHash), context: &mut Context) {
+        let (account_hash, mailbox_hash) = self.component.coordinates();
+        if (account_hash, mailbox_hash) != next && context.accounts.contains_key(&account_hash) {
+            context.accounts[&account_hash].defer_fetch(mailbox_hash);
+        }
+    }
+
     fn change_account(&mut self, context: &mut Context) {
         let account_hash = context.accounts[self.cursor_pos.0].hash();
-        self.accounts[self.cursor_pos.0].entries = context.accounts[self.cursor_pos.0]
-            .list_mailboxes()
-            .into_iter()
-            .filter(|mailbox_node| {
-                context.accounts[self.cursor_pos.0][&mailbox_node.hash]
-                    .ref_mailbox
-                    .is_subscribed()
-            })
-            .map(|f| (f.depth, f.indentation, f.has_sibling, f.hash))
-            .collect::<_>();
+        self.accounts[self.cursor_pos.0].entries = sidebar_entries(account_hash, context);
         match self.cursor_pos.1 {
             MenuEntryCursor::Mailbox(idx) => {
                 /* Account might have no mailboxes yet if it's offline */
                 if let Some((_, _, _, mailbox_hash)) =
                     self.accounts[self.cursor_pos.0].entries.get(idx)
                 {
+                    self.leave_mailbox((account_hash, *mailbox_hash), context);
                     self.component
                         .process_event(&mut UIEvent::VisibilityChange(false), context);
                     self.component
@@ -2128,6 +3134,9 @@ impl Listing {
         self.sidebar_divider = *account_settings!(context[account_hash].listing.sidebar_divider);
         self.set_dirty(true);
         self.menu_cursor_pos = self.cursor_pos;
+        if self.accounts[self.cursor_pos.0].collapsed {
+            self.menu_cursor_pos.1 = MenuEntryCursor::Status;
+        }
         /* clear menu to force redraw */
         self.menu_content.empty();
         if *account_settings!(context[account_hash].listing.show_menu_scrollbar) {
@@ -2138,6 +3147,65 @@ impl Listing {
         }
     }
 
+    /// Select the account, mailbox and entry of `session`, saved by a previous run. The mailbox
+    /// and entry are selected once the account has loaded them.
+    pub fn restore_session(&mut self, session: ListingSession, context: &mut Context) {
+        self.pending_session = Some(session);
+        self.apply_pending_session(context);
+    }
+
+    fn apply_pending_session(&mut self, context: &mut Context) {
+        if let Some(session) = self.pending_session.take() {
+            let account_idx = match self.accounts.iter().position(|a| a.name == session.account) {
+                Some(idx) => idx,
+                None => return,
+            };
+            let mailbox_path = match session.mailbox {
+                Some(ref path) => path,
+                None => {
+                    self.cursor_pos = (account_idx, MenuEntryCursor::Status);
+                    self.change_account(context);
+                    return;
+                }
+            };
+            let account = &context.accounts[account_idx];
+            if account.mailbox_entries.is_empty() {
+                /* Mailboxes haven't been fetched yet */
+                self.pending_session = Some(session);
+                return;
+            }
+            let entries = sidebar_entries(account.hash(), context);
+            let entry = entries.iter().position(|(_, _, _, mailbox_hash)| {
+                account
+                    .mailbox_entries
+                    .get(mailbox_hash)
+                    .map(|m| m.ref_mailbox.path() == mailbox_path)
+                    .unwrap_or(false)
+            });
+            let idx = match entry {
+                Some(idx) => idx,
+                None => return,
+            };
+            self.cursor_pos = (account_idx, MenuEntryCursor::Mailbox(idx));
+            self.change_account(context);
+            if session.cursor > 0 {
+                self.pending_cursor = Some((entries[idx].3, session.cursor));
+            }
+        }
+        if let Some((mailbox_hash, cursor)) = self.pending_cursor {
+            let (account_hash, coordinates_mailbox_hash) = self.component.coordinates();
+            if coordinates_mailbox_hash != mailbox_hash {
+                self.pending_cursor = None;
+            } else if context.accounts[&account_hash][&mailbox_hash]
+                .status
+                .is_available()
+            {
+                self.pending_cursor = None;
+                self.component.set_movement(PageMovement::Down(cursor));
+            }
+        }
+    }
+
     fn open_status(&mut self, account_idx: usize, context: &mut Context) {
         self.status = Some(AccountStatus::new(account_idx, self.theme_default));
         self.menu_content.empty();
@@ -2148,3 +3216,67 @@ impl Listing {
             )));
     }
 }
+
+#[test]
+fn test_format_sidebar_count() {
+    assert_eq!(format_sidebar_count(None, 3, 10), " 3");
+    assert_eq!(format_sidebar_count(None, 0, 10), "");
+    assert_eq!(format_sidebar_count(Some("%u/%t"), 0, 10), " 0/10");
+    assert_eq!(format_sidebar_count(Some("%u/%t"), 0, 0), "");
+}
+
+#[test]
+fn test_highlight_matches() {
+    let mut grid = CellBuffer::new(20, 2, Cell::default());
+    write_string_to_grid(
+        "Re: Lunch at noon",
+        &mut grid,
+        Color::Default,
+        Color::Default,
+        Attr::DEFAULT,
+        ((0, 0), (19, 0)),
+        None,
+    );
+    write_string_to_grid(
+        "lunch",
+        &mut grid,
+        Color::Default,
+        Color::Default,
+        Attr::DEFAULT,
+        ((0, 1), (19, 1)),
+        None,
+    );
+    let attr = ThemeAttribute {
+        fg: Color::White,
+        bg: Color::Byte(6),
+        attrs: Attr::BOLD,
+    };
+    highlight_matches(&mut grid, ((0, 0), (19, 0)), "LUNCH", attr);
+    let highlighted = |grid: &CellBuffer, y: usize| {
+        (0..20)
+            .filter(|&x| grid[(x, y)].bg() == Color::Byte(6))
+            .collect::<Vec<usize>>()
+    };
+    assert_eq!(highlighted(&grid, 0), vec![4, 5, 6, 7, 8]);
+    /* Outside of the area */
+    assert!(highlighted(&grid, 1).is_empty());
+}
+
+#[test]
+fn test_bulk_action_question() {
+    assert_eq!(
+        bulk_action_question(&ListingAction::Delete, 3),
+        Some("Delete 3 threads?".to_string())
+    );
+    assert_eq!(
+        bulk_action_question(
+            &ListingAction::Tag(TagAction::Remove("todo".to_string())),
+            2
+        ),
+        Some("Remove tag `todo` from 2 threads?".to_string())
+    );
+    assert_eq!(
+        bulk_action_question(&ListingAction::CopyTo("INBOX/old".to_string()), 2),
+        None
+    );
+}
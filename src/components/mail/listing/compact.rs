@@ -138,6 +138,8 @@ pub struct CompactListing {
     search_job: Option<(String, JoinHandle<Result<SmallVec<[EnvelopeHash; 512]>>>)>,
     select_job: Option<(String, JoinHandle<Result<SmallVec<[EnvelopeHash; 512]>>>)>,
     filter_term: String,
+    /// Term of a search preview to highlight, see `ListingTrait::set_highlight_term`.
+    highlight_term: Option<String>,
     filtered_selection: Vec<ThreadHash>,
     filtered_order: HashMap<ThreadHash, usize>,
     selection: HashMap<ThreadHash, bool>,
@@ -149,10 +151,17 @@ pub struct CompactListing {
     view: ThreadView,
     row_updates: SmallVec<[ThreadHash; 8]>,
     color_cache: ColorCache,
+    /// Parsed `listing.index_format` of the mailbox, if it is set.
+    index_format: Option<IndexFormat>,
 
     movement: Option<PageMovement>,
+    list_area: Area,
+    view_ratio: Option<usize>,
     modifier_active: bool,
     modifier_command: Option<Modifier>,
+    /// Row where visual mode started and the cursor's row when the selection was last
+    /// extended, see `ListingTrait::set_visual_mode`.
+    visual: Option<(usize, usize)>,
     id: ComponentId,
 }
 
@@ -196,6 +205,16 @@ impl MailListingTrait for CompactListing {
         {
             self.cursor_pos.2 = 0;
             self.new_cursor_pos.2 = 0;
+            self.sort = context.accounts[&self.new_cursor_pos.0]
+                .mailbox_sort(self.new_cursor_pos.1)
+                .unwrap_or_else(|| {
+                    let sort = *mailbox_settings!(
+                        context[self.new_cursor_pos.0][&self.new_cursor_pos.1]
+                            .listing
+                            .sort
+                    );
+                    (sort.field, sort.order)
+                });
         }
         self.cursor_pos.1 = self.new_cursor_pos.1;
         self.cursor_pos.0 = self.new_cursor_pos.0;
@@ -222,6 +241,14 @@ impl MailListingTrait for CompactListing {
             self.color_cache.odd_highlighted.attrs |= Attr::REVERSE;
         }
 
+        self.index_format = mailbox_settings!(
+            context[self.cursor_pos.0][&self.cursor_pos.1]
+                .listing
+                .index_format
+        )
+        .as_deref()
+        .map(IndexFormat::new);
+
         // Get mailbox as a reference.
         //
         match context.accounts[&self.cursor_pos.0].load(self.cursor_pos.1) {
@@ -325,6 +352,13 @@ impl MailListingTrait for CompactListing {
 
                 panic!();
             }
+            if is_hidden_duplicate(
+                context,
+                (self.cursor_pos.0, self.cursor_pos.1),
+                root_env_hash,
+            ) {
+                continue;
+            }
             let root_envelope: EnvelopeRef = context.accounts[&self.cursor_pos.0]
                 .collection
                 .get_env(root_env_hash);
@@ -438,8 +472,13 @@ impl ListingTrait for CompactListing {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
 
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
+
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
+        self.visual = None;
         self.unfocused = false;
         self.view = ThreadView::default();
         self.filtered_selection.clear();
@@ -494,6 +533,14 @@ impl ListingTrait for CompactListing {
         for c in grid.row_iter(x..(self.data_columns.widths[3] + x), get_y(upper_left)) {
             grid[c].set_bg(row_attr.bg).set_attrs(row_attr.attrs);
         }
+        if let Some(ref term) = self.highlight_term {
+            highlight_matches(
+                grid,
+                area,
+                term,
+                crate::conf::value(context, "mail.listing.highlight_search"),
+            );
+        }
     }
 
     /// Draw the list of `Envelope`s.
@@ -504,6 +551,7 @@ impl ListingTrait for CompactListing {
         }
         let upper_left = upper_left!(area);
         let bottom_right = bottom_right!(area);
+        self.list_area = area;
         if self.length == 0 {
             clear_area(grid, area, self.color_cache.theme_default);
             copy_area(
@@ -545,6 +593,12 @@ impl ListingTrait for CompactListing {
                     }
                 }
                 PageMovement::Right(_) | PageMovement::Left(_) => {}
+                PageMovement::Row(row) => {
+                    let idx = (self.new_cursor_pos.2 / rows) * rows + row;
+                    if idx < self.length {
+                        self.new_cursor_pos.2 = idx;
+                    }
+                }
                 PageMovement::Home => {
                     self.new_cursor_pos.2 = 0;
                 }
@@ -554,6 +608,8 @@ impl ListingTrait for CompactListing {
             }
         }
 
+        let visual_changed = self.extend_visual_selection(context);
+
         let prev_page_no = (self.cursor_pos.2).wrapping_div(rows);
         let page_no = (self.new_cursor_pos.2).wrapping_div(rows);
 
@@ -580,7 +636,7 @@ impl ListingTrait for CompactListing {
                 self.highlight_line(grid, new_area, *idx, context);
                 context.dirty_areas.push_back(new_area);
             }
-            if !self.force_draw {
+            if !self.force_draw && !visual_changed {
                 return;
             }
         } else if self.cursor_pos != self.new_cursor_pos {
@@ -635,6 +691,9 @@ impl ListingTrait for CompactListing {
             let diff = self.data_columns.widths.iter().sum::<usize>() - width;
             if self.data_columns.widths[2] > 2 * diff {
                 self.data_columns.widths[2] -= diff;
+            } else if self.data_columns.widths[2] == 0 {
+                /* `index_format` may leave the From column out */
+                self.data_columns.widths[4] = self.data_columns.widths[4].saturating_sub(diff);
             } else {
                 self.data_columns.widths[2] = std::cmp::max(
                     15,
@@ -721,6 +780,15 @@ impl ListingTrait for CompactListing {
             );
         }
 
+        if let Some(ref term) = self.highlight_term {
+            highlight_matches(
+                grid,
+                area,
+                term,
+                crate::conf::value(context, "mail.listing.highlight_search"),
+            );
+        }
+
         self.highlight_line(
             grid,
             (
@@ -825,6 +893,10 @@ impl ListingTrait for CompactListing {
         }
     }
 
+    fn set_highlight_term(&mut self, term: Option<String>) {
+        self.highlight_term = term;
+    }
+
     fn unfocused(&self) -> bool {
         self.unfocused
     }
@@ -845,6 +917,36 @@ impl ListingTrait for CompactListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn set_visual_mode(&mut self, active: bool) {
+        self.visual = if active && self.length > 0 {
+            let row = self.new_cursor_pos.2;
+            let thread = self.get_thread_under_cursor(row);
+            self.selection.entry(thread).and_modify(|e| *e = true);
+            self.row_updates.push(thread);
+            Some((row, row))
+        } else {
+            None
+        };
+    }
+
+    fn list_area(&self) -> Option<Area> {
+        if self.unfocused {
+            None
+        } else {
+            Some(self.list_area)
+        }
+    }
+
+    fn adjust_view_ratio(&mut self, delta: isize, context: &Context) {
+        adjust_view_ratio(
+            &mut self.view_ratio,
+            delta,
+            (self.new_cursor_pos.0, self.new_cursor_pos.1),
+            context,
+        );
+        self.set_dirty(true);
+    }
 }
 
 impl fmt::Display for CompactListing {
@@ -867,6 +969,7 @@ impl CompactListing {
             search_job: None,
             select_job: None,
             filter_term: String::new(),
+            highlight_term: None,
             filtered_selection: Vec::new(),
             filtered_order: HashMap::default(),
             selection: HashMap::default(),
@@ -879,9 +982,13 @@ impl CompactListing {
             unfocused: false,
             view: ThreadView::default(),
             color_cache: ColorCache::default(),
+            index_format: None,
             movement: None,
+            list_area: ((0, 0), (0, 0)),
+            view_ratio: None,
             modifier_active: false,
             modifier_command: None,
+            visual: None,
             id: ComponentId::new_v4(),
         }
     }
@@ -896,7 +1003,7 @@ impl CompactListing {
         let mut tags = String::new();
         let mut colors: SmallVec<[_; 8]> = SmallVec::new();
         let account = &context.accounts[&self.cursor_pos.0];
-        if account.backend_capabilities.supports_tags {
+        if account.backend_capabilities.tag_support.can_set() {
             let tags_lck = account.collection.tag_index.read().unwrap();
             for t in e.labels().iter() {
                 if mailbox_settings!(
@@ -932,14 +1039,16 @@ impl CompactListing {
         }
         let mut subject = e.subject().to_string();
         subject.truncate_at_boundary(150);
-        if thread.len() > 1 {
+        let entry_strings = if thread.len() > 1 {
             EntryStrings {
                 date: DateString(ConversationsListing::format_date(context, thread.date())),
                 subject: SubjectString(format!("{} ({})", subject, thread.len(),)),
                 flag: FlagString(format!(
-                    "{}{}",
+                    "{}{}{}{}",
                     if thread.has_attachments() { "📎" } else { "" },
-                    if thread.snoozed() { "💤" } else { "" }
+                    if thread.snoozed() { "💤" } else { "" },
+                    mailing_list_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), &e),
+                    duplicate_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), e)
                 )),
                 from: FromString(address_list!((e.from()) as comma_sep_list)),
                 tags: TagString(tags, colors),
@@ -949,14 +1058,25 @@ impl CompactListing {
                 date: DateString(ConversationsListing::format_date(context, thread.date())),
                 subject: SubjectString(subject),
                 flag: FlagString(format!(
-                    "{}{}",
+                    "{}{}{}{}",
                     if thread.has_attachments() { "📎" } else { "" },
-                    if thread.snoozed() { "💤" } else { "" }
+                    if thread.snoozed() { "💤" } else { "" },
+                    mailing_list_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), &e),
+                    duplicate_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), e)
                 )),
                 from: FromString(address_list!((e.from()) as comma_sep_list)),
                 tags: TagString(tags, colors),
             }
+        };
+        if let Some(ref index_format) = self.index_format {
+            return entry_strings.with_index_format(
+                index_format,
+                e,
+                thread.unseen() > 0,
+                thread.has_attachments(),
+            );
         }
+        entry_strings
     }
 
     fn get_thread_under_cursor(&self, cursor: usize) -> ThreadHash {
@@ -975,6 +1095,32 @@ impl CompactListing {
         }
     }
 
+    /// Select exactly the entries between the start of visual mode and the cursor. Returns
+    /// whether the selection changed.
+    fn extend_visual_selection(&mut self, context: &Context) -> bool {
+        let (anchor, prev) = match self.visual {
+            Some(v) => v,
+            None => return false,
+        };
+        let cursor = std::cmp::min(self.new_cursor_pos.2, self.length.saturating_sub(1));
+        if cursor == prev {
+            return false;
+        }
+        self.visual = Some((anchor, cursor));
+        let (start, end) = (std::cmp::min(anchor, cursor), std::cmp::max(anchor, cursor));
+        let mut changed = false;
+        for row in std::cmp::min(start, prev)..=std::cmp::max(end, prev) {
+            let thread = self.get_thread_under_cursor(row);
+            let selected = row >= start && row <= end;
+            if self.selection.get(&thread) != Some(&selected) {
+                self.selection.insert(thread, selected);
+                self.update_line(context, thread);
+                changed = true;
+            }
+        }
+        changed
+    }
+
     fn update_line(&mut self, context: &Context, thread_hash: ThreadHash) {
         let account = &context.accounts[&self.cursor_pos.0];
         let threads = account.collection.get_threads(self.cursor_pos.1);
@@ -1099,6 +1245,8 @@ impl CompactListing {
                 columns[4][c].set_bg(row_attr.bg);
             }
             match (thread.snoozed(), thread.has_attachments()) {
+                /* `index_format` may put other flags in the column */
+                _ if self.index_format.is_some() => {}
                 (true, true) => {
                     columns[3][(0, idx)].set_fg(self.color_cache.attachment_flag.fg);
                     columns[3][(2, idx)].set_fg(self.color_cache.thread_snooze_flag.fg);
@@ -1283,6 +1431,8 @@ impl CompactListing {
                     .set_attrs(row_attr.attrs);
             }
             match (thread.snoozed(), thread.has_attachments()) {
+                /* `index_format` may put other flags in the column */
+                _ if self.index_format.is_some() => {}
                 (true, true) => {
                     self.data_columns.columns[3][(0, idx)]
                         .set_fg(self.color_cache.attachment_flag.fg);
@@ -1509,6 +1659,7 @@ impl Component for CompactListing {
                             }
                         }
                         PageMovement::Right(_) | PageMovement::Left(_) => {}
+                        PageMovement::Row(_) => {}
                         PageMovement::Home => {
                             for c in 0..=self.new_cursor_pos.2 {
                                 let thread = self.get_thread_under_cursor(c);
@@ -1596,7 +1747,18 @@ impl Component for CompactListing {
                 return;
             }
 
-            self.view.draw(grid, area, context);
+            let (list_area, view_area) = split_view_area(
+                grid,
+                area,
+                (self.new_cursor_pos.0, self.new_cursor_pos.1),
+                ViewLayout::FullScreen,
+                self.view_ratio,
+                context,
+            );
+            if let Some(list_area) = list_area {
+                self.draw_list(grid, list_area, context);
+            }
+            self.view.draw(grid, view_area, context);
         }
         self.dirty = false;
     }
@@ -1649,11 +1811,37 @@ impl Component for CompactListing {
                     }
                     return true;
                 }
+                UIEvent::Input(ref key)
+                    if !self.unfocused
+                        && shortcut!(key == shortcuts[Listing::DESCRIPTION]["visual_mode"]) =>
+                {
+                    let active = self.visual.is_none();
+                    self.set_visual_mode(active);
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            if active {
+                                "Visual selection: move to select entries, press again or Esc to stop."
+                                    .to_string()
+                            } else {
+                                "Visual selection stopped.".to_string()
+                            },
+                        )));
+                    self.dirty = true;
+                    return true;
+                }
+                UIEvent::Input(Key::Esc) if !self.unfocused && self.visual.is_some() => {
+                    /* Keep the selection to apply an action to it. */
+                    self.visual = None;
+                    return true;
+                }
                 UIEvent::Action(ref action) => {
                     match action {
                         Action::Sort(field, order) if !self.unfocused => {
                             debug!("Sort {:?} , {:?}", field, order);
                             self.sort = (*field, *order);
+                            context.accounts[&self.cursor_pos.0]
+                                .set_mailbox_sort(self.cursor_pos.1, self.sort);
                             if !self.filtered_selection.is_empty() {
                                 // FIXME: perform sort
                                 self.dirty = true;
@@ -1816,7 +2004,14 @@ impl Component for CompactListing {
                 self.set_dirty(true);
                 return true;
             }
-            UIEvent::Action(Action::Listing(Search(ref filter_term))) if !self.unfocused => {
+            UIEvent::Action(Action::Listing(Search(ref filter_term))) => {
+                if self.unfocused {
+                    /* Searching from the opened entry, eg. with `list-filter` */
+                    self.unfocused = false;
+                    self.view
+                        .process_event(&mut UIEvent::VisibilityChange(false), context);
+                    self.force_draw = true;
+                }
                 match context.accounts[&self.cursor_pos.0].search(
                     filter_term,
                     self.sort,
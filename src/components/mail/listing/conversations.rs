@@ -105,7 +105,10 @@ pub struct ConversationsListing {
     content: CellBuffer,
 
     search_job: Option<(String, JoinHandle<Result<SmallVec<[EnvelopeHash; 512]>>>)>,
+    select_job: Option<(String, JoinHandle<Result<SmallVec<[EnvelopeHash; 512]>>>)>,
     filter_term: String,
+    /// Term of a search preview to highlight, see `ListingTrait::set_highlight_term`.
+    highlight_term: Option<String>,
     filtered_selection: Vec<ThreadHash>,
     filtered_order: HashMap<ThreadHash, usize>,
     selection: HashMap<ThreadHash, bool>,
@@ -119,8 +122,13 @@ pub struct ConversationsListing {
     color_cache: ColorCache,
 
     movement: Option<PageMovement>,
+    list_area: Area,
+    view_ratio: Option<usize>,
     modifier_active: bool,
     modifier_command: Option<Modifier>,
+    /// Row where visual mode started and the cursor's row when the selection was last
+    /// extended, see `ListingTrait::set_visual_mode`.
+    visual: Option<(usize, usize)>,
     id: ComponentId,
 }
 
@@ -163,6 +171,16 @@ impl MailListingTrait for ConversationsListing {
         {
             self.cursor_pos.2 = 0;
             self.new_cursor_pos.2 = 0;
+            self.sort = context.accounts[&self.new_cursor_pos.0]
+                .mailbox_sort(self.new_cursor_pos.1)
+                .unwrap_or_else(|| {
+                    let sort = *mailbox_settings!(
+                        context[self.new_cursor_pos.0][&self.new_cursor_pos.1]
+                            .listing
+                            .sort
+                    );
+                    (sort.field, sort.order)
+                });
         }
         self.cursor_pos.1 = self.new_cursor_pos.1;
         self.cursor_pos.0 = self.new_cursor_pos.0;
@@ -304,6 +322,13 @@ impl MailListingTrait for ConversationsListing {
                     from_address_list.push(addr.clone());
                 }
             }
+            if is_hidden_duplicate(
+                context,
+                (self.cursor_pos.0, self.cursor_pos.1),
+                root_env_hash,
+            ) {
+                continue;
+            }
             let root_envelope: &EnvelopeRef = &context.accounts[&self.cursor_pos.0]
                 .collection
                 .get_env(root_env_hash);
@@ -498,8 +523,13 @@ impl ListingTrait for ConversationsListing {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
 
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
+
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
+        self.visual = None;
         self.unfocused = false;
         self.view = ThreadView::default();
         self.filtered_selection.clear();
@@ -577,6 +607,14 @@ impl ListingTrait for ConversationsListing {
                     .set_attrs(row_attr.attrs);
             }
         }
+        if let Some(ref term) = self.highlight_term {
+            highlight_matches(
+                grid,
+                area,
+                term,
+                crate::conf::value(context, "mail.listing.highlight_search"),
+            );
+        }
     }
 
     /// Draw the list of `Envelope`s.
@@ -587,6 +625,7 @@ impl ListingTrait for ConversationsListing {
         }
         let upper_left = upper_left!(area);
         let bottom_right = bottom_right!(area);
+        self.list_area = area;
         if self.length == 0 {
             clear_area(grid, area, self.color_cache.theme_default);
             copy_area(
@@ -629,6 +668,13 @@ impl ListingTrait for ConversationsListing {
                     }
                 }
                 PageMovement::Right(_) | PageMovement::Left(_) => {}
+                PageMovement::Row(row) => {
+                    /* Each entry takes three rows. */
+                    let idx = (self.new_cursor_pos.2 / rows) * rows + row / 3;
+                    if idx < self.length {
+                        self.new_cursor_pos.2 = idx;
+                    }
+                }
                 PageMovement::Home => {
                     self.new_cursor_pos.2 = 0;
                 }
@@ -638,6 +684,8 @@ impl ListingTrait for ConversationsListing {
             }
         }
 
+        let visual_changed = self.extend_visual_selection(context);
+
         let prev_page_no = (self.cursor_pos.2).wrapping_div(rows);
         let page_no = (self.new_cursor_pos.2).wrapping_div(rows);
 
@@ -659,7 +707,9 @@ impl ListingTrait for ConversationsListing {
                 self.highlight_line(grid, new_area, *idx, context);
                 context.dirty_areas.push_back(new_area);
             }
-            return;
+            if !visual_changed {
+                return;
+            }
         } else if self.cursor_pos != self.new_cursor_pos {
             self.cursor_pos = self.new_cursor_pos;
         }
@@ -686,6 +736,15 @@ impl ListingTrait for ConversationsListing {
             ((0, 3 * top_idx), pos_dec(self.content.size(), (1, 1))),
         );
 
+        if let Some(ref term) = self.highlight_term {
+            highlight_matches(
+                grid,
+                area,
+                term,
+                crate::conf::value(context, "mail.listing.highlight_search"),
+            );
+        }
+
         self.highlight_line(
             grid,
             (
@@ -840,6 +899,10 @@ impl ListingTrait for ConversationsListing {
         }
     }
 
+    fn set_highlight_term(&mut self, term: Option<String>) {
+        self.highlight_term = term;
+    }
+
     fn unfocused(&self) -> bool {
         self.unfocused
     }
@@ -860,6 +923,36 @@ impl ListingTrait for ConversationsListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn set_visual_mode(&mut self, active: bool) {
+        self.visual = if active && self.length > 0 {
+            let row = self.new_cursor_pos.2;
+            let thread = self.get_thread_under_cursor(row);
+            self.selection.entry(thread).and_modify(|e| *e = true);
+            self.row_updates.push(thread);
+            Some((row, row))
+        } else {
+            None
+        };
+    }
+
+    fn list_area(&self) -> Option<Area> {
+        if self.unfocused {
+            None
+        } else {
+            Some(self.list_area)
+        }
+    }
+
+    fn adjust_view_ratio(&mut self, delta: isize, context: &Context) {
+        adjust_view_ratio(
+            &mut self.view_ratio,
+            delta,
+            (self.new_cursor_pos.0, self.new_cursor_pos.1),
+            context,
+        );
+        self.set_dirty(true);
+    }
 }
 
 impl fmt::Display for ConversationsListing {
@@ -880,7 +973,9 @@ impl ConversationsListing {
             order: HashMap::default(),
             all_threads: HashSet::default(),
             search_job: None,
+            select_job: None,
             filter_term: String::new(),
+            highlight_term: None,
             filtered_selection: Vec::new(),
             filtered_order: HashMap::default(),
             selection: HashMap::default(),
@@ -892,8 +987,11 @@ impl ConversationsListing {
             view: ThreadView::default(),
             color_cache: ColorCache::default(),
             movement: None,
+            list_area: ((0, 0), (0, 0)),
+            view_ratio: None,
             modifier_active: false,
             modifier_command: None,
+            visual: None,
             id: ComponentId::new_v4(),
         }
     }
@@ -909,7 +1007,7 @@ impl ConversationsListing {
         let mut tags = String::new();
         let mut colors = SmallVec::new();
         let account = &context.accounts[&self.cursor_pos.0];
-        if account.backend_capabilities.supports_tags {
+        if account.backend_capabilities.tag_support.can_set() {
             let tags_lck = account.collection.tag_index.read().unwrap();
             for t in e.labels().iter() {
                 if mailbox_settings!(
@@ -950,9 +1048,11 @@ impl ConversationsListing {
                 date: DateString(ConversationsListing::format_date(context, thread.date())),
                 subject: SubjectString(format!("{} ({})", subject, thread.len())),
                 flag: FlagString(format!(
-                    "{}{}",
+                    "{}{}{}{}",
                     if thread.has_attachments() { "📎" } else { "" },
-                    if thread.snoozed() { "💤" } else { "" }
+                    if thread.snoozed() { "💤" } else { "" },
+                    mailing_list_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), &e),
+                    duplicate_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), e)
                 )),
                 from: FromString(address_list!((from) as comma_sep_list)),
                 tags: TagString(tags, colors),
@@ -962,9 +1062,11 @@ impl ConversationsListing {
                 date: DateString(ConversationsListing::format_date(context, thread.date())),
                 subject: SubjectString(subject),
                 flag: FlagString(format!(
-                    "{}{}",
+                    "{}{}{}{}",
                     if thread.has_attachments() { "📎" } else { "" },
-                    if thread.snoozed() { "💤" } else { "" }
+                    if thread.snoozed() { "💤" } else { "" },
+                    mailing_list_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), &e),
+                    duplicate_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), e)
                 )),
                 from: FromString(address_list!((from) as comma_sep_list)),
                 tags: TagString(tags, colors),
@@ -973,38 +1075,11 @@ impl ConversationsListing {
     }
 
     pub(super) fn format_date(context: &Context, epoch: UnixTimestamp) -> String {
-        let d = std::time::UNIX_EPOCH + std::time::Duration::from_secs(epoch);
-        let now: std::time::Duration = std::time::SystemTime::now()
-            .duration_since(d)
-            .unwrap_or_else(|_| std::time::Duration::new(std::u64::MAX, 0));
-        match now.as_secs() {
-            n if context.settings.listing.recent_dates && n < 60 * 60 => format!(
-                "{} minute{} ago",
-                n / (60),
-                if n / 60 == 1 { "" } else { "s" }
-            ),
-            n if context.settings.listing.recent_dates && n < 24 * 60 * 60 => format!(
-                "{} hour{} ago",
-                n / (60 * 60),
-                if n / (60 * 60) == 1 { "" } else { "s" }
-            ),
-            n if context.settings.listing.recent_dates && n < 7 * 24 * 60 * 60 => format!(
-                "{} day{} ago",
-                n / (24 * 60 * 60),
-                if n / (24 * 60 * 60) == 1 { "" } else { "s" }
-            ),
-            _ => melib::datetime::timestamp_to_string(
-                epoch,
-                context
-                    .settings
-                    .listing
-                    .datetime_fmt
-                    .as_ref()
-                    .map(String::as_str)
-                    .or(Some("%Y-%m-%d %T")),
-                false,
-            ),
-        }
+        crate::dates::format_date(
+            epoch,
+            context.settings.listing.datetime_fmt.as_deref(),
+            context.settings.listing.recent_dates,
+        )
     }
 
     fn get_thread_under_cursor(&self, cursor: usize) -> ThreadHash {
@@ -1023,6 +1098,76 @@ impl ConversationsListing {
         }
     }
 
+    fn select(
+        &mut self,
+        search_term: &str,
+        results: Result<SmallVec<[EnvelopeHash; 512]>>,
+        context: &mut Context,
+    ) {
+        let account = &context.accounts[&self.cursor_pos.0];
+        match results {
+            Ok(results) => {
+                let threads = account.collection.get_threads(self.cursor_pos.1);
+                for env_hash in results {
+                    if !account.collection.contains_key(&env_hash) {
+                        continue;
+                    }
+                    let env_thread_node_hash = account.collection.get_env(env_hash).thread();
+                    if !threads.thread_nodes.contains_key(&env_thread_node_hash) {
+                        continue;
+                    }
+                    let thread =
+                        threads.find_group(threads.thread_nodes[&env_thread_node_hash].group);
+                    if self.all_threads.contains(&thread) {
+                        self.selection
+                            .entry(thread)
+                            .and_modify(|entry| *entry = true);
+                    }
+                }
+            }
+            Err(err) => {
+                self.cursor_pos.2 = 0;
+                self.new_cursor_pos.2 = 0;
+                let message = format!(
+                    "Encountered an error while searching for `{}`: {}.",
+                    search_term, &err
+                );
+                log(message.clone(), ERROR);
+                context.replies.push_back(UIEvent::Notification(
+                    Some("Could not perform search".to_string()),
+                    message,
+                    Some(crate::types::NotificationType::Error(err.kind)),
+                ));
+            }
+        }
+    }
+
+    /// Select exactly the entries between the start of visual mode and the cursor. Returns
+    /// whether the selection changed.
+    fn extend_visual_selection(&mut self, context: &Context) -> bool {
+        let (anchor, prev) = match self.visual {
+            Some(v) => v,
+            None => return false,
+        };
+        let cursor = std::cmp::min(self.new_cursor_pos.2, self.length.saturating_sub(1));
+        if cursor == prev {
+            return false;
+        }
+        self.visual = Some((anchor, cursor));
+        let (start, end) = (std::cmp::min(anchor, cursor), std::cmp::max(anchor, cursor));
+        let mut changed = false;
+        for row in std::cmp::min(start, prev)..=std::cmp::max(end, prev) {
+            let thread = self.get_thread_under_cursor(row);
+            let selected = row >= start && row <= end;
+            if self.selection.get(&thread) != Some(&selected) {
+                self.selection.insert(thread, selected);
+                self.update_line(context, thread);
+                changed = true;
+            }
+        }
+        changed
+    }
+
     fn update_line(&mut self, context: &Context, thread_hash: ThreadHash) {
         let account = &context.accounts[&self.cursor_pos.0];
         let threads = account.collection.get_threads(self.cursor_pos.1);
@@ -1195,29 +1340,20 @@ impl Component for ConversationsListing {
         if !self.is_dirty() {
             return;
         }
-        let (upper_left, bottom_right) = area;
-        {
-            let mut area = if self.unfocused {
-                clear_area(
-                    grid,
-                    (
-                        pos_inc(upper_left, (width!(area) / 3, 0)),
-                        set_x(bottom_right, get_x(upper_left) + width!(area) / 3 + 1),
-                    ),
-                    self.color_cache.theme_default,
-                );
-                context.dirty_areas.push_back((
-                    pos_inc(upper_left, (width!(area) / 3, 0)),
-                    set_x(bottom_right, get_x(upper_left) + width!(area) / 3 + 1),
-                ));
-                (
-                    upper_left,
-                    set_x(bottom_right, get_x(upper_left) + width!(area) / 3 - 1),
-                )
-            } else {
-                area
-            };
-
+        let (list_area, view_area) = if self.unfocused {
+            split_view_area(
+                grid,
+                area,
+                (self.new_cursor_pos.0, self.new_cursor_pos.1),
+                ViewLayout::Right,
+                self.view_ratio,
+                context,
+            )
+        } else {
+            (Some(area), area)
+        };
+        if let Some(mut area) = list_area {
+            let (upper_left, bottom_right) = area;
             if !self.filter_term.is_empty() {
                 let (x, y) = write_string_to_grid(
                     &format!(
@@ -1366,6 +1502,7 @@ impl Component for ConversationsListing {
                             }
                         }
                         PageMovement::Right(_) | PageMovement::Left(_) => {}
+                        PageMovement::Row(_) => {}
                         PageMovement::Home => {
                             for c in 0..=self.new_cursor_pos.2 {
                                 let thread = self.get_thread_under_cursor(c);
@@ -1456,11 +1593,7 @@ impl Component for ConversationsListing {
                 return;
             }
 
-            let area = (
-                set_x(upper_left, get_x(upper_left) + width!(area) / 3 + 2),
-                bottom_right,
-            );
-            self.view.draw(grid, area, context);
+            self.view.draw(grid, view_area, context);
         }
         self.dirty = false;
     }
@@ -1513,6 +1646,30 @@ impl Component for ConversationsListing {
                     }
                     return true;
                 }
+                UIEvent::Input(ref key)
+                    if !self.unfocused
+                        && shortcut!(key == shortcuts[Listing::DESCRIPTION]["visual_mode"]) =>
+                {
+                    let active = self.visual.is_none();
+                    self.set_visual_mode(active);
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            if active {
+                                "Visual selection: move to select entries, press again or Esc to stop."
+                                    .to_string()
+                            } else {
+                                "Visual selection stopped.".to_string()
+                            },
+                        )));
+                    self.dirty = true;
+                    return true;
+                }
+                UIEvent::Input(Key::Esc) if !self.unfocused && self.visual.is_some() => {
+                    /* Keep the selection to apply an action to it. */
+                    self.visual = None;
+                    return true;
+                }
                 UIEvent::EnvelopeRename(ref old_hash, ref new_hash) => {
                     let account = &context.accounts[&self.cursor_pos.0];
                     let threads = account.collection.get_threads(self.cursor_pos.1);
@@ -1587,6 +1744,8 @@ impl Component for ConversationsListing {
                         // FIXME sort
                         /*
                         self.sort = (*field, *order);
+                        context.accounts[&self.cursor_pos.0]
+                            .set_mailbox_sort(self.cursor_pos.1, self.sort);
                         if !self.filtered_selection.is_empty() {
                             let threads = &context.accounts[&self.cursor_pos.0].collection.threads
                                 [&self.cursor_pos.1];
@@ -1670,7 +1829,14 @@ impl Component for ConversationsListing {
                 self.dirty = true;
             }
             UIEvent::Action(ref action) => match action {
-                Action::Listing(Search(ref filter_term)) if !self.unfocused => {
+                Action::Listing(Search(ref filter_term)) => {
+                    if self.unfocused {
+                        /* Searching from the opened entry, eg. with `list-filter` */
+                        self.unfocused = false;
+                        self.view
+                            .process_event(&mut UIEvent::VisibilityChange(false), context);
+                        self.force_draw = true;
+                    }
                     match context.accounts[&self.cursor_pos.0].search(
                         filter_term,
                         self.sort,
@@ -1693,6 +1859,29 @@ impl Component for ConversationsListing {
                     self.set_dirty(true);
                     return true;
                 }
+                Action::Listing(Select(ref search_term)) if !self.unfocused => {
+                    match context.accounts[&self.cursor_pos.0].search(
+                        search_term,
+                        self.sort,
+                        self.cursor_pos.1,
+                    ) {
+                        Ok(job) => {
+                            let handle = context.accounts[&self.cursor_pos.0]
+                                .job_executor
+                                .spawn_specialized(job);
+                            self.select_job = Some((search_term.to_string(), handle));
+                        }
+                        Err(err) => {
+                            context.replies.push_back(UIEvent::Notification(
+                                Some("Could not perform search".to_string()),
+                                err.to_string(),
+                                Some(crate::types::NotificationType::Error(err.kind)),
+                            ));
+                        }
+                    };
+                    self.set_dirty(true);
+                    return true;
+                }
                 _ => {}
             },
             UIEvent::Input(Key::Esc)
@@ -1731,6 +1920,21 @@ impl Component for ConversationsListing {
                 }
                 self.set_dirty(true);
             }
+            UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
+                if self
+                    .select_job
+                    .as_ref()
+                    .map(|(_, j)| j == job_id)
+                    .unwrap_or(false) =>
+            {
+                let (search_term, mut handle) = self.select_job.take().unwrap();
+                match handle.chan.try_recv() {
+                    Err(_) => { /* search was canceled */ }
+                    Ok(None) => { /* something happened, perhaps a worker thread panicked */ }
+                    Ok(Some(results)) => self.select(&search_term, results, context),
+                }
+                self.set_dirty(true);
+            }
             _ => {}
         }
 
@@ -0,0 +1,353 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Mutt-like format strings for listing rows, set with `listing.index_format`.
+ *
+ * Each `%` sequence is `%[-][WIDTH]FIELD`. With a width the value is truncated or padded to that
+ * many columns, aligned to the right unless `-` is given.
+ *
+ * Each field is shown in its listing column, so that columns stay aligned and keep their colors.
+ * Literal text is shown with the field that follows it, or with the last field if there is none,
+ * without the whitespace that starts a column.
+ * Tags are shown with their colors in the tags column, without literal text or width.
+ */
+
+use super::{DateString, EntryStrings, FlagString, FromString, SubjectString, TagString};
+use melib::datetime::UnixTimestamp;
+use melib::text_processing::TextProcessing;
+use melib::{Envelope, Flag};
+use smallvec::SmallVec;
+
+#[derive(Debug, Clone, PartialEq)]
+enum Field {
+    /// `%Z`
+    Flags,
+    /// `%d`
+    Date,
+    /// `%{FORMAT}`
+    DateFormat(String),
+    /// `%a`
+    Author,
+    /// `%s`
+    Subject,
+    /// `%g`
+    Tags,
+    /// `%c`
+    Size,
+    /// `%X`
+    Attachments,
+    /// `%L`
+    List,
+}
+
+impl Field {
+    /// The listing column the field is shown in, as an index of [`IndexColumns`].
+    fn column(&self) -> Option<usize> {
+        match self {
+            Field::Date | Field::DateFormat(_) | Field::Size => Some(0),
+            Field::Author => Some(1),
+            Field::Flags | Field::Attachments => Some(2),
+            Field::Subject | Field::List => Some(3),
+            Field::Tags => None,
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq)]
+enum Segment {
+    Literal(String),
+    Field {
+        field: Field,
+        width: Option<usize>,
+        left_align: bool,
+    },
+}
+
+/// The values of a listing row.
+pub struct IndexFields<'a> {
+    pub flags: Flag,
+    pub unseen: bool,
+    pub date: &'a str,
+    pub timestamp: UnixTimestamp,
+    pub author: &'a str,
+    pub subject: &'a str,
+    pub size: Option<usize>,
+    pub has_attachments: bool,
+    pub list_id: Option<&'a str>,
+}
+
+/// The date, author, flags and subject columns of a listing row, and whether it shows tags.
+#[derive(Debug, Default, PartialEq)]
+pub struct IndexColumns(pub [String; 4], pub bool);
+
+#[derive(Debug, Clone, PartialEq)]
+pub struct IndexFormat(Vec<Segment>);
+
+impl IndexFormat {
+    pub fn new(format: &str) -> Self {
+        let mut segments = vec![];
+        let mut literal = String::new();
+        let mut chars = format.chars().peekable();
+        while let Some(c) = chars.next() {
+            if c != '%' {
+                literal.push(c);
+                continue;
+            }
+            let mut spec = String::from("%");
+            let left_align = chars.peek() == Some(&'-');
+            if left_align {
+                spec.push(chars.next().unwrap());
+            }
+            let mut width = String::new();
+            while let Some(d) = chars.peek().filter(|d| d.is_ascii_digit()) {
+                width.push(*d);
+                spec.push(chars.next().unwrap());
+            }
+            let field = match chars.next() {
+                Some('%') if spec == "%" => {
+                    literal.push('%');
+                    continue;
+                }
+                Some('Z') => Field::Flags,
+                Some('d') => Field::Date,
+                Some('a') => Field::Author,
+                Some('s') => Field::Subject,
+                Some('g') => Field::Tags,
+                Some('c') => Field::Size,
+                Some('X') => Field::Attachments,
+                Some('L') => Field::List,
+                Some('{') => {
+                    let mut date_format = String::new();
+                    let mut closed = false;
+                    for d in chars.by_ref() {
+                        if d == '}' {
+                            closed = true;
+                            break;
+                        }
+                        date_format.push(d);
+                    }
+                    if !closed {
+                        /* Unterminated format, show it as is */
+                        literal.push_str(&spec);
+                        literal.push('{');
+                        literal.push_str(&date_format);
+                        continue;
+                    }
+                    Field::DateFormat(date_format)
+                }
+                other => {
+                    /* Unknown fields are shown as is */
+                    literal.push_str(&spec);
+                    literal.extend(other);
+                    continue;
+                }
+            };
+            if !literal.is_empty() {
+                segments.push(Segment::Literal(std::mem::take(&mut literal)));
+            }
+            segments.push(Segment::Field {
+                field,
+                width: width.parse::<usize>().ok(),
+                left_align,
+            });
+        }
+        if !literal.is_empty() {
+            segments.push(Segment::Literal(literal));
+        }
+        IndexFormat(segments)
+    }
+
+    pub fn render(&self, fields: &IndexFields) -> IndexColumns {
+        let mut ret = IndexColumns::default();
+        let mut literal = String::new();
+        let mut last_column = None;
+        for segment in &self.0 {
+            let (field, width, left_align) = match segment {
+                Segment::Literal(ref s) => {
+                    literal.push_str(s);
+                    continue;
+                }
+                Segment::Field {
+                    ref field,
+                    width,
+                    left_align,
+                } => (field, width, left_align),
+            };
+            let column = match field.column() {
+                Some(column) => column,
+                None => {
+                    ret.1 = true;
+                    continue;
+                }
+            };
+            let value = match field {
+                Field::Flags => flags_string(fields.flags, fields.unseen),
+                Field::Date => fields.date.trim().to_string(),
+                Field::DateFormat(ref fmt) => {
+                    melib::datetime::timestamp_to_string(fields.timestamp, Some(fmt), false)
+                }
+                Field::Author => fields.author.to_string(),
+                Field::Subject => fields.subject.to_string(),
+                Field::Size => fields.size.map(size_string).unwrap_or_default(),
+                Field::Attachments => {
+                    if fields.has_attachments {
+                        "📎".to_string()
+                    } else {
+                        String::new()
+                    }
+                }
+                Field::List => fields.list_id.unwrap_or_default().to_string(),
+                Field::Tags => unreachable!(),
+            };
+            if ret.0[column].is_empty() {
+                ret.0[column].push_str(literal.trim_start());
+            } else {
+                ret.0[column].push_str(&literal);
+            }
+            literal.clear();
+            match width {
+                Some(width) => ret.0[column].push_str(&pad(&value, *width, *left_align)),
+                None => ret.0[column].push_str(&value),
+            }
+            last_column = Some(column);
+        }
+        ret.0[last_column.unwrap_or(3)].push_str(&literal);
+        ret
+    }
+}
+
+impl EntryStrings {
+    /// Replace the columns of a row with the ones formatted with `index_format`. `unseen` and
+    /// `has_attachments` are given separately since they may describe a whole thread.
+    pub(super) fn with_index_format(
+        self,
+        index_format: &IndexFormat,
+        e: &Envelope,
+        unseen: bool,
+        has_attachments: bool,
+    ) -> EntryStrings {
+        let IndexColumns([date, from, flag, subject], show_tags) =
+            index_format.render(&IndexFields {
+                flags: e.flags(),
+                unseen,
+                date: &self.date,
+                timestamp: e.date(),
+                author: &self.from,
+                subject: &self.subject,
+                size: e.size,
+                has_attachments,
+                list_id: e.list_id(),
+            });
+        EntryStrings {
+            date: DateString(date),
+            subject: SubjectString(subject),
+            flag: FlagString(flag),
+            from: FromString(from),
+            tags: if show_tags {
+                self.tags
+            } else {
+                TagString(String::new(), SmallVec::new())
+            },
+        }
+    }
+}
+
+/// `N` for unseen, `r` for replied, `f` for forwarded, `!` for flagged, `D` for drafts and `d`
+/// for trashed messages.
+fn flags_string(flags: Flag, unseen: bool) -> String {
+    let mut ret = String::new();
+    for (is_set, c) in &[
+        (unseen, 'N'),
+        (flags.is_replied(), 'r'),
+        (flags.is_passed(), 'f'),
+        (flags.is_flagged(), '!'),
+        (flags.is_draft(), 'D'),
+        (flags.is_trashed(), 'd'),
+    ] {
+        if *is_set {
+            ret.push(*c);
+        }
+    }
+    ret
+}
+
+fn size_string(size: usize) -> String {
+    if size < 1024 {
+        size.to_string()
+    } else if size < 1024 * 1024 {
+        format!("{:.1}K", size as f64 / 1024.0)
+    } else {
+        format!("{:.1}M", size as f64 / (1024.0 * 1024.0))
+    }
+}
+
+fn pad(value: &str, width: usize, left_align: bool) -> String {
+    let mut ret = String::with_capacity(width);
+    let mut ret_width = 0;
+    for g in value.split_graphemes() {
+        let g_width = g.grapheme_width();
+        if ret_width + g_width > width {
+            break;
+        }
+        ret.push_str(g);
+        ret_width += g_width;
+    }
+    let padding = " ".repeat(width - ret_width);
+    if left_align {
+        ret.push_str(&padding);
+        ret
+    } else {
+        padding + &ret
+    }
+}
+
+#[test]
+fn test_index_format() {
+    let format = IndexFormat::new("%-3Z %4c %-8a|%s%% %g %q %X");
+    let fields = IndexFields {
+        flags: Flag::REPLIED | Flag::SEEN,
+        unseen: true,
+        date: "2020-10-01 ",
+        timestamp: 0,
+        author: "Manos Pitsidianakis",
+        subject: "Hello",
+        size: Some(2048),
+        has_attachments: true,
+        list_id: Some("meli.example.com"),
+    };
+    assert_eq!(
+        format.render(&fields),
+        IndexColumns(
+            [
+                "2.0K".to_string(),
+                "Manos Pi".to_string(),
+                "Nr %  %q 📎".to_string(),
+                "|Hello".to_string()
+            ],
+            true
+        )
+    );
+    assert_eq!(IndexFormat::new("%{%Y").render(&fields).0[3], "%{%Y");
+    assert_eq!(
+        IndexFormat::new("[%L]").render(&fields).0[3],
+        "[meli.example.com]"
+    );
+}
@@ -111,12 +111,13 @@ impl Component for OfflineListing {
         }
         self.dirty = false;
         let theme_default = conf::value(context, "theme_default");
+        let offline_attr = conf::value(context, "mail.listing.offline");
         clear_area(grid, area, theme_default);
         if let Err(err) = context.is_online(self.cursor_pos.0) {
             let (x, _) = write_string_to_grid(
                 "offline: ",
                 grid,
-                Color::Byte(243),
+                offline_attr.fg,
                 theme_default.bg,
                 theme_default.attrs,
                 area,
@@ -135,7 +136,7 @@ impl Component for OfflineListing {
             let (_, mut y) = write_string_to_grid(
                 "loading...",
                 grid,
-                Color::Byte(243),
+                offline_attr.fg,
                 theme_default.bg,
                 theme_default.attrs,
                 area,
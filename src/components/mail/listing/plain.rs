@@ -135,6 +135,8 @@ pub struct PlainListing {
 
     search_job: Option<(String, JoinHandle<Result<SmallVec<[EnvelopeHash; 512]>>>)>,
     filter_term: String,
+    /// Term of a search preview to highlight, see `ListingTrait::set_highlight_term`.
+    highlight_term: Option<String>,
     filtered_selection: Vec<EnvelopeHash>,
     filtered_order: HashMap<EnvelopeHash, usize>,
     selection: HashMap<EnvelopeHash, bool>,
@@ -150,9 +152,13 @@ pub struct PlainListing {
     row_updates: SmallVec<[EnvelopeHash; 8]>,
     _row_updates: SmallVec<[ThreadHash; 8]>,
     color_cache: ColorCache,
+    /// Parsed `listing.index_format` of the mailbox, if it is set.
+    index_format: Option<IndexFormat>,
 
     active_jobs: HashMap<JobId, JoinHandle<Result<()>>>,
     movement: Option<PageMovement>,
+    list_area: Area,
+    view_ratio: Option<usize>,
     id: ComponentId,
 }
 
@@ -193,6 +199,16 @@ impl MailListingTrait for PlainListing {
         {
             self.cursor_pos.2 = 0;
             self.new_cursor_pos.2 = 0;
+            self.sort = context.accounts[&self.new_cursor_pos.0]
+                .mailbox_sort(self.new_cursor_pos.1)
+                .unwrap_or_else(|| {
+                    let sort = *mailbox_settings!(
+                        context[self.new_cursor_pos.0][&self.new_cursor_pos.1]
+                            .listing
+                            .sort
+                    );
+                    (sort.field, sort.order)
+                });
         }
         self.cursor_pos.1 = self.new_cursor_pos.1;
         self.cursor_pos.0 = self.new_cursor_pos.0;
@@ -219,6 +235,14 @@ impl MailListingTrait for PlainListing {
             self.color_cache.odd_highlighted.attrs |= Attr::REVERSE;
         }
 
+        self.index_format = mailbox_settings!(
+            context[self.cursor_pos.0][&self.cursor_pos.1]
+                .listing
+                .index_format
+        )
+        .as_deref()
+        .map(IndexFormat::new);
+
         // Get mailbox as a reference.
         //
         match context.accounts[&self.cursor_pos.0].load(self.cursor_pos.1) {
@@ -259,6 +283,16 @@ impl MailListingTrait for PlainListing {
             .map(|h| (*h, env_lck[h].thread()))
             .collect();
         let sort = self.sort;
+        let threads = context.accounts[&self.cursor_pos.0]
+            .collection
+            .get_threads(self.cursor_pos.1);
+        let activity = |e: &Envelope| {
+            threads
+                .thread_nodes()
+                .get(&e.thread())
+                .map(|n| threads.thread_ref(n.group).date())
+                .unwrap_or_else(|| e.date())
+        };
         self.local_collection.sort_by(|a, b| match sort {
             (SortField::Date, SortOrder::Desc) => {
                 let ma = &env_lck[a];
@@ -280,7 +314,30 @@ impl MailListingTrait for PlainListing {
                 let mb = &env_lck[b];
                 mb.subject().cmp(&ma.subject())
             }
+            (field, order) => {
+                let ma = &env_lck[a];
+                let mb = &env_lck[b];
+                let ord = match field {
+                    SortField::Sender => ma
+                        .field_from_to_string()
+                        .to_lowercase()
+                        .cmp(&mb.field_from_to_string().to_lowercase()),
+                    SortField::Size => ma.size.cmp(&mb.size),
+                    SortField::Attachments => ma.has_attachments().cmp(&mb.has_attachments()),
+                    SortField::Unread => (!ma.is_seen()).cmp(&!mb.is_seen()),
+                    SortField::Date | SortField::Subject | SortField::Activity => {
+                        activity(ma).cmp(&activity(mb))
+                    }
+                };
+                let ord = if order == SortOrder::Desc {
+                    ord.reverse()
+                } else {
+                    ord
+                };
+                ord.then_with(|| mb.date().cmp(&ma.date()))
+            }
         });
+        drop(threads);
         for &env_hash in &self.local_collection {
             self.all_envelopes.insert(env_hash);
         }
@@ -332,6 +389,10 @@ impl ListingTrait for PlainListing {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
 
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
+
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
         self.unfocused = false;
@@ -387,6 +448,14 @@ impl ListingTrait for PlainListing {
         for c in grid.row_iter(x..(x + self.data_columns.widths[3]), get_y(upper_left)) {
             grid[c].set_bg(row_attr.bg).set_attrs(row_attr.attrs);
         }
+        if let Some(ref term) = self.highlight_term {
+            highlight_matches(
+                grid,
+                area,
+                term,
+                crate::conf::value(context, "mail.listing.highlight_search"),
+            );
+        }
     }
 
     /// Draw the list of `Envelope`s.
@@ -397,6 +466,7 @@ impl ListingTrait for PlainListing {
         }
         let upper_left = upper_left!(area);
         let bottom_right = bottom_right!(area);
+        self.list_area = area;
         if self.length == 0 {
             clear_area(grid, area, self.color_cache.theme_default);
             copy_area(
@@ -438,6 +508,12 @@ impl ListingTrait for PlainListing {
                     }
                 }
                 PageMovement::Right(_) | PageMovement::Left(_) => {}
+                PageMovement::Row(row) => {
+                    let idx = (self.new_cursor_pos.2 / rows) * rows + row;
+                    if idx < self.length {
+                        self.new_cursor_pos.2 = idx;
+                    }
+                }
                 PageMovement::Home => {
                     self.new_cursor_pos.2 = 0;
                 }
@@ -580,6 +656,15 @@ impl ListingTrait for PlainListing {
             );
         }
 
+        if let Some(ref term) = self.highlight_term {
+            highlight_matches(
+                grid,
+                area,
+                term,
+                crate::conf::value(context, "mail.listing.highlight_search"),
+            );
+        }
+
         self.highlight_line(
             grid,
             (
@@ -679,6 +764,10 @@ impl ListingTrait for PlainListing {
         }
     }
 
+    fn set_highlight_term(&mut self, term: Option<String>) {
+        self.highlight_term = term;
+    }
+
     fn unfocused(&self) -> bool {
         self.unfocused
     }
@@ -687,6 +776,24 @@ impl ListingTrait for PlainListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn list_area(&self) -> Option<Area> {
+        if self.unfocused {
+            None
+        } else {
+            Some(self.list_area)
+        }
+    }
+
+    fn adjust_view_ratio(&mut self, delta: isize, context: &Context) {
+        adjust_view_ratio(
+            &mut self.view_ratio,
+            delta,
+            (self.new_cursor_pos.0, self.new_cursor_pos.1),
+            context,
+        );
+        self.set_dirty(true);
+    }
 }
 
 impl fmt::Display for PlainListing {
@@ -709,6 +816,7 @@ impl PlainListing {
             thread_node_hashes: HashMap::default(),
             order: HashMap::default(),
             filter_term: String::new(),
+            highlight_term: None,
             search_job: None,
             filtered_selection: Vec::new(),
             filtered_order: HashMap::default(),
@@ -722,9 +830,12 @@ impl PlainListing {
             unfocused: false,
             view: MailView::default(),
             color_cache: ColorCache::default(),
+            index_format: None,
             active_jobs: HashMap::default(),
 
             movement: None,
+            list_area: ((0, 0), (0, 0)),
+            view_ratio: None,
             id: ComponentId::new_v4(),
         }
     }
@@ -733,7 +844,7 @@ impl PlainListing {
         let mut tags = String::new();
         let mut colors = SmallVec::new();
         let account = &context.accounts[&self.cursor_pos.0];
-        if account.backend_capabilities.supports_tags {
+        if account.backend_capabilities.tag_support.can_set() {
             let tags_lck = account.collection.tag_index.read().unwrap();
             for t in e.labels().iter() {
                 if mailbox_settings!(
@@ -769,18 +880,33 @@ impl PlainListing {
         }
         let mut subject = e.subject().to_string();
         subject.truncate_at_boundary(150);
-        EntryStrings {
-            date: DateString(PlainListing::format_date(&e)),
+        let entry_strings = EntryStrings {
+            date: DateString(ConversationsListing::format_date(context, e.date())),
             subject: SubjectString(subject),
-            flag: FlagString(format!("{}", if e.has_attachments() { "📎" } else { "" },)),
+            flag: FlagString(format!(
+                "{}{}{}",
+                if e.has_attachments() { "📎" } else { "" },
+                mailing_list_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), &e),
+                duplicate_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), &e)
+            )),
             from: FromString(address_list!((e.from()) as comma_sep_list)),
             tags: TagString(tags, colors),
+        };
+        if let Some(ref index_format) = self.index_format {
+            return entry_strings.with_index_format(
+                index_format,
+                &e,
+                !e.is_seen(),
+                e.has_attachments(),
+            );
         }
+        entry_strings
     }
 
     fn redraw_list(&mut self, context: &Context, iter: Box<dyn Iterator<Item = EnvelopeHash>>) {
         let account = &context.accounts[&self.cursor_pos.0];
         let mailbox = &account[&self.cursor_pos.1];
+        let attachment_flag_attr = crate::conf::value(context, "mail.listing.attachment_flag");
 
         self.order.clear();
         self.selection.clear();
@@ -800,6 +926,9 @@ impl PlainListing {
 
                 panic!();
             }
+            if is_hidden_duplicate(context, (self.cursor_pos.0, self.cursor_pos.1), i) {
+                continue;
+            }
             let envelope: EnvelopeRef = context.accounts[&self.cursor_pos.0].collection.get_env(i);
             use melib::search::QueryTrait;
             if let Some(filter_query) = mailbox_settings!(
@@ -967,12 +1096,13 @@ impl PlainListing {
             for c in columns[4].row_iter(x..min_width.4, idx) {
                 columns[4][c].set_bg(row_attr.bg).set_attrs(row_attr.attrs);
             }
-            if context.accounts[&self.cursor_pos.0]
-                .collection
-                .get_env(i)
-                .has_attachments()
+            if self.index_format.is_none()
+                && context.accounts[&self.cursor_pos.0]
+                    .collection
+                    .get_env(i)
+                    .has_attachments()
             {
-                columns[3][(0, idx)].set_fg(Color::Byte(103));
+                columns[3][(0, idx)].set_fg(attachment_flag_attr.fg);
             }
         }
         if self.length == 0 && self.filter_term.is_empty() {
@@ -999,21 +1129,6 @@ impl PlainListing {
         }
     }
 
-    fn format_date(envelope: &Envelope) -> String {
-        let d = std::time::UNIX_EPOCH + std::time::Duration::from_secs(envelope.date());
-        let now: std::time::Duration = std::time::SystemTime::now()
-            .duration_since(d)
-            .unwrap_or_else(|_| std::time::Duration::new(std::u64::MAX, 0));
-        match now.as_secs() {
-            n if n < 10 * 60 * 60 => format!("{} hours ago{}", n / (60 * 60), " ".repeat(8)),
-            n if n < 24 * 60 * 60 => format!("{} hours ago{}", n / (60 * 60), " ".repeat(7)),
-            n if n < 4 * 24 * 60 * 60 => {
-                format!("{} days ago{}", n / (24 * 60 * 60), " ".repeat(9))
-            }
-            _ => melib::datetime::timestamp_to_string(envelope.datetime(), None, false),
-        }
-    }
-
     fn perform_action(&mut self, context: &mut Context, env_hash: EnvelopeHash, a: &ListingAction) {
         let account = &mut context.accounts[&self.cursor_pos.0];
         match {
@@ -1021,12 +1136,12 @@ impl PlainListing {
                 ListingAction::SetSeen => account.backend.write().unwrap().set_flags(
                     env_hash.into(),
                     self.cursor_pos.1,
-                    smallvec::smallvec![(Ok(Flag::SEEN), true)],
+                    smallvec::smallvec![FlagOp::Flag(Flag::SEEN, true)],
                 ),
                 ListingAction::SetUnseen => account.backend.write().unwrap().set_flags(
                     env_hash.into(),
                     self.cursor_pos.1,
-                    smallvec::smallvec![(Ok(Flag::SEEN), false)],
+                    smallvec::smallvec![FlagOp::Flag(Flag::SEEN, false)],
                 ),
                 ListingAction::Delete => {
                     /* do nothing */
@@ -1118,7 +1233,18 @@ impl Component for PlainListing {
                 return;
             }
 
-            self.view.draw(grid, area, context);
+            let (list_area, view_area) = split_view_area(
+                grid,
+                area,
+                (self.new_cursor_pos.0, self.new_cursor_pos.1),
+                ViewLayout::FullScreen,
+                self.view_ratio,
+                context,
+            );
+            if let Some(list_area) = list_area {
+                self.draw_list(grid, list_area, context);
+            }
+            self.view.draw(grid, view_area, context);
         }
         self.dirty = false;
     }
@@ -1177,6 +1303,8 @@ impl Component for PlainListing {
                     Action::Sort(field, order) if !self.unfocused => {
                         debug!("Sort {:?} , {:?}", field, order);
                         self.sort = (*field, *order);
+                        context.accounts[&self.cursor_pos.0]
+                            .set_mailbox_sort(self.cursor_pos.1, self.sort);
                         return true;
                     }
                     Action::Listing(a @ ListingAction::SetSeen)
@@ -1334,7 +1462,14 @@ impl Component for PlainListing {
                 self.refresh_mailbox(context, false);
                 return true;
             }
-            UIEvent::Action(Action::Listing(Search(ref filter_term))) if !self.unfocused => {
+            UIEvent::Action(Action::Listing(Search(ref filter_term))) => {
+                if self.unfocused {
+                    /* Searching from the opened entry, eg. with `list-filter` */
+                    self.unfocused = false;
+                    self.view
+                        .process_event(&mut UIEvent::VisibilityChange(false), context);
+                    self.force_draw = true;
+                }
                 match context.accounts[&self.cursor_pos.0].search(
                     filter_term,
                     self.sort,
@@ -126,6 +126,8 @@ pub struct ThreadListing {
     initialised: bool,
     view: Option<MailView>,
     movement: Option<PageMovement>,
+    list_area: Option<Area>,
+    view_ratio: Option<usize>,
     id: ComponentId,
 }
 
@@ -151,6 +153,16 @@ impl MailListingTrait for ThreadListing {
         {
             self.cursor_pos.2 = 0;
             self.new_cursor_pos.2 = 0;
+            self.sort = context.accounts[&self.new_cursor_pos.0]
+                .mailbox_sort(self.new_cursor_pos.1)
+                .unwrap_or_else(|| {
+                    let sort = *mailbox_settings!(
+                        context[self.new_cursor_pos.0][&self.new_cursor_pos.1]
+                            .listing
+                            .sort
+                    );
+                    (sort.field, sort.order)
+                });
         }
         self.cursor_pos.1 = self.new_cursor_pos.1;
         self.cursor_pos.0 = self.new_cursor_pos.0;
@@ -264,10 +276,26 @@ impl MailListingTrait for ThreadListing {
         /* This is just a desugared for loop so that we can use .peek() */
         let mut idx = 0;
         let mut prev_group = ThreadHash::null();
+        let mut shown_group = ThreadHash::null();
+        let mut collapsed = false;
         while let Some((indentation, thread_node_hash, has_sibling)) = iter.next() {
             let thread_node = &thread_nodes[&thread_node_hash];
 
             if thread_node.has_message() {
+                let group = threads.find_group(thread_node.group);
+                if group != shown_group {
+                    shown_group = group;
+                    collapsed = self.is_collapsed(context, group);
+                } else if collapsed {
+                    continue;
+                }
+                if is_hidden_duplicate(
+                    context,
+                    (self.cursor_pos.0, self.cursor_pos.1),
+                    thread_node.message().unwrap(),
+                ) {
+                    continue;
+                }
                 let envelope: EnvelopeRef =
                     account.collection.get_env(thread_node.message().unwrap());
                 self.order.insert(envelope.hash(), idx);
@@ -283,8 +311,12 @@ impl MailListingTrait for ThreadListing {
                         continue;
                     }
                 }
-                let is_root = threads.find_group(thread_node.group) != prev_group;
-                prev_group = threads.find_group(thread_node.group);
+                let is_root = group != prev_group;
+                prev_group = group;
+                if is_root {
+                    /* Branches of a collapsed thread were never closed */
+                    indentations.clear();
+                }
 
                 let mut entry_strings = self.make_entry_string(&envelope, context);
                 entry_strings.subject = SubjectString(ThreadListing::make_thread_entry(
@@ -296,6 +328,12 @@ impl MailListingTrait for ThreadListing {
                     has_sibling,
                     is_root,
                 ));
+                let thread_len = threads.thread_ref(group).len();
+                if collapsed && thread_len > 1 {
+                    entry_strings
+                        .subject
+                        .push_str(&format!(" (+{})", thread_len - 1));
+                }
                 row_widths.1.push(
                     entry_strings
                         .date
@@ -403,6 +441,10 @@ impl ListingTrait for ThreadListing {
     fn coordinates(&self) -> (AccountHash, MailboxHash) {
         (self.new_cursor_pos.0, self.new_cursor_pos.1)
     }
+
+    fn cursor_row(&self) -> usize {
+        self.new_cursor_pos.2
+    }
     fn set_coordinates(&mut self, coordinates: (AccountHash, MailboxHash)) {
         self.new_cursor_pos = (coordinates.0, coordinates.1, 0);
         self.unfocused = false;
@@ -419,6 +461,7 @@ impl ListingTrait for ThreadListing {
         }
         let upper_left = upper_left!(area);
         let bottom_right = bottom_right!(area);
+        self.list_area = Some(area);
         if self.length == 0 {
             clear_area(grid, area, self.color_cache.theme_default);
             context.dirty_areas.push_back(area);
@@ -453,6 +496,12 @@ impl ListingTrait for ThreadListing {
                     }
                 }
                 PageMovement::Right(_) | PageMovement::Left(_) => {}
+                PageMovement::Row(row) => {
+                    let idx = (self.new_cursor_pos.2 / rows) * rows + row;
+                    if idx < self.length {
+                        self.new_cursor_pos.2 = idx;
+                    }
+                }
                 PageMovement::Home => {
                     self.new_cursor_pos.2 = 0;
                 }
@@ -728,6 +777,20 @@ impl ListingTrait for ThreadListing {
         self.movement = Some(mvm);
         self.set_dirty(true);
     }
+
+    fn list_area(&self) -> Option<Area> {
+        self.list_area
+    }
+
+    fn adjust_view_ratio(&mut self, delta: isize, context: &Context) {
+        adjust_view_ratio(
+            &mut self.view_ratio,
+            delta,
+            (self.new_cursor_pos.0, self.new_cursor_pos.1),
+            context,
+        );
+        self.set_dirty(true);
+    }
 }
 
 impl fmt::Display for ThreadListing {
@@ -756,6 +819,8 @@ impl ThreadListing {
             view: None,
             initialised: false,
             movement: None,
+            list_area: None,
+            view_ratio: None,
             id: ComponentId::new_v4(),
         }
     }
@@ -824,6 +889,53 @@ impl ThreadListing {
         s
     }
 
+    /// Message-ID of the first message of `thread`, under which its collapse state is saved.
+    fn thread_key(&self, context: &Context, thread: ThreadHash) -> Option<String> {
+        let account = &context.accounts[&self.cursor_pos.0];
+        let threads = account.collection.get_threads(self.cursor_pos.1);
+        threads
+            .thread_group_iter(thread)
+            .find_map(|(_, h)| threads.thread_nodes()[&h].message())
+            .map(|env_hash| {
+                account
+                    .collection
+                    .get_env(env_hash)
+                    .message_id_display()
+                    .to_string()
+            })
+    }
+
+    /// Whether only the first message of `thread` is shown, either because the user collapsed it
+    /// or because it is longer than `listing.auto_collapse_threads`.
+    fn is_collapsed(&self, context: &Context, thread: ThreadHash) -> bool {
+        let account = &context.accounts[&self.cursor_pos.0];
+        self.thread_key(context, thread)
+            .and_then(|key| account.thread_collapsed(self.cursor_pos.1, &key))
+            .unwrap_or_else(|| {
+                match *mailbox_settings!(
+                    context[self.cursor_pos.0][&self.cursor_pos.1]
+                        .listing
+                        .auto_collapse_threads
+                ) {
+                    Some(max_len) => {
+                        let threads = account.collection.get_threads(self.cursor_pos.1);
+                        threads.thread_ref(thread).len() > max_len
+                    }
+                    None => false,
+                }
+            })
+    }
+
+    fn set_collapsed(&self, context: &mut Context, thread: ThreadHash, collapsed: bool) {
+        if let Some(key) = self.thread_key(context, thread) {
+            context.accounts[&self.cursor_pos.0].set_thread_collapsed(
+                self.cursor_pos.1,
+                key,
+                collapsed,
+            );
+        }
+    }
+
     fn get_env_under_cursor(&self, cursor: usize, _context: &Context) -> EnvelopeHash {
         *self
             .order
@@ -840,7 +952,7 @@ impl ThreadListing {
         let mut tags = String::new();
         let mut colors: SmallVec<[_; 8]> = SmallVec::new();
         let account = &context.accounts[&self.cursor_pos.0];
-        if account.backend_capabilities.supports_tags {
+        if account.backend_capabilities.tag_support.can_set() {
             let tags_lck = account.collection.tag_index.read().unwrap();
             for t in e.labels().iter() {
                 if mailbox_settings!(
@@ -879,7 +991,12 @@ impl ThreadListing {
         EntryStrings {
             date: DateString(ConversationsListing::format_date(context, e.date())),
             subject: SubjectString(subject),
-            flag: FlagString(format!("{}", if e.has_attachments() { "📎" } else { "" },)),
+            flag: FlagString(format!(
+                "{}{}{}",
+                if e.has_attachments() { "📎" } else { "" },
+                mailing_list_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), &e),
+                duplicate_indicator(context, (self.cursor_pos.0, self.cursor_pos.1), e)
+            )),
             from: FromString(address_list!((e.from()) as comma_sep_list)),
             tags: TagString(tags, colors),
         }
@@ -1080,30 +1197,12 @@ impl Component for ThreadListing {
             self.draw_list(grid, area, context);
         } else {
             self.cursor_pos = self.new_cursor_pos;
-            let upper_left = upper_left!(area);
-            let bottom_right = bottom_right!(area);
             if self.length == 0 && self.dirty {
                 clear_area(grid, area, self.color_cache.theme_default);
                 context.dirty_areas.push_back(area);
                 return;
             }
 
-            /* Render the mail body in a pager, basically copy what HSplit does */
-            let total_rows = get_y(bottom_right) - get_y(upper_left);
-            let pager_ratio = *mailbox_settings!(
-                context[self.cursor_pos.0][&self.cursor_pos.1]
-                    .pager
-                    .pager_ratio
-            );
-
-            let bottom_entity_rows = (pager_ratio * total_rows) / 100;
-
-            if bottom_entity_rows > total_rows {
-                clear_area(grid, area, self.color_cache.theme_default);
-                context.dirty_areas.push_back(area);
-                return;
-            }
-
             let idx = self.cursor_pos.2;
 
             /* Mark message as read */
@@ -1123,37 +1222,28 @@ impl Component for ThreadListing {
                 self.highlight_line_self(idx, context);
             }
 
-            let mid = get_y(upper_left) + total_rows - bottom_entity_rows;
-            self.draw_list(
+            let (list_area, view_area) = split_view_area(
                 grid,
-                (
-                    upper_left,
-                    (get_x(bottom_right), get_y(upper_left) + mid - 1),
-                ),
+                area,
+                (self.cursor_pos.0, self.cursor_pos.1),
+                ViewLayout::Bottom,
+                self.view_ratio,
                 context,
             );
+            if let Some(list_area) = list_area {
+                self.draw_list(grid, list_area, context);
+            } else {
+                self.list_area = None;
+            }
             if self.length == 0 {
                 self.dirty = false;
                 return;
             }
-            {
-                if get_x(upper_left) > 0 && grid[(get_x(upper_left) - 1, mid)].ch() == VERT_BOUNDARY
-                {
-                    grid[(get_x(upper_left) - 1, mid)].set_ch(LIGHT_VERTICAL_AND_RIGHT);
-                }
-
-                for i in get_x(upper_left)..=get_x(bottom_right) {
-                    grid[(i, mid)].set_ch(HORZ_BOUNDARY);
-                }
-                context
-                    .dirty_areas
-                    .push_back((set_y(upper_left, mid), set_y(bottom_right, mid)));
-            }
             // TODO: Make headers view configurable
 
             if !self.dirty {
                 if let Some(v) = self.view.as_mut() {
-                    v.draw(grid, (set_y(upper_left, mid + 1), bottom_right), context);
+                    v.draw(grid, view_area, context);
                 }
                 return;
             }
@@ -1171,7 +1261,7 @@ impl Component for ThreadListing {
             }
 
             if let Some(v) = self.view.as_mut() {
-                v.draw(grid, (set_y(upper_left, mid + 1), bottom_right), context);
+                v.draw(grid, view_area, context);
             }
 
             self.dirty = false;
@@ -1231,6 +1321,54 @@ impl Component for ThreadListing {
                 self.view = None;
                 return true;
             }
+            UIEvent::Input(ref k)
+                if !self.unfocused
+                    && self.length > 0
+                    && *k == context.settings.shortcuts.listing.toggle_thread_collapse =>
+            {
+                let env_hash = self.get_env_under_cursor(self.new_cursor_pos.2, context);
+                let thread = {
+                    let account = &context.accounts[&self.cursor_pos.0];
+                    let threads = account.collection.get_threads(self.cursor_pos.1);
+                    let thread_node = account.collection.get_env(env_hash).thread();
+                    threads.find_group(threads.thread_nodes()[&thread_node].group)
+                };
+                let collapsed = !self.is_collapsed(context, thread);
+                self.set_collapsed(context, thread, collapsed);
+                self.refresh_mailbox(context, false);
+                /* Keep the cursor on the thread */
+                let first_env_hash = {
+                    let threads = context.accounts[&self.cursor_pos.0]
+                        .collection
+                        .get_threads(self.cursor_pos.1);
+                    threads
+                        .thread_group_iter(thread)
+                        .find_map(|(_, h)| threads.thread_nodes()[&h].message())
+                };
+                if let Some(row) = first_env_hash.and_then(|h| self.order.get(&h)) {
+                    self.new_cursor_pos.2 = *row;
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref k)
+                if !self.unfocused
+                    && (*k == context.settings.shortcuts.listing.collapse_all_threads
+                        || *k == context.settings.shortcuts.listing.expand_all_threads) =>
+            {
+                let collapsed = *k == context.settings.shortcuts.listing.collapse_all_threads;
+                let roots = context.accounts[&self.cursor_pos.0]
+                    .collection
+                    .get_threads(self.cursor_pos.1)
+                    .roots();
+                for thread in roots {
+                    self.set_collapsed(context, thread, collapsed);
+                }
+                self.new_cursor_pos.2 = 0;
+                self.refresh_mailbox(context, false);
+                self.set_dirty(true);
+                return true;
+            }
             UIEvent::MailboxUpdate((ref idxa, ref idxf))
                 if (*idxa, *idxf) == (self.new_cursor_pos.0, self.cursor_pos.1) =>
             {
@@ -1303,6 +1441,8 @@ impl Component for ThreadListing {
                 Action::Sort(field, order) => {
                     debug!("Sort {:?} , {:?}", field, order);
                     self.sort = (*field, *order);
+                    context.accounts[&self.cursor_pos.0]
+                        .set_mailbox_sort(self.cursor_pos.1, self.sort);
                     self.dirty = true;
                     self.refresh_mailbox(context, false);
                     return true;
@@ -0,0 +1,464 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::*;
+use crate::components::PageMovement;
+use melib::backends::SpecialUsageMailbox;
+
+const ACCOUNT_COLORS: [&str; 6] = [
+    "mail.listing.unified.account0",
+    "mail.listing.unified.account1",
+    "mail.listing.unified.account2",
+    "mail.listing.unified.account3",
+    "mail.listing.unified.account4",
+    "mail.listing.unified.account5",
+];
+
+/// A list of the envelopes of several mailboxes across accounts, newest first. By default it
+/// includes the inbox of every account; mailboxes can opt in or out with the `unified_inbox`
+/// mailbox setting.
+#[derive(Debug)]
+pub struct UnifiedListing {
+    mailboxes: Vec<(AccountHash, MailboxHash)>,
+    rows: Vec<(AccountHash, MailboxHash, EnvelopeHash)>,
+    cursor_pos: usize,
+    movement: Option<PageMovement>,
+    color_cache: ColorCache,
+    account_colors: HashMap<AccountHash, ThemeAttribute>,
+    /// The message under the cursor, if it has been opened.
+    view: Option<MailView>,
+    dirty: bool,
+    id: ComponentId,
+}
+
+impl fmt::Display for UnifiedListing {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "unified inbox")
+    }
+}
+
+impl UnifiedListing {
+    pub fn new(context: &mut Context) -> Self {
+        let mut mailboxes = vec![];
+        for account in context.accounts.values() {
+            for mailbox_hash in account.mailboxes_order() {
+                let entry = &account[mailbox_hash];
+                if entry.conf.mailbox_conf.unified_inbox.unwrap_or_else(|| {
+                    entry.ref_mailbox.special_usage() == SpecialUsageMailbox::Inbox
+                }) {
+                    mailboxes.push((account.hash(), *mailbox_hash));
+                }
+            }
+        }
+        let mut ret = UnifiedListing {
+            mailboxes,
+            rows: vec![],
+            cursor_pos: 0,
+            movement: None,
+            color_cache: ColorCache::default(),
+            account_colors: HashMap::default(),
+            view: None,
+            dirty: true,
+            id: ComponentId::new_v4(),
+        };
+        ret.set_colors(context);
+        ret.refresh(context);
+        ret
+    }
+
+    fn set_colors(&mut self, context: &Context) {
+        self.color_cache = ColorCache {
+            even: crate::conf::value(context, "mail.listing.plain.even"),
+            odd: crate::conf::value(context, "mail.listing.plain.odd"),
+            even_unseen: crate::conf::value(context, "mail.listing.plain.even_unseen"),
+            odd_unseen: crate::conf::value(context, "mail.listing.plain.odd_unseen"),
+            even_highlighted: crate::conf::value(context, "mail.listing.plain.even_highlighted"),
+            odd_highlighted: crate::conf::value(context, "mail.listing.plain.odd_highlighted"),
+            theme_default: crate::conf::value(context, "theme_default"),
+            ..ColorCache::default()
+        };
+        if !context.settings.terminal.use_color() {
+            self.color_cache.even_highlighted.attrs |= Attr::REVERSE;
+            self.color_cache.odd_highlighted.attrs |= Attr::REVERSE;
+        }
+        self.account_colors = context
+            .accounts
+            .keys()
+            .enumerate()
+            .map(|(i, h)| {
+                (
+                    *h,
+                    crate::conf::value(context, ACCOUNT_COLORS[i % ACCOUNT_COLORS.len()]),
+                )
+            })
+            .collect();
+    }
+
+    /// Collect the envelopes of every loaded mailbox. Mailboxes that are still loading are added
+    /// when their `MailboxUpdate` or `StartupCheck` event arrives.
+    fn refresh(&mut self, context: &mut Context) {
+        let cursor_env = self.rows.get(self.cursor_pos).map(|(_, _, h)| *h);
+        let mut rows = vec![];
+        for &(account_hash, mailbox_hash) in &self.mailboxes {
+            if context.accounts[&account_hash].load(mailbox_hash).is_err() {
+                continue;
+            }
+            let collection = &context.accounts[&account_hash].collection;
+            let env_lck = collection.envelopes.read().unwrap();
+            for env_hash in collection.get_mailbox(mailbox_hash).iter() {
+                if let Some(envelope) = env_lck.get(env_hash) {
+                    rows.push((envelope.date(), (account_hash, mailbox_hash, *env_hash)));
+                }
+            }
+        }
+        rows.sort_by(|a, b| b.0.cmp(&a.0));
+        self.rows = rows.into_iter().map(|(_, r)| r).collect();
+        self.cursor_pos = cursor_env
+            .and_then(|h| self.rows.iter().position(|(_, _, r)| *r == h))
+            .unwrap_or_else(|| std::cmp::min(self.cursor_pos, self.rows.len().saturating_sub(1)));
+        self.dirty = true;
+    }
+
+    /// Apply `a` to the message under the cursor, the same way the other listings apply it to
+    /// their selected threads.
+    fn perform_action(&mut self, a: &ListingAction, context: &mut Context) {
+        if let Some((account_hash, mailbox_hash, env_hash)) =
+            self.rows.get(self.cursor_pos).cloned()
+        {
+            perform_action_on_messages(
+                context,
+                account_hash,
+                mailbox_hash,
+                smallvec::smallvec![env_hash],
+                a,
+            );
+            self.dirty = true;
+        }
+    }
+
+    fn draw_list(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
+        let upper_left = upper_left!(area);
+        let bottom_right = bottom_right!(area);
+        clear_area(grid, area, self.color_cache.theme_default);
+        context.dirty_areas.push_back(area);
+        if self.rows.is_empty() {
+            write_string_to_grid(
+                "No messages.",
+                grid,
+                self.color_cache.theme_default.fg,
+                self.color_cache.theme_default.bg,
+                self.color_cache.theme_default.attrs,
+                area,
+                None,
+            );
+            return;
+        }
+        let rows = get_y(bottom_right) - get_y(upper_left) + 1;
+        let length = self.rows.len();
+        if let Some(mvm) = self.movement.take() {
+            match mvm {
+                PageMovement::Up(amount) => {
+                    self.cursor_pos = self.cursor_pos.saturating_sub(amount);
+                }
+                PageMovement::PageUp(multiplier) => {
+                    self.cursor_pos = self.cursor_pos.saturating_sub(rows * multiplier);
+                }
+                PageMovement::Down(amount) => {
+                    self.cursor_pos = std::cmp::min(self.cursor_pos + amount, length - 1);
+                }
+                PageMovement::PageDown(multiplier) => {
+                    self.cursor_pos =
+                        std::cmp::min(self.cursor_pos + rows * multiplier, length - 1);
+                }
+                PageMovement::Right(_) | PageMovement::Left(_) => {}
+                PageMovement::Row(row) => {
+                    let idx = (self.cursor_pos / rows) * rows + row;
+                    if idx < length {
+                        self.cursor_pos = idx;
+                    }
+                }
+                PageMovement::Home => {
+                    self.cursor_pos = 0;
+                }
+                PageMovement::End => {
+                    self.cursor_pos = length - 1;
+                }
+            }
+        }
+        let top_idx = (self.cursor_pos / rows) * rows;
+        let page = top_idx..std::cmp::min(top_idx + rows, length);
+
+        let account_width = self
+            .mailboxes
+            .iter()
+            .map(|(h, _)| context.accounts[h].name().grapheme_width())
+            .max()
+            .unwrap_or(0);
+        let mut lines = Vec::with_capacity(page.len());
+        for idx in page {
+            let (account_hash, _, env_hash) = self.rows[idx];
+            let account = &context.accounts[&account_hash];
+            let envelope = account.collection.get_env(env_hash);
+            lines.push((
+                idx,
+                account_hash,
+                ConversationsListing::format_date(context, envelope.date()),
+                address_list!((envelope.from()) as comma_sep_list),
+                envelope.subject().to_string(),
+                !envelope.is_seen(),
+            ));
+        }
+        let date_width = lines
+            .iter()
+            .map(|l| l.2.grapheme_width())
+            .max()
+            .unwrap_or(0);
+        let from_width = std::cmp::min(
+            lines
+                .iter()
+                .map(|l| l.3.grapheme_width())
+                .max()
+                .unwrap_or(0),
+            width!(area) / 4,
+        );
+
+        for (y, (idx, account_hash, date, from, subject, unseen)) in lines.into_iter().enumerate() {
+            let y = get_y(upper_left) + y;
+            let row_area = (set_y(upper_left, y), set_y(bottom_right, y));
+            let row_attr = match (idx % 2 == 0, unseen, idx == self.cursor_pos) {
+                (true, _, true) => self.color_cache.even_highlighted,
+                (false, _, true) => self.color_cache.odd_highlighted,
+                (true, true, false) => self.color_cache.even_unseen,
+                (false, true, false) => self.color_cache.odd_unseen,
+                (true, false, false) => self.color_cache.even,
+                (false, false, false) => self.color_cache.odd,
+            };
+            clear_area(grid, row_area, row_attr);
+            let account_attr = self.account_colors[&account_hash];
+            let mut x = get_x(upper_left);
+            write_string_to_grid(
+                context.accounts[&account_hash].name(),
+                grid,
+                account_attr.fg,
+                row_attr.bg,
+                row_attr.attrs | account_attr.attrs,
+                (set_x(upper_left, x), set_y(bottom_right, y)),
+                None,
+            );
+            x += account_width + 2;
+            for (s, width) in &[(date, date_width), (from, from_width)] {
+                if x > get_x(bottom_right) {
+                    break;
+                }
+                write_string_to_grid(
+                    s,
+                    grid,
+                    row_attr.fg,
+                    row_attr.bg,
+                    row_attr.attrs,
+                    ((x, y), (std::cmp::min(x + width, get_x(bottom_right)), y)),
+                    None,
+                );
+                x += width + 2;
+            }
+            if x <= get_x(bottom_right) {
+                write_string_to_grid(
+                    &subject,
+                    grid,
+                    row_attr.fg,
+                    row_attr.bg,
+                    row_attr.attrs,
+                    ((x, y), set_y(bottom_right, y)),
+                    None,
+                );
+            }
+        }
+    }
+}
+
+impl Component for UnifiedListing {
+    fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
+        if let Some(ref mut view) = self.view {
+            view.draw(grid, area, context);
+            return;
+        }
+        if !self.dirty {
+            return;
+        }
+        self.draw_list(grid, area, context);
+        self.dirty = false;
+    }
+
+    fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        if let Some(ref mut view) = self.view {
+            if view.process_event(event, context) {
+                return true;
+            }
+        }
+
+        let shortcuts = self.get_shortcuts(context);
+        match *event {
+            UIEvent::Input(ref k)
+                if self.view.is_some()
+                    && shortcut!(k == shortcuts[CompactListing::DESCRIPTION]["exit_thread"]) =>
+            {
+                if let Some(mut view) = self.view.take() {
+                    view.process_event(&mut UIEvent::VisibilityChange(false), context);
+                }
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(_) if self.view.is_some() => {}
+            UIEvent::Input(ref k)
+                if shortcut!(k == shortcuts[CompactListing::DESCRIPTION]["open_thread"]) =>
+            {
+                if let Some(coordinates) = self.rows.get(self.cursor_pos).cloned() {
+                    self.view = Some(MailView::new(coordinates, None, None, context));
+                    self.dirty = true;
+                }
+                return true;
+            }
+            UIEvent::Input(ref k)
+                if shortcut!(k == shortcuts[Listing::DESCRIPTION]["scroll_up"]) =>
+            {
+                self.movement = Some(PageMovement::Up(1));
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(ref k)
+                if shortcut!(k == shortcuts[Listing::DESCRIPTION]["scroll_down"]) =>
+            {
+                self.movement = Some(PageMovement::Down(1));
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(ref k)
+                if shortcut!(k == shortcuts[Listing::DESCRIPTION]["prev_page"]) =>
+            {
+                self.movement = Some(PageMovement::PageUp(1));
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(ref k)
+                if shortcut!(k == shortcuts[Listing::DESCRIPTION]["next_page"]) =>
+            {
+                self.movement = Some(PageMovement::PageDown(1));
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(ref k)
+                if shortcut!(k == shortcuts[Listing::DESCRIPTION]["set_seen"]) =>
+            {
+                self.perform_action(&ListingAction::SetSeen, context);
+                return true;
+            }
+            UIEvent::Input(ref k) if shortcut!(k == shortcuts[Listing::DESCRIPTION]["archive"]) => {
+                self.perform_action(&ListingAction::Archive, context);
+                return true;
+            }
+            UIEvent::Action(Action::Listing(
+                ref a @ ListingAction::SetSeen
+                | ref a @ ListingAction::SetUnseen
+                | ref a @ ListingAction::Delete
+                | ref a @ ListingAction::Purge
+                | ref a @ ListingAction::Archive
+                | ref a @ ListingAction::Snooze(_)
+                | ref a @ ListingAction::CopyTo(_)
+                | ref a @ ListingAction::MoveTo(_)
+                | ref a @ ListingAction::Export(_, _, _)
+                | ref a @ ListingAction::SaveThreadAttachments(_)
+                | ref a @ ListingAction::Tag(_),
+            )) if self.view.is_none() => {
+                let a = a.clone();
+                self.perform_action(&a, context);
+                return true;
+            }
+            UIEvent::ConfigReload { old_settings: _ } => {
+                self.set_colors(context);
+                self.set_dirty(true);
+            }
+            UIEvent::MailboxUpdate((account_hash, mailbox_hash))
+                if self.mailboxes.contains(&(account_hash, mailbox_hash)) =>
+            {
+                self.refresh(context);
+            }
+            UIEvent::StartupCheck(mailbox_hash)
+                if self.mailboxes.iter().any(|(_, m)| *m == mailbox_hash) =>
+            {
+                self.refresh(context);
+            }
+            UIEvent::EnvelopeRename(_, ref new_hash) | UIEvent::EnvelopeUpdate(ref new_hash) => {
+                if self.mailboxes.iter().any(|(a, m)| {
+                    context.accounts[a]
+                        .collection
+                        .get_mailbox(*m)
+                        .contains(new_hash)
+                }) {
+                    self.refresh(context);
+                }
+            }
+            UIEvent::EnvelopeRemove(ref env_hash, _) => {
+                if self.rows.iter().any(|(_, _, h)| h == env_hash) {
+                    self.refresh(context);
+                }
+            }
+            UIEvent::Resize | UIEvent::ChangeMode(UIMode::Normal) => {
+                self.set_dirty(true);
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty || self.view.as_ref().map(|v| v.is_dirty()).unwrap_or(false)
+    }
+
+    fn set_dirty(&mut self, value: bool) {
+        self.dirty = value;
+        if let Some(ref mut view) = self.view {
+            view.set_dirty(value);
+        }
+    }
+
+    fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
+        let mut map = if let Some(ref view) = self.view {
+            view.get_shortcuts(context)
+        } else {
+            ShortcutMaps::default()
+        };
+
+        let config_map = context.settings.shortcuts.compact_listing.key_values();
+        map.insert(CompactListing::DESCRIPTION, config_map);
+        let config_map = context.settings.shortcuts.listing.key_values();
+        map.insert(Listing::DESCRIPTION, config_map);
+
+        map
+    }
+
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: ComponentId) {
+        self.id = id;
+    }
+}
@@ -44,8 +44,10 @@ pub async fn verify(a: Attachment) -> Result<()> {
     ctx.verify(sig, data)?.await
 }
 
+/// Sign with `sign_keys`, or if there are none with the secret keys matching `sign_key`.
 pub fn sign_filter(
     sign_keys: Vec<Key>,
+    sign_key: Option<String>,
 ) -> Result<
     impl FnOnce(AttachmentBuilder) -> Pin<Box<dyn Future<Output = Result<AttachmentBuilder>> + Send>>
         + Send,
@@ -55,6 +57,10 @@ pub fn sign_filter(
             Box::pin(async move {
                 let a: Attachment = a.into();
                 let mut ctx = Context::new()?;
+                let sign_keys = match sign_key {
+                    Some(pattern) if sign_keys.is_empty() => ctx.keylist(true, Some(pattern))?.await?,
+                    _ => sign_keys,
+                };
                 let data = ctx.new_data_mem(&melib_pgp::convert_attachment_to_rfc_spec(
                                 a.into_raw().as_bytes(),
                 ))?;
@@ -83,6 +89,7 @@ pub fn sign_filter(
 
 pub fn encrypt_filter(
     sign_keys: Option<Vec<Key>>,
+    sign_key: Option<String>,
     encrypt_keys: Vec<Key>,
 ) -> Result<
     impl FnOnce(AttachmentBuilder) -> Pin<Box<dyn Future<Output = Result<AttachmentBuilder>> + Send>>
@@ -94,6 +101,10 @@ pub fn encrypt_filter(
                 let a: Attachment = a.into();
                 debug!("main attachment is {:?}", &a);
                 let mut ctx = Context::new()?;
+                let sign_keys = match (sign_keys, sign_key) {
+                    (Some(sign_keys), Some(pattern)) if sign_keys.is_empty() => Some(ctx.keylist(true, Some(pattern))?.await?),
+                    (sign_keys, _) => sign_keys,
+                };
                 let data = ctx.new_data_mem(
                                 a.into_raw().as_bytes()
                 )?;
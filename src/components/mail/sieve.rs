@@ -0,0 +1,504 @@
+/*
+ * meli - sieve scripts tab module.
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Manage the server-side Sieve scripts of an IMAP account with ManageSieve.
+ */
+use super::*;
+use crate::jobs::JoinHandle;
+use melib::backends::imap::managesieve::{new_managesieve_connection, SieveScript};
+use melib::backends::{BackendEvent, BackendEventConsumer};
+use std::process::{Command, Stdio};
+use std::sync::Arc;
+
+#[derive(Debug)]
+enum SieveOperation {
+    List,
+    Get(String),
+    Put(String, String),
+    Check(String),
+    SetActive(String),
+    Delete(String),
+}
+
+#[derive(Debug)]
+enum SieveReply {
+    Scripts(Vec<SieveScript>),
+    Script(String, String),
+    Checked(String),
+}
+
+#[derive(Debug)]
+pub struct SieveScripts {
+    account_hash: AccountHash,
+    scripts: Vec<SieveScript>,
+    cursor_pos: usize,
+    /// A job in progress, and the script it uploads if any.
+    job: Option<(JoinHandle<Result<SieveReply>>, Option<(String, String)>)>,
+    /// An edited script the server rejected, reopened on the next edit of the same name.
+    rejected: Option<(String, String)>,
+    /// Name being typed for a new script.
+    new_name: Option<String>,
+    /// Script waiting for a second delete key press.
+    delete_confirmation: Option<String>,
+    status: String,
+    theme_default: ThemeAttribute,
+    dirty: bool,
+    id: ComponentId,
+}
+
+impl fmt::Display for SieveScripts {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "{}", SieveScripts::DESCRIPTION)
+    }
+}
+
+impl SieveScripts {
+    pub const DESCRIPTION: &'static str = "sieve scripts";
+
+    pub fn new(account_hash: AccountHash, context: &mut Context) -> Self {
+        let mut ret = SieveScripts {
+            account_hash,
+            scripts: vec![],
+            cursor_pos: 0,
+            job: None,
+            rejected: None,
+            new_name: None,
+            delete_confirmation: None,
+            status: String::new(),
+            theme_default: crate::conf::value(context, "theme_default"),
+            dirty: true,
+            id: ComponentId::new_v4(),
+        };
+        ret.spawn(SieveOperation::List, context);
+        ret
+    }
+
+    fn selected(&self) -> Option<&SieveScript> {
+        self.scripts.get(self.cursor_pos)
+    }
+
+    fn spawn(&mut self, operation: SieveOperation, context: &mut Context) {
+        if self.job.is_some() {
+            context
+                .replies
+                .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                    "Another ManageSieve command is in progress.".into(),
+                )));
+            return;
+        }
+        let account = &context.accounts[&self.account_hash];
+        let sender = context.sender.clone();
+        let conn = new_managesieve_connection(
+            self.account_hash,
+            account.name().to_string(),
+            account.settings.account(),
+            BackendEventConsumer::new(Arc::new(
+                move |account_hash: AccountHash, ev: BackendEvent| {
+                    sender
+                        .send(ThreadEvent::UIEvent(UIEvent::BackendEvent(
+                            account_hash,
+                            ev,
+                        )))
+                        .unwrap();
+                },
+            )),
+        );
+        let mut conn = match conn {
+            Ok(conn) => conn,
+            Err(err) => {
+                self.status = err.to_string();
+                self.set_dirty(true);
+                return;
+            }
+        };
+        let upload = if let SieveOperation::Put(ref name, ref script) = operation {
+            Some((name.clone(), script.clone()))
+        } else {
+            None
+        };
+        self.status = match operation {
+            SieveOperation::List => "Loading scripts…".to_string(),
+            SieveOperation::Get(ref name) => format!("Downloading {}…", name),
+            SieveOperation::Put(ref name, _) => format!("Checking and uploading {}…", name),
+            SieveOperation::Check(ref name) => format!("Checking {}…", name),
+            SieveOperation::SetActive(ref name) if name.is_empty() => {
+                "Deactivating scripts…".to_string()
+            }
+            SieveOperation::SetActive(ref name) => format!("Activating {}…", name),
+            SieveOperation::Delete(ref name) => format!("Deleting {}…", name),
+        };
+        let fut = async move {
+            conn.connect().await?;
+            let ret = match operation {
+                SieveOperation::List => SieveReply::Scripts(conn.listscripts().await?),
+                SieveOperation::Get(name) => {
+                    let script = conn.getscript(&name).await?;
+                    SieveReply::Script(name, script)
+                }
+                SieveOperation::Put(name, script) => {
+                    conn.checkscript(&script).await?;
+                    conn.havespace(&name, script.len()).await?;
+                    conn.putscript(&name, &script).await?;
+                    SieveReply::Scripts(conn.listscripts().await?)
+                }
+                SieveOperation::Check(name) => {
+                    let script = conn.getscript(&name).await?;
+                    conn.checkscript(&script).await?;
+                    SieveReply::Checked(name)
+                }
+                SieveOperation::SetActive(name) => {
+                    conn.setactive(&name).await?;
+                    SieveReply::Scripts(conn.listscripts().await?)
+                }
+                SieveOperation::Delete(name) => {
+                    conn.deletescript(&name).await?;
+                    SieveReply::Scripts(conn.listscripts().await?)
+                }
+            };
+            let _ = conn.logout().await;
+            Ok(ret)
+        };
+        let handle = context.accounts[&self.account_hash]
+            .job_executor
+            .spawn_specialized(fut);
+        context
+            .replies
+            .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(handle.job_id)));
+        self.job = Some((handle, upload));
+        self.set_dirty(true);
+    }
+
+    /// Edit `script` in the user's editor and upload it if it was changed.
+    fn edit(&mut self, name: String, script: String, context: &mut Context) {
+        let editor = if let Some(editor_command) =
+            account_settings!(context[self.account_hash].composing.editor_command).as_ref()
+        {
+            editor_command.to_string()
+        } else {
+            match std::env::var("EDITOR") {
+                Err(err) => {
+                    context.replies.push_back(UIEvent::Notification(
+                        Some(err.to_string()),
+                        "$EDITOR is not set. You can change an envvar's value with setenv or set composing.editor_command setting in your configuration.".to_string(),
+                        Some(NotificationType::Error(melib::error::ErrorKind::None)),
+                    ));
+                    return;
+                }
+                Ok(v) => v,
+            }
+        };
+        let f = create_temp_file(script.as_bytes(), None, None, true);
+        /* Kill input thread so that spawned command can be sole receiver of stdin */
+        context.input_kill();
+        let editor_command = format!("{} {}", editor, f.path().display());
+        match Command::new("sh")
+            .args(&["-c", &editor_command])
+            .stdin(Stdio::inherit())
+            .stdout(Stdio::inherit())
+            .spawn()
+        {
+            Ok(mut child) => {
+                let _ = child.wait();
+            }
+            Err(err) => {
+                context.replies.push_back(UIEvent::Notification(
+                    Some(format!("Failed to execute {}: {}", editor, err)),
+                    err.to_string(),
+                    Some(NotificationType::Error(melib::error::ErrorKind::External)),
+                ));
+                context.replies.push_back(UIEvent::Fork(ForkType::Finished));
+                context.restore_input();
+                return;
+            }
+        }
+        context.replies.push_back(UIEvent::Fork(ForkType::Finished));
+        let new_script = f.read_to_string();
+        if new_script == script || new_script.trim().is_empty() {
+            self.status = format!("{} was not changed.", name);
+            self.set_dirty(true);
+            return;
+        }
+        self.spawn(SieveOperation::Put(name, new_script), context);
+    }
+
+    fn open_script(&mut self, name: String, context: &mut Context) {
+        match self.rejected.take() {
+            Some((rejected_name, script)) if rejected_name == name => {
+                self.edit(name, script, context);
+            }
+            rejected => {
+                self.rejected = rejected;
+                if self.scripts.iter().any(|s| s.name == name) {
+                    self.spawn(SieveOperation::Get(name), context);
+                } else {
+                    self.edit(name, String::new(), context);
+                }
+            }
+        }
+    }
+}
+
+impl Component for SieveScripts {
+    fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
+        if !self.is_dirty() {
+            return;
+        }
+        self.dirty = false;
+        clear_area(grid, area, self.theme_default);
+        let upper_left = upper_left!(area);
+        let bottom_right = bottom_right!(area);
+        let header_attrs = crate::conf::value(context, "widgets.list.header");
+        let highlight_attrs = crate::conf::value(context, "widgets.options.highlighted");
+        write_string_to_grid(
+            &format!(
+                "Sieve scripts of {}",
+                context.accounts[&self.account_hash].name()
+            ),
+            grid,
+            header_attrs.fg,
+            header_attrs.bg,
+            header_attrs.attrs,
+            (upper_left, bottom_right),
+            None,
+        );
+        let rows = height!(area).saturating_sub(4);
+        let top = if rows == 0 {
+            0
+        } else {
+            (self.cursor_pos / rows) * rows
+        };
+        for (i, script) in self.scripts.iter().enumerate().skip(top).take(rows) {
+            let y = get_y(upper_left) + 2 + i - top;
+            let theme = if i == self.cursor_pos {
+                highlight_attrs
+            } else {
+                self.theme_default
+            };
+            let (x, _) = write_string_to_grid(
+                &format!("{} {}", if script.active { "*" } else { " " }, script.name),
+                grid,
+                theme.fg,
+                theme.bg,
+                theme.attrs,
+                ((get_x(upper_left), y), (get_x(bottom_right), y)),
+                None,
+            );
+            if script.active {
+                write_string_to_grid(
+                    " (active)",
+                    grid,
+                    theme.fg,
+                    theme.bg,
+                    theme.attrs | Attr::BOLD,
+                    ((x, y), (get_x(bottom_right), y)),
+                    None,
+                );
+            }
+            if i == self.cursor_pos {
+                change_colors(grid, ((x, y), (get_x(bottom_right), y)), theme.fg, theme.bg);
+            }
+        }
+        let status = if let Some(ref name) = self.new_name {
+            format!("New script name: {}", name)
+        } else {
+            self.status.clone()
+        };
+        let y = get_y(bottom_right);
+        write_string_to_grid(
+            &status,
+            grid,
+            self.theme_default.fg,
+            self.theme_default.bg,
+            self.theme_default.attrs,
+            ((get_x(upper_left), y), (get_x(bottom_right), y)),
+            None,
+        );
+        context.dirty_areas.push_back(area);
+    }
+
+    fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        if let Some(ref mut name) = self.new_name {
+            match event {
+                UIEvent::Input(Key::Char('\n')) => {
+                    let name = self.new_name.take().unwrap();
+                    if !name.trim().is_empty() {
+                        self.open_script(name.trim().to_string(), context);
+                    }
+                    self.set_dirty(true);
+                    return true;
+                }
+                UIEvent::Input(Key::Esc) => {
+                    self.new_name = None;
+                    self.set_dirty(true);
+                    return true;
+                }
+                UIEvent::Input(Key::Backspace) => {
+                    name.pop();
+                    self.set_dirty(true);
+                    return true;
+                }
+                UIEvent::Input(Key::Char(c)) => {
+                    name.push(*c);
+                    self.set_dirty(true);
+                    return true;
+                }
+                _ => {}
+            }
+        }
+        let shortcuts = self.get_shortcuts(context);
+        match event {
+            UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
+                if self
+                    .job
+                    .as_ref()
+                    .map(|(h, _)| h.job_id == *job_id)
+                    .unwrap_or(false) =>
+            {
+                let (mut handle, upload) = self.job.take().unwrap();
+                self.status.clear();
+                match handle.chan.try_recv() {
+                    Err(_) | Ok(None) => { /* Job was canceled */ }
+                    Ok(Some(Ok(SieveReply::Scripts(scripts)))) => {
+                        if let Some((name, _)) = upload {
+                            self.status = format!("{} was uploaded.", name);
+                        }
+                        self.scripts = scripts;
+                        self.cursor_pos =
+                            std::cmp::min(self.cursor_pos, self.scripts.len().saturating_sub(1));
+                    }
+                    Ok(Some(Ok(SieveReply::Script(name, script)))) => {
+                        self.edit(name, script, context);
+                    }
+                    Ok(Some(Ok(SieveReply::Checked(name)))) => {
+                        self.status = format!("{} has no errors.", name);
+                    }
+                    Ok(Some(Err(err))) => {
+                        if let Some((name, script)) = upload {
+                            self.status = format!(
+                                "{} was not uploaded. Edit it again to fix the errors.",
+                                name
+                            );
+                            self.rejected = Some((name, script));
+                        }
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("ManageSieve error".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                    }
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::ConfigReload { old_settings: _ } => {
+                self.theme_default = crate::conf::value(context, "theme_default");
+                self.set_dirty(true);
+            }
+            UIEvent::Resize => {
+                self.set_dirty(true);
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["scroll_up"]) => {
+                self.cursor_pos = self.cursor_pos.saturating_sub(1);
+                self.delete_confirmation = None;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["scroll_down"]) => {
+                if self.cursor_pos + 1 < self.scripts.len() {
+                    self.cursor_pos += 1;
+                }
+                self.delete_confirmation = None;
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["edit_script"]) => {
+                if let Some(name) = self.selected().map(|s| s.name.clone()) {
+                    self.open_script(name, context);
+                }
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["new_script"]) => {
+                self.new_name = Some(String::new());
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["activate_script"]) => {
+                if let Some(name) = self.selected().map(|s| s.name.clone()) {
+                    self.spawn(SieveOperation::SetActive(name), context);
+                }
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts["sieve"]["deactivate_scripts"]) =>
+            {
+                self.spawn(SieveOperation::SetActive(String::new()), context);
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["check_script"]) => {
+                if let Some(name) = self.selected().map(|s| s.name.clone()) {
+                    self.spawn(SieveOperation::Check(name), context);
+                }
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["delete_script"]) => {
+                if let Some(name) = self.selected().map(|s| s.name.clone()) {
+                    if self.delete_confirmation.as_ref() == Some(&name) {
+                        self.delete_confirmation = None;
+                        self.spawn(SieveOperation::Delete(name), context);
+                    } else {
+                        self.status = format!("Press the delete key again to delete {}.", name);
+                        self.delete_confirmation = Some(name);
+                        self.set_dirty(true);
+                    }
+                }
+                return true;
+            }
+            UIEvent::Input(ref key) if shortcut!(key == shortcuts["sieve"]["refresh"]) => {
+                self.spawn(SieveOperation::List, context);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
+        let mut map = ShortcutMaps::default();
+        map.insert("sieve", context.settings.shortcuts.sieve.key_values());
+        map
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, value: bool) {
+        self.dirty = value;
+    }
+
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: ComponentId) {
+        self.id = id;
+    }
+}
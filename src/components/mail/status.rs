@@ -151,7 +151,7 @@ impl Component for AccountStatus {
         );
         width = self.content.size().0;
         write_string_to_grid(
-            if a.backend_capabilities.supports_tags {
+            if a.backend_capabilities.tag_support.can_set() {
                 "yes"
             } else {
                 "no"
@@ -188,7 +188,7 @@ impl Component for AccountStatus {
                 }
                 #[cfg(feature = "sqlite3")]
                 (SearchBackend::Sqlite3, _) => {
-                    if let Ok(path) = crate::sqlite3::db_path() {
+                    if let Ok(path) = crate::sqlite3::db_path(a.name()) {
                         format!("sqlite3 database {}", path.display())
                     } else {
                         "sqlite3 database".to_string()
@@ -204,6 +204,32 @@ impl Component for AccountStatus {
         );
         width = self.content.size().0;
         line += 1;
+        if a.settings.account().format() == "imap" {
+            let (_x, _y) = write_string_to_grid(
+                "Sieve scripts: ",
+                &mut self.content,
+                self.theme_default.fg,
+                self.theme_default.bg,
+                Attr::BOLD,
+                ((1, line), (width - 1, line)),
+                None,
+            );
+            width = self.content.size().0;
+            write_string_to_grid(
+                &format!(
+                    "press {} to manage the server-side filters",
+                    context.settings.shortcuts.sieve.open_scripts
+                ),
+                &mut self.content,
+                self.theme_default.fg,
+                self.theme_default.bg,
+                self.theme_default.attrs,
+                ((_x, _y), (width - 1, _y)),
+                None,
+            );
+            width = self.content.size().0;
+            line += 1;
+        }
 
         write_string_to_grid(
             "Special Mailboxes:",
@@ -432,6 +458,21 @@ impl Component for AccountStatus {
                 self.dirty = true;
                 return true;
             }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts["sieve"]["open_scripts"])
+                    && context.accounts[self.account_pos]
+                        .settings
+                        .account()
+                        .format()
+                        == "imap" =>
+            {
+                let account_hash = context.accounts[self.account_pos].hash();
+                let sieve = SieveScripts::new(account_hash, context);
+                context
+                    .replies
+                    .push_back(UIEvent::Action(Tab(New(Some(Box::new(sieve))))));
+                return true;
+            }
             UIEvent::MailboxUpdate(_)
             | UIEvent::StatusEvent(StatusEvent::NewJob(_))
             | UIEvent::StatusEvent(StatusEvent::JobFinished(_))
@@ -448,6 +489,7 @@ impl Component for AccountStatus {
             context.settings.shortcuts.general.key_values();
         let mut ret: ShortcutMaps = Default::default();
         ret.insert("general", config_map);
+        ret.insert("sieve", context.settings.shortcuts.sieve.key_values());
         ret
     }
 
@@ -21,8 +21,10 @@
 
 use super::*;
 use crate::conf::accounts::JobRequest;
-use crate::jobs::{JobId, JoinHandle};
-use melib::email::attachment_types::ContentType;
+use crate::jobs::{JobId, JobPriority, JoinHandle};
+use melib::email::address::domains_to_unicode;
+use melib::email::attachment_types::{Charset, ContentTransferEncoding, ContentType, Text};
+use melib::icalendar::{Calendar, PartStat};
 use melib::list_management;
 use melib::parser::BytesExt;
 use smallvec::SmallVec;
@@ -35,13 +37,19 @@ use std::process::{Command, Stdio};
 
 mod html;
 pub use self::html::*;
+mod image;
+pub use self::image::*;
 mod thread;
 pub use self::thread::*;
 
 mod envelope;
 pub use self::envelope::*;
 
+mod display_filter;
+pub use self::display_filter::*;
+
 use linkify::LinkFinder;
+use melib::text_processing::diff::looks_like_patch;
 use xdg_utils::query_default_app;
 
 #[derive(PartialEq, Copy, Clone, Debug)]
@@ -155,6 +163,7 @@ pub struct MailView {
     initialised: bool,
     mode: ViewMode,
     expand_headers: bool,
+    raw_headers: bool,
     attachment_tree: String,
     attachment_paths: Vec<Vec<usize>>,
     headers_no: usize,
@@ -162,7 +171,11 @@ pub struct MailView {
     force_draw_headers: bool,
     theme_default: ThemeAttribute,
     active_jobs: HashSet<JobId>,
+    /// The command input is being piped to, and its job.
+    pipe_job: Option<(String, JoinHandle<Result<std::process::Output>>)>,
     state: MailViewState,
+    /// Marks the message as seen when it fires, see `pager.auto_mark_read_delay`.
+    mark_read_timer: Option<crate::jobs::Timer>,
 
     cmd_buf: String,
     id: ComponentId,
@@ -173,6 +186,7 @@ pub enum PendingReplyAction {
     Reply,
     ReplyToAuthor,
     ReplyToAll,
+    ReplyToList,
 }
 
 #[derive(Debug)]
@@ -228,6 +242,8 @@ impl Clone for MailView {
             attachment_paths: self.attachment_paths.clone(),
             state: MailViewState::default(),
             active_jobs: self.active_jobs.clone(),
+            pipe_job: None,
+            mark_read_timer: None,
             ..*self
         }
     }
@@ -255,6 +271,7 @@ impl MailView {
             initialised: false,
             mode: ViewMode::Normal,
             expand_headers: false,
+            raw_headers: false,
             attachment_tree: String::new(),
             attachment_paths: vec![],
 
@@ -264,7 +281,9 @@ impl MailView {
 
             theme_default: crate::conf::value(context, "mail.view.body"),
             active_jobs: Default::default(),
+            pipe_job: None,
             state: MailViewState::default(),
+            mark_read_timer: None,
 
             cmd_buf: String::with_capacity(4),
             id: ComponentId::new_v4(),
@@ -286,7 +305,9 @@ impl MailView {
                     .and_then(|mut op| op.as_bytes())
                 {
                     Ok(fut) => {
-                        let mut handle = account.job_executor.spawn_specialized(fut);
+                        let mut handle = account
+                            .job_executor
+                            .spawn_prioritized(fut, JobPriority::Interactive);
                         let job_id = handle.job_id;
                         pending_action = if let MailViewState::Init {
                             ref mut pending_action,
@@ -353,12 +374,85 @@ impl MailView {
                     }
                 }
             }
+            if let Some(info) = crate::ipc::MessageInfo::new(
+                context,
+                self.coordinates.0,
+                self.coordinates.1,
+                self.coordinates.2,
+            ) {
+                context
+                    .plugins
+                    .emit(crate::ipc::PluginEvent::MessageOpen(info));
+            }
+            let auto_mark_read = *mailbox_settings!(
+                context[self.coordinates.0][&self.coordinates.1]
+                    .pager
+                    .auto_mark_read
+            );
+            let delay = *mailbox_settings!(
+                context[self.coordinates.0][&self.coordinates.1]
+                    .pager
+                    .auto_mark_read_delay
+            );
+            self.mark_read_timer = None;
+            if auto_mark_read {
+                if delay == 0 {
+                    self.mark_as_read(context);
+                } else {
+                    self.mark_read_timer = Some(context.job_executor.clone().create_timer(
+                        std::time::Duration::from_secs(0),
+                        std::time::Duration::from_secs(delay),
+                    ));
+                }
+            }
+        }
+        if let Some(p) = pending_action {
+            self.perform_action(p, context);
+        }
+    }
+
+    /// Set the message as seen, collecting its addresses if `composing.collect_addresses` is set.
+    fn mark_as_read(&mut self, context: &mut Context) {
+        let collect_addresses =
+            *account_settings!(context[self.coordinates.0].composing.collect_addresses);
+        let account = &mut context.accounts[&self.coordinates.0];
+        if !account.collection.get_env(self.coordinates.2).is_seen() {
+            if collect_addresses {
+                let addresses = {
+                    let env = account.collection.get_env(self.coordinates.2);
+                    env.from()
+                        .iter()
+                        .chain(env.to().iter())
+                        .chain(env.cc.iter())
+                        .cloned()
+                        .collect::<Vec<Address>>()
+                };
+                let ignore = account_settings!(
+                    context[self.coordinates.0]
+                        .composing
+                        .collect_addresses_ignore
+                )
+                .clone();
+                context.accounts[&self.coordinates.0].collect_addresses(&addresses, &ignore);
+            }
+            let act_on_duplicates = *mailbox_settings!(
+                context[self.coordinates.0][&self.coordinates.1]
+                    .listing
+                    .act_on_duplicates
+            );
             let account = &mut context.accounts[&self.coordinates.0];
-            if !account.collection.get_env(self.coordinates.2).is_seen() {
+            let mut targets: SmallVec<[(EnvelopeHash, MailboxHash); 8]> = smallvec::smallvec![(
+                self.coordinates.2,
+                account.envelope_source_mailbox(self.coordinates.2, self.coordinates.1)
+            )];
+            if act_on_duplicates {
+                targets.extend(account.duplicates(self.coordinates.2));
+            }
+            for (env_hash, mailbox_hash) in targets {
                 let job = account.backend.write().unwrap().set_flags(
-                    self.coordinates.2.into(),
-                    self.coordinates.1,
-                    smallvec::smallvec![(Ok(Flag::SEEN), true)],
+                    env_hash.into(),
+                    mailbox_hash,
+                    smallvec::smallvec![FlagOp::Flag(Flag::SEEN, true)],
                 );
                 match job {
                     Ok(fut) => {
@@ -366,7 +460,7 @@ impl MailView {
                         account.insert_job(
                             handle.job_id,
                             JobRequest::SetFlags {
-                                env_hashes: self.coordinates.2.into(),
+                                env_hashes: env_hash.into(),
                                 handle,
                             },
                         );
@@ -382,9 +476,6 @@ impl MailView {
                 };
             }
         }
-        if let Some(p) = pending_action {
-            self.perform_action(p, context);
-        }
     }
 
     fn perform_action(&mut self, action: PendingReplyAction, context: &mut Context) {
@@ -425,6 +516,11 @@ impl MailView {
                 reply_body,
                 context,
             )),
+            PendingReplyAction::ReplyToList => Box::new(Composer::reply_to_list(
+                self.coordinates,
+                reply_body,
+                context,
+            )),
         };
 
         context
@@ -707,15 +803,60 @@ impl MailView {
             acc: &mut Vec<AttachmentDisplay>,
             active_jobs: &mut HashSet<JobId>,
         ) {
+            let filter = match a.content_type {
+                ContentType::Multipart { .. } => None,
+                _ => display_filter(context, (coordinates.0, coordinates.1), &a.mime_type()),
+            };
             if a.content_disposition.kind.is_attachment() || a.content_type == "message/rfc822" {
                 acc.push(AttachmentDisplay::Attachment { inner: a.clone() });
+            } else if let Some(filter) = filter {
+                let width =
+                    *mailbox_settings!(context[coordinates.0][&coordinates.1].pager.minimum_width);
+                let (comment, text) = match run_display_filter(a, &filter, width) {
+                    Ok(text) => (
+                        format!("Text filtered through `{}`. \n\n", filter.join(" | ")),
+                        text,
+                    ),
+                    Err(err) => (
+                        format!("{} \n\n", err),
+                        if a.is_text() {
+                            String::from_utf8_lossy(&decode(a, None)).to_string()
+                        } else {
+                            String::new()
+                        },
+                    ),
+                };
+                acc.push(AttachmentDisplay::InlineText {
+                    inner: a.clone(),
+                    comment: Some(comment),
+                    text,
+                });
             } else if a.content_type().is_text_html() {
                 let bytes = decode(a, None);
-                let filter_invocation =
+                let filter_invocation = if let Some(filter_invocation) =
                     mailbox_settings!(context[coordinates.0][&coordinates.1].pager.html_filter)
                         .as_ref()
-                        .map(|s| s.as_str())
-                        .unwrap_or("w3m -I utf-8 -T text/html");
+                {
+                    filter_invocation.as_str()
+                } else {
+                    let width = *mailbox_settings!(
+                        context[coordinates.0][&coordinates.1].pager.minimum_width
+                    );
+                    let text = melib::text_processing::html::html_to_text(
+                        &String::from_utf8_lossy(&bytes),
+                        width,
+                    )
+                    .text;
+                    acc.push(AttachmentDisplay::InlineText {
+                        inner: a.clone(),
+                        comment: Some(
+                            "Text rendered from HTML. Press `v` to open in web browser. \n\n"
+                                .to_string(),
+                        ),
+                        text,
+                    });
+                    return;
+                };
                 let command_obj = Command::new("sh")
                     .args(&["-c", filter_invocation])
                     .stdin(Stdio::piped())
@@ -764,11 +905,40 @@ impl MailView {
                         });
                     }
                 }
+            } else if a.content_type == "text/calendar" {
+                let text = String::from_utf8_lossy(&decode(a, None)).to_string();
+                match Calendar::from_str(&text) {
+                    Ok(calendar) if !calendar.events.is_empty() => {
+                        acc.push(AttachmentDisplay::InlineText {
+                            inner: a.clone(),
+                            comment: if calendar.is_request() {
+                                Some("Calendar invitation. Reply with `rsvp accept`, `rsvp tentative` or `rsvp decline`. \n\n".to_string())
+                            } else {
+                                None
+                            },
+                            text: calendar.to_string(),
+                        });
+                    }
+                    _ => {
+                        acc.push(AttachmentDisplay::InlineText {
+                            inner: a.clone(),
+                            comment: None,
+                            text,
+                        });
+                    }
+                }
             } else if a.is_text() {
                 let bytes = decode(a, None);
                 acc.push(AttachmentDisplay::InlineText {
                     inner: a.clone(),
-                    comment: None,
+                    comment: if is_vcard(a) {
+                        Some(
+                            "vCard. Add it to the address book with `import-vcard`. \n\n"
+                                .to_string(),
+                        )
+                    } else {
+                        None
+                    },
                     text: String::from_utf8_lossy(&bytes).to_string(),
                 });
             } else if let ContentType::Multipart {
@@ -807,6 +977,14 @@ impl MailView {
                                 chosen_attachment_idx = text_attachment_pos;
                             }
                         }
+                        /* Show the event summary of invitations instead of their text */
+                        if let Some(calendar_pos) = parts.iter().position(|a| {
+                            a.content_type == "text/calendar"
+                                && Calendar::from_str(&String::from_utf8_lossy(&decode(a, None)))
+                                    .is_ok()
+                        }) {
+                            chosen_attachment_idx = calendar_pos;
+                        }
                         for a in parts {
                             rec(a, context, coordinates, &mut display, active_jobs);
                         }
@@ -926,6 +1104,95 @@ impl MailView {
         }
     }
 
+    /// Pipe `input` to `command` with `sh -c` in a job and show its output in a pager when it
+    /// finishes, if there is any. The command runs in `current_dir` if given.
+    fn pipe_to_command(
+        &mut self,
+        input: &[u8],
+        command: &str,
+        current_dir: Option<&std::path::Path>,
+        context: &mut Context,
+    ) {
+        let mut command_builder = Command::new("sh");
+        command_builder.args(&["-c", command]);
+        if let Some(dir) = current_dir {
+            command_builder.current_dir(dir);
+        }
+        let input = input.to_vec();
+        let command_ = command.to_string();
+        let handle = context.job_executor.spawn_blocking(async move {
+            let mut child = command_builder
+                .stdin(Stdio::piped())
+                .stdout(Stdio::piped())
+                .stderr(Stdio::piped())
+                .spawn()
+                .map_err(|err| {
+                    MeliError::new(err.to_string())
+                        .set_summary(format!("Failed to start `{}`", command_))
+                        .set_kind(melib::ErrorKind::External)
+                })?;
+            /* Write from another thread so that a command that doesn't read all of its input
+             * before writing output can't block us. */
+            let mut stdin = child.stdin.take().unwrap();
+            let writer = std::thread::spawn(move || stdin.write_all(&input));
+            let output = child.wait_with_output().map_err(|err| {
+                MeliError::new(err.to_string())
+                    .set_summary(format!("Failed to wait on `{}`", command_))
+                    .set_kind(melib::ErrorKind::External)
+            })?;
+            let _ = writer.join();
+            Ok(output)
+        });
+        context
+            .replies
+            .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(handle.job_id)));
+        self.pipe_job = Some((command.to_string(), handle));
+    }
+
+    /// Report how the command of `pipe_to_command` exited and show its output.
+    fn show_pipe_output(
+        &mut self,
+        command: &str,
+        output: std::process::Output,
+        context: &mut Context,
+    ) {
+        if !output.status.success() {
+            context.replies.push_back(UIEvent::Notification(
+                Some(format!("`{}` exited with {}", command, output.status)),
+                String::from_utf8_lossy(&output.stderr).to_string(),
+                Some(NotificationType::Error(melib::ErrorKind::External)),
+            ));
+        } else {
+            context
+                .replies
+                .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                    "Piped to `{}`",
+                    command
+                ))));
+        }
+        if output.stdout.is_empty()
+            || !*mailbox_settings!(
+                context[self.coordinates.0][&self.coordinates.1]
+                    .pager
+                    .show_pipe_output
+            )
+        {
+            return;
+        }
+        let mut text = format!("Output of `{}`. Press `r` to return \n\n", command);
+        text.push_str(&String::from_utf8_lossy(&output.stdout));
+        let colors = crate::conf::value(context, "mail.view.body");
+        self.subview = Some(Box::new(Pager::from_string(
+            text,
+            Some(context),
+            None,
+            None,
+            colors,
+        )));
+        self.mode = ViewMode::Subview;
+        self.set_dirty(true);
+    }
+
     fn open_attachment(
         &'_ self,
         lidx: usize,
@@ -1072,7 +1339,7 @@ impl Component for MailView {
                 ) || height_p < height;
                 let (_, mut y) = upper_left;
                 macro_rules! print_header {
-                    ($(($header:literal, $string:expr)),*$(,)?) => {
+                    ($(($header:expr, $string:expr)),*$(,)?) => {
                         $({
                             if sticky || skip_header_ctr == 0 {
                                 if y <= get_y(bottom_right) {
@@ -1102,6 +1369,9 @@ impl Component for MailView {
                                         Some(get_x(upper_left)),
                                     );
                                     clear_area(grid, ((std::cmp::min(_x, get_x(bottom_right)), _y), (get_x(bottom_right), _y)), headers_area);
+                                    if context.settings.terminal.use_hyperlinks.is_true() {
+                                        set_hyperlinks(grid, (set_y(upper_left, y), set_y(bottom_right, _y)), &[linkify::LinkKind::Email]);
+                                    }
                                     y = _y + 1;
                                 }
                             } else {
@@ -1111,21 +1381,78 @@ impl Component for MailView {
                         })+
                     };
                 }
-                print_header!(
-                    ("Date:", envelope.date_as_str()),
-                    ("From:", envelope.field_from_to_string()),
-                    ("To:", envelope.field_to_to_string()),
-                );
-                if envelope.other_headers().contains_key("Cc")
-                    && !envelope.other_headers()["Cc"].is_empty()
-                {
-                    print_header!(("Cc:", envelope.field_cc_to_string()));
+                let list_actions = list_management::ListActions::detect(&envelope);
+                let raw_headers: Option<Vec<(String, String)>> = match self.state {
+                    MailViewState::Loaded { ref bytes, .. } if self.raw_headers => {
+                        melib::email::parser::headers::headers(bytes)
+                            .map(|(_, v)| {
+                                v.into_iter()
+                                    .map(|(name, value)| {
+                                        (
+                                            format!("{}:", String::from_utf8_lossy(name)),
+                                            String::from_utf8_lossy(value)
+                                                .replace("\r\n", "")
+                                                .replace('\n', ""),
+                                        )
+                                    })
+                                    .collect()
+                            })
+                            .ok()
+                    }
+                    _ => None,
+                };
+                if let Some(raw_headers) = raw_headers {
+                    for (name, value) in raw_headers {
+                        print_header!((&name, value));
+                    }
+                } else {
+                    for header in mailbox_settings!(
+                        context[self.coordinates.0][&self.coordinates.1]
+                            .pager
+                            .show_headers
+                    ) {
+                        let value = match header.to_ascii_lowercase().as_str() {
+                            "date" => crate::dates::format_date_header(
+                                &envelope,
+                                mailbox_settings!(
+                                    context[self.coordinates.0][&self.coordinates.1]
+                                        .pager
+                                        .datetime_fmt
+                                )
+                                .as_deref(),
+                                *mailbox_settings!(
+                                    context[self.coordinates.0][&self.coordinates.1]
+                                        .pager
+                                        .local_dates
+                                ),
+                            ),
+                            "from" => domains_to_unicode(&envelope.field_from_to_string()),
+                            "to" => domains_to_unicode(&envelope.field_to_to_string()),
+                            "cc" => domains_to_unicode(&envelope.field_cc_to_string()),
+                            "bcc" => domains_to_unicode(&envelope.field_bcc_to_string()),
+                            "subject" => envelope.subject().to_string(),
+                            "message-id" => format!("<{}>", envelope.message_id_raw()),
+                            /* Shown below along with the list actions */
+                            "list-id"
+                                if list_actions
+                                    .as_ref()
+                                    .map(|a| a.id.is_some())
+                                    .unwrap_or(false) =>
+                            {
+                                continue;
+                            }
+                            _ if envelope.other_headers().contains_key(header.as_str()) => {
+                                envelope.other_headers()[header.as_str()].to_string()
+                            }
+                            _ => continue,
+                        };
+                        if value.trim().is_empty() {
+                            continue;
+                        }
+                        print_header!((&format!("{}:", header), value));
+                    }
                 }
-                print_header!(
-                    ("Subject:", envelope.subject()),
-                    ("Message-ID:", format!("<{}>", envelope.message_id_raw()))
-                );
-                if self.expand_headers {
+                if self.expand_headers && !self.raw_headers {
                     if let Some(val) = envelope.in_reply_to_display() {
                         print_header!(
                             ("In-Reply-To:", val),
@@ -1146,7 +1473,8 @@ impl Component for MailView {
                     ref archive,
                     ref post,
                     ref unsubscribe,
-                }) = list_management::ListActions::detect(&envelope)
+                    ..
+                }) = list_actions
                 {
                     let mut x = get_x(upper_left);
                     if let Some(id) = id {
@@ -1337,14 +1665,37 @@ impl Component for MailView {
                 ViewMode::Attachment(aidx) => {
                     let mut text = "Viewing attachment. Press `r` to return \n".to_string();
                     if let Some(attachment) = self.open_attachment(aidx, context) {
-                        if attachment.is_html() {
+                        let filter = display_filter(
+                            context,
+                            (self.coordinates.0, self.coordinates.1),
+                            &attachment.mime_type(),
+                        );
+                        if let Some(filter) = filter {
+                            let width = *mailbox_settings!(
+                                context[self.coordinates.0][&self.coordinates.1]
+                                    .pager
+                                    .minimum_width
+                            );
+                            match run_display_filter(attachment, &filter, width) {
+                                Ok(filtered) => text.push_str(&filtered),
+                                Err(err) => text.push_str(&err.to_string()),
+                            }
+                            let colors = crate::conf::value(context, "mail.view.body");
+                            self.pager =
+                                Pager::from_string(text, Some(context), Some(0), None, colors);
+                            self.subview = None;
+                        } else if attachment.is_html() {
                             self.subview = Some(Box::new(HtmlView::new(&attachment, context)));
                             self.mode = ViewMode::Subview;
                         } else {
-                            text.push_str(&attachment.text());
+                            let attachment_text = attachment.text();
+                            let highlight_diff =
+                                is_patch(attachment) || looks_like_patch(&attachment_text);
+                            text.push_str(&attachment_text);
                             let colors = crate::conf::value(context, "mail.view.body");
                             self.pager =
                                 Pager::from_string(text, Some(context), Some(0), None, colors);
+                            self.pager.set_highlight_diff(highlight_diff);
                             self.subview = None;
                         }
                     } else {
@@ -1354,7 +1705,15 @@ impl Component for MailView {
                         self.subview = None;
                     }
                 }
-                ViewMode::Normal if body.is_html() => {
+                ViewMode::Normal
+                    if body.is_html()
+                        && display_filter(
+                            context,
+                            (self.coordinates.0, self.coordinates.1),
+                            &body.mime_type(),
+                        )
+                        .is_none() =>
+                {
                     self.subview = Some(Box::new(HtmlView::new(&body, context)));
                     self.mode = ViewMode::Subview;
                 }
@@ -1498,6 +1857,8 @@ impl Component for MailView {
                     let colors = crate::conf::value(context, "mail.view.body");
                     self.pager =
                         Pager::from_string(text, Some(context), Some(cursor_pos), None, colors);
+                    self.pager
+                        .set_highlight_diff(is_patch(body) || looks_like_patch(body_text));
                     self.subview = None;
                 }
             };
@@ -1528,6 +1889,31 @@ impl Component for MailView {
         }
         let shortcuts = self.get_shortcuts(context);
         match (&mut self.mode, &mut event) {
+            (_, UIEvent::Timer(id))
+                if self.mark_read_timer.as_ref().map(|t| t.id()) == Some(*id) =>
+            {
+                self.mark_read_timer = None;
+                self.mark_as_read(context);
+                return true;
+            }
+            (_, UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id)))
+                if self.pipe_job.as_ref().map(|(_, h)| h.job_id == *job_id) == Some(true) =>
+            {
+                let (command, mut handle) = self.pipe_job.take().unwrap();
+                match handle.chan.try_recv() {
+                    Err(_) => { /* Job was canceled */ }
+                    Ok(None) => { /* something happened, perhaps a worker thread panicked */ }
+                    Ok(Some(Ok(output))) => self.show_pipe_output(&command, output, context),
+                    Ok(Some(Err(err))) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            err.summary.as_ref().map(|s| s.to_string()),
+                            err.details.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                    }
+                }
+                return true;
+            }
             /*(ViewMode::Ansi(ref mut buf), _) => {
                 if buf.process_event(event, context) {
                     return true;
@@ -1787,6 +2173,12 @@ impl Component for MailView {
                 self.perform_action(PendingReplyAction::ReplyToAll, context);
                 return true;
             }
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[MailView::DESCRIPTION]["reply_to_list"]) =>
+            {
+                self.perform_action(PendingReplyAction::ReplyToList, context);
+                return true;
+            }
             UIEvent::Input(ref key)
                 if shortcut!(key == shortcuts[MailView::DESCRIPTION]["reply_to_author"]) =>
             {
@@ -1864,6 +2256,21 @@ impl Component for MailView {
                 );
                 return true;
             }
+            UIEvent::Input(ref key)
+                if !self.mode.is_contact_selector()
+                    && shortcut!(key == shortcuts[MailView::DESCRIPTION]["archive"]) =>
+            {
+                let (account_hash, mailbox_hash, env_hash) = self.coordinates;
+                if let Err(err) = context.accounts[&account_hash].archive(&[env_hash], mailbox_hash)
+                {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            err.to_string(),
+                        )));
+                }
+                return true;
+            }
             UIEvent::Input(ref key)
                 if !self.mode.is_contact_selector()
                     && shortcut!(
@@ -1904,7 +2311,9 @@ impl Component for MailView {
                 self.initialised = false;
                 return true;
             }
-            UIEvent::Input(Key::Esc) | UIEvent::Input(Key::Alt('')) if !self.cmd_buf.is_empty() => {
+            UIEvent::Input(Key::Esc) | UIEvent::Input(Key::Alt(''))
+                if !self.cmd_buf.is_empty() =>
+            {
                 self.cmd_buf.clear();
                 context
                     .replies
@@ -1995,11 +2404,25 @@ impl Component for MailView {
                 context
                     .replies
                     .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                let mut image_view = None;
                 match self.state {
                     MailViewState::Error { .. } | MailViewState::LoadingBody { .. } => {}
                     MailViewState::Loaded { .. } => {
                         if let Some(attachment) = self.open_attachment(lidx, context) {
+                            let has_display_filter = display_filter(
+                                context,
+                                (self.coordinates.0, self.coordinates.1),
+                                &attachment.mime_type(),
+                            )
+                            .is_some();
                             match attachment.content_type() {
+                                ContentType::Other { .. } | ContentType::OctetStream { .. }
+                                    if has_display_filter =>
+                                {
+                                    self.mode = ViewMode::Attachment(lidx);
+                                    self.initialised = false;
+                                    self.dirty = true;
+                                }
                                 ContentType::MessageRfc822 => {
                                     match Mail::new(attachment.body().to_vec(), Some(Flag::SEEN)) {
                                         Ok(wrapper) => {
@@ -2036,54 +2459,17 @@ impl Component for MailView {
                                     ));
                                 }
                                 ContentType::Other { .. } => {
-                                    let attachment_type = attachment.mime_type();
-                                    let filename = attachment.filename();
-                                    if let Ok(command) = query_default_app(&attachment_type) {
-                                        let p = create_temp_file(
-                                            &decode(attachment, None),
-                                            filename.as_ref().map(|s| s.as_str()),
-                                            None,
-                                            true,
-                                        );
-                                        let (exec_cmd, argument) = desktop_exec_to_command(
-                                            &command,
-                                            p.path.display().to_string(),
-                                            false,
-                                        );
-                                        match Command::new(&exec_cmd)
-                                            .arg(&argument)
-                                            .stdin(Stdio::piped())
-                                            .stdout(Stdio::piped())
-                                            .spawn()
-                                        {
-                                            Ok(child) => {
-                                                context.temp_files.push(p);
-                                                context.children.push(child);
-                                            }
-                                            Err(err) => {
-                                                context.replies.push_back(UIEvent::StatusEvent(
-                                                    StatusEvent::DisplayMessage(format!(
-                                                        "Failed to start `{} {}`: {}",
-                                                        &exec_cmd, &argument, err
-                                                    )),
-                                                ));
-                                            }
+                                    if let Some(protocol) =
+                                        context.settings.terminal.inline_images_protocol()
+                                    {
+                                        if attachment.mime_type().starts_with("image/") {
+                                            image_view =
+                                                Some(ImageView::new(attachment, protocol, context));
+                                        } else {
+                                            open_attachment_externally(attachment, context);
                                         }
                                     } else {
-                                        context.replies.push_back(UIEvent::StatusEvent(
-                                        StatusEvent::DisplayMessage(if let Some(filename) = filename.as_ref() {
-                                            format!(
-                                                "Couldn't find a default application for file {} (type {})",
-                                                filename,
-                                                attachment_type
-                                            )
-                                        } else {
-                                            format!(
-                                                "Couldn't find a default application for type {}",
-                                                attachment_type
-                                            )
-                                        }),
-                                ));
+                                        open_attachment_externally(attachment, context);
                                     }
                                 }
                                 ContentType::OctetStream { ref name } => {
@@ -2101,6 +2487,32 @@ impl Component for MailView {
                         self.init_futures(context);
                     }
                 }
+                if let Some(view) = image_view {
+                    self.subview = Some(Box::new(view));
+                    self.mode = ViewMode::Subview;
+                    self.set_dirty(true);
+                }
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if (self.mode == ViewMode::Normal || self.mode == ViewMode::Subview)
+                    && shortcut!(key == shortcuts[MailView::DESCRIPTION]["pipe"]) =>
+            {
+                let cmd = if self.cmd_buf.is_empty() {
+                    "pipe-raw ".to_string()
+                } else {
+                    format!("pipe-attachment {} ", self.cmd_buf)
+                };
+                self.cmd_buf.clear();
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::BufClear));
+                context
+                    .replies
+                    .push_back(UIEvent::CmdInput(Key::Paste(cmd)));
+                context
+                    .replies
+                    .push_back(UIEvent::ChangeMode(UIMode::Command));
                 return true;
             }
             UIEvent::Input(ref key)
@@ -2113,6 +2525,16 @@ impl Component for MailView {
                 self.set_dirty(true);
                 return true;
             }
+            UIEvent::Input(ref key)
+                if (self.mode == ViewMode::Normal
+                    || self.mode == ViewMode::Subview
+                    || self.mode == ViewMode::Url)
+                    && shortcut!(key == shortcuts[MailView::DESCRIPTION]["toggle_raw_headers"]) =>
+            {
+                self.raw_headers = !self.raw_headers;
+                self.set_dirty(true);
+                return true;
+            }
             UIEvent::Input(ref key)
                 if !self.cmd_buf.is_empty()
                     && self.mode == ViewMode::Url
@@ -2174,11 +2596,13 @@ impl Component for MailView {
                 return true;
             }
             UIEvent::Input(ref key)
-                if (self.mode == ViewMode::Normal || self.mode == ViewMode::Url)
+                if (self.mode == ViewMode::Normal
+                    || self.mode == ViewMode::Subview
+                    || self.mode == ViewMode::Url)
                     && shortcut!(key == shortcuts[MailView::DESCRIPTION]["toggle_url_mode"]) =>
             {
                 match self.mode {
-                    ViewMode::Normal => self.mode = ViewMode::Url,
+                    ViewMode::Normal | ViewMode::Subview => self.mode = ViewMode::Url,
                     ViewMode::Url => self.mode = ViewMode::Normal,
                     _ => {}
                 }
@@ -2189,6 +2613,89 @@ impl Component for MailView {
             UIEvent::EnvelopeRename(old_hash, new_hash) if self.coordinates.2 == old_hash => {
                 self.coordinates.2 = new_hash;
             }
+            UIEvent::Action(View(ViewAction::Reassemble)) => {
+                use melib::email::partial::{reassemble, PartialInfo};
+                let info = match self.state {
+                    MailViewState::Loaded { ref bytes, .. } => PartialInfo::from_bytes(bytes),
+                    _ => return true,
+                };
+                let info = if let Some(info) = info {
+                    info
+                } else {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            "Envelope is not a message/partial message.".to_string(),
+                        )));
+                    return true;
+                };
+                let (account_hash, mailbox_hash, env_hash) = self.coordinates;
+                let account = &context.accounts[&account_hash];
+                if !account.contains_key(env_hash) {
+                    return true;
+                }
+                /* Only envelopes from the same sender are fetched and checked for the same id. */
+                let from = account.collection.get_env(env_hash).from().to_vec();
+                let operations = account
+                    .collection
+                    .get_mailbox(mailbox_hash)
+                    .iter()
+                    .filter(|h| {
+                        account.contains_key(**h)
+                            && account.collection.get_env(**h).from() == from.as_slice()
+                    })
+                    .map(|h| account.operation(*h))
+                    .collect::<Vec<_>>();
+                let (sender, mut receiver) = crate::jobs::oneshot::channel();
+                let job = async move {
+                    let mut parts = vec![];
+                    for operation in operations {
+                        let bytes = operation?.as_bytes()?.await?;
+                        if PartialInfo::from_bytes(&bytes).map_or(false, |i| i.id == info.id) {
+                            parts.push(bytes);
+                        }
+                    }
+                    let _ = sender.send(reassemble(parts.iter().map(Vec::as_slice))?);
+                    Ok(())
+                };
+                let handle = if account.backend_capabilities.is_async {
+                    account.job_executor.spawn_specialized(job)
+                } else {
+                    account.job_executor.spawn_blocking(job)
+                };
+                context.accounts[&account_hash].insert_job(
+                    handle.job_id,
+                    JobRequest::Generic {
+                        name: "reassemble message".into(),
+                        handle,
+                        on_finish: Some(CallbackFn(Box::new(move |context: &mut Context| {
+                            if let Ok(Some(bytes)) = receiver.try_recv() {
+                                match Mail::new(bytes, Some(Flag::SEEN)) {
+                                    Ok(wrapper) => {
+                                        context.replies.push_back(UIEvent::Action(Tab(New(Some(
+                                            Box::new(EnvelopeView::new(
+                                                wrapper,
+                                                None,
+                                                None,
+                                                account_hash,
+                                            )),
+                                        )))));
+                                    }
+                                    Err(err) => {
+                                        context.replies.push_back(UIEvent::Notification(
+                                            Some("Could not reassemble message".to_string()),
+                                            err.to_string(),
+                                            Some(NotificationType::Error(err.kind)),
+                                        ));
+                                    }
+                                }
+                            }
+                        }))),
+                        logging_level: melib::LoggingLevel::DEBUG,
+                    },
+                );
+                return true;
+            }
             UIEvent::Action(View(ViewAction::ExportMail(ref path))) => {
                 // Save entire message as eml
                 let account = &context.accounts[&self.coordinates.0];
@@ -2249,6 +2756,397 @@ impl Component for MailView {
 
                 return true;
             }
+            UIEvent::Action(View(ViewAction::PipeRaw(ref command)))
+            | UIEvent::Action(View(ViewAction::PipeBody(ref command)))
+            | UIEvent::Action(View(ViewAction::PipeAttachment(_, ref command))) => {
+                let input = match (&self.state, &*event) {
+                    (
+                        MailViewState::Loaded { ref bytes, .. },
+                        UIEvent::Action(View(ViewAction::PipeRaw(_))),
+                    ) => Some(bytes.clone()),
+                    (
+                        MailViewState::Loaded { ref body, .. },
+                        UIEvent::Action(View(ViewAction::PipeBody(_))),
+                    ) => Some(body.text().into_bytes()),
+                    (
+                        MailViewState::Loaded { .. },
+                        UIEvent::Action(View(ViewAction::PipeAttachment(a_i, _))),
+                    ) => self.open_attachment(*a_i, context).map(|a| decode(a, None)),
+                    (MailViewState::Error { ref err }, _) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("Failed to open e-mail".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some(input) = input {
+                    let command = command.to_string();
+                    self.pipe_to_command(&input, &command, None, context);
+                }
+                return true;
+            }
+            UIEvent::Action(View(ViewAction::Rsvp(status))) => {
+                let calendar = if let MailViewState::Loaded { ref body, .. } = self.state {
+                    body.attachments()
+                        .into_iter()
+                        .filter(|a| a.content_type == "text/calendar")
+                        .filter_map(|a| {
+                            Calendar::from_str(&String::from_utf8_lossy(&decode(&a, None))).ok()
+                        })
+                        .find(Calendar::is_request)
+                } else {
+                    None
+                };
+                let calendar = if let Some(calendar) = calendar {
+                    calendar
+                } else {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            "This message has no calendar invitation.".to_string(),
+                        )));
+                    return true;
+                };
+                let identity = context.accounts[&self.coordinates.0]
+                    .settings
+                    .account()
+                    .identity
+                    .clone();
+                let reply = match calendar.reply(&identity, status) {
+                    Ok(reply) => reply,
+                    Err(err) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("Could not reply to invitation".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                        return true;
+                    }
+                };
+                /* `reply` succeeded so there is an event with an organizer */
+                let event = calendar
+                    .events
+                    .iter()
+                    .find(|e| e.organizer.is_some())
+                    .unwrap();
+                let verb = match status {
+                    PartStat::Accepted => "Accepted",
+                    PartStat::Tentative => "Tentatively accepted",
+                    _ => "Declined",
+                };
+                let mut draft = Draft::builder()
+                    .header(
+                        "From",
+                        crate::components::mail::get_display_name(context, self.coordinates.0),
+                    )
+                    .header("To", event.organizer.as_ref().unwrap().address.clone())
+                    .subject(format!(
+                        "{}: {}",
+                        verb,
+                        event.summary.as_deref().unwrap_or("(no title)")
+                    ))
+                    .body(format!("{} has {} the invitation.\n", identity, status));
+                {
+                    let account = &context.accounts[&self.coordinates.0];
+                    if account.contains_key(self.coordinates.2) {
+                        draft = draft.in_reply_to(
+                            account
+                                .collection
+                                .get_env(self.coordinates.2)
+                                .message_id_display()
+                                .to_string(),
+                        );
+                    }
+                }
+                let mut attachment = AttachmentBuilder::default();
+                attachment
+                    .set_raw(reply.into_bytes())
+                    .set_body_to_raw()
+                    .set_content_type(ContentType::Text {
+                        kind: Text::Other {
+                            tag: b"calendar".to_vec(),
+                        },
+                        parameters: vec![(b"method".to_vec(), b"REPLY".to_vec())],
+                        charset: Charset::UTF8,
+                    })
+                    .set_content_transfer_encoding(ContentTransferEncoding::_8Bit);
+                match draft.attachment(attachment).build().and_then(|draft| {
+                    super::compose::send_draft(
+                        ToggleFlag::False,
+                        context,
+                        self.coordinates.0,
+                        draft,
+                        SpecialUsageMailbox::Sent,
+                        Flag::SEEN,
+                        true,
+                    )
+                }) {
+                    Ok(_) => {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(format!(
+                                "Sent reply ({}) to {}",
+                                status,
+                                event.organizer.as_ref().unwrap()
+                            )),
+                        ));
+                    }
+                    Err(err) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("Could not send reply to invitation".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                    }
+                }
+                return true;
+            }
+            UIEvent::Action(Listing(ListingAction::BreakThread)) => {
+                let account = &mut context.accounts[&self.coordinates.0];
+                match account.set_thread_link(self.coordinates.2, melib::ThreadLink::Root) {
+                    Ok(()) => {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(
+                                "Message broken out of its thread.".to_string(),
+                            ),
+                        ));
+                    }
+                    Err(err) => {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(err.to_string()),
+                        ));
+                    }
+                }
+                return true;
+            }
+            UIEvent::Action(View(ViewAction::ImportVcard(a_i))) => {
+                let attachments = match (&self.state, a_i) {
+                    (MailViewState::Loaded { .. }, Some(a_i)) => self
+                        .open_attachment(a_i, context)
+                        .cloned()
+                        .into_iter()
+                        .collect::<Vec<Attachment>>(),
+                    (MailViewState::Loaded { ref body, .. }, None) => body
+                        .attachments()
+                        .into_iter()
+                        .filter(is_vcard)
+                        .collect::<Vec<Attachment>>(),
+                    _ => vec![],
+                };
+                if attachments.is_empty() {
+                    if a_i.is_none() {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(
+                                "This message has no vCard attachments.".to_string(),
+                            ),
+                        ));
+                    }
+                    return true;
+                }
+                let mut cards = vec![];
+                for a in attachments {
+                    match melib::vcard::parse_cards(&String::from_utf8_lossy(&decode(&a, None))) {
+                        Ok(c) => cards.extend(c),
+                        Err(err) => {
+                            context.replies.push_back(UIEvent::Notification(
+                                Some("Could not import vCard".to_string()),
+                                err.to_string(),
+                                Some(NotificationType::Error(err.kind)),
+                            ));
+                        }
+                    }
+                }
+                /* Contacts with the same e-mail address are updated instead of duplicated */
+                let address_book = &mut context.accounts[&self.coordinates.0].address_book;
+                let (mut added, mut updated, mut skipped) = (0, 0, 0);
+                for mut card in cards {
+                    match address_book
+                        .cards
+                        .values()
+                        .find(|c| {
+                            !c.email().is_empty() && c.email().eq_ignore_ascii_case(card.email())
+                        })
+                        .map(|c| (*c.id(), c.external_resource()))
+                    {
+                        Some((_, true)) => {
+                            skipped += 1;
+                            continue;
+                        }
+                        Some((id, false)) => {
+                            card.set_id(id);
+                            updated += 1;
+                        }
+                        None => {
+                            added += 1;
+                        }
+                    }
+                    address_book.add_card(card);
+                }
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                        if skipped > 0 {
+                            format!(
+                                "{} contacts added, {} updated, {} not updated because they are managed by a vcard_folder",
+                                added, updated, skipped
+                            )
+                        } else {
+                            format!("{} contacts added, {} updated", added, updated)
+                        },
+                    )));
+                return true;
+            }
+            #[cfg(feature = "gpgme")]
+            UIEvent::Action(View(ViewAction::ImportKey(a_i))) => {
+                let attachments = match (&self.state, a_i) {
+                    (MailViewState::Loaded { .. }, Some(a_i)) => self
+                        .open_attachment(a_i, context)
+                        .cloned()
+                        .into_iter()
+                        .collect::<Vec<Attachment>>(),
+                    (MailViewState::Loaded { ref body, .. }, None) => body
+                        .attachments()
+                        .into_iter()
+                        .filter(|a| a.content_type == "application/pgp-keys")
+                        .collect::<Vec<Attachment>>(),
+                    _ => vec![],
+                };
+                if attachments.is_empty() {
+                    if a_i.is_none() {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(
+                                "This message has no application/pgp-keys attachments.".to_string(),
+                            ),
+                        ));
+                    }
+                    return true;
+                }
+                let jobs = attachments
+                    .iter()
+                    .map(|a| {
+                        let mut ctx = melib::gpgme::Context::new()?;
+                        let data = ctx.new_data_mem(&decode(a, None))?;
+                        ctx.import_key(data)
+                    })
+                    .collect::<Result<Vec<_>>>();
+                match jobs {
+                    Ok(jobs) => spawn_pgp_key_job(
+                        self.coordinates.0,
+                        "import PGP keys",
+                        "Imported PGP keys".to_string(),
+                        async move {
+                            let mut fingerprints = vec![];
+                            for job in jobs {
+                                fingerprints.extend(job.await?);
+                            }
+                            Ok(fingerprints)
+                        },
+                        context,
+                    ),
+                    Err(err) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("Could not import PGP keys".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                    }
+                }
+                return true;
+            }
+            #[cfg(feature = "gpgme")]
+            UIEvent::Action(View(ViewAction::LookupSenderKey)) => {
+                let account = &context.accounts[&self.coordinates.0];
+                let sender = account
+                    .collection
+                    .get_env(self.coordinates.2)
+                    .from()
+                    .first()
+                    .map(|a| a.get_email());
+                let sender =
+                    if let Some(sender) = sender {
+                        sender
+                    } else {
+                        context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage("This message has no sender.".to_string()),
+                        ));
+                        return true;
+                    };
+                let keyserver = mailbox_settings!(
+                    context[self.coordinates.0][&self.coordinates.1]
+                        .pgp
+                        .keyserver
+                )
+                .clone();
+                let job = melib::gpgme::Context::new().and_then(|mut ctx| {
+                    ctx.set_flag(melib::gpgme::GpgmeFlag::OfflineMode, false)?;
+                    ctx.set_keyserver(&keyserver)?;
+                    ctx.set_locate_keys(true)?;
+                    ctx.keylist(false, Some(sender.clone()))
+                });
+                match job {
+                    Ok(job) => spawn_pgp_key_job(
+                        self.coordinates.0,
+                        "look up sender's PGP key",
+                        format!("Keys of {} on {}", sender, keyserver),
+                        async move {
+                            Ok(job
+                                .await?
+                                .iter()
+                                .map(|k| k.fingerprint().to_string())
+                                .collect())
+                        },
+                        context,
+                    ),
+                    Err(err) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("Could not look up the sender's key".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                    }
+                }
+                return true;
+            }
+            UIEvent::Action(View(ViewAction::ApplyPatch(a_i))) => {
+                let repository = if let Some(repository) = mailbox_settings!(
+                    context[self.coordinates.0][&self.coordinates.1]
+                        .pager
+                        .patch_repository
+                ) {
+                    std::path::Path::new(repository).expand()
+                } else {
+                    context.replies.push_back(UIEvent::Notification(
+                        Some("Cannot apply patch".to_string()),
+                        "Set pager.patch_repository to the repository directory.".to_string(),
+                        Some(NotificationType::Error(melib::ErrorKind::None)),
+                    ));
+                    return true;
+                };
+                /* A whole message is a patch for `git am`, an attachment is a plain diff. */
+                let input = match (&self.state, a_i) {
+                    (MailViewState::Loaded { ref bytes, .. }, None) => {
+                        Some((bytes.clone(), "git am"))
+                    }
+                    (MailViewState::Loaded { .. }, Some(a_i)) => self
+                        .open_attachment(a_i, context)
+                        .map(|a| (decode(a, None), "git apply")),
+                    (MailViewState::Error { ref err }, _) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("Failed to open e-mail".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                        None
+                    }
+                    _ => None,
+                };
+                if let Some((input, command)) = input {
+                    self.pipe_to_command(&input, command, Some(&repository), context);
+                }
+                return true;
+            }
             UIEvent::Action(View(ViewAction::SaveAttachment(a_i, ref path))) => {
                 {
                     let account = &context.accounts[&self.coordinates.0];
@@ -2355,6 +3253,88 @@ impl Component for MailView {
                 }
                 return true;
             }
+            UIEvent::Action(View(ViewAction::SaveAttachments(ref indices, ref path))) => {
+                let dir = if let Some(path) = path {
+                    std::path::Path::new(path).expand()
+                } else if let Some(dir) = mailbox_settings!(
+                    context[self.coordinates.0][&self.coordinates.1]
+                        .pager
+                        .attachment_dir
+                ) {
+                    std::path::Path::new(dir).expand()
+                } else {
+                    context.replies.push_back(UIEvent::Notification(
+                        Some("Cannot save attachments".to_string()),
+                        "Give a PATH or set pager.attachment_dir.".to_string(),
+                        Some(NotificationType::Error(melib::ErrorKind::None)),
+                    ));
+                    return true;
+                };
+                let attachments: Vec<Attachment> = match (&self.state, indices) {
+                    (MailViewState::Loaded { ref body, .. }, None) => {
+                        crate::attachments::saveable_attachments(body)
+                    }
+                    (MailViewState::Loaded { .. }, Some(indices)) => {
+                        let mut ret = Vec::with_capacity(indices.len());
+                        for &a_i in indices {
+                            if let Some(a) = self.open_attachment(a_i, context) {
+                                ret.push(a.clone());
+                            } else {
+                                context.replies.push_back(UIEvent::StatusEvent(
+                                    StatusEvent::DisplayMessage(format!(
+                                        "Attachment `{}` not found.",
+                                        a_i
+                                    )),
+                                ));
+                                return true;
+                            }
+                        }
+                        ret
+                    }
+                    (MailViewState::Error { ref err }, _) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some("Failed to open e-mail".to_string()),
+                            err.to_string(),
+                            Some(NotificationType::Error(err.kind)),
+                        ));
+                        return true;
+                    }
+                    _ => return true,
+                };
+                if attachments.is_empty() {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                            "No attachments to save.".to_string(),
+                        )));
+                    return true;
+                }
+                match crate::attachments::save_attachments(&dir, &attachments) {
+                    Err(err) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            Some(format!("Failed to save attachments to {}", dir.display())),
+                            err.to_string(),
+                            Some(NotificationType::Error(melib::ErrorKind::External)),
+                        ));
+                        log(
+                            format!(
+                                "Failed to save attachments to {}: {}",
+                                dir.display(),
+                                err.to_string()
+                            ),
+                            ERROR,
+                        );
+                    }
+                    Ok(paths) => {
+                        context.replies.push_back(UIEvent::Notification(
+                            None,
+                            crate::attachments::saved_message(&paths),
+                            Some(NotificationType::Info),
+                        ));
+                    }
+                }
+                return true;
+            }
             UIEvent::Action(MailingListAction(ref e)) => {
                 let account = &context.accounts[&self.coordinates.0];
                 if !account.contains_key(self.coordinates.2) {
@@ -2393,68 +3373,68 @@ impl Component for MailView {
                             return true;
                         }
                         MailingListAction::ListUnsubscribe if actions.unsubscribe.is_some() => {
-                            /* autosend or open unsubscribe option*/
-                            let unsubscribe = actions.unsubscribe.as_ref().unwrap();
-                            for option in unsubscribe.iter() {
-                                /* TODO: Ask for confirmation before proceding with an action */
-                                match option {
-                                    list_management::ListAction::Email(email) => {
-                                        if let Ok(mailto) = Mailto::try_from(*email) {
-                                            let mut draft: Draft = mailto.into();
-                                            draft.set_header(
-                                                "From",
-                                                crate::components::mail::get_display_name(
-                                                    context,
-                                                    self.coordinates.0,
-                                                ),
-                                            );
-                                            /* Manually drop stuff because borrowck doesn't do it
-                                             * on its own */
-                                            drop(detect);
-                                            drop(envelope);
-                                            if let Err(err) = super::compose::send_draft(
-                                                ToggleFlag::False,
-                                                context,
-                                                self.coordinates.0,
-                                                draft,
-                                                SpecialUsageMailbox::Sent,
-                                                Flag::SEEN,
-                                                true,
-                                            ) {
-                                                context.replies.push_back(UIEvent::StatusEvent(
-                                                    StatusEvent::DisplayMessage(format!(
-                                                        "Couldn't send unsubscribe e-mail: {}",
-                                                        err
-                                                    )),
-                                                ));
-                                            }
-                                            return true;
-                                        }
-                                    }
-                                    list_management::ListAction::Url(url) => {
-                                        match Command::new("xdg-open")
-                                            .arg(String::from_utf8_lossy(url).into_owned())
-                                            .stdin(Stdio::piped())
-                                            .stdout(Stdio::piped())
-                                            .spawn()
-                                        {
-                                            Ok(child) => {
-                                                context.children.push(child);
-                                            }
-                                            Err(err) => {
-                                                context.replies.push_back(UIEvent::StatusEvent(
-                                                    StatusEvent::DisplayMessage(format!(
-                                                        "Couldn't launch xdg-open: {}",
-                                                        err
-                                                    )),
-                                                ));
-                                            }
-                                        }
-                                        return true;
-                                    }
-                                    list_management::ListAction::No => {}
+                            /* ask for confirmation, then autosend or open unsubscribe option */
+                            use list_management::UnsubscribeOption;
+                            let option = match actions.unsubscribe_option(cfg!(feature = "http")) {
+                                Some(option) => option,
+                                None => {
+                                    context.replies.push_back(UIEvent::StatusEvent(
+                                        StatusEvent::DisplayMessage(String::from(
+                                            "Couldn't parse List-Unsubscribe header value",
+                                        )),
+                                    ));
+                                    return true;
                                 }
-                            }
+                            };
+                            let list = actions.id.unwrap_or("this list");
+                            let title = match option {
+                                UnsubscribeOption::OneClick(ref url) => {
+                                    format!("Unsubscribe from {} by posting to {}?", list, url)
+                                }
+                                UnsubscribeOption::Email(ref email) => format!(
+                                    "Unsubscribe from {} by sending an e-mail to {}?",
+                                    list,
+                                    email.trim_start_matches("mailto:")
+                                ),
+                                UnsubscribeOption::Url(ref url) => {
+                                    format!("Unsubscribe from {} by opening {}?", list, url)
+                                }
+                            };
+                            let account_hash = self.coordinates.0;
+                            context.replies.push_back(UIEvent::GlobalUIDialog(Box::new(
+                                UIConfirmationDialog::new(
+                                    &title,
+                                    vec![(true, "yes".to_string()), (false, "no".to_string())],
+                                    true,
+                                    Some(Box::new(move |_id: ComponentId, result: bool| {
+                                        if !result {
+                                            return None;
+                                        }
+                                        Some(UIEvent::Callback(CallbackFn(Box::new(
+                                            move |context: &mut Context| {
+                                                unsubscribe(option, account_hash, context)
+                                            },
+                                        ))))
+                                    })),
+                                    context,
+                                ),
+                            )));
+                            return true;
+                        }
+                        MailingListAction::ListReply if actions.post.is_some() => {
+                            drop(detect);
+                            drop(envelope);
+                            self.perform_action(PendingReplyAction::ReplyToList, context);
+                            return true;
+                        }
+                        MailingListAction::ListFilter if actions.id.is_some() => {
+                            context
+                                .replies
+                                .push_back(UIEvent::Action(Listing(Search(format!(
+                                    "list:\"{}\"",
+                                    actions.id.unwrap().replace('"', "")
+                                )))));
+                            return true;
                         }
                         MailingListAction::ListArchive if actions.archive.is_some() => {
                             /* open archive url with xdg-open */
@@ -2543,7 +3523,10 @@ impl Component for MailView {
         if self.mode != ViewMode::Url {
             our_map.remove("go_to_url");
         }
-        if !(self.mode == ViewMode::Normal || self.mode == ViewMode::Url) {
+        if !(self.mode == ViewMode::Normal
+            || self.mode == ViewMode::Subview
+            || self.mode == ViewMode::Url)
+        {
             our_map.remove("toggle_url_mode");
         }
         map.insert(MailView::DESCRIPTION, our_map);
@@ -2578,6 +3561,207 @@ fn save_attachment(path: &std::path::Path, bytes: &[u8]) -> Result<()> {
     Ok(())
 }
 
+/// Returns true if `attachment` is or contains a `text/x-patch` or `text/x-diff` part.
+fn is_patch(attachment: &Attachment) -> bool {
+    attachment.attachments().iter().any(|a| {
+        a.content_type == "text/x-patch"
+            || a.content_type == "text/x-diff"
+            || a.content_type == "text/x-diff-patch"
+    })
+}
+
+/// Runs `job` as a job of the account, notifying the fingerprints of the keys it returns under
+/// `title`.
+#[cfg(feature = "gpgme")]
+fn spawn_pgp_key_job(
+    account_hash: AccountHash,
+    name: &'static str,
+    title: String,
+    job: impl std::future::Future<Output = Result<Vec<String>>> + Send + 'static,
+    context: &mut Context,
+) {
+    let (sender, mut receiver) = crate::jobs::oneshot::channel();
+    let job = async move {
+        let _ = sender.send(job.await?);
+        Ok(())
+    };
+    let handle = context.accounts[&account_hash]
+        .job_executor
+        .spawn_specialized(job);
+    context.accounts[&account_hash].insert_job(
+        handle.job_id,
+        JobRequest::Generic {
+            name: name.into(),
+            handle,
+            logging_level: melib::LoggingLevel::INFO,
+            on_finish: Some(CallbackFn(Box::new(move |context: &mut Context| {
+                if let Ok(Some(fingerprints)) = receiver.try_recv() {
+                    context.replies.push_back(UIEvent::Notification(
+                        Some(title),
+                        if fingerprints.is_empty() {
+                            "No keys found.".to_string()
+                        } else {
+                            fingerprints.join("\n")
+                        },
+                        Some(NotificationType::Info),
+                    ));
+                }
+            }))),
+        },
+    );
+}
+
+fn is_vcard(attachment: &Attachment) -> bool {
+    ["text/vcard", "text/x-vcard", "text/directory"]
+        .iter()
+        .any(|t| attachment.content_type == *t)
+        || attachment
+            .filename()
+            .map(|f| f.to_ascii_lowercase().ends_with(".vcf"))
+            .unwrap_or(false)
+}
+
+/// Open an attachment with the default application for its MIME type.
+fn open_attachment_externally(attachment: &Attachment, context: &mut Context) {
+    let attachment_type = attachment.mime_type();
+    let filename = attachment.filename();
+    if let Ok(command) = query_default_app(&attachment_type) {
+        let p = create_temp_file(
+            &decode(attachment, None),
+            filename.as_ref().map(|s| s.as_str()),
+            None,
+            true,
+        );
+        let (exec_cmd, argument) =
+            desktop_exec_to_command(&command, p.path.display().to_string(), false);
+        match Command::new(&exec_cmd)
+            .arg(&argument)
+            .stdin(Stdio::piped())
+            .stdout(Stdio::piped())
+            .spawn()
+        {
+            Ok(child) => {
+                context.temp_files.push(p);
+                context.children.push(child);
+            }
+            Err(err) => {
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                        "Failed to start `{} {}`: {}",
+                        &exec_cmd, &argument, err
+                    ))));
+            }
+        }
+    } else {
+        context
+            .replies
+            .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+                if let Some(filename) = filename.as_ref() {
+                    format!(
+                        "Couldn't find a default application for file {} (type {})",
+                        filename, attachment_type
+                    )
+                } else {
+                    format!(
+                        "Couldn't find a default application for type {}",
+                        attachment_type
+                    )
+                },
+            )));
+    }
+}
+
+/// Unsubscribe from a mailing list with `option`, after the user has confirmed it.
+fn unsubscribe(
+    option: list_management::UnsubscribeOption,
+    account_hash: AccountHash,
+    context: &mut Context,
+) {
+    use list_management::UnsubscribeOption;
+    match option {
+        #[cfg(feature = "http")]
+        UnsubscribeOption::OneClick(url) => {
+            let job = {
+                let url = url.clone();
+                async move { list_management::one_click_unsubscribe(&url).await }
+            };
+            let handle = context.accounts[&account_hash]
+                .job_executor
+                .spawn_specialized(job);
+            context.accounts[&account_hash].insert_job(
+                handle.job_id,
+                JobRequest::Generic {
+                    name: format!("unsubscribe via {}", url).into(),
+                    handle,
+                    on_finish: None,
+                    logging_level: melib::LoggingLevel::INFO,
+                },
+            );
+        }
+        UnsubscribeOption::Email(email) => match Mailto::try_from(email.as_bytes()) {
+            Ok(mailto) => {
+                let mut draft: Draft = mailto.into();
+                draft.set_header(
+                    "From",
+                    crate::components::mail::get_display_name(context, account_hash),
+                );
+                if let Err(err) = super::compose::send_draft(
+                    ToggleFlag::False,
+                    context,
+                    account_hash,
+                    draft,
+                    SpecialUsageMailbox::Sent,
+                    Flag::SEEN,
+                    true,
+                ) {
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                            "Couldn't send unsubscribe e-mail: {}",
+                            err
+                        ))));
+                }
+            }
+            Err(err) => {
+                context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                        "Couldn't parse unsubscribe address {}: {}",
+                        email, err
+                    ))));
+            }
+        },
+        #[cfg(not(feature = "http"))]
+        UnsubscribeOption::OneClick(url) | UnsubscribeOption::Url(url) => {
+            open_unsubscribe_url(url, context)
+        }
+        #[cfg(feature = "http")]
+        UnsubscribeOption::Url(url) => open_unsubscribe_url(url, context),
+    }
+}
+
+fn open_unsubscribe_url(url: String, context: &mut Context) {
+    match Command::new("xdg-open")
+        .arg(url)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .spawn()
+    {
+        Ok(child) => {
+            context.children.push(child);
+        }
+        Err(err) => {
+            context
+                .replies
+                .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                    "Couldn't launch xdg-open: {}",
+                    err
+                ))));
+        }
+    }
+}
+
 fn desktop_exec_to_command(command: &str, path: String, is_url: bool) -> (String, String) {
     /* Purge unused field codes */
     let command = command
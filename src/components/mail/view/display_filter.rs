@@ -0,0 +1,161 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Filters of the `pager.display_filters` setting, which turn parts of some MIME type into text
+ * for the pager. Filtered text is cached for each part and filter, so that filters only run the
+ * first time a part is shown.
+ */
+
+use super::*;
+use std::cell::RefCell;
+use std::collections::hash_map::DefaultHasher;
+use std::collections::HashMap;
+use std::hash::{Hash, Hasher};
+
+thread_local!(static FILTERED_TEXT: RefCell<HashMap<u64, String>> = RefCell::new(HashMap::default()));
+
+/// The filter of `pager.display_filters` for `mime_type` in the mailbox at `coordinates`.
+pub fn display_filter(
+    context: &Context,
+    coordinates: (AccountHash, MailboxHash),
+    mime_type: &str,
+) -> Option<Vec<String>> {
+    find_display_filter(
+        mailbox_settings!(context[coordinates.0][&coordinates.1].pager.display_filters),
+        mime_type,
+    )
+    .map(<[String]>::to_vec)
+}
+
+/// Filters for a MIME type are preferred over filters for all subtypes of its type, eg. `text/*`.
+fn find_display_filter<'f>(
+    filters: &'f IndexMap<String, Vec<String>>,
+    mime_type: &str,
+) -> Option<&'f [String]> {
+    let type_ = mime_type.split('/').next().unwrap_or_default();
+    filters
+        .iter()
+        .find(|(k, _)| k.eq_ignore_ascii_case(mime_type))
+        .or_else(|| {
+            filters.iter().find(|(k, _)| {
+                k.strip_suffix("/*")
+                    .map(|t| t.eq_ignore_ascii_case(type_) || t == "*")
+                    .unwrap_or(false)
+            })
+        })
+        .map(|(_, filter)| filter.as_slice())
+}
+
+/// Runs the steps of `filter` on the decoded contents of `attachment`, each step getting the
+/// output of the previous one. `width` is the width of text rendered from HTML.
+pub fn run_display_filter(
+    attachment: &Attachment,
+    filter: &[String],
+    width: usize,
+) -> Result<String> {
+    let bytes = decode(attachment, None);
+    let mut hasher = DefaultHasher::new();
+    bytes.hash(&mut hasher);
+    filter.hash(&mut hasher);
+    width.hash(&mut hasher);
+    let key = hasher.finish();
+    if let Some(text) = FILTERED_TEXT.with(|cache| cache.borrow().get(&key).cloned()) {
+        return Ok(text);
+    }
+
+    let mut output = bytes;
+    for step in filter {
+        output = match step.as_str() {
+            "internal:html-to-text" => {
+                let html = String::from_utf8_lossy(&output);
+                melib::text_processing::html::html_to_text(&html, width)
+                    .text
+                    .into_bytes()
+            }
+            "internal:calendar" => Calendar::from_str(&String::from_utf8_lossy(&output))?
+                .to_string()
+                .into_bytes(),
+            command => pipe_through(command, output)?,
+        };
+    }
+    let text = String::from_utf8_lossy(&output).into_owned();
+    FILTERED_TEXT.with(|cache| cache.borrow_mut().insert(key, text.clone()));
+    Ok(text)
+}
+
+fn pipe_through(command: &str, input: Vec<u8>) -> Result<Vec<u8>> {
+    let mut child = Command::new("sh")
+        .args(&["-c", command])
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            MeliError::new(format!(
+                "Could not start display filter `{}`: {}",
+                command, err
+            ))
+        })?;
+    /* Write from another thread so that a filter writing before it has read all of its input
+     * can't block on a full pipe */
+    let mut stdin = child.stdin.take().unwrap();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    let _ = writer.join();
+    if !output.status.success() {
+        return Err(MeliError::new(format!(
+            "Display filter `{}` failed: {}",
+            command,
+            String::from_utf8_lossy(&output.stderr).trim()
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[test]
+fn test_display_filters() {
+    let mut filters: IndexMap<String, Vec<String>> = IndexMap::default();
+    filters.insert("text/*".to_string(), vec!["tr a-z A-Z".to_string()]);
+    filters.insert(
+        "text/html".to_string(),
+        vec![
+            "internal:html-to-text".to_string(),
+            "tr a-z A-Z".to_string(),
+        ],
+    );
+    assert_eq!(
+        find_display_filter(&filters, "text/html").map(<[String]>::len),
+        Some(2)
+    );
+    assert_eq!(
+        find_display_filter(&filters, "text/calendar").map(<[String]>::len),
+        Some(1)
+    );
+    assert!(find_display_filter(&filters, "application/pdf").is_none());
+
+    let attachment = AttachmentBuilder::new(
+        b"Content-Type: text/html\n\n<html><body><p>hello</p></body></html>",
+    )
+    .build();
+    let text = run_display_filter(&attachment, &filters["text/html"], 80).unwrap();
+    assert_eq!(text.trim(), "HELLO");
+    assert!(run_display_filter(&attachment, &["exit 1".to_string()], 80).is_err());
+}
@@ -21,6 +21,7 @@
 
 use super::*;
 use linkify::{Link, LinkFinder};
+use melib::email::address::domains_to_unicode;
 use std::process::{Command, Stdio};
 
 use xdg_utils::query_default_app;
@@ -124,6 +125,13 @@ impl EnvelopeView {
                                 v.extend(html_filter.wait_with_output().unwrap().stdout);
                             }
                         }
+                    } else {
+                        let text = melib::text_processing::html::html_to_text(
+                            &String::from_utf8_lossy(&v),
+                            settings.pager.minimum_width,
+                        )
+                        .text;
+                        *v = text.into_bytes();
                     }
                 }
             })),
@@ -228,12 +236,20 @@ impl Component for EnvelopeView {
                 context.dirty_areas.push_back(area);
                 get_y(upper_left).saturating_sub(1)
             } else {
+                let headers_attr = crate::conf::value(context, "mail.view.headers");
                 let (x, y) = write_string_to_grid(
-                    &format!("Date: {}", self.mail.date_as_str()),
+                    &format!(
+                        "Date: {}",
+                        crate::dates::format_date_header(
+                            &self.mail,
+                            context.settings.pager.datetime_fmt.as_deref(),
+                            context.settings.pager.local_dates,
+                        )
+                    ),
                     grid,
-                    Color::Byte(33),
-                    Color::Default,
-                    Attr::DEFAULT,
+                    headers_attr.fg,
+                    headers_attr.bg,
+                    headers_attr.attrs,
                     area,
                     Some(get_x(upper_left)),
                 );
@@ -243,11 +259,14 @@ impl Component for EnvelopeView {
                     grid[(x, y)].set_fg(Color::Default);
                 }
                 let (x, y) = write_string_to_grid(
-                    &format!("From: {}", self.mail.field_from_to_string()),
+                    &format!(
+                        "From: {}",
+                        domains_to_unicode(&self.mail.field_from_to_string())
+                    ),
                     grid,
-                    Color::Byte(33),
-                    Color::Default,
-                    Attr::DEFAULT,
+                    headers_attr.fg,
+                    headers_attr.bg,
+                    headers_attr.attrs,
                     (set_y(upper_left, y + 1), bottom_right),
                     Some(get_x(upper_left)),
                 );
@@ -257,11 +276,14 @@ impl Component for EnvelopeView {
                     grid[(x, y)].set_fg(Color::Default);
                 }
                 let (x, y) = write_string_to_grid(
-                    &format!("To: {}", self.mail.field_to_to_string()),
+                    &format!(
+                        "To: {}",
+                        domains_to_unicode(&self.mail.field_to_to_string())
+                    ),
                     grid,
-                    Color::Byte(33),
-                    Color::Default,
-                    Attr::DEFAULT,
+                    headers_attr.fg,
+                    headers_attr.bg,
+                    headers_attr.attrs,
                     (set_y(upper_left, y + 1), bottom_right),
                     Some(get_x(upper_left)),
                 );
@@ -273,9 +295,9 @@ impl Component for EnvelopeView {
                 let (x, y) = write_string_to_grid(
                     &format!("Subject: {}", self.mail.subject()),
                     grid,
-                    Color::Byte(33),
-                    Color::Default,
-                    Attr::DEFAULT,
+                    headers_attr.fg,
+                    headers_attr.bg,
+                    headers_attr.attrs,
                     (set_y(upper_left, y + 1), bottom_right),
                     Some(get_x(upper_left)),
                 );
@@ -287,9 +309,9 @@ impl Component for EnvelopeView {
                 let (x, y) = write_string_to_grid(
                     &format!("Message-ID: <{}>", self.mail.message_id_raw()),
                     grid,
-                    Color::Byte(33),
-                    Color::Default,
-                    Attr::DEFAULT,
+                    headers_attr.fg,
+                    headers_attr.bg,
+                    headers_attr.attrs,
                     (set_y(upper_left, y + 1), bottom_right),
                     Some(get_x(upper_left)),
                 );
@@ -365,7 +387,9 @@ impl Component for EnvelopeView {
             }
         }
         match *event {
-            UIEvent::Input(Key::Esc) | UIEvent::Input(Key::Alt('')) if !self.cmd_buf.is_empty() => {
+            UIEvent::Input(Key::Esc) | UIEvent::Input(Key::Alt(''))
+                if !self.cmd_buf.is_empty() =>
+            {
                 self.cmd_buf.clear();
                 context
                     .replies
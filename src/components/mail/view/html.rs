@@ -27,13 +27,30 @@ use std::process::{Command, Stdio};
 pub struct HtmlView {
     pager: Pager,
     bytes: Vec<u8>,
+    /// Parts of a `multipart/related` body that can be referenced with a `cid:` URL.
+    related: Vec<(String, Option<String>, Vec<u8>)>,
     id: ComponentId,
 }
 
 impl HtmlView {
     pub fn new(body: &Attachment, context: &mut Context) -> Self {
         let id = ComponentId::new_v4();
-        let bytes: Vec<u8> = decode_rec(body, None);
+        let related: Vec<(String, Option<String>, Vec<u8>)> = body
+            .attachments()
+            .into_iter()
+            .filter(|a| !a.is_text() && a.content_type.parts().is_none())
+            .filter_map(|a| Some((a.content_id()?, a.filename(), decode(&a, None))))
+            .collect();
+        let bytes: Vec<u8> = if related.is_empty() {
+            decode_rec(body, None)
+        } else {
+            /* Don't mix inline images into the html source */
+            body.attachments()
+                .into_iter()
+                .find(|a| a.content_type.parts().is_none() && a.is_html())
+                .map(|a| decode(&a, None))
+                .unwrap_or_else(|| decode_rec(body, None))
+        };
 
         let settings = &context.settings;
         let mut display_text = if let Some(filter_invocation) = settings.pager.html_filter.as_ref()
@@ -72,32 +89,16 @@ impl HtmlView {
                     display_text
                 }
             }
-        } else if let Ok(mut html_filter) = Command::new("w3m")
-            .args(&["-I", "utf-8", "-T", "text/html"])
-            .stdin(Stdio::piped())
-            .stdout(Stdio::piped())
-            .spawn()
-        {
-            html_filter
-                .stdin
-                .as_mut()
-                .unwrap()
-                .write_all(&bytes)
-                .expect("Failed to write to html filter stdin");
+        } else {
+            let text = melib::text_processing::html::html_to_text(
+                &String::from_utf8_lossy(&bytes),
+                settings.pager.minimum_width,
+            )
+            .text;
             let mut display_text =
-                String::from("Text piped through `w3m`. Press `v` to open in web browser. \n\n");
-            display_text.push_str(&String::from_utf8_lossy(
-                &html_filter.wait_with_output().unwrap().stdout,
-            ));
-
+                String::from("Text rendered from HTML. Press `v` to open in web browser. \n\n");
+            display_text.push_str(&text);
             display_text
-        } else {
-            context.replies.push_back(UIEvent::Notification(
-                Some("Failed to find any application to use as html filter".to_string()),
-                String::new(),
-                Some(NotificationType::Error(melib::error::ErrorKind::None)),
-            ));
-            String::from_utf8_lossy(&bytes).to_string()
         };
         if body.count_attachments() > 1 {
             display_text =
@@ -111,7 +112,75 @@ impl HtmlView {
         }
         let colors = crate::conf::value(context, "mail.view.body");
         let pager = Pager::from_string(display_text, None, None, None, colors);
-        HtmlView { pager, bytes, id }
+        HtmlView {
+            pager,
+            bytes,
+            related,
+            id,
+        }
+    }
+
+    /// Write related parts to temporary files and point `cid:` references in the html source
+    /// to them, so that inline images show up in the browser.
+    fn export_related(&self, context: &mut Context) -> Vec<u8> {
+        if self.related.is_empty() {
+            return self.bytes.clone();
+        }
+        let mut html = String::from_utf8_lossy(&self.bytes).into_owned();
+        for (cid, filename, bytes) in &self.related {
+            if !html.contains(&format!("cid:{}", cid)) {
+                continue;
+            }
+            /* Parts often share a name like `image.png`, so only keep the extension, which
+             * browsers may need to recognise the file type. */
+            let mut name = uuid::Uuid::new_v4().to_hyphenated().to_string();
+            if let Some(ext) = filename
+                .as_deref()
+                .and_then(|f| std::path::Path::new(f).extension())
+                .and_then(|e| e.to_str())
+                .filter(|e| e.chars().all(|c| c.is_ascii_alphanumeric()))
+            {
+                name.push('.');
+                name.push_str(ext);
+            }
+            let p = create_temp_file(bytes, Some(&name), None, true);
+            if let Some(replaced) = replace_cid(&html, cid, &format!("file://{}", p.path.display()))
+            {
+                html = replaced;
+                context.temp_files.push(p);
+            }
+        }
+        html.into_bytes()
+    }
+}
+
+/// Replace the `cid:` URLs referencing `cid` in `html` with `url`, or return `None` if there are
+/// none. A URL ends at a quote, a parenthesis, `>` or whitespace, so that `cid:img1` doesn't match
+/// `cid:img10`.
+fn replace_cid(html: &str, cid: &str, url: &str) -> Option<String> {
+    let needle = format!("cid:{}", cid);
+    let mut ret = String::with_capacity(html.len());
+    let mut rest = html;
+    let mut found = false;
+    while let Some(pos) = rest.find(&needle) {
+        let end = pos + needle.len();
+        ret.push_str(&rest[..pos]);
+        match rest[end..].chars().next() {
+            Some(c) if !matches!(c, '"' | '\'' | ')' | '>') && !c.is_whitespace() => {
+                ret.push_str(&needle);
+            }
+            _ => {
+                ret.push_str(url);
+                found = true;
+            }
+        }
+        rest = &rest[end..];
+    }
+    ret.push_str(rest);
+    if found {
+        Some(ret)
+    } else {
+        None
     }
 }
 
@@ -132,7 +201,7 @@ impl Component for HtmlView {
 
         if let UIEvent::Input(Key::Char('v')) = event {
             if let Ok(command) = query_default_app("text/html") {
-                let p = create_temp_file(&self.bytes, None, None, true);
+                let p = create_temp_file(&self.export_related(context), None, None, true);
                 let (exec_cmd, argument) =
                     super::desktop_exec_to_command(&command, p.path.display().to_string(), false);
                 match Command::new(&exec_cmd)
@@ -182,3 +251,16 @@ impl Component for HtmlView {
         self.id = id;
     }
 }
+
+#[test]
+fn test_replace_cid() {
+    let html =
+        r#"<img src="cid:img1"><img src='cid:img10'><div style="background: url(cid:img1)">"#;
+    assert_eq!(
+        replace_cid(html, "img1", "file:///tmp/a.png").as_deref(),
+        Some(
+            r#"<img src="file:///tmp/a.png"><img src='cid:img10'><div style="background: url(file:///tmp/a.png)">"#
+        )
+    );
+    assert_eq!(replace_cid(html, "img", "file:///tmp/a.png"), None);
+}
@@ -0,0 +1,210 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+use super::*;
+
+/// Displays an image attachment with the terminal's graphics protocol. The image is converted
+/// and encoded in jobs, since both may run external commands.
+#[derive(Debug)]
+pub struct ImageView {
+    attachment: Attachment,
+    image: Option<TerminalImage>,
+    image_job: Option<JoinHandle<Result<TerminalImage>>>,
+    protocol: GraphicsProtocol,
+    image_id: u32,
+    /// Escape sequence of the last drawn image and its size in cells
+    encoded: Option<((usize, usize), Vec<u8>)>,
+    /// Size in cells of the image being encoded
+    encode_job: Option<((usize, usize), JoinHandle<Result<Vec<u8>>>)>,
+    error: Option<MeliError>,
+    dirty: bool,
+    id: ComponentId,
+}
+
+impl ImageView {
+    pub fn new(attachment: &Attachment, protocol: GraphicsProtocol, context: &mut Context) -> Self {
+        let bytes = decode(attachment, None);
+        let handle = context
+            .job_executor
+            .spawn_blocking(async move { TerminalImage::new(&bytes) });
+        context
+            .replies
+            .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(handle.job_id)));
+        ImageView {
+            attachment: attachment.clone(),
+            image: None,
+            image_job: Some(handle),
+            protocol,
+            image_id: InlineImage::new_id(),
+            encoded: None,
+            encode_job: None,
+            error: None,
+            dirty: true,
+            id: ComponentId::new_v4(),
+        }
+    }
+}
+
+impl fmt::Display for ImageView {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "image")
+    }
+}
+
+impl Component for ImageView {
+    fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
+        if !self.dirty {
+            return;
+        }
+        let theme_default = crate::conf::value(context, "mail.view.body");
+        clear_area(grid, area, theme_default);
+        let upper_left = upper_left!(area);
+        let bottom_right = bottom_right!(area);
+        let header = format!(
+            "Viewing image {}. Press `r` to return, `v` to open externally.",
+            self.attachment
+                .filename()
+                .unwrap_or_else(|| self.attachment.mime_type())
+        );
+        write_string_to_grid(
+            &header,
+            grid,
+            theme_default.fg,
+            theme_default.bg,
+            theme_default.attrs,
+            area,
+            None,
+        );
+        context.dirty_areas.push_back(area);
+        self.dirty = false;
+        if height!(area) < 3 {
+            return;
+        }
+        let image_area = (pos_inc(upper_left, (0, 2)), bottom_right);
+        let status = if let Some(ref err) = self.error {
+            Some(format!("Could not display image: {}", err))
+        } else if let Some(ref image) = self.image {
+            let size = image.fit(width!(image_area), height!(image_area));
+            match self.encoded {
+                Some((s, ref escape)) if s == size => {
+                    context.inline_images.push(InlineImage {
+                        id: self.image_id,
+                        protocol: self.protocol,
+                        area: (
+                            upper_left!(image_area),
+                            pos_inc(
+                                upper_left!(image_area),
+                                (size.0.saturating_sub(1), size.1.saturating_sub(1)),
+                            ),
+                        ),
+                        escape: escape.clone(),
+                    });
+                    None
+                }
+                _ => {
+                    if self
+                        .encode_job
+                        .as_ref()
+                        .map(|(s, _)| *s != size)
+                        .unwrap_or(true)
+                    {
+                        let image = image.clone();
+                        let (protocol, image_id) = (self.protocol, self.image_id);
+                        let handle = context.job_executor.spawn_blocking(async move {
+                            image.encode(protocol, image_id, size.0, size.1)
+                        });
+                        self.encode_job = Some((size, handle));
+                    }
+                    Some("Loading image…".to_string())
+                }
+            }
+        } else {
+            Some("Loading image…".to_string())
+        };
+        if let Some(status) = status {
+            write_string_to_grid(
+                &status,
+                grid,
+                theme_default.fg,
+                theme_default.bg,
+                theme_default.attrs,
+                image_area,
+                None,
+            );
+        }
+    }
+
+    fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
+        match event {
+            UIEvent::Resize => {
+                self.set_dirty(true);
+            }
+            UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
+                if self.image_job.as_ref().map(|h| h.job_id == *job_id) == Some(true) =>
+            {
+                let mut handle = self.image_job.take().unwrap();
+                match handle.chan.try_recv() {
+                    Err(_) => { /* Job was canceled */ }
+                    Ok(None) => { /* something happened, perhaps a worker thread panicked */ }
+                    Ok(Some(Ok(image))) => self.image = Some(image),
+                    Ok(Some(Err(err))) => self.error = Some(err),
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
+                if self.encode_job.as_ref().map(|(_, h)| h.job_id == *job_id) == Some(true) =>
+            {
+                let (size, mut handle) = self.encode_job.take().unwrap();
+                match handle.chan.try_recv() {
+                    Err(_) => { /* Job was canceled */ }
+                    Ok(None) => { /* something happened, perhaps a worker thread panicked */ }
+                    Ok(Some(Ok(escape))) => self.encoded = Some((size, escape)),
+                    Ok(Some(Err(err))) => self.error = Some(err),
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(Key::Char('v')) => {
+                super::open_attachment_externally(&self.attachment, context);
+                return true;
+            }
+            _ => {}
+        }
+        false
+    }
+
+    fn is_dirty(&self) -> bool {
+        self.dirty
+    }
+
+    fn set_dirty(&mut self, value: bool) {
+        self.dirty = value;
+    }
+
+    fn id(&self) -> ComponentId {
+        self.id
+    }
+
+    fn set_id(&mut self, id: ComponentId) {
+        self.id = id;
+    }
+}
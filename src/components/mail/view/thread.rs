@@ -21,7 +21,9 @@
 
 use super::*;
 use crate::components::PageMovement;
+use crate::jobs::{JobId, JoinHandle};
 use std::cmp;
+use std::collections::HashMap;
 
 #[derive(Debug, Clone)]
 struct ThreadEntry {
@@ -35,6 +37,28 @@ struct ThreadEntry {
     heading: String,
 }
 
+/// All messages of a thread shown one after the other in a single pager, with quoted text
+/// collapsed unless `expand_quotes` is set.
+#[derive(Debug, Default)]
+struct Conversation {
+    pager: Pager,
+    bodies: HashMap<EnvelopeHash, String>,
+    loading: HashMap<JobId, (EnvelopeHash, JoinHandle<Result<Vec<u8>>>)>,
+    expand_quotes: bool,
+}
+
+impl Clone for Conversation {
+    fn clone(&self) -> Self {
+        /* Bodies still loading are fetched again by the clone. */
+        Conversation {
+            pager: self.pager.clone(),
+            bodies: self.bodies.clone(),
+            loading: HashMap::default(),
+            expand_quotes: self.expand_quotes,
+        }
+    }
+}
+
 #[derive(Debug, Default, Clone)]
 pub struct ThreadView {
     new_cursor_pos: usize,
@@ -47,6 +71,7 @@ pub struct ThreadView {
     mailview: MailView,
     show_mailview: bool,
     show_thread: bool,
+    conversation: Option<Conversation>,
     entries: Vec<ThreadEntry>,
     visible_entries: Vec<Vec<usize>>,
     indentation_colors: [ThemeAttribute; 6],
@@ -97,8 +122,86 @@ impl ThreadView {
         };
         view.initiate(expanded_hash, context);
         view.new_cursor_pos = view.new_expanded_pos;
+        if *mailbox_settings!(
+            context[coordinates.0][&coordinates.1]
+                .listing
+                .conversation_view
+        ) {
+            view.conversation = Some(Conversation::default());
+        }
         view
     }
+
+    /// Fetches the bodies of the thread's messages that are not loaded yet and fills the
+    /// conversation pager with every message of the thread, in thread order.
+    fn update_conversation(&mut self, context: &mut Context) {
+        let conversation = match self.conversation {
+            Some(ref mut c) => c,
+            None => return,
+        };
+        let account = &context.accounts[&self.coordinates.0];
+        for e in &self.entries {
+            if conversation.bodies.contains_key(&e.msg_hash)
+                || conversation
+                    .loading
+                    .values()
+                    .any(|(env_hash, _)| *env_hash == e.msg_hash)
+            {
+                continue;
+            }
+            match account
+                .operation(e.msg_hash)
+                .and_then(|mut op| op.as_bytes())
+            {
+                Ok(fut) => {
+                    let handle = account.job_executor.spawn_specialized(fut);
+                    context
+                        .replies
+                        .push_back(UIEvent::StatusEvent(StatusEvent::NewJob(handle.job_id)));
+                    conversation
+                        .loading
+                        .insert(handle.job_id, (e.msg_hash, handle));
+                }
+                Err(err) => {
+                    conversation
+                        .bodies
+                        .insert(e.msg_hash, format!("Could not get message: {}", err));
+                }
+            }
+        }
+
+        let mut text = String::new();
+        if let Some(root) = self.entries.iter().min_by_key(|e| e.index.0) {
+            text.push_str(&format!(
+                "{} ({})\n\n",
+                account.collection.get_env(root.msg_hash).subject(),
+                self.entries.len()
+            ));
+        }
+        for e in &self.entries {
+            let envelope: EnvelopeRef = account.collection.get_env(e.msg_hash);
+            text.push_str(&format!(
+                "── {} - {}{}\n\n",
+                envelope.field_from_to_string(),
+                crate::dates::format_date_header(
+                    &envelope,
+                    context.settings.pager.datetime_fmt.as_deref(),
+                    context.settings.pager.local_dates,
+                ),
+                if envelope.is_seen() { "" } else { " (unread)" },
+            ));
+            match conversation.bodies.get(&e.msg_hash) {
+                Some(body) if conversation.expand_quotes => text.push_str(body),
+                Some(body) => text.push_str(&collapse_quotes(body)),
+                None => text.push_str("Loading...\n"),
+            }
+            text.push('\n');
+        }
+        let colors = crate::conf::value(context, "mail.view.body");
+        let cursor_pos = conversation.pager.cursor_pos();
+        conversation.pager =
+            Pager::from_string(text, Some(context), Some(cursor_pos), None, colors);
+    }
     pub fn update(&mut self, context: &Context) {
         if self.entries.is_empty() {
             return;
@@ -203,12 +306,17 @@ impl ThreadView {
                 .collection
                 .get_env(e.msg_hash);
             let thread_node = &threads.thread_nodes()[&e.index.1];
+            let date = crate::dates::format_date(
+                envelope.date(),
+                context.settings.listing.datetime_fmt.as_deref(),
+                context.settings.listing.recent_dates,
+            );
             let string = if thread_node.show_subject() {
                 let subject = envelope.subject();
                 highlight_reply_subjects.push(Some(subject.grapheme_width()));
                 format!(
                     "  {} - {} {}{}",
-                    envelope.date_as_str(),
+                    date,
                     envelope.field_from_to_string(),
                     envelope.subject(),
                     if envelope.has_attachments() {
@@ -221,7 +329,7 @@ impl ThreadView {
                 highlight_reply_subjects.push(None);
                 format!(
                     "  {} - {}{}",
-                    envelope.date_as_str(),
+                    date,
                     envelope.field_from_to_string(),
                     if envelope.has_attachments() {
                         " 📎"
@@ -234,6 +342,9 @@ impl ThreadView {
             width = cmp::max(width, e.index.0 * 4 + e.heading.grapheme_width() + 2);
         }
         let theme_default = crate::conf::value(context, "theme_default");
+        let unseen_attr = crate::conf::value(context, "mail.view.thread.unseen");
+        let subject_attr = crate::conf::value(context, "mail.view.thread.subject");
+        let attachment_flag_attr = crate::conf::value(context, "mail.listing.attachment_flag");
         let mut content = CellBuffer::new_with_context(width, height, None, context);
         if self.reversed {
             for (y, e) in self.entries.iter().rev().enumerate() {
@@ -262,14 +373,18 @@ impl ThreadView {
                     if e.seen {
                         theme_default.fg
                     } else {
-                        Color::Byte(0)
+                        unseen_attr.fg
                     },
                     if e.seen {
                         theme_default.bg
                     } else {
-                        Color::Byte(251)
+                        unseen_attr.bg
+                    },
+                    if e.seen {
+                        theme_default.attrs
+                    } else {
+                        unseen_attr.attrs
                     },
-                    theme_default.attrs,
                     (
                         (e.index.0 * 4 + 1, 2 * y),
                         (e.index.0 * 4 + e.heading.grapheme_width() + 1, height - 1),
@@ -282,13 +397,13 @@ impl ThreadView {
                         .get_env(e.msg_hash);
                     if envelope.has_attachments() {
                         content[(e.index.0 * 4 + e.heading.grapheme_width(), 2 * y)]
-                            .set_fg(Color::Byte(103));
+                            .set_fg(attachment_flag_attr.fg);
                     }
                 }
                 if let Some(len) = highlight_reply_subjects[y] {
                     let index = e.index.0 * 4 + 1 + e.heading.grapheme_width() - len;
                     let area = ((index, 2 * y), (width - 2, 2 * y));
-                    change_colors(&mut content, area, Color::Byte(33), theme_default.bg);
+                    change_colors(&mut content, area, subject_attr.fg, theme_default.bg);
                 }
                 set_and_join_box(&mut content, (e.index.0 * 4, 2 * y), BoxBoundary::Vertical);
                 set_and_join_box(
@@ -343,14 +458,18 @@ impl ThreadView {
                     if e.seen {
                         theme_default.fg
                     } else {
-                        Color::Byte(0)
+                        unseen_attr.fg
                     },
                     if e.seen {
                         theme_default.bg
                     } else {
-                        Color::Byte(251)
+                        unseen_attr.bg
+                    },
+                    if e.seen {
+                        theme_default.attrs
+                    } else {
+                        unseen_attr.attrs
                     },
-                    theme_default.attrs,
                     (
                         (e.index.0 * 4 + 1, 2 * y),
                         (e.index.0 * 4 + e.heading.grapheme_width() + 1, height - 1),
@@ -363,13 +482,13 @@ impl ThreadView {
                         .get_env(e.msg_hash);
                     if envelope.has_attachments() {
                         content[(e.index.0 * 4 + e.heading.grapheme_width(), 2 * y)]
-                            .set_fg(Color::Byte(103));
+                            .set_fg(attachment_flag_attr.fg);
                     }
                 }
                 if let Some(_len) = highlight_reply_subjects[y] {
                     let index = e.index.0 * 4 + 1;
                     let area = ((index, 2 * y), (width - 2, 2 * y));
-                    change_colors(&mut content, area, Color::Byte(33), theme_default.bg);
+                    change_colors(&mut content, area, subject_attr.fg, theme_default.bg);
                 }
                 set_and_join_box(&mut content, (e.index.0 * 4, 2 * y), BoxBoundary::Vertical);
                 set_and_join_box(
@@ -425,7 +544,7 @@ impl ThreadView {
             .collect();
         if idx == *visibles[self.cursor_pos] {
             let theme_default = crate::conf::value(context, "theme_default");
-            let bg_color = Color::Byte(246);
+            let bg_color = crate::conf::value(context, "mail.view.thread.highlighted").bg;
             let attrs = if self.use_color {
                 theme_default.attrs
             } else {
@@ -484,6 +603,9 @@ impl ThreadView {
                     }
                 }
                 PageMovement::Right(_) | PageMovement::Left(_) => {}
+                PageMovement::Row(row) => {
+                    self.new_cursor_pos = (self.new_cursor_pos / rows) * rows + row;
+                }
                 PageMovement::Home => {
                     self.new_cursor_pos = 0;
                 }
@@ -658,6 +780,7 @@ impl ThreadView {
         let mid = get_x(upper_left) + self.content.size().0;
 
         let theme_default = crate::conf::value(context, "theme_default");
+        let subject_attr = crate::conf::value(context, "mail.view.thread.subject");
         /* First draw the thread subject on the first row */
         let y = if self.dirty {
             clear_area(grid, area, theme_default);
@@ -681,9 +804,9 @@ impl ThreadView {
             let (x, y) = write_string_to_grid(
                 &envelope.subject(),
                 grid,
-                Color::Byte(33),
-                theme_default.bg,
-                theme_default.attrs,
+                subject_attr.fg,
+                subject_attr.bg,
+                subject_attr.attrs,
                 area,
                 Some(get_x(upper_left)),
             );
@@ -715,7 +838,7 @@ impl ThreadView {
         for x in get_x(upper_left)..=get_x(bottom_right) {
             set_and_join_box(grid, (x, y - 1), BoxBoundary::Horizontal);
             grid[(x, y - 1)]
-                .set_fg(Color::Byte(33))
+                .set_fg(subject_attr.fg)
                 .set_bg(theme_default.bg);
         }
 
@@ -766,6 +889,7 @@ impl ThreadView {
         let mid = mid;
 
         let theme_default = crate::conf::value(context, "theme_default");
+        let subject_attr = crate::conf::value(context, "mail.view.thread.subject");
         /* First draw the thread subject on the first row */
         let y = {
             clear_area(grid, area, theme_default);
@@ -789,9 +913,9 @@ impl ThreadView {
             let (x, y) = write_string_to_grid(
                 &envelope.subject(),
                 grid,
-                Color::Byte(33),
-                theme_default.bg,
-                theme_default.attrs,
+                subject_attr.fg,
+                subject_attr.bg,
+                subject_attr.attrs,
                 area,
                 Some(get_x(upper_left)),
             );
@@ -988,6 +1112,17 @@ impl Component for ThreadView {
             self.mailview.update(coordinates, context);
         }
 
+        if self.conversation.is_some() {
+            if self.dirty {
+                self.update_conversation(context);
+            }
+            if let Some(ref mut conversation) = self.conversation {
+                conversation.pager.draw(grid, area, context);
+            }
+            self.dirty = false;
+            return;
+        }
+
         if self.entries.len() == 1 {
             self.mailview.draw(grid, area, context);
             self.dirty = false;
@@ -1013,7 +1148,68 @@ impl Component for ThreadView {
             _ => {}
         }
 
-        if self.show_mailview && self.mailview.process_event(event, context) {
+        let shortcuts = self.get_shortcuts(context);
+        match *event {
+            UIEvent::Input(ref key)
+                if shortcut!(key == shortcuts[ThreadView::DESCRIPTION]["toggle_conversation"]) =>
+            {
+                self.conversation = if self.conversation.is_some() {
+                    None
+                } else {
+                    Some(Conversation::default())
+                };
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if self.conversation.is_some()
+                    && shortcut!(key == shortcuts[ThreadView::DESCRIPTION]["toggle_quotes"]) =>
+            {
+                if let Some(ref mut conversation) = self.conversation {
+                    conversation.expand_quotes = !conversation.expand_quotes;
+                }
+                self.set_dirty(true);
+                return true;
+            }
+            UIEvent::StatusEvent(StatusEvent::JobFinished(ref job_id))
+                if self
+                    .conversation
+                    .as_ref()
+                    .map(|c| c.loading.contains_key(job_id))
+                    .unwrap_or(false) =>
+            {
+                let conversation = self.conversation.as_mut().unwrap();
+                let (env_hash, mut handle) = conversation.loading.remove(job_id).unwrap();
+                match handle.chan.try_recv() {
+                    Err(_) => { /* Job was canceled */ }
+                    Ok(None) => { /* something happened, perhaps a worker thread panicked */ }
+                    Ok(Some(Ok(bytes))) => {
+                        conversation.bodies.insert(
+                            env_hash,
+                            AttachmentBuilder::new(&bytes).build().searchable_text(),
+                        );
+                    }
+                    Ok(Some(Err(err))) => {
+                        conversation
+                            .bodies
+                            .insert(env_hash, format!("Could not get message: {}", err));
+                    }
+                }
+                self.dirty = true;
+                return true;
+            }
+            _ => {}
+        }
+        if let Some(ref mut conversation) = self.conversation {
+            if conversation.pager.process_event(event, context) {
+                return true;
+            }
+        }
+
+        if self.conversation.is_none()
+            && self.show_mailview
+            && self.mailview.process_event(event, context)
+        {
             return true;
         }
 
@@ -1163,11 +1359,18 @@ impl Component for ThreadView {
         false
     }
     fn is_dirty(&self) -> bool {
-        self.dirty || (self.show_mailview && self.mailview.is_dirty())
+        self.dirty
+            || match self.conversation {
+                Some(ref conversation) => conversation.pager.is_dirty(),
+                None => self.show_mailview && self.mailview.is_dirty(),
+            }
     }
     fn set_dirty(&mut self, value: bool) {
         self.dirty = value;
         self.mailview.set_dirty(value);
+        if let Some(ref mut conversation) = self.conversation {
+            conversation.pager.set_dirty(value);
+        }
     }
     fn get_shortcuts(&self, context: &Context) -> ShortcutMaps {
         let mut map = self.mailview.get_shortcuts(context);
@@ -1193,3 +1396,37 @@ impl Component for ThreadView {
             .push_back(UIEvent::Action(Tab(Kill(self.id))));
     }
 }
+
+/// Replaces each run of quoted lines, the ones starting with `>`, with a line counting them.
+fn collapse_quotes(text: &str) -> String {
+    fn flush(ret: &mut String, quoted: &mut usize) {
+        match *quoted {
+            0 => {}
+            1 => ret.push_str("[1 quoted line]\n"),
+            n => ret.push_str(&format!("[{} quoted lines]\n", n)),
+        }
+        *quoted = 0;
+    }
+    let mut ret = String::with_capacity(text.len());
+    let mut quoted = 0;
+    for line in text.lines() {
+        if line.trim_start().starts_with('>') {
+            quoted += 1;
+            continue;
+        }
+        flush(&mut ret, &mut quoted);
+        ret.push_str(line);
+        ret.push('\n');
+    }
+    flush(&mut ret, &mut quoted);
+    ret
+}
+
+#[test]
+fn test_collapse_quotes() {
+    assert_eq!(
+        collapse_quotes("Sure.\n\nOn Monday, Alice wrote:\n> Lunch?\n> > Hungry\n>\nBye\n> sig"),
+        "Sure.\n\nOn Monday, Alice wrote:\n[3 quoted lines]\nBye\n[1 quoted line]\n"
+    );
+    assert_eq!(collapse_quotes("no quotes"), "no quotes\n");
+}
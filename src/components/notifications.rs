@@ -29,6 +29,22 @@ use super::*;
 #[cfg(all(target_os = "linux", feature = "dbus-notifications"))]
 pub use dbus::*;
 
+/// Whether notifications are enabled for `kind`. New mail notifications use the settings of their
+/// mailbox, which can override the account and global `notifications.enable` values.
+fn is_enabled(kind: &Option<NotificationType>, context: &Context) -> bool {
+    match *kind {
+        Some(NotificationType::NewMail(account_hash, mailbox_hash, _))
+            if context.accounts.contains_key(&account_hash)
+                && context.accounts[&account_hash]
+                    .mailbox_entries
+                    .contains_key(&mailbox_hash) =>
+        {
+            *mailbox_settings!(context[account_hash][&mailbox_hash].notifications.enable)
+        }
+        _ => context.settings.notifications.enable,
+    }
+}
+
 #[cfg(all(target_os = "linux", feature = "dbus-notifications"))]
 mod dbus {
     use super::*;
@@ -58,12 +74,8 @@ mod dbus {
         fn draw(&mut self, _grid: &mut CellBuffer, _area: Area, _context: &mut Context) {}
 
         fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
-            if !context.settings.notifications.enable {
-                return false;
-            }
-
             if let UIEvent::Notification(ref title, ref body, ref kind) = event {
-                if !self.rate_limit.tick() {
+                if !is_enabled(kind, context) || !self.rate_limit.tick() {
                     return false;
                 }
 
@@ -74,10 +86,12 @@ mod dbus {
                     .summary(title.as_ref().map(String::as_str).unwrap_or("meli"))
                     .body(&escape_str(body));
                 match *kind {
-                    Some(NotificationType::NewMail) => {
-                        notification.hint(notify_rust::Hint::Category("email".to_owned()));
+                    Some(NotificationType::NewMail(..)) => {
+                        notification.hint(notify_rust::Hint::Category("email.arrived".to_owned()));
                         notification.icon("mail-message-new");
                         notification.sound_name("message-new-email");
+                        /* Clicking the notification shows the mailbox. */
+                        notification.action("default", "Show mailbox");
                     }
                     Some(NotificationType::SentMail) => {
                         notification.hint(notify_rust::Hint::Category("email".to_owned()));
@@ -97,7 +111,11 @@ mod dbus {
                         notification.icon("face-embarrassed");
                     }
                     Some(NotificationType::Error(melib::ErrorKind::None))
-                    | Some(NotificationType::Error(melib::ErrorKind::External)) => {
+                    | Some(NotificationType::Error(melib::ErrorKind::External))
+                    | Some(NotificationType::Error(melib::ErrorKind::NotFound))
+                    | Some(NotificationType::Error(melib::ErrorKind::Protocol))
+                    | Some(NotificationType::Error(melib::ErrorKind::Io))
+                    | Some(NotificationType::Error(melib::ErrorKind::Configuration)) => {
                         notification.icon("dialog-error");
                     }
                     Some(NotificationType::Error(melib::ErrorKind::Network)) => {
@@ -116,7 +134,29 @@ mod dbus {
                     notification.hint(notify_rust::Hint::SuppressSound(true));
                 }
 
-                if let Err(err) = notification.show() {
+                if let Some(NotificationType::NewMail(account_hash, mailbox_hash, _)) = *kind {
+                    /* Waiting for the notification to be clicked or closed blocks, so do it in
+                     * another thread. */
+                    let sender = context.sender.clone();
+                    std::thread::spawn(move || match notification.show() {
+                        Ok(handle) => handle.wait_for_action(|action| {
+                            if action == "default" {
+                                let _ = sender.send(ThreadEvent::UIEvent(UIEvent::Action(
+                                    Action::Listing(ListingAction::OpenMailbox(
+                                        account_hash,
+                                        mailbox_hash,
+                                    )),
+                                )));
+                            }
+                        }),
+                        Err(err) => {
+                            melib::log(
+                                format!("Could not show dbus notification: {}", err),
+                                melib::ERROR,
+                            );
+                        }
+                    });
+                } else if let Err(err) = notification.show() {
                     debug!("Could not show dbus notification: {:?}", &err);
                     melib::log(
                         format!("Could not show dbus notification: {}", err),
@@ -189,7 +229,7 @@ impl Component for NotificationCommand {
 
     fn process_event(&mut self, event: &mut UIEvent, context: &mut Context) -> bool {
         if let UIEvent::Notification(ref title, ref body, ref kind) = event {
-            if context.settings.notifications.enable {
+            if is_enabled(kind, context) {
                 if let Some(ref bin) = context.settings.notifications.script {
                     match Command::new(bin)
                         .arg(&kind.map(|k| k.to_string()).unwrap_or_default())
@@ -213,7 +253,33 @@ impl Component for NotificationCommand {
                 }
             }
 
-            if *kind == Some(NotificationType::NewMail) {
+            if let Some(NotificationType::NewMail(account_hash, mailbox_hash, env_hash)) = *kind {
+                if let Some(script) = mailbox_settings!(
+                    context[account_hash][&mailbox_hash]
+                        .notifications
+                        .new_mail_script
+                )
+                .clone()
+                {
+                    run_new_mail_script(&script, account_hash, mailbox_hash, env_hash, context);
+                }
+                if let Some(info) =
+                    crate::ipc::MessageInfo::new(context, account_hash, mailbox_hash, env_hash)
+                {
+                    context.plugins.emit(crate::ipc::PluginEvent::NewMail(info));
+                }
+                if let Some(hook) = context.settings.hooks.new_mail.clone() {
+                    let account = &context.accounts[&account_hash];
+                    if account.collection.contains_key(&env_hash) {
+                        crate::conf::hooks::spawn_hook(
+                            "new_mail",
+                            hook,
+                            None,
+                            account.envelope_env_vars(mailbox_hash, env_hash),
+                            context.sender.clone(),
+                        );
+                    }
+                }
                 if let Some(ref path) = context.settings.notifications.xbiff_file_path {
                     if let Err(err) = update_xbiff(path) {
                         debug!("Could not update xbiff file: {:?}", &err);
@@ -234,6 +300,37 @@ impl Component for NotificationCommand {
     fn set_id(&mut self, _id: ComponentId) {}
 }
 
+fn run_new_mail_script(
+    script: &str,
+    account_hash: AccountHash,
+    mailbox_hash: MailboxHash,
+    env_hash: EnvelopeHash,
+    context: &mut Context,
+) {
+    let account = &context.accounts[&account_hash];
+    if !account.collection.contains_key(&env_hash) {
+        return;
+    }
+    match Command::new("sh")
+        .args(&["-c", script])
+        .envs(account.envelope_env_vars(mailbox_hash, env_hash))
+        .stdin(Stdio::null())
+        .stdout(Stdio::null())
+        .stderr(Stdio::null())
+        .spawn()
+    {
+        Ok(child) => {
+            context.children.push(child);
+        }
+        Err(err) => {
+            log(
+                format!("Could not run new mail script: {}.", err.to_string()),
+                ERROR,
+            );
+        }
+    }
+}
+
 fn update_xbiff(path: &str) -> Result<()> {
     let mut file = std::fs::OpenOptions::new()
         .append(true) /* writes will append to a file instead of overwriting previous contents */
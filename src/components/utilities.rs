@@ -68,6 +68,13 @@ pub struct StatusBar {
 
     auto_complete: AutoComplete,
     cmd_history: Vec<String>,
+    search_history: Vec<String>,
+    /// Query and matched history position of an ongoing reverse history search.
+    reverse_search: Option<(String, usize)>,
+    /// Redraws the clock of `terminal.status_bar_format`.
+    clock_timer: Option<crate::jobs::Timer>,
+    /// Whether the listing is filtered by the `search` command being typed.
+    search_preview: bool,
 }
 
 impl fmt::Display for StatusBar {
@@ -110,23 +117,110 @@ impl StatusBar {
             done_jobs: HashSet::default(),
             scroll_contexts: IndexMap::default(),
             cmd_history: crate::command::history::old_cmd_history(),
+            search_history: crate::command::history::old_search_history(),
+            reverse_search: None,
+            clock_timer: context
+                .settings
+                .terminal
+                .status_bar_format
+                .as_ref()
+                .map(|_| {
+                    context.job_executor.clone().create_timer(
+                        std::time::Duration::from_secs(60),
+                        std::time::Duration::from_secs(60),
+                    )
+                }),
+            search_preview: false,
+        }
+    }
+
+    fn mouse_flag<'a>(&self, context: &'a Context) -> &'a str {
+        if self.mouse {
+            context
+                .settings
+                .terminal
+                .mouse_flag
+                .as_ref()
+                .map(|s| s.as_str())
+                .unwrap_or("🖱️ ")
+        } else {
+            ""
         }
     }
 
+    /// Value of field `field` of `terminal.status_bar_format`, if it is one.
+    fn status_bar_field(&self, field: char, context: &Context) -> Option<String> {
+        use crate::conf::accounts::MailboxStatus;
+        let coordinates = self.container.status_coordinates();
+        let account = coordinates.map(|(account_hash, _)| &context.accounts[&account_hash]);
+        let mailbox = coordinates.and_then(|(account_hash, mailbox_hash)| {
+            context.accounts[&account_hash]
+                .mailbox_entries
+                .get(&mailbox_hash?)
+        });
+        let (unseen, total) = mailbox
+            .and_then(|m| m.ref_mailbox.count().ok())
+            .unwrap_or((0, 0));
+        Some(match field {
+            'M' => format!("{} {}", self.mode, self.mouse_flag(context)),
+            's' => self.status_message.clone(),
+            'a' => account.map(|a| a.name().to_string()).unwrap_or_default(),
+            'm' => mailbox
+                .map(|m| m.ref_mailbox.path().to_string())
+                .unwrap_or_default(),
+            'u' => unseen.to_string(),
+            't' => total.to_string(),
+            'S' => match (account, mailbox.map(|m| &m.status)) {
+                (Some(a), _) if a.is_online.is_err() => "offline".to_string(),
+                (_, Some(MailboxStatus::Parsing(done, total))) => {
+                    format!("syncing {}/{}", done, total)
+                }
+                (_, Some(MailboxStatus::Failed(_))) => "failed".to_string(),
+                _ => String::new(),
+            },
+            'j' => self.in_progress_jobs.len().to_string(),
+            _ => return None,
+        })
+    }
+
     fn draw_status_bar(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
         let mut attribute = crate::conf::value(context, "status.bar");
         if !context.settings.terminal.use_color() {
             attribute.attrs |= Attr::REVERSE;
         }
-        let (x, y) = write_string_to_grid(
-            &self.status,
-            grid,
-            attribute.fg,
-            attribute.bg,
-            attribute.attrs,
-            area,
-            None,
-        );
+        let (x, y) = if let Some(format) = context.settings.terminal.status_bar_format.as_ref() {
+            let (mut x, mut y) = upper_left!(area);
+            let segments =
+                expand_status_bar_format(format, |field| self.status_bar_field(field, context));
+            for (theme_key, text) in segments {
+                let segment_attribute = match theme_key {
+                    Some(key) => crate::conf::value_of(context, &key).unwrap_or(attribute),
+                    None => attribute,
+                };
+                let pos = write_string_to_grid(
+                    &text,
+                    grid,
+                    segment_attribute.fg,
+                    segment_attribute.bg,
+                    segment_attribute.attrs,
+                    ((x, y), bottom_right!(area)),
+                    None,
+                );
+                x = pos.0;
+                y = pos.1;
+            }
+            (x, y)
+        } else {
+            write_string_to_grid(
+                &self.status,
+                grid,
+                attribute.fg,
+                attribute.bg,
+                attribute.attrs,
+                area,
+                None,
+            )
+        };
         for c in grid.row_iter(x..(get_x(bottom_right!(area)) + 1), y) {
             grid[c]
                 .set_ch(' ')
@@ -135,7 +229,9 @@ impl StatusBar {
                 .set_attrs(attribute.attrs);
         }
         let offset = self.status.find('|').unwrap_or_else(|| self.status.len());
-        if y < get_y(bottom_right!(area)) + 1 {
+        if y < get_y(bottom_right!(area)) + 1
+            && context.settings.terminal.status_bar_format.is_none()
+        {
             for x in get_x(upper_left!(area))
                 ..std::cmp::min(
                     get_x(upper_left!(area)) + offset,
@@ -200,24 +296,176 @@ impl StatusBar {
 
     fn draw_command_bar(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
         clear_area(grid, area, crate::conf::value(context, "theme_default"));
+        let command_bar_attr = crate::conf::value(context, "status.command_bar");
+        let prefix_width = if let Some((ref query, _)) = self.reverse_search {
+            let (x, _) = write_string_to_grid(
+                &format!("(reverse-search)`{}': ", query),
+                grid,
+                command_bar_attr.fg,
+                command_bar_attr.bg,
+                command_bar_attr.attrs | Attr::BOLD,
+                area,
+                None,
+            );
+            x - get_x(upper_left!(area))
+        } else {
+            0
+        };
         let (_, y) = write_string_to_grid(
             self.ex_buffer.as_str(),
             grid,
-            Color::Byte(219),
-            Color::Byte(88),
-            Attr::DEFAULT,
-            area,
+            command_bar_attr.fg,
+            command_bar_attr.bg,
+            command_bar_attr.attrs,
+            (
+                pos_inc(upper_left!(area), (prefix_width, 0)),
+                bottom_right!(area),
+            ),
             None,
         );
         if let Some(ref mut cell) = grid.get_mut(
-            pos_inc(upper_left!(area), (self.ex_buffer.cursor(), 0)).0,
+            pos_inc(
+                upper_left!(area),
+                (prefix_width + self.ex_buffer.cursor(), 0),
+            )
+            .0,
             y,
         ) {
             cell.set_attrs(Attr::UNDERLINE);
         }
-        change_colors(grid, area, Color::Byte(219), Color::Byte(88));
+        change_colors(grid, area, command_bar_attr.fg, command_bar_attr.bg);
         context.dirty_areas.push_back(area);
     }
+
+    /// Let the listing filter itself by the term of the `search` command being typed.
+    fn update_search_preview(&mut self, context: &mut Context) {
+        let term = self
+            .ex_buffer
+            .as_str()
+            .strip_prefix("search ")
+            .map(str::trim)
+            .unwrap_or_default();
+        if term.is_empty() && !self.search_preview {
+            return;
+        }
+        self.search_preview = !term.is_empty();
+        context.replies.push_back(UIEvent::Action(Action::Listing(
+            ListingAction::SearchPreview(term.to_string()),
+        )));
+    }
+
+    fn set_ex_buffer(&mut self, value: String) {
+        let mut utext = UText::new(value);
+        let len = utext.as_str().len();
+        utext.set_cursor(len);
+        self.container.set_dirty(true);
+        self.set_dirty(true);
+        self.ex_buffer = Field::Text(utext, None);
+    }
+
+    /// Replace the command with the previous entry in the command history.
+    fn history_prev(&mut self) {
+        if self.cmd_history.is_empty() {
+            return;
+        }
+        let pos = self.ex_buffer_cmd_history_pos.map(|p| p + 1).unwrap_or(0);
+        let pos = std::cmp::min(pos, self.cmd_history.len().saturating_sub(1));
+        if Some(pos) != self.ex_buffer_cmd_history_pos {
+            self.set_ex_buffer(self.cmd_history[self.cmd_history.len() - 1 - pos].clone());
+            self.ex_buffer_cmd_history_pos = Some(pos);
+        }
+    }
+
+    /// Replace the command with the next entry in the command history, or clear it after the
+    /// newest one.
+    fn history_next(&mut self) {
+        match self.ex_buffer_cmd_history_pos {
+            Some(0) => {
+                self.ex_buffer_cmd_history_pos = None;
+                self.ex_buffer.clear();
+                self.dirty = true;
+            }
+            Some(pos) => {
+                self.set_ex_buffer(self.cmd_history[self.cmd_history.len() - pos].clone());
+                self.ex_buffer_cmd_history_pos = Some(pos - 1);
+            }
+            None => {}
+        }
+    }
+
+    /// Show the newest command history entry from position `from` onwards that fuzzily matches
+    /// `query`. If there is none, the current match at position `current` is kept.
+    fn reverse_search(&mut self, query: String, from: usize, current: usize) {
+        let found = self
+            .cmd_history
+            .iter()
+            .rev()
+            .enumerate()
+            .skip(from)
+            .find(|(_, entry)| crate::command::history::fuzzy_match(&query, entry))
+            .map(|(pos, entry)| (pos, entry.clone()));
+        let pos = if let Some((pos, entry)) = found {
+            self.set_ex_buffer(entry);
+            pos
+        } else {
+            current
+        };
+        self.ex_buffer_cmd_history_pos = None;
+        self.reverse_search = Some((query, pos));
+        self.dirty = true;
+    }
+}
+
+/// Expand the fields of a `terminal.status_bar_format` string into runs of text, along with the
+/// theme key set with `%[THEME_KEY]` for each run, if any.
+fn expand_status_bar_format(
+    format: &str,
+    field: impl Fn(char) -> Option<String>,
+) -> Vec<(Option<String>, String)> {
+    let mut segments: Vec<(Option<String>, String)> = vec![(None, String::new())];
+    let mut chars = format.chars();
+    while let Some(c) = chars.next() {
+        if c != '%' {
+            segments.last_mut().unwrap().1.push(c);
+            continue;
+        }
+        let text = match chars.next() {
+            Some('[') => {
+                let key: String = chars.by_ref().take_while(|&c| c != ']').collect();
+                segments.push((Some(key), String::new()));
+                continue;
+            }
+            Some('{') => {
+                let fmt: String = chars.by_ref().take_while(|&c| c != '}').collect();
+                melib::datetime::timestamp_to_string(melib::datetime::now(), Some(&fmt), false)
+            }
+            Some('c') => {
+                melib::datetime::timestamp_to_string(melib::datetime::now(), Some("%H:%M"), false)
+            }
+            Some('%') => "%".to_string(),
+            Some(f) => field(f).unwrap_or_else(|| format!("%{}", f)),
+            None => "%".to_string(),
+        };
+        segments.last_mut().unwrap().1.push_str(&text);
+    }
+    segments
+}
+
+#[test]
+fn test_expand_status_bar_format() {
+    let field = |c| match c {
+        'a' => Some("work".to_string()),
+        'u' => Some("3".to_string()),
+        _ => None,
+    };
+    assert_eq!(
+        expand_status_bar_format("%a %[status.unread]%u%[] 100%% %q", field),
+        vec![
+            (None, "work ".to_string()),
+            (Some("status.unread".to_string()), "3".to_string()),
+            (Some(String::new()), " 100% %q".to_string()),
+        ]
+    );
 }
 
 impl Component for StatusBar {
@@ -284,8 +532,22 @@ impl Component for StatusBar {
                         }
                     })
                     .collect();
-                let command_completion_suggestions =
-                    crate::command::command_completion_suggestions(self.ex_buffer.as_str());
+                for verb in ["search ", "select "].iter() {
+                    if let Some(partial) = self.ex_buffer.as_str().strip_prefix(verb) {
+                        suggestions.extend(self.search_history.iter().rev().filter_map(|term| {
+                            let sug = format!("{}{}", verb, term);
+                            if term.starts_with(partial) && !self.cmd_history.contains(&sug) {
+                                Some(sug.into())
+                            } else {
+                                None
+                            }
+                        }));
+                    }
+                }
+                let command_completion_suggestions = crate::command::command_completion_suggestions(
+                    self.ex_buffer.as_str(),
+                    context,
+                );
 
                 suggestions.extend(command_completion_suggestions.iter().filter_map(|e| {
                     if !unique_suggestions.contains(e.as_str()) {
@@ -338,6 +600,11 @@ impl Component for StatusBar {
                     self.container.set_dirty(true);
                 }
                 let hist_height = std::cmp::min(15, self.auto_complete.suggestions().len());
+                let completions_attr = crate::conf::value(context, "status.completions");
+                let description_attr =
+                    crate::conf::value(context, "status.completions.description");
+                let selected_attr = crate::conf::value(context, "status.completions.selected");
+                let hint_attr = crate::conf::value(context, "status.command_bar.completion_hint");
                 let hist_area = if height < self.auto_complete.suggestions().len() {
                     let hist_area = (
                         (
@@ -357,12 +624,7 @@ impl Component for StatusBar {
                         hist_height,
                         self.auto_complete.suggestions().len(),
                     );
-                    change_colors(
-                        grid,
-                        hist_area,
-                        Color::Byte(197), // DeepPink2,
-                        Color::Byte(174), //LightPink3
-                    );
+                    change_colors(grid, hist_area, completions_attr.fg, completions_attr.bg);
                     context.dirty_areas.push_back(hist_area);
                     hist_area
                 } else {
@@ -390,12 +652,7 @@ impl Component for StatusBar {
                     crate::conf::value(context, "theme_default"),
                 );
                 if hist_height > 0 {
-                    change_colors(
-                        grid,
-                        hist_area,
-                        Color::Byte(88),  // DarkRed,
-                        Color::Byte(174), //LightPink3
-                    );
+                    change_colors(grid, hist_area, completions_attr.fg, completions_attr.bg);
                 }
                 for (y_offset, s) in self
                     .auto_complete
@@ -408,9 +665,9 @@ impl Component for StatusBar {
                     let (x, y) = write_string_to_grid(
                         s.as_str(),
                         grid,
-                        Color::Byte(88),  // DarkRed,
-                        Color::Byte(174), //LightPink3
-                        Attr::DEFAULT,
+                        completions_attr.fg,
+                        completions_attr.bg,
+                        completions_attr.attrs,
                         (
                             set_y(
                                 upper_left!(hist_area),
@@ -423,9 +680,9 @@ impl Component for StatusBar {
                     write_string_to_grid(
                         &s.description,
                         grid,
-                        Color::White,
-                        Color::Byte(174),
-                        Attr::DEFAULT,
+                        description_attr.fg,
+                        description_attr.bg,
+                        description_attr.attrs,
                         ((x + 2, y), bottom_right!(hist_area)),
                         None,
                     );
@@ -442,15 +699,15 @@ impl Component for StatusBar {
                                     get_y(bottom_right!(hist_area)) - hist_height + y_offset + 1,
                                 ),
                             ),
-                            Color::Byte(88),  // DarkRed,
-                            Color::Byte(173), //LightSalmon3
+                            selected_attr.fg,
+                            selected_attr.bg,
                         );
                         write_string_to_grid(
                             &s.as_str()[self.ex_buffer.as_str().len()..],
                             grid,
-                            Color::Byte(97), // MediumPurple3,
-                            Color::Byte(88), //LightPink3
-                            Attr::DEFAULT,
+                            hint_attr.fg,
+                            hint_attr.bg,
+                            hint_attr.attrs,
                             (
                                 (
                                     get_x(upper_left)
@@ -498,24 +755,8 @@ impl Component for StatusBar {
             }
             UIEvent::ChangeMode(m) => {
                 let offset = self.status.find('|').unwrap_or_else(|| self.status.len());
-                self.status.replace_range(
-                    ..offset,
-                    &format!(
-                        "{} {}",
-                        m,
-                        if self.mouse {
-                            context
-                                .settings
-                                .terminal
-                                .mouse_flag
-                                .as_ref()
-                                .map(|s| s.as_str())
-                                .unwrap_or("🖱️ ")
-                        } else {
-                            ""
-                        },
-                    ),
-                );
+                self.status
+                    .replace_range(..offset, &format!("{} {}", m, self.mouse_flag(context)));
                 self.set_dirty(true);
                 self.container.set_dirty(true);
                 self.mode = *m;
@@ -527,15 +768,28 @@ impl Component for StatusBar {
                                 .replies
                                 .push_back(UIEvent::Command(self.ex_buffer.as_str().to_string()));
                         }
-                        if parse_command(&self.ex_buffer.as_str().as_bytes()).is_ok()
-                            && self.cmd_history.last().map(String::as_str)
+                        if let Ok(action) = parse_command(&self.ex_buffer.as_str().as_bytes()) {
+                            if let Action::Listing(ListingAction::Search(ref pattern))
+                            | Action::Listing(ListingAction::Select(ref pattern)) = action
+                            {
+                                if self.search_history.last() != Some(pattern) {
+                                    crate::command::history::log_search(pattern.clone());
+                                    self.search_history.push(pattern.clone());
+                                }
+                            }
+                            if self.cmd_history.last().map(String::as_str)
                                 != Some(self.ex_buffer.as_str())
-                        {
-                            crate::command::history::log_cmd(self.ex_buffer.as_str().to_string());
-                            self.cmd_history.push(self.ex_buffer.as_str().to_string());
+                            {
+                                crate::command::history::log_cmd(
+                                    self.ex_buffer.as_str().to_string(),
+                                );
+                                self.cmd_history.push(self.ex_buffer.as_str().to_string());
+                            }
                         }
                         self.ex_buffer.clear();
                         self.ex_buffer_cmd_history_pos.take();
+                        self.reverse_search.take();
+                        self.search_preview = false;
                     }
                     UIMode::Command => {
                         self.height = 2;
@@ -545,6 +799,33 @@ impl Component for StatusBar {
                     }
                 };
             }
+            UIEvent::CmdInput(Key::Ctrl('r')) => {
+                let (query, from, current) = match self.reverse_search.take() {
+                    Some((query, pos)) => (query, pos + 1, pos),
+                    None => (self.ex_buffer.as_str().to_string(), 0, 0),
+                };
+                self.reverse_search(query, from, current);
+                return true;
+            }
+            UIEvent::CmdInput(Key::Char(c)) if self.reverse_search.is_some() => {
+                let (mut query, pos) = self.reverse_search.take().unwrap();
+                query.push(*c);
+                self.reverse_search(query, pos, pos);
+                return true;
+            }
+            UIEvent::CmdInput(Key::Backspace) if self.reverse_search.is_some() => {
+                let (mut query, pos) = self.reverse_search.take().unwrap();
+                query.pop();
+                self.reverse_search(query, 0, pos);
+                return true;
+            }
+            UIEvent::CmdInput(k) if k != &Key::Esc && self.reverse_search.is_some() => {
+                /* Any other key accepts the match and continues editing it. */
+                self.reverse_search.take();
+                self.container.set_dirty(true);
+                self.dirty = true;
+                return true;
+            }
             UIEvent::CmdInput(Key::Char('\t')) => {
                 if let Some(suggestion) = self.auto_complete.get_suggestion().or_else(|| {
                     if self.auto_complete.cursor() == 0 {
@@ -568,18 +849,29 @@ impl Component for StatusBar {
                 self.dirty = true;
                 self.ex_buffer
                     .process_event(&mut UIEvent::InsertInput(Key::Char(*c)), context);
+                self.update_search_preview(context);
                 return true;
             }
             UIEvent::CmdInput(Key::Paste(s)) => {
                 self.dirty = true;
                 self.ex_buffer
                     .process_event(&mut UIEvent::InsertInput(Key::Paste(s.clone())), context);
+                self.update_search_preview(context);
                 return true;
             }
             UIEvent::CmdInput(Key::Ctrl('u')) => {
                 self.dirty = true;
                 self.ex_buffer.clear();
                 self.ex_buffer_cmd_history_pos.take();
+                self.update_search_preview(context);
+                return true;
+            }
+            UIEvent::CmdInput(Key::Up) if self.auto_complete.suggestions().is_empty() => {
+                self.history_prev();
+                return true;
+            }
+            UIEvent::CmdInput(Key::Down) if self.auto_complete.suggestions().is_empty() => {
+                self.history_next();
                 return true;
             }
             UIEvent::CmdInput(Key::Up) => {
@@ -611,60 +903,24 @@ impl Component for StatusBar {
                 self.dirty = true;
             }
             UIEvent::CmdInput(Key::Ctrl('p')) => {
-                if self.cmd_history.is_empty() {
-                    return true;
-                }
-                let pos = self.ex_buffer_cmd_history_pos.map(|p| p + 1).unwrap_or(0);
-                let pos = Some(std::cmp::min(pos, self.cmd_history.len().saturating_sub(1)));
-                if pos != self.ex_buffer_cmd_history_pos {
-                    let mut utext = UText::new(
-                        self.cmd_history[self.cmd_history.len().saturating_sub(1) - pos.unwrap()]
-                            .clone(),
-                    );
-                    let len = utext.as_str().len();
-                    utext.set_cursor(len);
-                    self.container.set_dirty(true);
-                    self.set_dirty(true);
-                    self.ex_buffer = Field::Text(utext, None);
-                    self.ex_buffer_cmd_history_pos = pos;
-                    self.dirty = true;
-                }
-
+                self.history_prev();
                 return true;
             }
             UIEvent::CmdInput(Key::Ctrl('n')) => {
-                if self.cmd_history.is_empty() {
-                    return true;
-                }
-                if Some(0) == self.ex_buffer_cmd_history_pos {
-                    self.ex_buffer_cmd_history_pos = None;
-                    self.ex_buffer.clear();
-                    self.dirty = true;
-                } else if self.ex_buffer_cmd_history_pos.is_some() {
-                    let pos = self.ex_buffer_cmd_history_pos.map(|p| p - 1);
-                    let mut utext = UText::new(
-                        self.cmd_history[self.cmd_history.len().saturating_sub(1) - pos.unwrap()]
-                            .clone(),
-                    );
-                    let len = utext.as_str().len();
-                    utext.set_cursor(len);
-                    self.container.set_dirty(true);
-                    self.set_dirty(true);
-                    self.ex_buffer = Field::Text(utext, None);
-                    self.ex_buffer_cmd_history_pos = pos;
-                    self.dirty = true;
-                }
-
+                self.history_next();
                 return true;
             }
             UIEvent::CmdInput(k @ Key::Backspace) | UIEvent::CmdInput(k @ Key::Ctrl(_)) => {
                 self.dirty = true;
                 self.ex_buffer
                     .process_event(&mut UIEvent::InsertInput(k.clone()), context);
+                self.update_search_preview(context);
                 return true;
             }
             UIEvent::CmdInput(Key::Esc) => {
                 self.ex_buffer.clear();
+                self.reverse_search.take();
+                self.update_search_preview(context);
                 context
                     .replies
                     .push_back(UIEvent::ChangeMode(UIMode::Normal));
@@ -674,6 +930,10 @@ impl Component for StatusBar {
             UIEvent::Resize => {
                 self.dirty = true;
             }
+            UIEvent::Timer(n) if self.clock_timer.as_ref().map(|t| t.id()) == Some(*n) => {
+                self.dirty = true;
+                return true;
+            }
             UIEvent::StatusEvent(StatusEvent::BufClear) => {
                 self.display_buffer.clear();
                 self.dirty = true;
@@ -688,17 +948,7 @@ impl Component for StatusBar {
                 self.status = format!(
                     "{} {}| {}",
                     self.mode,
-                    if self.mouse {
-                        context
-                            .settings
-                            .terminal
-                            .mouse_flag
-                            .as_ref()
-                            .map(|s| s.as_str())
-                            .unwrap_or("🖱️ ")
-                    } else {
-                        ""
-                    },
+                    self.mouse_flag(context),
                     &self.status_message,
                 );
                 self.dirty = true;
@@ -708,17 +958,7 @@ impl Component for StatusBar {
                 self.status = format!(
                     "{} {}| {}",
                     self.mode,
-                    if self.mouse {
-                        context
-                            .settings
-                            .terminal
-                            .mouse_flag
-                            .as_ref()
-                            .map(|s| s.as_str())
-                            .unwrap_or("🖱️ ")
-                    } else {
-                        ""
-                    },
+                    self.mouse_flag(context),
                     &self.status_message,
                 );
                 self.dirty = true;
@@ -789,6 +1029,10 @@ impl Component for StatusBar {
     fn can_quit_cleanly(&mut self, context: &Context) -> bool {
         self.container.can_quit_cleanly(context)
     }
+
+    fn save_session(&self, session: &mut crate::session::Session, context: &Context) {
+        self.container.save_session(session, context)
+    }
 }
 
 #[derive(Debug)]
@@ -910,6 +1154,18 @@ impl Tabbed {
     pub fn add_component(&mut self, new: Box<dyn Component>) {
         self.children.push(new);
     }
+
+    /// Focus tab `idx`, eg. the one that was focused when the last session was saved.
+    pub fn set_active_tab(&mut self, idx: usize, context: &Context) {
+        if idx >= self.children.len() {
+            return;
+        }
+        self.cursor_pos = idx;
+        let mut children_maps = self.children[self.cursor_pos].get_shortcuts(context);
+        children_maps.extend(self.get_shortcuts(context));
+        self.help_curr_views = children_maps;
+        self.set_dirty(true);
+    }
 }
 
 impl fmt::Display for Tabbed {
@@ -1412,7 +1668,11 @@ impl Component for Tabbed {
                 self.dirty = true;
                 return true;
             }
-            UIEvent::Input(Key::Char('n')) if self.show_shortcuts && self.help_search.is_some() => {
+            UIEvent::Input(ref key)
+                if self.show_shortcuts
+                    && self.help_search.is_some()
+                    && *key == context.settings.shortcuts.pager.search_next =>
+            {
                 if let Some(ref mut search) = self.help_search {
                     search.movement = Some(PageMovement::Down(1));
                     search.cursor += 1;
@@ -1424,7 +1684,11 @@ impl Component for Tabbed {
                 self.dirty = true;
                 return true;
             }
-            UIEvent::Input(Key::Char('N')) if self.show_shortcuts && self.help_search.is_some() => {
+            UIEvent::Input(ref key)
+                if self.show_shortcuts
+                    && self.help_search.is_some()
+                    && *key == context.settings.shortcuts.pager.search_prev =>
+            {
                 if let Some(ref mut search) = self.help_search {
                     search.movement = Some(PageMovement::Up(1));
                     search.cursor = search.cursor.saturating_sub(1);
@@ -1516,6 +1780,10 @@ impl Component for Tabbed {
         map
     }
 
+    fn status_coordinates(&self) -> Option<(AccountHash, Option<MailboxHash>)> {
+        self.children[self.cursor_pos].status_coordinates()
+    }
+
     fn can_quit_cleanly(&mut self, context: &Context) -> bool {
         for (i, c) in self.children.iter_mut().enumerate() {
             if !c.can_quit_cleanly(context) {
@@ -1526,6 +1794,16 @@ impl Component for Tabbed {
         }
         true
     }
+
+    fn save_session(&self, session: &mut crate::session::Session, context: &Context) {
+        for (i, c) in self.children.iter().enumerate() {
+            let saved = session.listings.len();
+            c.save_session(session, context);
+            if i == self.cursor_pos && session.listings.len() > saved {
+                session.active_listing = Some(saved);
+            }
+        }
+    }
 }
 
 #[derive(Debug, Clone, PartialEq)]
@@ -33,16 +33,20 @@ pub struct Pager {
     height: usize,
     width: usize,
     minimum_width: usize,
-    search: Option<SearchPattern>,
+    search: Option<PagerSearch>,
     dirty: bool,
 
     colors: ThemeAttribute,
     initialised: bool,
     show_scrollbar: bool,
+    /// Highlight lines of unified diffs
+    highlight_diff: bool,
     content: CellBuffer,
     text_lines: Vec<String>,
     line_breaker: LineBreakText,
     movement: Option<PageMovement>,
+    /// Area the pager was last drawn in, to scroll with the mouse wheel over it.
+    area: Area,
     id: ComponentId,
 }
 
@@ -77,6 +81,11 @@ impl Pager {
         self
     }
 
+    pub fn set_highlight_diff(&mut self, new_val: bool) -> &mut Self {
+        self.highlight_diff = new_val;
+        self
+    }
+
     pub fn set_reflow(&mut self, new_val: Reflow) -> &mut Self {
         self.reflow = new_val;
         self
@@ -231,19 +240,16 @@ impl Pager {
         };
         self.height = self.text_lines.len();
         self.width = width;
+        if self.search.is_some() {
+            /* Search the whole text, so that all matches are counted */
+            self.draw_lines_up_to(grid, area, context, 0);
+        }
         if let Some(ref mut search) = self.search {
-            use melib::text_processing::search::KMP;
-            search.positions.clear();
-            for (y, l) in self.text_lines.iter().enumerate() {
-                search.positions.extend(
-                    l.kmp_search(&search.pattern)
-                        .into_iter()
-                        .map(|offset| (y, offset)),
-                );
-            }
-            if let Some(pos) = search.positions.get(search.cursor) {
-                if self.cursor.1 > pos.0 || self.cursor.1 + height!(area) < pos.0 {
-                    self.cursor.1 = pos.0.saturating_sub(3);
+            search.matches.clear();
+            search.search_lines(&self.text_lines, 0);
+            if let Some(m) = search.matches.get(search.cursor) {
+                if self.cursor.1 > m.line || self.cursor.1 + height!(area) < m.line {
+                    self.cursor.1 = m.line.saturating_sub(3);
                 }
             }
         }
@@ -280,39 +286,63 @@ impl Pager {
         };
         let new_lines_no = self.text_lines.len() - old_lines_no;
         if let Some(ref mut search) = self.search {
-            use melib::text_processing::search::KMP;
-            for (y, l) in self.text_lines.iter().enumerate().skip(old_lines_no) {
-                search.positions.extend(
-                    l.kmp_search(&search.pattern)
-                        .into_iter()
-                        .map(|offset| (y, offset)),
-                );
-            }
+            search.search_lines(&self.text_lines, old_lines_no);
         }
         self.height += new_lines_no;
     }
 
     fn draw_page(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
         let (mut upper_left, bottom_right) = area;
-        for l in self
+        let diff_kinds = if self.highlight_diff {
+            use melib::text_processing::diff::{diff_line_kinds, DiffLine};
+            let up_to = std::cmp::min(self.text_lines.len(), self.cursor.1 + height!(area) + 1);
+            diff_line_kinds(&self.text_lines[..up_to])
+                .into_iter()
+                .map(|kind| {
+                    kind.map(|k| {
+                        crate::conf::value(
+                            context,
+                            match k {
+                                DiffLine::Header => "pager.diff.header",
+                                DiffLine::Hunk => "pager.diff.hunk",
+                                DiffLine::Added => "pager.diff.added",
+                                DiffLine::Removed => "pager.diff.removed",
+                                DiffLine::Context => "pager.diff.context",
+                            },
+                        )
+                    })
+                })
+                .collect::<Vec<Option<ThemeAttribute>>>()
+        } else {
+            vec![]
+        };
+        let quote_attrs = [
+            crate::conf::value(context, "pager.quote.1"),
+            crate::conf::value(context, "pager.quote.2"),
+            crate::conf::value(context, "pager.quote.3"),
+            crate::conf::value(context, "pager.quote.4"),
+        ];
+        let wrap_indicator_attr = crate::conf::value(context, "pager.wrap_indicator");
+        for (i, l) in self
             .text_lines
             .iter()
+            .enumerate()
             .skip(self.cursor.1)
             .take(height!(area) + 1)
         {
-            write_string_to_grid(
-                l,
-                grid,
-                self.colors.fg,
-                self.colors.bg,
-                Attr::DEFAULT,
-                (upper_left, bottom_right),
-                None,
-            );
+            let (fg, bg, attrs) = match (diff_kinds.get(i), quote_depth(l)) {
+                (Some(Some(attr)), _) => (attr.fg, attr.bg, attr.attrs),
+                (_, 0) => (self.colors.fg, self.colors.bg, Attr::DEFAULT),
+                (_, depth) => {
+                    let attr = quote_attrs[std::cmp::min(depth, quote_attrs.len()) - 1];
+                    (attr.fg, attr.bg, attr.attrs)
+                }
+            };
+            write_string_to_grid(l, grid, fg, bg, attrs, (upper_left, bottom_right), None);
             if l.starts_with("⤷") {
                 grid[upper_left]
-                    .set_fg(Color::Byte(240))
-                    .set_attrs(Attr::BOLD);
+                    .set_fg(wrap_indicator_attr.fg)
+                    .set_attrs(wrap_indicator_attr.attrs);
             }
             upper_left = pos_inc(upper_left, (0, 1));
         }
@@ -325,6 +355,10 @@ impl Pager {
             );
         }
 
+        if context.settings.terminal.use_hyperlinks.is_true() {
+            set_hyperlinks(grid, area, &[linkify::LinkKind::Url]);
+        }
+
         let (upper_left, _bottom_right) = area;
         #[cfg(feature = "regexp")]
         {
@@ -351,30 +385,36 @@ impl Pager {
             let results_attr = crate::conf::value(context, "pager.highlight_search");
             let results_current_attr =
                 crate::conf::value(context, "pager.highlight_search_current");
-            search.cursor = std::cmp::min(search.positions.len().saturating_sub(1), search.cursor);
-            for (i, (y, x)) in search
-                .positions
+            search.cursor = std::cmp::min(search.matches.len().saturating_sub(1), search.cursor);
+            let rows = height!(area);
+            for (i, m) in search
+                .matches
                 .iter()
                 .enumerate()
-                .filter(|(_, (y, _))| *y >= cursor_line)
-                .take(height!(area) + 1)
+                .filter(|(_, m)| m.line + 1 >= cursor_line && m.line < cursor_line + rows)
             {
-                let x = *x + get_x(upper_left);
-                let y = *y - cursor_line;
-                for c in grid.row_iter(
-                    x..x + search.pattern.grapheme_width(),
-                    y + get_y(upper_left),
-                ) {
-                    if i == search.cursor {
-                        grid[c]
-                            .set_fg(results_current_attr.fg)
-                            .set_bg(results_current_attr.bg)
-                            .set_attrs(results_current_attr.attrs);
-                    } else {
+                let attr = if i == search.cursor {
+                    results_current_attr
+                } else {
+                    results_attr
+                };
+                /* A match continuing on the next line is highlighted on both */
+                for &(line, col, width) in [
+                    (m.line, m.col, m.width),
+                    (m.line + 1, m.next_col, m.next_width),
+                ]
+                .iter()
+                {
+                    if width == 0 || line < cursor_line || line >= cursor_line + rows {
+                        continue;
+                    }
+                    let x = col + get_x(upper_left);
+                    let y = line - cursor_line + get_y(upper_left);
+                    for c in grid.row_iter(x..x + width, y) {
                         grid[c]
-                            .set_fg(results_attr.fg)
-                            .set_bg(results_attr.bg)
-                            .set_attrs(results_attr.attrs);
+                            .set_fg(attr.fg)
+                            .set_bg(attr.bg)
+                            .set_attrs(attr.attrs);
                     }
                 }
             }
@@ -382,11 +422,20 @@ impl Pager {
     }
 }
 
+/// Number of `>` quotation markers a line starts with, ignoring whitespace between them.
+fn quote_depth(line: &str) -> usize {
+    line.chars()
+        .take_while(|c| *c == '>' || *c == ' ')
+        .filter(|c| *c == '>')
+        .count()
+}
+
 impl Component for Pager {
     fn draw(&mut self, grid: &mut CellBuffer, area: Area, context: &mut Context) {
         if !is_valid_area!(area) {
             return;
         }
+        self.area = area;
         if !self.is_dirty() {
             return;
         }
@@ -451,6 +500,7 @@ impl Component for Pager {
                     self.draw_lines_up_to(grid, area, context, 0);
                     self.cursor.1 = self.height.saturating_sub(1);
                 }
+                PageMovement::Row(_) => {}
             }
         }
 
@@ -458,17 +508,18 @@ impl Component for Pager {
             return;
         }
         if let Some(ref mut search) = self.search {
-            if !search.positions.is_empty() {
+            if !search.matches.is_empty() {
                 if let Some(mvm) = search.movement.take() {
+                    let line = search.matches[search.cursor].line;
                     match mvm {
                         PageMovement::Up(_) => {
-                            if self.cursor.1 > search.positions[search.cursor].0 {
-                                self.cursor.1 = search.positions[search.cursor].0;
+                            if self.cursor.1 > line {
+                                self.cursor.1 = line;
                             }
                         }
                         PageMovement::Down(_) => {
-                            if self.cursor.1 + height < search.positions[search.cursor].0 {
-                                self.cursor.1 = search.positions[search.cursor].0;
+                            if self.cursor.1 + height < line {
+                                self.cursor.1 = line;
                             }
                         }
                         _ => {}
@@ -560,12 +611,12 @@ impl Component for Pager {
                     "{results_str}{search_pattern}: {current_pos}/{total_results}{has_more_lines}",
                     results_str = RESULTS_STR,
                     search_pattern = &search.pattern,
-                    current_pos = if search.positions.is_empty() {
+                    current_pos = if search.matches.is_empty() {
                         0
                     } else {
                         search.cursor + 1
                     },
-                    total_results = search.positions.len(),
+                    total_results = search.matches.len(),
                     has_more_lines = if self.line_breaker.is_finished() {
                         ""
                     } else {
@@ -619,6 +670,20 @@ impl Component for Pager {
                 self.dirty = true;
                 return true;
             }
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(MouseButton::WheelUp, x, y)))
+                if mouse_position(*x, *y, self.area).is_some() =>
+            {
+                self.movement = Some(PageMovement::Up(MOUSE_SCROLL_LINES));
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(Key::Mouse(MouseEvent::Press(MouseButton::WheelDown, x, y)))
+                if mouse_position(*x, *y, self.area).is_some() =>
+            {
+                self.movement = Some(PageMovement::Down(MOUSE_SCROLL_LINES));
+                self.dirty = true;
+                return true;
+            }
             UIEvent::Input(Key::Home) => {
                 self.movement = Some(PageMovement::Home);
                 self.dirty = true;
@@ -692,17 +757,26 @@ impl Component for Pager {
                 return true;
             }
             UIEvent::Action(Action::Listing(ListingAction::Search(pattern))) => {
-                self.search = Some(SearchPattern {
-                    pattern: pattern.to_string(),
-                    positions: vec![],
-                    cursor: 0,
-                    movement: Some(PageMovement::Home),
-                });
+                self.search = Some(PagerSearch::new(pattern));
                 self.initialised = false;
                 self.dirty = true;
                 return true;
             }
-            UIEvent::Input(Key::Char('n')) if self.search.is_some() => {
+            UIEvent::Action(Action::Listing(ListingAction::SearchPreview(pattern))) => {
+                /* Highlight the matches while the search command is typed */
+                self.search = if pattern.is_empty() {
+                    None
+                } else {
+                    Some(PagerSearch::new(pattern))
+                };
+                self.initialised = false;
+                self.dirty = true;
+                return true;
+            }
+            UIEvent::Input(ref key)
+                if self.search.is_some()
+                    && shortcut!(key == shortcuts[Self::DESCRIPTION]["search_next"]) =>
+            {
                 if let Some(ref mut search) = self.search {
                     search.movement = Some(PageMovement::Down(1));
                     search.cursor += 1;
@@ -715,7 +789,10 @@ impl Component for Pager {
                 self.dirty = true;
                 return true;
             }
-            UIEvent::Input(Key::Char('N')) if self.search.is_some() => {
+            UIEvent::Input(ref key)
+                if self.search.is_some()
+                    && shortcut!(key == shortcuts[Self::DESCRIPTION]["search_prev"]) =>
+            {
                 if let Some(ref mut search) = self.search {
                     search.movement = Some(PageMovement::Up(1));
                     search.cursor = search.cursor.saturating_sub(1);
@@ -773,3 +850,196 @@ impl Component for Pager {
         self.id = id;
     }
 }
+
+/// A search in the text of a `Pager`. The pattern is a regular expression if the `regexp`
+/// feature is enabled and it is valid, otherwise a literal string. Matching ignores case unless
+/// the pattern has an uppercase letter.
+#[derive(Debug, Clone)]
+struct PagerSearch {
+    pattern: String,
+    caseless: bool,
+    #[cfg(feature = "regexp")]
+    regex: Option<pcre2::bytes::Regex>,
+    matches: Vec<SearchMatch>,
+    cursor: usize,
+    movement: Option<PageMovement>,
+}
+
+/// A match in the lines of a `Pager`, in columns. Matches that continue on the next line, because
+/// the line was wrapped, have a non-zero `next_width`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+struct SearchMatch {
+    line: usize,
+    col: usize,
+    width: usize,
+    next_col: usize,
+    next_width: usize,
+}
+
+impl PagerSearch {
+    fn new(pattern: &str) -> Self {
+        let caseless = !pattern.chars().any(char::is_uppercase);
+        PagerSearch {
+            pattern: pattern.to_string(),
+            caseless,
+            #[cfg(feature = "regexp")]
+            regex: pcre2::bytes::RegexBuilder::new()
+                .caseless(caseless)
+                .utf(true)
+                .ucp(true)
+                .build(pattern)
+                .ok(),
+            matches: vec![],
+            cursor: 0,
+            movement: Some(PageMovement::Home),
+        }
+    }
+
+    /// Byte ranges of the non-empty matches in `text`.
+    fn find_iter(&self, text: &str) -> Vec<(usize, usize)> {
+        #[cfg(feature = "regexp")]
+        {
+            if let Some(ref regex) = self.regex {
+                return regex
+                    .find_iter(text.as_bytes())
+                    .filter_map(|m| m.ok())
+                    .filter(|m| m.start() < m.end())
+                    .map(|m| (m.start(), m.end()))
+                    .collect();
+            }
+        }
+        let mut ret = vec![];
+        if self.pattern.is_empty() {
+            return ret;
+        }
+        let mut start = 0;
+        while start < text.len() {
+            let mut end = start;
+            let mut pattern = self.pattern.chars();
+            let mut chars = text[start..].chars();
+            let found = loop {
+                match (pattern.next(), chars.next()) {
+                    (None, _) => break true,
+                    (Some(_), None) => break false,
+                    (Some(p), Some(c)) if p == c => end += c.len_utf8(),
+                    (Some(p), Some(c))
+                        if self.caseless && p.to_lowercase().eq(c.to_lowercase()) =>
+                    {
+                        end += c.len_utf8()
+                    }
+                    _ => break false,
+                }
+            };
+            if found {
+                ret.push((start, end));
+                start = end;
+            } else {
+                start += text[start..]
+                    .chars()
+                    .next()
+                    .map(char::len_utf8)
+                    .unwrap_or(1);
+            }
+        }
+        ret
+    }
+
+    /// Adds the matches of `lines` from line `from` onwards, and the ones of the line before it
+    /// that continue on line `from`.
+    fn search_lines(&mut self, lines: &[String], from: usize) {
+        if from > 0 && from < lines.len() {
+            self.search_line(lines, from - 1, true);
+        }
+        for y in from..lines.len() {
+            self.search_line(lines, y, false);
+        }
+    }
+
+    fn search_line(&mut self, lines: &[String], y: usize, only_continued: bool) {
+        let line = lines[y].as_str();
+        if !only_continued {
+            for (start, end) in self.find_iter(line) {
+                self.matches.push(SearchMatch {
+                    line: y,
+                    col: line[..start].grapheme_width(),
+                    width: line[start..end].grapheme_width(),
+                    next_col: 0,
+                    next_width: 0,
+                });
+            }
+        }
+        let next = match lines.get(y + 1) {
+            Some(next) => next.as_str(),
+            None => return,
+        };
+        /* Search the line joined with the next one, without its quote prefix if both are quoted */
+        let prefix_len = if line.trim_start().starts_with('>') {
+            next.len() - next.trim_start_matches(|c| c == '>' || c == ' ').len()
+        } else {
+            0
+        };
+        let joined = format!("{} {}", line, &next[prefix_len..]);
+        for (start, end) in self.find_iter(&joined) {
+            if start < line.len() && end > line.len() + 1 {
+                let next_end = prefix_len + end - line.len() - 1;
+                self.matches.push(SearchMatch {
+                    line: y,
+                    col: line[..start].grapheme_width(),
+                    width: line[start..].grapheme_width(),
+                    next_col: next[..prefix_len].grapheme_width(),
+                    next_width: next[prefix_len..next_end].grapheme_width(),
+                });
+            }
+        }
+    }
+}
+
+#[test]
+fn test_pager_search() {
+    let lines = [
+        "Meeting at the Town".to_string(),
+        "> Hall? the hall is booked".to_string(),
+        "> on monday".to_string(),
+    ];
+    let mut search = PagerSearch::new("hall");
+    search.search_lines(&lines, 0);
+    assert_eq!(
+        search
+            .matches
+            .iter()
+            .map(|m| (m.line, m.col, m.width))
+            .collect::<Vec<_>>(),
+        vec![(1, 2, 4), (1, 12, 4)]
+    );
+    /* Uppercase letters make the search case-sensitive */
+    let mut search = PagerSearch::new("Hall");
+    search.search_lines(&lines, 0);
+    assert_eq!(search.matches.len(), 1);
+    /* Matches can continue on the next line, after its quote prefix */
+    let mut search = PagerSearch::new("booked on");
+    search.search_lines(&lines[..2], 0);
+    search.search_lines(&lines, 2);
+    assert_eq!(
+        search.matches,
+        vec![SearchMatch {
+            line: 1,
+            col: 20,
+            width: 6,
+            next_col: 2,
+            next_width: 2,
+        }]
+    );
+    #[cfg(feature = "regexp")]
+    {
+        let mut search = PagerSearch::new(r"the h\w+|booked\s+on");
+        search.search_lines(&lines, 0);
+        assert_eq!(
+            search
+                .matches
+                .iter()
+                .map(|m| (m.line, m.col, m.width, m.next_width))
+                .collect::<Vec<_>>(),
+            vec![(1, 8, 8, 0), (1, 20, 6, 2)]
+        );
+    }
+}
@@ -33,6 +33,8 @@ use std::collections::HashSet;
 mod overrides;
 pub use overrides::*;
 pub mod composing;
+pub mod filters;
+pub mod hooks;
 pub mod notifications;
 pub mod pager;
 pub mod pgp;
@@ -41,18 +43,22 @@ pub mod tags;
 pub mod shortcuts;
 mod listing;
 pub mod terminal;
+pub mod test_config;
 mod themes;
 pub use themes::*;
 
 pub mod accounts;
 pub use self::accounts::Account;
 pub use self::composing::*;
+pub use self::filters::*;
 pub use self::pgp::*;
 pub use self::shortcuts::*;
 pub use self::tags::*;
 
 use self::default_vals::*;
+use self::hooks::HooksSettings;
 use self::listing::ListingSettings;
+pub use self::listing::{ListingSort, ViewLayout};
 use self::notifications::NotificationsSettings;
 use self::terminal::TerminalSettings;
 use crate::pager::PagerSettings;
@@ -130,8 +136,8 @@ pub struct MailUIConf {
     pub pgp: PGPSettingsOverride,
 }
 
-#[serde(default)]
 #[derive(Debug, Default, Clone, Serialize, Deserialize)]
+#[serde(default)]
 pub struct FileMailboxConf {
     #[serde(flatten)]
     pub conf_override: MailUIConf,
@@ -168,8 +174,30 @@ pub struct FileAccount {
     search_backend: SearchBackend,
     #[serde(default = "false_val")]
     pub manual_refresh: bool,
+    #[serde(default = "false_val")]
+    pub connect_on_startup: bool,
     #[serde(default = "none")]
     pub refresh_command: Option<String>,
+    /// Move deleted messages to the Trash mailbox instead of deleting them.
+    #[serde(default = "false_val")]
+    pub move_to_trash: bool,
+    /// Permanently delete messages that have been in the Trash mailbox for more than this many
+    /// days.
+    #[serde(default = "none")]
+    pub empty_trash_after_days: Option<u64>,
+    /// Archive messages into subfolders of the Archive mailbox named after their year.
+    #[serde(default = "false_val")]
+    pub archive_by_year: bool,
+    /// Path of the mailbox snoozed messages are moved to, created if needed. Default: `Snoozed`.
+    #[serde(default = "none")]
+    pub snooze_mailbox: Option<String>,
+    /// Virtual mailboxes listing the messages matching a search query, by name.
+    #[serde(default)]
+    pub saved_searches: IndexMap<String, String>,
+    #[serde(default = "none")]
+    pub ldap: Option<melib::addressbook::ldap::LdapSettings>,
+    #[serde(default)]
+    pub filters: Vec<FilterRule>,
     #[serde(flatten)]
     pub conf_override: MailUIConf,
     #[serde(flatten)]
@@ -212,6 +240,10 @@ pub struct FileSettings {
     pub terminal: TerminalSettings,
     #[serde(default)]
     pub log: LogSettings,
+    #[serde(default)]
+    pub hooks: HooksSettings,
+    #[serde(default)]
+    pub plugins: PluginsSettings,
 }
 
 #[derive(Debug, Clone, Default, Serialize)]
@@ -346,6 +378,7 @@ impl FileSettings {
                 path.display(),
                 e.to_string()
             ))
+            .set_kind(ErrorKind::Configuration)
         })?;
         let backends = melib::backends::Backends::new();
         let Themes {
@@ -374,11 +407,19 @@ impl FileSettings {
             "dark" | "light" => {}
             t if s.terminal.themes.other_themes.contains_key(t) => {}
             t => {
-                return Err(MeliError::new(format!("Theme `{}` was not found.", t)));
+                return Err(MeliError::new(format!("Theme `{}` was not found.", t))
+                    .set_kind(ErrorKind::Configuration));
             }
         }
 
-        s.terminal.themes.validate()?;
+        s.terminal
+            .themes
+            .validate()
+            .chain_err_kind(ErrorKind::Configuration)?;
+        s.shortcuts.validate().map_err(|err| {
+            MeliError::new(format!("{}:\n{}", path.display(), err))
+                .set_kind(ErrorKind::Configuration)
+        })?;
         for (name, acc) in &s.accounts {
             let FileAccount {
                 root_mailbox,
@@ -390,8 +431,16 @@ impl FileSettings {
                 mailboxes,
                 extra,
                 manual_refresh,
+                connect_on_startup: _,
                 refresh_command: _,
+                move_to_trash: _,
+                empty_trash_after_days: _,
+                archive_by_year: _,
+                snooze_mailbox: _,
+                saved_searches: _,
                 search_backend: _,
+                ldap: _,
+                filters: _,
                 conf_override: _,
             } = acc.clone();
 
@@ -412,6 +461,7 @@ impl FileSettings {
                 extra: extra.into_iter().collect(),
             };
             backends.validate_config(&lowercase_format, &s)?;
+            melib::connections::ConnectionPolicy::new(&s)?;
         }
 
         Ok(s)
@@ -430,6 +480,8 @@ pub struct Settings {
     pub pgp: PGPSettings,
     pub terminal: TerminalSettings,
     pub log: LogSettings,
+    pub hooks: HooksSettings,
+    pub plugins: PluginsSettings,
 }
 
 impl Settings {
@@ -444,12 +496,7 @@ impl Settings {
             s.insert(id, ac);
         }
 
-        if let Some(ref log_path) = fs.log.log_file {
-            melib::change_log_dest(log_path.into());
-        }
-        if fs.log.maximum_level != melib::LoggingLevel::default() {
-            melib::change_log_level(fs.log.maximum_level);
-        }
+        fs.log.apply();
 
         Ok(Settings {
             accounts: s,
@@ -462,17 +509,14 @@ impl Settings {
             pgp: fs.pgp,
             terminal: fs.terminal,
             log: fs.log,
+            hooks: fs.hooks,
+            plugins: fs.plugins,
         })
     }
 
     pub fn without_accounts() -> Result<Settings> {
         let fs = FileSettings::new()?;
-        if let Some(ref log_path) = fs.log.log_file {
-            melib::change_log_dest(log_path.into());
-        }
-        if fs.log.maximum_level != melib::LoggingLevel::default() {
-            melib::change_log_level(fs.log.maximum_level);
-        }
+        fs.log.apply();
 
         Ok(Settings {
             accounts: IndexMap::new(),
@@ -485,8 +529,32 @@ impl Settings {
             pgp: fs.pgp,
             terminal: fs.terminal,
             log: fs.log,
+            hooks: fs.hooks,
+            plugins: fs.plugins,
         })
     }
+
+    /// Names of the top level sections (`pager`, `shortcuts`, ...) whose values differ between
+    /// `self` and `other`.
+    pub fn changed_sections(&self, other: &Settings) -> Vec<String> {
+        let (self_value, other_value) =
+            match (toml::Value::try_from(self), toml::Value::try_from(other)) {
+                (Ok(toml::Value::Table(s)), Ok(toml::Value::Table(o))) => (s, o),
+                _ => return vec![],
+            };
+        let mut ret = self_value
+            .iter()
+            .filter(|(k, v)| other_value.get(k.as_str()) != Some(v))
+            .map(|(k, _)| k.to_string())
+            .collect::<Vec<String>>();
+        ret.extend(
+            other_value
+                .keys()
+                .filter(|k| !self_value.contains_key(k.as_str()))
+                .cloned(),
+        );
+        ret
+    }
 }
 
 #[derive(Copy, Debug, Clone, Hash, PartialEq)]
@@ -682,7 +750,8 @@ pub fn create_config_file(p: &Path) -> Result<()> {
 }
 
 mod pp {
-    //! Preprocess configuration files by unfolding `include` macros.
+    //! Preprocess configuration files by unfolding `include` macros and directives and
+    //! expanding `${ENV_VAR}` references.
     use melib::{
         error::{MeliError, Result},
         parsec::*,
@@ -748,7 +817,148 @@ mod pp {
         }
     }
 
-    /// Expands `include` macros in path.
+    /// Try to parse line into a list of glob patterns of an `include = [..]` directive.
+    fn include_list(line: &str) -> Option<std::result::Result<Vec<String>, String>> {
+        let rest = line.trim_start().strip_prefix("include")?;
+        if !rest.trim_start().starts_with('=') {
+            return None;
+        }
+        Some(
+            toml::from_str::<toml::Value>(line)
+                .map_err(|err| err.to_string())
+                .and_then(|v| {
+                    v.get("include")
+                        .and_then(|v| v.as_array())
+                        .and_then(|a| {
+                            a.iter()
+                                .map(|s| s.as_str().map(str::to_string))
+                                .collect::<Option<Vec<String>>>()
+                        })
+                        .ok_or_else(|| "expected an array of strings".to_string())
+                }),
+        )
+    }
+
+    /// Matches `name` against a shell wildcard `pattern` with `*` and `?`.
+    fn wildcard_match(pattern: &[u8], name: &[u8]) -> bool {
+        match (pattern.first(), name.first()) {
+            (None, None) => true,
+            (Some(b'*'), _) => {
+                wildcard_match(&pattern[1..], name)
+                    || (!name.is_empty() && wildcard_match(pattern, &name[1..]))
+            }
+            (Some(b'?'), Some(_)) => wildcard_match(&pattern[1..], &name[1..]),
+            (Some(p), Some(n)) if p == n => wildcard_match(&pattern[1..], &name[1..]),
+            _ => false,
+        }
+    }
+
+    /// Returns the files matching `pattern`, sorted by name. Wildcards are only allowed in the
+    /// file name component.
+    fn glob(pattern: &Path) -> Result<Vec<PathBuf>> {
+        let file_pattern = match pattern.file_name().and_then(|f| f.to_str()) {
+            Some(f) if f.contains(&['*', '?'][..]) => f,
+            _ => return Ok(vec![pattern.to_path_buf()]),
+        };
+        let dir = pattern.parent().unwrap_or_else(|| Path::new("."));
+        if !dir.exists() {
+            return Ok(vec![]);
+        }
+        let mut ret = vec![];
+        for entry in std::fs::read_dir(dir)? {
+            let p = entry?.path();
+            if p.is_file()
+                && p.file_name()
+                    .and_then(|f| f.to_str())
+                    .map(|f| {
+                        !f.starts_with('.') && wildcard_match(file_pattern.as_bytes(), f.as_bytes())
+                    })
+                    .unwrap_or(false)
+            {
+                ret.push(p);
+            }
+        }
+        ret.sort();
+        Ok(ret)
+    }
+
+    /// Replaces `${ENV_VAR}` references inside the single-line strings of `line` with the value of
+    /// the environment variable, escaped for the kind of string it is inserted in. `$${` stands
+    /// for a literal `${`, and references that aren't a plain variable name, such as the shell's
+    /// `${VAR:-default}`, are left as they are.
+    fn expand_env(line: &str) -> std::result::Result<String, String> {
+        let mut ret = String::with_capacity(line.len());
+        /* The quote of the string we are in, if any. */
+        let mut quote: Option<char> = None;
+        let mut rest = line;
+        while let Some(c) = rest.chars().next() {
+            match (quote, c) {
+                (None, '#') => break,
+                (None, '"') | (None, '\'') => quote = Some(c),
+                (Some(q), _) if q == c => quote = None,
+                (Some('"'), '\\') if rest.len() > 1 => {
+                    /* Keep escape sequences like `\"` as they are. */
+                    let escaped = rest[1..].chars().next().unwrap();
+                    ret.push(c);
+                    ret.push(escaped);
+                    rest = &rest[1 + escaped.len_utf8()..];
+                    continue;
+                }
+                (Some(_), '$') if rest.starts_with("$${") => {
+                    ret.push_str("${");
+                    rest = &rest["$${".len()..];
+                    continue;
+                }
+                (Some(q), '$') if rest.starts_with("${") => {
+                    let name = rest["${".len()..]
+                        .find('}')
+                        .map(|end| &rest["${".len().."${".len() + end])
+                        .filter(|name| {
+                            name.chars().enumerate().all(|(i, c)| {
+                                c == '_' || c.is_ascii_alphabetic() || (i > 0 && c.is_ascii_digit())
+                            }) && !name.is_empty()
+                        });
+                    if let Some(name) = name {
+                        let value = std::env::var(name)
+                            .map_err(|_| format!("environment variable {} is not set", name))?;
+                        if q == '"' {
+                            for c in value.chars() {
+                                match c {
+                                    '"' => ret.push_str("\\\""),
+                                    '\\' => ret.push_str("\\\\"),
+                                    '\n' => ret.push_str("\\n"),
+                                    '\t' => ret.push_str("\\t"),
+                                    '\r' => ret.push_str("\\r"),
+                                    c if c.is_control() => {
+                                        ret.push_str(&format!("\\u{:04X}", c as u32))
+                                    }
+                                    c => ret.push(c),
+                                }
+                            }
+                        } else if value
+                            .contains(|c: char| c == '\'' || (c.is_control() && c != '\t'))
+                        {
+                            return Err(format!(
+                                "environment variable {} contains characters that can't be in a literal string",
+                                name
+                            ));
+                        } else {
+                            ret.push_str(&value);
+                        }
+                        rest = &rest["${".len() + name.len() + "}".len()..];
+                        continue;
+                    }
+                }
+                _ => {}
+            }
+            ret.push(c);
+            rest = &rest[c.len_utf8()..];
+        }
+        ret.push_str(rest);
+        Ok(ret)
+    }
+
+    /// Expands `include` macros and directives and environment variables in path.
     fn pp_helper(path: &Path, level: u8) -> Result<String> {
         if level > 7 {
             return Err(MeliError::new(format!("Maximum recursion limit reached while unfolding include directives in {}. Have you included a config file within itself?", path.display())));
@@ -775,9 +985,37 @@ mod pp {
                 }
 
                 ret.push_str(&pp_helper(&p, level + 1)?);
-            } else {
+            } else if let Some(patterns) = include_list(l) {
+                let patterns = patterns.map_err(|err| {
+                    MeliError::new(format!(
+                        "Malformed include directive in line {} of file {}: {}",
+                        i,
+                        path.display(),
+                        err
+                    ))
+                })?;
+                for pattern in patterns {
+                    let mut p = Path::new(&pattern).expand();
+                    if p.is_relative() {
+                        p = path.parent().unwrap().join(p)
+                    }
+                    for p in glob(&p)? {
+                        ret.push_str(&pp_helper(&p, level + 1)?);
+                    }
+                }
+            } else if l.trim_start().starts_with('#') {
                 ret.push_str(l);
                 ret.push('\n');
+            } else {
+                ret.push_str(&expand_env(l).map_err(|err| {
+                    MeliError::new(format!(
+                        "Could not expand line {} of file {}: {}",
+                        i,
+                        path.display(),
+                        err
+                    ))
+                })?);
+                ret.push('\n');
             }
         }
 
@@ -805,15 +1043,127 @@ mod pp {
         }
         Ok(ret)
     }
+
+    #[test]
+    fn test_pp_include_env() {
+        let dir = std::env::temp_dir().join(format!("meli-pp-{}", std::process::id()));
+        std::fs::create_dir_all(dir.join("accounts.d")).unwrap();
+        std::fs::write(
+            dir.join("config.toml"),
+            "include = [\"accounts.d/*.toml\"]\n# ${NOT_EXPANDED}\n",
+        )
+        .unwrap();
+        std::fs::write(dir.join("accounts.d/b.toml"), "b = \"${MELI_PP_TEST}\"\n").unwrap();
+        std::fs::write(dir.join("accounts.d/a.toml"), "a = 1\n").unwrap();
+        std::fs::write(dir.join("accounts.d/a.txt"), "ignored\n").unwrap();
+        std::env::set_var("MELI_PP_TEST", "secret");
+        assert_eq!(
+            pp_helper(&dir.join("config.toml"), 0).unwrap(),
+            "a = 1\nb = \"secret\"\n# ${NOT_EXPANDED}\n"
+        );
+        std::env::remove_var("MELI_PP_TEST");
+        assert!(pp_helper(&dir.join("config.toml"), 0).is_err());
+        std::fs::remove_dir_all(&dir).unwrap();
+    }
+
+    #[test]
+    fn test_expand_env() {
+        std::env::set_var("MELI_EXPAND_TEST", "a \"b\" \\ 'c'");
+        assert_eq!(
+            expand_env(r#"x = "${MELI_EXPAND_TEST}" # ${MELI_EXPAND_TEST}"#).unwrap(),
+            r#"x = "a \"b\" \\ 'c'" # ${MELI_EXPAND_TEST}"#
+        );
+        /* Shell parameter expansions, escaped references and references outside of strings are
+         * kept. */
+        assert_eq!(
+            expand_env(r#"send_mail = "msmtp --from=${FROM:-me} $${HOME}""#).unwrap(),
+            r#"send_mail = "msmtp --from=${FROM:-me} ${HOME}""#
+        );
+        assert_eq!(
+            expand_env("port = ${MELI_EXPAND_TEST}").unwrap(),
+            "port = ${MELI_EXPAND_TEST}"
+        );
+        assert_eq!(
+            expand_env(r#"x = "\"${MELI_EXPAND_UNSET:-}\"""#).unwrap(),
+            r#"x = "\"${MELI_EXPAND_UNSET:-}\"""#
+        );
+        assert!(expand_env("x = '${MELI_EXPAND_TEST}'").is_err());
+        assert!(expand_env(r#"x = "${MELI_EXPAND_UNSET}""#).is_err());
+        std::env::remove_var("MELI_EXPAND_TEST");
+    }
 }
 
-#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[derive(Debug, Clone, Serialize, Deserialize)]
 #[serde(deny_unknown_fields)]
 pub struct LogSettings {
+    /// Path of the log file.
+    /// Default: `$XDG_STATE_HOME/meli/meli.log`
     #[serde(default)]
     log_file: Option<PathBuf>,
+    /// Default: INFO
     #[serde(default)]
     maximum_level: melib::LoggingLevel,
+    /// Maximum level of messages of a module, overriding `maximum_level`. Modules are `ui`,
+    /// `melib`, `smtp` and the backends, such as `imap` or `jmap`.
+    /// Default: empty
+    #[serde(default)]
+    modules: IndexMap<String, melib::LoggingLevel>,
+    /// Rotate the log file once it grows larger than this many bytes. 0 disables rotation.
+    /// Default: 5242880 (5MiB)
+    #[serde(default = "default_log_max_size")]
+    max_size: u64,
+    /// How many rotated log files to keep.
+    /// Default: 3
+    #[serde(default = "default_log_rotate")]
+    rotate: usize,
+}
+
+fn default_log_max_size() -> u64 {
+    melib::LOG_MAX_SIZE
+}
+
+fn default_log_rotate() -> usize {
+    melib::LOG_ROTATE
+}
+
+impl Default for LogSettings {
+    fn default() -> Self {
+        LogSettings {
+            log_file: None,
+            maximum_level: melib::LoggingLevel::default(),
+            modules: IndexMap::default(),
+            max_size: default_log_max_size(),
+            rotate: default_log_rotate(),
+        }
+    }
+}
+
+impl LogSettings {
+    /// Configure the logger with these settings.
+    pub fn apply(&self) {
+        melib::change_log_dest(
+            self.log_file
+                .clone()
+                .unwrap_or_else(melib::default_log_path),
+        );
+        melib::change_log_level(self.maximum_level);
+        melib::change_module_log_levels(self.modules.clone());
+        melib::change_log_rotation(self.max_size, self.rotate);
+    }
+}
+
+/// Settings for the plugin socket.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct PluginsSettings {
+    /// Listen for plugin connections.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub enable: bool,
+    /// Path of the socket.
+    /// Default: `$XDG_RUNTIME_DIR/meli/plugins.sock`
+    #[serde(default = "none", alias = "socket-path")]
+    pub socket_path: Option<PathBuf>,
 }
 
 pub use dotaddressable::*;
@@ -838,10 +1188,12 @@ mod dotaddressable {
     impl DotAddressable for String {}
     impl DotAddressable for char {}
     impl DotAddressable for IndexStyle {}
+    impl DotAddressable for ViewLayout {}
     impl DotAddressable for u64 {}
     impl DotAddressable for crate::terminal::Color {}
     impl DotAddressable for crate::terminal::Attr {}
     impl DotAddressable for crate::terminal::Key {}
+    impl DotAddressable for crate::terminal::GraphicsProtocol {}
     impl DotAddressable for usize {}
     impl DotAddressable for Query {}
     impl DotAddressable for melib::LoggingLevel {}
@@ -869,6 +1221,29 @@ mod dotaddressable {
                     match *field {
                         "log_file" => self.log_file.lookup(field, tail),
                         "maximum_level" => self.maximum_level.lookup(field, tail),
+                        "modules" => self.modules.lookup(field, tail),
+                        "max_size" => self.max_size.lookup(field, tail),
+                        "rotate" => self.rotate.lookup(field, tail),
+
+                        other => Err(MeliError::new(format!(
+                            "{} has no field named {}",
+                            parent_field, other
+                        ))),
+                    }
+                }
+                None => Ok(toml::to_string(self).map_err(|err| err.to_string())?),
+            }
+        }
+    }
+
+    impl DotAddressable for PluginsSettings {
+        fn lookup(&self, parent_field: &str, path: &[&str]) -> Result<String> {
+            match path.first() {
+                Some(field) => {
+                    let tail = &path[1..];
+                    match *field {
+                        "enable" => self.enable.lookup(field, tail),
+                        "socket_path" => self.socket_path.lookup(field, tail),
 
                         other => Err(MeliError::new(format!(
                             "{} has no field named {}",
@@ -897,6 +1272,8 @@ mod dotaddressable {
                         "pgp" => Err(MeliError::new("unimplemented")),
                         "terminal" => self.terminal.lookup(field, tail),
                         "log" => self.log.lookup(field, tail),
+                        "hooks" => self.hooks.lookup(field, tail),
+                        "plugins" => self.plugins.lookup(field, tail),
 
                         other => Err(MeliError::new(format!(
                             "{} has no field named {}",
@@ -992,6 +1369,11 @@ mod dotaddressable {
                         "search_backend" => self.search_backend.lookup(field, tail),
                         "manual_refresh" => self.manual_refresh.lookup(field, tail),
                         "refresh_command" => self.refresh_command.lookup(field, tail),
+                        "move_to_trash" => self.move_to_trash.lookup(field, tail),
+                        "empty_trash_after_days" => self.empty_trash_after_days.lookup(field, tail),
+                        "archive_by_year" => self.archive_by_year.lookup(field, tail),
+                        "snooze_mailbox" => self.snooze_mailbox.lookup(field, tail),
+                        "saved_searches" => self.saved_searches.lookup(field, tail),
                         "conf_override" => self.conf_override.lookup(field, tail),
                         "extra" => self.extra.lookup(field, tail),
                         other => Err(MeliError::new(format!(
@@ -23,16 +23,21 @@
  * Account management from user configuration.
  */
 
-use super::{AccountConf, FileMailboxConf};
-use crate::jobs::{JobExecutor, JobId, JoinHandle};
+use super::{AccountConf, EncryptPreference, FileMailboxConf};
+use crate::follow_up::FollowUp;
+use crate::jobs::{JobExecutor, JobId, JobPriority, JoinHandle};
+use crate::saved_search::SavedSearch;
+use crate::snooze::SnoozedMessage;
 use indexmap::IndexMap;
+use melib::addressbook::collected::CollectedAddresses;
+use melib::addressbook::ldap::LdapAddressBook;
 use melib::backends::*;
 use melib::email::*;
 use melib::error::{MeliError, Result};
 use melib::text_processing::GlobMatch;
 use melib::thread::{SortField, SortOrder, Threads};
 use melib::AddressBook;
-use melib::Collection;
+use melib::{Collection, ThreadLink, UnixTimestamp};
 use smallvec::SmallVec;
 use std::collections::BTreeMap;
 use std::collections::{HashMap, HashSet};
@@ -148,6 +153,8 @@ pub struct Account {
     sent_mailbox: Option<MailboxHash>,
     pub(crate) collection: Collection,
     pub(crate) address_book: AddressBook,
+    pub(crate) ldap: Option<LdapAddressBook>,
+    pub(crate) collected_addresses: CollectedAddresses,
     pub(crate) settings: AccountConf,
     pub(crate) backend: Arc<RwLock<Box<dyn MailBackend>>>,
 
@@ -157,6 +164,73 @@ pub struct Account {
     sender: Sender<ThreadEvent>,
     event_queue: VecDeque<(MailboxHash, RefreshEvent)>,
     pub backend_capabilities: MailBackendCapabilities,
+    /// If the mailboxes of the account have been requested, see [`Account::connect`].
+    connected: bool,
+    /// Consecutive recoverable failures of the watch job, see [`Account::watch`].
+    watch_failures: u32,
+    /// Messages waiting for their destination mailbox to be created before being moved, as
+    /// `(destination path, source mailbox, envelopes, job name)`, see [`Account::archive`] and
+    /// [`Account::snooze`].
+    pending_moves: Vec<(String, MailboxHash, Vec<EnvelopeHash>, &'static str)>,
+    /// Messages moved to the snooze mailbox, see [`Account::snooze`].
+    pub(crate) snoozed: Vec<SnoozedMessage>,
+    /// Sent messages waiting for a reply, see [`Account::check_follow_ups`].
+    pub(crate) follow_ups: Vec<FollowUp>,
+    /// When each message of the Trash mailbox was moved there, or first seen there, keyed by
+    /// Message-ID, see [`Account::empty_trash`].
+    trashed_at: HashMap<String, UnixTimestamp>,
+    /// Last sort chosen with the `sort` command for each mailbox path, see
+    /// [`Account::set_mailbox_sort`].
+    mailbox_sorts: HashMap<String, (SortField, SortOrder)>,
+    /// Threads collapsed or expanded by the user in each mailbox path, keyed by the Message-ID
+    /// of their first message, see [`Account::set_thread_collapsed`].
+    collapsed_threads: HashMap<String, HashMap<String, bool>>,
+    /// Encryption preferences of recipients keyed by their lowercase e-mail address, see
+    /// [`Account::set_encrypt_preference`].
+    encrypt_preferences: HashMap<String, EncryptPreference>,
+    /// Fingerprints of the keys chosen for recipients with several public keys, keyed by their
+    /// lowercase e-mail address, see [`Account::set_encrypt_key_choice`].
+    encrypt_key_choices: HashMap<String, String>,
+    /// Virtual mailboxes of the `saved_searches` setting, see
+    /// [`Account::update_saved_searches`].
+    pub(crate) saved_searches: HashMap<MailboxHash, SavedSearch>,
+    /// Fetches of mailboxes the user has left, holding the rest of the fetch once its current
+    /// chunk has arrived, see [`Account::defer_fetch`].
+    deferred_fetches: HashMap<MailboxHash, Option<DeferredFetch>>,
+    /// If the machine has no network link, see [`Account::network_lost`].
+    network_down: bool,
+    /// Consecutive failed online status checks, see [`Account::spawn_online_check`].
+    online_failures: u32,
+}
+
+/// The rest of a fetch deferred with [`Account::defer_fetch`].
+struct DeferredFetch(Pin<Box<dyn Stream<Item = Result<Vec<Envelope>>> + Send + 'static>>);
+
+impl core::fmt::Debug for DeferredFetch {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        write!(f, "DeferredFetch")
+    }
+}
+
+/// If `entry` is fetched when the account is loaded instead of when it is first opened.
+fn autoloads(entry: &MailboxEntry, load_trash: bool) -> bool {
+    entry.conf.mailbox_conf.autoload
+        || (entry.ref_mailbox.special_usage() == SpecialUsageMailbox::Inbox
+            || entry.ref_mailbox.special_usage() == SpecialUsageMailbox::Sent)
+        || (load_trash && entry.ref_mailbox.special_usage() == SpecialUsageMailbox::Trash)
+}
+
+/// How many times a watch job that failed with a recoverable error is relaunched before the
+/// failure is reported.
+const WATCH_MAX_RETRIES: u32 = 8;
+
+/// Delay before relaunching a watch job after its `failures`th consecutive recoverable failure:
+/// 5 seconds, doubling every time up to 5 minutes.
+fn watch_backoff(failures: u32) -> std::time::Duration {
+    std::time::Duration::from_secs(std::cmp::min(
+        5u64.saturating_mul(1 << std::cmp::min(failures.saturating_sub(1), 16)),
+        5 * 60,
+    ))
 }
 
 pub enum JobRequest {
@@ -370,53 +444,60 @@ impl JobRequest {
 
 impl Drop for Account {
     fn drop(&mut self) {
-        if let Ok(data_dir) = xdg::BaseDirectories::with_profile("meli", &self.name) {
-            if let Ok(data) = data_dir.place_data_file("addressbook") {
-                /* place result in cache directory */
-                let f = match fs::File::create(data) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        return;
-                    }
-                };
-                let metadata = f.metadata().unwrap();
-                let mut permissions = metadata.permissions();
-
-                permissions.set_mode(0o600); // Read/write for owner only.
-                f.set_permissions(permissions).unwrap();
-                let writer = io::BufWriter::new(f);
-                if let Err(err) = serde_json::to_writer(writer, &self.address_book) {
-                    eprintln!("{}", err);
+        self.save_data_file("addressbook", &self.address_book, false);
+        self.save_data_file(
+            "collected_addresses",
+            &self.collected_addresses,
+            self.collected_addresses.is_empty(),
+        );
+        self.save_snoozed();
+        self.save_data_file("follow_ups", &self.follow_ups, self.follow_ups.is_empty());
+        self.save_data_file("trashed_at", &self.trashed_at, self.trashed_at.is_empty());
+        self.save_data_file("sorts", &self.mailbox_sorts, self.mailbox_sorts.is_empty());
+        self.save_data_file(
+            "collapsed_threads",
+            &self.collapsed_threads,
+            self.collapsed_threads.is_empty(),
+        );
+        self.save_data_file(
+            "encrypt_preferences",
+            &self.encrypt_preferences,
+            self.encrypt_preferences.is_empty(),
+        );
+        self.save_data_file(
+            "encrypt_key_choices",
+            &self.encrypt_key_choices,
+            self.encrypt_key_choices.is_empty(),
+        );
+        {
+            let thread_links = self.collection.thread_links.read().unwrap();
+            self.save_data_file("thread_links", &*thread_links, thread_links.is_empty());
+        }
+        /*
+        if let Ok(data) = data_dir.place_data_file("mailbox") {
+            /* place result in cache directory */
+            let f = match fs::File::create(data) {
+                Ok(f) => f,
+                Err(e) => {
+                    eprintln!("{}", e);
                     return;
-                };
+                }
             };
-            /*
-            if let Ok(data) = data_dir.place_data_file("mailbox") {
-                /* place result in cache directory */
-                let f = match fs::File::create(data) {
-                    Ok(f) => f,
-                    Err(e) => {
-                        eprintln!("{}", e);
-                        return;
-                    }
-                };
-                let metadata = f.metadata().unwrap();
-                let mut permissions = metadata.permissions();
-
-                permissions.set_mode(0o600); // Read/write for owner only.
-                f.set_permissions(permissions).unwrap();
-                let writer = io::BufWriter::new(f);
-                if let Err(err) = bincode::Options::serialize_into(
-                    bincode::config::DefaultOptions::new(),
-                    writer,
-                    &self.collection,
-                ) {
-                    eprintln!("{}", err);
-                };
+            let metadata = f.metadata().unwrap();
+            let mut permissions = metadata.permissions();
+
+            permissions.set_mode(0o600); // Read/write for owner only.
+            f.set_permissions(permissions).unwrap();
+            let writer = io::BufWriter::new(f);
+            if let Err(err) = bincode::Options::serialize_into(
+                bincode::config::DefaultOptions::new(),
+                writer,
+                &self.collection,
+            ) {
+                eprintln!("{}", err);
             };
-                */
-        }
+        };
+        */
     }
 }
 
@@ -454,9 +535,30 @@ impl Account {
             event_consumer,
         )?;
 
+        let mut saved_searches = HashMap::default();
+        for (search_name, query) in settings.conf.saved_searches.iter() {
+            let search = SavedSearch::new(search_name, query).map_err(|err| {
+                MeliError::new(format!(
+                    "Account `{}`: invalid query of saved search `{}`: {}",
+                    &name, search_name, err
+                ))
+            })?;
+            saved_searches.insert(search.hash(), search);
+        }
+
         let data_dir = xdg::BaseDirectories::with_profile("meli", &name).unwrap();
         let mut address_book = AddressBook::with_account(&settings.account());
 
+        let mut collected_addresses = CollectedAddresses::new();
+        if let Ok(data) = data_dir.place_data_file("collected_addresses") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<CollectedAddresses, _> = serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    collected_addresses = data_t;
+                }
+            }
+        };
         if let Ok(data) = data_dir.place_data_file("addressbook") {
             if data.exists() {
                 let reader = io::BufReader::new(fs::File::open(data).unwrap());
@@ -471,6 +573,101 @@ impl Account {
             }
         };
 
+        let mut snoozed = vec![];
+        if let Ok(data) = data_dir.place_data_file("snoozed") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<Vec<SnoozedMessage>, _> =
+                    serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    snoozed = data_t;
+                }
+            }
+        };
+
+        let mut follow_ups = vec![];
+        if let Ok(data) = data_dir.place_data_file("follow_ups") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<Vec<FollowUp>, _> = serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    follow_ups = data_t;
+                }
+            }
+        };
+
+        let mut trashed_at = HashMap::default();
+        if let Ok(data) = data_dir.place_data_file("trashed_at") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<HashMap<String, UnixTimestamp>, _> =
+                    serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    trashed_at = data_t;
+                }
+            }
+        };
+
+        let mut mailbox_sorts = HashMap::default();
+        if let Ok(data) = data_dir.place_data_file("sorts") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<HashMap<String, (SortField, SortOrder)>, _> =
+                    serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    mailbox_sorts = data_t;
+                }
+            }
+        };
+
+        let mut collapsed_threads = HashMap::default();
+        if let Ok(data) = data_dir.place_data_file("collapsed_threads") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<HashMap<String, HashMap<String, bool>>, _> =
+                    serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    collapsed_threads = data_t;
+                }
+            }
+        };
+
+        let mut encrypt_preferences = HashMap::default();
+        if let Ok(data) = data_dir.place_data_file("encrypt_preferences") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<HashMap<String, EncryptPreference>, _> =
+                    serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    encrypt_preferences = data_t;
+                }
+            }
+        };
+
+        let mut encrypt_key_choices = HashMap::default();
+        if let Ok(data) = data_dir.place_data_file("encrypt_key_choices") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<HashMap<String, String>, _> =
+                    serde_json::from_reader(reader);
+                if let Ok(data_t) = result {
+                    encrypt_key_choices = data_t;
+                }
+            }
+        };
+
+        let collection = backend.collection();
+        if let Ok(data) = data_dir.place_data_file("thread_links") {
+            if data.exists() {
+                let reader = io::BufReader::new(fs::File::open(data).unwrap());
+                let result: result::Result<HashMap<String, ThreadLink>, _> =
+                    serde_json::from_reader(reader);
+                if let Ok(thread_links) = result {
+                    *collection.thread_links.write().unwrap() = thread_links;
+                }
+            }
+        };
+
         if settings.conf.search_backend == crate::conf::SearchBackend::Auto {
             if backend.capabilities().supports_search {
                 settings.conf.search_backend = crate::conf::SearchBackend::None;
@@ -486,26 +683,32 @@ impl Account {
             }
         }
 
-        let mut active_jobs = HashMap::default();
-        let mut active_job_instants = BTreeMap::default();
-        if let Ok(mailboxes_job) = backend.mailboxes() {
-            if let Ok(online_job) = backend.is_online() {
-                let handle = if backend.capabilities().is_async {
-                    job_executor.spawn_specialized(online_job.then(|_| mailboxes_job))
-                } else {
-                    job_executor.spawn_blocking(online_job.then(|_| mailboxes_job))
-                };
-                let job_id = handle.job_id;
-                active_jobs.insert(job_id, JobRequest::Mailboxes { handle });
-                active_job_instants.insert(std::time::Instant::now(), job_id);
-                sender
-                    .send(ThreadEvent::UIEvent(UIEvent::StatusEvent(
-                        StatusEvent::NewJob(job_id),
-                    )))
-                    .unwrap();
+        #[cfg(feature = "sqlite3")]
+        if settings.conf.search_backend == crate::conf::SearchBackend::Sqlite3 {
+            /* A new or outdated index is recreated empty here and gets repopulated as each
+             * mailbox is fetched. */
+            match crate::sqlite3::check_index(&name) {
+                Ok(true) => {
+                    sender
+                        .send(ThreadEvent::UIEvent(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage(format!(
+                                "Rebuilding {} search index.",
+                                &name
+                            )),
+                        )))
+                        .unwrap();
+                }
+                Ok(false) => {}
+                Err(err) => {
+                    melib::log(
+                        format!("Could not check {} search index: {}", &name, err),
+                        melib::ERROR,
+                    );
+                }
             }
         }
-        Ok(Account {
+
+        let mut ret = Account {
             hash,
             name,
             is_online: if !backend.capabilities().is_remote {
@@ -517,17 +720,70 @@ impl Account {
             mailboxes_order: Default::default(),
             tree: Default::default(),
             address_book,
+            ldap: settings.conf.ldap.clone().map(LdapAddressBook::new),
+            collected_addresses,
             sent_mailbox: Default::default(),
-            collection: backend.collection(),
+            collection,
             settings,
             sender,
             job_executor,
-            active_jobs,
-            active_job_instants,
+            active_jobs: HashMap::default(),
+            active_job_instants: BTreeMap::default(),
             event_queue: VecDeque::with_capacity(8),
             backend_capabilities: backend.capabilities(),
             backend: Arc::new(RwLock::new(backend)),
-        })
+            connected: false,
+            watch_failures: 0,
+            pending_moves: vec![],
+            snoozed,
+            follow_ups,
+            trashed_at,
+            mailbox_sorts,
+            collapsed_threads,
+            encrypt_preferences,
+            encrypt_key_choices,
+            saved_searches,
+            deferred_fetches: HashMap::default(),
+            network_down: false,
+            online_failures: 0,
+        };
+        if !ret.backend_capabilities.is_remote || ret.settings.conf.connect_on_startup {
+            ret.connect();
+        }
+        Ok(ret)
+    }
+
+    /// Connect to the backend and fetch the list of mailboxes in a background job. Remote
+    /// accounts are connected the first time they are opened, unless `connect_on_startup` is
+    /// set.
+    pub fn connect(&mut self) {
+        if self.connected {
+            return;
+        }
+        self.connected = true;
+        let mailboxes_job = self.backend.read().unwrap().mailboxes();
+        let online_job = self.backend.read().unwrap().is_online();
+        if let (Ok(mailboxes_job), Ok(online_job)) = (mailboxes_job, online_job) {
+            let handle = if self.backend_capabilities.is_async {
+                self.job_executor
+                    .spawn_specialized(online_job.then(|_| mailboxes_job))
+            } else {
+                self.job_executor
+                    .spawn_blocking(online_job.then(|_| mailboxes_job))
+            };
+            self.insert_job(handle.job_id, JobRequest::Mailboxes { handle });
+        }
+    }
+
+    pub fn is_connected(&self) -> bool {
+        self.connected
+    }
+
+    /// If the list of mailboxes is being fetched.
+    pub fn is_loading(&self) -> bool {
+        self.active_jobs
+            .values()
+            .any(|j| matches!(j, JobRequest::Mailboxes { .. }) || j.is_online())
     }
 
     fn init(&mut self, mut ref_mailboxes: HashMap<MailboxHash, Mailbox>) -> Result<()> {
@@ -571,6 +827,8 @@ impl Account {
                     }
                     _ => {}
                 }
+                self.collection
+                    .set_subject_threading(f.hash(), conf.mailbox_conf.subject_threading());
                 mailbox_entries.insert(
                     f.hash(),
                     MailboxEntry {
@@ -645,6 +903,20 @@ impl Account {
             );
         }
 
+        for (h, search) in self.saved_searches.iter() {
+            ref_mailboxes.insert(*h, BackendMailbox::clone(search));
+            mailbox_entries.insert(
+                *h,
+                MailboxEntry {
+                    ref_mailbox: BackendMailbox::clone(search),
+                    name: search.path().to_string(),
+                    status: MailboxStatus::Available,
+                    conf: FileMailboxConf::default(),
+                },
+            );
+        }
+
+        let load_trash = self.settings.conf.empty_trash_after_days.is_some();
         let mut tree: Vec<MailboxNode> = Vec::new();
         for (h, f) in ref_mailboxes.iter() {
             if !f.is_subscribed() {
@@ -652,10 +924,7 @@ impl Account {
                 continue;
             }
             mailbox_entries.entry(*h).and_modify(|entry| {
-                if entry.conf.mailbox_conf.autoload
-                    || (entry.ref_mailbox.special_usage() == SpecialUsageMailbox::Inbox
-                        || entry.ref_mailbox.special_usage() == SpecialUsageMailbox::Sent)
-                {
+                if autoloads(entry, load_trash) {
                     let total = entry.ref_mailbox.count().ok().unwrap_or((0, 0)).1;
                     entry.status = MailboxStatus::Parsing(0, total);
                     if let Ok(mailbox_job) = self.backend.write().unwrap().fetch(*h) {
@@ -691,10 +960,13 @@ impl Account {
         self.mailbox_entries = mailbox_entries;
         self.tree = tree;
         self.sent_mailbox = sent_mailbox;
+        self.update_saved_searches();
         Ok(())
     }
 
     pub fn reload(&mut self, event: RefreshEvent, mailbox_hash: MailboxHash) -> Option<UIEvent> {
+        /* Events from the backend mean the watch job works again. */
+        self.watch_failures = 0;
         if !self.mailbox_entries[&mailbox_hash].status.is_available()
             && !self.mailbox_entries[&mailbox_hash].status.is_parsing()
         {
@@ -718,11 +990,13 @@ impl Account {
                     }
                     #[cfg(feature = "sqlite3")]
                     if self.settings.conf.search_backend == crate::conf::SearchBackend::Sqlite3 {
-                        match crate::sqlite3::remove(old_hash).map(|_| {
+                        let mailboxes = self.envelope_mailboxes(old_hash, mailbox_hash);
+                        match crate::sqlite3::remove(&self.name, old_hash).map(|_| {
                             crate::sqlite3::insert(
                                 (*envelope).clone(),
                                 self.backend.clone(),
                                 self.name.clone(),
+                                mailboxes,
                             )
                         }) {
                             Err(err) => {
@@ -774,11 +1048,13 @@ impl Account {
                         });
                     #[cfg(feature = "sqlite3")]
                     if self.settings.conf.search_backend == crate::conf::SearchBackend::Sqlite3 {
-                        match crate::sqlite3::remove(env_hash).map(|_| {
+                        let mailboxes = self.envelope_mailboxes(env_hash, mailbox_hash);
+                        match crate::sqlite3::remove(&self.name, env_hash).map(|_| {
                             crate::sqlite3::insert(
                                 self.collection.envelopes.read().unwrap()[&env_hash].clone(),
                                 self.backend.clone(),
                                 self.name.clone(),
+                                mailboxes,
                             )
                         }) {
                             Ok(job) => {
@@ -821,11 +1097,13 @@ impl Account {
                     }
                     #[cfg(feature = "sqlite3")]
                     if self.settings.conf.search_backend == crate::conf::SearchBackend::Sqlite3 {
-                        match crate::sqlite3::remove(old_hash).map(|_| {
+                        let mailboxes = self.envelope_mailboxes(old_hash, mailbox_hash);
+                        match crate::sqlite3::remove(&self.name, old_hash).map(|_| {
                             crate::sqlite3::insert(
                                 self.collection.envelopes.read().unwrap()[&new_hash].clone(),
                                 self.backend.clone(),
                                 self.name.clone(),
+                                mailboxes,
                             )
                         }) {
                             Err(err) => {
@@ -870,6 +1148,7 @@ impl Account {
                     {
                         return None;
                     }
+                    self.follow_ups.retain(|f| !f.is_replied_by(&envelope));
                     let (is_seen, is_draft) =
                         { (envelope.is_seen(), envelope.flags().contains(Flag::DRAFT)) };
                     let (subject, from) = {
@@ -884,6 +1163,7 @@ impl Account {
                             (*envelope).clone(),
                             self.backend.clone(),
                             self.name.clone(),
+                            vec![mailbox_hash],
                         ));
                         self.insert_job(
                             handle.job_id,
@@ -937,15 +1217,32 @@ impl Account {
                         return Some(UIEvent::MailboxUpdate((self.hash, mailbox_hash)));
                     }
 
+                    let (filtered, notify) = self.apply_filters(env_hash, mailbox_hash);
+                    if filtered && notify.is_empty() {
+                        return Some(UIEvent::MailboxUpdate((self.hash, mailbox_hash)));
+                    }
+
                     return Some(Notification(
-                        Some(format!("new e-mail from: {}", from)),
-                        format!(
-                            "{}\n{} {}",
-                            subject,
-                            self.name,
-                            self.mailbox_entries[&mailbox_hash].name()
-                        ),
-                        Some(crate::types::NotificationType::NewMail),
+                        Some(if notify.is_empty() {
+                            format!(
+                                "new e-mail in {} {}",
+                                self.name,
+                                self.mailbox_entries[&mailbox_hash].name()
+                            )
+                        } else {
+                            format!(
+                                "new e-mail in {} {} ({})",
+                                self.name,
+                                self.mailbox_entries[&mailbox_hash].name(),
+                                notify.join(", ")
+                            )
+                        }),
+                        format!("From: {}\nSubject: {}", from, subject),
+                        Some(crate::types::NotificationType::NewMail(
+                            self.hash,
+                            mailbox_hash,
+                            env_hash,
+                        )),
                     ));
                 }
                 RefreshEventKind::Remove(env_hash) => {
@@ -954,7 +1251,7 @@ impl Account {
                     }
                     #[cfg(feature = "sqlite3")]
                     if self.settings.conf.search_backend == crate::conf::SearchBackend::Sqlite3 {
-                        if let Err(err) = crate::sqlite3::remove(env_hash) {
+                        if let Err(err) = crate::sqlite3::remove(&self.name, env_hash) {
                             let envelopes = self.collection.envelopes.read().unwrap();
                             melib::log(
                                 format!(
@@ -1025,6 +1322,10 @@ impl Account {
         None
     }
     pub fn refresh(&mut self, mailbox_hash: MailboxHash) -> Result<()> {
+        if self.saved_searches.contains_key(&mailbox_hash) {
+            self.update_saved_searches();
+            return Ok(());
+        }
         if let Some(ref refresh_command) = self.settings.conf().refresh_command {
             let child = std::process::Command::new("sh")
                 .args(&["-c", refresh_command])
@@ -1063,6 +1364,11 @@ impl Account {
     }
 
     pub fn watch(&mut self) {
+        self.watch_after(None);
+    }
+
+    /// Launch the watch job if it isn't running, waiting for `delay` before it starts.
+    pub fn watch_after(&mut self, delay: Option<std::time::Duration>) {
         if self.settings.account().manual_refresh {
             return;
         }
@@ -1070,6 +1376,14 @@ impl Account {
         if !self.active_jobs.values().any(|j| j.is_watch()) {
             match self.backend.read().unwrap().watch() {
                 Ok(fut) => {
+                    let fut = if let Some(delay) = delay {
+                        Box::pin(async move {
+                            melib::connections::sleep(delay).await;
+                            fut.await
+                        })
+                    } else {
+                        fut
+                    };
                     let handle = if self.backend_capabilities.is_async {
                         self.job_executor.spawn_specialized(fut)
                     } else {
@@ -1089,6 +1403,48 @@ impl Account {
         }
     }
 
+    /// If the watch job is running or waiting to start.
+    pub fn is_watching(&self) -> bool {
+        self.active_jobs.values().any(|j| j.is_watch())
+    }
+
+    /// Cancel the watch job, until the next [`Account::watch`].
+    pub fn stop_watching(&mut self) {
+        self.active_jobs.retain(|_, j| !j.is_watch());
+    }
+
+    /// The highest `watch_urgency` of the subscribed mailboxes.
+    pub fn watch_urgency(&self) -> MailboxWatchUrgency {
+        self.mailbox_entries
+            .values()
+            .filter(|entry| entry.ref_mailbox.is_subscribed())
+            .map(|entry| entry.conf.mailbox_conf.watch_urgency)
+            .max()
+            .unwrap_or_default()
+    }
+
+    /// Subscribed mailboxes whose `watch_urgency` is `urgency`.
+    pub fn mailboxes_with_urgency(&self, urgency: MailboxWatchUrgency) -> Vec<MailboxHash> {
+        self.mailbox_entries
+            .iter()
+            .filter(|(_, entry)| {
+                entry.ref_mailbox.is_subscribed()
+                    && entry.conf.mailbox_conf.watch_urgency == urgency
+            })
+            .map(|(hash, _)| *hash)
+            .collect()
+    }
+
+    /// If a refresh of `mailbox_hash` is in progress.
+    pub fn is_refreshing(&self, mailbox_hash: MailboxHash) -> bool {
+        self.active_jobs.values().any(|j| match j {
+            JobRequest::Refresh {
+                mailbox_hash: h, ..
+            } => *h == mailbox_hash,
+            _ => false,
+        })
+    }
+
     pub fn len(&self) -> usize {
         self.tree.len()
     }
@@ -1125,6 +1481,9 @@ impl Account {
         if mailbox_hash == 0 {
             return Err(0);
         }
+        if let Some(DeferredFetch(rest)) = self.deferred_fetches.remove(&mailbox_hash).flatten() {
+            self.spawn_fetch(mailbox_hash, rest, JobPriority::Interactive);
+        }
         match self.mailbox_entries[&mailbox_hash].status {
             MailboxStatus::Available | MailboxStatus::Parsing(_, _)
                 if self
@@ -1141,19 +1500,7 @@ impl Account {
                     let mailbox_job = self.backend.write().unwrap().fetch(mailbox_hash);
                     match mailbox_job {
                         Ok(mailbox_job) => {
-                            let mailbox_job = mailbox_job.into_future();
-                            let handle = if self.backend_capabilities.is_async {
-                                self.job_executor.spawn_specialized(mailbox_job)
-                            } else {
-                                self.job_executor.spawn_blocking(mailbox_job)
-                            };
-                            self.insert_job(
-                                handle.job_id,
-                                JobRequest::Fetch {
-                                    mailbox_hash,
-                                    handle,
-                                },
-                            );
+                            self.spawn_fetch(mailbox_hash, mailbox_job, JobPriority::Interactive);
                         }
                         Err(err) => {
                             self.mailbox_entries
@@ -1174,6 +1521,44 @@ impl Account {
         }
     }
 
+    /// Fetch the next chunk of envelopes of `mailbox_hash` from `stream`.
+    fn spawn_fetch(
+        &mut self,
+        mailbox_hash: MailboxHash,
+        stream: Pin<Box<dyn Stream<Item = Result<Vec<Envelope>>> + Send + 'static>>,
+        priority: JobPriority,
+    ) {
+        let handle = if self.backend_capabilities.is_async {
+            self.job_executor
+                .spawn_prioritized(stream.into_future(), priority)
+        } else {
+            self.job_executor.spawn_blocking(stream.into_future())
+        };
+        self.insert_job(
+            handle.job_id,
+            JobRequest::Fetch {
+                mailbox_hash,
+                handle,
+            },
+        );
+    }
+
+    /// Stop fetching `mailbox_hash` once the chunk being fetched arrives, until it is opened
+    /// again with [`Account::load`]. Mailboxes fetched when the account is loaded are not
+    /// deferred.
+    pub fn defer_fetch(&mut self, mailbox_hash: MailboxHash) {
+        let load_trash = self.settings.conf.empty_trash_after_days.is_some();
+        if self.active_jobs.values().any(|j| j.is_fetch(mailbox_hash))
+            && self
+                .mailbox_entries
+                .get(&mailbox_hash)
+                .map(|entry| !autoloads(entry, load_trash))
+                .unwrap_or(false)
+        {
+            self.deferred_fetches.entry(mailbox_hash).or_insert(None);
+        }
+    }
+
     pub fn save_special(
         &mut self,
         bytes: &[u8],
@@ -1260,6 +1645,11 @@ impl Account {
         Ok(())
     }
 
+    /// Timeouts, TCP keepalive and retry policy of the connections of the account.
+    pub fn connection_policy(&self) -> melib::connections::ConnectionPolicy {
+        melib::connections::ConnectionPolicy::new(&self.settings.account).unwrap_or_default()
+    }
+
     pub fn send(
         &mut self,
         message: String,
@@ -1312,7 +1702,8 @@ impl Account {
                 Ok(None)
             }
             #[cfg(feature = "smtp")]
-            SendMail::Smtp(conf) => {
+            SendMail::Smtp(mut conf) => {
+                conf.connection_policy = self.connection_policy();
                 let handle = self.job_executor.spawn_specialized(async move {
                     let mut smtp_connection =
                         melib::smtp::SmtpConnection::new_connection(conf).await?;
@@ -1332,8 +1723,11 @@ impl Account {
     pub fn send_async(
         &self,
         send_mail: crate::conf::composing::SendMail,
-    ) -> impl FnOnce(Arc<String>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send {
-        |message: Arc<String>| -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+    ) -> impl Fn(Arc<String>) -> Pin<Box<dyn Future<Output = Result<()>> + Send>> + Send {
+        #[cfg(feature = "smtp")]
+        let connection_policy = self.connection_policy();
+        move |message: Arc<String>| -> Pin<Box<dyn Future<Output = Result<()>> + Send>> {
+            let send_mail = send_mail.clone();
             Box::pin(async move {
                 use crate::conf::composing::SendMail;
                 use std::io::Write;
@@ -1379,7 +1773,8 @@ impl Account {
                         Ok(())
                     }
                     #[cfg(feature = "smtp")]
-                    SendMail::Smtp(conf) => {
+                    SendMail::Smtp(mut conf) => {
+                        conf.connection_policy = connection_policy;
                         let mut smtp_connection =
                             melib::smtp::SmtpConnection::new_connection(conf).await?;
                         smtp_connection
@@ -1391,9 +1786,52 @@ impl Account {
         }
     }
 
+    /// Write `value` as JSON to the account data file `name`, readable and writable by the owner
+    /// only. Nothing is written if `is_empty` and the file doesn't exist yet. Errors are logged
+    /// rather than returned, so that a failure doesn't prevent saving other files.
+    fn save_data_file<T: serde::Serialize + ?Sized>(&self, name: &str, value: &T, is_empty: bool) {
+        let result = (|| -> Result<()> {
+            let data_dir = xdg::BaseDirectories::with_profile("meli", &self.name)
+                .map_err(|err| MeliError::new(err.to_string()))?;
+            let path = data_dir.place_data_file(name)?;
+            if is_empty && !path.exists() {
+                return Ok(());
+            }
+            let f = fs::File::create(path)?;
+            f.set_permissions(fs::Permissions::from_mode(0o600))?; // Read/write for owner only.
+            serde_json::to_writer(io::BufWriter::new(f), value)
+                .map_err(|err| MeliError::new(err.to_string()))?;
+            Ok(())
+        })();
+        if let Err(err) = result {
+            melib::log(
+                format!("Account `{}`: could not save {}: {}", &self.name, name, err),
+                melib::ERROR,
+            );
+        }
+    }
+
     pub fn contains_key(&self, h: EnvelopeHash) -> bool {
         self.collection.contains_key(&h)
     }
+
+    /// Checks that the backend can apply the tag operations in `ops` to the messages of
+    /// `mailbox_hash`, i.e. that it supports tags at all and, if the mailbox can't get new
+    /// keywords, that every tag being set already exists.
+    pub fn check_flag_ops(&self, mailbox_hash: MailboxHash, ops: &[FlagOp]) -> Result<()> {
+        let tag_support = self.backend.read().unwrap().capabilities().tag_support;
+        let can_create = tag_support.can_create()
+            && self
+                .mailbox_entries
+                .get(&mailbox_hash)
+                .map(|entry| entry.ref_mailbox.can_create_tags())
+                .unwrap_or(true);
+        let tag_index = self.collection.tag_index.read().unwrap();
+        check_tag_ops(&self.name, tag_support, can_create, ops, |tag| {
+            tag_index.values().any(|t| t == tag)
+        })
+    }
+
     pub fn operation(&self, h: EnvelopeHash) -> Result<Box<dyn BackendOp>> {
         let operation = self.backend.read().unwrap().operation(h)?;
         Ok(if self.settings.account.read_only() {
@@ -1408,7 +1846,7 @@ impl Account {
         op: crate::command::actions::MailboxOperation,
     ) -> Result<()> {
         use crate::command::actions::MailboxOperation;
-        if self.settings.account.read_only() {
+        if self.settings.account.read_only() && !matches!(op, MailboxOperation::Download(_)) {
             return Err(MeliError::new("Account is read-only."));
         }
         match op {
@@ -1487,6 +1925,30 @@ impl Account {
                     .expect("Could not send event on main channel");
                 Ok(())
             }
+            MailboxOperation::Download(path) => {
+                let mailbox_hash = self.mailbox_by_path(&path)?;
+                let job = self
+                    .backend
+                    .write()
+                    .unwrap()
+                    .download_mailbox(mailbox_hash)?;
+                let handle = if self.backend_capabilities.is_async {
+                    self.job_executor
+                        .spawn_prioritized(job, JobPriority::Background)
+                } else {
+                    self.job_executor.spawn_blocking(job)
+                };
+                self.insert_job(
+                    handle.job_id,
+                    JobRequest::Generic {
+                        name: format!("Downloading `{}`", path).into(),
+                        handle,
+                        on_finish: None,
+                        logging_level: melib::LoggingLevel::INFO,
+                    },
+                );
+                Ok(())
+            }
             MailboxOperation::Rename(_, _) => Err(MeliError::new("Not implemented.")),
             MailboxOperation::SetPermissions(_) => Err(MeliError::new("Not implemented.")),
         }
@@ -1504,60 +1966,727 @@ impl Account {
         }
     }
 
-    /* Call only in Context::is_online, since only Context can launch the watcher threads if an
-     * account goes from offline to online. */
-    pub fn is_online(&mut self) -> Result<()> {
-        if !self.backend_capabilities.is_remote && !self.backend_capabilities.is_async {
-            return Ok(());
+    /// Record that `env_hashes` are moved to the Trash mailbox now, for [`Account::empty_trash`].
+    pub fn mark_trashed(&mut self, env_hashes: &EnvelopeHashBatch) {
+        if self.settings.conf.empty_trash_after_days.is_none() {
+            return;
         }
-
-        if self.is_online.is_err()
-            && self
-                .is_online
-                .as_ref()
-                .unwrap_err()
-                .kind
-                .is_authentication()
+        let now = melib::datetime::now();
         {
-            return self.is_online.clone();
-        }
-        if self.is_online.is_ok() {
-            return Ok(());
-        }
-        if !self.active_jobs.values().any(JobRequest::is_online) {
-            let online_job = self.backend.read().unwrap().is_online();
-            if let Ok(online_job) = online_job {
-                let handle = if self.backend_capabilities.is_async {
-                    self.job_executor.spawn_specialized(online_job)
-                } else {
-                    self.job_executor.spawn_blocking(online_job)
-                };
-                self.insert_job(handle.job_id, JobRequest::IsOnline { handle });
+            let envelopes = self.collection.envelopes.read().unwrap();
+            for env in env_hashes.iter().filter_map(|h| envelopes.get(&h)) {
+                if !env.message_id().raw().is_empty() {
+                    self.trashed_at.insert(
+                        String::from_utf8_lossy(env.message_id().raw()).to_string(),
+                        now,
+                    );
+                }
             }
         }
-        return self.is_online.clone();
+        self.save_data_file("trashed_at", &self.trashed_at, self.trashed_at.is_empty());
     }
 
-    pub fn search(
-        &self,
-        search_term: &str,
-        _sort: (SortField, SortOrder),
-        mailbox_hash: MailboxHash,
-    ) -> ResultFuture<SmallVec<[EnvelopeHash; 512]>> {
-        let query = melib::search::Query::try_from(search_term)?;
-        match self.settings.conf.search_backend {
-            #[cfg(feature = "sqlite3")]
-            crate::conf::SearchBackend::Sqlite3 => crate::sqlite3::search(&query, _sort),
-            crate::conf::SearchBackend::Auto | crate::conf::SearchBackend::None => {
-                if self.backend_capabilities.supports_search {
-                    self.backend
-                        .read()
-                        .unwrap()
-                        .search(query, Some(mailbox_hash))
-                } else {
-                    use melib::search::QueryTrait;
-                    let mut ret = SmallVec::new();
-                    let envelopes = self.collection.envelopes.read().unwrap();
+    /// Permanently delete the messages that have been in the Trash mailbox for more than `days`
+    /// days. Messages are timed from their move to Trash if it was done by
+    /// [`Account::mark_trashed`], otherwise from the first time they are seen there. Messages
+    /// without a Message-ID are never deleted.
+    pub fn empty_trash(&mut self, days: u64) {
+        let trash_hash = match self.special_use_mailbox(SpecialUsageMailbox::Trash) {
+            Some(h) => h,
+            None => return,
+        };
+        let now = melib::datetime::now();
+        let cutoff = now.saturating_sub(days * 24 * 60 * 60);
+        let in_trash: Vec<(EnvelopeHash, String)> = {
+            let envelopes = self.collection.envelopes.read().unwrap();
+            let trash = self.collection.get_mailbox(trash_hash);
+            trash
+                .iter()
+                .filter_map(|h| envelopes.get(h))
+                .filter(|e| !e.message_id().raw().is_empty())
+                .map(|e| {
+                    (
+                        e.hash(),
+                        String::from_utf8_lossy(e.message_id().raw()).to_string(),
+                    )
+                })
+                .collect()
+        };
+        /* Forget messages that have left the Trash. */
+        self.trashed_at
+            .retain(|id, _| in_trash.iter().any(|(_, i)| i == id));
+        let mut old: Vec<EnvelopeHash> = vec![];
+        for (h, id) in in_trash {
+            if *self.trashed_at.entry(id).or_insert(now) < cutoff {
+                old.push(h);
+            }
+        }
+        self.save_data_file("trashed_at", &self.trashed_at, self.trashed_at.is_empty());
+        let env_hashes = match EnvelopeHashBatch::try_from(old.as_slice()) {
+            Ok(env_hashes) => env_hashes,
+            Err(_) => return,
+        };
+        melib::log(
+            format!(
+                "Account `{}`: deleting {} messages in Trash for more than {} days",
+                &self.name,
+                old.len(),
+                days
+            ),
+            melib::INFO,
+        );
+        let job = self
+            .backend
+            .write()
+            .unwrap()
+            .delete_messages(env_hashes.clone(), trash_hash);
+        match job {
+            Ok(fut) => {
+                let handle = self.job_executor.spawn_specialized(fut);
+                self.insert_job(
+                    handle.job_id,
+                    JobRequest::DeleteMessages { env_hashes, handle },
+                );
+            }
+            Err(err) => {
+                self.sender
+                    .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                        Some(format!("{}: could not empty Trash", &self.name)),
+                        err.to_string(),
+                        Some(crate::types::NotificationType::Error(err.kind)),
+                    )))
+                    .expect("Could not send event on main channel");
+            }
+        }
+    }
+
+    /// Move `env_hashes` from `mailbox_hash` to the Archive mailbox. If `archive_by_year` is set,
+    /// each message goes to the subfolder of the Archive mailbox named after the year of its date,
+    /// which is created if it doesn't exist.
+    pub fn archive(
+        &mut self,
+        env_hashes: &[EnvelopeHash],
+        mailbox_hash: MailboxHash,
+    ) -> Result<()> {
+        let archive_hash = self
+            .special_use_mailbox(SpecialUsageMailbox::Archive)
+            .ok_or_else(|| {
+                MeliError::new(
+                    "No Archive mailbox found. Set `usage = \"Archive\"` in the configuration of a mailbox.",
+                )
+            })?;
+        if !self.settings.conf.archive_by_year {
+            return self.move_messages(
+                env_hashes.to_vec(),
+                mailbox_hash,
+                archive_hash,
+                "archiving messages",
+            );
+        }
+        let archive_path = self.mailbox_entries[&archive_hash]
+            .ref_mailbox
+            .path()
+            .to_string();
+        let mut by_year: BTreeMap<String, Vec<EnvelopeHash>> = BTreeMap::new();
+        {
+            let envelopes = self.collection.envelopes.read().unwrap();
+            for env_hash in env_hashes {
+                if let Some(env) = envelopes.get(env_hash) {
+                    by_year
+                        .entry(melib::datetime::timestamp_to_string(
+                            env.date(),
+                            Some("%Y"),
+                            false,
+                        ))
+                        .or_default()
+                        .push(*env_hash);
+                }
+            }
+        }
+        for (year, hashes) in by_year {
+            self.move_to_path(
+                hashes,
+                mailbox_hash,
+                format!("{}/{}", archive_path, year),
+                "archiving messages",
+            )?;
+        }
+        Ok(())
+    }
+
+    /// Move `env_hashes` from `mailbox_hash` to the snooze mailbox, creating it if needed, to be
+    /// moved back unread at `until` by [`Account::wake_snoozed`].
+    pub fn snooze(
+        &mut self,
+        env_hashes: &[EnvelopeHash],
+        mailbox_hash: MailboxHash,
+        until: UnixTimestamp,
+    ) -> Result<()> {
+        let snooze_path = self.snooze_mailbox_path().to_string();
+        let mailbox_path = self.mailbox_entries[&mailbox_hash]
+            .ref_mailbox
+            .path()
+            .to_string();
+        let resnooze = mailbox_path == snooze_path;
+        {
+            let envelopes = self.collection.envelopes.read().unwrap();
+            for env_hash in env_hashes {
+                if let Some(env) = envelopes.get(env_hash) {
+                    let message_id = String::from_utf8_lossy(env.message_id().raw()).to_string();
+                    if let Some(s) = self.snoozed.iter_mut().find(|s| s.message_id == message_id) {
+                        s.until = until;
+                        if !resnooze {
+                            s.mailbox_path = mailbox_path.clone();
+                        }
+                    } else {
+                        self.snoozed.push(SnoozedMessage {
+                            message_id,
+                            mailbox_path: if resnooze {
+                                self.special_use_mailbox(SpecialUsageMailbox::Inbox)
+                                    .map(|h| self.mailbox_entries[&h].ref_mailbox.path())
+                                    .unwrap_or(mailbox_path.as_str())
+                                    .to_string()
+                            } else {
+                                mailbox_path.clone()
+                            },
+                            until,
+                        });
+                    }
+                }
+            }
+        }
+        self.save_snoozed();
+        if resnooze {
+            return Ok(());
+        }
+        self.move_to_path(
+            env_hashes.to_vec(),
+            mailbox_hash,
+            snooze_path,
+            "snoozing messages",
+        )
+    }
+
+    /// Save the snooze timers right away, so that they survive a crash.
+    fn save_snoozed(&self) {
+        self.save_data_file("snoozed", &self.snoozed, self.snoozed.is_empty());
+    }
+
+    fn snooze_mailbox_path(&self) -> &str {
+        self.settings
+            .conf
+            .snooze_mailbox
+            .as_deref()
+            .unwrap_or("Snoozed")
+    }
+
+    /// Move the snoozed messages whose time has come back to their mailbox, mark them as unread
+    /// and notify the user. The snooze mailbox is loaded first if needed.
+    pub fn wake_snoozed(&mut self) {
+        let now = melib::datetime::now();
+        if !self.snoozed.iter().any(|s| s.until <= now) {
+            return;
+        }
+        let snooze_hash = match self.mailbox_by_path(self.snooze_mailbox_path()) {
+            Ok(h) => h,
+            Err(_) => return,
+        };
+        if self.load(snooze_hash).is_err()
+            || !matches!(
+                self.mailbox_entries[&snooze_hash].status,
+                MailboxStatus::Available
+            )
+        {
+            return;
+        }
+        let (due, rest): (Vec<SnoozedMessage>, Vec<SnoozedMessage>) =
+            std::mem::take(&mut self.snoozed)
+                .into_iter()
+                .partition(|s| s.until <= now);
+        self.snoozed = rest;
+        for s in due {
+            let found = {
+                let envelopes = self.collection.envelopes.read().unwrap();
+                self.collection
+                    .get_mailbox(snooze_hash)
+                    .iter()
+                    .filter_map(|h| envelopes.get(h))
+                    .find(|e| String::from_utf8_lossy(e.message_id().raw()) == s.message_id)
+                    .map(|e| (e.hash(), e.subject().to_string()))
+            };
+            /* The message was moved or deleted in the meantime. */
+            let (env_hash, subject) = match found {
+                Some(found) => found,
+                None => continue,
+            };
+            let destination = match self
+                .mailbox_by_path(&s.mailbox_path)
+                .ok()
+                .or_else(|| self.special_use_mailbox(SpecialUsageMailbox::Inbox))
+            {
+                Some(h) => h,
+                None => continue,
+            };
+            let job = self
+                .backend
+                .write()
+                .unwrap()
+                .set_flags(
+                    env_hash.into(),
+                    snooze_hash,
+                    smallvec::smallvec![FlagOp::Flag(Flag::SEEN, false)],
+                )
+                .and_then(|set_unseen| {
+                    let move_back = self.backend.write().unwrap().copy_messages(
+                        env_hash.into(),
+                        snooze_hash,
+                        destination,
+                        /* move? */ true,
+                    )?;
+                    Ok(Box::pin(async move {
+                        set_unseen.await?;
+                        move_back.await
+                    }))
+                });
+            match job {
+                Ok(fut) => {
+                    let handle = self.job_executor.spawn_specialized(fut);
+                    self.insert_job(
+                        handle.job_id,
+                        JobRequest::Generic {
+                            name: "waking snoozed message".into(),
+                            handle,
+                            on_finish: None,
+                            logging_level: melib::LoggingLevel::INFO,
+                        },
+                    );
+                    self.sender
+                        .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                            Some(format!("{}: snoozed message is back", &self.name)),
+                            subject,
+                            Some(crate::types::NotificationType::Info),
+                        )))
+                        .expect("Could not send event on main channel");
+                }
+                Err(err) => {
+                    self.snoozed.push(s);
+                    self.sender
+                        .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                            Some(format!("{}: could not wake snoozed message", &self.name)),
+                            err.to_string(),
+                            Some(crate::types::NotificationType::Error(err.kind)),
+                        )))
+                        .expect("Could not send event on main channel");
+                }
+            }
+        }
+        self.save_snoozed();
+    }
+
+    /// Match the envelopes of the account against each saved search and update the searches'
+    /// messages and counts. Only envelopes of mailboxes that have been loaded are matched.
+    pub fn update_saved_searches(&mut self) {
+        for (&mailbox_hash, search) in self.saved_searches.iter() {
+            let (env_hashes, unseen) = {
+                let envelopes = self.collection.envelopes.read().unwrap();
+                let env_hashes = envelopes
+                    .iter()
+                    .filter(|(_, e)| search.is_match(e))
+                    .map(|(h, _)| *h)
+                    .collect::<HashSet<EnvelopeHash>>();
+                let unseen = env_hashes
+                    .iter()
+                    .filter(|h| !envelopes[h].is_seen())
+                    .count();
+                (env_hashes, unseen)
+            };
+            search.set_count(unseen, env_hashes.len());
+            if self
+                .collection
+                .set_mailbox_envelopes(mailbox_hash, env_hashes)
+            {
+                self.sender
+                    .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((
+                        self.hash,
+                        mailbox_hash,
+                    ))))
+                    .unwrap();
+            }
+        }
+    }
+
+    /// The mailbox `env_hash` is stored in if `mailbox_hash` is a saved search, otherwise
+    /// `mailbox_hash`. Operations on the messages of saved searches are performed there.
+    pub fn envelope_source_mailbox(
+        &self,
+        env_hash: EnvelopeHash,
+        mailbox_hash: MailboxHash,
+    ) -> MailboxHash {
+        if !self.saved_searches.contains_key(&mailbox_hash) {
+            return mailbox_hash;
+        }
+        self.collection
+            .mailboxes
+            .read()
+            .unwrap()
+            .iter()
+            .find(|(h, envs)| !self.saved_searches.contains_key(h) && envs.contains(&env_hash))
+            .map(|(h, _)| *h)
+            .unwrap_or(mailbox_hash)
+    }
+
+    /// The other copies of `env_hash` (envelopes with the same Message-ID), each with the
+    /// mailbox it is stored in.
+    pub fn duplicates(&self, env_hash: EnvelopeHash) -> SmallVec<[(EnvelopeHash, MailboxHash); 8]> {
+        let duplicates = self.collection.duplicates(env_hash);
+        if duplicates.is_empty() {
+            return SmallVec::new();
+        }
+        let mailboxes_lck = self.collection.mailboxes.read().unwrap();
+        duplicates
+            .into_iter()
+            .filter_map(|h| {
+                mailboxes_lck
+                    .iter()
+                    .find(|(m, envs)| !self.saved_searches.contains_key(m) && envs.contains(&h))
+                    .map(|(m, _)| (h, *m))
+            })
+            .collect()
+    }
+
+    /// The sort last chosen for `mailbox_hash` with the `sort` command, if any.
+    pub fn mailbox_sort(&self, mailbox_hash: MailboxHash) -> Option<(SortField, SortOrder)> {
+        let path = self.mailbox_entries.get(&mailbox_hash)?.ref_mailbox.path();
+        self.mailbox_sorts.get(path).copied()
+    }
+
+    /// Remember `sort` for `mailbox_hash`; it is saved along with the account's other data and
+    /// takes precedence over the `listing.sort` setting.
+    pub fn set_mailbox_sort(&mut self, mailbox_hash: MailboxHash, sort: (SortField, SortOrder)) {
+        if let Some(entry) = self.mailbox_entries.get(&mailbox_hash) {
+            self.mailbox_sorts
+                .insert(entry.ref_mailbox.path().to_string(), sort);
+        }
+    }
+
+    /// Whether the user collapsed (`Some(true)`) or expanded (`Some(false)`) the thread starting
+    /// with `message_id` in `mailbox_hash`.
+    pub fn thread_collapsed(&self, mailbox_hash: MailboxHash, message_id: &str) -> Option<bool> {
+        let path = self.mailbox_entries.get(&mailbox_hash)?.ref_mailbox.path();
+        self.collapsed_threads.get(path)?.get(message_id).copied()
+    }
+
+    /// Remember that the thread starting with `message_id` in `mailbox_hash` is `collapsed`; it is
+    /// saved along with the account's other data.
+    pub fn set_thread_collapsed(
+        &mut self,
+        mailbox_hash: MailboxHash,
+        message_id: String,
+        collapsed: bool,
+    ) {
+        if let Some(entry) = self.mailbox_entries.get(&mailbox_hash) {
+            self.collapsed_threads
+                .entry(entry.ref_mailbox.path().to_string())
+                .or_default()
+                .insert(message_id, collapsed);
+        }
+    }
+
+    /// Whether the user chose to always or never encrypt messages to `email`.
+    #[cfg(feature = "gpgme")]
+    pub fn encrypt_preference(&self, email: &str) -> Option<EncryptPreference> {
+        self.encrypt_preferences.get(&email.to_lowercase()).copied()
+    }
+
+    /// Remember whether to always or never encrypt messages to `email`, or forget it if
+    /// `preference` is `None`; it is saved along with the account's other data.
+    #[cfg(feature = "gpgme")]
+    pub fn set_encrypt_preference(&mut self, email: &str, preference: Option<EncryptPreference>) {
+        if let Some(preference) = preference {
+            self.encrypt_preferences
+                .insert(email.to_lowercase(), preference);
+        } else {
+            self.encrypt_preferences.remove(&email.to_lowercase());
+        }
+    }
+
+    /// Fingerprint of the key the user chose to encrypt to `email` with.
+    #[cfg(feature = "gpgme")]
+    pub fn encrypt_key_choice(&self, email: &str) -> Option<&str> {
+        self.encrypt_key_choices
+            .get(&email.to_lowercase())
+            .map(String::as_str)
+    }
+
+    /// Remember the key to encrypt to `email` with, when several keys match it; it is saved along
+    /// with the account's other data.
+    #[cfg(feature = "gpgme")]
+    pub fn set_encrypt_key_choice(&mut self, email: &str, fingerprint: String) {
+        self.encrypt_key_choices
+            .insert(email.to_lowercase(), fingerprint);
+    }
+
+    /// Remind the user if there's no reply to a sent message by the time in `follow_up`.
+    pub fn add_follow_up(&mut self, follow_up: FollowUp) {
+        self.follow_ups
+            .retain(|f| f.message_id != follow_up.message_id);
+        self.follow_ups.push(follow_up);
+    }
+
+    /// Forget follow-ups of messages that have been replied to by a message in the collection.
+    fn prune_answered_follow_ups(&mut self) {
+        if self.follow_ups.is_empty() {
+            return;
+        }
+        let envelopes = self.collection.envelopes.read().unwrap();
+        self.follow_ups
+            .retain(|f| !envelopes.values().any(|e| f.is_replied_by(e)));
+    }
+
+    /// Notify the user of sent messages that still have no reply when their follow-up is due.
+    /// They are kept in the `awaiting-reply` listing until a reply arrives.
+    pub fn check_follow_ups(&mut self) {
+        let now = melib::datetime::now();
+        if !self
+            .follow_ups
+            .iter()
+            .any(|f| !f.notified && f.until <= now)
+        {
+            return;
+        }
+        self.prune_answered_follow_ups();
+        for f in self
+            .follow_ups
+            .iter_mut()
+            .filter(|f| !f.notified && f.until <= now)
+        {
+            f.notified = true;
+            self.sender
+                .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                    Some(format!("{}: no reply yet", &self.name)),
+                    f.subject.clone(),
+                    Some(crate::types::NotificationType::Info),
+                )))
+                .expect("Could not send event on main channel");
+        }
+    }
+
+    /// Messages of `mailbox_hash` with a follow-up that haven't been replied to.
+    pub fn awaiting_reply(&mut self, mailbox_hash: MailboxHash) -> SmallVec<[EnvelopeHash; 512]> {
+        self.prune_answered_follow_ups();
+        let message_ids: HashSet<&[u8]> = self
+            .follow_ups
+            .iter()
+            .map(|f| f.message_id.as_bytes())
+            .collect();
+        let envelopes = self.collection.envelopes.read().unwrap();
+        self.collection
+            .get_mailbox(mailbox_hash)
+            .iter()
+            .filter(|h| {
+                envelopes
+                    .get(h)
+                    .map(|e| message_ids.contains(e.message_id().raw()))
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// The envelopes of `mailbox_hash` with `term` in their subject or author, ignoring case.
+    pub fn quick_search(
+        &self,
+        term: &str,
+        mailbox_hash: MailboxHash,
+    ) -> SmallVec<[EnvelopeHash; 512]> {
+        let term = term.to_lowercase();
+        let envelopes = self.collection.envelopes.read().unwrap();
+        self.collection
+            .get_mailbox(mailbox_hash)
+            .iter()
+            .filter(|h| {
+                envelopes
+                    .get(h)
+                    .map(|e| {
+                        e.subject().to_lowercase().contains(&term)
+                            || e.field_from_to_string().to_lowercase().contains(&term)
+                    })
+                    .unwrap_or(false)
+            })
+            .cloned()
+            .collect()
+    }
+
+    /// Move `env_hashes` from `source` to the mailbox at `path`, creating it first if it doesn't
+    /// exist.
+    fn move_to_path(
+        &mut self,
+        env_hashes: Vec<EnvelopeHash>,
+        source: MailboxHash,
+        path: String,
+        name: &'static str,
+    ) -> Result<()> {
+        if let Ok(destination) = self.mailbox_by_path(&path) {
+            return self.move_messages(env_hashes, source, destination, name);
+        }
+        let already_requested = self.pending_moves.iter().any(|(p, _, _, _)| *p == path);
+        self.pending_moves
+            .push((path.clone(), source, env_hashes, name));
+        if !already_requested {
+            if let Err(err) = self.mailbox_operation(
+                crate::command::actions::MailboxOperation::Create(path.clone()),
+            ) {
+                self.pending_moves.retain(|(p, _, _, _)| *p != path);
+                return Err(err);
+            }
+        }
+        Ok(())
+    }
+
+    fn move_messages(
+        &mut self,
+        env_hashes: Vec<EnvelopeHash>,
+        source: MailboxHash,
+        destination: MailboxHash,
+        name: &'static str,
+    ) -> Result<()> {
+        let env_hashes = EnvelopeHashBatch::try_from(env_hashes.as_slice())
+            .map_err(|_| MeliError::new("No messages to move."))?;
+        let job = self.backend.write().unwrap().copy_messages(
+            env_hashes,
+            source,
+            destination,
+            /* move? */ true,
+        )?;
+        let handle = self.job_executor.spawn_specialized(job);
+        self.insert_job(
+            handle.job_id,
+            JobRequest::Generic {
+                name: name.into(),
+                handle,
+                on_finish: None,
+                logging_level: melib::LoggingLevel::INFO,
+            },
+        );
+        Ok(())
+    }
+
+    /* Call only in Context::is_online, since only Context can launch the watcher threads if an
+     * account goes from offline to online. */
+    pub fn is_online(&mut self) -> Result<()> {
+        if !self.backend_capabilities.is_remote && !self.backend_capabilities.is_async {
+            return Ok(());
+        }
+
+        if self.is_online.is_err()
+            && self
+                .is_online
+                .as_ref()
+                .unwrap_err()
+                .kind
+                .is_authentication()
+        {
+            return self.is_online.clone();
+        }
+        if self.is_online.is_ok() {
+            return Ok(());
+        }
+        if !self.active_jobs.values().any(JobRequest::is_online) {
+            self.spawn_online_check(None);
+        }
+        return self.is_online.clone();
+    }
+
+    /// Launch an online status check after `delay`, or after a jittered backoff delay if the
+    /// previous checks failed. Nothing is launched while the network is down.
+    fn spawn_online_check(&mut self, delay: Option<std::time::Duration>) {
+        if self.network_down {
+            return;
+        }
+        let delay = delay.or_else(|| {
+            if self.online_failures > 0 {
+                Some(crate::network::jittered(
+                    watch_backoff(self.online_failures),
+                    self.hash,
+                ))
+            } else {
+                None
+            }
+        });
+        let online_job = self.backend.read().unwrap().is_online();
+        if let Ok(online_job) = online_job {
+            let online_job = if let Some(delay) = delay {
+                Box::pin(async move {
+                    melib::connections::sleep(delay).await;
+                    online_job.await
+                })
+            } else {
+                online_job
+            };
+            let handle = if self.backend_capabilities.is_async {
+                self.job_executor.spawn_specialized(online_job)
+            } else {
+                self.job_executor.spawn_blocking(online_job)
+            };
+            self.insert_job(handle.job_id, JobRequest::IsOnline { handle });
+        }
+    }
+
+    /// Mark the account offline because the machine lost its network link, and cancel its watch
+    /// job and online status checks until [`Account::network_restored`].
+    pub fn network_lost(&mut self) {
+        if !self.backend_capabilities.is_remote || self.network_down {
+            return;
+        }
+        self.network_down = true;
+        self.stop_watching();
+        self.active_jobs.retain(|_, j| !j.is_online());
+        if !matches!(self.is_online, Err(ref err) if err.kind.is_authentication()) {
+            self.is_online = Err(MeliError::new("Network is unreachable.")
+                .set_kind(melib::error::ErrorKind::Network));
+        }
+        self.sender
+            .send(ThreadEvent::UIEvent(UIEvent::AccountStatusChange(
+                self.hash,
+            )))
+            .unwrap();
+    }
+
+    /// Reconnect after `delay` once the network link is back. The watch job is restarted when the
+    /// account is found online.
+    pub fn network_restored(&mut self, delay: std::time::Duration) {
+        if !self.network_down {
+            return;
+        }
+        self.network_down = false;
+        self.online_failures = 0;
+        if !self.active_jobs.values().any(JobRequest::is_online) {
+            self.spawn_online_check(Some(delay));
+        }
+    }
+
+    pub fn search(
+        &self,
+        search_term: &str,
+        _sort: (SortField, SortOrder),
+        mailbox_hash: MailboxHash,
+    ) -> ResultFuture<SmallVec<[EnvelopeHash; 512]>> {
+        let query = melib::search::Query::try_from(search_term)?;
+        match self.settings.conf.search_backend {
+            #[cfg(feature = "sqlite3")]
+            crate::conf::SearchBackend::Sqlite3 => {
+                crate::sqlite3::search(&query, _sort, &self.name, Some(mailbox_hash))
+            }
+            crate::conf::SearchBackend::Auto | crate::conf::SearchBackend::None => {
+                if self.backend_capabilities.supports_search {
+                    self.backend
+                        .read()
+                        .unwrap()
+                        .search(query, Some(mailbox_hash))
+                } else {
+                    use melib::search::QueryTrait;
+                    let mut ret = SmallVec::new();
+                    let envelopes = self.collection.envelopes.read().unwrap();
                     for &env_hash in self.collection.get_mailbox(mailbox_hash).iter() {
                         if let Some(envelope) = envelopes.get(&env_hash) {
                             if envelope.is_match(&query) {
@@ -1571,6 +2700,43 @@ impl Account {
         }
     }
 
+    /// The mailboxes that contain `env_hash`, including `mailbox_hash`.
+    /// Record `addresses` in the collected addresses, except for the account's own address and
+    /// addresses that match one of the `ignore` glob patterns.
+    pub fn collect_addresses(&mut self, addresses: &[Address], ignore: &[String]) {
+        let now = melib::datetime::now();
+        for address in addresses {
+            let email = address.get_email().to_lowercase();
+            if email.eq_ignore_ascii_case(self.settings.account.identity())
+                || ignore
+                    .iter()
+                    .any(|pattern| email.matches_glob(&pattern.to_lowercase()))
+            {
+                continue;
+            }
+            self.collected_addresses.collect(address, now);
+        }
+    }
+
+    #[cfg(feature = "sqlite3")]
+    fn envelope_mailboxes(
+        &self,
+        env_hash: EnvelopeHash,
+        mailbox_hash: MailboxHash,
+    ) -> Vec<MailboxHash> {
+        let mut ret = self
+            .collection
+            .mailboxes
+            .read()
+            .unwrap()
+            .iter()
+            .filter(|(h, envs)| **h != mailbox_hash && envs.contains(&env_hash))
+            .map(|(h, _)| *h)
+            .collect::<Vec<MailboxHash>>();
+        ret.push(mailbox_hash);
+        ret
+    }
+
     pub fn mailbox_by_path(&self, path: &str) -> Result<MailboxHash> {
         if let Some((mailbox_hash, _)) = self
             .mailbox_entries
@@ -1583,6 +2749,227 @@ impl Account {
         }
     }
 
+    /// Environment variables describing envelope `env_hash` for external commands.
+    pub fn envelope_env_vars(
+        &self,
+        mailbox_hash: MailboxHash,
+        env_hash: EnvelopeHash,
+    ) -> Vec<(&'static str, String)> {
+        let envelope = self.collection.get_env(env_hash);
+        vec![
+            ("MELI_ACCOUNT", self.name.clone()),
+            (
+                "MELI_MAILBOX",
+                self.mailbox_entries[&mailbox_hash]
+                    .ref_mailbox
+                    .path()
+                    .to_string(),
+            ),
+            ("MELI_FROM", envelope.field_from_to_string()),
+            ("MELI_TO", envelope.field_to_to_string()),
+            ("MELI_SUBJECT", envelope.subject().into_owned()),
+            ("MELI_DATE", envelope.date_as_str().to_string()),
+            (
+                "MELI_MESSAGE_ID",
+                envelope.message_id_display().into_owned(),
+            ),
+        ]
+    }
+
+    /// Apply the account's filtering rules to new envelope `env_hash`. Returns whether the
+    /// envelope was moved or marked as read, and the names of the matching rules that notify.
+    fn apply_filters(
+        &mut self,
+        env_hash: EnvelopeHash,
+        mailbox_hash: MailboxHash,
+    ) -> (bool, Vec<String>) {
+        let mut filtered = false;
+        let mut notify = vec![];
+        if self.settings.conf.filters.is_empty() {
+            return (filtered, notify);
+        }
+        let mailbox_path = self.mailbox_entries[&mailbox_hash]
+            .ref_mailbox
+            .path()
+            .to_string();
+        let rules = self
+            .settings
+            .conf
+            .filters
+            .iter()
+            .filter(|r| r.applies_to(&mailbox_path))
+            .cloned()
+            .collect::<Vec<crate::conf::FilterRule>>();
+        for rule in rules {
+            if !rule.matches(&self.collection.get_env(env_hash)) {
+                continue;
+            }
+            debug!("filter {} matched {}", rule.name(), env_hash);
+            let mut flags: FlagOps = rule
+                .tag
+                .iter()
+                .map(|t| FlagOp::Tag(t.clone(), true))
+                .collect();
+            if rule.mark_read {
+                flags.push(FlagOp::Flag(Flag::SEEN, true));
+                filtered = true;
+            }
+            let mut futures = vec![];
+            if !flags.is_empty() {
+                match self.check_flag_ops(mailbox_hash, &flags).and_then(|()| {
+                    self.backend
+                        .write()
+                        .unwrap()
+                        .set_flags(env_hash.into(), mailbox_hash, flags)
+                }) {
+                    Ok(fut) => futures.push(fut),
+                    Err(err) => melib::log(
+                        format!("Filter {}: could not set flags: {}", rule.name(), err),
+                        melib::ERROR,
+                    ),
+                }
+            }
+            let move_to = rule.move_to.as_ref().filter(|path| **path != mailbox_path);
+            if let Some(move_to) = move_to {
+                match self.mailbox_by_path(move_to).and_then(|destination| {
+                    self.backend.write().unwrap().copy_messages(
+                        env_hash.into(),
+                        mailbox_hash,
+                        destination,
+                        /* move? */ true,
+                    )
+                }) {
+                    Ok(fut) => {
+                        futures.push(fut);
+                        filtered = true;
+                    }
+                    Err(err) => melib::log(
+                        format!(
+                            "Filter {}: could not move message to {}: {}",
+                            rule.name(),
+                            move_to,
+                            err
+                        ),
+                        melib::ERROR,
+                    ),
+                }
+            }
+            if !futures.is_empty() {
+                /* Run the jobs in order, so that flags are set before the message is moved. */
+                let fut = async move {
+                    for fut in futures {
+                        fut.await?;
+                    }
+                    Ok(())
+                };
+                let handle = if self.backend_capabilities.is_async {
+                    self.job_executor.spawn_specialized(fut)
+                } else {
+                    self.job_executor.spawn_blocking(fut)
+                };
+                self.insert_job(
+                    handle.job_id,
+                    JobRequest::Generic {
+                        name: format!("Filter {}", rule.name()).into(),
+                        handle,
+                        on_finish: None,
+                        logging_level: melib::LoggingLevel::INFO,
+                    },
+                );
+            }
+            if let Some(ref command) = rule.command {
+                match std::process::Command::new("sh")
+                    .args(&["-c", command])
+                    .envs(self.envelope_env_vars(mailbox_hash, env_hash))
+                    .stdin(std::process::Stdio::null())
+                    .stdout(std::process::Stdio::null())
+                    .stderr(std::process::Stdio::piped())
+                    .spawn()
+                {
+                    Ok(child) => {
+                        self.sender
+                            .send(ThreadEvent::UIEvent(UIEvent::Fork(
+                                crate::ForkType::Generic(child),
+                            )))
+                            .unwrap();
+                    }
+                    Err(err) => melib::log(
+                        format!("Filter {}: could not run {}: {}", rule.name(), command, err),
+                        melib::ERROR,
+                    ),
+                }
+            }
+            if rule.notify {
+                notify.push(rule.name().to_string());
+            }
+            if rule.stop || move_to.is_some() {
+                break;
+            }
+        }
+        (filtered, notify)
+    }
+
+    /// Override the threading of envelope `env_hash` with `link` and store the link as a tag if
+    /// the backend supports tags.
+    pub fn set_thread_link(&mut self, env_hash: EnvelopeHash, link: ThreadLink) -> Result<()> {
+        if !self.collection.contains_key(&env_hash) {
+            return Err(MeliError::new("Message not found."));
+        }
+        let message_id =
+            String::from_utf8_lossy(self.collection.get_env(env_hash).message_id().raw())
+                .to_string();
+        if message_id.is_empty() {
+            return Err(MeliError::new("Message has no Message-ID."));
+        }
+        if let ThreadLink::Parent(ref parent) = link {
+            if *parent == message_id {
+                return Err(MeliError::new("Cannot link a message to itself."));
+            }
+        }
+        let previous = self
+            .collection
+            .thread_links
+            .read()
+            .unwrap()
+            .get(&message_id)
+            .cloned();
+        self.collection.set_thread_link(message_id, link.clone());
+        if self.backend_capabilities.tag_support.can_create() {
+            let mailbox_hash = self
+                .collection
+                .mailboxes
+                .read()
+                .unwrap()
+                .iter()
+                .find(|(_, hashes)| hashes.contains(&env_hash))
+                .map(|(mailbox_hash, _)| *mailbox_hash);
+            if let Some(mailbox_hash) = mailbox_hash {
+                let mut flags: FlagOps = SmallVec::new();
+                if let Some(previous) = previous.filter(|p| *p != link) {
+                    flags.push(FlagOp::Tag(previous.tag(), false));
+                }
+                flags.push(FlagOp::Tag(link.tag(), true));
+                let env_hashes = EnvelopeHashBatch::from(env_hash);
+                let job = self.backend.write().unwrap().set_flags(
+                    env_hashes.clone(),
+                    mailbox_hash,
+                    flags,
+                )?;
+                let handle = self.job_executor.spawn_specialized(job);
+                self.insert_job(handle.job_id, JobRequest::SetFlags { env_hashes, handle });
+            }
+        }
+        for mailbox_hash in self.mailbox_entries.keys() {
+            self.sender
+                .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((
+                    self.hash,
+                    *mailbox_hash,
+                ))))
+                .unwrap();
+        }
+        Ok(())
+    }
+
     pub fn process_event(&mut self, job_id: &JobId) -> bool {
         self.sender
             .send(ThreadEvent::UIEvent(UIEvent::StatusEvent(
@@ -1590,6 +2977,7 @@ impl Account {
             )))
             .unwrap();
 
+        self.active_job_instants.retain(|_, id| id != job_id);
         if let Some(mut job) = self.active_jobs.remove(job_id) {
             match job {
                 JobRequest::Mailboxes { ref mut handle } => {
@@ -1639,11 +3027,19 @@ impl Account {
                         }
                         Ok(Some((None, _))) => {
                             debug!("finished in status for {}", mailbox_hash);
+                            self.deferred_fetches.remove(&mailbox_hash);
                             self.mailbox_entries
                                 .entry(mailbox_hash)
                                 .and_modify(|entry| {
                                     entry.status = MailboxStatus::Available;
                                 });
+                            if let Some(days) = self.settings.conf.empty_trash_after_days {
+                                if self.special_use_mailbox(SpecialUsageMailbox::Trash)
+                                    == Some(mailbox_hash)
+                                {
+                                    self.empty_trash(days);
+                                }
+                            }
                             self.sender
                                 .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((
                                     self.hash,
@@ -1653,6 +3049,7 @@ impl Account {
                             return true;
                         }
                         Ok(Some((Some(Err(err)), _))) => {
+                            self.deferred_fetches.remove(&mailbox_hash);
                             self.sender
                                 .send(ThreadEvent::UIEvent(UIEvent::Notification(
                                     Some(format!("{}: could not fetch mailbox", &self.name)),
@@ -1674,18 +3071,38 @@ impl Account {
                             return true;
                         }
                         Ok(Some((Some(Ok(payload)), rest))) => {
-                            let handle = if self.backend_capabilities.is_async {
-                                self.job_executor.spawn_specialized(rest.into_future())
+                            if let Some(deferred) = self.deferred_fetches.get_mut(&mailbox_hash) {
+                                *deferred = Some(DeferredFetch(rest));
                             } else {
-                                self.job_executor.spawn_blocking(rest.into_future())
-                            };
-                            self.insert_job(
-                                handle.job_id,
-                                JobRequest::Fetch {
-                                    mailbox_hash,
-                                    handle,
-                                },
-                            );
+                                self.spawn_fetch(mailbox_hash, rest, JobPriority::Normal);
+                            }
+                            #[cfg(feature = "sqlite3")]
+                            if self.settings.conf.search_backend
+                                == crate::conf::SearchBackend::Sqlite3
+                                && !self.backend_capabilities.lightweight_sync
+                            {
+                                let handle =
+                                    self.job_executor
+                                        .spawn_blocking(crate::sqlite3::insert_batch(
+                                            payload.clone(),
+                                            self.backend.clone(),
+                                            self.name.clone(),
+                                            mailbox_hash,
+                                        ));
+                                self.insert_job(
+                                    handle.job_id,
+                                    JobRequest::Generic {
+                                        name: format!(
+                                            "Index {} envelopes in sqlite3 cache",
+                                            payload.len()
+                                        )
+                                        .into(),
+                                        handle,
+                                        logging_level: melib::LoggingLevel::TRACE,
+                                        on_finish: None,
+                                    },
+                                );
+                            }
                             let envelopes = payload
                                 .into_iter()
                                 .map(|e| (e.hash(), e))
@@ -1702,6 +3119,7 @@ impl Account {
                                         .unwrap();
                                 }
                             }
+                            self.update_saved_searches();
                             self.sender
                                 .send(ThreadEvent::UIEvent(UIEvent::MailboxUpdate((
                                     self.hash,
@@ -1730,19 +3148,13 @@ impl Account {
                                 self.watch();
                             }
                             self.is_online = Ok(());
+                            self.online_failures = 0;
                             return true;
                         }
                         self.is_online = is_online;
+                        self.online_failures = self.online_failures.saturating_add(1);
                     }
-                    let online_job = self.backend.read().unwrap().is_online();
-                    if let Ok(online_job) = online_job {
-                        let handle = if self.backend_capabilities.is_async {
-                            self.job_executor.spawn_specialized(online_job)
-                        } else {
-                            self.job_executor.spawn_blocking(online_job)
-                        };
-                        self.insert_job(handle.job_id, JobRequest::IsOnline { handle });
-                    };
+                    self.spawn_online_check(None);
                 }
                 JobRequest::Refresh { ref mut handle, .. } => {
                     match handle.chan.try_recv() {
@@ -1777,15 +3189,7 @@ impl Account {
                         }
                         Ok(Some(Err(err))) => {
                             if !err.kind.is_authentication() {
-                                let online_job = self.backend.read().unwrap().is_online();
-                                if let Ok(online_job) = online_job {
-                                    let handle = if self.backend_capabilities.is_async {
-                                        self.job_executor.spawn_specialized(online_job)
-                                    } else {
-                                        self.job_executor.spawn_blocking(online_job)
-                                    };
-                                    self.insert_job(handle.job_id, JobRequest::IsOnline { handle });
-                                };
+                                self.spawn_online_check(None);
                             }
                             self.is_online = Err(err);
                             self.sender
@@ -1890,6 +3294,7 @@ impl Account {
                     if let Ok(Some(r)) = handle.chan.try_recv() {
                         match r {
                             Err(err) => {
+                                self.pending_moves.retain(|(p, _, _, _)| p != path);
                                 self.sender
                                     .send(ThreadEvent::UIEvent(UIEvent::Notification(
                                         Some(format!(
@@ -1962,6 +3367,28 @@ impl Account {
                                     &self.mailbox_entries,
                                     &mut self.mailboxes_order,
                                 );
+                                let (pending, rest) = std::mem::take(&mut self.pending_moves)
+                                    .into_iter()
+                                    .partition(|(p, _, _, _)| p == path);
+                                self.pending_moves = rest;
+                                for (_, source, env_hashes, name) in pending {
+                                    if let Err(err) =
+                                        self.move_messages(env_hashes, source, mailbox_hash, name)
+                                    {
+                                        self.sender
+                                            .send(ThreadEvent::UIEvent(UIEvent::Notification(
+                                                Some(format!(
+                                                    "{}: could not move messages to {}",
+                                                    &self.name, path
+                                                )),
+                                                err.to_string(),
+                                                Some(crate::types::NotificationType::Error(
+                                                    err.kind,
+                                                )),
+                                            )))
+                                            .expect("Could not send event on main channel");
+                                    }
+                                }
                                 //Ok(format!("`{}` successfully created.", &path))
                             }
                         }
@@ -2108,8 +3535,21 @@ impl Account {
                     if let Ok(Some(Err(err))) = handle.chan.try_recv() {
                         if err.kind.is_timeout() {
                             self.watch();
+                        } else if err.is_recoverable() && self.watch_failures < WATCH_MAX_RETRIES {
+                            self.watch_failures += 1;
+                            let delay = watch_backoff(self.watch_failures);
+                            melib::log(
+                                format!(
+                                    "{}: watch thread failed, retrying in {}s: {}",
+                                    &self.name,
+                                    delay.as_secs(),
+                                    err
+                                ),
+                                melib::LoggingLevel::WARN,
+                            );
+                            self.watch_after(Some(delay));
                         } else {
-                            //TODO: relaunch watch job with ratelimit for failure
+                            self.watch_failures = 0;
                             self.sender
                                 .send(ThreadEvent::UIEvent(UIEvent::Notification(
                                     Some(format!("{}: watch thread failed", &self.name)),
@@ -2176,7 +3616,16 @@ impl Account {
             .unwrap();
     }
 
+    /// When the job `job_id` was started, if it is still active.
+    pub fn job_start(&self, job_id: &JobId) -> Option<std::time::Instant> {
+        self.active_job_instants
+            .iter()
+            .find(|(_, id)| *id == job_id)
+            .map(|(instant, _)| *instant)
+    }
+
     pub fn cancel_job(&mut self, job_id: JobId) -> Option<JobRequest> {
+        self.active_job_instants.retain(|_, id| *id != job_id);
         if let Some(req) = self.active_jobs.remove(&job_id) {
             self.sender
                 .send(ThreadEvent::UIEvent(UIEvent::StatusEvent(
@@ -2327,3 +3776,62 @@ fn build_mailboxes_order(
         rec(node, &mailbox_entries, 0, 0, false);
     }
 }
+
+/// See `Account::check_flag_ops`. `tag_exists` tells whether a tag is already known.
+fn check_tag_ops(
+    account_name: &str,
+    tag_support: TagSupport,
+    can_create: bool,
+    ops: &[FlagOp],
+    tag_exists: impl Fn(&str) -> bool,
+) -> Result<()> {
+    for op in ops {
+        match op {
+            FlagOp::Tag(_, _) if !tag_support.can_set() => {
+                return Err(MeliError::new(format!(
+                    "Account {} does not support tags.",
+                    account_name
+                )));
+            }
+            FlagOp::Tag(tag, true) if !can_create && !tag_exists(tag) => {
+                return Err(MeliError::new(format!(
+                    "Account {} cannot create new tags in this mailbox and `{}` does not exist.",
+                    account_name, tag
+                )));
+            }
+            _ => {}
+        }
+    }
+    Ok(())
+}
+
+#[test]
+fn test_check_tag_ops() {
+    let ops = [
+        FlagOp::Flag(Flag::SEEN, true),
+        FlagOp::Tag("todo".to_string(), true),
+    ];
+    let exists = |tag: &str| tag == "work";
+    assert!(check_tag_ops("a", TagSupport::Any, true, &ops, exists).is_ok());
+    /* A mailbox that doesn't allow new keywords rejects a new tag, but not an existing one or
+     * the removal of one. */
+    assert!(check_tag_ops("a", TagSupport::Any, false, &ops, exists).is_err());
+    assert!(check_tag_ops(
+        "a",
+        TagSupport::Any,
+        false,
+        &[FlagOp::Tag("work".to_string(), true)],
+        exists
+    )
+    .is_ok());
+    assert!(check_tag_ops(
+        "a",
+        TagSupport::Existing,
+        true,
+        &[FlagOp::Tag("todo".to_string(), false)],
+        exists
+    )
+    .is_ok());
+    assert!(check_tag_ops("a", TagSupport::None, true, &ops, exists).is_err());
+    assert!(check_tag_ops("a", TagSupport::None, true, &ops[..1], exists).is_ok());
+}
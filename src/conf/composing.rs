@@ -22,6 +22,7 @@
 //! Configuration for composing email.
 use super::default_vals::{false_val, none, true_val};
 use std::collections::HashMap;
+use std::convert::TryInto;
 
 /// Settings for writing and sending new e-mail
 #[derive(Debug, Serialize, Deserialize, Clone)]
@@ -72,6 +73,140 @@ pub struct ComposingSettings {
     /// Default: true
     #[serde(default = "true_val")]
     pub attribution_use_posix_locale: bool,
+    /// Collect the From, To and Cc addresses of mail you read or send and use them in recipient
+    /// completion.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub collect_addresses: bool,
+    /// Glob patterns of addresses that are never collected, e.g. `"*noreply*"`.
+    /// Default: empty
+    #[serde(default)]
+    pub collect_addresses_ignore: Vec<String>,
+    /// Split outgoing messages larger than this many bytes into message/partial messages
+    /// (RFC 2046) that are sent separately.
+    /// Default: None
+    #[serde(default = "none")]
+    pub max_message_size: Option<usize>,
+    /// Recipients of replies started with the `reply` shortcut: `ask`, `author`, `all` or
+    /// `list`. `ask` prompts only if the envelope is from a mailing list or has other recipients.
+    /// Default: ask
+    #[serde(default, alias = "reply-mode")]
+    pub reply_mode: ReplyMode,
+    /// Other identities of the account, such as aliases. Replies to messages delivered to one of
+    /// them, according to their `Delivered-To`, `To` or `Cc` headers, are sent from it. The
+    /// `cycle_identity` composer shortcut switches between them.
+    /// Default: empty
+    #[serde(default)]
+    pub identities: Vec<Identity>,
+    /// Checks run before a draft is sent. Sending asks for confirmation for each failed check.
+    #[serde(default, alias = "pre-send-checks")]
+    pub pre_send_checks: PreSendChecks,
+}
+
+/// See `pre_send_checks`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct PreSendChecks {
+    /// Warn if the subject is empty.
+    /// Default: true
+    #[serde(default = "true_val")]
+    pub missing_subject: bool,
+    /// Warn if the body mentions one of these words, outside quoted lines, but there are no
+    /// attachments. Matching is case-insensitive.
+    /// Default: ["attach"]
+    #[serde(default = "default_attachment_keywords")]
+    pub attachment_keywords: Vec<String>,
+    /// Warn if a recipient's address is not in one of these domains.
+    /// Default: empty
+    #[serde(default)]
+    pub internal_domains: Vec<String>,
+    /// Warn if there are more recipients than this.
+    /// Default: None
+    #[serde(default = "none")]
+    pub max_recipients: Option<usize>,
+    /// Warn if the draft isn't encrypted although there are public keys for all its recipients.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub unencrypted_with_keys: bool,
+}
+
+fn default_attachment_keywords() -> Vec<String> {
+    vec!["attach".to_string()]
+}
+
+impl Default for PreSendChecks {
+    fn default() -> Self {
+        PreSendChecks {
+            missing_subject: true,
+            attachment_keywords: default_attachment_keywords(),
+            internal_domains: vec![],
+            max_recipients: None,
+            unencrypted_with_keys: false,
+        }
+    }
+}
+
+/// Another address of an account, see `identities`.
+#[derive(Debug, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct Identity {
+    /// The address, with an optional display name, e.g. `"Name <alias@example.com>"`.
+    pub address: String,
+    /// Display name, instead of the one in `address`.
+    #[serde(default = "none")]
+    pub display_name: Option<String>,
+    /// Signature appended to drafts sent from this address.
+    #[serde(default = "none")]
+    pub signature: Option<String>,
+    /// Send mail from this address with this instead of `send_mail`.
+    #[serde(default = "none")]
+    pub send_mail: Option<SendMail>,
+    /// Key to sign mail from this address with, instead of `pgp.sign_key`.
+    #[serde(default = "none")]
+    pub pgp_key: Option<String>,
+    /// Path of the mailbox to save copies of mail sent from this address in, instead of the
+    /// account's Sent mailbox.
+    #[serde(default = "none")]
+    pub fcc: Option<String>,
+}
+
+impl Identity {
+    /// The value of the `From` header of drafts sent from this identity.
+    pub fn from_header(&self) -> String {
+        match self.display_name {
+            Some(ref display_name) => {
+                let address: Option<melib::Address> = self.address.as_str().try_into().ok();
+                format!(
+                    "{} <{}>",
+                    display_name,
+                    address
+                        .map(|a| a.get_email())
+                        .unwrap_or_else(|| self.address.clone())
+                )
+            }
+            None => self.address.clone(),
+        }
+    }
+}
+
+/// Who a reply is addressed to.
+#[derive(Debug, Serialize, Deserialize, Clone, Copy, PartialEq, Eq)]
+#[serde(rename_all = "kebab-case")]
+pub enum ReplyMode {
+    /// Prompt for one of the others when the choice is ambiguous, otherwise `Author`.
+    Ask,
+    /// `Mail-Reply-To`, `Reply-To` or `From`.
+    Author,
+    /// `Mail-Followup-To`, or the author, `To` and `Cc`.
+    All,
+    /// The `List-Post` address, or `All` if there is none.
+    List,
+}
+
+impl Default for ReplyMode {
+    fn default() -> Self {
+        ReplyMode::Ask
+    }
 }
 
 impl Default for ComposingSettings {
@@ -86,6 +221,12 @@ impl Default for ComposingSettings {
             store_sent_mail: true,
             attribution_format_string: None,
             attribution_use_posix_locale: true,
+            collect_addresses: false,
+            collect_addresses_ignore: vec![],
+            max_message_size: None,
+            reply_mode: ReplyMode::default(),
+            identities: vec![],
+            pre_send_checks: PreSendChecks::default(),
         }
     }
 }
@@ -0,0 +1,215 @@
+/*
+ * meli - configuration module.
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Local filtering rules applied to new mail.
+
+use super::default_vals::{false_val, none};
+use indexmap::IndexMap;
+use melib::Envelope;
+
+/// A filtering rule. Its conditions are checked against every new envelope in its mailboxes, and
+/// if they match its actions are performed.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(deny_unknown_fields)]
+pub struct FilterRule {
+    /// Name of the rule, shown in notifications.
+    /// Default: None
+    #[serde(default = "none")]
+    pub name: Option<String>,
+    /// Paths of the mailboxes whose new mail is filtered. All mailboxes if empty.
+    /// Default: []
+    #[serde(default)]
+    pub mailboxes: Vec<String>,
+    /// Match if any condition matches instead of all of them.
+    /// Default: false
+    #[serde(default = "false_val", alias = "match-any")]
+    pub match_any: bool,
+
+    /// Header names and values they must contain, case-insensitively.
+    /// Default: {}
+    #[serde(default)]
+    pub header: IndexMap<String, String>,
+    /// Value the From header must contain, case-insensitively.
+    /// Default: None
+    #[serde(default = "none")]
+    pub from: Option<String>,
+    /// Value the To or Cc headers must contain, case-insensitively.
+    /// Default: None
+    #[serde(default = "none")]
+    pub to: Option<String>,
+    /// Value the Subject header must contain, case-insensitively.
+    /// Default: None
+    #[serde(default = "none")]
+    pub subject: Option<String>,
+    /// Value the List-Id header must contain, case-insensitively.
+    /// Default: None
+    #[serde(default = "none", alias = "list-id")]
+    pub list_id: Option<String>,
+    /// Minimum size of the message in bytes.
+    /// Default: None
+    #[serde(default = "none", alias = "size-over")]
+    pub size_over: Option<usize>,
+    /// Maximum size of the message in bytes.
+    /// Default: None
+    #[serde(default = "none", alias = "size-under")]
+    pub size_under: Option<usize>,
+    /// Minimum spam score, read from the X-Spam-Score, X-Spam-Status or X-Spam-Level headers.
+    /// Default: None
+    #[serde(default = "none", alias = "spam-score-over")]
+    pub spam_score_over: Option<f64>,
+
+    /// Path of the mailbox to move the message to.
+    /// Default: None
+    #[serde(default = "none", alias = "move-to")]
+    pub move_to: Option<String>,
+    /// Tags to add to the message.
+    /// Default: []
+    #[serde(default)]
+    pub tag: Vec<String>,
+    /// Mark the message as read.
+    /// Default: false
+    #[serde(default = "false_val", alias = "mark-read")]
+    pub mark_read: bool,
+    /// Show a notification with the rule's name. Messages that are moved or marked as read
+    /// otherwise do not trigger a new mail notification.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub notify: bool,
+    /// A shell command to execute, with the message's fields in the same environment variables as
+    /// `notifications.new_mail_script`.
+    /// Default: None
+    #[serde(default = "none")]
+    pub command: Option<String>,
+    /// Do not check the rules following this one if it matches.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub stop: bool,
+}
+
+fn contains(haystack: &str, needle: &str) -> bool {
+    haystack.to_lowercase().contains(&needle.to_lowercase())
+}
+
+/// The spam score set by SpamAssassin or similar filters, if any.
+fn spam_score(envelope: &Envelope) -> Option<f64> {
+    let headers = envelope.other_headers();
+    if let Some(score) = headers
+        .get("X-Spam-Score")
+        .and_then(|s| s.trim().parse::<f64>().ok())
+    {
+        return Some(score);
+    }
+    if let Some(score) = headers.get("X-Spam-Status").and_then(|s| {
+        s.split(|c: char| c.is_whitespace() || c == ',')
+            .find_map(|w| w.strip_prefix("score="))
+            .and_then(|s| s.parse::<f64>().ok())
+    }) {
+        return Some(score);
+    }
+    headers
+        .get("X-Spam-Level")
+        .map(|s| s.trim().chars().filter(|c| *c == '*').count() as f64)
+}
+
+impl FilterRule {
+    pub fn name(&self) -> &str {
+        self.name.as_deref().unwrap_or("unnamed rule")
+    }
+
+    pub fn applies_to(&self, mailbox_path: &str) -> bool {
+        self.mailboxes.is_empty() || self.mailboxes.iter().any(|m| m == mailbox_path)
+    }
+
+    pub fn matches(&self, envelope: &Envelope) -> bool {
+        let headers = envelope.other_headers();
+        let mut conditions: Vec<bool> = self
+            .header
+            .iter()
+            .map(|(name, value)| {
+                headers
+                    .get(name.as_str())
+                    .map(|v| contains(v, value))
+                    .unwrap_or(false)
+            })
+            .collect();
+        if let Some(ref from) = self.from {
+            conditions.push(contains(&envelope.field_from_to_string(), from));
+        }
+        if let Some(ref to) = self.to {
+            conditions.push(
+                contains(&envelope.field_to_to_string(), to)
+                    || contains(&envelope.field_cc_to_string(), to),
+            );
+        }
+        if let Some(ref subject) = self.subject {
+            conditions.push(contains(&envelope.subject(), subject));
+        }
+        if let Some(ref list_id) = self.list_id {
+            conditions.push(
+                headers
+                    .get("List-Id")
+                    .map(|v| contains(v, list_id))
+                    .unwrap_or(false),
+            );
+        }
+        if let Some(size_over) = self.size_over {
+            conditions.push(envelope.size.map(|s| s > size_over).unwrap_or(false));
+        }
+        if let Some(size_under) = self.size_under {
+            conditions.push(envelope.size.map(|s| s < size_under).unwrap_or(false));
+        }
+        if let Some(spam_score_over) = self.spam_score_over {
+            conditions.push(
+                spam_score(envelope)
+                    .map(|s| s > spam_score_over)
+                    .unwrap_or(false),
+            );
+        }
+        if conditions.is_empty() {
+            return false;
+        }
+        if self.match_any {
+            conditions.into_iter().any(|c| c)
+        } else {
+            conditions.into_iter().all(|c| c)
+        }
+    }
+}
+
+#[test]
+fn test_filter_rule_matches() {
+    let envelope = Envelope::from_bytes(
+        b"From: John Doe <jdoe@example.com>\r\nTo: list@lists.example.com\r\nSubject: [rust] release\r\nList-Id: Rust users <rust.lists.example.com>\r\nX-Spam-Status: No, score=2.5 required=5.0\r\nMessage-ID: <1@example.com>\r\n\r\nbody\r\n",
+        None,
+    )
+    .unwrap();
+    let mut rule = FilterRule {
+        list_id: Some("RUST.lists".to_string()),
+        from: Some("jdoe@".to_string()),
+        ..FilterRule::default()
+    };
+    assert!(rule.matches(&envelope));
+    rule.spam_score_over = Some(5.0);
+    assert!(!rule.matches(&envelope));
+    rule.match_any = true;
+    assert!(rule.matches(&envelope));
+    assert!(!FilterRule::default().matches(&envelope));
+}
@@ -0,0 +1,179 @@
+/*
+ * meli - configuration module.
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Shell commands executed on lifecycle events.
+
+use super::default_vals::none;
+use super::DotAddressable;
+use crate::types::{NotificationType, ThreadEvent, UIEvent};
+use crossbeam::Sender;
+use melib::error::ErrorKind;
+use melib::{MeliError, Result};
+use std::io::Write;
+use std::process::{Command, Stdio};
+
+/// Settings for hooks, shell commands executed on lifecycle events. Their standard output, if
+/// any, and their failures are shown as notifications.
+#[derive(Debug, Default, Serialize, Deserialize, Clone)]
+#[serde(deny_unknown_fields)]
+pub struct HooksSettings {
+    /// Executed before a message is sent, with the message on its standard input. A non-zero exit
+    /// status cancels sending, and non-empty standard output replaces the message.
+    /// Default: None
+    #[serde(default = "none", alias = "pre-send")]
+    pub pre_send: Option<String>,
+    /// Executed after a message has been sent, with the message on its standard input.
+    /// Default: None
+    #[serde(default = "none", alias = "post-send")]
+    pub post_send: Option<String>,
+    /// Executed when new mail arrives, with the same environment variables as
+    /// `notifications.new_mail_script`.
+    /// Default: None
+    #[serde(default = "none", alias = "new-mail")]
+    pub new_mail: Option<String>,
+    /// Executed when meli starts.
+    /// Default: None
+    #[serde(default = "none")]
+    pub startup: Option<String>,
+    /// Executed when meli exits. meli waits for it to finish.
+    /// Default: None
+    #[serde(default = "none")]
+    pub shutdown: Option<String>,
+    /// Executed every minute to check whether the machine runs on battery, which a zero exit
+    /// status means. Watchers of accounts whose mailboxes all have low `watch_urgency` are
+    /// paused meanwhile.
+    /// Default: None
+    #[serde(default = "none", alias = "on-battery")]
+    pub on_battery: Option<String>,
+}
+
+impl DotAddressable for HooksSettings {
+    fn lookup(&self, parent_field: &str, path: &[&str]) -> Result<String> {
+        match path.first() {
+            Some(field) => {
+                let tail = &path[1..];
+                match *field {
+                    "pre_send" => self.pre_send.lookup(field, tail),
+                    "post_send" => self.post_send.lookup(field, tail),
+                    "new_mail" => self.new_mail.lookup(field, tail),
+                    "startup" => self.startup.lookup(field, tail),
+                    "shutdown" => self.shutdown.lookup(field, tail),
+                    "on_battery" => self.on_battery.lookup(field, tail),
+                    other => Err(MeliError::new(format!(
+                        "{} has no field named {}",
+                        parent_field, other
+                    ))),
+                }
+            }
+            None => Ok(toml::to_string(self).map_err(|err| err.to_string())?),
+        }
+    }
+}
+
+/// Run hook `name` and wait for it to finish, returning its standard output.
+pub fn run_hook(
+    name: &str,
+    command: &str,
+    input: Option<&[u8]>,
+    env: Vec<(&'static str, String)>,
+) -> Result<String> {
+    let mut child = Command::new("sh")
+        .args(&["-c", command])
+        .envs(env)
+        .stdin(if input.is_some() {
+            Stdio::piped()
+        } else {
+            Stdio::null()
+        })
+        .stdout(Stdio::piped())
+        .stderr(Stdio::piped())
+        .spawn()
+        .map_err(|err| {
+            MeliError::new(format!("Could not run {} hook: {}", name, err))
+                .set_kind(ErrorKind::External)
+        })?;
+    /* Write the input from another thread, so that hooks that filter it don't block on a full
+     * stdout pipe. */
+    let writer = input.map(|input| {
+        let mut stdin = child.stdin.take().unwrap();
+        let input = input.to_vec();
+        std::thread::spawn(move || {
+            /* The hook may exit without reading its input. */
+            let _ = stdin.write_all(&input);
+        })
+    });
+    let output = child.wait_with_output()?;
+    if let Some(writer) = writer {
+        let _ = writer.join();
+    }
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr);
+        return Err(MeliError::new(format!(
+            "{} hook exited with {}{}{}",
+            name,
+            output.status,
+            if stderr.trim().is_empty() { "" } else { ": " },
+            stderr.trim()
+        ))
+        .set_kind(ErrorKind::External));
+    }
+    Ok(String::from_utf8_lossy(&output.stdout).into_owned())
+}
+
+/// Run hook `name` in a new thread and report its output or failure as a notification.
+pub fn spawn_hook(
+    name: &'static str,
+    command: String,
+    input: Option<Vec<u8>>,
+    env: Vec<(&'static str, String)>,
+    sender: Sender<ThreadEvent>,
+) {
+    std::thread::spawn(move || {
+        let notification = match run_hook(name, &command, input.as_deref(), env) {
+            Ok(output) if output.trim().is_empty() => return,
+            Ok(output) => UIEvent::Notification(
+                Some(format!("{} hook", name)),
+                output.trim().to_string(),
+                Some(NotificationType::Info),
+            ),
+            Err(err) => UIEvent::Notification(
+                Some(format!("{} hook failed", name)),
+                err.to_string(),
+                Some(NotificationType::Error(err.kind)),
+            ),
+        };
+        let _ = sender.send(ThreadEvent::UIEvent(notification));
+    });
+}
+
+#[test]
+fn test_run_hook() {
+    assert_eq!(
+        run_hook("test", "tr a-z A-Z", Some(b"subject"), vec![]).unwrap(),
+        "SUBJECT"
+    );
+    assert!(
+        run_hook("test", "echo rejected >&2; exit 1", Some(b""), vec![])
+            .unwrap_err()
+            .to_string()
+            .contains("rejected")
+    );
+}
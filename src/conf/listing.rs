@@ -21,7 +21,9 @@
 
 use super::{default_vals::*, DotAddressable, IndexStyle};
 use melib::search::Query;
+use melib::thread::{SortField, SortOrder};
 use melib::{MeliError, Result};
+use std::str::FromStr;
 
 /// Settings for mail listings
 ///
@@ -67,7 +69,8 @@ pub struct ListingSettings {
     #[serde(default = "none", alias = "datetime-fmt")]
     pub datetime_fmt: Option<String>,
 
-    /// Show recent dates as `X {minutes,hours,days} ago`, up to 7 days.
+    /// Show dates of the past week relative to now, as `5m ago`, `2h ago`, `Yesterday` or the
+    /// weekday.
     /// Default: true
     #[serde(default = "true_val", alias = "recent-dates")]
     pub recent_dates: bool,
@@ -99,12 +102,153 @@ pub struct ListingSettings {
     ///Default: ' '
     #[serde(default = "default_divider")]
     pub sidebar_divider: char,
+
+    /// Mutt-like format of rows in plain and compact listings, replacing the default columns.
+    /// Default: None
+    #[serde(default = "none", alias = "index-format")]
+    pub index_format: Option<String>,
+
+    /// Where an opened entry is shown: `full-screen`, to the `right` of the list or at the
+    /// `bottom` of it. The view takes `pager.pager_ratio` percent of the area in split layouts.
+    /// Default: None, `bottom` in the threaded style and `full-screen` in the others.
+    #[serde(default = "none", alias = "view-layout")]
+    pub view_layout: Option<ViewLayout>,
+
+    /// Format of the message counts shown after each mailbox in the sidebar, where `%u` is the
+    /// number of unread messages and `%t` the total. Counts are hidden if both are zero.
+    /// Default: None, the number of unread messages if there are any.
+    #[serde(default = "none", alias = "sidebar-count-format")]
+    pub sidebar_count_format: Option<String>,
+
+    /// Glob patterns of mailbox paths to hide from the sidebar. Hidden mailboxes stay subscribed.
+    /// Default: []
+    #[serde(default, alias = "sidebar-hide-mailboxes")]
+    pub sidebar_hide_mailboxes: Vec<String>,
+
+    /// Order of the entries of a mailbox when it is opened, eg. `"date desc"` or `"subject asc"`.
+    /// Default: "date desc"
+    #[serde(default)]
+    pub sort: ListingSort,
+
+    /// Shown in the flags column of envelopes sent through a mailing list. Set to "" to hide it.
+    /// Default: None, "📨"
+    #[serde(default = "none", alias = "mailing-list-indicator")]
+    pub mailing_list_indicator: Option<String>,
+
+    /// Open threads as a single conversation, showing all their messages one after the other in
+    /// one scrollable view with quoted text collapsed, instead of a tree of messages.
+    /// Default: false
+    #[serde(default = "false_val", alias = "conversation-view")]
+    pub conversation_view: bool,
+
+    /// Collapse threads with more than this many messages in the threaded listing, unless they
+    /// have been expanded. Collapsed threads show only their first message.
+    /// Default: None
+    #[serde(default = "none", alias = "auto-collapse-threads")]
+    pub auto_collapse_threads: Option<usize>,
+
+    /// Shown in the flags column of envelopes with a duplicate, another message of the account
+    /// with the same Message-ID such as a copy in a mailing list mailbox. Set to "" to hide it.
+    /// Default: None, "⧉"
+    #[serde(default = "none", alias = "duplicate-indicator")]
+    pub duplicate_indicator: Option<String>,
+
+    /// Hide envelopes that have a duplicate in a mailbox which doesn't hide duplicates, and show
+    /// duplicates within the same mailbox only once, as their oldest copy. Meant to be set for
+    /// mailboxes such as Gmail's All Mail.
+    /// Default: false
+    #[serde(default = "false_val", alias = "hide-duplicates")]
+    pub hide_duplicates: bool,
+
+    /// Also apply seen/unseen, tag and delete actions, and marking an opened message as seen, to
+    /// its duplicates in other mailboxes.
+    /// Default: false
+    #[serde(default = "false_val", alias = "act-on-duplicates")]
+    pub act_on_duplicates: bool,
 }
 
 const fn default_divider() -> char {
     ' '
 }
 
+/// Composition of a mail listing with the view of its opened entry.
+#[derive(Debug, Deserialize, Clone, Copy, PartialEq, Serialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum ViewLayout {
+    FullScreen,
+    Right,
+    Bottom,
+}
+
+/// Field and order a listing is sorted by, written as
+/// `"<date|subject|sender|size|attachments|unread|activity> <asc|desc>"`.
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct ListingSort {
+    pub field: SortField,
+    pub order: SortOrder,
+}
+
+impl FromStr for ListingSort {
+    type Err = MeliError;
+    fn from_str(s: &str) -> Result<Self> {
+        let mut words = s.split_whitespace();
+        let err = || {
+            MeliError::new(format!(
+                "invalid sort order `{}`, expected eg. \"date desc\"",
+                s
+            ))
+        };
+        let field = words
+            .next()
+            .and_then(|w| SortField::from_str(w).ok())
+            .ok_or_else(err)?;
+        let order = match words.next() {
+            Some(w) => SortOrder::from_str(w).map_err(|_| err())?,
+            None => SortOrder::default(),
+        };
+        if words.next().is_some() {
+            return Err(err());
+        }
+        Ok(ListingSort { field, order })
+    }
+}
+
+impl<'de> serde::Deserialize<'de> for ListingSort {
+    fn deserialize<D>(deserializer: D) -> std::result::Result<Self, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <String>::deserialize(deserializer)?;
+        ListingSort::from_str(&s).map_err(serde::de::Error::custom)
+    }
+}
+
+impl serde::Serialize for ListingSort {
+    fn serialize<S>(&self, serializer: S) -> std::result::Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&format!(
+            "{} {}",
+            match self.field {
+                SortField::Date => "date",
+                SortField::Subject => "subject",
+                SortField::Sender => "sender",
+                SortField::Size => "size",
+                SortField::Attachments => "attachments",
+                SortField::Unread => "unread",
+                SortField::Activity => "activity",
+            },
+            match self.order {
+                SortOrder::Asc => "asc",
+                SortOrder::Desc => "desc",
+            }
+        ))
+    }
+}
+
+impl DotAddressable for ListingSort {}
+
 impl Default for ListingSettings {
     fn default() -> Self {
         Self {
@@ -119,6 +263,17 @@ impl Default for ListingSettings {
             sidebar_mailbox_tree_has_sibling_leaf: None,
             sidebar_mailbox_tree_no_sibling_leaf: None,
             sidebar_divider: default_divider(),
+            index_format: None,
+            view_layout: None,
+            sidebar_count_format: None,
+            sidebar_hide_mailboxes: vec![],
+            sort: ListingSort::default(),
+            mailing_list_indicator: None,
+            conversation_view: false,
+            auto_collapse_threads: None,
+            duplicate_indicator: None,
+            hide_duplicates: false,
+            act_on_duplicates: false,
         }
     }
 }
@@ -148,6 +303,17 @@ impl DotAddressable for ListingSettings {
                         .sidebar_mailbox_tree_no_sibling_leaf
                         .lookup(field, tail),
                     "sidebar_divider" => self.sidebar_divider.lookup(field, tail),
+                    "index_format" => self.index_format.lookup(field, tail),
+                    "view_layout" => self.view_layout.lookup(field, tail),
+                    "sidebar_count_format" => self.sidebar_count_format.lookup(field, tail),
+                    "sidebar_hide_mailboxes" => self.sidebar_hide_mailboxes.lookup(field, tail),
+                    "sort" => self.sort.lookup(field, tail),
+                    "mailing_list_indicator" => self.mailing_list_indicator.lookup(field, tail),
+                    "conversation_view" => self.conversation_view.lookup(field, tail),
+                    "auto_collapse_threads" => self.auto_collapse_threads.lookup(field, tail),
+                    "duplicate_indicator" => self.duplicate_indicator.lookup(field, tail),
+                    "hide_duplicates" => self.hide_duplicates.lookup(field, tail),
+                    "act_on_duplicates" => self.act_on_duplicates.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
@@ -158,3 +324,26 @@ impl DotAddressable for ListingSettings {
         }
     }
 }
+
+#[test]
+fn test_listing_sort() {
+    assert_eq!(
+        ListingSort::from_str("subject asc").unwrap(),
+        ListingSort {
+            field: SortField::Subject,
+            order: SortOrder::Asc
+        }
+    );
+    assert_eq!(
+        ListingSort::from_str("date").unwrap(),
+        ListingSort::default()
+    );
+    assert_eq!(
+        ListingSort::from_str("unread desc").unwrap(),
+        ListingSort {
+            field: SortField::Unread,
+            order: SortOrder::Desc
+        }
+    );
+    assert!(ListingSort::from_str("colour desc").is_err());
+}
@@ -19,7 +19,7 @@
  * along with meli. If not, see <http://www.gnu.org/licenses/>.
  */
 
-use super::default_vals::{internal_value_false, none, true_val};
+use super::default_vals::{false_val, internal_value_false, none, true_val};
 use super::DotAddressable;
 use melib::{MeliError, Result, ToggleFlag};
 
@@ -44,6 +44,16 @@ pub struct NotificationsSettings {
     pub play_sound: ToggleFlag,
     #[serde(default = "none", alias = "sound-file")]
     pub sound_file: Option<String>,
+    /// A shell command to execute when new mail arrives, independently of `enable`. The
+    /// envelope's fields are passed in the environment variables `MELI_ACCOUNT`, `MELI_MAILBOX`,
+    /// `MELI_FROM`, `MELI_TO`, `MELI_SUBJECT`, `MELI_DATE` and `MELI_MESSAGE_ID`.
+    /// Default: None
+    #[serde(default = "none", alias = "new-mail-script")]
+    pub new_mail_script: Option<String>,
+    /// Ring the terminal bell when new mail arrives, independently of `enable`.
+    /// Default: false
+    #[serde(default = "false_val")]
+    pub bell: bool,
 }
 
 impl Default for NotificationsSettings {
@@ -54,6 +64,8 @@ impl Default for NotificationsSettings {
             xbiff_file_path: None,
             play_sound: ToggleFlag::InternalVal(false),
             sound_file: None,
+            new_mail_script: None,
+            bell: false,
         }
     }
 }
@@ -68,6 +80,8 @@ impl DotAddressable for NotificationsSettings {
                     "xbiff_file_path" => self.xbiff_file_path.lookup(field, tail),
                     "play_sound" => self.play_sound.lookup(field, tail),
                     "sound_file" => self.sound_file.lookup(field, tail),
+                    "new_mail_script" => self.new_mail_script.lookup(field, tail),
+                    "bell" => self.bell.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
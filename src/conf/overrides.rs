@@ -76,6 +76,55 @@ pub struct PagerSettingsOverride {
     #[serde(alias = "auto-choose-multipart-alternative")]
     #[serde(default)]
     pub auto_choose_multipart_alternative: Option<ToggleFlag>,
+    #[doc = " Headers to show in the envelope view, in this order. Missing or empty headers are skipped."]
+    #[doc = " Default: [\"Date\", \"From\", \"To\", \"Cc\", \"Subject\", \"Message-ID\"]"]
+    #[serde(alias = "show-headers")]
+    #[serde(default)]
+    pub show_headers: Option<Vec<String>>,
+    #[doc = " Show the output of commands the message is piped to (with `pipe-raw`, `pipe-body` and"]
+    #[doc = " `pipe-attachment`) in a pager."]
+    #[doc = " Default: true"]
+    #[serde(alias = "show-pipe-output")]
+    #[serde(default)]
+    pub show_pipe_output: Option<bool>,
+    #[doc = " Repository directory where `apply-patch` runs `git am` or `git apply`."]
+    #[doc = " Default: None"]
+    #[serde(deserialize_with = "non_empty_string", alias = "patch-repository")]
+    #[serde(default)]
+    pub patch_repository: Option<Option<String>>,
+    #[doc = " Directory where `save-attachments` and `save-thread-attachments` save attachments when no"]
+    #[doc = " path is given."]
+    #[doc = " Default: None"]
+    #[serde(deserialize_with = "non_empty_string", alias = "attachment-dir")]
+    #[serde(default)]
+    pub attachment_dir: Option<Option<String>>,
+    #[doc = " Mark a message as read when it is opened."]
+    #[doc = " Default: true"]
+    #[serde(alias = "auto-mark-read")]
+    #[serde(default)]
+    pub auto_mark_read: Option<bool>,
+    #[doc = " Seconds a message has to stay open before it is marked as read. 0 marks it as read at once."]
+    #[doc = " Default: 0"]
+    #[serde(alias = "auto-mark-read-delay")]
+    #[serde(default)]
+    pub auto_mark_read_delay: Option<u64>,
+    #[doc = " Filters that turn parts of a MIME type, eg. `\"application/pdf\"` or `\"text/*\"`, into text"]
+    #[doc = " for the pager. Each filter is a list of shell commands, the output of one piped to the next,"]
+    #[doc = " that may also be the built-in `internal:html-to-text` and `internal:calendar` filters."]
+    #[doc = " Default: empty"]
+    #[serde(alias = "display-filters")]
+    #[serde(default)]
+    pub display_filters: Option<IndexMap<String, Vec<String>>>,
+    #[doc = " Show the Date header in local time, formatted with `datetime_fmt`, instead of as sent."]
+    #[doc = " Default: true"]
+    #[serde(alias = "local-dates")]
+    #[serde(default)]
+    pub local_dates: Option<bool>,
+    #[doc = " Datetime formatting of the Date header passed verbatim to strftime(3)."]
+    #[doc = " Default: %a, %d %b %Y %T %z"]
+    #[serde(alias = "datetime-fmt")]
+    #[serde(default)]
+    pub datetime_fmt: Option<Option<String>>,
 }
 impl Default for PagerSettingsOverride {
     fn default() -> Self {
@@ -90,6 +139,15 @@ impl Default for PagerSettingsOverride {
             split_long_lines: None,
             minimum_width: None,
             auto_choose_multipart_alternative: None,
+            show_headers: None,
+            show_pipe_output: None,
+            patch_repository: None,
+            attachment_dir: None,
+            auto_mark_read: None,
+            auto_mark_read_delay: None,
+            display_filters: None,
+            local_dates: None,
+            datetime_fmt: None,
         }
     }
 }
@@ -111,7 +169,8 @@ pub struct ListingSettingsOverride {
     #[serde(alias = "datetime-fmt")]
     #[serde(default)]
     pub datetime_fmt: Option<Option<String>>,
-    #[doc = " Show recent dates as `X {minutes,hours,days} ago`, up to 7 days."]
+    #[doc = " Show dates of the past week relative to now, as `5m ago`, `2h ago`, `Yesterday` or the"]
+    #[doc = " weekday."]
     #[doc = " Default: true"]
     #[serde(alias = "recent-dates")]
     #[serde(default)]
@@ -138,6 +197,68 @@ pub struct ListingSettingsOverride {
     #[doc = "Default: ' '"]
     #[serde(default)]
     pub sidebar_divider: Option<char>,
+    #[doc = " Mutt-like format of rows in plain and compact listings, replacing the default columns."]
+    #[doc = " Default: None"]
+    #[serde(alias = "index-format")]
+    #[serde(default)]
+    pub index_format: Option<Option<String>>,
+    #[doc = " Where an opened entry is shown: `full-screen`, to the `right` of the list or at the"]
+    #[doc = " `bottom` of it. The view takes `pager.pager_ratio` percent of the area in split layouts."]
+    #[doc = " Default: None, `bottom` in the threaded style and `full-screen` in the others."]
+    #[serde(alias = "view-layout")]
+    #[serde(default)]
+    pub view_layout: Option<Option<ViewLayout>>,
+    #[doc = " Format of the message counts shown after each mailbox in the sidebar, where `%u` is the"]
+    #[doc = " number of unread messages and `%t` the total. Counts are hidden if both are zero."]
+    #[doc = " Default: None, the number of unread messages if there are any."]
+    #[serde(alias = "sidebar-count-format")]
+    #[serde(default)]
+    pub sidebar_count_format: Option<Option<String>>,
+    #[doc = " Glob patterns of mailbox paths to hide from the sidebar. Hidden mailboxes stay subscribed."]
+    #[doc = " Default: []"]
+    #[serde(alias = "sidebar-hide-mailboxes")]
+    #[serde(default)]
+    pub sidebar_hide_mailboxes: Option<Vec<String>>,
+    #[doc = " Order of the entries of a mailbox when it is opened, eg. `\"date desc\"` or `\"subject asc\"`."]
+    #[doc = " Default: \"date desc\""]
+    #[serde(default)]
+    pub sort: Option<ListingSort>,
+    #[doc = " Shown in the flags column of envelopes sent through a mailing list. Set to \"\" to hide it."]
+    #[doc = " Default: None, \"📨\""]
+    #[serde(alias = "mailing-list-indicator")]
+    #[serde(default)]
+    pub mailing_list_indicator: Option<Option<String>>,
+    #[doc = " Open threads as a single conversation, showing all their messages one after the other in"]
+    #[doc = " one scrollable view with quoted text collapsed, instead of a tree of messages."]
+    #[doc = " Default: false"]
+    #[serde(alias = "conversation-view")]
+    #[serde(default)]
+    pub conversation_view: Option<bool>,
+    #[doc = " Collapse threads with more than this many messages in the threaded listing, unless they"]
+    #[doc = " have been expanded. Collapsed threads show only their first message."]
+    #[doc = " Default: None"]
+    #[serde(alias = "auto-collapse-threads")]
+    #[serde(default)]
+    pub auto_collapse_threads: Option<Option<usize>>,
+    #[doc = " Shown in the flags column of envelopes with a duplicate, another message of the account"]
+    #[doc = " with the same Message-ID such as a copy in a mailing list mailbox. Set to \"\" to hide it."]
+    #[doc = " Default: None, \"⧉\""]
+    #[serde(alias = "duplicate-indicator")]
+    #[serde(default)]
+    pub duplicate_indicator: Option<Option<String>>,
+    #[doc = " Hide envelopes that have a duplicate in a mailbox which doesn't hide duplicates, and show"]
+    #[doc = " duplicates within the same mailbox only once, as their oldest copy. Meant to be set for"]
+    #[doc = " mailboxes such as Gmail's All Mail."]
+    #[doc = " Default: false"]
+    #[serde(alias = "hide-duplicates")]
+    #[serde(default)]
+    pub hide_duplicates: Option<bool>,
+    #[doc = " Also apply seen/unseen, tag and delete actions, and marking an opened message as seen, to"]
+    #[doc = " its duplicates in other mailboxes."]
+    #[doc = " Default: false"]
+    #[serde(alias = "act-on-duplicates")]
+    #[serde(default)]
+    pub act_on_duplicates: Option<bool>,
 }
 impl Default for ListingSettingsOverride {
     fn default() -> Self {
@@ -153,6 +274,17 @@ impl Default for ListingSettingsOverride {
             sidebar_mailbox_tree_has_sibling_leaf: None,
             sidebar_mailbox_tree_no_sibling_leaf: None,
             sidebar_divider: None,
+            index_format: None,
+            view_layout: None,
+            sidebar_count_format: None,
+            sidebar_hide_mailboxes: None,
+            sort: None,
+            mailing_list_indicator: None,
+            conversation_view: None,
+            auto_collapse_threads: None,
+            duplicate_indicator: None,
+            hide_duplicates: None,
+            act_on_duplicates: None,
         }
     }
 }
@@ -180,6 +312,17 @@ pub struct NotificationsSettingsOverride {
     #[serde(alias = "sound-file")]
     #[serde(default)]
     pub sound_file: Option<Option<String>>,
+    #[doc = " A shell command to execute when new mail arrives, independently of `enable`. The"]
+    #[doc = " envelope's fields are passed in the environment variables `MELI_ACCOUNT`, `MELI_MAILBOX`,"]
+    #[doc = " `MELI_FROM`, `MELI_TO`, `MELI_SUBJECT`, `MELI_DATE` and `MELI_MESSAGE_ID`."]
+    #[doc = " Default: None"]
+    #[serde(alias = "new-mail-script")]
+    #[serde(default)]
+    pub new_mail_script: Option<Option<String>>,
+    #[doc = " Ring the terminal bell when new mail arrives, independently of `enable`."]
+    #[doc = " Default: false"]
+    #[serde(default)]
+    pub bell: Option<bool>,
 }
 impl Default for NotificationsSettingsOverride {
     fn default() -> Self {
@@ -189,6 +332,8 @@ impl Default for NotificationsSettingsOverride {
             xbiff_file_path: None,
             play_sound: None,
             sound_file: None,
+            new_mail_script: None,
+            bell: None,
         }
     }
 }
@@ -216,6 +361,15 @@ pub struct ShortcutsOverride {
     pub thread_view: Option<ThreadViewShortcuts>,
     #[serde(default)]
     pub pager: Option<PagerShortcuts>,
+    #[serde(default)]
+    pub sieve: Option<SieveShortcuts>,
+    #[serde(default)]
+    pub jobs: Option<JobsShortcuts>,
+    #[serde(default)]
+    pub log: Option<LogShortcuts>,
+    #[doc = " Key sequences, such as `\"g i\"`, and the commands they execute."]
+    #[serde(default)]
+    pub sequences: Option<IndexMap<String, String>>,
 }
 impl Default for ShortcutsOverride {
     fn default() -> Self {
@@ -228,6 +382,10 @@ impl Default for ShortcutsOverride {
             envelope_view: None,
             thread_view: None,
             pager: None,
+            sieve: None,
+            jobs: None,
+            log: None,
+            sequences: None,
         }
     }
 }
@@ -280,6 +438,36 @@ pub struct ComposingSettingsOverride {
     #[doc = " Default: true"]
     #[serde(default)]
     pub attribution_use_posix_locale: Option<bool>,
+    #[doc = " Collect the From, To and Cc addresses of mail you read or send and use them in recipient"]
+    #[doc = " completion."]
+    #[doc = " Default: false"]
+    #[serde(default)]
+    pub collect_addresses: Option<bool>,
+    #[doc = " Glob patterns of addresses that are never collected, e.g. `\"*noreply*\"`."]
+    #[doc = " Default: empty"]
+    #[serde(default)]
+    pub collect_addresses_ignore: Option<Vec<String>>,
+    #[doc = " Split outgoing messages larger than this many bytes into message/partial messages"]
+    #[doc = " (RFC 2046) that are sent separately."]
+    #[doc = " Default: None"]
+    #[serde(default)]
+    pub max_message_size: Option<Option<usize>>,
+    #[doc = " Recipients of replies started with the `reply` shortcut: `ask`, `author`, `all` or"]
+    #[doc = " `list`. `ask` prompts only if the envelope is from a mailing list or has other recipients."]
+    #[doc = " Default: ask"]
+    #[serde(alias = "reply-mode")]
+    #[serde(default)]
+    pub reply_mode: Option<ReplyMode>,
+    #[doc = " Other identities of the account, such as aliases. Replies to messages delivered to one of"]
+    #[doc = " them, according to their `Delivered-To`, `To` or `Cc` headers, are sent from it. The"]
+    #[doc = " `cycle_identity` composer shortcut switches between them."]
+    #[doc = " Default: empty"]
+    #[serde(default)]
+    pub identities: Option<Vec<Identity>>,
+    #[doc = " Checks run before a draft is sent. Sending asks for confirmation for each failed check."]
+    #[serde(alias = "pre-send-checks")]
+    #[serde(default)]
+    pub pre_send_checks: Option<PreSendChecks>,
 }
 impl Default for ComposingSettingsOverride {
     fn default() -> Self {
@@ -293,6 +481,12 @@ impl Default for ComposingSettingsOverride {
             store_sent_mail: None,
             attribution_format_string: None,
             attribution_use_posix_locale: None,
+            collect_addresses: None,
+            collect_addresses_ignore: None,
+            max_message_size: None,
+            reply_mode: None,
+            identities: None,
+            pre_send_checks: None,
         }
     }
 }
@@ -340,6 +534,12 @@ pub struct PGPSettingsOverride {
     #[serde(alias = "auto-encrypt")]
     #[serde(default)]
     pub auto_encrypt: Option<bool>,
+    #[doc = " Encrypt sent e-mail when all recipients have valid public keys, unless the user chose to"]
+    #[doc = " never encrypt to one of them with the `encrypt-preference` command."]
+    #[doc = " Default: false"]
+    #[serde(alias = "opportunistic-encryption")]
+    #[serde(default)]
+    pub opportunistic_encryption: Option<bool>,
     #[doc = " Default: None"]
     #[serde(alias = "sign-key")]
     #[serde(default)]
@@ -362,6 +562,10 @@ pub struct PGPSettingsOverride {
     #[serde(alias = "remote-lookup-mechanisms")]
     #[serde(default)]
     pub remote_lookup_mechanisms: Option<melib::gpgme::LocateKey>,
+    #[doc = " Keyserver to look senders' keys up on with the `lookup-sender-key` command."]
+    #[doc = " Default: \"hkps://keys.openpgp.org\""]
+    #[serde(default)]
+    pub keyserver: Option<String>,
 }
 #[cfg(feature = "gpgme")]
 impl Default for PGPSettingsOverride {
@@ -371,11 +575,13 @@ impl Default for PGPSettingsOverride {
             auto_decrypt: None,
             auto_sign: None,
             auto_encrypt: None,
+            opportunistic_encryption: None,
             sign_key: None,
             decrypt_key: None,
             encrypt_key: None,
             allow_remote_lookup: None,
             remote_lookup_mechanisms: None,
+            keyserver: None,
         }
     }
 }
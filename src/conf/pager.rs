@@ -24,6 +24,7 @@
 use super::default_vals::*;
 use super::deserializers::*;
 use super::DotAddressable;
+use indexmap::IndexMap;
 use melib::{MeliError, Result, ToggleFlag};
 
 /// Settings for the pager function.
@@ -87,6 +88,70 @@ pub struct PagerSettings {
         alias = "auto-choose-multipart-alternative"
     )]
     pub auto_choose_multipart_alternative: ToggleFlag,
+
+    /// Headers to show in the envelope view, in this order. Missing or empty headers are skipped.
+    /// Default: ["Date", "From", "To", "Cc", "Subject", "Message-ID"]
+    #[serde(default = "show_headers_val", alias = "show-headers")]
+    pub show_headers: Vec<String>,
+
+    /// Show the output of commands the message is piped to (with `pipe-raw`, `pipe-body` and
+    /// `pipe-attachment`) in a pager.
+    /// Default: true
+    #[serde(default = "true_val", alias = "show-pipe-output")]
+    pub show_pipe_output: bool,
+
+    /// Repository directory where `apply-patch` runs `git am` or `git apply`.
+    /// Default: None
+    #[serde(
+        default = "none",
+        deserialize_with = "non_empty_string",
+        alias = "patch-repository"
+    )]
+    pub patch_repository: Option<String>,
+
+    /// Directory where `save-attachments` and `save-thread-attachments` save attachments when no
+    /// path is given.
+    /// Default: None
+    #[serde(
+        default = "none",
+        deserialize_with = "non_empty_string",
+        alias = "attachment-dir"
+    )]
+    pub attachment_dir: Option<String>,
+
+    /// Mark a message as read when it is opened.
+    /// Default: true
+    #[serde(default = "true_val", alias = "auto-mark-read")]
+    pub auto_mark_read: bool,
+
+    /// Seconds a message has to stay open before it is marked as read. 0 marks it as read at once.
+    /// Default: 0
+    #[serde(default, alias = "auto-mark-read-delay")]
+    pub auto_mark_read_delay: u64,
+
+    /// Filters that turn parts of a MIME type, eg. `"application/pdf"` or `"text/*"`, into text
+    /// for the pager. Each filter is a list of shell commands, the output of one piped to the next,
+    /// that may also be the built-in `internal:html-to-text` and `internal:calendar` filters.
+    /// Default: empty
+    #[serde(default, alias = "display-filters")]
+    pub display_filters: IndexMap<String, Vec<String>>,
+
+    /// Show the Date header in local time, formatted with `datetime_fmt`, instead of as sent.
+    /// Default: true
+    #[serde(default = "true_val", alias = "local-dates")]
+    pub local_dates: bool,
+
+    /// Datetime formatting of the Date header passed verbatim to strftime(3).
+    /// Default: %a, %d %b %Y %T %z
+    #[serde(default = "none", alias = "datetime-fmt")]
+    pub datetime_fmt: Option<String>,
+}
+
+fn show_headers_val() -> Vec<String> {
+    ["Date", "From", "To", "Cc", "Subject", "Message-ID"]
+        .iter()
+        .map(|h| h.to_string())
+        .collect()
 }
 
 impl Default for PagerSettings {
@@ -102,6 +167,15 @@ impl Default for PagerSettings {
             split_long_lines: true,
             minimum_width: 80,
             auto_choose_multipart_alternative: ToggleFlag::InternalVal(true),
+            show_headers: show_headers_val(),
+            show_pipe_output: true,
+            patch_repository: None,
+            attachment_dir: None,
+            auto_mark_read: true,
+            auto_mark_read_delay: 0,
+            display_filters: IndexMap::default(),
+            local_dates: true,
+            datetime_fmt: None,
         }
     }
 }
@@ -124,6 +198,15 @@ impl DotAddressable for PagerSettings {
                     "auto_choose_multipart_alternative" => {
                         self.auto_choose_multipart_alternative.lookup(field, tail)
                     }
+                    "show_headers" => self.show_headers.lookup(field, tail),
+                    "show_pipe_output" => self.show_pipe_output.lookup(field, tail),
+                    "patch_repository" => self.patch_repository.lookup(field, tail),
+                    "attachment_dir" => self.attachment_dir.lookup(field, tail),
+                    "auto_mark_read" => self.auto_mark_read.lookup(field, tail),
+                    "auto_mark_read_delay" => self.auto_mark_read_delay.lookup(field, tail),
+                    "display_filters" => self.display_filters.lookup(field, tail),
+                    "local_dates" => self.local_dates.lookup(field, tail),
+                    "datetime_fmt" => self.datetime_fmt.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
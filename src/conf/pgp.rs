@@ -49,6 +49,12 @@ pub struct PGPSettings {
     #[serde(default = "false_val", alias = "auto-encrypt")]
     pub auto_encrypt: bool,
 
+    /// Encrypt sent e-mail when all recipients have valid public keys, unless the user chose to
+    /// never encrypt to one of them with the `encrypt-preference` command.
+    /// Default: false
+    #[serde(default = "false_val", alias = "opportunistic-encryption")]
+    pub opportunistic_encryption: bool,
+
     // https://tools.ietf.org/html/rfc4880#section-12.2
     /// Default: None
     #[serde(default = "none", alias = "sign-key")]
@@ -74,6 +80,11 @@ pub struct PGPSettings {
         alias = "remote-lookup-mechanisms"
     )]
     pub remote_lookup_mechanisms: melib::gpgme::LocateKey,
+
+    /// Keyserver to look senders' keys up on with the `lookup-sender-key` command.
+    /// Default: "hkps://keys.openpgp.org"
+    #[serde(default = "default_keyserver")]
+    pub keyserver: String,
 }
 
 #[cfg(feature = "gpgme")]
@@ -81,6 +92,11 @@ fn default_lookup_mechanism() -> melib::gpgme::LocateKey {
     melib::gpgme::LocateKey::LOCAL | melib::gpgme::LocateKey::WKD
 }
 
+#[cfg(feature = "gpgme")]
+fn default_keyserver() -> String {
+    "hkps://keys.openpgp.org".to_string()
+}
+
 #[cfg(feature = "gpgme")]
 impl Default for PGPSettings {
     fn default() -> Self {
@@ -89,15 +105,26 @@ impl Default for PGPSettings {
             auto_decrypt: true,
             auto_sign: false,
             auto_encrypt: false,
+            opportunistic_encryption: false,
             sign_key: None,
             decrypt_key: None,
             encrypt_key: None,
             allow_remote_lookup: internal_value_false::<ToggleFlag>(),
             remote_lookup_mechanisms: default_lookup_mechanism(),
+            keyserver: default_keyserver(),
         }
     }
 }
 
+/// Whether to encrypt messages to a recipient regardless of `opportunistic_encryption`, set with
+/// the `encrypt-preference` command.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Deserialize, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum EncryptPreference {
+    Always,
+    Never,
+}
+
 #[cfg(not(feature = "gpgme"))]
 #[derive(Debug, Default, Deserialize, Clone, Serialize)]
 #[serde(deny_unknown_fields)]
@@ -53,6 +53,15 @@ pub struct Shortcuts {
     pub thread_view: ThreadViewShortcuts,
     #[serde(default)]
     pub pager: PagerShortcuts,
+    #[serde(default)]
+    pub sieve: SieveShortcuts,
+    #[serde(default)]
+    pub jobs: JobsShortcuts,
+    #[serde(default)]
+    pub log: LogShortcuts,
+    /// Key sequences, such as `"g i"`, and the commands they execute.
+    #[serde(default)]
+    pub sequences: IndexMap<String, String>,
 }
 
 impl Default for Shortcuts {
@@ -66,6 +75,10 @@ impl Default for Shortcuts {
             envelope_view: EnvelopeViewShortcuts::default(),
             thread_view: ThreadViewShortcuts::default(),
             pager: PagerShortcuts::default(),
+            sieve: SieveShortcuts::default(),
+            jobs: JobsShortcuts::default(),
+            log: LogShortcuts::default(),
+            sequences: IndexMap::new(),
         }
     }
 }
@@ -86,6 +99,10 @@ impl DotAddressable for Shortcuts {
                     "envelope_view" | "envelope-view" => self.envelope_view.lookup(field, tail),
                     "thread_view" | "thread-view" => self.thread_view.lookup(field, tail),
                     "pager" => self.pager.lookup(field, tail),
+                    "sieve" => self.sieve.lookup(field, tail),
+                    "jobs" => self.jobs.lookup(field, tail),
+                    "log" => self.log.lookup(field, tail),
+                    "sequences" => self.sequences.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
@@ -97,6 +114,107 @@ impl DotAddressable for Shortcuts {
     }
 }
 
+/// General shortcuts that are handled before any other, and can't be bound in other sections.
+const GLOBAL_SHORTCUTS: &[&str] = &[
+    "toggle_help",
+    "enter_command_mode",
+    "quit",
+    "go_to_tab",
+    "next_tab",
+];
+
+/// Parse a key sequence such as `"g i"`.
+pub fn parse_key_sequence(sequence: &str) -> Result<Vec<Key>> {
+    use serde::de::{value::Error, IntoDeserializer};
+    use serde::Deserialize;
+    let keys = sequence
+        .split_whitespace()
+        .map(|key| {
+            Key::deserialize(key.into_deserializer()).map_err(|err: Error| {
+                MeliError::new(format!("Invalid key sequence `{}`: {}", sequence, err))
+            })
+        })
+        .collect::<Result<Vec<Key>>>()?;
+    if keys.is_empty() {
+        return Err(MeliError::new("Key sequences can't be empty."));
+    }
+    Ok(keys)
+}
+
+impl Shortcuts {
+    fn sections(&self) -> Vec<(&'static str, IndexMap<&'static str, Key>)> {
+        vec![
+            ("general", self.general.key_values()),
+            ("listing", self.listing.key_values()),
+            ("composing", self.composing.key_values()),
+            ("compact-listing", self.compact_listing.key_values()),
+            ("contact-list", self.contact_list.key_values()),
+            ("envelope-view", self.envelope_view.key_values()),
+            ("thread-view", self.thread_view.key_values()),
+            ("pager", self.pager.key_values()),
+            ("sieve", self.sieve.key_values()),
+            ("jobs", self.jobs.key_values()),
+            ("log", self.log.key_values()),
+        ]
+    }
+
+    /// Check for keys bound to more than one shortcut of a section, global shortcuts bound in
+    /// other sections and key sequences that shadow shortcuts or each other.
+    pub fn validate(&self) -> Result<()> {
+        let sections = self.sections();
+        let mut conflicts = vec![];
+        for (section, keys) in sections.iter() {
+            for (i, (name, key)) in keys.iter().enumerate() {
+                for (other, _) in keys.iter().skip(i + 1).filter(|(_, k)| *k == key) {
+                    conflicts.push(format!(
+                        "{0}.{1} and {0}.{2} are both bound to `{3}`",
+                        section, name, other, key
+                    ));
+                }
+            }
+        }
+        for name in GLOBAL_SHORTCUTS {
+            let key = &sections[0].1[name];
+            for (section, keys) in sections.iter().skip(1) {
+                for (other, _) in keys.iter().filter(|(_, k)| *k == key) {
+                    conflicts.push(format!(
+                        "general.{} and {}.{} are both bound to `{}`",
+                        name, section, other, key
+                    ));
+                }
+            }
+        }
+        let sequences = self
+            .sequences
+            .keys()
+            .map(|s| Ok((s, parse_key_sequence(s)?)))
+            .collect::<Result<Vec<(&String, Vec<Key>)>>>()?;
+        for (i, (sequence, keys)) in sequences.iter().enumerate() {
+            for (section, section_keys) in sections.iter() {
+                for (name, _) in section_keys.iter().filter(|(_, k)| **k == keys[0]) {
+                    conflicts.push(format!(
+                        "sequence `{}` shadows {}.{}, which is bound to `{}`",
+                        sequence, section, name, keys[0]
+                    ));
+                }
+            }
+            for (other, other_keys) in sequences.iter().skip(i + 1) {
+                if keys.starts_with(other_keys) || other_keys.starts_with(keys) {
+                    conflicts.push(format!("sequences `{}` and `{}` overlap", sequence, other));
+                }
+            }
+        }
+        if conflicts.is_empty() {
+            Ok(())
+        } else {
+            Err(MeliError::new(format!(
+                "Conflicting shortcuts:\n{}",
+                conflicts.join("\n")
+            )))
+        }
+    }
+}
+
 /// Create a struct holding all of a Component's shortcuts.
 #[macro_export]
 macro_rules! shortcut_key_values {
@@ -180,11 +298,19 @@ shortcut_key_values! { "listing",
         search |> "Search within list of e-mails." |> Key::Char('/'),
         refresh |> "Manually request a mailbox refresh." |> Key::F(5),
         set_seen |> "Set thread as seen." |> Key::Char('n'),
+        archive |> "Move thread to the Archive mailbox." |> Key::Char('A'),
         union_modifier |> "Union modifier." |> Key::Ctrl('u'),
         diff_modifier |> "Difference modifier." |> Key::Ctrl('d'),
         intersection_modifier |> "Intersection modifier." |> Key::Ctrl('i'),
         select_entry |> "Select thread entry." |> Key::Char('v'),
-        toggle_menu_visibility |> "Toggle visibility of side menu in mail list." |> Key::Char('`')
+        visual_mode |> "Select the entries the cursor moves over, until pressed again or Esc." |> Key::Char('V'),
+        toggle_menu_visibility |> "Toggle visibility of side menu in mail list." |> Key::Char('`'),
+        toggle_account_collapse |> "Fold or unfold the account's mailboxes in the side menu." |> Key::Char('z'),
+        toggle_thread_collapse |> "Collapse or expand the thread under the cursor in the threaded listing." |> Key::Char('Z'),
+        collapse_all_threads |> "Collapse all threads in the threaded listing." |> Key::Alt('c'),
+        expand_all_threads |> "Expand all threads in the threaded listing." |> Key::Alt('e'),
+        increase_view_ratio |> "Enlarge the view of the opened entry in split layouts." |> Key::Char('+'),
+        decrease_view_ratio |> "Shrink the view of the opened entry in split layouts." |> Key::Char('-')
     }
 }
 
@@ -195,6 +321,7 @@ shortcut_key_values! { "contact-list",
         scroll_down |> "Scroll down list." |> Key::Down,
         create_contact |> "Create new contact." |> Key::Char('c'),
         edit_contact |> "Edit contact under cursor." |> Key::Char('e'),
+        delete_contact |> "Delete contact under cursor." |> Key::Char('d'),
         mail_contact |> "Mail contact under cursor." |> Key::Char('m'),
         next_account |> "Go to next account." |> Key::Char('h'),
         prev_account |> "Go to previous account." |> Key::Char('l'),
@@ -208,7 +335,46 @@ shortcut_key_values! { "pager",
         page_down |> "Go to next pager page" |>  Key::PageDown,
         page_up |> "Go to previous pager page" |>  Key::PageUp,
         scroll_down |> "Scroll down pager." |> Key::Char('j'),
-        scroll_up |> "Scroll up pager." |> Key::Char('k')
+        scroll_up |> "Scroll up pager." |> Key::Char('k'),
+        search_next |> "Go to next search match." |> Key::Char('n'),
+        search_prev |> "Go to previous search match." |> Key::Char('N')
+    }
+}
+
+shortcut_key_values! { "sieve",
+    /// Shortcut listing for the Sieve scripts view
+    pub struct SieveShortcuts {
+        open_scripts |> "Open the account's Sieve scripts from its status page." |> Key::Char('S'),
+        scroll_up |> "Scroll up list." |> Key::Up,
+        scroll_down |> "Scroll down list." |> Key::Down,
+        edit_script |> "Edit script under cursor." |> Key::Char('e'),
+        new_script |> "Create new script." |> Key::Char('n'),
+        activate_script |> "Activate script under cursor." |> Key::Char('a'),
+        deactivate_scripts |> "Deactivate all scripts." |> Key::Char('A'),
+        check_script |> "Check script under cursor for errors." |> Key::Char('c'),
+        delete_script |> "Delete script under cursor." |> Key::Char('d'),
+        refresh |> "Reload scripts from the server." |> Key::Char('r')
+    }
+}
+
+shortcut_key_values! { "jobs",
+    /// Shortcut listing for the jobs view
+    pub struct JobsShortcuts {
+        scroll_up |> "Scroll up list." |> Key::Up,
+        scroll_down |> "Scroll down list." |> Key::Down,
+        cancel_job |> "Cancel job under cursor." |> Key::Char('c')
+    }
+}
+
+shortcut_key_values! { "log",
+    /// Shortcut listing for the log view
+    pub struct LogShortcuts {
+        scroll_up |> "Scroll up list." |> Key::Up,
+        scroll_down |> "Scroll down list." |> Key::Down,
+        page_up |> "Go to previous page." |> Key::PageUp,
+        page_down |> "Go to next page." |> Key::PageDown,
+        go_to_end |> "Go to the newest record and follow new ones." |> Key::End,
+        cycle_level |> "Cycle the least severe level shown." |> Key::Char('l')
     }
 }
 
@@ -230,23 +396,29 @@ shortcut_key_values! { "composing",
     pub struct ComposingShortcuts {
         edit_mail |> "Edit mail." |> Key::Char('e'),
         send_mail |> "Deliver draft to mailer" |> Key::Char('s'),
+        cycle_identity |> "Switch the From header to the account's next identity." |> Key::Char('f'),
         scroll_up |> "Change field focus." |> Key::Up,
-        scroll_down |> "Change field focus." |> Key::Down
+        scroll_down |> "Change field focus." |> Key::Down,
+        scroll_embed |> "Browse the scrollback of the embedded editor." |> Key::Alt('s')
     }
 }
 
 shortcut_key_values! { "envelope-view",
     pub struct EnvelopeViewShortcuts {
         add_addresses_to_contacts |> "Select addresses from envelope to add to contacts." |> Key::Char('c'),
+        archive |> "Move envelope to the Archive mailbox." |> Key::Char('A'),
         edit |> "Open envelope in composer." |> Key::Char('e'),
         go_to_url |> "Go to url of given index" |> Key::Char('g'),
         open_attachment |> "Opens selected attachment with xdg-open." |> Key::Char('a'),
         open_mailcap |> "Opens selected attachment according to its mailcap entry." |> Key::Char('m'),
+        pipe |> "Pipe envelope, or attachment of given index, to a shell command." |> Key::Char('|'),
         reply |> "Reply to envelope." |> Key::Char('R'),
         reply_to_author |> "Reply to author." |> Key::Ctrl('r'),
         reply_to_all |> "Reply to all/Reply to list/Follow up." |> Key::Ctrl('g'),
+        reply_to_list |> "Reply to mailing list only." |> Key::Char('L'),
         return_to_normal_view |> "Return to envelope if viewing raw source or attachment." |> Key::Char('r'),
         toggle_expand_headers |> "Expand extra headers (References and others)." |> Key::Char('h'),
+        toggle_raw_headers |> "Show all headers of the envelope as they are." |> Key::Char('H'),
         toggle_url_mode |> "Toggles url open mode." |> Key::Char('u'),
         view_raw_source |> "View envelope source in a pager. (toggles between raw and decoded source)" |> Key::Alt('r')
     }
@@ -261,6 +433,25 @@ shortcut_key_values! { "thread-view",
         prev_page |> "Go to previous page." |> Key::PageUp,
         reverse_thread_order |> "reverse thread order" |> Key::Ctrl('r'),
         toggle_mailview |> "toggle mail view visibility" |> Key::Char('p'),
-        toggle_threadview |> "toggle thread view visibility" |> Key::Char('t')
+        toggle_threadview |> "toggle thread view visibility" |> Key::Char('t'),
+        toggle_conversation |> "show all messages of the thread one after the other" |> Key::Char('C'),
+        toggle_quotes |> "expand or collapse quoted text in conversation view" |> Key::Char('Q')
     }
 }
+
+#[test]
+fn test_shortcuts_validate() {
+    let mut shortcuts = Shortcuts::default();
+    assert!(shortcuts.validate().is_ok());
+    shortcuts.listing.set_seen = Key::Char('m');
+    shortcuts
+        .sequences
+        .insert("g i".to_string(), "go 0".to_string());
+    shortcuts
+        .sequences
+        .insert("g".to_string(), "go 1".to_string());
+    let err = shortcuts.validate().unwrap_err().to_string();
+    assert!(err.contains("listing.new_mail and listing.set_seen are both bound to `m`"));
+    assert!(err.contains("sequence `g i` shadows envelope-view.go_to_url"));
+    assert!(err.contains("sequences `g i` and `g` overlap"));
+}
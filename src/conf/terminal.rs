@@ -24,6 +24,7 @@
 use super::deserializers::non_empty_string;
 use super::DotAddressable;
 use super::Themes;
+use crate::terminal::GraphicsProtocol;
 use melib::{MeliError, Result, ToggleFlag};
 
 /// Settings for terminal display
@@ -36,7 +37,8 @@ pub struct TerminalSettings {
     pub ascii_drawing: bool,
     pub use_color: ToggleFlag,
     /// Use mouse events. This will disable text selection, but you will be able to resize some
-    /// widgets.
+    /// widgets, click on mailboxes and messages to open and select them, and scroll lists and the
+    /// pager with the mouse wheel.
     /// Default: False
     pub use_mouse: ToggleFlag,
     /// String to show in status bar if mouse is active.
@@ -52,6 +54,27 @@ pub struct TerminalSettings {
     /// Default: 0
     #[serde(default)]
     pub progress_spinner_sequence: Option<ProgressSpinnerSequence>,
+    /// Show image attachments inline in the message view, if the terminal supports the kitty
+    /// graphics protocol or sixel. Otherwise they are opened with an external application.
+    /// Default: False
+    pub inline_images: ToggleFlag,
+    /// Graphics protocol to use for inline images, either "kitty" or "sixel". If unset, it is
+    /// guessed from the environment.
+    /// Default: None
+    pub graphics_protocol: Option<GraphicsProtocol>,
+    /// Mutt-like format of the status bar, replacing the default one. `%[THEME_KEY]` draws the
+    /// rest of the bar with the attributes of a theme key, and `%[]` resets them to `status.bar`.
+    /// Default: None
+    #[serde(deserialize_with = "non_empty_string")]
+    pub status_bar_format: Option<String>,
+    /// Turn URLs in the pager and e-mail addresses in the headers of the message view into
+    /// clickable hyperlinks, if the terminal supports OSC 8 escape sequences.
+    /// Default: False
+    pub use_hyperlinks: ToggleFlag,
+    /// Save the open mail listing tabs and their selected account, mailbox and entry on exit, and
+    /// restore them on the next start.
+    /// Default: False
+    pub restore_session: ToggleFlag,
 }
 
 impl Default for TerminalSettings {
@@ -66,6 +89,11 @@ impl Default for TerminalSettings {
             window_title: Some("meli".to_string()),
             file_picker_command: None,
             progress_spinner_sequence: None,
+            inline_images: ToggleFlag::InternalVal(false),
+            graphics_protocol: None,
+            status_bar_format: None,
+            use_hyperlinks: ToggleFlag::InternalVal(false),
+            restore_session: ToggleFlag::InternalVal(false),
         }
     }
 }
@@ -80,6 +108,14 @@ impl TerminalSettings {
             && (self.use_color.is_false() || self.use_color.is_internal()))
             || (self.use_color.is_false() && !self.use_color.is_internal()))
     }
+
+    /// The graphics protocol to draw inline images with, if inline images are enabled.
+    pub fn inline_images_protocol(&self) -> Option<GraphicsProtocol> {
+        if !self.inline_images.is_true() {
+            return None;
+        }
+        self.graphics_protocol.or_else(GraphicsProtocol::detect)
+    }
 }
 
 impl DotAddressable for TerminalSettings {
@@ -99,6 +135,11 @@ impl DotAddressable for TerminalSettings {
                     "progress_spinner_sequence" => {
                         self.progress_spinner_sequence.lookup(field, tail)
                     }
+                    "inline_images" => self.inline_images.lookup(field, tail),
+                    "graphics_protocol" => self.graphics_protocol.lookup(field, tail),
+                    "status_bar_format" => self.status_bar_format.lookup(field, tail),
+                    "use_hyperlinks" => self.use_hyperlinks.lookup(field, tail),
+                    "restore_session" => self.restore_session.lookup(field, tail),
                     other => Err(MeliError::new(format!(
                         "{} has no field named {}",
                         parent_field, other
@@ -0,0 +1,207 @@
+/*
+ * meli - conf/test_config.rs
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+//! Checks of `meli test-config` that go beyond parsing: commands and paths the configuration
+//! refers to, and optionally connections to the servers of remote accounts.
+
+use super::composing::SendMail;
+use super::{AccountConf, FileSettings};
+use melib::backends::{BackendEventConsumer, MailBackendExtensionStatus};
+use melib::ShellExpandTrait;
+use std::path::Path;
+use std::sync::Arc;
+
+/// If the program of shell command `command` is an executable path or can be found in `$PATH`.
+fn command_exists(command: &str) -> bool {
+    let program = match command.split_whitespace().next() {
+        Some(program) => program,
+        None => return true,
+    };
+    if program.contains('/') {
+        return Path::new(program).expand().is_file();
+    }
+    std::env::var_os("PATH")
+        .map(|paths| std::env::split_paths(&paths).any(|dir| dir.join(program).is_file()))
+        .unwrap_or(false)
+}
+
+/// Commands and paths referred to by the configuration that don't exist.
+pub fn check_references(s: &FileSettings) -> Vec<String> {
+    let mut commands: Vec<(String, &str)> = [
+        ("composing.editor_command", &s.composing.editor_command),
+        ("pager.filter", &s.pager.filter),
+        ("pager.html_filter", &s.pager.html_filter),
+        ("notifications.script", &s.notifications.script),
+        (
+            "notifications.new_mail_script",
+            &s.notifications.new_mail_script,
+        ),
+        (
+            "terminal.file_picker_command",
+            &s.terminal.file_picker_command,
+        ),
+        ("hooks.pre_send", &s.hooks.pre_send),
+        ("hooks.post_send", &s.hooks.post_send),
+    ]
+    .iter()
+    .filter_map(|(setting, value)| Some((setting.to_string(), value.as_deref()?)))
+    .collect();
+    let mut paths: Vec<(String, &str)> = vec![];
+    if let SendMail::ShellCommand(ref cmd) = s.composing.send_mail {
+        commands.push(("composing.send_mail".to_string(), cmd.as_str()));
+    }
+    if let Some(ref path) = s.pager.patch_repository {
+        paths.push(("pager.patch_repository".to_string(), path.as_str()));
+    }
+    if let Some(ref path) = s.notifications.sound_file {
+        paths.push(("notifications.sound_file".to_string(), path.as_str()));
+    }
+    for (name, acc) in s.accounts.iter() {
+        if let Some(ref cmd) = acc.refresh_command {
+            commands.push((format!("accounts.{}.refresh_command", name), cmd.as_str()));
+        }
+        if let Some(SendMail::ShellCommand(ref cmd)) = acc.conf_override.composing.send_mail {
+            commands.push((format!("accounts.{}.send_mail", name), cmd.as_str()));
+        }
+        if ["maildir", "mbox", "notmuch"].contains(&acc.format.to_lowercase().as_str()) {
+            paths.push((format!("accounts.{}.root_mailbox", name), &acc.root_mailbox));
+        }
+    }
+    let mut ret = commands
+        .into_iter()
+        .filter(|(_, cmd)| !command_exists(cmd))
+        .map(|(setting, cmd)| format!("{}: command `{}` was not found.", setting, cmd))
+        .collect::<Vec<String>>();
+    ret.extend(
+        paths
+            .into_iter()
+            .filter(|(_, path)| !Path::new(path).expand().exists())
+            .map(|(setting, path)| format!("{}: `{}` does not exist.", setting, path)),
+    );
+    ret
+}
+
+/// Connect to the server of each remote account and its SMTP server, printing what was found.
+/// Returns the problems encountered.
+pub fn probe_accounts(s: &FileSettings) -> Vec<String> {
+    let backends = melib::backends::Backends::new();
+    let mut ret = vec![];
+    for (name, acc) in s.accounts.iter() {
+        let mut conf = AccountConf::from(acc.clone());
+        conf.account.set_name(name.to_string());
+        let backend = match backends.get(&conf.account.format)(
+            &conf.account,
+            Box::new(|_: &str| true),
+            BackendEventConsumer::new(Arc::new(|_, _| {})),
+        ) {
+            Ok(backend) => backend,
+            Err(err) => {
+                ret.push(format!("accounts.{}: {}", name, err.details));
+                continue;
+            }
+        };
+        if backend.capabilities().is_remote {
+            println!("Connecting to account `{}`...", name);
+            let online = backend
+                .is_online()
+                .and_then(|fut| futures::executor::block_on(fut));
+            if let Err(err) = online {
+                ret.push(if err.kind.is_authentication() {
+                    format!("accounts.{}: authentication failed: {}", name, err.details)
+                } else {
+                    format!("accounts.{}: could not connect: {}", name, err.details)
+                });
+                continue;
+            }
+            let extensions = backend
+                .capabilities()
+                .extensions
+                .unwrap_or_default()
+                .into_iter()
+                .filter(|(_, status)| {
+                    !matches!(status, MailBackendExtensionStatus::Unsupported { .. })
+                })
+                .map(|(ext, _)| ext)
+                .collect::<Vec<String>>();
+            if extensions.is_empty() {
+                println!("  connected.");
+            } else {
+                println!("  connected, supports {}.", extensions.join(", "));
+            }
+        }
+        match backend
+            .mailboxes()
+            .and_then(|fut| futures::executor::block_on(fut))
+        {
+            Ok(mailboxes) => {
+                for path in acc.mailboxes.keys() {
+                    if !mailboxes.values().any(|m| m.path() == path) {
+                        ret.push(format!(
+                            "accounts.{}.mailboxes: mailbox `{}` was not found on the server.",
+                            name, path
+                        ));
+                    }
+                }
+            }
+            Err(err) => {
+                ret.push(format!(
+                    "accounts.{}: could not list mailboxes: {}",
+                    name, err.details
+                ));
+            }
+        }
+        #[cfg(feature = "smtp")]
+        {
+            let send_mail = acc
+                .conf_override
+                .composing
+                .send_mail
+                .as_ref()
+                .unwrap_or(&s.composing.send_mail);
+            if let SendMail::Smtp(ref smtp_conf) = send_mail {
+                println!(
+                    "Connecting to SMTP server {} of account `{}`...",
+                    smtp_conf.hostname, name
+                );
+                let mut smtp_conf = smtp_conf.clone();
+                smtp_conf.connection_policy =
+                    melib::connections::ConnectionPolicy::new(&conf.account).unwrap_or_default();
+                match futures::executor::block_on(melib::smtp::SmtpConnection::new_connection(
+                    smtp_conf.clone(),
+                )) {
+                    Ok(_) => println!("  connected."),
+                    Err(err) => ret.push(format!(
+                        "accounts.{}: could not connect to SMTP server {}: {}",
+                        name, smtp_conf.hostname, err.details
+                    )),
+                }
+            }
+        }
+    }
+    ret
+}
+
+#[test]
+fn test_command_exists() {
+    assert!(command_exists("sh -c true"));
+    assert!(command_exists("/bin/sh"));
+    assert!(!command_exists("meli-nonexistent-command --flag"));
+}
@@ -53,6 +53,25 @@ pub fn value(context: &Context, key: &'static str) -> ThemeAttribute {
     unlink(theme, &Cow::from(key))
 }
 
+/// Like [`value`], for theme keys named at runtime. Returns `None` if the key does not exist.
+pub fn value_of(context: &Context, key: &str) -> Option<ThemeAttribute> {
+    let theme = match context.settings.terminal.theme.as_str() {
+        "light" => &context.settings.terminal.themes.light,
+        "dark" => &context.settings.terminal.themes.dark,
+        t => context
+            .settings
+            .terminal
+            .themes
+            .other_themes
+            .get(t)
+            .unwrap_or(&context.settings.terminal.themes.dark),
+    };
+    if !theme.keys.contains_key(key) {
+        return None;
+    }
+    Some(unlink(theme, &Cow::from(key.to_string())))
+}
+
 #[inline(always)]
 pub fn fg_color(context: &Context, key: &'static str) -> Color {
     let theme = match context.settings.terminal.theme.as_str() {
@@ -246,6 +265,11 @@ const DEFAULT_KEYS: &[&str] = &[
     "theme_default",
     "status.bar",
     "status.notification",
+    "status.command_bar",
+    "status.command_bar.completion_hint",
+    "status.completions",
+    "status.completions.description",
+    "status.completions.selected",
     "tab.focused",
     "tab.unfocused",
     "tab.bar",
@@ -282,6 +306,12 @@ const DEFAULT_KEYS: &[&str] = &[
     "mail.listing.plain.odd_selected",
     "mail.listing.plain.even_highlighted",
     "mail.listing.plain.odd_highlighted",
+    "mail.listing.unified.account0",
+    "mail.listing.unified.account1",
+    "mail.listing.unified.account2",
+    "mail.listing.unified.account3",
+    "mail.listing.unified.account4",
+    "mail.listing.unified.account5",
     "mail.listing.conversations",
     "mail.listing.conversations.subject",
     "mail.listing.conversations.from",
@@ -301,11 +331,31 @@ const DEFAULT_KEYS: &[&str] = &[
     "mail.view.thread.indentation.d",
     "mail.view.thread.indentation.e",
     "mail.view.thread.indentation.f",
+    "mail.view.thread.subject",
+    "mail.view.thread.unseen",
+    "mail.view.thread.highlighted",
+    "mail.compose.heading",
+    "mail.compose.highlighted",
+    "mail.compose.embed_stopped",
+    "mail.listing.offline",
+    "contacts.view.note",
+    "contacts.list.highlighted",
     "mail.listing.attachment_flag",
     "mail.listing.thread_snooze_flag",
     "mail.listing.tag_default",
+    "mail.listing.highlight_search",
     "pager.highlight_search",
     "pager.highlight_search_current",
+    "pager.diff.header",
+    "pager.diff.hunk",
+    "pager.diff.added",
+    "pager.diff.removed",
+    "pager.diff.context",
+    "pager.quote.1",
+    "pager.quote.2",
+    "pager.quote.3",
+    "pager.quote.4",
+    "pager.wrap_indicator",
 ];
 
 /// `ThemeAttributeInner` but with the links resolved.
@@ -1316,6 +1366,11 @@ impl Default for Themes {
         add!("theme_default", dark = { fg: Color::Default, bg: Color::Default, attrs: Attr::DEFAULT }, light = { fg: Color::Default, bg: Color::Default, attrs: Attr::DEFAULT });
         add!("status.bar", dark = { fg: Color::Byte(123), bg: Color::Byte(26) }, light = { fg: Color::Byte(123), bg: Color::Byte(26) });
         add!("status.notification", dark = { fg: Color::Byte(219), bg: Color::Default }, light = { fg: Color::Byte(219), bg: Color::Default });
+        add!("status.command_bar", dark = { fg: Color::Byte(219), bg: Color::Byte(88) }, light = { fg: Color::Byte(219), bg: Color::Byte(88) });
+        add!("status.command_bar.completion_hint", dark = { fg: Color::Byte(97) /* MediumPurple3 */, bg: "status.command_bar" }, light = { fg: Color::Byte(97) /* MediumPurple3 */, bg: "status.command_bar" });
+        add!("status.completions", dark = { fg: Color::Byte(88) /* DarkRed */, bg: Color::Byte(174) /* LightPink3 */ }, light = { fg: Color::Byte(88) /* DarkRed */, bg: Color::Byte(174) /* LightPink3 */ });
+        add!("status.completions.description", dark = { fg: Color::White, bg: "status.completions" }, light = { fg: Color::White, bg: "status.completions" });
+        add!("status.completions.selected", dark = { fg: "status.completions", bg: Color::Byte(173) /* LightSalmon3 */ }, light = { fg: "status.completions", bg: Color::Byte(173) /* LightSalmon3 */ });
 
         add!("tab.focused");
         add!("tab.unfocused", dark = { fg: Color::Byte(15), bg: Color::Byte(8), }, light = { fg: Color::Byte(15), bg: Color::Byte(8), });
@@ -1639,6 +1694,14 @@ impl Default for Themes {
             }
         );
 
+        /* UnifiedListing */
+        add!("mail.listing.unified.account0", light = { fg: Color::Byte(25) }, dark = { fg: Color::Byte(33) }); // DodgerBlue
+        add!("mail.listing.unified.account1", light = { fg: Color::Byte(28) }, dark = { fg: Color::Byte(35) }); // SpringGreen
+        add!("mail.listing.unified.account2", light = { fg: Color::Byte(130) }, dark = { fg: Color::Byte(172) }); // Orange
+        add!("mail.listing.unified.account3", light = { fg: Color::Byte(90) }, dark = { fg: Color::Byte(170) }); // Orchid
+        add!("mail.listing.unified.account4", light = { fg: Color::Byte(30) }, dark = { fg: Color::Byte(37) }); // DarkCyan
+        add!("mail.listing.unified.account5", light = { fg: Color::Byte(124) }, dark = { fg: Color::Byte(167) }); // IndianRed
+
         add!(
             "mail.view.headers",
             dark = {
@@ -1670,6 +1733,19 @@ impl Default for Themes {
         add!("mail.view.thread.indentation.e", light = { bg: Color::Byte(172) }, dark = { bg: Color::Byte(172) }); // Orange3
         add!("mail.view.thread.indentation.f", light = { bg: Color::Byte(72) }, dark = { bg: Color::Byte(72) }); // CadetBlue
 
+        add!("mail.view.thread.subject", light = { fg: "mail.view.headers" }, dark = { fg: "mail.view.headers" });
+        add!("mail.view.thread.unseen", light = { fg: Color::Byte(0), bg: Color::Byte(251) }, dark = { fg: Color::Byte(0), bg: Color::Byte(251) });
+        add!("mail.view.thread.highlighted", light = { bg: Color::Byte(244) }, dark = { bg: Color::Byte(246) });
+
+        add!("mail.compose.heading", light = { fg: Color::Byte(189), bg: Color::Byte(167) }, dark = { fg: Color::Byte(189), bg: Color::Byte(167) });
+        add!("mail.compose.highlighted", light = { bg: Color::Byte(252) }, dark = { bg: Color::Byte(237) });
+        add!("mail.compose.embed_stopped", light = { fg: Color::Byte(8) }, dark = { fg: Color::Byte(8) });
+
+        add!("mail.listing.offline", light = { fg: Color::Byte(243) }, dark = { fg: Color::Byte(243) });
+
+        add!("contacts.view.note", light = { fg: Color::Byte(243) }, dark = { fg: Color::Byte(250) });
+        add!("contacts.list.highlighted", light = { bg: Color::Byte(244) }, dark = { bg: Color::Byte(246) });
+
         add!(
             "mail.listing.attachment_flag",
             light = {
@@ -1703,9 +1779,20 @@ impl Default for Themes {
                 attrs: Attr::BOLD
             }
         );
+        add!("mail.listing.highlight_search", light = { fg: "pager.highlight_search", bg: "pager.highlight_search", attrs: "pager.highlight_search" }, dark = { fg: "pager.highlight_search", bg: "pager.highlight_search", attrs: "pager.highlight_search" });
 
         add!("pager.highlight_search", light = { fg: Color::White, bg: Color::Byte(6) /* Teal */, attrs: Attr::BOLD }, dark = { fg: Color::White, bg: Color::Byte(6) /* Teal */, attrs: Attr::BOLD });
         add!("pager.highlight_search_current", light = { fg: Color::White, bg: Color::Byte(17) /* NavyBlue */, attrs: Attr::BOLD }, dark = { fg: Color::White, bg: Color::Byte(17) /* NavyBlue */, attrs: Attr::BOLD });
+        add!("pager.diff.header", light = { fg: "mail.view.body", bg: "mail.view.body", attrs: Attr::BOLD }, dark = { fg: "mail.view.body", bg: "mail.view.body", attrs: Attr::BOLD });
+        add!("pager.diff.hunk", light = { fg: Color::Byte(6) /* Teal */, bg: "mail.view.body" }, dark = { fg: Color::Byte(14) /* Aqua */, bg: "mail.view.body" });
+        add!("pager.diff.added", light = { fg: Color::Byte(2) /* Green */, bg: "mail.view.body" }, dark = { fg: Color::Byte(10) /* Lime */, bg: "mail.view.body" });
+        add!("pager.diff.removed", light = { fg: Color::Byte(1) /* Maroon */, bg: "mail.view.body" }, dark = { fg: Color::Byte(9) /* Red */, bg: "mail.view.body" });
+        add!("pager.diff.context", light = { fg: "mail.view.body", bg: "mail.view.body" }, dark = { fg: "mail.view.body", bg: "mail.view.body" });
+        add!("pager.quote.1", light = { fg: Color::Byte(25) /* DeepSkyBlue7 */, bg: "mail.view.body" }, dark = { fg: Color::Byte(74) /* SkyBlue3 */, bg: "mail.view.body" });
+        add!("pager.quote.2", light = { fg: Color::Byte(28) /* Green4 */, bg: "mail.view.body" }, dark = { fg: Color::Byte(107) /* DarkOliveGreen4 */, bg: "mail.view.body" });
+        add!("pager.quote.3", light = { fg: Color::Byte(90) /* DarkMagenta */, bg: "mail.view.body" }, dark = { fg: Color::Byte(139) /* Grey63 */, bg: "mail.view.body" });
+        add!("pager.quote.4", light = { fg: Color::Byte(130) /* DarkOrange2 */, bg: "mail.view.body" }, dark = { fg: Color::Byte(173) /* LightSalmon3 */, bg: "mail.view.body" });
+        add!("pager.wrap_indicator", light = { fg: Color::Byte(240), attrs: Attr::BOLD }, dark = { fg: Color::Byte(240), attrs: Attr::BOLD });
         Themes {
             light: Theme {
                 keys: light,
@@ -0,0 +1,100 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Formatting of message dates in listings and in the mail view.
+ *
+ * Dates are converted to local time. Recent dates can be shown relative to the current time, eg.
+ * `5m ago`, `2h ago` or `Yesterday`, older ones are formatted with a `strftime(3)` format.
+ */
+
+use melib::datetime::{timestamp_to_string, UnixTimestamp};
+use melib::Envelope;
+
+/// Format used when none is configured.
+pub const DEFAULT_FMT: &str = "%Y-%m-%d %T";
+/// Format of the Date header in the mail view when none is configured.
+pub const HEADER_FMT: &str = "%a, %d %b %Y %T %z";
+
+const MINUTE: u64 = 60;
+const HOUR: u64 = 60 * MINUTE;
+const DAY: u64 = 24 * HOUR;
+
+/// `timestamp` relative to `now`, if it's less than a week old: `just now`, `Xm ago`, `Xh ago`
+/// for earlier the same day, `Yesterday`, or the name of the weekday.
+pub fn relative_date(timestamp: UnixTimestamp, now: UnixTimestamp) -> Option<String> {
+    let ago = now.saturating_sub(timestamp);
+    let local_day = |t: UnixTimestamp| timestamp_to_string(t, Some("%Y-%j"), true);
+    if ago < MINUTE {
+        Some("just now".to_string())
+    } else if ago < HOUR {
+        Some(format!("{}m ago", ago / MINUTE))
+    } else if ago < DAY && local_day(timestamp) == local_day(now) {
+        Some(format!("{}h ago", ago / HOUR))
+    } else if ago < 2 * DAY && local_day(timestamp) == local_day(now.saturating_sub(DAY)) {
+        Some("Yesterday".to_string())
+    } else if ago < 7 * DAY {
+        Some(timestamp_to_string(timestamp, Some("%A"), false))
+    } else {
+        None
+    }
+}
+
+/// `timestamp` in local time, relative to the current time if `relative` is set and it is recent,
+/// otherwise formatted with `fmt` or [`DEFAULT_FMT`].
+pub fn format_date(timestamp: UnixTimestamp, fmt: Option<&str>, relative: bool) -> String {
+    if relative {
+        if let Some(s) = relative_date(timestamp, melib::datetime::now()) {
+            return s;
+        }
+    }
+    timestamp_to_string(timestamp, Some(fmt.unwrap_or(DEFAULT_FMT)), false)
+}
+
+/// The Date header of `envelope`, converted to local time and formatted with `fmt` or
+/// [`HEADER_FMT`] if `local` is set, otherwise as sent.
+pub fn format_date_header(envelope: &Envelope, fmt: Option<&str>, local: bool) -> String {
+    if !local || envelope.date_as_str().trim().is_empty() {
+        return envelope.date_as_str().to_string();
+    }
+    timestamp_to_string(envelope.date(), Some(fmt.unwrap_or(HEADER_FMT)), false)
+}
+
+#[test]
+fn test_dates_relative_date() {
+    let now = melib::datetime::rfc822_to_timestamp("Wed, 8 Jan 2020 10:44:03 -0800").unwrap();
+    assert_eq!(relative_date(now + 30, now), Some("just now".to_string()));
+    assert_eq!(relative_date(now - 30, now), Some("just now".to_string()));
+    assert_eq!(
+        relative_date(now - 5 * MINUTE, now),
+        Some("5m ago".to_string())
+    );
+    assert_eq!(relative_date(now - 8 * DAY, now), None);
+    let local_now = timestamp_to_string(now, Some("%H"), true)
+        .parse::<u64>()
+        .unwrap();
+    if local_now >= 2 {
+        assert_eq!(
+            relative_date(now - 2 * HOUR, now),
+            Some("2h ago".to_string())
+        );
+    }
+    assert_eq!(relative_date(now - DAY, now), Some("Yesterday".to_string()));
+}
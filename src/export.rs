@@ -0,0 +1,268 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Export messages to an mbox file or a maildir, see the `export-*` commands and the `export`
+ * subcommand.
+ */
+
+use crate::conf::FileSettings;
+use melib::backends::maildir::MaildirType;
+use melib::backends::mbox::{MboxFormat, MboxMetadata};
+use melib::search::{Query, QueryTrait};
+use melib::{Envelope, MeliError, Result};
+use std::convert::TryFrom;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+
+/// Format of exported messages.
+#[derive(Debug, Clone, Copy)]
+pub enum ExportFormat {
+    Mbox(MboxFormat),
+    Maildir,
+}
+
+impl Default for ExportFormat {
+    fn default() -> Self {
+        ExportFormat::Mbox(MboxFormat::MboxCl2)
+    }
+}
+
+impl std::str::FromStr for ExportFormat {
+    type Err = MeliError;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "mbox" | "mboxcl2" => ExportFormat::Mbox(MboxFormat::MboxCl2),
+            "mboxcl" => ExportFormat::Mbox(MboxFormat::MboxCl),
+            "mboxrd" => ExportFormat::Mbox(MboxFormat::MboxRd),
+            "mboxo" => ExportFormat::Mbox(MboxFormat::MboxO),
+            "maildir" => ExportFormat::Maildir,
+            _ => {
+                return Err(MeliError::new(format!(
+                    "Unknown export format `{}`, expected one of mbox, mboxcl2, mboxcl, mboxrd, mboxo or maildir.",
+                    s
+                )))
+            }
+        })
+    }
+}
+
+/// What to do when the export destination already exists.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ExportMode {
+    /// Refuse to export to an existing file or directory.
+    Create,
+    /// Add the messages to an existing mbox file or maildir.
+    Append,
+    /// Replace an existing mbox file.
+    Overwrite,
+}
+
+impl Default for ExportMode {
+    fn default() -> Self {
+        ExportMode::Create
+    }
+}
+
+impl std::str::FromStr for ExportMode {
+    type Err = MeliError;
+    fn from_str(s: &str) -> Result<Self> {
+        Ok(match s {
+            "append" => ExportMode::Append,
+            "overwrite" => ExportMode::Overwrite,
+            _ => {
+                return Err(MeliError::new(format!(
+                    "Unknown export mode `{}`, expected append or overwrite.",
+                    s
+                )))
+            }
+        })
+    }
+}
+
+/// Writes messages one by one to an mbox file or a maildir. Flags are kept in both formats, tags
+/// only in mbox files.
+pub struct Exporter {
+    format: ExportFormat,
+    path: PathBuf,
+    file: Option<std::io::BufWriter<std::fs::File>>,
+    is_empty: bool,
+    count: usize,
+}
+
+impl Exporter {
+    /// Open the mbox file or maildir at `path`, creating it if needed. An existing destination is
+    /// only written to if `mode` asks to append to or overwrite it.
+    pub fn new(format: ExportFormat, mode: ExportMode, path: &Path) -> Result<Self> {
+        if mode == ExportMode::Create && path.exists() {
+            return Err(MeliError::new(format!(
+                "{} already exists, choose to append to or overwrite it.",
+                path.display()
+            )));
+        }
+        let mut ret = Exporter {
+            format,
+            path: path.to_path_buf(),
+            file: None,
+            is_empty: true,
+            count: 0,
+        };
+        match format {
+            ExportFormat::Mbox(_) => {
+                let file = std::fs::OpenOptions::new()
+                    .write(true)
+                    .create(true)
+                    .append(mode == ExportMode::Append)
+                    .truncate(mode != ExportMode::Append)
+                    .open(path)?;
+                ret.is_empty = file.metadata()?.len() == 0;
+                ret.file = Some(std::io::BufWriter::new(file));
+            }
+            ExportFormat::Maildir => {
+                if mode == ExportMode::Overwrite && path.exists() {
+                    return Err(MeliError::new(format!(
+                        "Cannot overwrite the maildir {}, only append to it.",
+                        path.display()
+                    )));
+                }
+                for d in &["cur", "new", "tmp"] {
+                    std::fs::create_dir_all(path.join(d))?;
+                }
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Write a message, given its envelope, its raw bytes and the names of its tags.
+    pub fn write(&mut self, env: &Envelope, bytes: Vec<u8>, tags: &[String]) -> Result<()> {
+        match (self.format, self.file.as_mut()) {
+            (ExportFormat::Mbox(format), Some(file)) => {
+                format.append(
+                    file,
+                    bytes.as_slice(),
+                    env.from().get(0),
+                    Some(env.date()),
+                    (env.flags(), tags.iter().map(String::as_str).collect()),
+                    MboxMetadata::CClient,
+                    self.is_empty,
+                    false,
+                )?;
+                self.is_empty = false;
+            }
+            _ => {
+                MaildirType::save_to_mailbox(self.path.clone(), bytes, Some(env.flags()))?;
+            }
+        }
+        self.count += 1;
+        Ok(())
+    }
+
+    /// Flush the written messages and return how many there were.
+    pub fn finish(self) -> Result<usize> {
+        if let Some(mut file) = self.file {
+            file.flush()?;
+        }
+        Ok(self.count)
+    }
+}
+
+/// Export `mailbox_path` of `account_name`, or the messages in it matching `query`, to `output`.
+pub fn export(
+    settings: &FileSettings,
+    account_name: &str,
+    mailbox_path: &str,
+    query: Option<&str>,
+    format: ExportFormat,
+    mode: ExportMode,
+    output: &Path,
+) -> Result<()> {
+    let query = query.map(Query::try_from).transpose()?;
+    let (mut backend, mailbox_hash) =
+        crate::import::open_mailbox(settings, account_name, mailbox_path)?;
+    let mut exporter = Exporter::new(format, mode, output)?;
+    let mut envelopes = vec![];
+    let mut stream = backend.fetch(mailbox_hash)?;
+    while let Some(batch) =
+        futures::executor::block_on(futures::stream::StreamExt::next(&mut stream))
+    {
+        envelopes.extend(batch?);
+    }
+    drop(stream);
+    if let Some(ref query) = query {
+        envelopes.retain(|env| env.is_match(query));
+    }
+    envelopes.sort_by_key(|env| env.date());
+    for env in envelopes {
+        let bytes = futures::executor::block_on(backend.operation(env.hash())?.as_bytes()?)?;
+        exporter.write(&env, bytes, &[])?;
+    }
+    let count = exporter.finish()?;
+    println!(
+        "Exported {} message{} to {}.",
+        count,
+        if count == 1 { "" } else { "s" },
+        output.display()
+    );
+    Ok(())
+}
+
+#[test]
+fn test_exporter() {
+    use melib::Flag;
+    let dir = std::env::temp_dir().join(format!("meli-export-{}", std::process::id()));
+    std::fs::create_dir_all(&dir).unwrap();
+    let messages: Vec<(Envelope, Vec<u8>)> = vec![
+        (b"Subject: a\n\na\n".to_vec(), Flag::SEEN),
+        (b"Subject: b\n\nb\n".to_vec(), Flag::FLAGGED),
+    ]
+    .into_iter()
+    .map(|(bytes, flags)| (Envelope::from_bytes(&bytes, Some(flags)).unwrap(), bytes))
+    .collect();
+    for format in &["mbox", "maildir"] {
+        let path = dir.join(format);
+        let format: ExportFormat = format.parse().unwrap();
+        for (i, (env, bytes)) in messages.iter().enumerate() {
+            let mode = if i == 0 {
+                ExportMode::Create
+            } else {
+                ExportMode::Append
+            };
+            let mut exporter = Exporter::new(format, mode, &path).unwrap();
+            exporter.write(env, bytes.clone(), &[]).unwrap();
+            assert_eq!(exporter.finish().unwrap(), 1);
+        }
+        assert!(Exporter::new(format, ExportMode::Create, &path).is_err());
+        let mut flags = crate::import::collect_messages(&path)
+            .unwrap()
+            .into_iter()
+            .map(|(_, flags)| flags)
+            .collect::<Vec<Flag>>();
+        flags.sort();
+        assert_eq!(flags, vec![Flag::SEEN, Flag::FLAGGED]);
+    }
+    let path = dir.join("mbox");
+    let (env, bytes) = &messages[0];
+    let mut exporter =
+        Exporter::new(ExportFormat::default(), ExportMode::Overwrite, &path).unwrap();
+    exporter.write(env, bytes.clone(), &[]).unwrap();
+    exporter.finish().unwrap();
+    assert_eq!(crate::import::collect_messages(&path).unwrap().len(), 1);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
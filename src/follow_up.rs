@@ -0,0 +1,89 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Follow-up reminders for sent messages that haven't been replied to, see the
+ * `remind-if-no-reply` and `awaiting-reply` commands.
+ */
+
+use melib::{Envelope, StrBuild, UnixTimestamp};
+
+/// A sent message waiting for a reply.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct FollowUp {
+    /// Raw Message-ID of the sent message, without angle brackets.
+    pub message_id: String,
+    pub subject: String,
+    /// When to notify the user if there's no reply yet.
+    pub until: UnixTimestamp,
+    /// Whether the user has already been notified.
+    #[serde(default)]
+    pub notified: bool,
+}
+
+impl FollowUp {
+    /// `message_id` is the value of the `Message-ID` header of the sent message.
+    pub fn new(message_id: &str, subject: String, until: UnixTimestamp) -> Self {
+        FollowUp {
+            message_id: message_id
+                .trim()
+                .trim_start_matches('<')
+                .trim_end_matches('>')
+                .to_string(),
+            subject,
+            until,
+            notified: false,
+        }
+    }
+
+    /// Whether `envelope` replies to this message, according to its `In-Reply-To` and
+    /// `References` headers.
+    pub fn is_replied_by(&self, envelope: &Envelope) -> bool {
+        envelope
+            .in_reply_to()
+            .into_iter()
+            .chain(envelope.references().into_iter())
+            .any(|m| m.raw() == self.message_id.as_bytes())
+    }
+}
+
+#[test]
+fn test_follow_up_is_replied_by() {
+    let follow_up = FollowUp::new("<sent.1@example.com>", "Lunch?".to_string(), 0);
+    assert_eq!(follow_up.message_id, "sent.1@example.com");
+    let reply = Envelope::from_bytes(
+        b"From: a@example.com\nMessage-ID: <reply.1@example.com>\nIn-Reply-To: <sent.1@example.com>\nSubject: Re: Lunch?\n\nSure\n",
+        None,
+    )
+    .unwrap();
+    assert!(follow_up.is_replied_by(&reply));
+    let later = Envelope::from_bytes(
+        b"From: b@example.com\nMessage-ID: <reply.2@example.com>\nIn-Reply-To: <reply.1@example.com>\nReferences: <sent.1@example.com> <reply.1@example.com>\nSubject: Re: Lunch?\n\nMe too\n",
+        None,
+    )
+    .unwrap();
+    assert!(follow_up.is_replied_by(&later));
+    let other = Envelope::from_bytes(
+        b"From: c@example.com\nMessage-ID: <other.1@example.com>\nSubject: Lunch?\n\nHi\n",
+        None,
+    )
+    .unwrap();
+    assert!(!follow_up.is_replied_by(&other));
+}
@@ -0,0 +1,239 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Import messages from an mbox file or a maildir tree into a mailbox of an account, see the
+ * `import` subcommand.
+ */
+
+use crate::conf::{AccountConf, FileSettings};
+use melib::backends::maildir::MaildirPathTrait;
+use melib::backends::{BackendEventConsumer, MailBackend, MailboxHash};
+use melib::{Flag, MeliError, Result};
+use std::collections::HashMap;
+use std::io::Write;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex};
+
+/// Read the messages and their flags from an mbox file or a maildir tree.
+pub fn collect_messages(input: &Path) -> Result<Vec<(Vec<u8>, Flag)>> {
+    if input.is_dir() {
+        let mut paths = vec![];
+        collect_maildir_paths(input, &mut paths)?;
+        paths
+            .into_iter()
+            .map(|p| {
+                let flags = p.flags();
+                std::fs::read(&p)
+                    .map(|bytes| (bytes, flags))
+                    .map_err(|err| MeliError::new(format!("{}: {}", p.display(), err)))
+            })
+            .collect()
+    } else {
+        let contents = std::fs::read(input)
+            .map_err(|err| MeliError::new(format!("{}: {}", input.display(), err)))?;
+        if contents.is_empty() {
+            return Ok(vec![]);
+        }
+        let index = Arc::new(Mutex::new(HashMap::default()));
+        let (_, envelopes) = melib::backends::mbox::mbox_parse(index.clone(), &contents, 0, None)
+            .map_err(|err| {
+            MeliError::new(format!(
+                "{} is not a valid mbox file: {}",
+                input.display(),
+                MeliError::from(err)
+            ))
+        })?;
+        let index = index.lock().unwrap();
+        Ok(envelopes
+            .into_iter()
+            .filter_map(|env| {
+                let (offset, length) = index.get(&env.hash())?;
+                Some((contents[*offset..*offset + *length].to_vec(), env.flags()))
+            })
+            .collect())
+    }
+}
+
+/// Collect the messages of every maildir in the tree rooted at `path`, in a stable order.
+fn collect_maildir_paths(path: &Path, ret: &mut Vec<PathBuf>) -> Result<()> {
+    let mut entries = std::fs::read_dir(path)?
+        .map(|e| e.map(|e| e.path()))
+        .collect::<std::result::Result<Vec<PathBuf>, _>>()?;
+    entries.sort();
+    for entry in entries {
+        if !entry.is_dir() {
+            continue;
+        }
+        match entry.file_name().and_then(|n| n.to_str()) {
+            Some("cur") | Some("new") => {
+                let mut messages = std::fs::read_dir(&entry)?
+                    .map(|e| e.map(|e| e.path()))
+                    .collect::<std::result::Result<Vec<PathBuf>, _>>()?;
+                messages.retain(|p| p.is_file());
+                messages.sort();
+                ret.extend(messages);
+            }
+            Some("tmp") => {}
+            _ => collect_maildir_paths(&entry, ret)?,
+        }
+    }
+    Ok(())
+}
+
+fn print_progress(done: usize, total: usize) {
+    const WIDTH: usize = 40;
+    let filled = if total == 0 {
+        WIDTH
+    } else {
+        done * WIDTH / total
+    };
+    eprint!(
+        "\r[{}{}] {}/{}",
+        "#".repeat(filled),
+        " ".repeat(WIDTH - filled),
+        done,
+        total
+    );
+    std::io::stderr().flush().ok();
+}
+
+/// Create the backend of `account_name` outside of the UI, connect to it and look up
+/// `mailbox_path`.
+pub fn open_mailbox(
+    settings: &FileSettings,
+    account_name: &str,
+    mailbox_path: &str,
+) -> Result<(Box<dyn MailBackend>, MailboxHash)> {
+    let acc = settings.accounts.get(account_name).ok_or_else(|| {
+        MeliError::new(format!(
+            "Account `{}` was not found in the configuration.",
+            account_name
+        ))
+    })?;
+    let mut conf = AccountConf::from(acc.clone());
+    conf.account.set_name(account_name.to_string());
+    let backends = melib::backends::Backends::new();
+    let backend: Box<dyn MailBackend> = backends.get(&conf.account.format)(
+        &conf.account,
+        Box::new(|_: &str| true),
+        BackendEventConsumer::new(Arc::new(|_, _| {})),
+    )?;
+    futures::executor::block_on(backend.is_online()?)?;
+    let mailboxes = futures::executor::block_on(backend.mailboxes()?)?;
+    let mailbox_hash: MailboxHash = mailboxes
+        .iter()
+        .find(|(_, m)| m.path() == mailbox_path)
+        .map(|(h, _)| *h)
+        .ok_or_else(|| {
+            let mut paths = mailboxes.values().map(|m| m.path()).collect::<Vec<&str>>();
+            paths.sort();
+            MeliError::new(format!(
+                "Mailbox `{}` was not found in account `{}`. Available mailboxes: {}",
+                mailbox_path,
+                account_name,
+                paths.join(", ")
+            ))
+        })?;
+    Ok((backend, mailbox_hash))
+}
+
+/// Save the messages of `input` in `mailbox_path` of `account_name` with the backend's `save()`,
+/// keeping their flags. Message contents, and thus their `Date` headers, are not altered. With
+/// `dry_run`, only report what would be imported.
+pub fn import(
+    settings: &FileSettings,
+    account_name: &str,
+    mailbox_path: &str,
+    input: &Path,
+    dry_run: bool,
+) -> Result<()> {
+    let (backend, mailbox_hash) = open_mailbox(settings, account_name, mailbox_path)?;
+
+    let messages = collect_messages(input)?;
+    if dry_run {
+        println!(
+            "Would import {} message{} from {} into {}/{}.",
+            messages.len(),
+            if messages.len() == 1 { "" } else { "s" },
+            input.display(),
+            account_name,
+            mailbox_path
+        );
+        return Ok(());
+    }
+
+    let total = messages.len();
+    let mut failures = vec![];
+    print_progress(0, total);
+    for (i, (bytes, flags)) in messages.into_iter().enumerate() {
+        if let Err(err) = backend
+            .save(bytes, mailbox_hash, Some(flags))
+            .and_then(futures::executor::block_on)
+        {
+            failures.push(format!("message {}: {}", i + 1, err.details));
+        }
+        print_progress(i + 1, total);
+    }
+    eprintln!();
+    for failure in failures.iter() {
+        eprintln!("{}", failure);
+    }
+    if failures.is_empty() {
+        println!("Imported {} messages.", total);
+        Ok(())
+    } else {
+        Err(MeliError::new(format!(
+            "{} of {} messages could not be imported.",
+            failures.len(),
+            total
+        )))
+    }
+}
+
+#[test]
+fn test_collect_messages() {
+    let dir = std::env::temp_dir().join(format!("meli-import-{}", std::process::id()));
+    for sub in &["INBOX/cur", "INBOX/new", "INBOX/tmp"] {
+        std::fs::create_dir_all(dir.join(sub)).unwrap();
+    }
+    std::fs::write(dir.join("INBOX/cur/1:2,FS"), "Subject: a\n\na\n").unwrap();
+    std::fs::write(dir.join("INBOX/new/2"), "Subject: b\n\nb\n").unwrap();
+    std::fs::write(dir.join("INBOX/tmp/3"), "Subject: c\n\nc\n").unwrap();
+    let messages = collect_messages(&dir).unwrap();
+    assert_eq!(
+        messages,
+        vec![
+            (b"Subject: a\n\na\n".to_vec(), Flag::FLAGGED | Flag::SEEN),
+            (b"Subject: b\n\nb\n".to_vec(), Flag::default()),
+        ]
+    );
+
+    let mbox = dir.join("mbox");
+    std::fs::write(
+        &mbox,
+        "From a@example.com Mon Jan  1 00:00:00 2020\nSubject: a\nStatus: RO\n\na\n\nFrom b@example.com Mon Jan  1 00:00:00 2020\nSubject: b\n\nb\n",
+    )
+    .unwrap();
+    let messages = collect_messages(&mbox).unwrap();
+    assert_eq!(messages.len(), 2);
+    assert_eq!(messages[0].1, Flag::SEEN);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
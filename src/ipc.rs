@@ -0,0 +1,541 @@
+/*
+ * meli - plugin socket
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Plugin API over a local Unix socket.
+ *
+ * External processes connect to the socket and exchange JSON objects, one per line. Requests
+ * have a `method`, optional `params` and an optional `id` that is echoed in their response:
+ *
+ * ```text
+ * {"id": 1, "method": "subscribe", "params": {"events": ["new_mail"]}}
+ * {"id": 1, "result": null}
+ * ```
+ *
+ * Events are sent to the connections that subscribed to them, and `command` events to the
+ * connection that registered the command:
+ *
+ * ```text
+ * {"event": "new_mail", "account": "work", "mailbox": "INBOX", "envelope": 1234, ...}
+ * {"event": "command", "name": "archive-old", "args": "30"}
+ * ```
+ */
+
+use crate::components::mail::Composer;
+use crate::conf::accounts::JobRequest;
+use crate::conf::PluginsSettings;
+use crate::types::{CallbackFn, NotificationType, ThreadEvent, UIEvent};
+use crate::{Action::Tab, Context, TabAction::New};
+use crossbeam::Sender;
+use indexmap::IndexMap;
+use melib::backends::{AccountHash, FlagOp, FlagOps, MailboxHash};
+use melib::email::EnvelopeHash;
+use melib::error::{MeliError, Result};
+use std::collections::HashSet;
+use std::io::{BufRead, BufReader, Write};
+use std::os::unix::fs::FileTypeExt;
+use std::os::unix::net::{UnixListener, UnixStream};
+use std::path::{Path, PathBuf};
+use std::sync::atomic::{AtomicU64, Ordering};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+
+pub type ConnectionId = u64;
+
+/// A request sent by a plugin.
+#[derive(Debug, Deserialize)]
+struct Request {
+    #[serde(default)]
+    id: Option<serde_json::Value>,
+    #[serde(flatten)]
+    method: Method,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "method", content = "params", rename_all = "snake_case")]
+enum Method {
+    /// Receive the events in `events`: `new_mail`, `message_open`.
+    Subscribe { events: Vec<String> },
+    /// Register a command that can be executed from meli's command mode.
+    RegisterCommand { name: String },
+    /// Add or remove a tag of an envelope.
+    Tag {
+        account: String,
+        mailbox: String,
+        envelope: EnvelopeHash,
+        tag: String,
+        #[serde(default)]
+        remove: bool,
+    },
+    /// Move an envelope to another mailbox of its account.
+    Move {
+        account: String,
+        mailbox: String,
+        envelope: EnvelopeHash,
+        destination: String,
+    },
+    /// Open a new composer tab.
+    Compose {
+        account: String,
+        #[serde(default)]
+        headers: IndexMap<String, String>,
+        #[serde(default)]
+        body: Option<String>,
+    },
+    /// Execute a command as if it was entered in command mode.
+    Command { command: String },
+    /// Show a notification.
+    Notify {
+        #[serde(default)]
+        title: Option<String>,
+        body: String,
+    },
+}
+
+/// Events sent to plugins.
+#[derive(Debug, Serialize)]
+#[serde(tag = "event", rename_all = "snake_case")]
+pub enum PluginEvent {
+    NewMail(MessageInfo),
+    MessageOpen(MessageInfo),
+    Command { name: String, args: String },
+}
+
+impl PluginEvent {
+    fn name(&self) -> &'static str {
+        match self {
+            PluginEvent::NewMail(_) => "new_mail",
+            PluginEvent::MessageOpen(_) => "message_open",
+            PluginEvent::Command { .. } => "command",
+        }
+    }
+}
+
+#[derive(Debug, Serialize)]
+pub struct MessageInfo {
+    account: String,
+    mailbox: String,
+    envelope: EnvelopeHash,
+    message_id: String,
+    from: String,
+    to: String,
+    subject: String,
+    date: String,
+}
+
+impl MessageInfo {
+    pub fn new(
+        context: &Context,
+        account_hash: AccountHash,
+        mailbox_hash: MailboxHash,
+        env_hash: EnvelopeHash,
+    ) -> Option<Self> {
+        let account = context.accounts.get(&account_hash)?;
+        if !account.contains_key(env_hash) || !account.mailbox_entries.contains_key(&mailbox_hash) {
+            return None;
+        }
+        let envelope = account.collection.get_env(env_hash);
+        Some(MessageInfo {
+            account: account.name().to_string(),
+            mailbox: account.mailbox_entries[&mailbox_hash]
+                .ref_mailbox
+                .path()
+                .to_string(),
+            envelope: env_hash,
+            message_id: envelope.message_id_display().into_owned(),
+            from: envelope.field_from_to_string(),
+            to: envelope.field_to_to_string(),
+            subject: envelope.subject().into_owned(),
+            date: envelope.date_as_str().to_string(),
+        })
+    }
+}
+
+#[derive(Debug)]
+struct Connection {
+    stream: UnixStream,
+    events: HashSet<String>,
+    commands: HashSet<String>,
+}
+
+impl Connection {
+    fn send<T: serde::Serialize>(&mut self, value: &T) -> std::io::Result<()> {
+        let mut line = serde_json::to_vec(value)?;
+        line.push(b'\n');
+        self.stream.write_all(&line)?;
+        self.stream.flush()
+    }
+
+    fn respond(&mut self, id: Option<serde_json::Value>, result: Result<()>) {
+        let response = match result {
+            Ok(()) => serde_json::json!({ "id": id, "result": null }),
+            Err(err) => serde_json::json!({ "id": id, "error": err.to_string() }),
+        };
+        let _ = self.send(&response);
+    }
+}
+
+type Connections = Arc<Mutex<IndexMap<ConnectionId, Connection>>>;
+
+/// Listens on the plugin socket, if enabled, and keeps track of connected plugins.
+#[derive(Debug, Default)]
+pub struct PluginServer {
+    socket_path: Option<PathBuf>,
+    connections: Connections,
+}
+
+impl Drop for PluginServer {
+    fn drop(&mut self) {
+        if let Some(ref path) = self.socket_path {
+            let _ = std::fs::remove_file(path);
+        }
+    }
+}
+
+fn default_socket_path() -> Result<PathBuf> {
+    let base_dirs =
+        xdg::BaseDirectories::with_prefix("meli").map_err(|err| MeliError::new(err.to_string()))?;
+    Ok(base_dirs
+        .place_runtime_file("plugins.sock")
+        .or_else(|_| base_dirs.place_cache_file("plugins.sock"))?)
+}
+
+/// Remove the socket left at `path` by a meli instance that didn't exit cleanly. Fails if
+/// another instance is listening on it, or if `path` isn't a socket.
+fn remove_stale_socket(path: &Path) -> Result<()> {
+    let metadata = match std::fs::symlink_metadata(path) {
+        Ok(metadata) => metadata,
+        Err(err) if err.kind() == std::io::ErrorKind::NotFound => return Ok(()),
+        Err(err) => return Err(err.into()),
+    };
+    if !metadata.file_type().is_socket() {
+        return Err(MeliError::new(format!(
+            "Could not bind plugin socket {}: file exists and is not a socket.",
+            path.display()
+        )));
+    }
+    if UnixStream::connect(path).is_ok() {
+        return Err(MeliError::new(format!(
+            "Could not bind plugin socket {}: it is in use by another process.",
+            path.display()
+        )));
+    }
+    std::fs::remove_file(path)?;
+    Ok(())
+}
+
+impl PluginServer {
+    pub fn new(settings: &PluginsSettings, sender: Sender<ThreadEvent>) -> Result<Self> {
+        let mut ret = PluginServer::default();
+        if !settings.enable {
+            return Ok(ret);
+        }
+        let socket_path = match settings.socket_path {
+            Some(ref path) => path.clone(),
+            None => default_socket_path()?,
+        };
+        remove_stale_socket(&socket_path)?;
+        /* Create the socket read/write for owner only, instead of changing its permissions after
+         * it has been bound and is already reachable. */
+        let old_umask = unsafe { libc::umask(0o177) };
+        let listener = UnixListener::bind(&socket_path);
+        unsafe { libc::umask(old_umask) };
+        let listener = listener.map_err(|err| {
+            MeliError::new(format!(
+                "Could not bind plugin socket {}: {}",
+                socket_path.display(),
+                err
+            ))
+        })?;
+        ret.socket_path = Some(socket_path);
+        let connections = ret.connections.clone();
+        std::thread::Builder::new()
+            .name("plugin socket".to_string())
+            .spawn(move || {
+                let counter = AtomicU64::new(0);
+                for stream in listener.incoming() {
+                    match stream {
+                        Ok(stream) => {
+                            let id = counter.fetch_add(1, Ordering::SeqCst);
+                            if let Err(err) =
+                                Self::accept(id, stream, connections.clone(), sender.clone())
+                            {
+                                debug!("Could not accept plugin connection: {}", err);
+                            }
+                        }
+                        Err(err) => {
+                            debug!("plugin socket error: {}", err);
+                        }
+                    }
+                }
+            })?;
+        Ok(ret)
+    }
+
+    fn accept(
+        id: ConnectionId,
+        stream: UnixStream,
+        connections: Connections,
+        sender: Sender<ThreadEvent>,
+    ) -> Result<()> {
+        /* Plugins that don't read their events are dropped instead of blocking the UI. */
+        stream.set_write_timeout(Some(Duration::from_secs(1)))?;
+        let reader = BufReader::new(stream.try_clone()?);
+        connections.lock().unwrap().insert(
+            id,
+            Connection {
+                stream,
+                events: HashSet::default(),
+                commands: HashSet::default(),
+            },
+        );
+        std::thread::spawn(move || {
+            for line in reader.lines() {
+                let line = match line {
+                    Ok(line) => line,
+                    Err(_) => break,
+                };
+                if line.trim().is_empty() {
+                    continue;
+                }
+                let request: Request = match serde_json::from_str(&line) {
+                    Ok(request) => request,
+                    Err(err) => {
+                        if let Some(connection) = connections.lock().unwrap().get_mut(&id) {
+                            connection.respond(
+                                None,
+                                Err(MeliError::new(format!("Invalid request: {}", err))),
+                            );
+                        }
+                        continue;
+                    }
+                };
+                let Request {
+                    id: request_id,
+                    method,
+                } = request;
+                match method {
+                    Method::Subscribe { events } => {
+                        if let Some(connection) = connections.lock().unwrap().get_mut(&id) {
+                            connection.events.extend(events);
+                            connection.respond(request_id, Ok(()));
+                        }
+                    }
+                    Method::RegisterCommand { name } => {
+                        let mut connections = connections.lock().unwrap();
+                        let result = if connections.values().any(|c| c.commands.contains(&name)) {
+                            Err(MeliError::new(format!(
+                                "Command {} is already registered.",
+                                name
+                            )))
+                        } else {
+                            Ok(())
+                        };
+                        if let Some(connection) = connections.get_mut(&id) {
+                            if result.is_ok() {
+                                connection.commands.insert(name);
+                            }
+                            connection.respond(request_id, result);
+                        }
+                    }
+                    method => {
+                        let connections = connections.clone();
+                        let _ = sender.send(ThreadEvent::UIEvent(UIEvent::Callback(CallbackFn(
+                            Box::new(move |context: &mut Context| {
+                                let result = handle_request(method, context);
+                                if let Some(connection) = connections.lock().unwrap().get_mut(&id) {
+                                    connection.respond(request_id, result);
+                                }
+                            }),
+                        ))));
+                    }
+                }
+            }
+            connections.lock().unwrap().remove(&id);
+        });
+        Ok(())
+    }
+
+    /// Send `event` to the plugins that subscribed to it.
+    pub fn emit(&self, event: PluginEvent) {
+        let name = event.name();
+        self.connections.lock().unwrap().retain(|_, connection| {
+            !connection.events.contains(name) || connection.send(&event).is_ok()
+        });
+    }
+
+    /// Pass `cmd` to the plugin that registered its first word as a command, if any.
+    pub fn run_command(&self, cmd: &str) -> bool {
+        let mut words = cmd.trim().splitn(2, char::is_whitespace);
+        let name = match words.next() {
+            Some(name) if !name.is_empty() => name.to_string(),
+            _ => return false,
+        };
+        let args = words.next().unwrap_or_default().trim().to_string();
+        let mut connections = self.connections.lock().unwrap();
+        if let Some((&id, connection)) = connections
+            .iter_mut()
+            .find(|(_, c)| c.commands.contains(&name))
+        {
+            if connection
+                .send(&PluginEvent::Command { name, args })
+                .is_err()
+            {
+                connections.remove(&id);
+            }
+            return true;
+        }
+        false
+    }
+}
+
+fn account_hash(context: &Context, name: &str) -> Result<AccountHash> {
+    context
+        .accounts
+        .iter()
+        .find(|(_, a)| a.name() == name)
+        .map(|(h, _)| *h)
+        .ok_or_else(|| MeliError::new(format!("Account {} not found.", name)))
+}
+
+fn handle_request(method: Method, context: &mut Context) -> Result<()> {
+    match method {
+        Method::Tag {
+            account,
+            mailbox,
+            envelope,
+            tag,
+            remove,
+        } => {
+            let account_hash = account_hash(context, &account)?;
+            let account = &mut context.accounts[&account_hash];
+            let mailbox_hash = account.mailbox_by_path(&mailbox)?;
+            if !account.contains_key(envelope) {
+                return Err(MeliError::new(format!("Envelope {} not found.", envelope)));
+            }
+            let flags: FlagOps = smallvec::smallvec![FlagOp::Tag(tag, !remove)];
+            account.check_flag_ops(mailbox_hash, &flags)?;
+            let fut =
+                account
+                    .backend
+                    .write()
+                    .unwrap()
+                    .set_flags(envelope.into(), mailbox_hash, flags)?;
+            let handle = account.job_executor.spawn_specialized(fut);
+            account.insert_job(
+                handle.job_id,
+                JobRequest::SetFlags {
+                    env_hashes: envelope.into(),
+                    handle,
+                },
+            );
+        }
+        Method::Move {
+            account,
+            mailbox,
+            envelope,
+            destination,
+        } => {
+            let account_hash = account_hash(context, &account)?;
+            let account = &mut context.accounts[&account_hash];
+            let mailbox_hash = account.mailbox_by_path(&mailbox)?;
+            let destination_hash = account.mailbox_by_path(&destination)?;
+            if !account.contains_key(envelope) {
+                return Err(MeliError::new(format!("Envelope {} not found.", envelope)));
+            }
+            let fut = account.backend.write().unwrap().copy_messages(
+                envelope.into(),
+                mailbox_hash,
+                destination_hash,
+                /* move? */ true,
+            )?;
+            let handle = account.job_executor.spawn_specialized(fut);
+            account.insert_job(
+                handle.job_id,
+                JobRequest::Generic {
+                    name: "message moving".into(),
+                    handle,
+                    on_finish: None,
+                    logging_level: melib::LoggingLevel::INFO,
+                },
+            );
+        }
+        Method::Compose {
+            account,
+            headers,
+            body,
+        } => {
+            let mut composer = Composer::with_account(account_hash(context, &account)?, context);
+            let mut draft = composer.draft().clone();
+            for (header, value) in headers {
+                draft.set_header(&header, value);
+            }
+            if let Some(body) = body {
+                draft.set_body(body);
+            }
+            composer.set_draft(draft);
+            context
+                .replies
+                .push_back(UIEvent::Action(Tab(New(Some(Box::new(composer))))));
+        }
+        Method::Command { command } => {
+            context.replies.push_back(UIEvent::Command(command));
+        }
+        Method::Notify { title, body } => {
+            context.replies.push_back(UIEvent::Notification(
+                title,
+                body,
+                Some(NotificationType::Info),
+            ));
+        }
+        Method::Subscribe { .. } | Method::RegisterCommand { .. } => {}
+    }
+    Ok(())
+}
+
+#[test]
+fn test_plugin_request_parse() {
+    let request: Request = serde_json::from_str(
+        r#"{"id": 3, "method": "tag", "params": {"account": "work", "mailbox": "INBOX", "envelope": 12, "tag": "todo"}}"#,
+    )
+    .unwrap();
+    assert_eq!(request.id, Some(serde_json::json!(3)));
+    assert!(matches!(
+        request.method,
+        Method::Tag {
+            envelope: 12,
+            remove: false,
+            ..
+        }
+    ));
+    let request: Request =
+        serde_json::from_str(r#"{"method": "command", "params": {"command": "sort date"}}"#)
+            .unwrap();
+    assert!(request.id.is_none());
+    assert!(matches!(request.method, Method::Command { .. }));
+    assert_eq!(
+        serde_json::to_string(&PluginEvent::Command {
+            name: "archive".to_string(),
+            args: String::new(),
+        })
+        .unwrap(),
+        r#"{"event":"command","name":"archive","args":""}"#
+    );
+}
@@ -45,22 +45,72 @@ use std::iter;
 
 type AsyncTask = async_task::Task<()>;
 
-fn find_task<T>(local: &Worker<T>, global: &Injector<T>, stealers: &[Stealer<T>]) -> Option<T> {
-    // Pop a task from the local queue, if not empty.
-    local.pop().or_else(|| {
-        // Otherwise, we need to look for a task elsewhere.
-        iter::repeat_with(|| {
-            // Try stealing a batch of tasks from the global queue.
-            global
-                .steal_batch_and_pop(local)
-                // Or try stealing a task from one of the other threads.
-                .or_else(|| stealers.iter().map(|s| s.steal()).collect())
-        })
-        // Loop while no task was stolen and any steal operation needs to be retried.
+fn find_task(
+    local: &Worker<MeliTask>,
+    queues: &PriorityQueues,
+    stealers: &[Stealer<MeliTask>],
+) -> Option<MeliTask> {
+    // Interactive tasks go first, even before the local queue.
+    iter::repeat_with(|| queues.interactive.steal())
         .find(|s| !s.is_retry())
-        // Extract the stolen task, if there is one.
         .and_then(|s| s.success())
-    })
+        // Then pop a task from the local queue, if not empty.
+        .or_else(|| local.pop())
+        .or_else(|| {
+            // Otherwise, we need to look for a task elsewhere.
+            iter::repeat_with(|| {
+                // Try stealing a batch of tasks from the global queue.
+                queues
+                    .normal
+                    .steal_batch_and_pop(local)
+                    // Or try stealing a task from one of the other threads.
+                    .or_else(|| stealers.iter().map(|s| s.steal()).collect())
+                    // Background tasks are stolen one at a time, so that they never wait in a
+                    // local queue ahead of other tasks.
+                    .or_else(|| queues.background.steal())
+            })
+            // Loop while no task was stolen and any steal operation needs to be retried.
+            .find(|s| !s.is_retry())
+            // Extract the stolen task, if there is one.
+            .and_then(|s| s.success())
+        })
+}
+
+/// Scheduling priority of a job. Workers run interactive jobs before anything else and
+/// background jobs only when there is nothing else to run.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum JobPriority {
+    /// Jobs the user is waiting for, such as opening a message.
+    Interactive,
+    Normal,
+    /// Jobs nobody is waiting for, such as downloading or indexing messages ahead of time.
+    Background,
+}
+
+/// Queues of scheduled tasks, one per [`JobPriority`].
+#[derive(Debug)]
+struct PriorityQueues {
+    interactive: Injector<MeliTask>,
+    normal: Injector<MeliTask>,
+    background: Injector<MeliTask>,
+}
+
+impl PriorityQueues {
+    fn new() -> Self {
+        PriorityQueues {
+            interactive: Injector::new(),
+            normal: Injector::new(),
+            background: Injector::new(),
+        }
+    }
+
+    fn get(&self, priority: JobPriority) -> &Injector<MeliTask> {
+        match priority {
+            JobPriority::Interactive => &self.interactive,
+            JobPriority::Normal => &self.normal,
+            JobPriority::Background => &self.background,
+        }
+    }
 }
 
 macro_rules! uuid_hash_type {
@@ -103,7 +153,7 @@ pub struct MeliTask {
 
 #[derive(Debug)]
 pub struct JobExecutor {
-    global_queue: Arc<Injector<MeliTask>>,
+    global_queues: Arc<PriorityQueues>,
     workers: Vec<Stealer<MeliTask>>,
     sender: Sender<ThreadEvent>,
     parkers: Vec<Unparker>,
@@ -155,7 +205,7 @@ impl JobExecutor {
     pub fn new(sender: Sender<ThreadEvent>) -> Self {
         // Create a queue.
         let mut ret = JobExecutor {
-            global_queue: Arc::new(Injector::new()),
+            global_queues: Arc::new(PriorityQueues::new()),
             workers: vec![],
             parkers: vec![],
             sender,
@@ -182,7 +232,7 @@ impl JobExecutor {
 
         // Spawn executor threads the first time the queue is created.
         for (i, (local, parker)) in workers.into_iter().enumerate() {
-            let global = ret.global_queue.clone();
+            let global = ret.global_queues.clone();
             let stealers = ret.workers.clone();
             thread::Builder::new()
                 .name(format!("meli-executor-{}", i))
@@ -207,6 +257,15 @@ impl JobExecutor {
 
     /// Spawns a future with a generic return value `R`
     pub fn spawn_specialized<F, R>(&self, future: F) -> JoinHandle<R>
+    where
+        F: Future<Output = R> + Send + 'static,
+        R: Send + 'static,
+    {
+        self.spawn_prioritized(future, JobPriority::Normal)
+    }
+
+    /// Spawns a future with a generic return value `R`, scheduled with `priority`.
+    pub fn spawn_prioritized<F, R>(&self, future: F, priority: JobPriority) -> JoinHandle<R>
     where
         F: Future<Output = R> + Send + 'static,
         R: Send + 'static,
@@ -214,7 +273,7 @@ impl JobExecutor {
         let (sender, receiver) = oneshot::channel();
         let finished_sender = self.sender.clone();
         let job_id = JobId::new();
-        let injector = self.global_queue.clone();
+        let queues = self.global_queues.clone();
         // Create a task and schedule it for execution.
         let (task, handle) = async_task::spawn(
             async move {
@@ -226,7 +285,7 @@ impl JobExecutor {
                 Ok(())
             },
             move |task| {
-                injector.push(MeliTask {
+                queues.get(priority).push(MeliTask {
                     task,
                     id: job_id,
                     timer: false,
@@ -286,7 +345,7 @@ impl JobExecutor {
     fn arm_timer(&self, id: Uuid, value: Duration) {
         let job_id = JobId::new();
         let sender = self.sender.clone();
-        let injector = self.global_queue.clone();
+        let queues = self.global_queues.clone();
         let timers = self.timers.clone();
         let (task, handle) = async_task::spawn(
             async move {
@@ -312,7 +371,7 @@ impl JobExecutor {
                 }
             },
             move |task| {
-                injector.push(MeliTask {
+                queues.normal.push(MeliTask {
                     task,
                     id: job_id,
                     timer: true,
@@ -384,3 +443,25 @@ impl Future for JoinHandle {
     }
 }
 */
+
+#[test]
+fn test_find_task_priority() {
+    let queues = PriorityQueues::new();
+    let local = Worker::new_fifo();
+    let task = |id| MeliTask {
+        task: async_task::spawn(async {}, |_| {}, ()).0,
+        id,
+        timer: false,
+    };
+    let (interactive, normal, background) = (JobId::new(), JobId::new(), JobId::new());
+    queues.background.push(task(background));
+    queues.normal.push(task(normal));
+    queues.interactive.push(task(interactive));
+    let order = iter::repeat_with(|| find_task(&local, &queues, &[]).map(|t| t.id))
+        .take(4)
+        .collect::<Vec<_>>();
+    assert_eq!(
+        order,
+        vec![Some(interactive), Some(normal), Some(background), None]
+    );
+}
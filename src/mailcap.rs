@@ -165,8 +165,8 @@ impl MailcapEntry {
                         "%t" => a.content_type().to_string(),
                         param if param.starts_with("%{") && param.ends_with('}') => {
                             let param = &param["%{".len()..param.len() - 1];
-                            if let Some(v) = params.iter().find(|(k, _)| *k == param.as_bytes()) {
-                                String::from_utf8_lossy(v.1).into()
+                            if let Some(v) = params.iter().find(|(k, _)| &**k == param.as_bytes()) {
+                                String::from_utf8_lossy(&v.1).into()
                             } else if param == "charset" {
                                 String::from("utf-8")
                             } else {
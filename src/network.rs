@@ -0,0 +1,143 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Detection of the loss and return of the network link.
+ *
+ * The network interfaces are probed every couple of seconds. When no interface other than
+ * loopback is up, remote accounts are marked offline right away instead of waiting for their
+ * connections to time out, and no connection attempts are made until the link is back. Accounts
+ * then reconnect after a short jittered delay, so that they don't all reconnect at once.
+ */
+
+use crate::conf::accounts::Account;
+use crate::types::UIEvent;
+use crate::StatusEvent;
+use indexmap::IndexMap;
+use melib::backends::AccountHash;
+use nix::net::if_::InterfaceFlags;
+use nix::sys::socket::SockAddr;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant, SystemTime, UNIX_EPOCH};
+
+/// Time between two probes of the network interfaces.
+const PROBE_INTERVAL: Duration = Duration::from_secs(2);
+/// Delay before reconnecting once the link is back, before jitter.
+const RECONNECT_DELAY: Duration = Duration::from_secs(2);
+
+#[derive(Debug, Default)]
+pub struct NetworkMonitor {
+    /// The result of the last probe, `None` before the first one.
+    link_up: Option<bool>,
+    last_probe: Option<Instant>,
+}
+
+/// If an interface other than loopback is up and running with an IP address. If the interfaces
+/// can't be listed the link is assumed to be up.
+pub fn link_is_up() -> bool {
+    match nix::ifaddrs::getifaddrs() {
+        Ok(addrs) => addrs.into_iter().any(|ifaddr| {
+            ifaddr
+                .flags
+                .contains(InterfaceFlags::IFF_UP | InterfaceFlags::IFF_RUNNING)
+                && !ifaddr.flags.contains(InterfaceFlags::IFF_LOOPBACK)
+                && matches!(ifaddr.address, Some(SockAddr::Inet(_)))
+        }),
+        Err(err) => {
+            debug!("Could not list network interfaces: {}", err);
+            true
+        }
+    }
+}
+
+/// `delay` plus up to half of it, picked from `seed` and the current time so that accounts
+/// retrying at the same time spread out.
+pub fn jittered(delay: Duration, seed: u64) -> Duration {
+    let max = delay.as_millis() as u64 / 2;
+    if max == 0 {
+        return delay;
+    }
+    let nanos = SystemTime::now()
+        .duration_since(UNIX_EPOCH)
+        .map(|d| u64::from(d.subsec_nanos()))
+        .unwrap_or(0);
+    delay + Duration::from_millis((seed ^ nanos) % (max + 1))
+}
+
+impl NetworkMonitor {
+    /// If the last probe found the network link up.
+    pub fn is_link_up(&self) -> bool {
+        self.link_up.unwrap_or(true)
+    }
+
+    /// Probe the network interfaces if it's time to, and mark connected accounts offline or
+    /// reconnect them when the link goes down or comes back. Called periodically.
+    pub fn tick(
+        &mut self,
+        accounts: &mut IndexMap<AccountHash, Account>,
+        replies: &mut VecDeque<UIEvent>,
+    ) {
+        let now = Instant::now();
+        if self
+            .last_probe
+            .map(|t| now.duration_since(t) < PROBE_INTERVAL)
+            .unwrap_or(false)
+        {
+            return;
+        }
+        self.last_probe = Some(now);
+        let link_up = link_is_up();
+        if self.link_up == Some(link_up) || (self.link_up.is_none() && link_up) {
+            self.link_up = Some(link_up);
+            return;
+        }
+        self.link_up = Some(link_up);
+        for account in accounts.values_mut() {
+            if !account.is_connected() {
+                continue;
+            }
+            if link_up {
+                account.network_restored(jittered(RECONNECT_DELAY, account.hash()));
+            } else {
+                account.network_lost();
+            }
+        }
+        replies.push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(
+            if link_up {
+                "Network is back, reconnecting.".to_string()
+            } else {
+                "Network is down.".to_string()
+            },
+        )));
+    }
+}
+
+#[test]
+fn test_network_jittered() {
+    let delay = Duration::from_secs(4);
+    for seed in 0..64 {
+        let d = jittered(delay, seed);
+        assert!(d >= delay && d <= delay + delay / 2);
+    }
+    assert_eq!(
+        jittered(Duration::from_millis(1), 7),
+        Duration::from_millis(1)
+    );
+}
@@ -70,8 +70,9 @@ impl MailBackend for PluginBackend {
             is_remote: false,
             supports_search: false,
             extensions: None,
-            supports_tags: false,
+            tag_support: TagSupport::None,
             supports_submission: false,
+            lightweight_sync: false,
         };
         CAPABILITIES
     }
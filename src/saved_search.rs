@@ -0,0 +1,151 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Saved searches are virtual mailboxes listing the messages of an account that match a search
+ * query, whatever the account's backend. The account keeps their messages up to date, see
+ * `Account::update_saved_searches` and the `saved_searches` account setting.
+ */
+
+use melib::backends::{
+    BackendMailbox, Mailbox, MailboxHash, MailboxPermissions, SpecialUsageMailbox,
+};
+use melib::search::{Query, QueryTrait};
+use melib::{Envelope, Result};
+use std::collections::hash_map::DefaultHasher;
+use std::convert::TryFrom;
+use std::hash::{Hash, Hasher};
+use std::sync::{Arc, Mutex};
+
+#[derive(Debug, Clone)]
+pub struct SavedSearch {
+    hash: MailboxHash,
+    name: String,
+    query: Query,
+    /// Unseen and total messages, shared by the clones given out as `Mailbox`.
+    count: Arc<Mutex<(usize, usize)>>,
+}
+
+impl SavedSearch {
+    pub fn new(name: &str, query: &str) -> Result<Self> {
+        let query = Query::try_from(query)?;
+        let mut hasher = DefaultHasher::new();
+        "saved-search".hash(&mut hasher);
+        name.hash(&mut hasher);
+        Ok(SavedSearch {
+            hash: hasher.finish(),
+            name: name.to_string(),
+            query,
+            count: Arc::new(Mutex::new((0, 0))),
+        })
+    }
+
+    pub fn is_match(&self, envelope: &Envelope) -> bool {
+        envelope.is_match(&self.query)
+    }
+
+    pub fn set_count(&self, unseen: usize, total: usize) {
+        *self.count.lock().unwrap() = (unseen, total);
+    }
+}
+
+impl BackendMailbox for SavedSearch {
+    fn hash(&self) -> MailboxHash {
+        self.hash
+    }
+
+    fn name(&self) -> &str {
+        &self.name
+    }
+
+    fn path(&self) -> &str {
+        &self.name
+    }
+
+    fn change_name(&mut self, _s: &str) {}
+
+    fn clone(&self) -> Mailbox {
+        Box::new(std::clone::Clone::clone(self))
+    }
+
+    fn children(&self) -> &[MailboxHash] {
+        &[]
+    }
+
+    fn parent(&self) -> Option<MailboxHash> {
+        None
+    }
+
+    fn is_subscribed(&self) -> bool {
+        true
+    }
+
+    fn set_is_subscribed(&mut self, _new_val: bool) -> Result<()> {
+        Ok(())
+    }
+
+    fn set_special_usage(&mut self, _new_val: SpecialUsageMailbox) -> Result<()> {
+        Ok(())
+    }
+
+    fn special_usage(&self) -> SpecialUsageMailbox {
+        SpecialUsageMailbox::Normal
+    }
+
+    fn permissions(&self) -> MailboxPermissions {
+        MailboxPermissions {
+            create_messages: false,
+            remove_messages: false,
+            set_flags: true,
+            create_child: false,
+            rename_messages: false,
+            delete_messages: false,
+            delete_mailbox: false,
+            change_permissions: false,
+        }
+    }
+
+    fn count(&self) -> Result<(usize, usize)> {
+        Ok(*self.count.lock()?)
+    }
+}
+
+#[test]
+fn test_saved_search() {
+    let search = SavedSearch::new("Lunch", "subject:Lunch and not flags:seen").unwrap();
+    assert_eq!(search.path(), "Lunch");
+    assert_ne!(
+        search.hash(),
+        SavedSearch::new("Dinner", "subject:lunch").unwrap().hash()
+    );
+    let mut envelope = Envelope::from_bytes(
+        b"From: a@example.com\nMessage-ID: <lunch.1@example.com>\nSubject: Lunch?\n\nHi\n",
+        None,
+    )
+    .unwrap();
+    assert!(search.is_match(&envelope));
+    envelope.set_seen();
+    assert!(!search.is_match(&envelope));
+
+    let mailbox = BackendMailbox::clone(&search);
+    search.set_count(1, 3);
+    assert_eq!(mailbox.count().unwrap(), (1, 3));
+    assert!(SavedSearch::new("Broken", "subject:(").is_err());
+}
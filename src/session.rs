@@ -0,0 +1,94 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Save the open mail listings on exit and restore them on the next start, if
+ * `terminal.restore_session` is set.
+ */
+use melib::{MeliError, Result};
+
+const SESSION_FILE: &str = "session.json";
+
+/// UI state saved between runs of meli.
+#[derive(Debug, Default, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Session {
+    /// Mail listing tabs in tab order. The first one is the main listing.
+    pub listings: Vec<ListingSession>,
+    /// Index in `listings` of the focused tab, if it was a listing.
+    pub active_listing: Option<usize>,
+}
+
+/// Position of a mail listing tab.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ListingSession {
+    pub account: String,
+    /// Path of the open mailbox, or `None` if the account's status page was open.
+    pub mailbox: Option<String>,
+    /// Index of the entry under the cursor.
+    pub cursor: usize,
+}
+
+impl Session {
+    /// The session saved by the last run, if any.
+    pub fn load() -> Result<Option<Session>> {
+        let path = match xdg::BaseDirectories::with_prefix("meli")
+            .map_err(|err| MeliError::new(err.to_string()))?
+            .find_data_file(SESSION_FILE)
+        {
+            Some(path) => path,
+            None => return Ok(None),
+        };
+        let s = std::fs::read_to_string(&path)?;
+        Ok(Some(serde_json::from_str(&s).map_err(|err| {
+            MeliError::new(format!("Could not parse {}: {}", path.display(), err))
+        })?))
+    }
+
+    pub fn save(&self) -> Result<()> {
+        let path = xdg::BaseDirectories::with_prefix("meli")
+            .map_err(|err| MeliError::new(err.to_string()))?
+            .place_data_file(SESSION_FILE)?;
+        let s =
+            serde_json::to_string_pretty(self).map_err(|err| MeliError::new(err.to_string()))?;
+        std::fs::write(&path, s)?;
+        Ok(())
+    }
+}
+
+#[test]
+fn test_session_roundtrip() {
+    let session = Session {
+        listings: vec![
+            ListingSession {
+                account: "work".to_string(),
+                mailbox: Some("INBOX".to_string()),
+                cursor: 12,
+            },
+            ListingSession {
+                account: "personal".to_string(),
+                mailbox: None,
+                cursor: 0,
+            },
+        ],
+        active_listing: Some(1),
+    };
+    let s = serde_json::to_string(&session).unwrap();
+    assert_eq!(serde_json::from_str::<Session>(&s).unwrap(), session);
+}
@@ -0,0 +1,79 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Snoozed messages are moved to the account's snooze mailbox and moved back, unread, once their
+ * time has come. See the `snooze` command.
+ */
+
+use melib::datetime::{timestamp_from_string, UnixTimestamp};
+use melib::{MeliError, Result};
+
+/// A message waiting in the snooze mailbox. Messages are identified by their Message-ID since
+/// their envelope hash changes when they are moved.
+#[derive(Debug, Clone, PartialEq, Deserialize, Serialize)]
+pub struct SnoozedMessage {
+    /// Raw Message-ID of the message.
+    pub message_id: String,
+    /// Path of the mailbox the message is moved back to.
+    pub mailbox_path: String,
+    pub until: UnixTimestamp,
+}
+
+/// Parse the time to snooze until: a duration from `now` such as `30m`, `2h`, `3d` or `1w`, or a
+/// local date `YYYY-MM-DD` with an optional `HH:MM` time.
+pub fn parse_until(s: &str, now: UnixTimestamp) -> Result<UnixTimestamp> {
+    let s = s.trim();
+    let unit = match s.chars().last() {
+        Some('m') => Some(60),
+        Some('h') => Some(60 * 60),
+        Some('d') => Some(24 * 60 * 60),
+        Some('w') => Some(7 * 24 * 60 * 60),
+        _ => None,
+    };
+    if let Some(unit) = unit {
+        if let Ok(n) = s[..s.len() - 1].parse::<u64>() {
+            return Ok(now + n * unit);
+        }
+    }
+    for fmt in &["%Y-%m-%d %H:%M", "%Y-%m-%d"] {
+        if let Some(t) = timestamp_from_string(s, fmt)? {
+            return Ok(t);
+        }
+    }
+    Err(MeliError::new(format!(
+        "Invalid snooze time `{}`: use a duration like 30m, 2h, 3d or 1w, or a date like 2024-01-31 09:00.",
+        s
+    )))
+}
+
+#[test]
+fn test_snooze_parse_until() {
+    assert_eq!(parse_until("30m", 1000).unwrap(), 1000 + 30 * 60);
+    assert_eq!(parse_until("2h", 1000).unwrap(), 1000 + 2 * 60 * 60);
+    assert_eq!(parse_until(" 3d", 1000).unwrap(), 1000 + 3 * 24 * 60 * 60);
+    assert_eq!(parse_until("1w", 1000).unwrap(), 1000 + 7 * 24 * 60 * 60);
+    assert_eq!(
+        parse_until("2024-01-31 09:00", 1000).unwrap(),
+        parse_until("2024-01-31", 1000).unwrap() + 9 * 60 * 60
+    );
+    assert!(parse_until("soon", 1000).is_err());
+    assert!(parse_until("m", 1000).is_err());
+}
@@ -27,8 +27,8 @@ use melib::search::{
     Query::{self, *},
 };
 use melib::{
-    backends::{MailBackend, ResultFuture},
-    email::{Envelope, EnvelopeHash},
+    backends::{MailBackend, MailboxHash, ResultFuture},
+    email::{attachment_types::ContentType, Attachment, Envelope, EnvelopeHash},
     log,
     sqlite3::{
         self as melib_sqlite3,
@@ -36,9 +36,10 @@ use melib::{
         DatabaseDescription,
     },
     thread::{SortField, SortOrder},
-    MeliError, Result, ERROR,
+    Flag, MeliError, Result, ERROR, INFO,
 };
 
+use crate::types::{StatusEvent, ThreadEvent, UIEvent};
 use smallvec::SmallVec;
 use std::convert::TryInto;
 use std::path::PathBuf;
@@ -62,28 +63,21 @@ init_script:Some( "CREATE TABLE IF NOT EXISTS envelopes (
                     flags            INTEGER NOT NULL,
                     has_attachments  BOOLEAN NOT NULL,
                     body_text        TEXT NOT NULL,
+                    attachment_names TEXT NOT NULL,
+                    attachment_types TEXT NOT NULL,
+                    list_id          TEXT NOT NULL,
                     timestamp        BLOB NOT NULL
                    );
-        CREATE TABLE IF NOT EXISTS folders (
-                    id               INTEGER PRIMARY KEY,
-                    account_id       INTEGER NOT NULL REFERENCES accounts ON UPDATE CASCADE,
-                    hash             BLOB NOT NULL,
-                    date             TEXT NOT NULL,
-                    name             TEXT NOT NULL
-                  );
         CREATE TABLE IF NOT EXISTS accounts (
                     id               INTEGER PRIMARY KEY,
                     name             TEXT NOT NULL UNIQUE
                   );
-        CREATE TABLE IF NOT EXISTS folder_and_envelope (
-                    folder_id        INTEGER NOT NULL,
-                    envelope_id      INTEGER NOT NULL,
-                    PRIMARY KEY (folder_id, envelope_id),
-                    FOREIGN KEY(folder_id) REFERENCES folders(id) ON UPDATE CASCADE,
-                    FOREIGN KEY(envelope_id) REFERENCES envelopes(id) ON UPDATE CASCADE
+        CREATE TABLE IF NOT EXISTS mailbox_envelopes (
+                    mailbox_hash     BLOB NOT NULL,
+                    envelope_hash    BLOB NOT NULL,
+                    PRIMARY KEY (mailbox_hash, envelope_hash)
                   );
-      CREATE INDEX IF NOT EXISTS folder_env_idx ON folder_and_envelope(folder_id);
-      CREATE INDEX IF NOT EXISTS env_folder_idx ON folder_and_envelope(envelope_id);
+      CREATE INDEX IF NOT EXISTS mailbox_envelopes_envelope_idx ON mailbox_envelopes(envelope_hash);
       CREATE UNIQUE INDEX IF NOT EXISTS acc_idx ON accounts(name);
 
 
@@ -109,11 +103,104 @@ CREATE TRIGGER IF NOT EXISTS envelopes_au AFTER UPDATE ON envelopes BEGIN
   INSERT INTO fts(fts, rowid, subject, body_text) VALUES('delete', old.id, old.subject, old.body_text);
   INSERT INTO fts(rowid, subject, body_text) VALUES (new.id, new.subject, new.body_text);
 END; "),
-version: 1,
+version: 4,
 };
 
-pub fn db_path() -> Result<PathBuf> {
-    melib_sqlite3::db_path(DB.name)
+/// Each account has its own database, named after the account with every character other than
+/// ASCII alphanumerics, `-` and `_` percent-encoded, so that any account name makes a distinct,
+/// plain file name.
+fn db_identifier(acc_name: &str) -> String {
+    let mut ret = String::with_capacity(acc_name.len());
+    for b in acc_name.bytes() {
+        if b.is_ascii_alphanumeric() || b == b'-' || b == b'_' {
+            ret.push(b as char);
+        } else {
+            ret.push_str(&format!("%{:02X}", b));
+        }
+    }
+    ret
+}
+
+pub fn db_path(acc_name: &str) -> Result<PathBuf> {
+    melib_sqlite3::db_path(&format!("{}_{}", db_identifier(acc_name), DB.name))
+}
+
+/// Remove the database that was shared by all accounts, and rename an account's database that
+/// was named after the account as is.
+fn migrate_old_index(acc_name: &str) -> Result<()> {
+    let shared = melib_sqlite3::db_path(DB.name)?;
+    if shared.exists() {
+        log(
+            format!(
+                "Removing shared search index {}, replaced by per-account ones.",
+                shared.display()
+            ),
+            INFO,
+        );
+        std::fs::remove_file(&shared)?;
+    }
+    /* Account names with a `/` weren't plain file names, don't touch them. */
+    if !acc_name.contains('/') {
+        let old = melib_sqlite3::db_path(&format!("{}_{}", acc_name, DB.name))?;
+        let new = db_path(acc_name)?;
+        if old != new && old.exists() && !new.exists() {
+            std::fs::rename(&old, &new)?;
+        }
+    }
+    Ok(())
+}
+
+/// Check the integrity of an account's index on startup. A missing index is created and an index
+/// with a different schema version is dropped and recreated. Returns `true` if the index is new
+/// and has to be repopulated.
+pub fn check_index(acc_name: &str) -> Result<bool> {
+    if let Err(err) = migrate_old_index(acc_name) {
+        log(
+            format!("Could not migrate {} search index: {}", acc_name, err),
+            ERROR,
+        );
+    }
+    let path = db_path(acc_name)?;
+    let needs_rebuild = if !path.exists() {
+        true
+    } else {
+        let conn = melib_sqlite3::open_db(path)?;
+        let version: i32 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        if version as u32 != DB.version {
+            log(
+                format!(
+                    "{} search index has schema version {} but expected {}, rebuilding.",
+                    acc_name, version, DB.version
+                ),
+                INFO,
+            );
+            drop(conn);
+            melib_sqlite3::reset_db(&DB, Some(&db_identifier(acc_name)))?;
+            true
+        } else {
+            false
+        }
+    };
+    melib_sqlite3::open_or_create_db(&DB, Some(&db_identifier(acc_name)))?;
+    Ok(needs_rebuild)
+}
+
+/// Record that `env_hash` is in each of `mailbox_hashes`.
+fn insert_mailboxes(
+    conn: &rusqlite::Connection,
+    env_hash: EnvelopeHash,
+    mailbox_hashes: &[MailboxHash],
+) -> Result<()> {
+    for mailbox_hash in mailbox_hashes {
+        conn.execute(
+            "INSERT OR IGNORE INTO mailbox_envelopes (mailbox_hash, envelope_hash) VALUES (?1, ?2)",
+            params![
+                mailbox_hash.to_be_bytes().to_vec(),
+                env_hash.to_be_bytes().to_vec()
+            ],
+        )?;
+    }
+    Ok(())
 }
 
 //#[inline(always)]
@@ -144,8 +231,9 @@ pub async fn insert(
     envelope: Envelope,
     backend: Arc<RwLock<Box<dyn MailBackend>>>,
     acc_name: String,
+    mailbox_hashes: Vec<MailboxHash>,
 ) -> Result<()> {
-    let db_path = db_path()?;
+    let db_path = db_path(&acc_name)?;
     if !db_path.exists() {
         return Err(MeliError::new(
             "Database hasn't been initialised. Run `reindex` command",
@@ -161,7 +249,7 @@ pub async fn insert(
         .as_bytes()?;
 
     let body = match op.await.map(|bytes| envelope.body_bytes(&bytes)) {
-        Ok(body) => body.text(),
+        Ok(body) => body,
         Err(err) => {
             debug!(
                 "{}",
@@ -214,31 +302,27 @@ pub async fn insert(
             .unwrap();
         x
     };
-    if let Err(err) = conn.execute(
-            "INSERT OR REPLACE INTO envelopes (account_id, hash, date, _from, _to, cc, bcc, subject, message_id, in_reply_to, _references, flags, has_attachments, body_text, timestamp)
-              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
-              params![account_id, envelope.hash().to_be_bytes().to_vec(), envelope.date_as_str(), envelope.field_from_to_string(), envelope.field_to_to_string(), envelope.field_cc_to_string(), envelope.field_bcc_to_string(), envelope.subject().into_owned().trim_end_matches('\u{0}'), envelope.message_id_display().to_string(), envelope.in_reply_to_display().map(|f| f.to_string()).unwrap_or(String::new()), envelope.field_references_to_string(), i64::from(envelope.flags().bits()), if envelope.has_attachments() { 1 } else { 0 }, body, envelope.date().to_be_bytes().to_vec()],
-        )
-            .map_err(|e| MeliError::new(e.to_string())) {
-                debug!(
-                        "Failed to insert envelope {}: {}",
-                        envelope.message_id_display(),
-                        err.to_string()
-                    );
-                log(
-                    format!(
-                        "Failed to insert envelope {}: {}",
-                        envelope.message_id_display(),
-                        err.to_string()
-                    ),
-                    ERROR,
-                );
-              }
+    if let Err(err) = insert_envelope(&conn, account_id, &envelope, &body) {
+        debug!(
+            "Failed to insert envelope {}: {}",
+            envelope.message_id_display(),
+            err.to_string()
+        );
+        log(
+            format!(
+                "Failed to insert envelope {}: {}",
+                envelope.message_id_display(),
+                err.to_string()
+            ),
+            ERROR,
+        );
+    }
+    insert_mailboxes(&conn, envelope.hash(), &mailbox_hashes)?;
     Ok(())
 }
 
-pub fn remove(env_hash: EnvelopeHash) -> Result<()> {
-    let db_path = db_path()?;
+pub fn remove(acc_name: &str, env_hash: EnvelopeHash) -> Result<()> {
+    let db_path = db_path(acc_name)?;
     if !db_path.exists() {
         return Err(MeliError::new(
             "Database hasn't been initialised. Run `reindex` command",
@@ -251,6 +335,12 @@ pub fn remove(env_hash: EnvelopeHash) -> Result<()> {
             "DELETE FROM envelopes WHERE hash = ?",
             params![env_hash.to_be_bytes().to_vec(),],
         )
+        .and_then(|_| {
+            conn.execute(
+                "DELETE FROM mailbox_envelopes WHERE envelope_hash = ?",
+                params![env_hash.to_be_bytes().to_vec(),],
+            )
+        })
         .map_err(|e| MeliError::new(e.to_string()))
     {
         debug!(
@@ -271,14 +361,130 @@ pub fn remove(env_hash: EnvelopeHash) -> Result<()> {
     Ok(())
 }
 
+/// Number of envelopes whose bodies are fetched and inserted in one transaction.
+const BATCH_SIZE: usize = 200;
+
+/// Add `envelopes` that are not already in the index, creating the database if needed. Bodies are
+/// fetched and inserted in batches of `BATCH_SIZE`, each inside a single transaction.
+pub async fn insert_batch(
+    envelopes: Vec<Envelope>,
+    backend: Arc<RwLock<Box<dyn MailBackend>>>,
+    acc_name: String,
+    mailbox_hash: MailboxHash,
+) -> Result<()> {
+    let mut conn = melib_sqlite3::open_or_create_db(&DB, Some(&db_identifier(&acc_name)))?;
+    conn.execute(
+        "INSERT OR IGNORE INTO accounts (name) VALUES (?1)",
+        params![acc_name.as_str(),],
+    )
+    .chain_err_summary(|| "Failed to update index:")?;
+    let account_id: i32 = conn.query_row(
+        "SELECT id FROM accounts WHERE name = ?",
+        params![acc_name.as_str()],
+        |row| row.get(0),
+    )?;
+    {
+        let tx = conn.transaction()?;
+        for envelope in &envelopes {
+            insert_mailboxes(&tx, envelope.hash(), &[mailbox_hash])?;
+        }
+        tx.commit()?;
+    }
+    let envelopes = {
+        let mut stmt = conn.prepare("SELECT 1 FROM envelopes WHERE hash = ?")?;
+        envelopes
+            .into_iter()
+            .filter(|e| {
+                !stmt
+                    .exists(params![e.hash().to_be_bytes().to_vec()])
+                    .unwrap_or(false)
+            })
+            .collect::<Vec<Envelope>>()
+    };
+    for chunk in envelopes.chunks(BATCH_SIZE) {
+        let mut bodies = Vec::with_capacity(chunk.len());
+        for envelope in chunk {
+            let op = backend
+                .read()
+                .unwrap()
+                .operation(envelope.hash())?
+                .as_bytes()?;
+            match op.await {
+                Ok(bytes) => bodies.push((envelope, envelope.body_bytes(&bytes))),
+                Err(err) => {
+                    log(
+                        format!(
+                            "Failed to open envelope {}: {}",
+                            envelope.message_id_display(),
+                            err.to_string()
+                        ),
+                        ERROR,
+                    );
+                }
+            }
+        }
+        let tx = conn.transaction()?;
+        for (envelope, body) in bodies {
+            insert_envelope(&tx, account_id, envelope, &body).chain_err_summary(|| {
+                format!(
+                    "Failed to insert envelope {}",
+                    envelope.message_id_display()
+                )
+            })?;
+        }
+        tx.commit()?;
+    }
+    Ok(())
+}
+
+fn insert_envelope(
+    conn: &rusqlite::Connection,
+    account_id: i32,
+    e: &Envelope,
+    body: &Attachment,
+) -> Result<()> {
+    let body_text = body.searchable_text().replace('\0', "");
+    let mut attachment_names = vec![];
+    let mut attachment_types = vec![];
+    for a in body.attachments() {
+        if let ContentType::Multipart { .. } = a.content_type() {
+            continue;
+        }
+        let filename = a.filename();
+        if filename.is_none() && a.content_type().is_text() {
+            /* Part of the message text */
+            continue;
+        }
+        attachment_names.extend(filename);
+        attachment_types.push(a.mime_type());
+    }
+    conn.execute("INSERT OR REPLACE INTO envelopes (account_id, hash, date, _from, _to, cc, bcc, subject, message_id, in_reply_to, _references, flags, has_attachments, body_text, attachment_names, attachment_types, list_id, timestamp)
+              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15, ?16, ?17, ?18)",
+              params![account_id, e.hash().to_be_bytes().to_vec(), e.date_as_str(), e.field_from_to_string(), e.field_to_to_string(), e.field_cc_to_string(), e.field_bcc_to_string(), e.subject().into_owned().trim_end_matches('\u{0}'), e.message_id_display().to_string(), e.in_reply_to_display().map(|f| f.to_string()).unwrap_or(String::new()), e.field_references_to_string(), i64::from(e.flags().bits()), if e.has_attachments() { 1 } else { 0 }, body_text, attachment_names.join("\n"), attachment_types.join(" "), e.list_id().unwrap_or_default(), e.date().to_be_bytes().to_vec()],
+    )?;
+    Ok(())
+}
+
+/// Drop the account's index and rebuild it from every envelope currently loaded, reporting
+/// progress in the status bar.
 pub fn index(context: &mut crate::state::Context, account_index: usize) -> ResultFuture<()> {
+    let sender = context.sender.clone();
     let account = &context.accounts[account_index];
     let (acc_name, acc_mutex, backend_mutex): (String, Arc<RwLock<_>>, Arc<_>) = (
         account.name().to_string(),
         account.collection.envelopes.clone(),
         account.backend.clone(),
     );
-    let conn = melib_sqlite3::open_or_create_db(&DB, None)?;
+    let mailboxes = account
+        .collection
+        .mailboxes
+        .read()
+        .unwrap()
+        .iter()
+        .map(|(h, envs)| (*h, envs.iter().cloned().collect::<Vec<EnvelopeHash>>()))
+        .collect::<Vec<(MailboxHash, Vec<EnvelopeHash>)>>();
+    melib_sqlite3::reset_db(&DB, Some(&db_identifier(&acc_name)))?;
+    let conn = melib_sqlite3::open_or_create_db(&DB, Some(&db_identifier(&acc_name)))?;
     let env_hashes = acc_mutex
         .read()
         .unwrap()
@@ -306,16 +512,7 @@ pub fn index(context: &mut crate::state::Context, account_index: usize) -> Resul
             x
         };
         let mut ctr = 0;
-        debug!(
-            "{}",
-            format!(
-                "Rebuilding {} index. {}/{}",
-                acc_name,
-                ctr,
-                env_hashes.len()
-            )
-        );
-        for chunk in env_hashes.chunks(200) {
+        for chunk in env_hashes.chunks(BATCH_SIZE) {
             ctr += chunk.len();
             for env_hash in chunk {
                 let mut op = backend_mutex.read().unwrap().operation(*env_hash)?;
@@ -325,25 +522,45 @@ pub fn index(context: &mut crate::state::Context, account_index: usize) -> Resul
                     .chain_err_summary(|| format!("Failed to open envelope {}", env_hash))?;
                 let envelopes_lck = acc_mutex.read().unwrap();
                 if let Some(e) = envelopes_lck.get(&env_hash) {
-                    let body = e.body_bytes(&bytes).text().replace('\0', "");
-                    conn.execute("INSERT OR REPLACE INTO envelopes (account_id, hash, date, _from, _to, cc, bcc, subject, message_id, in_reply_to, _references, flags, has_attachments, body_text, timestamp)
-              VALUES (?1, ?2, ?3, ?4, ?5, ?6, ?7, ?8, ?9, ?10, ?11, ?12, ?13, ?14, ?15)",
-              params![account_id, e.hash().to_be_bytes().to_vec(), e.date_as_str(), e.field_from_to_string(), e.field_to_to_string(), e.field_cc_to_string(), e.field_bcc_to_string(), e.subject().into_owned().trim_end_matches('\u{0}'), e.message_id_display().to_string(), e.in_reply_to_display().map(|f| f.to_string()).unwrap_or(String::new()), e.field_references_to_string(), i64::from(e.flags().bits()), if e.has_attachments() { 1 } else { 0 }, body, e.date().to_be_bytes().to_vec()],
-                        ).chain_err_summary(|| format!( "Failed to insert envelope {}", e.message_id_display()))?;
+                    insert_envelope(&conn, account_id, e, &e.body_bytes(&bytes))
+                        .chain_err_summary(|| {
+                            format!("Failed to insert envelope {}", e.message_id_display())
+                        })?;
                 }
             }
+            sender
+                .send(ThreadEvent::UIEvent(UIEvent::StatusEvent(
+                    StatusEvent::DisplayMessage(format!(
+                        "Rebuilding {} index. {}/{}",
+                        acc_name,
+                        ctr,
+                        env_hashes.len()
+                    )),
+                )))
+                .unwrap();
             let sleep_dur = std::time::Duration::from_millis(20);
             std::thread::sleep(sleep_dur);
         }
+        let tx = conn.unchecked_transaction()?;
+        for (mailbox_hash, env_hashes) in mailboxes {
+            for env_hash in env_hashes {
+                insert_mailboxes(&tx, env_hash, &[mailbox_hash])?;
+            }
+        }
+        tx.commit()?;
         Ok(())
     }))
 }
 
+/// Search the index of account `acc_name`, in mailbox `mailbox_hash` or in all of the account's
+/// mailboxes if it is `None`.
 pub fn search(
     query: &Query,
     (sort_field, sort_order): (SortField, SortOrder),
+    acc_name: &str,
+    mailbox_hash: Option<MailboxHash>,
 ) -> ResultFuture<SmallVec<[EnvelopeHash; 512]>> {
-    let db_path = db_path()?;
+    let db_path = db_path(acc_name)?;
     if !db_path.exists() {
         return Err(MeliError::new(
             "Database hasn't been initialised. Run `reindex` command",
@@ -352,9 +569,15 @@ pub fn search(
 
     let conn = melib_sqlite3::open_db(db_path)?;
 
+    /* Sizes aren't indexed, and listings sort threads by the date of their latest message
+     * themselves. */
+    let unread = format!("(flags & {}) = 0", Flag::SEEN.bits());
     let sort_field = match debug!(sort_field) {
         SortField::Subject => "subject",
-        SortField::Date => "timestamp",
+        SortField::Date | SortField::Size | SortField::Activity => "timestamp",
+        SortField::Sender => "_from",
+        SortField::Attachments => "has_attachments",
+        SortField::Unread => unread.as_str(),
     };
 
     let sort_order = match debug!(sort_order) {
@@ -365,8 +588,14 @@ pub fn search(
     let mut stmt = conn
         .prepare(
             debug!(format!(
-                "SELECT hash FROM envelopes WHERE {} ORDER BY {} {};",
+                "SELECT hash FROM envelopes WHERE ({}){} ORDER BY {} {};",
                 query_to_sql(&query),
+                mailbox_hash
+                    .map(|h| format!(
+                        " AND hash IN (SELECT envelope_hash FROM mailbox_envelopes WHERE mailbox_hash = x'{:016X}')",
+                        h
+                    ))
+                    .unwrap_or_default(),
                 sort_field,
                 sort_order
             ))
@@ -389,6 +618,47 @@ pub fn search(
     Ok(Box::pin(async { results }))
 }
 
+/// Unseen and total message counts of each mailbox in the index of account `acc_name`, or `None`
+/// if the account hasn't been indexed.
+pub fn mailbox_counts(
+    acc_name: &str,
+) -> Result<Option<std::collections::HashMap<MailboxHash, (usize, usize)>>> {
+    let db_path = db_path(acc_name)?;
+    if !db_path.exists() {
+        return Ok(None);
+    }
+    let conn = melib_sqlite3::open_db(db_path)?;
+    let mut stmt = conn
+        .prepare(
+            "SELECT m.mailbox_hash, SUM((e.flags & ?1) = 0), COUNT(*) FROM mailbox_envelopes AS m JOIN envelopes AS e ON e.hash = m.envelope_hash GROUP BY m.mailbox_hash;",
+        )
+        .map_err(|e| MeliError::new(e.to_string()))?;
+    let counts = stmt
+        .query_map(params![i64::from(melib::Flag::SEEN.bits())], |row| {
+            Ok((
+                row.get::<_, Vec<u8>>(0)?,
+                row.get::<_, i64>(1)?,
+                row.get::<_, i64>(2)?,
+            ))
+        })
+        .map_err(|e| MeliError::new(e.to_string()))?
+        .map(|r| {
+            let (hash, unseen, total) = r.map_err(|e| MeliError::new(e.to_string()))?;
+            Ok((
+                u64::from_be_bytes(
+                    hash.as_slice()
+                        .try_into()
+                        .map_err(|e: std::array::TryFromSliceError| {
+                            MeliError::new(e.to_string())
+                        })?,
+                ),
+                (unseen as usize, total as usize),
+            ))
+        })
+        .collect::<Result<_>>()?;
+    Ok(Some(counts))
+}
+
 /// Translates a `Query` to an Sqlite3 expression in a `String`.
 pub fn query_to_sql(q: &Query) -> String {
     fn rec(q: &Query, s: &mut String) {
@@ -418,11 +688,31 @@ pub fn query_to_sql(q: &Query) -> String {
                 s.extend(escape_double_quote(t).chars());
                 s.push_str("%\" ");
             }
-            AllText(t) => {
+            AllText(t) | Body(t) => {
                 s.push_str("body_text LIKE \"%");
                 s.extend(escape_double_quote(t).chars());
                 s.push_str("%\" ");
             }
+            /* Timestamps are stored as big endian blobs, which sqlite compares bytewise */
+            Before(t) => {
+                s.push_str(&format!("timestamp < x'{:016X}' ", t));
+            }
+            After(t) => {
+                s.push_str(&format!("timestamp > x'{:016X}' ", t));
+            }
+            Between(a, b) => {
+                s.push_str(&format!(
+                    "timestamp > x'{:016X}' AND timestamp < x'{:016X}' ",
+                    a, b
+                ));
+            }
+            On(t) => {
+                s.push_str(&format!(
+                    "timestamp >= x'{:016X}' AND timestamp < x'{:016X}' ",
+                    t,
+                    *t + 60 * 60 * 24
+                ));
+            }
             And(q1, q2) => {
                 s.push_str("(");
                 rec(q1, s);
@@ -488,6 +778,21 @@ pub fn query_to_sql(q: &Query) -> String {
             HasAttachment => {
                 s.push_str("has_attachments == 1 ");
             }
+            AttachmentName(t) => {
+                s.push_str("attachment_names LIKE \"%");
+                s.extend(escape_double_quote(t).chars());
+                s.push_str("%\" ");
+            }
+            AttachmentType(t) => {
+                s.push_str("attachment_types LIKE \"%");
+                s.extend(escape_double_quote(t).chars());
+                s.push_str("%\" ");
+            }
+            List(t) => {
+                s.push_str("list_id LIKE \"%");
+                s.extend(escape_double_quote(t).chars());
+                s.push_str("%\" ");
+            }
             _ => {}
         }
     }
@@ -504,6 +809,17 @@ fn test_query_to_sql() {
         "(subject LIKE \"%test%\" ) AND (body_text LIKE \"%i%\" ) ",
         &query_to_sql(&query().parse_complete("subject: test and i").unwrap().1)
     );
+    assert_eq!(
+        "(_from LIKE \"%alice%\" ) AND (timestamp < x'000000005FEE6600' ) ",
+        &query_to_sql(&And(
+            Box::new(From("alice".to_string())),
+            Box::new(Before(1609459200))
+        ))
+    );
+    assert_eq!(
+        "timestamp >= x'000000005FEE6600' AND timestamp < x'000000005FEFB780' ",
+        &query_to_sql(&On(1609459200))
+    );
     assert_eq!(
         "(subject LIKE \"%github%\" ) OR ((_from LIKE \"%epilys%\" ) AND ((subject LIKE \"%lib%\" ) OR (subject LIKE \"%meli%\" ) ) ) ",
         &query_to_sql(
@@ -516,3 +832,10 @@ fn test_query_to_sql() {
         )
     );
 }
+
+#[test]
+fn test_db_identifier() {
+    assert_eq!(db_identifier("work-mail_2"), "work-mail_2");
+    assert_eq!(db_identifier("../a b"), "%2E%2E%2Fa%20b");
+    assert_ne!(db_identifier("a%2F"), db_identifier("a/"));
+}
@@ -33,6 +33,8 @@ use super::*;
 use melib::backends::{AccountHash, BackendEventConsumer};
 
 use crate::jobs::JobExecutor;
+use crate::network::NetworkMonitor;
+use crate::watch_scheduler::WatchScheduler;
 use crossbeam::channel::{unbounded, Receiver, Sender};
 use indexmap::IndexMap;
 use smallvec::SmallVec;
@@ -114,8 +116,14 @@ pub struct Context {
     input_thread: InputHandler,
     pub job_executor: Arc<JobExecutor>,
     pub children: Vec<std::process::Child>,
+    /// Plugins connected to the plugin socket
+    pub plugins: crate::ipc::PluginServer,
 
     pub temp_files: Vec<File>,
+    /// Images to draw on top of the grid in the next render
+    pub inline_images: Vec<InlineImage>,
+    pub watch_scheduler: WatchScheduler,
+    pub network_monitor: NetworkMonitor,
 }
 
 impl Context {
@@ -135,8 +143,11 @@ impl Context {
         let Context {
             ref mut accounts,
             ref mut replies,
+            ref mut watch_scheduler,
             ..
         } = self;
+        /* Accounts that are not connected yet are connected the first time they are needed */
+        accounts[account_pos].connect();
         let was_online = accounts[account_pos].is_online.is_ok();
         let ret = accounts[account_pos].is_online();
         if ret.is_ok() {
@@ -149,7 +160,7 @@ impl Context {
                         accounts[account_pos][&mailbox_node.hash].name()
                     );
                 }
-                accounts[account_pos].watch();
+                watch_scheduler.watch(&mut accounts[account_pos]);
 
                 replies.push_back(UIEvent::AccountStatusChange(accounts[account_pos].hash()));
             }
@@ -174,11 +185,14 @@ pub struct State {
 
     grid: CellBuffer,
     overlay_grid: CellBuffer,
+    /// The cells last flushed to the terminal, so that only cells that changed are redrawn.
+    front_grid: CellBuffer,
     draw_rate_limit: RateLimit,
     stdout: Option<StateStdout>,
     mouse: bool,
     child: Option<ForkType>,
-    draw_horizontal_segment_fn: fn(&mut CellBuffer, &mut StateStdout, usize, usize, usize) -> (),
+    draw_horizontal_segment_fn:
+        fn(&mut CellBuffer, &mut CellBuffer, &mut StateStdout, usize, usize, usize) -> (),
     pub mode: UIMode,
     overlay: Vec<Box<dyn Component>>,
     components: Vec<Box<dyn Component>>,
@@ -192,6 +206,10 @@ pub struct State {
     display_messages_initialised: bool,
     display_messages_pos: usize,
     display_messages_area: Area,
+    /// Images currently drawn on screen
+    inline_images: Vec<InlineImage>,
+    /// Keys typed so far of a key sequence in `shortcuts.sequences`
+    pending_keys: SmallVec<[Key; 8]>,
 }
 
 #[derive(Debug)]
@@ -219,6 +237,11 @@ impl Drop for State {
                 debug!("Failed to wait on subprocess {}: {}", child_pid, e);
             }
         }
+        if let Some(ref hook) = self.context.settings.hooks.shutdown {
+            if let Err(err) = crate::conf::hooks::run_hook("shutdown", hook, None, vec![]) {
+                eprintln!("{}", err);
+            }
+        }
     }
 }
 
@@ -261,6 +284,11 @@ impl State {
         let rows = termsize.1 as usize;
 
         let job_executor = Arc::new(JobExecutor::new(sender.clone()));
+        let plugins = crate::ipc::PluginServer::new(&settings.plugins, sender.clone())
+            .unwrap_or_else(|err| {
+                melib::log(err.to_string(), melib::ERROR);
+                crate::ipc::PluginServer::default()
+            });
         let accounts = {
             settings
                 .accounts
@@ -319,6 +347,7 @@ impl State {
             rows,
             grid: CellBuffer::new(cols, rows, Cell::with_char(' ')),
             overlay_grid: CellBuffer::new(cols, rows, Cell::with_char(' ')),
+            front_grid: CellBuffer::new(cols, rows, Cell::with_char('\0')),
             stdout: None,
             mouse: settings.terminal.use_mouse.is_true(),
             child: None,
@@ -339,14 +368,20 @@ impl State {
             display_messages_dirty: false,
             display_messages_initialised: false,
             display_messages_area: ((0, 0), (0, 0)),
+            inline_images: Vec::new(),
+            pending_keys: SmallVec::new(),
             context: Context {
                 accounts,
                 settings: settings,
                 dirty_areas: VecDeque::with_capacity(5),
                 replies: VecDeque::with_capacity(5),
                 temp_files: Vec::new(),
+                inline_images: Vec::new(),
+                watch_scheduler: WatchScheduler::default(),
+                network_monitor: NetworkMonitor::default(),
                 job_executor,
                 children: vec![],
+                plugins,
 
                 input_thread: InputHandler {
                     pipe: input_thread_pipe,
@@ -367,9 +402,12 @@ impl State {
         s.switch_to_alternate_screen();
         for i in 0..s.context.accounts.len() {
             if !s.context.accounts[i].backend_capabilities.is_remote {
-                s.context.accounts[i].watch();
+                s.context.watch_scheduler.watch(&mut s.context.accounts[i]);
             }
-            if s.context.is_online_idx(i).is_ok() && s.context.accounts[i].is_empty() {
+            if s.context.accounts[i].is_connected()
+                && s.context.is_online_idx(i).is_ok()
+                && s.context.accounts[i].is_empty()
+            {
                 //return Err(MeliError::new(format!(
                 //    "Account {} has no mailboxes configured.",
                 //    s.context.accounts[i].name()
@@ -377,6 +415,9 @@ impl State {
             }
         }
         s.context.restore_input();
+        if let Some(hook) = s.context.settings.hooks.startup.clone() {
+            crate::conf::hooks::spawn_hook("startup", hook, None, vec![], s.context.sender.clone());
+        }
         Ok(s)
     }
 
@@ -406,9 +447,17 @@ impl State {
             if let Some(notification) = accounts[&account_hash].reload(event, mailbox_hash) {
                 if let UIEvent::Notification(_, _, _) = notification {
                     self.rcv_event(UIEvent::MailboxUpdate((account_hash, mailbox_hash)));
+                    let context = &self.context;
+                    if *mailbox_settings!(context[account_hash][&mailbox_hash].notifications.bell) {
+                        if let Some(stdout) = self.stdout.as_mut() {
+                            write!(stdout, "\x07").unwrap();
+                            stdout.flush().unwrap();
+                        }
+                    }
                 }
                 self.rcv_event(notification);
             }
+            self.context.accounts[&account_hash].update_saved_searches();
         } else {
             if let melib::backends::RefreshEventKind::Failure(err) = event.kind {
                 debug!(err);
@@ -420,13 +469,21 @@ impl State {
     /// the application)
     pub fn switch_to_main_screen(&mut self) {
         let mouse = self.mouse;
+        for image in self.inline_images.drain(..) {
+            if image.protocol == GraphicsProtocol::Kitty {
+                if let Some(stdout) = self.stdout.as_mut() {
+                    write!(stdout, "{}", kitty_delete(image.id)).unwrap();
+                }
+            }
+        }
         write!(
             self.stdout(),
-            "{}{}{}{}{disable_sgr_mouse}{disable_mouse}",
+            "{}{}{}{}{}{disable_sgr_mouse}{disable_mouse}",
             termion::screen::ToMainScreen,
             cursor::Show,
             RestoreWindowTitleIconFromStack,
             BracketModeEnd,
+            DisableFocusReporting,
             disable_sgr_mouse = if mouse { DisableSGRMouse.as_ref() } else { "" },
             disable_mouse = if mouse { DisableMouse.as_ref() } else { "" },
         )
@@ -442,12 +499,13 @@ impl State {
 
         write!(
             &mut stdout,
-            "{save_title_to_stack}{}{}{}{window_title}{}{}{enable_mouse}{enable_sgr_mouse}",
+            "{save_title_to_stack}{}{}{}{window_title}{}{}{}{enable_mouse}{enable_sgr_mouse}",
             termion::screen::ToAlternateScreen,
             cursor::Hide,
             clear::All,
             cursor::Goto(1, 1),
             BracketModeStart,
+            EnableFocusReporting,
             save_title_to_stack = SaveWindowTitleIconToStack,
             window_title = if let Some(ref title) = self.context.settings.terminal.window_title {
                 format!("\x1b]2;{}\x07", title)
@@ -522,6 +580,7 @@ impl State {
             );
         }
         let _ = self.overlay_grid.resize(self.cols, self.rows, None);
+        let _ = self.front_grid.resize(self.cols, self.rows, None);
 
         self.rcv_event(UIEvent::Resize);
         self.display_messages_dirty = true;
@@ -533,6 +592,68 @@ impl State {
     }
 
     /// Force a redraw for all dirty components.
+    /// Re-read the configuration file and apply the changes that take effect without a restart.
+    ///
+    /// Changes to accounts and plugins are not applied; they are reported to the user instead.
+    pub fn reload_config(&mut self) {
+        let mut new_settings = match Settings::new() {
+            Ok(new_settings) => new_settings,
+            Err(err) => {
+                self.context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(format!(
+                        "Could not load configuration: {}",
+                        err
+                    ))));
+                return;
+            }
+        };
+        let mut needs_restart = vec![];
+        for (key, acc) in new_settings.accounts.iter() {
+            match self.context.settings.accounts.get(key) {
+                None => needs_restart.push(format!("new account `{}`", key)),
+                Some(old_acc) if toml::Value::try_from(acc) != toml::Value::try_from(old_acc) => {
+                    needs_restart.push(format!("account `{}`", key))
+                }
+                Some(_) => {}
+            }
+        }
+        for key in self.context.settings.accounts.keys() {
+            if !new_settings.accounts.contains_key(key) {
+                needs_restart.push(format!("removed account `{}`", key));
+            }
+        }
+        new_settings.accounts = self.context.settings.accounts.clone();
+        if toml::Value::try_from(&new_settings.plugins)
+            != toml::Value::try_from(&self.context.settings.plugins)
+        {
+            needs_restart.push("plugins".to_string());
+            new_settings.plugins = self.context.settings.plugins.clone();
+        }
+        let changed = self.context.settings.changed_sections(&new_settings);
+        let mut message = if changed.is_empty() {
+            "No changes detected.".to_string()
+        } else {
+            format!("Configuration reloaded: {} changed.", changed.join(", "))
+        };
+        if !needs_restart.is_empty() {
+            message.push_str(&format!(
+                " Restart meli to apply changes to {}.",
+                needs_restart.join(", ")
+            ));
+        }
+        if !changed.is_empty() {
+            let old_settings = std::mem::replace(&mut self.context.settings, new_settings);
+            self.context
+                .replies
+                .push_back(UIEvent::ConfigReload { old_settings });
+            self.context.replies.push_back(UIEvent::Resize);
+        }
+        self.context
+            .replies
+            .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(message)));
+    }
+
     pub fn redraw(&mut self) {
         if !self.draw_rate_limit.tick() {
             return;
@@ -586,6 +707,7 @@ impl State {
                 if let Some((x_start, x_end)) = segment.take() {
                     (self.draw_horizontal_segment_fn)(
                         &mut self.grid,
+                        &mut self.front_grid,
                         self.stdout.as_mut().unwrap(),
                         x_start,
                         x_end,
@@ -599,6 +721,7 @@ impl State {
                     ref mut s @ Some(_) if s.unwrap().1 < *x_start => {
                         (self.draw_horizontal_segment_fn)(
                             &mut self.grid,
+                            &mut self.front_grid,
                             self.stdout.as_mut().unwrap(),
                             s.unwrap().0,
                             s.unwrap().1,
@@ -609,6 +732,7 @@ impl State {
                     ref mut s @ Some(_) if s.unwrap().1 < *x_end => {
                         (self.draw_horizontal_segment_fn)(
                             &mut self.grid,
+                            &mut self.front_grid,
                             self.stdout.as_mut().unwrap(),
                             s.unwrap().0,
                             s.unwrap().1,
@@ -624,6 +748,7 @@ impl State {
             if let Some((x_start, x_end)) = segment {
                 (self.draw_horizontal_segment_fn)(
                     &mut self.grid,
+                    &mut self.front_grid,
                     self.stdout.as_mut().unwrap(),
                     x_start,
                     x_end,
@@ -632,6 +757,8 @@ impl State {
             }
         }
 
+        self.draw_inline_images(&areas);
+
         if self.display_messages_dirty && self.display_messages_active {
             if let Some(DisplayMessage {
                 ref timestamp,
@@ -646,6 +773,7 @@ impl State {
                         for y in get_y(upper_left!(displ_area))..=get_y(bottom_right!(displ_area)) {
                             (self.draw_horizontal_segment_fn)(
                                 &mut self.grid,
+                                &mut self.front_grid,
                                 self.stdout.as_mut().unwrap(),
                                 get_x(upper_left!(displ_area)),
                                 get_x(bottom_right!(displ_area)),
@@ -722,6 +850,7 @@ impl State {
                 {
                     (self.draw_horizontal_segment_fn)(
                         &mut self.overlay_grid,
+                        &mut self.front_grid,
                         self.stdout.as_mut().unwrap(),
                         get_x(upper_left!(self.display_messages_area)),
                         get_x(bottom_right!(self.display_messages_area)),
@@ -736,6 +865,7 @@ impl State {
             for y in get_y(upper_left!(displ_area))..=get_y(bottom_right!(displ_area)) {
                 (self.draw_horizontal_segment_fn)(
                     &mut self.grid,
+                    &mut self.front_grid,
                     self.stdout.as_mut().unwrap(),
                     get_x(upper_left!(displ_area)),
                     get_x(bottom_right!(displ_area)),
@@ -771,6 +901,7 @@ impl State {
             for y in get_y(upper_left!(area))..=get_y(bottom_right!(area)) {
                 (self.draw_horizontal_segment_fn)(
                     &mut self.overlay_grid,
+                    &mut self.front_grid,
                     self.stdout.as_mut().unwrap(),
                     get_x(upper_left!(area)),
                     get_x(bottom_right!(area)),
@@ -781,26 +912,85 @@ impl State {
         self.flush();
     }
 
-    /// Draw only a specific `area` on the screen.
+    /// Remove images whose area was redrawn and draw the images components requested.
+    fn draw_inline_images(&mut self, areas: &[Area]) {
+        if self.inline_images.is_empty() && self.context.inline_images.is_empty() {
+            return;
+        }
+        let stdout = self.stdout.as_mut().unwrap();
+        let new_images = std::mem::replace(&mut self.context.inline_images, Vec::new());
+        self.inline_images.retain(|image| {
+            let ((img_top_x, img_top_y), (img_bottom_x, img_bottom_y)) = image.area;
+            let redrawn = new_images.iter().any(|new| new.id == image.id)
+                || areas.iter().any(|&((top_x, top_y), (bottom_x, bottom_y))| {
+                    !(bottom_y < img_top_y
+                        || img_bottom_y < top_y
+                        || bottom_x < img_top_x
+                        || img_bottom_x < top_x)
+                });
+            /* Sixel images are erased by drawing over them, kitty images must be deleted. */
+            if redrawn && image.protocol == GraphicsProtocol::Kitty {
+                write!(stdout, "{}", kitty_delete(image.id)).unwrap();
+            }
+            !redrawn
+        });
+        for image in new_images {
+            write!(
+                stdout,
+                "{}",
+                cursor::Goto(
+                    get_x(upper_left!(image.area)) as u16 + 1,
+                    get_y(upper_left!(image.area)) as u16 + 1
+                )
+            )
+            .unwrap();
+            stdout.write_all(&image.escape).unwrap();
+            /* The image covers the cells under it, which must be written again when it is
+             * removed. */
+            for row in self.front_grid.bounds_iter(image.area) {
+                for c in row {
+                    self.front_grid[c] = Cell::with_char('\0');
+                }
+            }
+            self.inline_images.push(image);
+        }
+    }
+
+    /// Draw the cells of row `y` from `x_start` to `x_end` that differ from the ones in
+    /// `front`, the cells last flushed to the terminal, and update `front`.
     fn draw_horizontal_segment(
         grid: &mut CellBuffer,
+        front: &mut CellBuffer,
         stdout: &mut StateStdout,
         x_start: usize,
         x_end: usize,
         y: usize,
     ) {
-        write!(
-            stdout,
-            "{}",
-            cursor::Goto(x_start as u16 + 1, (y + 1) as u16)
-        )
-        .unwrap();
         let mut current_fg = Color::Default;
         let mut current_bg = Color::Default;
         let mut current_attrs = Attr::DEFAULT;
-        write!(stdout, "\x1B[m").unwrap();
+        let mut current_hyperlink = 0;
+        let mut started = false;
+        /* Column of the terminal cursor, if it is known */
+        let mut cursor_x = None;
         for x in x_start..=x_end {
-            let c = &grid[(x, y)];
+            let c = grid[(x, y)];
+            if front.get(x, y) == Some(&c) {
+                continue;
+            }
+            if let Some(front_cell) = front.get_mut(x, y) {
+                *front_cell = c;
+            }
+            if c.empty() {
+                continue;
+            }
+            if !started {
+                write!(stdout, "\x1B[m").unwrap();
+                started = true;
+            }
+            if cursor_x != Some(x) {
+                write!(stdout, "{}", cursor::Goto(x as u16 + 1, (y + 1) as u16)).unwrap();
+            }
             if c.attrs() != current_attrs {
                 c.attrs().write(current_attrs, stdout).unwrap();
                 current_attrs = c.attrs();
@@ -813,42 +1003,74 @@ impl State {
                 c.fg().write_fg(stdout).unwrap();
                 current_fg = c.fg();
             }
-            if !c.empty() {
-                write!(stdout, "{}", c.ch()).unwrap();
+            if c.hyperlink() != current_hyperlink {
+                write_hyperlink(c.hyperlink(), stdout).unwrap();
+                current_hyperlink = c.hyperlink();
             }
+            write!(stdout, "{}", c.ch()).unwrap();
+            cursor_x = Some(x + 1);
+        }
+        if current_hyperlink != 0 {
+            write_hyperlink(0, stdout).unwrap();
         }
     }
 
     fn draw_horizontal_segment_no_color(
         grid: &mut CellBuffer,
+        front: &mut CellBuffer,
         stdout: &mut StateStdout,
         x_start: usize,
         x_end: usize,
         y: usize,
     ) {
-        write!(
-            stdout,
-            "{}",
-            cursor::Goto(x_start as u16 + 1, (y + 1) as u16)
-        )
-        .unwrap();
         let mut current_attrs = Attr::DEFAULT;
-        write!(stdout, "\x1B[m").unwrap();
+        let mut current_hyperlink = 0;
+        let mut started = false;
+        /* Column of the terminal cursor, if it is known */
+        let mut cursor_x = None;
         for x in x_start..=x_end {
-            let c = &grid[(x, y)];
+            let c = grid[(x, y)];
+            if front.get(x, y) == Some(&c) {
+                continue;
+            }
+            if let Some(front_cell) = front.get_mut(x, y) {
+                *front_cell = c;
+            }
+            if c.empty() {
+                continue;
+            }
+            if !started {
+                write!(stdout, "\x1B[m").unwrap();
+                started = true;
+            }
+            if cursor_x != Some(x) {
+                write!(stdout, "{}", cursor::Goto(x as u16 + 1, (y + 1) as u16)).unwrap();
+            }
             if c.attrs() != current_attrs {
                 c.attrs().write(current_attrs, stdout).unwrap();
                 current_attrs = c.attrs();
             }
-            if !c.empty() {
-                write!(stdout, "{}", c.ch()).unwrap();
+            if c.hyperlink() != current_hyperlink {
+                write_hyperlink(c.hyperlink(), stdout).unwrap();
+                current_hyperlink = c.hyperlink();
             }
+            write!(stdout, "{}", c.ch()).unwrap();
+            cursor_x = Some(x + 1);
         }
+        if current_hyperlink != 0 {
+            write_hyperlink(0, stdout).unwrap();
+        }
+    }
+
+    /// Forget what is shown on the terminal, so that the next redraw writes every cell again.
+    fn invalidate_front_grid(&mut self) {
+        self.front_grid.clear(Some(Cell::with_char('\0')));
     }
 
     /// Draw the entire screen from scratch.
     pub fn render(&mut self) {
         self.update_size();
+        self.invalidate_front_grid();
         let cols = self.cols;
         let rows = self.rows;
         self.context
@@ -872,6 +1094,38 @@ impl State {
         }
     }
 
+    /// Track the key sequences of `shortcuts.sequences`, executing their command when one is
+    /// completed. Returns the keys that must be processed as input: none if `key` continues or
+    /// completes a sequence, otherwise the keys typed since the last completed sequence.
+    pub fn key_sequence(&mut self, key: Key) -> SmallVec<[Key; 8]> {
+        if self.context.settings.shortcuts.sequences.is_empty() {
+            return smallvec![key];
+        }
+        self.pending_keys.push(key);
+        let mut is_prefix = false;
+        let mut command = None;
+        for (sequence, cmd) in self.context.settings.shortcuts.sequences.iter() {
+            let keys = match crate::conf::parse_key_sequence(sequence) {
+                Ok(keys) => keys,
+                Err(_) => continue,
+            };
+            if keys.as_slice() == self.pending_keys.as_slice() {
+                command = Some(cmd.clone());
+                break;
+            }
+            is_prefix |= keys.starts_with(&self.pending_keys);
+        }
+        if let Some(command) = command {
+            self.pending_keys.clear();
+            self.rcv_event(UIEvent::Command(command));
+            SmallVec::new()
+        } else if is_prefix {
+            SmallVec::new()
+        } else {
+            self.pending_keys.drain(..).collect()
+        }
+    }
+
     pub fn can_quit_cleanly(&mut self) -> bool {
         let State {
             ref mut components,
@@ -881,10 +1135,53 @@ impl State {
         components.iter_mut().all(|c| c.can_quit_cleanly(context))
     }
 
+    /// Save the state of the UI to restore on the next start, see `terminal.restore_session`.
+    pub fn save_session(&self) {
+        let mut session = crate::session::Session::default();
+        for c in self.components.iter() {
+            c.save_session(&mut session, &self.context);
+        }
+        if let Err(err) = session.save() {
+            log(format!("Could not save session: {}", err), ERROR);
+        }
+    }
+
     pub fn register_component(&mut self, component: Box<dyn Component>) {
         self.components.push(component);
     }
 
+    /// Drop and rebuild the sqlite3 search index of an account in the background.
+    #[cfg(feature = "sqlite3")]
+    fn reindex(&mut self, account_index: usize) {
+        let account_name = self.context.accounts[account_index].name().to_string();
+        match crate::sqlite3::index(&mut self.context, account_index) {
+            Ok(job) => {
+                let handle = self.context.job_executor.spawn_blocking(job);
+                self.context.accounts[account_index].active_jobs.insert(
+                    handle.job_id,
+                    crate::conf::accounts::JobRequest::Generic {
+                        name: format!("{} message index rebuild", account_name).into(),
+                        handle,
+                        on_finish: None,
+                        logging_level: melib::LoggingLevel::INFO,
+                    },
+                );
+                self.context.replies.push_back(UIEvent::Notification(
+                    None,
+                    format!("{} message index rebuild started.", account_name),
+                    Some(NotificationType::Info),
+                ));
+            }
+            Err(err) => {
+                self.context.replies.push_back(UIEvent::Notification(
+                    Some(format!("{} message index rebuild failed", account_name)),
+                    err.to_string(),
+                    Some(NotificationType::Error(err.kind)),
+                ));
+            }
+        }
+    }
+
     /// Convert user commands to actions/method calls.
     fn exec_command(&mut self, cmd: Action) {
         match cmd {
@@ -952,35 +1249,34 @@ impl State {
                     ));
                     return;
                 }
-                match crate::sqlite3::index(&mut self.context, account_index) {
-                    Ok(job) => {
-                        let handle = self.context.job_executor.spawn_blocking(job);
-                        self.context.accounts[account_index].active_jobs.insert(
-                            handle.job_id,
-                            crate::conf::accounts::JobRequest::Generic {
-                                name: "Message index rebuild".into(),
-                                handle,
-                                on_finish: None,
-                                logging_level: melib::LoggingLevel::INFO,
-                            },
-                        );
-                        self.context.replies.push_back(UIEvent::Notification(
-                            None,
-                            "Message index rebuild started.".to_string(),
-                            Some(NotificationType::Info),
-                        ));
-                    }
-                    Err(err) => {
-                        self.context.replies.push_back(UIEvent::Notification(
-                            Some("Message index rebuild failed".to_string()),
-                            err.to_string(),
-                            Some(NotificationType::Error(err.kind)),
-                        ));
-                    }
+                self.reindex(account_index);
+            }
+            #[cfg(feature = "sqlite3")]
+            ReIndexAll => {
+                let account_indices = self
+                    .context
+                    .accounts
+                    .values()
+                    .enumerate()
+                    .filter(|(_, acc)| {
+                        *acc.settings.conf.search_backend() == crate::conf::SearchBackend::Sqlite3
+                    })
+                    .map(|(i, _)| i)
+                    .collect::<SmallVec<[usize; 8]>>();
+                if account_indices.is_empty() {
+                    self.context.replies.push_back(UIEvent::Notification(
+                        None,
+                        "No account has an sqlite3 search backend.".to_string(),
+                        Some(NotificationType::Error(ErrorKind::None)),
+                    ));
+                    return;
+                }
+                for account_index in account_indices {
+                    self.reindex(account_index);
                 }
             }
             #[cfg(not(feature = "sqlite3"))]
-            AccountAction(ref account_name, ReIndex) => {
+            AccountAction(_, ReIndex) | ReIndexAll => {
                 self.context.replies.push_back(UIEvent::Notification(
                     None,
                     "Message index rebuild failed: meli is not built with sqlite3 support."
@@ -1014,6 +1310,153 @@ impl State {
                     return;
                 }
             }
+            AccountAction(ref account_name, ImportContacts(format, ref path)) => {
+                let account = if let Some(a) = self
+                    .context
+                    .accounts
+                    .values_mut()
+                    .find(|a| a.name() == account_name)
+                {
+                    a
+                } else {
+                    self.context.replies.push_back(UIEvent::Notification(
+                        None,
+                        format!("Account {} was not found.", account_name),
+                        Some(NotificationType::Error(ErrorKind::None)),
+                    ));
+                    return;
+                };
+                let path = std::path::Path::new(path).expand();
+                match std::fs::read_to_string(&path) {
+                    Ok(contents) => {
+                        let cards = match format {
+                            ContactsFormat::MuttAliases => {
+                                melib::addressbook::mutt::parse_aliases(&contents)
+                            }
+                            ContactsFormat::Abook => {
+                                melib::addressbook::abook::parse_addressbook(&contents)
+                            }
+                        };
+                        let len = cards.len();
+                        for card in cards {
+                            account.address_book.add_card(card);
+                        }
+                        self.context.replies.push_back(UIEvent::Notification(
+                            None,
+                            format!("Imported {} contacts into {}.", len, account_name),
+                            Some(NotificationType::Info),
+                        ));
+                    }
+                    Err(err) => {
+                        self.context.replies.push_back(UIEvent::Notification(
+                            Some(format!("Could not read {}", path.display())),
+                            err.to_string(),
+                            Some(NotificationType::Error(ErrorKind::External)),
+                        ));
+                    }
+                }
+            }
+            AccountAction(ref account_name, ExportContacts(format, ref path)) => {
+                let account = if let Some(a) = self
+                    .context
+                    .accounts
+                    .values()
+                    .find(|a| a.name() == account_name)
+                {
+                    a
+                } else {
+                    self.context.replies.push_back(UIEvent::Notification(
+                        None,
+                        format!("Account {} was not found.", account_name),
+                        Some(NotificationType::Error(ErrorKind::None)),
+                    ));
+                    return;
+                };
+                let cards = account.address_book.values();
+                let contents = match format {
+                    ContactsFormat::MuttAliases => melib::addressbook::mutt::export_aliases(cards),
+                    ContactsFormat::Abook => melib::addressbook::abook::export_addressbook(cards),
+                };
+                let path = std::path::Path::new(path).expand();
+                match std::fs::write(&path, contents) {
+                    Ok(()) => {
+                        self.context.replies.push_back(UIEvent::Notification(
+                            None,
+                            format!(
+                                "Exported {} contacts to {}.",
+                                account.address_book.len(),
+                                path.display()
+                            ),
+                            Some(NotificationType::Info),
+                        ));
+                    }
+                    Err(err) => {
+                        self.context.replies.push_back(UIEvent::Notification(
+                            Some(format!("Could not write {}", path.display())),
+                            err.to_string(),
+                            Some(NotificationType::Error(ErrorKind::External)),
+                        ));
+                    }
+                }
+            }
+            AccountAction(ref account_name, PurgeCollectedAddresses(days)) => {
+                if let Some(account) = self
+                    .context
+                    .accounts
+                    .values_mut()
+                    .find(|a| a.name() == account_name)
+                {
+                    let removed =
+                        account.collected_addresses.purge(days.map(|days| {
+                            melib::datetime::now().saturating_sub(days * 24 * 60 * 60)
+                        }));
+                    self.context.replies.push_back(UIEvent::Notification(
+                        None,
+                        format!(
+                            "Removed {} collected addresses from {}.",
+                            removed, account_name
+                        ),
+                        Some(NotificationType::Info),
+                    ));
+                } else {
+                    self.context.replies.push_back(UIEvent::Notification(
+                        None,
+                        format!("Account {} was not found.", account_name),
+                        Some(NotificationType::Error(ErrorKind::None)),
+                    ));
+                }
+            }
+            AccountAction(ref account_name, ManageSieve) => {
+                match self
+                    .context
+                    .accounts
+                    .values()
+                    .find(|a| a.name() == account_name)
+                    .map(|a| (a.hash(), a.settings.account().format() == "imap"))
+                {
+                    Some((account_hash, true)) => {
+                        let sieve = SieveScripts::new(account_hash, &mut self.context);
+                        self.rcv_event(UIEvent::Action(Tab(New(Some(Box::new(sieve))))));
+                    }
+                    Some((_, false)) => {
+                        self.context.replies.push_back(UIEvent::Notification(
+                            None,
+                            format!(
+                                "Account {} is not an IMAP account, Sieve scripts can only be managed on IMAP servers.",
+                                account_name
+                            ),
+                            Some(NotificationType::Error(ErrorKind::None)),
+                        ));
+                    }
+                    None => {
+                        self.context.replies.push_back(UIEvent::Notification(
+                            None,
+                            format!("Account {} was not found.", account_name),
+                            Some(NotificationType::Error(ErrorKind::None)),
+                        ));
+                    }
+                }
+            }
             PrintSetting(ref setting) => {
                 let path = setting.split(".").collect::<SmallVec<[&str; 16]>>();
                 self.context
@@ -1026,6 +1469,52 @@ impl State {
                             .unwrap_or_else(|err| err.to_string())
                     ))));
             }
+            UnifiedInbox => {
+                /* The inboxes of all accounts are opened */
+                for account in self.context.accounts.values_mut() {
+                    account.connect();
+                }
+                let listing = UnifiedListing::new(&mut self.context);
+                self.rcv_event(UIEvent::Action(Tab(New(Some(Box::new(listing))))));
+            }
+            NewComposer(mailto) => {
+                let account_hash = match self.context.accounts.keys().next() {
+                    Some(h) => *h,
+                    None => {
+                        self.context.replies.push_back(UIEvent::StatusEvent(
+                            StatusEvent::DisplayMessage("No accounts to compose from.".into()),
+                        ));
+                        return;
+                    }
+                };
+                let mut composer = Composer::with_account(account_hash, &self.context);
+                if let Some(mailto) = mailto {
+                    let mut draft = composer.draft().clone();
+                    mailto.apply_to(&mut draft);
+                    composer.set_draft(draft);
+                }
+                self.rcv_event(UIEvent::Action(Tab(New(Some(Box::new(composer))))));
+            }
+            SetLogLevel(module, level) => {
+                let message = if let Some(module) = module {
+                    melib::change_module_log_level(&module, Some(level));
+                    format!("Log level of {} set to {}", module, level)
+                } else {
+                    melib::change_log_level(level);
+                    format!("Log level set to {}", level)
+                };
+                self.context
+                    .replies
+                    .push_back(UIEvent::StatusEvent(StatusEvent::DisplayMessage(message)));
+            }
+            Jobs => {
+                let jobs = JobsView::new(&self.context);
+                self.rcv_event(UIEvent::Action(Tab(New(Some(Box::new(jobs))))));
+            }
+            Log(level) => {
+                let log = LogView::new(level, &self.context);
+                self.rcv_event(UIEvent::Action(Tab(New(Some(Box::new(log))))));
+            }
             ToggleMouse => {
                 self.mouse = !self.mouse;
                 self.set_mouse(self.mouse);
@@ -1072,42 +1561,11 @@ impl State {
                             &mut self.context,
                         )));
                     } else if let Action::ReloadConfiguration = action {
-                        match Settings::new().and_then(|new_settings| {
-                            let old_accounts = self.context.settings.accounts.keys().collect::<std::collections::HashSet<&String>>();
-                            let new_accounts = new_settings.accounts.keys().collect::<std::collections::HashSet<&String>>();
-                            if old_accounts != new_accounts {
-                                return Err("cannot reload account configuration changes; restart meli instead.".into());
-                            }
-                            for (key, acc) in new_settings.accounts.iter() {
-                                if toml::Value::try_from(&acc) != toml::Value::try_from(&self.context.settings.accounts[key]) {
-                                    return Err("cannot reload account configuration changes; restart meli instead.".into());
-                                }
-                            }
-                            if toml::Value::try_from(&new_settings) == toml::Value::try_from(&self.context.settings) {
-                                return Err("No changes detected.".into());
-                            }
-                            Ok(new_settings)
-                        }) {
-                            Ok(new_settings) => {
-                                let old_settings = std::mem::replace(&mut self.context.settings, new_settings);
-                                self.context.replies.push_back(UIEvent::ConfigReload {
-                                    old_settings
-                                });
-                                self.context.replies.push_back(UIEvent::Resize);
-                            }
-                            Err(err) => {
-                                self.context.replies.push_back(UIEvent::StatusEvent(
-                                        StatusEvent::DisplayMessage(format!(
-                                                "Could not load configuration: {}",
-                                                err
-                                        )),
-                                ));
-                            }
-                        }
+                        self.reload_config();
                     } else {
                         self.exec_command(action);
                     }
-                } else {
+                } else if !self.context.plugins.run_command(&cmd) {
                     self.context.replies.push_back(UIEvent::StatusEvent(
                         StatusEvent::DisplayMessage("invalid command".to_string()),
                     ));
@@ -1304,15 +1762,26 @@ impl State {
     }
 
     pub fn check_accounts(&mut self) {
+        self.context
+            .network_monitor
+            .tick(&mut self.context.accounts, &mut self.context.replies);
         let mut ctr = 0;
         for i in 0..self.context.accounts.len() {
-            if self.context.is_online_idx(i).is_ok() {
+            /* Don't connect accounts that haven't been opened yet */
+            if !self.context.accounts[i].is_connected() || self.context.is_online_idx(i).is_ok() {
                 ctr += 1;
             }
         }
         if ctr != self.context.accounts.len() {
             self.timer.thread().unpark();
         }
+        self.context
+            .watch_scheduler
+            .tick(&mut self.context.accounts, &self.context.settings);
+        for account in self.context.accounts.values_mut() {
+            account.wake_snoozed();
+            account.check_follow_ups();
+        }
         self.context.input_thread.check();
     }
 }
@@ -0,0 +1,166 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Print unread and total message counts of the subscribed mailboxes of every account without
+ * starting the UI, see the `status` subcommand.
+ */
+
+use crate::conf::{AccountConf, FileSettings};
+use melib::backends::{BackendEventConsumer, Backends, MailBackend, Mailbox};
+use melib::text_processing::GlobMatch;
+use melib::{MeliError, Result};
+use std::sync::Arc;
+
+/// Message counts of an account.
+#[derive(Debug, Serialize)]
+pub struct AccountStatus {
+    pub name: String,
+    pub unseen: usize,
+    pub total: usize,
+    pub mailboxes: Vec<MailboxStatus>,
+    /// Why the counts could not be retrieved.
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub error: Option<String>,
+}
+
+/// Message counts of a mailbox.
+#[derive(Debug, Serialize)]
+pub struct MailboxStatus {
+    pub path: String,
+    pub unseen: usize,
+    pub total: usize,
+}
+
+/// Count the messages of `mailbox` by fetching its envelopes.
+fn fetch_counts(backend: &mut Box<dyn MailBackend>, mailbox: &Mailbox) -> Result<(usize, usize)> {
+    let (mut unseen, mut total) = (0, 0);
+    let mut stream = backend.fetch(mailbox.hash())?;
+    while let Some(batch) =
+        futures::executor::block_on(futures::stream::StreamExt::next(&mut stream))
+    {
+        for env in batch? {
+            total += 1;
+            if !env.is_seen() {
+                unseen += 1;
+            }
+        }
+    }
+    Ok((unseen, total))
+}
+
+fn account_status(backends: &Backends, conf: AccountConf) -> Result<AccountStatus> {
+    let s = conf.clone();
+    let mut backend = backends.get(conf.account().format())(
+        conf.account(),
+        Box::new(move |path: &str| {
+            s.account.subscribed_mailboxes.is_empty()
+                || (s.mailbox_confs.contains_key(path)
+                    && s.mailbox_confs[path].mailbox_conf().subscribe.is_true())
+                || s.account
+                    .subscribed_mailboxes
+                    .iter()
+                    .any(|m| path.matches_glob(m))
+        }),
+        BackendEventConsumer::new(Arc::new(|_, _| {})),
+    )?;
+    futures::executor::block_on(backend.is_online()?)?;
+    let mut mailboxes = futures::executor::block_on(backend.mailboxes()?)?
+        .into_iter()
+        .map(|(_, m)| m)
+        .filter(|m| m.is_subscribed())
+        .collect::<Vec<Mailbox>>();
+    mailboxes.sort_by(|a, b| a.path().cmp(b.path()));
+
+    #[cfg(feature = "sqlite3")]
+    let cached = if *conf.conf().search_backend() == crate::conf::SearchBackend::Sqlite3 {
+        crate::sqlite3::mailbox_counts(conf.account().name())
+            .ok()
+            .flatten()
+    } else {
+        None
+    };
+    #[cfg(not(feature = "sqlite3"))]
+    let cached: Option<
+        std::collections::HashMap<melib::backends::MailboxHash, (usize, usize)>,
+    > = None;
+
+    let mut ret = AccountStatus {
+        name: conf.account().name().to_string(),
+        unseen: 0,
+        total: 0,
+        mailboxes: Vec::with_capacity(mailboxes.len()),
+        error: None,
+    };
+    for mailbox in mailboxes {
+        let (unseen, total) = match cached.as_ref().and_then(|c| c.get(&mailbox.hash())) {
+            Some(counts) => *counts,
+            None => fetch_counts(&mut backend, &mailbox)?,
+        };
+        ret.unseen += unseen;
+        ret.total += total;
+        ret.mailboxes.push(MailboxStatus {
+            path: mailbox.path().to_string(),
+            unseen,
+            total,
+        });
+    }
+    Ok(ret)
+}
+
+/// Print the message counts of every account, as a JSON array of `AccountStatus` if `json` is
+/// set or as lines of `account/mailbox: unseen/total` otherwise.
+pub fn status(settings: &FileSettings, json: bool) -> Result<()> {
+    let backends = Backends::new();
+    let mut accounts = vec![];
+    for (name, acc) in settings.accounts.iter() {
+        let mut conf = AccountConf::from(acc.clone());
+        conf.account.set_name(name.to_string());
+        accounts.push(
+            account_status(&backends, conf).unwrap_or_else(|err| AccountStatus {
+                name: name.to_string(),
+                unseen: 0,
+                total: 0,
+                mailboxes: vec![],
+                error: Some(err.details.to_string()),
+            }),
+        );
+    }
+    if json {
+        println!(
+            "{}",
+            serde_json::to_string(&accounts).map_err(|err| MeliError::new(err.to_string()))?
+        );
+    } else {
+        for acc in accounts.iter() {
+            if let Some(ref err) = acc.error {
+                println!("{}: {}", acc.name, err);
+                continue;
+            }
+            for mailbox in acc.mailboxes.iter() {
+                println!(
+                    "{}/{}: {}/{}",
+                    acc.name, mailbox.path, mailbox.unseen, mailbox.total
+                );
+            }
+        }
+    }
+    Ok(())
+}
@@ -35,8 +35,10 @@ mod cells;
 #[macro_use]
 mod keys;
 pub mod embed;
+mod graphics;
 mod text_editing;
 pub use self::cells::*;
+pub use self::graphics::*;
 pub use self::keys::*;
 pub use self::position::*;
 pub use self::text_editing::*;
@@ -123,6 +125,19 @@ derive_csi_sequence!(
 pub const BRACKET_PASTE_START: &[u8] = b"\x1B[200~";
 pub const BRACKET_PASTE_END: &[u8] = b"\x1B[201~";
 
+derive_csi_sequence!(
+    #[doc = "Empty struct with a Display implementation that returns the byte sequence to start [Focus Event Mode](http://www.xfree86.org/current/ctlseqs.html#FocusIn_FocusOut)"]
+    (EnableFocusReporting, "?1004h")
+);
+
+derive_csi_sequence!(
+    #[doc = "Empty struct with a Display implementation that returns the byte sequence to end [Focus Event Mode](http://www.xfree86.org/current/ctlseqs.html#FocusIn_FocusOut)"]
+    (DisableFocusReporting, "?1004l")
+);
+
+pub const FOCUS_IN: &[u8] = b"\x1B[I";
+pub const FOCUS_OUT: &[u8] = b"\x1B[O";
+
 pub use braille::BraillePixelIter;
 mod braille {
     mod tests {
@@ -26,12 +26,13 @@
 
 use super::{position::*, Color};
 use crate::state::Context;
-use melib::text_processing::wcwidth;
+use melib::text_processing::grapheme_cluster_width;
+use unicode_segmentation::UnicodeSegmentation;
 
 use serde::{de, Deserialize, Deserializer, Serialize, Serializer};
 use smallvec::SmallVec;
+use std::cell::RefCell;
 use std::collections::HashMap;
-use std::convert::From;
 use std::fmt;
 use std::ops::{Deref, DerefMut, Index, IndexMut};
 
@@ -305,7 +306,7 @@ impl CellBuffer {
                 self[(x, y)] = Cell::default();
             }
         }
-        for y in top..=(scroll_region.bottom - offset) {
+        for y in top..(scroll_region.bottom + 1).saturating_sub(offset) {
             for x in l..r {
                 let temp = self[(x, y)];
                 self[(x, y)] = self[(x, y + offset)];
@@ -357,7 +358,7 @@ impl CellBuffer {
         //    "scroll_down scroll_region {:?}, top: {} offset {}",
         //    scroll_region, top, offset
         //);
-        for y in (scroll_region.bottom - offset + 1)..=scroll_region.bottom {
+        for y in (scroll_region.bottom + 1).saturating_sub(offset)..=scroll_region.bottom {
             for x in 0..self.size().0 {
                 self[(x, y)] = Cell::default();
             }
@@ -510,8 +511,13 @@ pub struct Cell {
     keep_fg: bool,
     keep_bg: bool,
     keep_attrs: bool,
+    /// Index of the `Cell`'s hyperlink target in `HYPERLINKS` plus one, or zero if it isn't part
+    /// of a hyperlink.
+    hyperlink: u32,
 }
 
+thread_local!(static HYPERLINKS: RefCell<indexmap::IndexSet<String>> = RefCell::new(indexmap::IndexSet::new()));
+
 impl Cell {
     /// Creates a new `Cell` with the given `char`, `Color`s and `Attr`.
     ///
@@ -534,6 +540,7 @@ impl Cell {
             keep_fg: false,
             keep_bg: false,
             keep_attrs: false,
+            hyperlink: 0,
         }
     }
 
@@ -595,6 +602,7 @@ impl Cell {
         self.keep_fg = false;
         self.keep_bg = false;
         self.keep_attrs = false;
+        self.hyperlink = 0;
         self
     }
 
@@ -700,6 +708,33 @@ impl Cell {
         self.keep_attrs = new_val;
         self
     }
+
+    /// Returns the identifier of the hyperlink the `Cell` is part of, or zero if there is none.
+    /// See `write_hyperlink`.
+    pub fn hyperlink(&self) -> u32 {
+        self.hyperlink
+    }
+
+    /// Makes the `Cell` part of a hyperlink to `url`, shown with OSC 8 escape sequences. The link
+    /// is removed by the next `set_ch`.
+    pub fn set_hyperlink(&mut self, url: &str) -> &mut Cell {
+        self.hyperlink = HYPERLINKS.with(|links| {
+            let (idx, _) = links.borrow_mut().insert_full(url.to_string());
+            idx as u32 + 1
+        });
+        self
+    }
+}
+
+/// Write the OSC 8 escape sequence that starts the hyperlink with identifier `id`, or ends the
+/// current hyperlink if `id` is zero.
+pub fn write_hyperlink<W: std::io::Write>(id: u32, stdout: &mut W) -> std::io::Result<()> {
+    let url = if id == 0 {
+        None
+    } else {
+        HYPERLINKS.with(|links| links.borrow().get_index(id as usize - 1).cloned())
+    };
+    write!(stdout, "\x1b]8;;{}\x1b\\", url.as_deref().unwrap_or(""))
 }
 
 impl Default for Cell {
@@ -1161,12 +1196,12 @@ pub fn write_string_to_grid(
             return (x, y);
         }
     }
-    for c in s.chars() {
+    for g in UnicodeSegmentation::graphemes(s, true) {
         inspect_bounds!(grid, area, x, y, line_break);
-        if c == '\r' {
+        if g == "\r" {
             continue;
         }
-        if c == '\n' {
+        if g == "\n" || g == "\r\n" {
             y += 1;
             if let Some(_x) = line_break {
                 x = _x;
@@ -1176,43 +1211,96 @@ pub fn write_string_to_grid(
                 break;
             }
         }
-        if c == '\t' {
+        let width = if g == "\t" {
+            1
+        } else {
+            grapheme_cluster_width(g)
+        };
+        if width == 0 {
+            /* Skip zero width graphemes instead of giving them a cell of their own */
+            continue;
+        }
+        if width == 2 && !grid.growable && (x >= get_x(bottom_right) || x + 1 >= get_x(grid.size()))
+        {
+            /* Don't split a wide grapheme over the edge of the area, move it to the next line
+             * instead. */
+            grid[(x, y)].set_ch(' ').set_empty(false);
+            x += 1;
+            inspect_bounds!(grid, area, x, y, line_break);
+        }
+        /* Overwriting half of a wide grapheme leaves its other half blank. */
+        if grid[(x, y)].empty() && x > 0 {
+            grid[(x - 1, y)].set_ch(' ');
+        }
+        if g == "\t" {
             grid[(x, y)].set_ch(' ');
             x += 1;
             inspect_bounds!(grid, area, x, y, line_break);
             grid[(x, y)].set_ch(' ');
         } else {
-            grid[(x, y)].set_ch(c);
+            grid[(x, y)].set_ch(g.chars().next().unwrap());
         }
         grid[(x, y)]
             .set_fg(fg_color)
             .set_bg(bg_color)
-            .set_attrs(attrs);
+            .set_attrs(attrs)
+            .set_empty(false);
 
-        match wcwidth(u32::from(c)) {
-            Some(0) | None => {
-                /* Skip drawing zero width characters */
-                grid[(x, y)].empty = true;
-            }
-            Some(2) => {
-                /* Grapheme takes more than one column, so the next cell will be
-                 * drawn over. Set it as empty to skip drawing it. */
-                x += 1;
-                inspect_bounds!(grid, area, x, y, line_break);
-                grid[(x, y)] = Cell::default();
-                grid[(x, y)]
-                    .set_fg(fg_color)
-                    .set_bg(bg_color)
-                    .set_attrs(attrs)
-                    .set_empty(true);
-            }
-            _ => {}
+        if width == 2 {
+            /* Grapheme takes more than one column, so the next cell will be
+             * drawn over. Set it as empty to skip drawing it. */
+            x += 1;
+            inspect_bounds!(grid, area, x, y, line_break);
+            grid[(x, y)] = Cell::default();
+            grid[(x, y)]
+                .set_fg(fg_color)
+                .set_bg(bg_color)
+                .set_attrs(attrs)
+                .set_empty(true);
+        } else if x + 1 < get_x(grid.size()) && grid[(x + 1, y)].empty() {
+            grid[(x + 1, y)].set_ch(' ').set_empty(false);
         }
         x += 1;
     }
     (x, y)
 }
 
+/// Turn the URLs and e-mail addresses of `kinds` shown in each row of `area` into hyperlinks.
+/// E-mail addresses link to `mailto:` URIs.
+pub fn set_hyperlinks(grid: &mut CellBuffer, area: Area, kinds: &[linkify::LinkKind]) {
+    if !is_valid_area!(area) {
+        return;
+    }
+    let mut finder = linkify::LinkFinder::new();
+    finder.kinds(kinds);
+    let (upper_left, bottom_right) = area;
+    let mut line = String::new();
+    let mut positions: Vec<(usize, Pos)> = vec![];
+    for y in get_y(upper_left)..=get_y(bottom_right) {
+        line.clear();
+        positions.clear();
+        for c in grid.row_iter(get_x(upper_left)..(get_x(bottom_right) + 1), y) {
+            if !grid[c].empty() {
+                positions.push((line.len(), c));
+                line.push(grid[c].ch());
+            }
+        }
+        for link in finder.links(&line) {
+            let url = if *link.kind() == linkify::LinkKind::Email {
+                format!("mailto:{}", link.as_str())
+            } else {
+                link.as_str().to_string()
+            };
+            for &(_, c) in positions
+                .iter()
+                .filter(|(offset, _)| *offset >= link.start() && *offset < link.end())
+            {
+                grid[c].set_hyperlink(&url);
+            }
+        }
+    }
+}
+
 /// Completely clear an `Area` with an empty char and the terminal's default colors.
 pub fn clear_area(grid: &mut CellBuffer, area: Area, attributes: crate::conf::ThemeAttribute) {
     if !is_valid_area!(area) {
@@ -184,6 +184,8 @@ pub enum State {
     Csi1(SmallVec<[u8; 8]>),
     Csi2(SmallVec<[u8; 8]>, SmallVec<[u8; 8]>),
     Csi3(SmallVec<[u8; 8]>, SmallVec<[u8; 8]>, SmallVec<[u8; 8]>),
+    /// A CSI sequence with more than three parameters.
+    CsiN(SmallVec<[SmallVec<[u8; 8]>; 8]>),
     CsiQ(SmallVec<[u8; 8]>),
     Normal,
 }
@@ -25,6 +25,10 @@ use melib::error::{MeliError, Result};
 use melib::text_processing::wcwidth;
 use nix::sys::wait::WaitStatus;
 use nix::sys::wait::{waitpid, WaitPidFlag};
+use std::collections::VecDeque;
+
+/// Maximum number of lines kept in the scrollback buffer.
+const SCROLLBACK_LINES: usize = 10_000;
 /**
  * `EmbedGrid` manages the terminal grid state of the embed process.
  *
@@ -49,6 +53,7 @@ pub struct EmbedGrid {
     pub terminal_size: (usize, usize),
     fg_color: Color,
     bg_color: Color,
+    attrs: Attr,
     /// Store the fg/bg color when highlighting the cell where the cursor is so that it can be
     /// restored afterwards
     prev_fg_color: Option<Color>,
@@ -57,11 +62,17 @@ pub struct EmbedGrid {
     show_cursor: bool,
     origin_mode: bool,
     auto_wrap_mode: bool,
+    /// The child has requested pasted text to be wrapped in bracketed paste sequences
+    pub bracketed_paste_mode: bool,
+    /// The child has requested to be notified of terminal focus changes
+    pub focus_reporting_mode: bool,
     /// If next grapheme should be placed in the next line
     /// This should be reset whenever the cursor value changes
     wrap_next: bool,
     /// Store state in case a multi-byte character is encountered
     codepoints: CodepointBuf,
+    /// Lines scrolled off the top of the screen, oldest first
+    pub scrollback: VecDeque<Vec<Cell>>,
 }
 
 #[derive(Debug, PartialEq)]
@@ -72,6 +83,84 @@ enum CodepointBuf {
     FourCodepoints(u8, Option<u8>, Option<u8>),
 }
 
+/// Apply the parameters of a Select Graphic Rendition sequence (`CSI Pm m`) to the current
+/// colors and attributes.
+fn apply_sgr<B: AsRef<[u8]>>(
+    params: &[B],
+    fg_color: &mut Color,
+    bg_color: &mut Color,
+    attrs: &mut Attr,
+) {
+    let params: SmallVec<[usize; 8]> = params
+        .iter()
+        .map(|p| {
+            std::str::from_utf8(p.as_ref())
+                .ok()
+                .and_then(|p| p.parse::<usize>().ok())
+                .unwrap_or(0)
+        })
+        .collect();
+    /* Parse the color of a `38` or `48` parameter starting at `params[i]` and return it along
+     * with the number of parameters it took. */
+    let extended_color = |i: usize| -> Option<(Color, usize)> {
+        match params.get(i + 1) {
+            Some(5) => Some((Color::Byte(*params.get(i + 2)? as u8), 2)),
+            Some(2) => Some((
+                Color::Rgb(
+                    *params.get(i + 2)? as u8,
+                    *params.get(i + 3)? as u8,
+                    *params.get(i + 4)? as u8,
+                ),
+                4,
+            )),
+            _ => None,
+        }
+    };
+    let mut i = 0;
+    while i < params.len() {
+        match params[i] {
+            0 => {
+                *fg_color = Color::Default;
+                *bg_color = Color::Default;
+                *attrs = Attr::DEFAULT;
+            }
+            1 => *attrs |= Attr::BOLD,
+            2 => *attrs |= Attr::DIM,
+            3 => *attrs |= Attr::ITALICS,
+            4 => *attrs |= Attr::UNDERLINE,
+            5 | 6 => *attrs |= Attr::BLINK,
+            7 => *attrs |= Attr::REVERSE,
+            8 => *attrs |= Attr::HIDDEN,
+            21 | 22 => attrs.remove(Attr::BOLD | Attr::DIM),
+            23 => attrs.remove(Attr::ITALICS),
+            24 => attrs.remove(Attr::UNDERLINE),
+            25 => attrs.remove(Attr::BLINK),
+            27 => attrs.remove(Attr::REVERSE),
+            28 => attrs.remove(Attr::HIDDEN),
+            n @ 30..=37 => *fg_color = Color::Byte((n - 30) as u8),
+            38 => {
+                if let Some((color, len)) = extended_color(i) {
+                    *fg_color = color;
+                    i += len;
+                }
+            }
+            39 => *fg_color = Color::Default,
+            n @ 40..=47 => *bg_color = Color::Byte((n - 40) as u8),
+            48 => {
+                if let Some((color, len)) = extended_color(i) {
+                    *bg_color = color;
+                    i += len;
+                }
+            }
+            49 => *bg_color = Color::Default,
+            n @ 90..=97 => *fg_color = Color::Byte((n - 90 + 8) as u8),
+            n @ 100..=107 => *bg_color = Color::Byte((n - 100 + 8) as u8),
+            _ => {}
+        }
+        i += 1;
+    }
+}
+
 impl EmbedGrid {
     pub fn new(stdin: std::fs::File, child_pid: nix::unistd::Pid) -> Self {
         EmbedGrid {
@@ -89,13 +178,41 @@ impl EmbedGrid {
             child_pid,
             fg_color: Color::Default,
             bg_color: Color::Default,
+            attrs: Attr::DEFAULT,
             prev_fg_color: None,
             prev_bg_color: None,
             show_cursor: true,
             auto_wrap_mode: true,
             wrap_next: false,
             origin_mode: false,
+            bracketed_paste_mode: false,
+            focus_reporting_mode: false,
             codepoints: CodepointBuf::None,
+            scrollback: VecDeque::new(),
+        }
+    }
+
+    /// Draw the screen scrolled back by `offset` lines of the scrollback buffer in `area`.
+    pub fn draw_scrollback(&self, grid: &mut CellBuffer, area: Area, offset: usize) {
+        let rows = height!(area) + 1;
+        let width = width!(area) + 1;
+        let total = self.scrollback.len() + self.terminal_size.1;
+        let start = total
+            .saturating_sub(rows)
+            .saturating_sub(std::cmp::min(offset, self.scrollback.len()));
+        let (x, y) = upper_left!(area);
+        for row in 0..rows {
+            let line = start + row;
+            for col in 0..width {
+                let cell = if line < self.scrollback.len() {
+                    self.scrollback[line].get(col).copied()
+                } else {
+                    self.grid.get(col, line - self.scrollback.len()).copied()
+                };
+                if let (Some(cell), Some(dest)) = (cell, grid.get_mut(x + col, y + row)) {
+                    *dest = cell;
+                }
+            }
         }
     }
 
@@ -107,15 +224,43 @@ impl EmbedGrid {
         self.scroll_region.top = 0;
         self.scroll_region.bottom = new_val.1.saturating_sub(1);
 
+        /* Keep the cursor row on screen when shrinking by pushing the lines above it into the
+         * scrollback buffer, like other terminal emulators do. */
+        let overflow = (self.cursor.1 + 1).saturating_sub(new_val.1);
+        if overflow > 0 {
+            let (cols, rows) = self.terminal_size;
+            for y in 0..overflow {
+                self.scrollback.push_back(
+                    self.grid
+                        .row_iter(0..cols, y)
+                        .map(|c| self.grid[c])
+                        .collect(),
+                );
+            }
+            while self.scrollback.len() > SCROLLBACK_LINES {
+                self.scrollback.pop_front();
+            }
+            let full_screen = ScrollRegion {
+                top: 0,
+                bottom: rows.saturating_sub(1),
+                ..Default::default()
+            };
+            self.grid.scroll_up(&full_screen, 0, overflow);
+            self.cursor.1 -= overflow;
+        }
+
         self.terminal_size = new_val;
-        if !self.grid.resize(new_val.0, new_val.1, None) {
+        if !self
+            .grid
+            .resize(new_val.0, new_val.1, Some(Cell::default()))
+        {
             panic!(
                 "Terminal size too big: ({} cols, {} rows)",
                 new_val.0, new_val.1
             );
         }
-        self.grid.clear(Some(Cell::default()));
-        self.cursor = (0, 0);
+        self.cursor.0 = std::cmp::min(self.cursor.0, new_val.0.saturating_sub(1));
+        self.cursor.1 = std::cmp::min(self.cursor.1, new_val.1.saturating_sub(1));
         self.wrap_next = false;
         let winsize = Winsize {
             ws_row: <u16>::try_from(new_val.1).unwrap(),
@@ -153,6 +298,7 @@ impl EmbedGrid {
             ref mut stdin,
             ref mut fg_color,
             ref mut bg_color,
+            ref mut attrs,
             ref mut prev_fg_color,
             ref mut prev_bg_color,
             ref mut codepoints,
@@ -160,9 +306,30 @@ impl EmbedGrid {
             ref mut auto_wrap_mode,
             ref mut wrap_next,
             ref mut origin_mode,
+            ref mut bracketed_paste_mode,
+            ref mut focus_reporting_mode,
+            ref mut scrollback,
             child_pid: _,
         } = self;
 
+        /* Keep lines that scroll off the top of the screen */
+        macro_rules! save_scrollback {
+            ($top:expr, $n:expr) => {
+                if $top == 0 {
+                    for y in 0..std::cmp::min($n, terminal_size.1) {
+                        scrollback.push_back(
+                            grid.row_iter(0..terminal_size.0, y)
+                                .map(|c| grid[c])
+                                .collect(),
+                        );
+                    }
+                    while scrollback.len() > SCROLLBACK_LINES {
+                        scrollback.pop_front();
+                    }
+                }
+            };
+        }
+
         macro_rules! increase_cursor_x {
             () => {
                 if cursor.0 + 1 < terminal_size.0 {
@@ -183,10 +350,7 @@ impl EmbedGrid {
         }
         macro_rules! cursor_y {
             () => {
-                std::cmp::min(
-                    cursor.1 + scroll_region.top,
-                    terminal_size.1.saturating_sub(1),
-                )
+                std::cmp::min(cursor.1, terminal_size.1.saturating_sub(1))
             };
         }
         macro_rules! cursor_val {
@@ -214,6 +378,7 @@ impl EmbedGrid {
                 // ESCD Linefeed
                 //debug!("{}", EscCode::from((&(*state), byte)));
                 if cursor.1 == scroll_region.bottom {
+                    save_scrollback!(scroll_region.top, 1);
                     grid.scroll_up(scroll_region, scroll_region.top, 1);
                 } else {
                     cursor.1 += 1;
@@ -221,6 +386,16 @@ impl EmbedGrid {
                 *wrap_next = false;
                 *state = State::Normal;
             }
+            (b'M', State::ExpectingControlChar) => {
+                // ESCM Reverse index
+                if cursor.1 == scroll_region.top {
+                    grid.scroll_down(scroll_region, scroll_region.top, 1);
+                } else if cursor.1 > 0 {
+                    cursor.1 -= 1;
+                }
+                *wrap_next = false;
+                *state = State::Normal;
+            }
             (b'J', State::ExpectingControlChar) => {
                 // ESCJ Erase from the cursor to the end of the screen
                 //debug!("sending {}", EscCode::from((&(*state), byte)));
@@ -276,6 +451,7 @@ impl EmbedGrid {
 
                 if cursor.1 + 1 < terminal_size.1 {
                     if cursor.1 == scroll_region.bottom {
+                        save_scrollback!(cursor.1, 1);
                         grid.scroll_up(scroll_region, cursor.1, 1);
                     } else {
                         cursor.1 += 1;
@@ -362,6 +538,7 @@ impl EmbedGrid {
                 if *auto_wrap_mode && *wrap_next {
                     *wrap_next = false;
                     if cursor.1 == scroll_region.bottom {
+                        save_scrollback!(scroll_region.top, 1);
                         grid.scroll_up(scroll_region, scroll_region.top, 1);
                     } else {
                         cursor.1 += 1;
@@ -375,6 +552,7 @@ impl EmbedGrid {
                 grid[cursor_val!()].set_ch(c);
                 grid[cursor_val!()].set_fg(*fg_color);
                 grid[cursor_val!()].set_bg(*bg_color);
+                grid[cursor_val!()].set_attrs(*attrs);
                 match wcwidth(u32::from(c)) {
                     Some(0) | None => {
                         /* Skip drawing zero width characters */
@@ -389,6 +567,7 @@ impl EmbedGrid {
                             grid[cursor_val!()].set_empty(true);
                             grid[cursor_val!()].set_fg(*fg_color);
                             grid[cursor_val!()].set_bg(*bg_color);
+                            grid[cursor_val!()].set_attrs(*attrs);
                         }
                     }
                 }
@@ -405,6 +584,7 @@ impl EmbedGrid {
                 //debug!("{}", EscCode::from((&(*state), byte)));
                 *fg_color = Color::Default;
                 *bg_color = Color::Default;
+                *attrs = Attr::DEFAULT;
                 grid[cursor_val!()].set_fg(Color::Default);
                 grid[cursor_val!()].set_bg(Color::Default);
                 *state = State::Normal;
@@ -429,6 +609,12 @@ impl EmbedGrid {
                     b"7" => {
                         *auto_wrap_mode = true;
                     }
+                    b"1004" => {
+                        *focus_reporting_mode = true;
+                    }
+                    b"2004" => {
+                        *bracketed_paste_mode = true;
+                    }
                     b"25" => {
                         *show_cursor = true;
                         *prev_fg_color = Some(grid[cursor_val!()].fg());
@@ -450,6 +636,12 @@ impl EmbedGrid {
                     b"7" => {
                         *auto_wrap_mode = false;
                     }
+                    b"1004" => {
+                        *focus_reporting_mode = false;
+                    }
+                    b"2004" => {
+                        *bracketed_paste_mode = false;
+                    }
                     b"25" => {
                         *show_cursor = false;
                         if let Some(fg_color) = prev_fg_color.take() {
@@ -482,10 +674,7 @@ impl EmbedGrid {
                     (
                         (
                             0,
-                            std::cmp::min(
-                                cursor.1 + 1 + scroll_region.top,
-                                terminal_size.1.saturating_sub(1),
-                            ),
+                            std::cmp::min(cursor.1 + 1, terminal_size.1.saturating_sub(1)),
                         ),
                         (
                             terminal_size.0.saturating_sub(1),
@@ -516,7 +705,12 @@ impl EmbedGrid {
                     1
                 };
 
-                grid.scroll_down(scroll_region, cursor.1, n);
+                if cursor.1 >= scroll_region.top && cursor.1 <= scroll_region.bottom {
+                    let n = std::cmp::min(n, scroll_region.bottom + 1 - cursor.1);
+                    grid.scroll_down(scroll_region, cursor.1, n);
+                    cursor.0 = 0;
+                    *wrap_next = false;
+                }
 
                 //debug!("{}", EscCode::from((&(*state), byte)));
                 *state = State::Normal;
@@ -531,7 +725,13 @@ impl EmbedGrid {
                     1
                 };
 
-                grid.scroll_up(scroll_region, cursor.1, n);
+                if cursor.1 >= scroll_region.top && cursor.1 <= scroll_region.bottom {
+                    let n = std::cmp::min(n, scroll_region.bottom + 1 - cursor.1);
+                    save_scrollback!(cursor.1, n);
+                    grid.scroll_up(scroll_region, cursor.1, n);
+                    cursor.0 = 0;
+                    *wrap_next = false;
+                }
 
                 //debug!("{}", EscCode::from((&(*state), byte)));
                 *state = State::Normal;
@@ -590,10 +790,7 @@ impl EmbedGrid {
                     (
                         (
                             0,
-                            std::cmp::min(
-                                cursor.1 + 1 + scroll_region.top,
-                                terminal_size.1.saturating_sub(1),
-                            ),
+                            std::cmp::min(cursor.1 + 1, terminal_size.1.saturating_sub(1)),
                         ),
                         (
                             terminal_size.0.saturating_sub(1),
@@ -614,7 +811,7 @@ impl EmbedGrid {
                         (0, 0),
                         (
                             terminal_size.0.saturating_sub(1),
-                            cursor.1.saturating_sub(1) + scroll_region.top,
+                            cursor.1.saturating_sub(1),
                         ),
                     ),
                     Default::default(),
@@ -727,7 +924,7 @@ impl EmbedGrid {
                 } else if offset + cursor.1 < terminal_size.1 {
                     cursor.1 += offset;
                 }
-                if scroll_region.top + cursor.1 >= terminal_size.1 {
+                if cursor.1 >= terminal_size.1 {
                     cursor.1 = terminal_size.1.saturating_sub(1);
                 }
                 *wrap_next = false;
@@ -760,7 +957,7 @@ impl EmbedGrid {
                 if offset + cursor.1 < terminal_size.1 {
                     cursor.1 += offset;
                 }
-                if scroll_region.top + cursor.1 >= terminal_size.1 {
+                if cursor.1 >= terminal_size.1 {
                     cursor.1 = terminal_size.1.saturating_sub(1);
                 }
                 cursor.0 = 0;
@@ -817,6 +1014,28 @@ impl EmbedGrid {
                 //debug!("cursor became: {:?}", cursor);
                 *state = State::Normal;
             }
+            (b'@', State::Csi1(_)) | (b'@', State::Csi) => {
+                // ESC[{buf}@   CSI Insert {buf} blank characters, default = 1
+                let offset = if let State::Csi1(buf) = state {
+                    unsafe { std::str::from_utf8_unchecked(buf) }
+                        .parse::<usize>()
+                        .unwrap_or(1)
+                } else {
+                    1
+                };
+                let (x, y) = cursor_val!();
+                let offset = std::cmp::min(offset, terminal_size.0 - x);
+                for i in (x + offset..terminal_size.0).rev() {
+                    grid[(i, y)] = grid[(i - offset, y)];
+                }
+                for i in x..(x + offset) {
+                    grid[(i, y)] = Cell::default();
+                    grid[(i, y)].set_fg(*fg_color);
+                    grid[(i, y)].set_bg(*bg_color);
+                }
+                *wrap_next = false;
+                *state = State::Normal;
+            }
             (b'P', State::Csi1(_)) | (b'P', State::Csi) => {
                 // ESC[{buf}P   CSI Delete {buf} characters, default = 1
                 let offset = if let State::Csi1(buf) = state {
@@ -853,7 +1072,7 @@ impl EmbedGrid {
                 //    row, cursor
                 //);
                 cursor.1 = row.saturating_sub(1);
-                if scroll_region.top + cursor.1 >= terminal_size.1 {
+                if cursor.1 >= terminal_size.1 {
                     cursor.1 = terminal_size.1.saturating_sub(1);
                 }
                 *wrap_next = false;
@@ -867,129 +1086,13 @@ impl EmbedGrid {
             }
             (b'm', State::Csi1(ref buf1)) => {
                 // Character Attributes.
-                match buf1.as_slice() {
-                    b"0" => {
-                        *fg_color = Color::Default;
-                        *bg_color = Color::Default;
-                    }
-                    b"1" => { /* bold */ }
-                    b"7" => {
-                        /* Inverse */
-                        let temp = *fg_color;
-                        *fg_color = *bg_color;
-                        *bg_color = temp;
-                    }
-                    b"27" => {
-                        /* Inverse off */
-                        let temp = *fg_color;
-                        *fg_color = *bg_color;
-                        *bg_color = temp;
-                    }
-                    b"30" => *fg_color = Color::Black,
-                    b"31" => *fg_color = Color::Red,
-                    b"32" => *fg_color = Color::Green,
-                    b"33" => *fg_color = Color::Yellow,
-                    b"34" => *fg_color = Color::Blue,
-                    b"35" => *fg_color = Color::Magenta,
-                    b"36" => *fg_color = Color::Cyan,
-                    b"37" => *fg_color = Color::White,
-
-                    b"39" => *fg_color = Color::Default,
-                    b"40" => *bg_color = Color::Black,
-                    b"41" => *bg_color = Color::Red,
-                    b"42" => *bg_color = Color::Green,
-                    b"43" => *bg_color = Color::Yellow,
-                    b"44" => *bg_color = Color::Blue,
-                    b"45" => *bg_color = Color::Magenta,
-                    b"46" => *bg_color = Color::Cyan,
-                    b"47" => *bg_color = Color::White,
-
-                    b"49" => *bg_color = Color::Default,
-
-                    b"90" => *fg_color = Color::Black,
-                    b"91" => *fg_color = Color::Red,
-                    b"92" => *fg_color = Color::Green,
-                    b"93" => *fg_color = Color::Yellow,
-                    b"94" => *fg_color = Color::Blue,
-                    b"95" => *fg_color = Color::Magenta,
-                    b"96" => *fg_color = Color::Cyan,
-                    b"97" => *fg_color = Color::White,
-
-                    b"100" => *bg_color = Color::Black,
-                    b"101" => *bg_color = Color::Red,
-                    b"102" => *bg_color = Color::Green,
-                    b"103" => *bg_color = Color::Yellow,
-                    b"104" => *bg_color = Color::Blue,
-                    b"105" => *bg_color = Color::Magenta,
-                    b"106" => *bg_color = Color::Cyan,
-                    b"107" => *bg_color = Color::White,
-                    _ => {}
-                }
+                apply_sgr(&[buf1], fg_color, bg_color, attrs);
                 grid[cursor_val!()].set_fg(*fg_color);
                 grid[cursor_val!()].set_bg(*bg_color);
                 *state = State::Normal;
             }
             (b'm', State::Csi2(ref buf1, ref buf2)) => {
-                for b in &[buf1, buf2] {
-                    match b.as_slice() {
-                        b"0" => {
-                            *fg_color = Color::Default;
-                            *bg_color = Color::Default;
-                        }
-                        b"1" => { /* bold */ }
-                        b"7" => {
-                            /* Inverse */
-                            let temp = *fg_color;
-                            *fg_color = *bg_color;
-                            *bg_color = temp;
-                        }
-                        b"27" => {
-                            /* Inverse off */
-                            let temp = *fg_color;
-                            *fg_color = *bg_color;
-                            *bg_color = temp;
-                        }
-                        b"30" => *fg_color = Color::Black,
-                        b"31" => *fg_color = Color::Red,
-                        b"32" => *fg_color = Color::Green,
-                        b"33" => *fg_color = Color::Yellow,
-                        b"34" => *fg_color = Color::Blue,
-                        b"35" => *fg_color = Color::Magenta,
-                        b"36" => *fg_color = Color::Cyan,
-                        b"37" => *fg_color = Color::White,
-
-                        b"39" => *fg_color = Color::Default,
-                        b"40" => *bg_color = Color::Black,
-                        b"41" => *bg_color = Color::Red,
-                        b"42" => *bg_color = Color::Green,
-                        b"43" => *bg_color = Color::Yellow,
-                        b"44" => *bg_color = Color::Blue,
-                        b"45" => *bg_color = Color::Magenta,
-                        b"46" => *bg_color = Color::Cyan,
-                        b"47" => *bg_color = Color::White,
-
-                        b"49" => *bg_color = Color::Default,
-
-                        b"90" => *fg_color = Color::Black,
-                        b"91" => *fg_color = Color::Red,
-                        b"92" => *fg_color = Color::Green,
-                        b"93" => *fg_color = Color::Yellow,
-                        b"94" => *fg_color = Color::Blue,
-                        b"95" => *fg_color = Color::Magenta,
-                        b"96" => *fg_color = Color::Cyan,
-                        b"97" => *fg_color = Color::White,
-
-                        b"100" => *bg_color = Color::Black,
-                        b"101" => *bg_color = Color::Red,
-                        b"102" => *bg_color = Color::Green,
-                        b"103" => *bg_color = Color::Yellow,
-                        b"104" => *bg_color = Color::Blue,
-                        b"105" => *bg_color = Color::Magenta,
-                        b"106" => *bg_color = Color::Cyan,
-                        b"107" => *bg_color = Color::White,
-                        _ => {}
-                    }
-                }
+                apply_sgr(&[buf1, buf2], fg_color, bg_color, attrs);
                 grid[cursor_val!()].set_fg(*fg_color);
                 grid[cursor_val!()].set_bg(*bg_color);
                 *state = State::Normal;
@@ -1047,7 +1150,7 @@ impl EmbedGrid {
             (c, State::Csi2(_, ref mut buf)) if c >= b'0' && c <= b'9' => {
                 buf.push(c);
             }
-            (b'r', State::Csi2(_, _)) | (b'r', State::Csi) => {
+            (b'r', State::Csi2(_, _)) | (b'r', State::Csi1(_)) | (b'r', State::Csi) => {
                 /* CSI Ps ; Ps r Set Scrolling Region [top;bottom] (default = full size of window) (DECSTBM). */
                 let (top, bottom) = if let State::Csi2(ref top, ref bottom) = state {
                     (
@@ -1058,14 +1161,31 @@ impl EmbedGrid {
                             .parse::<usize>()
                             .unwrap_or(1),
                     )
+                } else if let State::Csi1(ref top) = state {
+                    (
+                        unsafe { std::str::from_utf8_unchecked(top) }
+                            .parse::<usize>()
+                            .unwrap_or(1),
+                        terminal_size.1,
+                    )
                 } else {
                     (1, terminal_size.1)
                 };
+                let top = std::cmp::max(top, 1);
+                let bottom = if bottom == 0 {
+                    terminal_size.1
+                } else {
+                    std::cmp::min(bottom, terminal_size.1)
+                };
 
                 if bottom > top {
                     scroll_region.top = top - 1;
                     scroll_region.bottom = bottom - 1;
-                    *cursor = (0, 0);
+                    *cursor = if *origin_mode {
+                        (0, scroll_region.top)
+                    } else {
+                        (0, 0)
+                    };
                     *wrap_next = false;
                 }
                 //debug!("set scrolling region to {:?}", scroll_region);
@@ -1080,33 +1200,31 @@ impl EmbedGrid {
             (c, State::Csi3(_, _, ref mut buf)) if c >= b'0' && c <= b'9' => {
                 buf.push(c);
             }
-            (b'm', State::Csi3(ref buf1, ref buf2, ref buf3))
-                if buf1.as_ref() == b"38" && buf2.as_ref() == b"5" =>
-            {
-                /* Set character attributes | foreground color */
-                *fg_color = if let Ok(byte) =
-                    u8::from_str_radix(unsafe { std::str::from_utf8_unchecked(buf3) }, 10)
-                {
-                    //debug!("parsed buf as {}", byte);
-                    Color::Byte(byte)
-                } else {
-                    Color::Default
-                };
+            (b'm', State::Csi3(ref buf1, ref buf2, ref buf3)) => {
+                /* Set character attributes */
+                apply_sgr(&[buf1, buf2, buf3], fg_color, bg_color, attrs);
                 grid[cursor_val!()].set_fg(*fg_color);
+                grid[cursor_val!()].set_bg(*bg_color);
                 *state = State::Normal;
             }
-            (b'm', State::Csi3(ref buf1, ref buf2, ref buf3))
-                if buf1.as_ref() == b"48" && buf2.as_ref() == b"5" =>
-            {
-                /* Set character attributes | background color */
-                *bg_color = if let Ok(byte) =
-                    u8::from_str_radix(unsafe { std::str::from_utf8_unchecked(buf3) }, 10)
-                {
-                    //debug!("parsed buf as {}", byte);
-                    Color::Byte(byte)
-                } else {
-                    Color::Default
-                };
+            (b';', State::Csi3(ref mut buf1_p, ref mut buf2_p, ref mut buf3_p)) => {
+                let mut bufs = SmallVec::new();
+                bufs.push(std::mem::replace(buf1_p, SmallVec::new()));
+                bufs.push(std::mem::replace(buf2_p, SmallVec::new()));
+                bufs.push(std::mem::replace(buf3_p, SmallVec::new()));
+                bufs.push(SmallVec::new());
+                *state = State::CsiN(bufs);
+            }
+            (c, State::CsiN(ref mut bufs)) if c >= b'0' && c <= b'9' => {
+                bufs.last_mut().unwrap().push(c);
+            }
+            (b';', State::CsiN(ref mut bufs)) => {
+                bufs.push(SmallVec::new());
+            }
+            (b'm', State::CsiN(ref bufs)) => {
+                /* Set character attributes, eg. direct colors `38;2;R;G;B` */
+                apply_sgr(bufs, fg_color, bg_color, attrs);
+                grid[cursor_val!()].set_fg(*fg_color);
                 grid[cursor_val!()].set_bg(*bg_color);
                 *state = State::Normal;
             }
@@ -1126,6 +1244,10 @@ impl EmbedGrid {
                 debug!("ignoring unknown code {}", EscCode::from((&(*state), byte)));
                 *state = State::Normal;
             }
+            (_, State::CsiN(_)) => {
+                debug!("ignoring unknown code {}", EscCode::from((&(*state), byte)));
+                *state = State::Normal;
+            }
             (_, State::Osc1(_)) => {
                 debug!("ignoring unknown code {}", EscCode::from((&(*state), byte)));
                 *state = State::Normal;
@@ -1146,3 +1268,22 @@ impl EmbedGrid {
         }
     }
 }
+
+#[test]
+fn test_apply_sgr() {
+    let mut fg_color = Color::Default;
+    let mut bg_color = Color::Default;
+    let mut attrs = Attr::DEFAULT;
+    apply_sgr(
+        &["1", "38", "2", "10", "20", "30", "44"],
+        &mut fg_color,
+        &mut bg_color,
+        &mut attrs,
+    );
+    assert_eq!(fg_color, Color::Rgb(10, 20, 30));
+    assert_eq!(bg_color, Color::Byte(4));
+    assert_eq!(attrs, Attr::BOLD);
+    apply_sgr(&["22", "39"], &mut fg_color, &mut bg_color, &mut attrs);
+    assert_eq!(fg_color, Color::Default);
+    assert_eq!(attrs, Attr::DEFAULT);
+}
@@ -0,0 +1,250 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Inline images with terminal graphics protocols.
+
+Images are converted to PNG (with ImageMagick's `convert` if they aren't PNG already) and then
+either sent as is with the kitty graphics protocol or converted to sixel with `img2sixel` from
+libsixel. Both run external commands, so components call them from jobs.
+*/
+
+use super::position::*;
+use melib::data_encoding::BASE64;
+use melib::{MeliError, Result};
+use std::convert::TryInto;
+use std::io::Write;
+use std::process::{Command, Stdio};
+use std::sync::atomic::{AtomicU32, Ordering};
+
+const PNG_MAGIC: &[u8] = b"\x89PNG\r\n\x1a\n";
+/// Maximum payload size of a kitty graphics protocol escape code.
+const KITTY_CHUNK_SIZE: usize = 4096;
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum GraphicsProtocol {
+    Kitty,
+    Sixel,
+}
+
+impl GraphicsProtocol {
+    /// Guess the graphics protocol the terminal supports from the environment.
+    pub fn detect() -> Option<Self> {
+        if std::env::var_os("KITTY_WINDOW_ID").is_some() {
+            return Some(GraphicsProtocol::Kitty);
+        }
+        let term = std::env::var("TERM").unwrap_or_default();
+        if term == "xterm-kitty" || term == "wezterm" {
+            Some(GraphicsProtocol::Kitty)
+        } else if ["mlterm", "foot", "yaft", "contour"]
+            .iter()
+            .any(|t| term.starts_with(t))
+            || term.contains("sixel")
+        {
+            Some(GraphicsProtocol::Sixel)
+        } else {
+            None
+        }
+    }
+}
+
+/// An image ready to be drawn on the terminal.
+#[derive(Debug, Clone)]
+pub struct TerminalImage {
+    png: Vec<u8>,
+    /// Width in pixels
+    width: usize,
+    /// Height in pixels
+    height: usize,
+}
+
+impl TerminalImage {
+    pub fn new(bytes: &[u8]) -> Result<Self> {
+        let png = if bytes.starts_with(PNG_MAGIC) {
+            bytes.to_vec()
+        } else {
+            pipe_through("convert", &["-", "png:-"], bytes)?
+        };
+        let (width, height) = png_dimensions(&png)
+            .ok_or_else(|| MeliError::new("Could not read image dimensions."))?;
+        Ok(TerminalImage { png, width, height })
+    }
+
+    /// Size in cells of the image when scaled to fit in `cols`×`rows` cells, keeping its aspect
+    /// ratio. Images are never scaled up.
+    pub fn fit(&self, cols: usize, rows: usize) -> (usize, usize) {
+        let (cell_width, cell_height) = cell_size();
+        let scale = (1.0_f64)
+            .min((cols * cell_width) as f64 / self.width as f64)
+            .min((rows * cell_height) as f64 / self.height as f64);
+        let width = (self.width as f64 * scale / cell_width as f64).ceil() as usize;
+        let height = (self.height as f64 * scale / cell_height as f64).ceil() as usize;
+        (
+            std::cmp::max(1, std::cmp::min(width, cols)),
+            std::cmp::max(1, std::cmp::min(height, rows)),
+        )
+    }
+
+    /// Escape sequences that draw the image at the cursor position in `cols`×`rows` cells.
+    pub fn encode(
+        &self,
+        protocol: GraphicsProtocol,
+        id: u32,
+        cols: usize,
+        rows: usize,
+    ) -> Result<Vec<u8>> {
+        match protocol {
+            GraphicsProtocol::Kitty => Ok(kitty_transmit(&self.png, id, cols, rows)),
+            GraphicsProtocol::Sixel => {
+                let (cell_width, _) = cell_size();
+                pipe_through(
+                    "img2sixel",
+                    &["-w", &(cols * cell_width).to_string()],
+                    &self.png,
+                )
+            }
+        }
+    }
+}
+
+/// An encoded image placed on screen. Components push these to `Context::inline_images` when
+/// drawing, and they are written to the terminal after the grid has been drawn.
+#[derive(Debug, Clone)]
+pub struct InlineImage {
+    pub id: u32,
+    pub protocol: GraphicsProtocol,
+    pub area: Area,
+    pub escape: Vec<u8>,
+}
+
+impl InlineImage {
+    /// Returns a new unique image id.
+    pub fn new_id() -> u32 {
+        static ID: AtomicU32 = AtomicU32::new(1);
+        ID.fetch_add(1, Ordering::Relaxed)
+    }
+}
+
+/// Escape sequence that removes a kitty image placement and frees its data.
+pub fn kitty_delete(id: u32) -> String {
+    format!("\x1b_Ga=d,d=I,i={},q=2\x1b\\", id)
+}
+
+fn kitty_transmit(png: &[u8], id: u32, cols: usize, rows: usize) -> Vec<u8> {
+    let payload = BASE64.encode(png);
+    let chunks = payload
+        .as_bytes()
+        .chunks(KITTY_CHUNK_SIZE)
+        .collect::<Vec<_>>();
+    let mut ret = Vec::with_capacity(payload.len() + 32 * chunks.len());
+    for (i, chunk) in chunks.iter().enumerate() {
+        let more = if i + 1 < chunks.len() { 1 } else { 0 };
+        if i == 0 {
+            /* C=1: don't move the cursor, q=2: suppress responses */
+            write!(
+                &mut ret,
+                "\x1b_Ga=T,f=100,t=d,i={},c={},r={},C=1,q=2,m={};",
+                id, cols, rows, more
+            )
+            .unwrap();
+        } else {
+            write!(&mut ret, "\x1b_Gm={};", more).unwrap();
+        }
+        ret.extend_from_slice(chunk);
+        ret.extend_from_slice(b"\x1b\\");
+    }
+    ret
+}
+
+fn png_dimensions(png: &[u8]) -> Option<(usize, usize)> {
+    /* The IHDR chunk always comes first, right after the signature and the chunk length. */
+    if png.len() < 24 || !png.starts_with(PNG_MAGIC) || &png[12..16] != b"IHDR" {
+        return None;
+    }
+    let width = u32::from_be_bytes(png[16..20].try_into().ok()?) as usize;
+    let height = u32::from_be_bytes(png[20..24].try_into().ok()?) as usize;
+    if width == 0 || height == 0 {
+        return None;
+    }
+    Some((width, height))
+}
+
+/// Size of a terminal cell in pixels, if the terminal reports it. Defaults to 8×16.
+pub fn cell_size() -> (usize, usize) {
+    let mut ws: libc::winsize = unsafe { std::mem::zeroed() };
+    let ret = unsafe { libc::ioctl(libc::STDOUT_FILENO, libc::TIOCGWINSZ, &mut ws) };
+    if ret == 0 && ws.ws_xpixel > 0 && ws.ws_ypixel > 0 && ws.ws_col > 0 && ws.ws_row > 0 {
+        (
+            (ws.ws_xpixel / ws.ws_col) as usize,
+            (ws.ws_ypixel / ws.ws_row) as usize,
+        )
+    } else {
+        (8, 16)
+    }
+}
+
+fn pipe_through(cmd: &str, args: &[&str], input: &[u8]) -> Result<Vec<u8>> {
+    let mut child = Command::new(cmd)
+        .args(args)
+        .stdin(Stdio::piped())
+        .stdout(Stdio::piped())
+        .stderr(Stdio::null())
+        .spawn()
+        .map_err(|err| MeliError::new(format!("Could not execute `{}`: {}", cmd, err)))?;
+    /* Write the input from another thread, so that the command doesn't block on a full stdout
+     * pipe while we block on a full stdin pipe. */
+    let mut stdin = child.stdin.take().unwrap();
+    let input = input.to_vec();
+    let writer = std::thread::spawn(move || stdin.write_all(&input));
+    let output = child.wait_with_output()?;
+    writer
+        .join()
+        .map_err(|_| MeliError::new(format!("Could not write to `{}`", cmd)))??;
+    if !output.status.success() || output.stdout.is_empty() {
+        return Err(MeliError::new(format!(
+            "`{}` exited with {}",
+            cmd, output.status
+        )));
+    }
+    Ok(output.stdout)
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_kitty_transmit() {
+        let mut png = PNG_MAGIC.to_vec();
+        png.extend_from_slice(b"\x00\x00\x00\x0dIHDR\x00\x00\x01\x00\x00\x00\x00\x80");
+        assert_eq!(png_dimensions(&png), Some((256, 128)));
+        assert_eq!(png_dimensions(&png[..20]), None);
+
+        /* 3 bytes are encoded as 4 base64 characters */
+        png.resize(3 * KITTY_CHUNK_SIZE, 0);
+        let escape = String::from_utf8(kitty_transmit(&png, 7, 10, 5)).unwrap();
+        assert!(escape.starts_with("\x1b_Ga=T,f=100,t=d,i=7,c=10,r=5,C=1,q=2,m=1;"));
+        assert_eq!(escape.matches("\x1b_G").count(), 4);
+        assert_eq!(escape.matches("\x1b_Gm=1;").count(), 2);
+        assert_eq!(escape.matches("\x1b_Gm=0;").count(), 1);
+        assert!(escape.ends_with("\x1b\\"));
+    }
+}
@@ -67,6 +67,8 @@ pub enum Key {
     Esc,
     Mouse(termion::event::MouseEvent),
     Paste(String),
+    /// The terminal gained (`true`) or lost (`false`) focus.
+    Focus(bool),
 }
 
 pub use termion::event::MouseButton;
@@ -98,6 +100,8 @@ impl fmt::Display for Key {
             Delete => write!(f, "Delete"),
             Insert => write!(f, "Insert"),
             Mouse(_) => write!(f, "Mouse"),
+            Focus(true) => write!(f, "Focus in"),
+            Focus(false) => write!(f, "Focus out"),
         }
     }
 }
@@ -210,6 +214,13 @@ pub fn get_events(
                                     closure((ret, buf));
                                     continue 'poll_while;
                                 }
+                            (Ok((TermionEvent::Unsupported(ref k), ref mut bytes)), InputMode::Normal)
+                                if k.as_slice() == FOCUS_IN || k.as_slice() == FOCUS_OUT =>
+                                {
+                                    let bytes = std::mem::replace(bytes, Vec::new());
+                                    closure((Key::Focus(k.as_slice() == FOCUS_IN), bytes));
+                                    continue 'poll_while;
+                                }
                             (Ok((TermionEvent::Mouse(mev), bytes)), InputMode::Normal) => {
                                 closure((Key::Mouse(mev), bytes));
                                 continue 'poll_while;
@@ -351,7 +362,7 @@ impl Serialize for Key {
             Key::Alt(c) => serializer.serialize_str(&format!("M-{}", c)),
             Key::Ctrl(c) => serializer.serialize_str(&format!("C-{}", c)),
             Key::Null => serializer.serialize_str("Null"),
-            Key::Mouse(_) => unreachable!(),
+            Key::Mouse(_) | Key::Focus(_) => unreachable!(),
             Key::Paste(s) => serializer.serialize_str(s),
         }
     }
@@ -96,7 +96,8 @@ pub enum ForkType {
 pub enum NotificationType {
     Info,
     Error(melib::error::ErrorKind),
-    NewMail,
+    /// New mail in an account's mailbox.
+    NewMail(AccountHash, MailboxHash, EnvelopeHash),
     SentMail,
     Saved,
 }
@@ -107,7 +108,7 @@ impl core::fmt::Display for NotificationType {
             NotificationType::Info => write!(f, "info"),
             NotificationType::Error(melib::error::ErrorKind::None) => write!(f, "error"),
             NotificationType::Error(kind) => write!(f, "error: {}", kind),
-            NotificationType::NewMail => write!(f, "new mail"),
+            NotificationType::NewMail(..) => write!(f, "new mail"),
             NotificationType::SentMail => write!(f, "sent mail"),
             NotificationType::Saved => write!(f, "saved"),
         }
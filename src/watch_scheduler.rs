@@ -0,0 +1,196 @@
+/*
+ * meli
+ *
+ * Copyright 2020 Manos Pitsidianakis
+ *
+ * This file is part of meli.
+ *
+ * meli is free software: you can redistribute it and/or modify
+ * it under the terms of the GNU General Public License as published by
+ * the Free Software Foundation, either version 3 of the License, or
+ * (at your option) any later version.
+ *
+ * meli is distributed in the hope that it will be useful,
+ * but WITHOUT ANY WARRANTY; without even the implied warranty of
+ * MERCHANTABILITY or FITNESS FOR A PARTICULAR PURPOSE.  See the
+ * GNU General Public License for more details.
+ *
+ * You should have received a copy of the GNU General Public License
+ * along with meli. If not, see <http://www.gnu.org/licenses/>.
+ */
+
+/*! Scheduling of the watchers of all accounts by the `watch_urgency` of their mailboxes.
+ *
+ * Watchers are started a few seconds apart instead of all at once, high urgency mailboxes are
+ * polled every minute, one poll at a time, and watchers of accounts whose mailboxes all have low
+ * urgency are paused while the `on_battery` hook reports that the machine runs on battery.
+ */
+
+use crate::conf::accounts::Account;
+use crate::conf::hooks::run_hook;
+use crate::conf::Settings;
+use indexmap::IndexMap;
+use melib::backends::{AccountHash, MailboxHash, MailboxWatchUrgency};
+use std::collections::{HashMap, HashSet};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+
+/// Time between the starts of two watchers, and between two scheduled polls.
+const STAGGER: Duration = Duration::from_secs(3);
+/// Time between two polls of a high urgency mailbox.
+const HIGH_URGENCY_POLL: Duration = Duration::from_secs(60);
+/// Time between two runs of the `on_battery` hook.
+const BATTERY_CHECK: Duration = Duration::from_secs(60);
+
+#[derive(Debug, Default)]
+pub struct WatchScheduler {
+    /// When the last watcher was scheduled to start.
+    last_start: Option<Instant>,
+    /// When the last scheduled poll was sent.
+    last_poll: Option<Instant>,
+    /// When each high urgency mailbox is polled next.
+    next_poll: HashMap<(AccountHash, MailboxHash), Instant>,
+    /// Set by the `on_battery` hook, which runs in its own thread.
+    on_battery: Arc<AtomicBool>,
+    last_battery_check: Option<Instant>,
+    /// Accounts whose watcher is paused because the machine runs on battery.
+    paused: HashSet<AccountHash>,
+}
+
+impl WatchScheduler {
+    /// If watchers of accounts of `urgency` are paused.
+    pub fn is_paused(&self, urgency: MailboxWatchUrgency) -> bool {
+        urgency == MailboxWatchUrgency::Low && self.on_battery.load(Ordering::Relaxed)
+    }
+
+    /// Delay before starting the watcher of an account of `urgency`, so that watchers of several
+    /// accounts don't wake up at the same time. High urgency watchers start right away.
+    fn start_delay(&mut self, urgency: MailboxWatchUrgency, now: Instant) -> Option<Duration> {
+        if urgency == MailboxWatchUrgency::High {
+            return None;
+        }
+        let start = match self.last_start {
+            Some(last) if last + STAGGER > now => last + STAGGER,
+            _ => now,
+        };
+        self.last_start = Some(start);
+        if start > now {
+            Some(start - now)
+        } else {
+            None
+        }
+    }
+
+    /// Start the watcher of `account`, unless it is running or paused.
+    pub fn watch(&mut self, account: &mut Account) {
+        if account.is_watching() {
+            return;
+        }
+        let urgency = account.watch_urgency();
+        if self.is_paused(urgency) {
+            self.paused.insert(account.hash());
+            return;
+        }
+        let delay = self.start_delay(urgency, Instant::now());
+        account.watch_after(delay);
+    }
+
+    /// Check whether the machine runs on battery, pause or resume watchers accordingly and send
+    /// the next due poll of a high urgency mailbox. Called periodically.
+    pub fn tick(&mut self, accounts: &mut IndexMap<AccountHash, Account>, settings: &Settings) {
+        let now = Instant::now();
+        if let Some(ref command) = settings.hooks.on_battery {
+            if self
+                .last_battery_check
+                .map(|t| now.duration_since(t) >= BATTERY_CHECK)
+                .unwrap_or(true)
+            {
+                self.last_battery_check = Some(now);
+                let on_battery = self.on_battery.clone();
+                let command = command.clone();
+                std::thread::spawn(move || {
+                    let val = run_hook("on_battery", &command, None, vec![]).is_ok();
+                    on_battery.store(val, Ordering::Relaxed);
+                });
+            }
+        }
+
+        for account in accounts.values_mut() {
+            if !account.is_connected() || account.is_online.is_err() {
+                continue;
+            }
+            let urgency = account.watch_urgency();
+            if self.is_paused(urgency) {
+                if account.is_watching() {
+                    account.stop_watching();
+                    self.paused.insert(account.hash());
+                }
+            } else if self.paused.remove(&account.hash()) {
+                let delay = self.start_delay(urgency, now);
+                account.watch_after(delay);
+            }
+        }
+
+        if self
+            .last_poll
+            .map(|t| now.duration_since(t) < STAGGER)
+            .unwrap_or(false)
+        {
+            return;
+        }
+        for account in accounts.values_mut() {
+            if !account.is_connected()
+                || account.is_online.is_err()
+                || account.settings.account().manual_refresh
+                || account.settings.conf().refresh_command.is_some()
+            {
+                continue;
+            }
+            let account_hash = account.hash();
+            for mailbox_hash in account.mailboxes_with_urgency(MailboxWatchUrgency::High) {
+                let next_poll = self
+                    .next_poll
+                    .entry((account_hash, mailbox_hash))
+                    .or_insert(now + HIGH_URGENCY_POLL);
+                if *next_poll > now || account.is_refreshing(mailbox_hash) {
+                    continue;
+                }
+                *next_poll = now + HIGH_URGENCY_POLL;
+                self.last_poll = Some(now);
+                if let Err(err) = account.refresh(mailbox_hash) {
+                    debug!("Could not poll mailbox {}: {}", mailbox_hash, err);
+                }
+                return;
+            }
+        }
+    }
+}
+
+#[test]
+fn test_watch_scheduler_start_delay() {
+    let mut scheduler = WatchScheduler::default();
+    let now = Instant::now();
+    assert_eq!(
+        scheduler.start_delay(MailboxWatchUrgency::Normal, now),
+        None
+    );
+    assert_eq!(
+        scheduler.start_delay(MailboxWatchUrgency::Low, now),
+        Some(STAGGER)
+    );
+    assert_eq!(scheduler.start_delay(MailboxWatchUrgency::High, now), None);
+    assert_eq!(
+        scheduler.start_delay(MailboxWatchUrgency::Normal, now),
+        Some(STAGGER * 2)
+    );
+    assert_eq!(
+        scheduler.start_delay(MailboxWatchUrgency::Normal, now + STAGGER * 10),
+        None
+    );
+
+    assert!(!scheduler.is_paused(MailboxWatchUrgency::Low));
+    scheduler.on_battery.store(true, Ordering::Relaxed);
+    assert!(scheduler.is_paused(MailboxWatchUrgency::Low));
+    assert!(!scheduler.is_paused(MailboxWatchUrgency::Normal));
+}
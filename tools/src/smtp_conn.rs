@@ -20,6 +20,7 @@ fn main() -> Result<()> {
             auth_type: Default::default(),
         },
         envelope_from: String::new(),
+        connection_policy: Default::default(),
     };
     std::thread::spawn(move || {
         let ex = smol::Executor::new();